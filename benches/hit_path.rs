@@ -0,0 +1,56 @@
+//! A microbenchmark of the hit path: reading a page that is already in memory, whose read lock is
+//! uncontended, and whose access does not need to update its eviction state.
+//!
+//! Run with `cargo bench --bench hit_path`. The benchmark fails if a resident read takes longer
+//! than [`BUDGET`] on average.
+
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// The number of resident reads to warm up with before measuring.
+const WARMUP: u32 = 100_000;
+
+/// The number of resident reads to measure.
+const READS: u32 = 10_000_000;
+
+/// The budget for the average latency of a single resident read.
+const BUDGET: Duration = Duration::from_nanos(100);
+
+fn main() {
+    // `cargo test` also builds and runs benchmark targets, but only `cargo bench` passes `--bench`.
+    if !std::env::args().any(|arg| arg == "--bench") {
+        return;
+    }
+
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).hot_access_threshold(Duration::from_secs(60)),
+    );
+    let bpm = BufferPoolManager::get();
+
+    let per_read = BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        ph.write().await.unwrap().fill(42);
+
+        for _ in 0..WARMUP {
+            black_box(ph.read().await.unwrap()[0]);
+        }
+
+        let start = Instant::now();
+        for _ in 0..READS {
+            black_box(ph.read().await.unwrap()[0]);
+        }
+        let per_read = start.elapsed() / READS;
+
+        drop(ph);
+        bpm.shutdown().await.unwrap();
+
+        per_read
+    });
+
+    println!("hit_path: {per_read:?} per resident read (budget {BUDGET:?})");
+    assert!(
+        per_read <= BUDGET,
+        "A resident read took {per_read:?} on average, over the budget of {BUDGET:?}"
+    );
+}
@@ -0,0 +1,90 @@
+//! This module contains closure-based helpers that access a single page of the
+//! [`BufferPoolManager`].
+//!
+//! Most callers get a handle to a page, lock it, read or modify it, and then maybe flush it. Doing
+//! that by hand makes it easy to hold a guard for longer than intended (for example, across an
+//! unrelated `.await`), which blocks every other task that needs the page. The helpers here scope
+//! the guard to a closure, so it is always released as soon as the closure returns.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::Result;
+use crate::page::PageId;
+
+/// What [`BufferPoolManager::with_page_write`] does with a page after it has been modified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Leave the page dirty in memory, so that it is written back when it is evicted, by the
+    /// background flusher, or by [`BufferPoolManager::flush_all`].
+    #[default]
+    Deferred,
+
+    /// Write the page out before returning, see [`WritePageGuard::flush`].
+    ///
+    /// [`WritePageGuard::flush`]: crate::page::WritePageGuard::flush
+    Flush,
+
+    /// Write the page out and wait until it is durable before returning, see
+    /// [`WritePageGuard::flush_sync`].
+    ///
+    /// [`WritePageGuard::flush_sync`]: crate::page::WritePageGuard::flush_sync
+    FlushSync,
+}
+
+impl BufferPoolManager {
+    /// Reads a page and calls `f` with its data, returning whatever `f` returns.
+    ///
+    /// The page is read locked while `f` runs, and the lock is released as soon as `f` returns.
+    /// Since `f` is not asynchronous, it cannot hold the lock across an `.await`.
+    ///
+    /// This must be called from a thread started with [`BufferPoolManager::start_thread`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page cannot be read into memory, see [`PageHandle::read`]. `f` is
+    /// not called in that case.
+    ///
+    /// [`PageHandle::read`]: crate::page::PageHandle::read
+    pub async fn with_page_read<R>(&self, pid: &PageId, f: impl FnOnce(&[u8]) -> R) -> Result<R> {
+        let ph = self.get_page(pid)?;
+        let guard = ph.read().await?;
+
+        Ok(f(&guard))
+    }
+
+    /// Write locks a page and calls `f` with its data, then handles the modified page according to
+    /// `flush` and returns whatever `f` returned.
+    ///
+    /// The whole page is marked dirty, and the lock is released once the page has been flushed
+    /// (if `flush` asks for it). Since `f` is not asynchronous, it cannot hold the lock across an
+    /// `.await`.
+    ///
+    /// This must be called from a thread started with [`BufferPoolManager::start_thread`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page cannot be read into memory, see [`PageHandle::write`], in
+    /// which case `f` is not called. Also returns an error if the page cannot be flushed, in which
+    /// case the modification is kept in memory and the page stays dirty, but the result of `f` is
+    /// lost.
+    ///
+    /// [`PageHandle::write`]: crate::page::PageHandle::write
+    pub async fn with_page_write<R>(
+        &self,
+        pid: &PageId,
+        flush: FlushPolicy,
+        f: impl FnOnce(&mut [u8]) -> R,
+    ) -> Result<R> {
+        let ph = self.get_page(pid)?;
+        let mut guard = ph.write().await?;
+
+        let res = f(&mut guard);
+
+        match flush {
+            FlushPolicy::Deferred => {}
+            FlushPolicy::Flush => guard.flush().await?,
+            FlushPolicy::FlushSync => guard.flush_sync().await?,
+        }
+
+        Ok(res)
+    }
+}
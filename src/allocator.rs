@@ -0,0 +1,422 @@
+//! This module contains the persistent page allocator, with [`BufferPoolManager::allocate_page`]
+//! and [`BufferPoolManager::deallocate_page`].
+//!
+//! Embedders that do not keep track of which pages hold data themselves can let the buffer pool
+//! manager hand out page IDs instead. The allocator remembers the high-water mark of every page
+//! that was ever allocated and the pages that were deallocated below it, so that a page that still
+//! holds data is never handed out twice, even across restarts.
+//!
+//! The allocation state lives next to the first database file, with the extension `alloc`. Its
+//! first line is `next_page=<id>`, and every following line is `free_page=<id>`. Every other
+//! [data file](BufferPoolManager::create_file) has an allocator of its own, whose state lives next
+//! to the data file and counts the data file's page numbers instead of page IDs.
+//!
+//! When the buffer pool manager is initialized, the allocation state is cross-checked against the
+//! database files (which, for a [database directory](crate::BufferPoolManagerConfig::directory),
+//! are sized according to its superblock), so that pages are never served from metadata that does
+//! not match the files. Mismatches that are trivially recoverable are repaired:
+//!
+//! - A temporary allocation file that was left behind by an interrupted update is removed.
+//! - A database file that ends with a partial page past every allocated page (for example, because
+//!   growing it was interrupted) is truncated to a whole number of pages.
+//!
+//! Every other mismatch fails initialization with an [`AllocationMismatch`] error.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::{AllocationMismatch, AllocationProblem, Result};
+use crate::page::{FileId, PageId};
+use crate::storage::StorageManager;
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// The in-memory allocation state of a [`PageAllocator`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct AllocationState {
+    /// The ID of the next page that has never been allocated.
+    next_page: u64,
+
+    /// The pages below `next_page` that were deallocated and can be allocated again.
+    free_pages: BTreeSet<u64>,
+}
+
+impl AllocationState {
+    /// Reads the allocation state at the given path, or returns an empty state if it does not
+    /// exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the allocation file cannot be read or is malformed.
+    fn read(path: &Path) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+
+        let malformed = |line: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "The allocation file {} has an invalid line `{line}`",
+                    path.display()
+                ),
+            )
+        };
+
+        let mut state = Self::default();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line.split_once('=').ok_or_else(|| malformed(line))?;
+            let value: u64 = value.trim().parse().map_err(|_| malformed(line))?;
+
+            match key.trim() {
+                "next_page" => state.next_page = value,
+                "free_page" => {
+                    state.free_pages.insert(value);
+                }
+                _ => return Err(malformed(line)),
+            }
+        }
+
+        if state.free_pages.last() >= Some(&state.next_page) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "The allocation file {} frees pages that were never allocated",
+                    path.display()
+                ),
+            ));
+        }
+
+        Ok(state)
+    }
+
+    /// Checks that the given page is allocated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if the page is not allocated.
+    fn check_allocated(&self, pid: PageId) -> io::Result<()> {
+        let id = pid.page_number();
+        if pid.is_temp() || id >= self.next_page || self.free_pages.contains(&id) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{pid} is not allocated"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Durably replaces the allocation state at the given path, such that a crash leaves either
+    /// the old or the new state.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the allocation file cannot be written.
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let tmp = path.with_extension("alloc.tmp");
+
+        let mut file = File::create(&tmp)?;
+        writeln!(file, "next_page={}", self.next_page)?;
+        for pid in &self.free_pages {
+            writeln!(file, "free_page={pid}")?;
+        }
+        file.sync_all()?;
+
+        fs::rename(&tmp, path)
+    }
+}
+
+/// The persistent allocator of the page IDs of a single data file.
+#[derive(Debug)]
+pub(crate) struct PageAllocator {
+    /// The data file that the pages are allocated in.
+    file: FileId,
+
+    /// The path to the allocation file.
+    path: PathBuf,
+
+    /// The number of pages that the database files can hold.
+    capacity: u64,
+
+    /// The allocation state, which is written out on every change.
+    state: Mutex<AllocationState>,
+}
+
+impl PageAllocator {
+    /// Loads the allocation state of the data file `file`, which is striped across the files at
+    /// the given paths, and cross-checks it against the files (see the
+    /// [module-level documentation](self)).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the allocation file exists but cannot be read or is malformed, an
+    /// [`AllocationMismatch`] error if it does not match the database files, or an error if a
+    /// mismatch cannot be repaired.
+    pub(crate) fn load(
+        file: FileId,
+        paths: &[PathBuf],
+        capacity: usize,
+        page_size: usize,
+    ) -> io::Result<Self> {
+        let path = paths[0].with_extension("alloc");
+
+        match fs::remove_file(path.with_extension("alloc.tmp")) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+
+        let state = AllocationState::read(&path)?;
+        let allocator = Self {
+            file,
+            path,
+            capacity: capacity as u64,
+            state: Mutex::new(state),
+        };
+        allocator.check_files(paths, page_size as u64)?;
+
+        Ok(allocator)
+    }
+
+    /// Cross-checks the allocation state against the database files, repairing trivially
+    /// recoverable mismatches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AllocationMismatch`] error if the allocation state does not match the files, or
+    /// an error if the files cannot be inspected or repaired.
+    fn check_files(&self, paths: &[PathBuf], page_size: u64) -> io::Result<()> {
+        let next_page = self
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .next_page;
+        if next_page == 0 {
+            return Ok(());
+        }
+
+        let mut problems = Vec::new();
+
+        if next_page > self.capacity {
+            problems.push(AllocationProblem::BeyondCapacity {
+                next_page,
+                capacity: self.capacity,
+            });
+        }
+
+        // Page `n` is stored on drive `n % num_drives`, at page `n / num_drives` of its file.
+        let num_drives = paths.len() as u64;
+        for (drive, path) in (0..num_drives).zip(paths) {
+            let allocated = next_page.saturating_sub(drive).div_ceil(num_drives);
+            let required = allocated * page_size;
+
+            let len = fs::metadata(path)?.len();
+            if len < required {
+                problems.push(AllocationProblem::FileTooShort {
+                    path: path.clone(),
+                    len,
+                    required,
+                });
+                continue;
+            }
+
+            // The partial page at the end of the file is past every allocated page, so nothing
+            // can have been stored in it.
+            if len % page_size != 0 {
+                let file = OpenOptions::new().write(true).open(path)?;
+                file.set_len(len - len % page_size)?;
+                file.sync_all()?;
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(AllocationMismatch::new(self.path.clone(), problems).into())
+        }
+    }
+
+    /// Allocates the lowest free page ID, and durably records the allocation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if every page is allocated, or if the allocation file cannot be written.
+    fn allocate(&self) -> io::Result<PageId> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut next = state.clone();
+        let pid = match next.free_pages.pop_first() {
+            Some(pid) => pid,
+            None if next.next_page < self.capacity => {
+                next.next_page += 1;
+                next.next_page - 1
+            }
+            None => {
+                return Err(Error::other(format!(
+                    "Every one of the {} pages is allocated",
+                    self.capacity
+                )))
+            }
+        };
+
+        next.write(&self.path)?;
+        *state = next;
+
+        Ok(PageId::in_file(self.file, pid))
+    }
+
+    /// Checks that the given page is allocated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if the page is not allocated.
+    fn check_allocated(&self, pid: PageId) -> io::Result<()> {
+        self.check_file(pid)?;
+
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .check_allocated(pid)
+    }
+
+    /// Checks that the given page belongs to the data file of this allocator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if it does not.
+    fn check_file(&self, pid: PageId) -> io::Result<()> {
+        if pid.is_temp() || pid.file_id() != self.file {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{pid} does not belong to {}", self.file),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Frees the given page, and durably records the deallocation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if the page is not allocated,
+    /// or an error if the allocation file cannot be written.
+    fn free(&self, pid: PageId) -> io::Result<()> {
+        self.check_file(pid)?;
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.check_allocated(pid)?;
+
+        let mut next = state.clone();
+        next.free_pages.insert(pid.page_number());
+
+        next.write(&self.path)?;
+        *state = next;
+
+        Ok(())
+    }
+}
+
+impl BufferPoolManager {
+    /// Allocates a page that holds no data, and returns its ID.
+    ///
+    /// Pages are allocated in ascending order of page ID, and deallocated pages are reused first.
+    /// The allocation is durable before this returns, so a page is never allocated twice, even
+    /// across restarts. Note that the allocator only knows about the pages that it handed out, so
+    /// embedders that choose page IDs themselves should not mix that with allocation.
+    ///
+    /// A page that is allocated again after it was deallocated reads as zeroes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if every page of the configured capacity is allocated, or if the allocation
+    /// cannot be recorded.
+    pub fn allocate_page(&self) -> Result<PageId> {
+        Ok(self.allocator.allocate()?)
+    }
+
+    /// Allocates a page of the data file `file` that holds no data, and returns its ID.
+    ///
+    /// This behaves like [`BufferPoolManager::allocate_page`], except that every data file counts
+    /// its pages on its own, starting at page `0`, up to the capacity that the data file was
+    /// [created](BufferPoolManager::create_file) with.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NotFound`](ErrorKind::NotFound) error if the data file does not exist, an error
+    /// if every page of the data file is allocated, or an error if the allocation cannot be
+    /// recorded.
+    pub fn allocate_page_in(&self, file: FileId) -> Result<PageId> {
+        Ok(self.allocator_of(file)?.allocate()?)
+    }
+
+    /// Deallocates a page that was allocated with [`BufferPoolManager::allocate_page`] (or with
+    /// [`BufferPoolManager::allocate_page_in`]), discarding its contents, so that its ID can be
+    /// allocated again.
+    ///
+    /// The page is dropped from memory without being written out, and its space in the database
+    /// file is released to the file system by punching a hole into the file. The deallocation is
+    /// recorded in the allocation file, which serves as the free-space map, before this returns.
+    ///
+    /// Every other handle to the page is invalidated: accessing the page through one of them
+    /// returns a [`BpmError::PageNotFound`](crate::error::BpmError::PageNotFound) error, even once
+    /// the page ID is allocated again. Handles to a reallocated page must be created anew with
+    /// [`BufferPoolManager::get_page`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if the page is not allocated, a
+    /// [`NotFound`](ErrorKind::NotFound) error if its data file does not exist, an error if the
+    /// page is still locked by a page guard or still shared by copy-on-write clones (see
+    /// [`PageHandle::clone_cow`](crate::page::PageHandle::clone_cow)), or an error if the hole
+    /// cannot be punched or the deallocation cannot be recorded.
+    pub async fn deallocate_page(&self, pid: &PageId) -> Result<()> {
+        let allocator = self.allocator_of(pid.file_id())?;
+        allocator.check_allocated(*pid)?;
+
+        if let Some(page) = self.pages.read(pid, |_, page| page.clone()) {
+            let Ok(mut guard) = page.frame.try_write() else {
+                return Err(Error::other(format!("{pid} is still in use")).into());
+            };
+            page.check_no_clones()?;
+            #[cfg(feature = "experimental")]
+            if !page.try_invalidate_replicas() {
+                return Err(Error::other(format!("{pid} is still in use")).into());
+            }
+
+            if let Some(mut frame) = guard.take() {
+                page.set_evicted();
+                frame.clear_dirty();
+                frame.evict_page_owner();
+                frame.group().release_frame(frame).await;
+            }
+            page.set_removed();
+
+            drop(guard);
+            self.pages.remove(pid);
+        }
+
+        let sm = StorageManager::get().create_handle()?;
+        sm.punch_hole(*pid).await?;
+
+        Ok(allocator.free(*pid)?)
+    }
+
+    /// Gets the page allocator of the data file `file`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NotFound`](ErrorKind::NotFound) error if the data file does not exist.
+    fn allocator_of(&self, file: FileId) -> io::Result<Arc<PageAllocator>> {
+        if file == FileId::DEFAULT {
+            return Ok(self.allocator.clone());
+        }
+
+        StorageManager::get()
+            .data_files()
+            .allocator(file)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("{file} does not exist")))
+    }
+}
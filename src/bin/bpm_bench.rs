@@ -0,0 +1,243 @@
+//! A throughput benchmark of the buffer pool manager under a skewed random workload.
+//!
+//! Every thread runs a number of tasks that repeatedly access pages chosen from a Zipfian
+//! distribution, taking a read guard or a write guard depending on the read/write mix. Once a
+//! second, the number of accesses and I/O operations of the last second is printed as a line of
+//! CSV.
+//!
+//! Run with `cargo run --release --bin bpm-bench -- --help` to see every option.
+
+// `zipf` deprecated `ZipfDistribution` in favor of `rand_distr::Zipf`, which requires `rand 0.9`.
+#![allow(deprecated)]
+
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, ReplacementPolicy};
+use rand::{distributions::Distribution, Rng};
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::{sync::Barrier, task::JoinSet};
+use zipf::ZipfDistribution;
+
+/// The usage message printed by `--help` and on invalid arguments.
+const USAGE: &str = "\
+Usage: bpm-bench [OPTIONS]
+
+Options:
+    --threads <N>        Number of threads, each pinned to its own core [default: all cores]
+    --tasks <N>          Number of tasks on every thread [default: 16]
+    --frames <N>         Number of buffer frames [default: 65536]
+    --storage-pages <N>  Number of pages on persistent storage [default: 8 * frames]
+    --zipf <EXP>         Exponent of the Zipfian page distribution [default: 1.1]
+    --duration <SECS>    Number of seconds to run for [default: 60]
+    --read-ratio <F>     Fraction of accesses that are reads, from 0 to 1 [default: 0.5]
+    --policy <POLICY>    Replacement policy: clock, fifo, or lru-<K> [default: clock]
+    -h, --help           Print this message";
+
+/// The options of a benchmark run.
+#[derive(Debug)]
+struct Options {
+    /// The number of threads to run tasks on.
+    threads: usize,
+
+    /// The number of tasks on every thread.
+    tasks: usize,
+
+    /// The number of buffer frames.
+    frames: usize,
+
+    /// The number of pages on persistent storage.
+    storage_pages: usize,
+
+    /// The exponent of the Zipfian distribution that pages are chosen from.
+    zipf: f64,
+
+    /// How long to run for.
+    duration: Duration,
+
+    /// The fraction of accesses that are reads.
+    read_ratio: f64,
+
+    /// The replacement policy of every frame group.
+    policy: ReplacementPolicy,
+}
+
+impl Options {
+    /// Parses the options from the command line arguments, without the program name.
+    fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = Self {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            tasks: 16,
+            frames: 1 << 16,
+            storage_pages: 0,
+            zipf: 1.1,
+            duration: Duration::from_secs(60),
+            read_ratio: 0.5,
+            policy: ReplacementPolicy::Clock,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            if flag == "-h" || flag == "--help" {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing a value for `{flag}`"))?;
+            match flag.as_str() {
+                "--threads" => options.threads = parse_value(&flag, &value)?,
+                "--tasks" => options.tasks = parse_value(&flag, &value)?,
+                "--frames" => options.frames = parse_value(&flag, &value)?,
+                "--storage-pages" => options.storage_pages = parse_value(&flag, &value)?,
+                "--zipf" => options.zipf = parse_value(&flag, &value)?,
+                "--duration" => {
+                    options.duration = Duration::from_secs(parse_value(&flag, &value)?);
+                }
+                "--read-ratio" => options.read_ratio = parse_value(&flag, &value)?,
+                "--policy" => options.policy = parse_policy(&value)?,
+                _ => return Err(format!("Unknown option `{flag}`")),
+            }
+        }
+
+        if options.storage_pages == 0 {
+            options.storage_pages = options.frames * 8;
+        }
+        if options.threads == 0 || options.tasks == 0 {
+            return Err("There must be at least one thread and one task".to_string());
+        }
+        if !(0.0..=1.0).contains(&options.read_ratio) {
+            return Err("The read ratio must be between 0 and 1".to_string());
+        }
+        if options.zipf <= 0.0 {
+            return Err("The Zipfian exponent must be positive".to_string());
+        }
+
+        Ok(options)
+    }
+}
+
+/// Parses the value of a numeric option.
+fn parse_value<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value `{value}` for `{flag}`"))
+}
+
+/// Parses a replacement policy, which is `clock`, `fifo`, or `lru-<K>`.
+fn parse_policy(value: &str) -> Result<ReplacementPolicy, String> {
+    match value {
+        "clock" => Ok(ReplacementPolicy::Clock),
+        "fifo" => Ok(ReplacementPolicy::Fifo),
+        _ => value
+            .strip_prefix("lru-")
+            .and_then(|k| k.parse().ok())
+            .filter(|&k| k > 0)
+            .map(ReplacementPolicy::LruK)
+            .ok_or_else(|| format!("Invalid replacement policy `{value}`")),
+    }
+}
+
+fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{e}\n\n{USAGE}");
+        process::exit(2);
+    });
+    eprintln!("{options:?}");
+
+    let config = BufferPoolManagerConfig::new(options.frames, options.storage_pages)
+        .replacement_policy(options.policy);
+    if let Err(e) = BufferPoolManager::try_initialize_with_config(config) {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    let options = Arc::new(options);
+    let cores = core_affinity::get_core_ids().unwrap_or_default();
+    let barrier = Arc::new(Barrier::new(options.threads * options.tasks));
+
+    for thread in 0..options.threads {
+        let options = options.clone();
+        let barrier = barrier.clone();
+        let core = cores.get(thread).copied();
+
+        thread::spawn(move || {
+            if let Some(core) = core {
+                core_affinity::set_for_current(core);
+            }
+
+            BufferPoolManager::start_thread(async move {
+                let mut set = JoinSet::new();
+                for _ in 0..options.tasks {
+                    set.spawn(BufferPoolManager::spawn_local(run_task(
+                        options.clone(),
+                        barrier.clone(),
+                    )));
+                }
+
+                while let Some(res) = set.join_next().await {
+                    res.unwrap().unwrap();
+                }
+            });
+        });
+    }
+
+    report(&options);
+}
+
+/// Accesses random pages until the benchmark ends.
+async fn run_task(options: Arc<Options>, barrier: Arc<Barrier>) {
+    let bpm = BufferPoolManager::get();
+    let zipf = ZipfDistribution::new(options.storage_pages, options.zipf)
+        .expect("The options were validated");
+
+    barrier.wait().await;
+
+    loop {
+        // The distribution samples from 1 to the number of pages, inclusive.
+        let (pid, read) = {
+            let mut rng = rand::thread_rng();
+            let pid = PageId::new(zipf.sample(&mut rng) as u64 - 1);
+            (pid, rng.gen_bool(options.read_ratio))
+        };
+
+        let ph = bpm.get_page(&pid).unwrap();
+        if read {
+            let guard = ph.read().await.unwrap();
+            std::hint::black_box(&guard[..]);
+        } else {
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(b'a');
+        }
+    }
+}
+
+/// Prints the accesses and I/O operations of every second as CSV, and exits once the benchmark is
+/// over.
+fn report(options: &Options) {
+    let bpm = BufferPoolManager::get();
+
+    // Wait for the tasks to start.
+    while bpm.stats().read_accesses + bpm.stats().write_accesses == 0 {
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    println!("second,read_accesses,write_accesses,io_operations");
+
+    let start = Instant::now();
+    let mut window = bpm.stats_window(Duration::from_secs(1));
+    for second in 0..options.duration.as_secs() {
+        let stats = window.next_blocking();
+        println!(
+            "{second},{},{},{}",
+            stats.read_accesses,
+            stats.write_accesses,
+            stats.io_operations()
+        );
+    }
+
+    eprintln!("Time elapsed: {:?}", start.elapsed());
+    process::exit(0);
+}
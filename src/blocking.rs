@@ -0,0 +1,112 @@
+//! A synchronous facade over the asynchronous buffer pool API.
+//!
+//! Every function in this module drives the asynchronous machinery to completion on a hidden,
+//! lazily-created [`tokio_uring`] runtime that is local to the calling thread. This allows
+//! applications that are not asynchronous to use the buffer pool without setting up a runtime
+//! themselves.
+//!
+//! Since page handles and guards are thread-local, they must only be used on the thread that
+//! created them.
+//!
+//! # Panics
+//!
+//! Every function in this module that drives a future will panic if it is called from within an
+//! asynchronous runtime (for example, from a thread started with
+//! [`BufferPoolManager::start_thread`]), since a thread cannot block on itself.
+
+use crate::error::Result;
+use crate::page::{PageHandle, PageId, ReadPageGuard, WritePageGuard};
+use crate::BufferPoolManager;
+use std::future::Future;
+
+std::thread_local! {
+    static RUNTIME: tokio_uring::Runtime = BufferPoolManager::new_runtime()
+        .expect("Thread is unable to create a tokio_uring runtime");
+}
+
+/// Runs a future to completion on the calling thread's hidden runtime.
+///
+/// # Panics
+///
+/// Panics if the calling thread is unable to create a runtime, or if it is called from within an
+/// asynchronous runtime.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    RUNTIME.with(|rt| rt.block_on(future))
+}
+
+/// Gets a [`PageHandle`] to the page with the given [`PageId`].
+///
+/// See [`BufferPoolManager::get_page`].
+///
+/// # Errors
+///
+/// Returns an error if unable to create a handle to persistent storage.
+///
+/// # Panics
+///
+/// Panics if the buffer pool manager has not been initialized.
+pub fn get_page_blocking(pid: &PageId) -> Result<PageHandle> {
+    BufferPoolManager::get().get_page(pid)
+}
+
+/// Gets a read guard on a page, blocking until the page's data is in memory.
+///
+/// See [`PageHandle::read`].
+///
+/// # Errors
+///
+/// Returns an error if an I/O error occurs while trying to load the data from disk into memory.
+///
+/// # Panics
+///
+/// Panics if called from within an asynchronous runtime.
+pub fn read_blocking(ph: &PageHandle) -> Result<ReadPageGuard<'_>> {
+    block_on(ph.read())
+}
+
+/// Gets a write guard on a page, blocking until the page's data is in memory.
+///
+/// See [`PageHandle::write`].
+///
+/// # Errors
+///
+/// Returns an error if an I/O error occurs while trying to load the data from disk into memory.
+///
+/// # Panics
+///
+/// Panics if called from within an asynchronous runtime.
+pub fn write_blocking(ph: &PageHandle) -> Result<WritePageGuard<'_>> {
+    block_on(ph.write())
+}
+
+/// Flushes a page's data out to persistent storage, blocking until the write completes.
+///
+/// See [`WritePageGuard::flush`].
+///
+/// # Errors
+///
+/// Returns an error if it is unable to complete the write operation to a file.
+///
+/// # Panics
+///
+/// Panics if called from within an asynchronous runtime.
+pub fn flush_blocking(guard: &mut WritePageGuard<'_>) -> Result<()> {
+    block_on(guard.flush())
+}
+
+/// Flushes a page's data out to persistent storage and waits until it is durable, blocking until
+/// the sync completes.
+///
+/// See [`WritePageGuard::flush_sync`].
+///
+/// # Errors
+///
+/// Returns an error if it is unable to complete the write operation to a file, or if it is unable
+/// to sync the file.
+///
+/// # Panics
+///
+/// Panics if called from within an asynchronous runtime.
+pub fn flush_sync_blocking(guard: &mut WritePageGuard<'_>) -> Result<()> {
+    block_on(guard.flush_sync())
+}
@@ -10,26 +10,67 @@
 //! pool manager would work.
 
 use crate::{
-    page::{Page, PageHandle, PageId, PAGE_SIZE},
-    storage::{Frame, FrameGroup, StorageManager, FRAME_GROUP_SIZE},
+    allocator::PageAllocator,
+    config::{BufferPoolManagerConfig, CachePolicy, GroupSelection, PoisonPolicy},
+    daemon::{self, DaemonRegistry},
+    error::{BpmError, DaemonError, FlushAllError, Result},
+    events::{PageEvent, PageEventListener},
+    executor,
+    flusher::WriteBackCounters,
+    hashing::PageTableHasher,
+    init::PoolBuilder,
+    metrics::LatencyRecorder,
+    numa::NumaLayout,
+    page::{Page, PageHandle, PageId, ReadPageGuard, WritePageGuard},
+    probe::RingProbeReport,
+    stats::StatsCounters,
+    storage::{
+        EvictionState, Frame, FrameArena, FrameGroup, GroupCommit, IoDriver, PageCodec,
+        StorageBackend, StorageManager, StorageOptions, ARENA_ALIGNMENT,
+    },
+    tenant::{TenantAccounting, TenantId},
+    wal::WalHook,
 };
+use async_channel::Receiver;
 use rand::prelude::*;
-use scc::HashMap;
-use std::sync::{atomic::AtomicBool, Arc, OnceLock};
-use std::{future::Future, io::Result};
-use tokio::sync::RwLock;
+use scc::{HashMap, HashSet};
+use std::future::Future;
+use std::io::{self, Error, ErrorKind};
+use std::mem;
+use std::ops::Range;
+use std::ptr;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::Duration;
+use tokio::sync::RwLockWriteGuard;
 use tokio::task;
 
-/// The global buffer pool manager instance.
-static BPM: OnceLock<BufferPoolManager> = OnceLock::new();
+/// The global buffer pool manager instance, or null if it has not been initialized.
+///
+/// Every instance is leaked, since [`BufferPoolManager::get`] hands out `'static` references.
+/// [`BufferPoolManager::shutdown`] frees everything that an instance owns except for the instance
+/// itself.
+static BPM: AtomicPtr<BufferPoolManager> = AtomicPtr::new(ptr::null_mut());
 
 /// A parallel Buffer Pool Manager that manages bringing logical pages from persistent storage into
 /// memory via shared and fixed buffer frames.
 #[derive(Debug)]
 pub struct BufferPoolManager {
-    /// The total number of buffer frames this [`BufferPoolManager`] manages.
+    /// The total number of buffer frames this [`BufferPoolManager`] manages, including the frames
+    /// of retired frame groups.
     num_frames: usize,
 
+    /// The number of frame groups that are in use, which always come first in `frame_groups`.
+    ///
+    /// The frame groups after them are retired: every one of their frames is free, and their
+    /// memory is released to the operating system (see [`BufferPoolManager::resize`]).
+    pub(crate) active_groups: AtomicUsize,
+
+    /// The arenas of memory that hold every buffer frame, which are freed on shutdown.
+    pub(crate) arenas: Vec<FrameArena>,
+
     /// A mapping between unique [`PageId`]s and shared [`Page`]s.
     ///
     /// Note that this is _not_ the same as a page table in a traditional buffer pool manager. In a
@@ -41,75 +82,318 @@ pub struct BufferPoolManager {
     /// TODO it is not strictly necessary that we need to store the `Arc<Page>` inside the hash
     /// table - the user should be allowed to manage the pages themselves (for example, if they are
     /// performing a scan we don't want to saturate this hash table with temporary pages).
-    pages: HashMap<PageId, Arc<Page>>,
+    pub(crate) pages: HashMap<PageId, Arc<Page>, PageTableHasher>,
 
     /// All of the [`FrameGroup`]s that hold the [`Frame`]s that this buffer pool manages.
     frame_groups: Vec<Arc<FrameGroup>>,
+
+    /// The NUMA nodes that the frame groups are bound to, or `None` if NUMA-aware allocation is
+    /// disabled or unavailable.
+    numa: Option<NumaLayout>,
+
+    /// Chooses the frame group to take a frame from when a frame is needed.
+    group_selector: GroupSelector,
+
+    /// The configuration that this buffer pool manager was initialized with.
+    config: BufferPoolManagerConfig,
+
+    /// The shared state of all of the background daemons of this buffer pool manager.
+    pub(crate) daemons: DaemonRegistry,
+
+    /// The latest `io_uring` latency probe measurements of every thread that runs a probe.
+    pub(crate) ring_probes: HashMap<ThreadId, RingProbeReport>,
+
+    /// The pages that currently have a [`WritePageGuard`](crate::page::WritePageGuard) that was
+    /// decomposed into raw parts and has not been reconstructed yet.
+    pub(crate) raw_guards: HashSet<PageId>,
+
+    /// The copy-on-write clones that still share the frame of their source, see
+    /// [`PageHandle::clone_cow`].
+    pub(crate) cow_clones: HashSet<PageId>,
+
+    /// The counters of the pages that the background flusher has written back.
+    pub(crate) write_backs: WriteBackCounters,
+
+    /// The cumulative counters of the work that the buffer pool manager has done.
+    pub(crate) stats: StatsCounters,
+
+    /// The histograms of the latencies of page operations.
+    pub(crate) latencies: LatencyRecorder,
+
+    /// The index of the next temporary page to create.
+    next_temp_page: AtomicU64,
+
+    /// The persistent allocator of the page IDs of the default data file, see
+    /// [`BufferPoolManager::allocate_page`].
+    pub(crate) allocator: Arc<PageAllocator>,
+
+    /// The number of resident frames of every tenant, see [`BufferPoolManager::get_page_for`].
+    pub(crate) tenants: TenantAccounting,
+
+    /// The dedicated I/O driver threads, see [`BufferPoolManagerConfig::io_threads`].
+    pub(crate) io_driver: Option<IoDriver>,
+
+    /// Serializes [`BufferPoolManager::commit_pages`], since every commit stages its pages in the
+    /// same doublewrite file.
+    pub(crate) commit_lock: tokio::sync::Mutex<()>,
+
+    /// Serializes [`BufferPoolManager::resize`].
+    pub(crate) resize_lock: tokio::sync::Mutex<()>,
+}
+
+/// The state behind a [`GroupSelection`].
+#[derive(Debug)]
+enum GroupSelector {
+    /// See [`GroupSelection::Random`].
+    Random,
+
+    /// See [`GroupSelection::Seeded`].
+    Seeded(Box<Mutex<StdRng>>),
+
+    /// See [`GroupSelection::RoundRobin`], with the number of choices made so far.
+    RoundRobin(AtomicUsize),
+
+    /// See [`GroupSelection::PowerOfTwoChoices`].
+    PowerOfTwoChoices,
+}
+
+impl GroupSelector {
+    /// Creates the state for a [`GroupSelection`].
+    fn new(selection: GroupSelection) -> Self {
+        match selection {
+            GroupSelection::Random => Self::Random,
+            GroupSelection::Seeded(seed) => {
+                Self::Seeded(Box::new(Mutex::new(StdRng::seed_from_u64(seed))))
+            }
+            GroupSelection::RoundRobin => Self::RoundRobin(AtomicUsize::new(0)),
+            GroupSelection::PowerOfTwoChoices => Self::PowerOfTwoChoices,
+        }
+    }
+
+    /// Chooses an index in `0..len`, where `free_frames` gets the number of free frames of the
+    /// frame group at an index.
+    fn pick(&self, len: usize, free_frames: impl Fn(usize) -> usize) -> usize {
+        match self {
+            Self::Random => rand::thread_rng().gen_range(0..len),
+            Self::Seeded(rng) => rng
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .gen_range(0..len),
+            Self::RoundRobin(next) => next.fetch_add(1, Ordering::Relaxed) % len,
+            Self::PowerOfTwoChoices => {
+                let mut rng = rand::thread_rng();
+                let first = rng.gen_range(0..len);
+                if len == 1 {
+                    return first;
+                }
+
+                let second = (first + rng.gen_range(1..len)) % len;
+                if free_frames(second) > free_frames(first) {
+                    second
+                } else {
+                    first
+                }
+            }
+        }
+    }
 }
 
 /// TODO add method that creates a page but does not add it to the global page table.
 impl BufferPoolManager {
-    /// Constructs a new buffer pool manager with the given number of [`PAGE_SIZE`]ed buffer frames
-    /// and an initial file capacity for storage.
+    /// Constructs a new buffer pool manager with the given number of
+    /// [`PAGE_SIZE`](crate::page::PAGE_SIZE)d buffer frames and an initial file capacity for
+    /// storage.
     ///
     /// The amount of memory the buffer pool will manage is determined by `num_frames`, and the
     /// amount of data stored in persistent storage (for example, a hard drive) is determined by
     /// `capacity`.
     ///
-    /// Note that this function may round `num_frames` down to a multiple of `FRAME_GROUP_SIZE`,
-    /// which is an internal constant that groups memory frames together. Expect this constant to be
-    /// set to 64 frames, but _do not_ rely on this fact.
+    /// The frames are divided into groups of 64 frames, which can be changed with
+    /// [`BufferPoolManagerConfig::frame_group_size`]. If `num_frames` is not a multiple of the
+    /// group size, the last group is smaller than the others.
     ///
     /// # Panics
     ///
-    /// This function will panic if `num_frames` is equal to zero, if `capacity` is greater than
-    /// or equal to `num_frames`, or if the caller has already called `initialize` before.
+    /// This function will panic if `num_frames` is equal to zero, if `capacity` is less than or
+    /// equal to `num_frames`, or if the caller has already called `initialize` before without
+    /// calling [`BufferPoolManager::shutdown`] in between. Use
+    /// [`BufferPoolManager::try_initialize_with_config`] to handle invalid arguments instead.
     pub fn initialize(num_frames: usize, capacity: usize) {
-        assert!(
-            BPM.get().is_none(),
-            "Tried to initialize a BufferPoolManager more than once"
-        );
+        Self::initialize_with_config(BufferPoolManagerConfig::new(num_frames, capacity));
+    }
+
+    /// Constructs a new buffer pool manager with the given [`BufferPoolManagerConfig`].
+    ///
+    /// See [`BufferPoolManager::initialize`] for more information.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if [`BufferPoolManager::try_initialize_with_config`] would return
+    /// an error or panic.
+    pub fn initialize_with_config(config: BufferPoolManagerConfig) {
+        Self::try_initialize_with_config(config).unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    /// Constructs a new buffer pool manager with the given [`BufferPoolManagerConfig`], returning
+    /// an error instead of panicking if the configuration is invalid.
+    ///
+    /// See [`BufferPoolManager::initialize`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BpmError::Config`] error if the configuration is invalid (see
+    /// [`BufferPoolManagerConfig::validate`]), a [`BpmError::AllocationMismatch`] error if the
+    /// database files do not match the persisted state of the page allocator, or an error if the
+    /// configured database directory cannot be prepared (see
+    /// [`BufferPoolManagerConfig::directory`]). The buffer pool manager is not initialized in that
+    /// case.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if NUMA-aware frame memory cannot be mapped, or if the caller has
+    /// already initialized the buffer pool manager before without shutting it down in between.
+    pub fn try_initialize_with_config(config: BufferPoolManagerConfig) -> Result<()> {
+        let mut builder = PoolBuilder::new(config)?;
 
-        // Round down to the nearest multiple of `FRAME_GROUP_SIZE`.
-        let num_frames = num_frames - (num_frames % FRAME_GROUP_SIZE);
+        while !builder.is_allocated() {
+            builder.allocate_group();
+        }
 
-        assert!(num_frames != 0);
-        assert!(num_frames < capacity);
+        Self::install(builder);
 
-        let num_groups = num_frames / FRAME_GROUP_SIZE;
+        Ok(())
+    }
 
-        // Allocate all of the buffer memory up front and initialize to 0s.
-        let bytes: &'static mut [u8] = vec![0u8; num_frames * PAGE_SIZE].leak();
+    /// Divides the arenas of a fully allocated [`PoolBuilder`] up into frames, and sets the
+    /// resulting buffer pool manager as the global instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder has not allocated every arena, or if the buffer pool manager was
+    /// initialized in the meantime.
+    pub(crate) fn install(mut builder: PoolBuilder) {
+        assert!(builder.is_allocated(), "Not every frame group is allocated");
 
-        // Divide the memory up into `PAGE_SIZE` chunks.
-        let buffers: Vec<&'static mut [u8]> = bytes.chunks_exact_mut(PAGE_SIZE).collect();
-        debug_assert_eq!(buffers.len(), num_frames);
+        // From here on, the arenas are owned by the frames, and later by the buffer pool manager.
+        let memory = mem::take(&mut builder.memory);
+        let mut arenas = mem::take(&mut builder.arenas);
+        let paths = mem::take(&mut builder.paths);
+        let quarantine = builder
+            .quarantine
+            .take()
+            .expect("The quarantine list is only taken once");
+        let allocator = builder
+            .allocator
+            .take()
+            .expect("The page allocator is only taken once");
+        let data_files = builder
+            .data_files
+            .take()
+            .expect("The data files are only taken once");
+        let doublewrite = builder.doublewrite.take();
+        let direct_io = builder.direct_io;
+        let numa = builder.numa.take();
+        let num_frames = builder.num_frames;
+        let config = builder.config.clone();
+        drop(builder);
 
-        let mut frames: Vec<Frame> = buffers
-            .into_iter()
-            .enumerate()
-            .map(|(i, buf)| Frame::new(i, buf))
-            .collect();
+        let page_size = config.page_size;
+        let checksums = config.checksums;
+        let user_region = config.user_region();
+        let group_size = config.group_size();
+        let num_groups = arenas.len();
+
+        if config.registered_buffers {
+            FrameArena::assign_buffer_indices(&mut arenas);
+        }
 
         let mut frame_groups: Vec<Arc<FrameGroup>> = Vec::with_capacity(num_groups);
 
-        for id in 0..num_groups {
-            let group: Vec<Frame> = (0..FRAME_GROUP_SIZE)
-                .map(|_| frames.pop().expect("Somehow ran out of frames"))
+        for (id, (bytes, arena)) in memory.into_iter().zip(&arenas).enumerate() {
+            // Divide the memory up into `page_size` chunks.
+            let frames: Vec<Frame> = bytes
+                .chunks_exact_mut(page_size)
+                .enumerate()
+                .map(|(i, buf)| {
+                    Frame::new(
+                        arena.first_frame_id + i,
+                        group_size,
+                        buf,
+                        arena.buf_index(i),
+                        user_region.clone(),
+                    )
+                })
                 .collect();
-            frame_groups.push(Arc::new(FrameGroup::new(id, group)));
+
+            for (i, frame) in frames.iter().enumerate() {
+                arena.check_frame(i, frame);
+            }
+
+            let replacer = config.new_replacer();
+            frame_groups.push(Arc::new(FrameGroup::new(id, group_size, frames, replacer)));
+        }
+
+        let registered_frames = config.registered_buffers.then(|| arenas.clone());
+        let io_driver = config.io_threads.map(IoDriver::new);
+        let group_commit = config.group_commit_window.map(GroupCommit::new);
+
+        // The frame groups beyond the initial number of frames start out retired.
+        let active_groups = config.num_frames.div_ceil(group_size);
+        for arena in &arenas[active_groups..] {
+            arena.release_memory();
         }
 
+        let latencies = LatencyRecorder::new(config.latency_histograms);
+
         // Create the buffer pool and set it as the global static instance.
-        BPM.set(Self {
+        let bpm = Box::into_raw(Box::new(Self {
             num_frames,
-            pages: HashMap::with_capacity(num_frames),
+            arenas,
+            pages: HashMap::with_capacity_and_hasher(
+                num_frames,
+                PageTableHasher::new(config.page_hashing),
+            ),
             frame_groups,
-        })
-        .expect("Tried to initialize the buffer pool manager more than once");
+            active_groups: AtomicUsize::new(active_groups),
+            numa,
+            group_selector: GroupSelector::new(config.group_selection),
+            config,
+            daemons: DaemonRegistry::new(),
+            ring_probes: HashMap::new(),
+            raw_guards: HashSet::new(),
+            cow_clones: HashSet::new(),
+            write_backs: WriteBackCounters::default(),
+            stats: StatsCounters::default(),
+            latencies,
+            next_temp_page: AtomicU64::new(0),
+            allocator: Arc::new(allocator),
+            tenants: TenantAccounting::default(),
+            io_driver,
+            commit_lock: tokio::sync::Mutex::new(()),
+            resize_lock: tokio::sync::Mutex::new(()),
+        }));
+
+        BPM.compare_exchange(ptr::null_mut(), bpm, Ordering::AcqRel, Ordering::Acquire)
+            .expect("Tried to initialize the buffer pool manager more than once");
 
         // Also initialize the global `StorageManager` instance.
-        StorageManager::initialize(capacity);
+        StorageManager::initialize_with_paths(
+            page_size,
+            &paths,
+            StorageOptions {
+                registered_frames,
+                checksums,
+                direct_io,
+                doublewrite,
+                quarantine,
+                data_files,
+                group_commit,
+            },
+        );
+
+        if let Some(driver) = Self::get().io_driver() {
+            driver.start();
+        }
     }
 
     /// Retrieve a static reference to the global buffer pool manager.
@@ -119,15 +403,143 @@ impl BufferPoolManager {
     /// This function will panic if it is called before [`BufferPoolManager::initialize`] has been
     /// called.
     pub fn get() -> &'static Self {
-        BPM.get()
-            .expect("Tried to get a reference to the BPM before it was initialized")
+        let bpm = BPM.load(Ordering::Acquire);
+        assert!(
+            !bpm.is_null(),
+            "Tried to get a reference to the BPM before it was initialized"
+        );
+
+        // Safety: Every instance is leaked, so a non-null pointer is valid forever.
+        unsafe { &*bpm }
+    }
+
+    /// Checks if the global buffer pool manager has been initialized (and has not been shut down).
+    pub fn is_initialized() -> bool {
+        !BPM.load(Ordering::Acquire).is_null()
     }
 
-    /// Gets the number of fixed frames the buffer pool manages.
+    /// Gets the number of buffer frames that the buffer pool currently uses.
+    ///
+    /// This only changes when the pool is resized with [`BufferPoolManager::resize`].
     pub fn num_frames(&self) -> usize {
+        let active = self.active_groups.load(Ordering::Acquire);
+        self.num_frames.min(active * self.frame_group_size())
+    }
+
+    /// Gets the number of buffer frames that the buffer pool can grow to with
+    /// [`BufferPoolManager::resize`].
+    ///
+    /// See [`BufferPoolManagerConfig::max_frames`].
+    pub fn max_frames(&self) -> usize {
         self.num_frames
     }
 
+    /// Gets the size of every page (and therefore every buffer frame) in bytes.
+    ///
+    /// This is [`PAGE_SIZE`](crate::page::PAGE_SIZE) unless configured otherwise with
+    /// [`BufferPoolManagerConfig::page_size`].
+    pub fn page_size(&self) -> usize {
+        self.config.page_size
+    }
+
+    /// Gets the number of buffer frames in every frame group, except possibly the last one.
+    ///
+    /// This is 64 unless configured otherwise with [`BufferPoolManagerConfig::frame_group_size`].
+    pub fn frame_group_size(&self) -> usize {
+        self.config.group_size()
+    }
+
+    /// Checks if pages are read and written with `O_DIRECT`.
+    ///
+    /// This is `false` if buffered I/O was enabled with [`BufferPoolManagerConfig::buffered_io`],
+    /// or if the buffer pool manager fell back to it because the database files do not support
+    /// `O_DIRECT`.
+    pub fn direct_io(&self) -> bool {
+        StorageManager::get().direct_io()
+    }
+
+    /// Gets the alignment in bytes that the memory of every buffer frame is guaranteed to have.
+    ///
+    /// Frames are read and written with `O_DIRECT`, so they are always aligned to at least 512
+    /// bytes, and to 4 KiB if the page size is a multiple of 4 KiB.
+    pub fn frame_alignment(&self) -> usize {
+        let page_size = self.config.page_size;
+        ARENA_ALIGNMENT.min(1 << page_size.trailing_zeros())
+    }
+
+    /// Gets the number of bytes of every page that are available through page guards.
+    ///
+    /// This is the [page size](BufferPoolManager::page_size), minus the bytes reserved for the
+    /// page's checksum if checksums were enabled with [`BufferPoolManagerConfig::checksums`], and
+    /// minus the bytes reserved with [`BufferPoolManagerConfig::reserved_header`] and
+    /// [`BufferPoolManagerConfig::reserved_trailer`].
+    pub fn usable_page_size(&self) -> usize {
+        self.config.user_region().len()
+    }
+
+    /// Gets the range of every page that is available through page guards, as byte offsets into
+    /// the page's [raw](crate::page::ReadPageGuard::raw) data.
+    ///
+    /// See [`BufferPoolManagerConfig::user_region`].
+    pub fn user_region(&self) -> Range<usize> {
+        self.config.user_region()
+    }
+
+    /// Gets the registered [`WalHook`], if any.
+    ///
+    /// See [`BufferPoolManagerConfig::wal_hook`].
+    pub(crate) fn wal_hook(&self) -> Option<&dyn WalHook> {
+        self.config.wal_hook.as_deref()
+    }
+
+    /// Gets the registered [`PageEventListener`]s, in the order that they were registered.
+    ///
+    /// See [`BufferPoolManagerConfig::page_event_listener`].
+    pub(crate) fn page_event_listeners(&self) -> &[Arc<dyn PageEventListener>] {
+        &self.config.page_event_listeners
+    }
+
+    /// Gets the registered [`StorageBackend`], if there is one.
+    pub(crate) fn storage_backend(&self) -> Option<&dyn StorageBackend> {
+        self.config.storage_backend.as_deref()
+    }
+
+    /// Gets the registered [`PageCodec`], if there is one.
+    pub(crate) fn page_codec(&self) -> Option<&dyn PageCodec> {
+        self.config.page_codec.as_deref()
+    }
+
+    /// Checks if the page with the given [`PageId`] is exempt from eviction.
+    ///
+    /// See [`BufferPoolManagerConfig::eviction_exemption`].
+    pub(crate) fn is_eviction_exempt(&self, pid: PageId) -> bool {
+        self.config
+            .eviction_exemption
+            .is_some_and(|predicate| predicate(pid))
+    }
+
+    /// Gets the write coalescing window of the background flusher.
+    ///
+    /// See [`BufferPoolManagerConfig::write_coalescing_window`].
+    pub(crate) fn write_coalescing_window(&self) -> Duration {
+        self.config.write_coalescing_window
+    }
+
+    /// See [`BufferPoolManagerConfig::flusher_dirty_threshold`].
+    pub(crate) fn flusher_dirty_threshold(&self) -> f64 {
+        self.config.flusher_dirty_threshold
+    }
+
+    /// Gets the [`PoisonPolicy`] the buffer pool manager was configured with.
+    pub(crate) fn poison_policy(&self) -> PoisonPolicy {
+        self.config.poison_policy
+    }
+
+    /// Gets the [`CachePolicy`] the buffer pool manager was configured with.
+    pub(crate) fn cache_policy(&self) -> CachePolicy {
+        self.config.cache_policy
+    }
+
     /// Gets a thread-local page handle of the buffer pool manager, returning a [`PageHandle`] to
     /// the logical page data.
     ///
@@ -135,45 +547,789 @@ impl BufferPoolManager {
     ///
     /// # Errors
     ///
-    /// If this function is unable to create a [`File`](tokio_uring::fs::File), this function will
+    /// Returns a [`BpmError::PageNotFound`] error if `pid` is a temporary page that does not
+    /// exist, or a page of a [data file](BufferPoolManager::create_file) that does not exist. If
+    /// this function is unable to create a [`File`](tokio_uring::fs::File), this function will
     /// raise the I/O error in the form of [`Result`].
     pub fn get_page(&self, pid: &PageId) -> Result<PageHandle> {
-        let sm: crate::storage::StorageManagerHandle = StorageManager::get().create_handle()?;
+        let sm = StorageManager::get().create_handle()?;
+
+        // Fast path: most pages already exist, and looking them up only needs a shared lock on a
+        // single bucket of the page table.
+        if let Some(page) = self.pages.read(pid, |_, page| page.clone()) {
+            return Ok(PageHandle::new(page, sm));
+        }
+
+        // Temporary pages are only ever created by `new_temp_page`, and the pages of a data file
+        // only exist for as long as the data file does.
+        if pid.is_temp() || !StorageManager::get().data_files().contains(pid.file_id()) {
+            return Err(BpmError::PageNotFound(*pid));
+        }
 
-        // Get the page if it exists, otherwise create a new one return that.
+        // Otherwise, create the page (unless someone else created it in the meantime).
         let page = self
             .pages
             .entry(*pid)
-            .or_insert_with(|| {
-                Arc::new(Page {
-                    pid: *pid,
-                    is_loaded: AtomicBool::new(false),
-                    frame: RwLock::new(None),
-                })
-            })
+            .or_insert_with(|| Arc::new(Page::new(*pid)))
             .get()
             .clone();
 
         Ok(PageHandle::new(page, sm))
     }
 
+    /// Gets a thread-local page handle of the page with the given [`PageId`] on behalf of
+    /// `tenant`.
+    ///
+    /// This behaves like [`BufferPoolManager::get_page`], except that if the handle reads the page
+    /// into memory, the page's frame is charged to `tenant` until the page is evicted again. Pages
+    /// that are already in memory stay charged to whichever tenant read them in, if any.
+    ///
+    /// If `tenant` has a [quota](crate::BufferPoolManagerConfig::tenant_quota) and already has as
+    /// many resident frames as the quota allows, reading a page in first evicts one of the
+    /// tenant's own pages and reuses its frame, instead of evicting a page of any tenant. The
+    /// number of resident frames of a tenant is reported by
+    /// [`BufferPoolManager::tenant_resident_frames`].
+    ///
+    /// # Errors
+    ///
+    /// See [`BufferPoolManager::get_page`].
+    pub fn get_page_for(&self, tenant: TenantId, pid: &PageId) -> Result<PageHandle> {
+        let mut handle = self.get_page(pid)?;
+        handle.tenant = Some(tenant);
+
+        Ok(handle)
+    }
+
+    /// Creates a new temporary page and gets a thread-local [`PageHandle`] to it.
+    ///
+    /// A temporary page behaves like any other page while it is in memory, and starts out zeroed.
+    /// Its contents never need to survive the buffer pool manager though, which makes it suitable
+    /// as spill space for query operators: when it is evicted, it is written to a spill file next
+    /// to the first database file instead of the database files, and it is never written out by
+    /// [`BufferPoolManager::flush_all`] or [`BufferPoolManager::shutdown`]. The
+    /// [`WalHook`](crate::WalHook) is not awaited before temporary pages are written out.
+    ///
+    /// Every temporary page has a unique ID (see [`PageId::is_temp`]), which can be used to get
+    /// more handles to the page with [`BufferPoolManager::get_page`]. A temporary page lives until
+    /// it is dropped with [`BufferPoolManager::drop_temp_page`] or the buffer pool manager is shut
+    /// down, at which point the spill file is deleted.
+    ///
+    /// # Errors
+    ///
+    /// If this function is unable to create a [`File`](tokio_uring::fs::File), this function will
+    /// raise the I/O error in the form of [`Result`].
+    pub fn new_temp_page(&self) -> Result<PageHandle> {
+        let sm = StorageManager::get().create_handle()?;
+
+        let pid = PageId::temp(self.next_temp_page.fetch_add(1, Ordering::Relaxed));
+        let page = Arc::new(Page::new(pid));
+
+        let inserted = self.pages.insert(pid, page.clone()).is_ok();
+        debug_assert!(inserted, "Temporary page IDs are never reused");
+
+        Ok(PageHandle::new(page, sm))
+    }
+
+    /// Drops a temporary page created with [`BufferPoolManager::new_temp_page`], discarding its
+    /// contents and freeing its frame (if it is in memory) without writing it out.
+    ///
+    /// Every other handle to the page is invalidated, and returns a [`BpmError::PageNotFound`]
+    /// error when it is used afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidInput`](ErrorKind::InvalidInput) if the page is not a
+    /// temporary page, or an error if the page is still locked by a page guard or still shared by
+    /// copy-on-write clones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the page's frame is not owned by the page, which should never happen.
+    pub async fn drop_temp_page(&self, handle: PageHandle) -> Result<()> {
+        let page = &handle.page;
+        if !page.pid.is_temp() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} is not a temporary page", page.pid),
+            )
+            .into());
+        }
+
+        let Ok(mut guard) = page.frame.try_write() else {
+            return Err(Error::other(format!("{} is still in use", page.pid)).into());
+        };
+        page.check_no_clones()?;
+        #[cfg(feature = "experimental")]
+        if !page.try_invalidate_replicas() {
+            return Err(Error::other(format!("{} is still in use", page.pid)).into());
+        }
+
+        if let Some(mut frame) = guard.take() {
+            page.set_evicted();
+            frame.clear_dirty();
+            frame
+                .evict_page_owner()
+                .expect("A page's frame is always owned by the page");
+            frame.group().release_frame(frame).await;
+        }
+
+        page.set_removed();
+
+        drop(guard);
+        self.pages.remove(&page.pid);
+
+        Ok(())
+    }
+
+    /// Attempts to get a thread-local [`PageHandle`] to a page that is already in memory, without
+    /// blocking.
+    ///
+    /// Unlike [`BufferPoolManager::get_page`], this function never creates a page and never takes
+    /// an exclusive lock on the page table, so it is intended as a fast path for workloads where
+    /// most pages are cached. Returns `None` if the page is not in memory (or is being loaded or
+    /// evicted), or if this thread is unable to create a [`File`](tokio_uring::fs::File), in which
+    /// case the caller should fall back to [`BufferPoolManager::get_page`].
+    ///
+    /// Note that the page may still be evicted after this returns, in which case getting a guard
+    /// from the handle loads the page again.
+    pub fn try_get_page(&self, pid: &PageId) -> Option<PageHandle> {
+        let page = self.pages.read(pid, |_, page| page.clone())?;
+
+        if !page.is_loaded.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let sm = StorageManager::get().create_handle().ok()?;
+
+        Some(PageHandle::new(page, sm))
+    }
+
+    /// Gets read guards on a batch of logical pages, which guarantees that all of their data is in
+    /// memory.
+    ///
+    /// This function behaves like calling [`PageHandle::read`] on every handle, except that it
+    /// allocates frames for every page that is not in memory up front and then submits all of the
+    /// reads to the `io_uring` instance together, instead of waiting for each read to complete
+    /// before submitting the next one. Pages that are not in memory and have consecutive
+    /// [`PageId`]s are read with a single vectored read per drive. This is intended for workloads
+    /// that scan ranges of pages.
+    ///
+    /// The guards are returned in the same order as the handles. Every page is locked in
+    /// [`PageId`] order, so concurrent batches on overlapping pages will not deadlock. Since a
+    /// copy-on-write clone that still shares its source's frame would have to lock its source
+    /// instead, every such clone gets a frame of its own first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if the same page appears more
+    /// than once in `handles`, or if `handles` holds more pages than the buffer pool has frames,
+    /// since every page of the batch stays in memory until its guard is dropped. Otherwise, raises
+    /// an error if an I/O error occurs while trying to load any of the pages into memory. Pages
+    /// that were loaded successfully stay in memory.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the eviction state lock of a frame group was poisoned and the
+    /// buffer pool manager is configured with [`PoisonPolicy::Panic`].
+    pub async fn read_pages<'a>(
+        &self,
+        handles: &'a [PageHandle],
+    ) -> Result<Vec<ReadPageGuard<'a>>> {
+        // Lock the pages in `PageId` order so that concurrent batches cannot deadlock.
+        let mut order: Vec<usize> = (0..handles.len()).collect();
+        order.sort_unstable_by_key(|&i| handles[i].page.pid.as_u64());
+
+        if order
+            .windows(2)
+            .any(|w| handles[w[0]].page.pid == handles[w[1]].page.pid)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Tried to read the same page more than once in a single batch",
+            )
+            .into());
+        }
+
+        if handles.len() > self.num_frames() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Tried to read more pages in a single batch than the buffer pool has frames",
+            )
+            .into());
+        }
+
+        for handle in handles {
+            handle.materialize().await?;
+        }
+
+        let mut guards: Vec<Option<RwLockWriteGuard<'a, Option<Frame>>>> =
+            handles.iter().map(|_| None).collect();
+        let mut misses: Vec<usize> = Vec::new();
+
+        // The number of frames of every frame group that the batch holds.
+        let mut held = vec![0; self.frame_groups.len()];
+
+        for i in order {
+            let guard = handles[i].page.lock_write().await;
+            if let Some(frame) = guard.as_ref() {
+                held[frame.group_id()] += 1;
+            } else {
+                if handles[i].page.is_removed() {
+                    return Err(BpmError::PageNotFound(handles[i].page.pid));
+                }
+                handles[i].page.check_loadable()?;
+                self.stats.record_miss();
+                misses.push(i);
+            }
+            guards[i] = Some(guard);
+        }
+
+        // Allocate a frame for every miss before submitting any reads, so that we can give the
+        // frames back if we fail to allocate all of them.
+        let mut frames: Vec<Frame> = Vec::with_capacity(misses.len());
+        for _ in &misses {
+            match self.get_batch_frame(&held).await {
+                Ok(frame) => {
+                    held[frame.group_id()] += 1;
+                    frames.push(frame);
+                }
+                Err(e) => {
+                    for frame in frames {
+                        frame.group().release_frame(frame).await;
+                    }
+                    return Err(e.into());
+                }
+            }
+        }
+
+        // Misses are in `PageId` order, so runs of consecutive pages can be read with a single
+        // vectored read.
+        let mut runs: Vec<Vec<usize>> = Vec::new();
+        for &i in &misses {
+            let pid = handles[i].page.pid;
+            match runs.last_mut() {
+                Some(run)
+                    if !pid.is_temp()
+                        && handles[run[run.len() - 1]].page.pid.as_u64() + 1 == pid.as_u64() =>
+                {
+                    run.push(i);
+                }
+                _ => runs.push(vec![i]),
+            }
+        }
+
+        // Spawn every read before awaiting any of them, so that they are all submitted together.
+        let mut frames = frames.into_iter();
+        let reads: Vec<_> = runs
+            .into_iter()
+            .map(|run| {
+                let run_frames: Vec<Frame> = run
+                    .iter()
+                    .zip(frames.by_ref())
+                    .map(|(&i, mut frame)| {
+                        let none = frame.replace_page_owner(handles[i].page.clone());
+                        debug_assert!(none.is_none());
+                        frame
+                    })
+                    .collect();
+
+                let sm = handles[run[0]].sm.clone();
+                let start = handles[run[0]].page.pid;
+                let read = executor::spawn_local(async move {
+                    match <[Frame; 1]>::try_from(run_frames) {
+                        Ok([frame]) => {
+                            let (res, frame) = sm.read_into(start, frame).await;
+                            (res, vec![frame])
+                        }
+                        Err(run_frames) => sm.read_range_into(start, run_frames).await,
+                    }
+                });
+
+                (run, read)
+            })
+            .collect();
+
+        // Await every read, even once one has failed, so that every frame is either installed or
+        // given back.
+        let mut error = None;
+
+        for (run, read) in reads {
+            // If the read task was dropped, its frames were given back when they were dropped.
+            let (res, run_frames) = match read.await {
+                Ok(read) => read,
+                Err(e) => {
+                    error.get_or_insert(Error::other(e).into());
+                    continue;
+                }
+            };
+
+            if let Err(e) = res {
+                for mut frame in run_frames {
+                    frame.evict_page_owner();
+                    frame.group().release_frame(frame).await;
+                }
+                error.get_or_insert(BpmError::at(handles[run[0]].page.pid, e));
+                continue;
+            }
+
+            for (i, frame) in run.into_iter().zip(run_frames) {
+                let page = &handles[i].page;
+                let guard = guards[i]
+                    .as_mut()
+                    .expect("We locked every page in the batch");
+
+                page.set_loaded(&frame);
+                let old = guard.replace(frame);
+                debug_assert!(old.is_none());
+                self.record_page_event(PageEvent::Load(page.pid));
+            }
+        }
+
+        // Every page that is in memory is accessed, including the pages that were loaded by a batch
+        // that failed, so that their frames become candidates for eviction.
+        let mut loaded = Vec::with_capacity(handles.len());
+        for (handle, guard) in handles.iter().zip(guards) {
+            let guard = guard.expect("We locked every page in the batch");
+            let Some(frame) = guard.as_ref() else {
+                debug_assert!(error.is_some(), "We loaded every page in the batch");
+                continue;
+            };
+
+            handle.page.is_loaded.store(true, Ordering::Release);
+            if let Err(e) = frame.record_access(&handle.page) {
+                error.get_or_insert(e.into());
+            }
+
+            loaded.push(ReadPageGuard::new(&handle.page, guard.downgrade()));
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(loaded),
+        }
+    }
+
+    /// Gets a free frame for a page of a batch read, where `held` counts the frames of every frame
+    /// group that the batch already holds (see [`BufferPoolManager::read_pages`]).
+    ///
+    /// A batch keeps every one of its pages locked, so it can never evict its own pages. Thus, if
+    /// the chosen frame group has no free frame, this takes a free frame from any other frame group
+    /// before evicting anything, and only evicts pages of a frame group whose frames are not all
+    /// held by the batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while evicting a page, or a [`BufferPoolFull`]
+    /// error if the configured [free frame timeout](BufferPoolManagerConfig::free_frame_timeout)
+    /// expires.
+    ///
+    /// [`BufferPoolFull`]: crate::error::BufferPoolFull
+    async fn get_batch_frame(&self, held: &[usize]) -> io::Result<Frame> {
+        let group = self.get_random_frame_group();
+        if let Some(frame) = group.try_get_free_frame() {
+            return Ok(frame);
+        }
+
+        let groups = self.active_frame_groups();
+        if let Some(frame) = groups.iter().find_map(|group| group.try_get_free_frame()) {
+            return Ok(frame);
+        }
+
+        let group = if held[group.group_id] < group.num_frames {
+            group
+        } else {
+            groups
+                .iter()
+                .find(|group| held[group.group_id] < group.num_frames)
+                .cloned()
+                .unwrap_or(group)
+        };
+
+        group.get_free_frame().await
+    }
+
+    /// Lists the pages whose [`WritePageGuard`](crate::page::WritePageGuard)s have been
+    /// decomposed with [`into_raw_parts`](crate::page::WritePageGuard::into_raw_parts) but not yet
+    /// returned with [`from_raw_parts`](crate::page::WritePageGuard::from_raw_parts).
+    ///
+    /// Every page in this list is still write-locked, so a raw guard that is never returned is a
+    /// leak.
+    pub fn outstanding_raw_guards(&self) -> Vec<PageId> {
+        let mut pids = Vec::new();
+        self.raw_guards.scan(|pid| pids.push(*pid));
+        pids
+    }
+
+    /// Writes every dirty page in the buffer pool out to persistent storage, returning the number
+    /// of pages that were written.
+    ///
+    /// This function visits every [`FrameGroup`] and write-locks each of the pages that it holds,
+    /// so it waits for any outstanding guards on those pages to be dropped. Thus, a task must not
+    /// hold any page guards while calling this function, otherwise it will deadlock.
+    ///
+    /// Pages that are dirtied while this function runs may or may not be written out, so this
+    /// function only guarantees that every page that was dirty _before_ it was called has been
+    /// written out when it returns. This can be used to implement checkpoints. Copy-on-write clones
+    /// that still share their source's frame have nothing in persistent storage yet, so they are
+    /// given frames of their own and written out as well (see [`PageHandle::clone_cow`]).
+    ///
+    /// # Errors
+    ///
+    /// If any of the writes fail, this function still attempts to write out every other dirty
+    /// page, and then returns a [`FlushAllError`] that lists every page that failed.
+    pub async fn flush_all(&self) -> Result<usize> {
+        let sm = StorageManager::get().create_handle()?;
+
+        let mut flushed = 0;
+        let mut failures = self.materialize_clones().await;
+
+        for group in &self.frame_groups {
+            for page in group.resident_pages()? {
+                let mut guard = page.frame.write().await;
+
+                // Skip pages that were evicted, moved to another group, or are already clean, as
+                // well as temporary pages, which never need to be persisted.
+                let Some(frame) = guard.take_if(|frame| {
+                    frame.is_dirty() && frame.group_id() == group.group_id && !page.pid.is_temp()
+                }) else {
+                    continue;
+                };
+
+                let (res, mut frame) = sm.write_from(page.pid, frame).await;
+                match res {
+                    Ok(()) => {
+                        frame.clear_dirty();
+                        flushed += 1;
+                    }
+                    Err(e) => failures.push((page.pid, e)),
+                }
+
+                guard.replace(frame);
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(FlushAllError::new(failures).into());
+        }
+
+        Ok(flushed)
+    }
+
+    /// Flushes the pages of every guard in `guards` out to persistent storage.
+    ///
+    /// This behaves like calling [`WritePageGuard::flush`] on every guard, except that the guards
+    /// are sorted by [`PageId`] (so `guards` is reordered), and pages with consecutive IDs are
+    /// written with a single vectored write per drive instead of one write per page. Like
+    /// [`WritePageGuard::flush`], this only hands the pages to the operating system, see
+    /// [`BufferPoolManager::sync_all`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if two guards protect the same
+    /// page, in which case nothing is written. Otherwise, returns the first error of any of the
+    /// writes, after attempting every write. The pages that were written out are marked clean
+    /// either way.
+    pub async fn flush_pages(&self, guards: &mut [WritePageGuard<'_>]) -> Result<()> {
+        guards.sort_unstable_by_key(|guard| guard.pid().as_u64());
+        if let Some(pair) = guards
+            .windows(2)
+            .find(|pair| pair[0].pid() == pair[1].pid())
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Page {} was passed to flush_pages twice", pair[0].pid()),
+            )
+            .into());
+        }
+
+        let sm = StorageManager::get().create_handle()?;
+        let mut error = None;
+
+        for run in guards
+            .chunk_by_mut(|a, b| !b.pid().is_temp() && a.pid().as_u64() + 1 == b.pid().as_u64())
+        {
+            let res = match run {
+                [guard] => guard.flush().await,
+                run => {
+                    let start = run[0].pid();
+                    let frames = run.iter_mut().map(WritePageGuard::take_frame).collect();
+                    let (res, frames) = sm.write_range_from(start, frames).await;
+                    for (guard, frame) in run.iter_mut().zip(frames) {
+                        guard.restore_frame(frame, res.is_ok());
+                    }
+                    res.map_err(|e| BpmError::at(start, e))
+                }
+            };
+
+            if let Err(e) = res {
+                error.get_or_insert(e);
+            }
+        }
+
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Flushes every database file (and its metadata) to persistent storage with `fsync`.
+    ///
+    /// Writing a page out (for example, with [`BufferPoolManager::flush_all`]) only hands the data
+    /// to the operating system, so it can still be lost on a crash. Every write that completed
+    /// before this function was called is durable once it returns, so calling this after
+    /// [`BufferPoolManager::flush_all`] makes a checkpoint durable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files cannot be synced.
+    pub async fn sync_all(&self) -> Result<()> {
+        Ok(StorageManager::get().create_handle()?.sync_all().await?)
+    }
+
+    /// Flushes the data of every database file to persistent storage with `fdatasync`.
+    ///
+    /// This is the same as [`BufferPoolManager::sync_all`], except that file metadata that is not
+    /// needed to read the data back (such as modification times) is not flushed, which is cheaper.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files cannot be synced.
+    pub async fn sync_data(&self) -> Result<()> {
+        Ok(StorageManager::get().create_handle()?.sync_data().await?)
+    }
+
+    /// Shuts down the buffer pool manager and tears down all of its resources, so that a new buffer
+    /// pool manager can be initialized afterwards.
+    ///
+    /// This function:
+    /// - Stops every background daemon and waits for all of them to exit
+    /// - Writes every dirty page (including every copy-on-write clone that still shares its
+    ///   source's frame) out to persistent storage
+    /// - Reclaims every buffer frame and frees the memory of the buffer pool
+    /// - Closes the calling thread's database files and unregisters its registered buffers
+    /// - Resets the global state, after which [`BufferPoolManager::initialize`] can be called again
+    ///
+    /// Since every I/O operation owns the frame that it reads into or writes from, reclaiming every
+    /// frame also guarantees that there is no I/O in flight on any thread. Other threads close
+    /// their database files the next time they use a storage manager, or when they exit.
+    ///
+    /// Every page guard must be dropped before calling this function, and any [`PageHandle`]s that
+    /// were created before the shutdown must not be used afterwards. Daemons running on other
+    /// threads must still be making progress, otherwise this function waits for them forever.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page is still locked (for example, by a page guard or a raw guard that
+    /// was never returned), or if any dirty page fails to be written out. In either case, nothing
+    /// is torn down, and the caller may retry after fixing the problem. Note that the background
+    /// daemons have already been stopped at that point.
+    pub async fn shutdown(&self) -> Result<()> {
+        self.stop_daemons();
+        while self.daemons.num_alive() != 0 {
+            executor::sleep(Duration::from_millis(1)).await;
+        }
+
+        // Copy-on-write clones that still share their source's frame have nothing in persistent
+        // storage yet, so they need frames of their own to be written out.
+        let failures = self.materialize_clones().await;
+        if !failures.is_empty() {
+            return Err(FlushAllError::new(failures).into());
+        }
+
+        let mut pages: Vec<Arc<Page>> = Vec::new();
+        self.pages.scan(|_, page| pages.push(page.clone()));
+
+        // Lock every page so that no one can use them while we tear them down.
+        let mut guards = Vec::with_capacity(pages.len());
+        for page in &pages {
+            let Ok(guard) = page.frame.try_write() else {
+                return Err(Error::other(format!("{} is still in use", page.pid)).into());
+            };
+            guards.push((page, guard));
+        }
+
+        let resident = guards.iter().filter(|(_, guard)| guard.is_some()).count();
+        let free: usize = self.frame_groups.iter().map(|g| g.free_list.1.len()).sum();
+        if resident + free != self.num_frames {
+            return Err(Error::other("Some buffer frames are still in use").into());
+        }
+
+        // Write out every dirty page so that no data is lost. Temporary pages are simply discarded.
+        let sm = StorageManager::get().create_handle()?;
+        let mut failures = Vec::new();
+
+        for (page, guard) in &mut guards {
+            let Some(frame) = guard.take_if(|frame| frame.is_dirty() && !page.pid.is_temp()) else {
+                continue;
+            };
+
+            let (res, mut frame) = sm.write_from(page.pid, frame).await;
+            match res {
+                Ok(()) => frame.clear_dirty(),
+                Err(e) => failures.push((page.pid, e)),
+            }
+
+            guard.replace(frame);
+        }
+
+        if !failures.is_empty() {
+            return Err(FlushAllError::new(failures).into());
+        }
+
+        drop(sm);
+
+        // Reclaim every frame, breaking the reference cycles between frames and their pages.
+        for (page, guard) in &mut guards {
+            if let Some(mut frame) = guard.take() {
+                frame.evict_page_owner();
+            }
+            page.set_evicted();
+        }
+
+        for group in &self.frame_groups {
+            while group.free_list.1.try_recv().is_ok() {}
+            group.num_free_frames.store(0, Ordering::Release);
+
+            // The eviction states are only hints, so we do not care if the lock was poisoned.
+            let mut states = group
+                .eviction_states
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            states.fill_with(EvictionState::default);
+        }
+
+        drop(guards);
+        self.pages.clear();
+
+        // Every dirty page has been written out, so no I/O is in flight anymore.
+        if let Some(driver) = &self.io_driver {
+            driver.shutdown();
+        }
+
+        BPM.store(ptr::null_mut(), Ordering::Release);
+        StorageManager::shutdown();
+
+        for arena in &self.arenas {
+            // Safety: Every `Frame` that pointed into the arena has been dropped.
+            unsafe { arena.free() };
+        }
+
+        Ok(())
+    }
+
     /// Gets an [`Arc`] to a [`FrameGroup`] given the frame group ID.
     pub(crate) fn get_frame_group(&self, group_id: usize) -> Arc<FrameGroup> {
         self.frame_groups[group_id].clone()
     }
 
-    /// Gets an [`Arc`] to a random [`FrameGroup`] in the buffer pool manager.
+    /// Gets how long after a recorded access of a page further accesses skip recording, if they
+    /// skip it at all.
+    ///
+    /// See [`BufferPoolManagerConfig::hot_access_threshold`].
+    pub(crate) fn hot_access_threshold(&self) -> Option<Duration> {
+        self.config.hot_access_threshold
+    }
+
+    /// Gets how long a prefetched page that has not been accessed yet is protected from eviction.
+    ///
+    /// See [`BufferPoolManagerConfig::prefetch_expiry`].
+    pub(crate) fn prefetch_expiry(&self) -> Duration {
+        self.config.prefetch_expiry
+    }
+
+    /// See [`BufferPoolManagerConfig::eviction_time_budget`].
+    pub(crate) fn eviction_time_budget(&self) -> Option<Duration> {
+        self.config.eviction_time_budget
+    }
+
+    /// Gets the largest number of frames that `tenant` may keep resident before it evicts its own
+    /// pages, if it has a quota.
+    ///
+    /// See [`BufferPoolManagerConfig::tenant_quota`].
+    pub(crate) fn tenant_quota(&self, tenant: TenantId) -> Option<usize> {
+        self.config.tenant_quotas.get(&tenant).copied()
+    }
+
+    /// See [`BufferPoolManagerConfig::preload_fraction`].
+    pub(crate) fn preload_fraction(&self) -> f64 {
+        self.config.preload_fraction
+    }
+
+    /// See [`BufferPoolManagerConfig::lock_wait_threshold`].
+    #[cfg(feature = "deadlock-detection")]
+    pub(crate) fn lock_wait_threshold(&self) -> Duration {
+        self.config.lock_wait_threshold
+    }
+
+    /// See [`BufferPoolManagerConfig::max_in_flight_io`].
+    pub(crate) fn max_in_flight_io(&self) -> Option<usize> {
+        self.config.max_in_flight_io
+    }
+
+    /// Gets the dedicated I/O driver threads, if there are any.
+    ///
+    /// See [`BufferPoolManagerConfig::io_threads`].
+    pub(crate) fn io_driver(&self) -> Option<&IoDriver> {
+        self.io_driver.as_ref()
+    }
+
+    /// See [`BufferPoolManagerConfig::eviction_write_batch`].
+    pub(crate) fn eviction_write_batch(&self) -> usize {
+        self.config.eviction_write_batch
+    }
+
+    /// See [`BufferPoolManagerConfig::loaded_hint`].
+    pub(crate) fn loaded_hint(&self) -> bool {
+        self.config.loaded_hint
+    }
+
+    /// Gets how long a task waits for a free frame before giving up, if it gives up at all.
+    pub(crate) fn free_frame_timeout(&self) -> Option<Duration> {
+        self.config.free_frame_timeout
+    }
+
+    /// Gets all of the [`FrameGroup`]s in the buffer pool manager, including retired ones.
+    pub(crate) fn frame_groups(&self) -> &[Arc<FrameGroup>] {
+        &self.frame_groups
+    }
+
+    /// Gets the [`FrameGroup`]s that are in use, excluding the ones that were retired by
+    /// [`BufferPoolManager::resize`].
+    pub(crate) fn active_frame_groups(&self) -> &[Arc<FrameGroup>] {
+        &self.frame_groups[..self.active_groups.load(Ordering::Acquire)]
+    }
+
+    /// Gets an [`Arc`] to a random [`FrameGroup`] in the buffer pool manager, chosen according to
+    /// the configured [`GroupSelection`].
+    ///
+    /// In NUMA-aware mode, this only picks from the frame groups that are local to the NUMA node
+    /// that the calling thread is running on (if there are any). Retired frame groups are never
+    /// picked.
     ///
     /// Intended for use by an eviction algorithm.
     pub(crate) fn get_random_frame_group(&self) -> Arc<FrameGroup> {
-        let mut rng = rand::thread_rng();
-        let index = rng.gen_range(0..self.frame_groups.len());
+        let active = self.active_groups.load(Ordering::Acquire);
+
+        if let Some(local) = self.numa.as_ref().and_then(NumaLayout::local_groups) {
+            // The local frame groups are sorted by ID, so the active ones come first.
+            let local = &local[..local.partition_point(|&id| id < active)];
+            if !local.is_empty() {
+                let free_frames = |i: usize| self.frame_groups[local[i]].num_free_frames();
+                let index = local[self.group_selector.pick(local.len(), free_frames)];
+                return self.get_frame_group(index);
+            }
+        }
+
+        let free_frames = |i: usize| self.frame_groups[i].num_free_frames();
+        let index = self.group_selector.pick(active, free_frames);
 
         self.get_frame_group(index)
     }
 
     /// Starts a [`tokio_uring`] runtime on a single thread that runs the given [`Future`].
     ///
+    /// If the buffer pool manager is initialized, the runtime's `io_uring` instance is set up
+    /// according to its configuration (see [`BufferPoolManagerConfig::sqpoll`]). Once the future
+    /// completes, the thread's file handles and registered buffers are released, so a thread can
+    /// start several runtimes one after another.
+    ///
     /// TODO more docs
     ///
     /// # Panics
@@ -186,7 +1342,58 @@ impl BufferPoolManager {
         //         _ = Self::spawn_evictor() => unreachable!("The eviction task should never return")
         //     }
         // })
-        tokio_uring::start(future)
+        let runtime =
+            Self::new_runtime().expect("Thread is unable to create a tokio_uring runtime");
+        let output = runtime.block_on(async move {
+            let output = future.await;
+
+            // The thread-local storage state is tied to this runtime's `io_uring` instance, so it
+            // must not outlive the runtime.
+            StorageManager::release_thread_state();
+
+            output
+        });
+
+        // Dropping the runtime waits for the kernel to finish every operation that is still in
+        // flight, after which the frames of interrupted operations can be recovered.
+        drop(runtime);
+        Frame::recover_interrupted();
+
+        output
+    }
+
+    /// Creates a [`tokio_uring`] runtime for the current thread, set up according to the
+    /// configuration of the buffer pool manager if it is initialized.
+    ///
+    /// With submission queue polling enabled, the `NEED_WAKEUP` flag of the submission queue is
+    /// handled by the `io_uring` submitter: every submission checks whether the kernel's polling
+    /// thread has gone to sleep, and only then enters the kernel with `IORING_ENTER_SQ_WAKEUP` to
+    /// wake it up. If the kernel refuses to set up a polling thread, this falls back to a regular
+    /// `io_uring` instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime or its `io_uring` instance cannot be created.
+    pub(crate) fn new_runtime() -> io::Result<tokio_uring::Runtime> {
+        let sqpoll_idle = Self::is_initialized()
+            .then(|| Self::get().config.sqpoll_idle)
+            .flatten();
+
+        if let Some(idle) = sqpoll_idle {
+            let idle_ms = u32::try_from(idle.as_millis()).unwrap_or(u32::MAX);
+
+            let mut uring = tokio_uring::uring_builder();
+            uring.setup_sqpoll(idle_ms);
+
+            let mut builder = tokio_uring::builder();
+            builder.uring_builder(&uring);
+
+            if let Ok(runtime) = tokio_uring::Runtime::new(&builder) {
+                return Ok(runtime);
+            }
+        }
+
+        tokio_uring::Runtime::new(&tokio_uring::builder())
     }
 
     /// Spawns a thread-local task on the current thread.
@@ -195,34 +1402,47 @@ impl BufferPoolManager {
     ///
     /// TODO docs
     pub fn spawn_local<T: Future + 'static>(task: T) -> task::JoinHandle<T::Output> {
-        tokio_uring::spawn(task)
+        executor::spawn_local(task)
     }
 
     /// Spawns an eviction task.
     ///
-    /// TODO more docs
-    ///
-    /// # Panics
+    /// The eviction task runs in the background until [`BufferPoolManager::stop_daemons`] is
+    /// called. If it encounters an I/O error, it reports the error on the channel returned by
+    /// [`BufferPoolManager::daemon_errors`] and restarts after a backoff.
     ///
-    /// Panics if unable to evict frames due to an I/O error.
+    /// TODO more docs
     pub fn spawn_evictor() -> task::JoinHandle<()> {
-        tokio_uring::spawn(async {
+        daemon::spawn_daemon("evictor", || async {
             let bpm = Self::get();
             loop {
-                tokio::task::yield_now().await;
+                executor::yield_now().await;
 
                 let group = bpm.get_random_frame_group();
-                if group.num_free_frames() < FRAME_GROUP_SIZE / 10 {
-                    group
-                        .cool_frames()
-                        .await
-                        .expect("Unable to evict frames due to I/O error");
+                if group.is_under_pressure() {
+                    group.cool_frames(None).await?;
                 }
 
                 // Sleep once we have nothing to do.
                 // TODO removing this should not cause the system to halt.
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                executor::sleep(Duration::from_millis(100)).await;
             }
         })
     }
+
+    /// Signals every background daemon (for example, the eviction task) to stop.
+    ///
+    /// Daemons stop at their next `.await` point, and no daemon can be restarted afterwards.
+    pub fn stop_daemons(&self) {
+        self.daemons.signal_shutdown();
+    }
+
+    /// Gets a receiver for the errors that the background daemons have encountered.
+    ///
+    /// Daemons restart themselves after failing, so their errors are never returned to a caller
+    /// directly. Only a bounded number of errors are buffered, and any errors that arrive while the
+    /// buffer is full are dropped.
+    pub fn daemon_errors(&self) -> Receiver<DaemonError> {
+        self.daemons.errors()
+    }
 }
@@ -0,0 +1,243 @@
+//! This module contains [`PageBuf`] and the reads that bypass the buffer pool, which let a scan
+//! that touches every page only once read pages without taking frames away from other pages.
+//!
+//! A bypassing read of a page that is not in memory reads the page from persistent storage
+//! straight into a caller-provided [`PageBuf`], without taking a free frame, evicting anything, or
+//! recording an access with the replacer. A page that is in memory is copied out of its frame
+//! instead, since its frame may hold changes that have not been written out yet.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::{BpmError, Result};
+use crate::page::{PageHandle, PageId, DIRECT_IO_ALIGNMENT};
+use crate::storage::{join_all, StorageManager};
+use std::alloc::{self, Layout};
+use std::fmt::Debug;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::slice;
+use tokio_uring::buf::{IoBuf, IoBufMut};
+
+/// A buffer that holds a copy of a single page, aligned for direct I/O.
+///
+/// A `PageBuf` is empty when it is created, and holds an entire page (including any reserved
+/// bytes, see [`BufferPoolManagerConfig::user_region`]) once a page has been read into it with
+/// [`BufferPoolManager::read_bypass`]. Reusing the same buffer for every page of a scan avoids an
+/// allocation per page.
+///
+/// [`BufferPoolManagerConfig::user_region`]: crate::BufferPoolManagerConfig::user_region
+pub struct PageBuf {
+    /// The start of the buffer, which is aligned to [`DIRECT_IO_ALIGNMENT`].
+    ptr: NonNull<u8>,
+
+    /// The length of the buffer, which is `0` if nothing has been allocated.
+    len: usize,
+}
+
+// Safety: A `PageBuf` owns its memory, just like a `Box<[u8]>`.
+unsafe impl Send for PageBuf {}
+
+// Safety: A `PageBuf` only hands out mutable access to its memory through `&mut self`.
+unsafe impl Sync for PageBuf {}
+
+impl PageBuf {
+    /// Creates an empty `PageBuf`, which does not allocate until a page is read into it.
+    pub fn new() -> Self {
+        // A dangling pointer that is still aligned, which is never dereferenced.
+        // Safety: The alignment is never zero.
+        let ptr = unsafe { NonNull::new_unchecked(DIRECT_IO_ALIGNMENT as *mut u8) };
+
+        Self { ptr, len: 0 }
+    }
+
+    /// Allocates a `PageBuf` of `len` zeroed bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is zero, and aborts if the allocation fails.
+    fn zeroed(len: usize) -> Self {
+        let layout = Self::layout(len);
+        assert_ne!(layout.size(), 0, "Cannot allocate an empty page buffer");
+
+        // Safety: The layout has a non-zero size.
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            alloc::handle_alloc_error(layout);
+        };
+
+        Self { ptr, len }
+    }
+
+    /// Gets the layout of a buffer of `len` bytes.
+    fn layout(len: usize) -> Layout {
+        Layout::from_size_align(len, DIRECT_IO_ALIGNMENT).expect("The page buffer is too large")
+    }
+
+    /// Makes sure that this buffer is exactly `len` bytes long, reallocating it if it is not.
+    fn resize(&mut self, len: usize) {
+        if self.len != len {
+            *self = Self::zeroed(len);
+        }
+    }
+}
+
+impl Default for PageBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for PageBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PageBuf").field("len", &self.len).finish()
+    }
+}
+
+impl Drop for PageBuf {
+    fn drop(&mut self) {
+        if self.len != 0 {
+            // Safety: The buffer was allocated by `PageBuf::zeroed` with the same layout.
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), Self::layout(self.len)) };
+        }
+    }
+}
+
+impl Deref for PageBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        // Safety: The buffer holds `len` initialized bytes (or is empty).
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for PageBuf {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // Safety: The buffer holds `len` initialized bytes (or is empty), and we have `&mut self`.
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+/// # Safety
+///
+/// The safety contract for `IoBuf` is as follows:
+/// > Buffers passed to `io-uring` operations must reference a stable memory region. While the
+/// > runtime holds ownership to a buffer, the pointer returned by `stable_ptr` must remain valid
+/// > even if the `IoBuf` value is moved.
+///
+/// The memory of a `PageBuf` is on the heap, so it does not move with the `PageBuf`.
+unsafe impl IoBuf for PageBuf {
+    fn stable_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
+    }
+
+    fn bytes_init(&self) -> usize {
+        self.len
+    }
+
+    fn bytes_total(&self) -> usize {
+        self.len
+    }
+}
+
+/// # Safety
+///
+/// The safety contract for `IoBufMut` is as follows:
+/// > Buffers passed to `io-uring` operations must reference a stable memory region. While the
+/// > runtime holds ownership to a buffer, the pointer returned by `stable_mut_ptr` must remain
+/// > valid even if the `IoBufMut` value is moved.
+///
+/// The memory of a `PageBuf` is on the heap, so it does not move with the `PageBuf`.
+unsafe impl IoBufMut for PageBuf {
+    fn stable_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    unsafe fn set_init(&mut self, _pos: usize) {
+        // All bytes are initialized on allocation, so this function is a no-op.
+    }
+}
+
+impl BufferPoolManager {
+    /// Reads the page `pid` into `buf` without bringing it into the buffer pool.
+    ///
+    /// If the page is not in memory, it is read from persistent storage directly into `buf`, like
+    /// any other read (checksums are verified and the registered
+    /// [`PageCodec`](crate::PageCodec) decodes the page), but without taking a frame or recording
+    /// an access with the replacer, so a scan that reads every page only once does not evict the
+    /// working set of everyone else. If the page is in memory, its data is copied from its frame,
+    /// since the frame may hold changes that were not written out yet. A copy-on-write clone that
+    /// still shares its source's frame is read like with [`PageHandle::read`], since it has nothing
+    /// in persistent storage yet.
+    ///
+    /// `buf` is resized to the page size if it is not already that large, and holds the entire
+    /// page (including any reserved bytes) afterwards. If this future is dropped before it
+    /// completes, `buf` is left empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read fails, in which case the contents of `buf` are unspecified.
+    pub async fn read_bypass(&self, pid: &PageId, buf: &mut PageBuf) -> Result<()> {
+        let page_size = StorageManager::get().page_size();
+
+        let page = self.pages.read(pid, |_, page| page.clone());
+        let guard = match &page {
+            Some(page) => Some(page.frame.read().await),
+            None => None,
+        };
+
+        // A resident page may be newer than its copy in persistent storage.
+        if let Some(frame) = guard.as_deref().and_then(Option::as_ref) {
+            buf.resize(page_size);
+            buf.copy_from_slice(frame);
+            return Ok(());
+        }
+
+        // A copy-on-write clone that still shares its source's frame has nothing in persistent
+        // storage yet, so it is read through the buffer pool, which reads its source's frame.
+        if let Some(page) = page.as_ref().filter(|page| page.cow.is_shared()) {
+            drop(guard);
+            let ph = PageHandle::new(page.clone(), StorageManager::get().create_handle()?);
+            let guard = ph.read().await?;
+            buf.resize(page_size);
+            buf.copy_from_slice(guard.raw());
+            return Ok(());
+        }
+
+        // Otherwise, holding the page's read lock keeps anyone from loading and modifying it until
+        // we are done.
+        let mut owned = mem::take(buf);
+        owned.resize(page_size);
+
+        let sm = StorageManager::get().create_handle()?;
+        let (res, owned) = sm.read_bypass(*pid, owned).await;
+        *buf = owned;
+        drop(guard);
+
+        res.map_err(|e| BpmError::at(*pid, e))
+    }
+
+    /// Reads the contiguous pages `start, start + 1, ...` into `bufs` without bringing them into
+    /// the buffer pool, where the `i`-th buffer receives page `start + i`.
+    ///
+    /// Every page is read like with [`BufferPoolManager::read_bypass`], and all of the reads are
+    /// submitted together.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first page that could not be read, in which case the contents of
+    /// every buffer are unspecified.
+    pub async fn read_range_bypass(&self, start: &PageId, bufs: &mut [PageBuf]) -> Result<()> {
+        let pids: Vec<PageId> = (start.as_u64()..start.as_u64() + bufs.len() as u64)
+            .map(PageId::new)
+            .collect();
+
+        let reads = pids
+            .iter()
+            .zip(bufs.iter_mut())
+            .map(|(pid, buf)| self.read_bypass(pid, buf))
+            .collect();
+
+        join_all(reads).await.into_iter().collect()
+    }
+}
@@ -0,0 +1,257 @@
+//! This module contains [`BufferPoolManager::commit_pages`], which writes out several pages
+//! atomically with respect to crashes.
+//!
+//! Writing out a page is atomic on its own (at least for page sizes that the drive writes
+//! atomically), but a structure modification of an index (for example, a B+ tree split) usually
+//! modifies several pages at once, and a crash in the middle of writing them out leaves the
+//! structure half-modified. Instead of requiring a full write-ahead log for such modifications,
+//! [`BufferPoolManager::commit_pages`] uses a doublewrite file:
+//!
+//! 1. The images of every page are staged in the doublewrite file, along with a checksum of the
+//!    whole file, and the file is synced.
+//! 2. Every page is written to its home location in the database files, and the database files are
+//!    synced.
+//! 3. The doublewrite file is invalidated.
+//!
+//! If the buffer pool crashes before the staged images are durable, their checksum does not
+//! match, none of the pages were written to their home locations yet, and the staged images are
+//! discarded. If it crashes after that, the next initialization replays every staged image to its
+//! home location. Either way, either all or none of the pages have their new contents.
+//!
+//! The doublewrite file lives next to the first database file, with the extension `dwb`.
+
+use crate::bpm::BufferPoolManager;
+use crate::files::HomeFiles;
+use crate::page::{PageId, WritePageGuard};
+use crate::storage::{checksum, StorageManager};
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Result};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+
+/// The magic number at the start of a valid doublewrite file.
+const MAGIC: [u8; 8] = *b"BPMDWB01";
+
+/// The length of the header of a doublewrite file: the magic number, the page size, and the
+/// number of staged pages.
+const HEADER_LEN: usize = 24;
+
+/// A page staged in a doublewrite file, along with its image.
+type StagedPage<'a> = (PageId, &'a [u8]);
+
+/// Gets the path to the doublewrite file of the database files at the given paths.
+fn doublewrite_path(paths: &[PathBuf]) -> PathBuf {
+    paths[0].with_extension("dwb")
+}
+
+/// Encodes the doublewrite file that stages the given page images.
+///
+/// The file holds the header, the ID of every page, the image of every page, and finally the
+/// CRC32C checksum of everything before it.
+fn encode(page_size: usize, pages: &[StagedPage<'_>]) -> Vec<u8> {
+    let len = HEADER_LEN + pages.len() * (8 + page_size) + checksum::CHECKSUM_SIZE;
+    let mut buf = Vec::with_capacity(len);
+
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&(page_size as u64).to_le_bytes());
+    buf.extend_from_slice(&(pages.len() as u64).to_le_bytes());
+    for (pid, _) in pages {
+        buf.extend_from_slice(&pid.as_u64().to_le_bytes());
+    }
+    for (_, image) in pages {
+        debug_assert_eq!(image.len(), page_size);
+        buf.extend_from_slice(image);
+    }
+
+    let crc = checksum::crc32c(&buf);
+    buf.extend_from_slice(&crc.to_le_bytes());
+
+    debug_assert_eq!(buf.len(), len);
+    buf
+}
+
+/// Decodes a doublewrite file into its page size and staged page images.
+///
+/// Returns `None` if the file does not hold a complete, valid set of staged pages, which is the
+/// case if it was invalidated, or if the buffer pool crashed while staging the pages.
+fn decode(buf: &[u8]) -> Option<(usize, Vec<StagedPage<'_>>)> {
+    let body_len = buf.len().checked_sub(checksum::CHECKSUM_SIZE)?;
+    if body_len < HEADER_LEN || buf[..MAGIC.len()] != MAGIC {
+        return None;
+    }
+
+    let (body, crc) = buf.split_at(body_len);
+    if checksum::crc32c(body) != u32::from_le_bytes(crc.try_into().ok()?) {
+        return None;
+    }
+
+    let word = |i: usize| u64::from_le_bytes(body[8 * i..8 * (i + 1)].try_into().unwrap());
+    let page_size = word(1) as usize;
+    let count = word(2) as usize;
+
+    if body.len() != HEADER_LEN + count * (8 + page_size) {
+        return None;
+    }
+
+    let images = body[HEADER_LEN + count * 8..].chunks_exact(page_size);
+    let pages = (0..count)
+        .map(|i| PageId::new(word(3 + i)))
+        .zip(images)
+        .collect();
+
+    Some((page_size, pages))
+}
+
+/// Replays the pages of an interrupted [`BufferPoolManager::commit_pages`] to their home locations
+/// in the database files at the given paths (or in the data files next to them), returning the
+/// number of pages that were replayed.
+///
+/// This must be called before the database files are opened by the storage manager.
+///
+/// # Errors
+///
+/// Returns an error if the doublewrite file or the database files cannot be read or written, or if
+/// the staged pages have a different page size than `page_size`.
+pub(crate) fn recover(paths: &[PathBuf], page_size: usize) -> Result<usize> {
+    let Some(path) = paths.first().map(|_| doublewrite_path(paths)) else {
+        return Ok(0);
+    };
+
+    let mut buf = Vec::new();
+    match File::open(&path) {
+        Ok(mut file) => file.read_to_end(&mut buf)?,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let Some((staged_page_size, pages)) = decode(&buf) else {
+        // The pages were never published, so the (partially) staged images can be discarded.
+        invalidate(&path)?;
+        return Ok(0);
+    };
+
+    if staged_page_size != page_size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "The doublewrite file {} holds pages of {staged_page_size} bytes, but the page \
+                 size is {page_size} bytes",
+                path.display()
+            ),
+        ));
+    }
+
+    let mut files = HomeFiles::open(paths, page_size)?;
+    for &(pid, image) in &pages {
+        if let Some((file, offset)) = files.locate(pid)? {
+            file.write_all_at(image, offset)?;
+        }
+    }
+    files.sync_all()?;
+
+    invalidate(&path)?;
+
+    Ok(pages.len())
+}
+
+/// Durably empties the doublewrite file at the given path.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be truncated or synced.
+fn invalidate(path: &Path) -> Result<()> {
+    let file = OpenOptions::new().write(true).open(path)?;
+    file.set_len(0)?;
+    file.sync_all()
+}
+
+impl BufferPoolManager {
+    /// Writes out the pages of every given write guard, such that after a crash either all or none
+    /// of the pages have their new contents on persistent storage.
+    ///
+    /// The pages are first staged in a doublewrite file next to the first database file, which is
+    /// synced, and only then written to their home locations, which are synced as well. If the
+    /// process crashes after the pages were staged, the next initialization of the buffer pool
+    /// manager replays the staged pages to their home locations. If it crashes before that, none of
+    /// the pages were written to their home locations yet.
+    ///
+    /// Like [`WritePageGuard::flush`], the registered [`WalHook`](crate::WalHook) is awaited
+    /// for every dirty page before any page is staged, and every page is clean once this returns
+    /// successfully. Commits are serialized with each other, but not with other writes of the same
+    /// pages, which are impossible anyway as long as the caller holds their write guards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registered [`WalHook`](crate::WalHook) fails, or if a page cannot
+    /// be staged or written out. If the error happens after the pages were staged, the pages stay
+    /// dirty in memory, and the staged images are replayed the next time the buffer pool manager is
+    /// initialized, unless another commit overwrites them first.
+    pub async fn commit_pages(&self, guards: &mut [WritePageGuard<'_>]) -> Result<()> {
+        if guards.is_empty() {
+            return Ok(());
+        }
+
+        let _commit = self.commit_lock.lock().await;
+
+        let sm = StorageManager::get();
+        let checksums = sm.checksums();
+
+        // Write-ahead logging applies to the staged images as well, since they may be replayed.
+        for guard in guards.iter_mut() {
+            let pid = guard.pid();
+            let frame = guard.frame_mut();
+            if frame.is_dirty() {
+                let dirty = frame.dirty_range().unwrap_or(0..frame.data().len());
+                self.before_write_back(pid, frame.lsn(), dirty).await?;
+            }
+        }
+
+        // The staged images are replayed as they are, so they are encoded and sealed like every
+        // other write, but the pages in memory must not stay encoded.
+        let sealed = guards.iter_mut().try_for_each(|guard| -> Result<()> {
+            let pid = guard.pid();
+            let frame = guard.frame_mut();
+            if let Some(codec) = self.page_codec() {
+                frame.encode(pid, codec, sm.codec_len())?;
+            }
+            if checksums {
+                checksum::seal(frame);
+            }
+            Ok(())
+        });
+        let staged = sealed.map(|()| {
+            let pages: Vec<StagedPage<'_>> = guards
+                .iter_mut()
+                .map(|guard| (guard.pid(), &**guard.frame_mut()))
+                .collect();
+            encode(sm.page_size(), &pages)
+        });
+        guards
+            .iter_mut()
+            .for_each(|guard| guard.frame_mut().restore_plain());
+        let staged = staged?;
+
+        // Stage the page images.
+        let file = tokio_uring::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(doublewrite_path(sm.paths()))
+            .await?;
+        let (res, _) = file.write_all_at(staged, 0).await;
+        res?;
+        file.sync_all().await?;
+
+        // Publish the pages to their home locations.
+        for guard in guards.iter_mut() {
+            guard.flush().await?;
+        }
+        sm.create_handle()?.sync_all().await?;
+
+        // Invalidate the staged images, so that they are never replayed over newer writes.
+        let (res, _) = file.write_all_at(vec![0u8; MAGIC.len()], 0).await;
+        res?;
+        file.sync_all().await?;
+        file.close().await
+    }
+}
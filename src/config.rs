@@ -0,0 +1,1118 @@
+//! This module contains the [`BufferPoolManagerConfig`] type, which is used to configure a
+//! [`BufferPoolManager`](crate::BufferPoolManager) before it is initialized.
+//!
+//! All of the options have sensible defaults, so the only values that a caller must provide are the
+//! number of buffer frames and the capacity of persistent storage (in pages).
+
+use crate::error::ConfigError;
+use crate::events::PageEventListener;
+#[cfg(feature = "deadlock-detection")]
+use crate::lock_tracker::DEFAULT_LOCK_WAIT_THRESHOLD;
+use crate::page::{PageId, DIRECT_IO_ALIGNMENT, PAGE_SIZE};
+use crate::prefetch::DEFAULT_PREFETCH_EXPIRY;
+use crate::storage::{
+    ClockReplacer, FifoReplacer, LrukReplacer, PageCodec, Replacer, StorageBackend, CHECKSUM_SIZE,
+    DATABASE_NAME, DEFAULT_FRAME_GROUP_SIZE,
+};
+use crate::tenant::TenantId;
+use crate::wal::WalHook;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The configuration for a [`BufferPoolManager`](crate::BufferPoolManager).
+///
+/// This type follows the builder pattern: create a configuration with
+/// [`BufferPoolManagerConfig::new`], chain any options that should differ from the defaults, and
+/// then pass it to [`BufferPoolManager::initialize_with_config`].
+///
+/// [`BufferPoolManager::initialize_with_config`]: crate::BufferPoolManager::initialize_with_config
+#[derive(Debug, Clone)]
+pub struct BufferPoolManagerConfig {
+    /// The number of buffer frames the buffer pool manager will manage.
+    pub(crate) num_frames: usize,
+
+    /// The number of buffer frames that the buffer pool manager can grow to, if it is larger than
+    /// `num_frames`.
+    pub(crate) max_frames: Option<usize>,
+
+    /// The number of buffer frames in every frame group, if it differs from the default.
+    pub(crate) frame_group_size: Option<usize>,
+
+    /// The number of pages that persistent storage should be able to hold.
+    pub(crate) capacity: usize,
+
+    /// The size of every page (and therefore every buffer frame) in bytes.
+    pub(crate) page_size: usize,
+
+    /// What the buffer pool should do when it observes a poisoned internal latch.
+    pub(crate) poison_policy: PoisonPolicy,
+
+    /// When modified pages are written out to persistent storage.
+    pub(crate) cache_policy: CachePolicy,
+
+    /// The paths to the database files that pages are striped across.
+    pub(crate) paths: Vec<PathBuf>,
+
+    /// The database directory whose layout the buffer pool manager manages, which overrides
+    /// `paths` if set.
+    pub(crate) directory: Option<PathBuf>,
+
+    /// A predicate that determines which pages are exempt from eviction.
+    pub(crate) eviction_exemption: Option<fn(PageId) -> bool>,
+
+    /// The replacement policy of every frame group.
+    pub(crate) replacement_policy: ReplacementPolicy,
+
+    /// How long the background flusher waits after a page is first dirtied before writing it back.
+    pub(crate) write_coalescing_window: Duration,
+
+    /// How long a batch of durable flushes waits for more flushes to join it, or `None` if every
+    /// durable flush writes and syncs on its own.
+    pub(crate) group_commit_window: Option<Duration>,
+
+    /// The fraction of a frame group's frames that must be dirty before the background flusher
+    /// writes any of them back.
+    pub(crate) flusher_dirty_threshold: f64,
+
+    /// Whether to register the buffer frames with every thread's `io_uring` instance.
+    pub(crate) registered_buffers: bool,
+
+    /// Whether pages are read and written through the operating system's page cache instead of
+    /// with `O_DIRECT`.
+    pub(crate) buffered_io: bool,
+
+    /// Whether every page holds a checksum that is verified when the page is read.
+    pub(crate) checksums: bool,
+
+    /// Whether every page is staged in a double-write buffer before it is written out.
+    pub(crate) doublewrite_buffer: bool,
+
+    /// The number of bytes at the start of every page that are reserved for metadata.
+    pub(crate) reserved_header: usize,
+
+    /// The number of bytes at the end of every page (before the checksum, if any) that are
+    /// reserved for metadata.
+    pub(crate) reserved_trailer: usize,
+
+    /// The hook that is awaited before every write of a dirty page.
+    pub(crate) wal_hook: Option<Arc<dyn WalHook>>,
+
+    /// The listeners that are notified of every load, eviction, and flush of a page, in the order
+    /// that they were registered.
+    pub(crate) page_event_listeners: Vec<Arc<dyn PageEventListener>>,
+
+    /// The backend that pages are stored in, or `None` to store them in the database files.
+    pub(crate) storage_backend: Option<Arc<dyn StorageBackend>>,
+
+    /// The codec that transforms pages on their way to and from persistent storage, if any.
+    pub(crate) page_codec: Option<Arc<dyn PageCodec>>,
+
+    /// How long the kernel's submission queue polling thread may idle before it goes to sleep, or
+    /// `None` to submit I/O with system calls instead.
+    pub(crate) sqpoll_idle: Option<Duration>,
+
+    /// The largest number of reads and writes that every thread keeps in flight, or `None` if
+    /// there is no limit.
+    pub(crate) max_in_flight_io: Option<usize>,
+
+    /// The number of dedicated I/O driver threads, or `None` if every thread performs its own I/O.
+    pub(crate) io_threads: Option<usize>,
+
+    /// Whether to bind every frame group's memory to a NUMA node.
+    pub(crate) numa_aware: bool,
+
+    /// How frame groups are chosen when a frame is needed.
+    pub(crate) group_selection: GroupSelection,
+
+    /// How long a task waits for a free frame before giving up, or `None` to wait forever.
+    pub(crate) free_frame_timeout: Option<Duration>,
+
+    /// How long after an access of a page further accesses skip updating its eviction state, or
+    /// `None` to update it on every access.
+    pub(crate) hot_access_threshold: Option<Duration>,
+
+    /// How long a prefetched page that has not been accessed yet is protected from eviction.
+    pub(crate) prefetch_expiry: Duration,
+
+    /// How long a single eviction pass over a frame group may keep evicting pages, or `None` to
+    /// evict every victim of the pass.
+    pub(crate) eviction_time_budget: Option<Duration>,
+
+    /// The largest number of dirty pages that an eviction pass writes back together.
+    pub(crate) eviction_write_batch: usize,
+
+    /// How the page table hashes page IDs.
+    pub(crate) page_hashing: PageHashing,
+
+    /// Whether readers check if a page is loaded before taking its read lock.
+    pub(crate) loaded_hint: bool,
+
+    /// The largest number of frames that every tenant with a quota may keep resident.
+    pub(crate) tenant_quotas: HashMap<TenantId, usize>,
+
+    /// The fraction of the buffer frames that [`BufferPoolManager::preload`] fills at most.
+    ///
+    /// [`BufferPoolManager::preload`]: crate::BufferPoolManager::preload
+    pub(crate) preload_fraction: f64,
+
+    /// Whether the latencies of page operations are recorded in histograms.
+    pub(crate) latency_histograms: bool,
+
+    /// How long a task may wait for a page lock before the wait is reported.
+    #[cfg(feature = "deadlock-detection")]
+    pub(crate) lock_wait_threshold: Duration,
+}
+
+impl BufferPoolManagerConfig {
+    /// Creates a new configuration with the given number of buffer frames and storage capacity.
+    ///
+    /// All other options are set to their defaults.
+    pub fn new(num_frames: usize, capacity: usize) -> Self {
+        Self {
+            num_frames,
+            max_frames: None,
+            frame_group_size: None,
+            capacity,
+            page_size: PAGE_SIZE,
+            poison_policy: PoisonPolicy::default(),
+            cache_policy: CachePolicy::default(),
+            paths: vec![PathBuf::from(DATABASE_NAME)],
+            directory: None,
+            eviction_exemption: None,
+            replacement_policy: ReplacementPolicy::Clock,
+            write_coalescing_window: Duration::ZERO,
+            group_commit_window: None,
+            flusher_dirty_threshold: 0.0,
+            registered_buffers: false,
+            buffered_io: false,
+            checksums: false,
+            doublewrite_buffer: false,
+            reserved_header: 0,
+            reserved_trailer: 0,
+            wal_hook: None,
+            page_event_listeners: Vec::new(),
+            storage_backend: None,
+            page_codec: None,
+            sqpoll_idle: None,
+            max_in_flight_io: None,
+            io_threads: None,
+            numa_aware: false,
+            group_selection: GroupSelection::default(),
+            free_frame_timeout: None,
+            hot_access_threshold: None,
+            prefetch_expiry: DEFAULT_PREFETCH_EXPIRY,
+            eviction_time_budget: None,
+            eviction_write_batch: usize::MAX,
+            page_hashing: PageHashing::default(),
+            loaded_hint: true,
+            tenant_quotas: HashMap::new(),
+            preload_fraction: 1.0,
+            latency_histograms: false,
+            #[cfg(feature = "deadlock-detection")]
+            lock_wait_threshold: DEFAULT_LOCK_WAIT_THRESHOLD,
+        }
+    }
+
+    /// Sets the size of every page (and therefore every buffer frame) in bytes.
+    ///
+    /// Since pages are read and written with `O_DIRECT`, the page size must be a non-zero multiple
+    /// of 512 bytes, which is checked when the buffer pool manager is initialized.
+    ///
+    /// By default, the page size is [`PAGE_SIZE`] (4 KiB).
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Sets the number of buffer frames that the buffer pool manager can grow to with
+    /// [`BufferPoolManager::resize`](crate::BufferPoolManager::resize).
+    ///
+    /// The memory of every frame up to `max_frames` is reserved when the buffer pool manager is
+    /// initialized, but the memory of frames beyond the current size of the pool is only reserved
+    /// address space and is never touched, so it does not count towards the memory usage of the
+    /// process. The pool always holds a whole number of frame groups, so the initial number of
+    /// frames is rounded up to the next multiple of the frame group size.
+    ///
+    /// By default, the pool can never grow beyond its initial number of frames.
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Gets the number of buffer frames that the buffer pool manager allocates memory for, which
+    /// is the most it can grow to.
+    pub(crate) fn total_frames(&self) -> usize {
+        self.max_frames
+            .unwrap_or(self.num_frames)
+            .max(self.num_frames)
+    }
+
+    /// Sets the number of buffer frames in every frame group.
+    ///
+    /// The buffer frames are divided into groups that each have their own free list, eviction
+    /// state lock, and replacer, and a page is always evicted by the group of its frame. Larger
+    /// groups make every cooling pass scan (and possibly write back) more frames, while smaller
+    /// groups mean more groups, so that concurrent tasks contend less on any one free list and
+    /// eviction state lock, at the cost of every replacer seeing fewer pages. If the number of
+    /// frames is not a multiple of the group size, the last group is smaller than the others.
+    ///
+    /// The group size must be non-zero and must not exceed the number of buffer frames that the
+    /// pool can grow to, which is checked when the buffer pool manager is initialized.
+    ///
+    /// By default, every frame group holds 64 frames.
+    pub fn frame_group_size(mut self, frames: usize) -> Self {
+        self.frame_group_size = Some(frames);
+        self
+    }
+
+    /// Gets the number of buffer frames in every frame group.
+    pub(crate) fn group_size(&self) -> usize {
+        self.frame_group_size.unwrap_or(DEFAULT_FRAME_GROUP_SIZE)
+    }
+
+    /// Sets the [`PoisonPolicy`] of the buffer pool.
+    ///
+    /// By default, the buffer pool will panic when it observes a poisoned latch.
+    pub fn poison_policy(mut self, policy: PoisonPolicy) -> Self {
+        self.poison_policy = policy;
+        self
+    }
+
+    /// Sets the [`CachePolicy`] of the buffer pool, which decides when modified pages are written
+    /// out to persistent storage.
+    ///
+    /// With [`CachePolicy::WriteThrough`], every page that is modified through a
+    /// [`WritePageGuard`](crate::page::WritePageGuard) is written out once the guard is dropped, so
+    /// callers that do not manage flushes themselves do not lose updates that were never evicted.
+    /// Note that the writes are not synced, so they can still be lost on a crash until the files
+    /// are synced (see [`BufferPoolManager::sync_data`](crate::BufferPoolManager::sync_data)).
+    ///
+    /// By default, the buffer pool is write-back: modified pages are only written out when they
+    /// are evicted or flushed.
+    pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// Sets the paths of the files that pages are stored in.
+    ///
+    /// Pages are striped across the files in the style of RAID-0, so for the best performance,
+    /// every file should live on a different drive. Every file must already exist.
+    ///
+    /// By default, every page is stored in a single `bpm.db` file in the current directory.
+    pub fn paths<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Stores the database in a directory whose layout is managed by the buffer pool manager,
+    /// instead of in the files set with [`BufferPoolManagerConfig::paths`].
+    ///
+    /// When the buffer pool manager is initialized, it creates the directory if it does not exist
+    /// yet, and then creates (or opens) a `superblock` file that records the page size and capacity
+    /// of the database, along with the data files that pages are stored in. The data files are
+    /// grown whenever the database is opened with a larger capacity than before, and are never
+    /// shrunk. Opening a database with a different page size than it was created with fails.
+    ///
+    /// Embedders should treat the contents of the directory as opaque.
+    pub fn directory<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.directory = Some(dir.into());
+        self
+    }
+
+    /// Registers a predicate that exempts pages from eviction.
+    ///
+    /// Every page for which the predicate returns `true` is never evicted once it has been loaded
+    /// into memory, without requiring the caller to hold a guard on it forever. This is intended
+    /// for small sets of pages that are accessed by almost every operation, such as catalog pages
+    /// or allocation maps.
+    ///
+    /// Since the predicate is evaluated every time the eviction algorithm considers a page, it
+    /// should be cheap. Note that exempt pages permanently take up buffer frames, so exempting too
+    /// many pages will starve the rest of the buffer pool.
+    pub fn eviction_exemption(mut self, predicate: fn(PageId) -> bool) -> Self {
+        self.eviction_exemption = Some(predicate);
+        self
+    }
+
+    /// Sets the policy that decides which pages get evicted.
+    ///
+    /// By default, the buffer pool uses the [clock](ReplacementPolicy::Clock) (second chance)
+    /// replacement policy.
+    pub fn replacement_policy(mut self, policy: ReplacementPolicy) -> Self {
+        self.replacement_policy = policy;
+        self
+    }
+
+    /// Replaces pages with the LRU-K replacement policy instead of the clock replacement policy.
+    ///
+    /// This is a shorthand for [`BufferPoolManagerConfig::replacement_policy`] with
+    /// [`ReplacementPolicy::LruK`]. Note that `k` must not be zero, which is checked when the
+    /// buffer pool manager is initialized.
+    pub fn lru_k_replacement(self, k: usize) -> Self {
+        self.replacement_policy(ReplacementPolicy::LruK(k))
+    }
+
+    /// Sets how long the background flusher waits after a page is first dirtied before it writes
+    /// the page back to persistent storage.
+    ///
+    /// Pages are often updated in bursts, so delaying the write-back of a page that was just
+    /// dirtied allows every update in the burst to be written out with a single physical write. The
+    /// window only applies to the background flusher: explicit flushes, checkpoints, and evictions
+    /// always write pages out immediately, and the flusher ignores the window for frame groups that
+    /// are running out of free frames.
+    ///
+    /// By default, there is no window.
+    pub fn write_coalescing_window(mut self, window: Duration) -> Self {
+        self.write_coalescing_window = window;
+        self
+    }
+
+    /// Enables group commit of durable flushes with the given batch window.
+    ///
+    /// With group commit, [`WritePageGuard::flush_sync`](crate::page::WritePageGuard::flush_sync)
+    /// does not write and sync its page on its own. Instead, the first durable flush opens a batch
+    /// that every other durable flush (from any thread) joins for `window`, and then the whole
+    /// batch is written out with one vectored write per run of consecutive pages, every database
+    /// file that was written to is synced once with `fdatasync`, and every flush of the batch
+    /// completes together. This trades up to `window` of extra latency per flush for far fewer
+    /// syncs when many tasks commit at the same time. A zero window still batches every durable
+    /// flush that is ready to run at the same time.
+    ///
+    /// Plain [`WritePageGuard::flush`](crate::page::WritePageGuard::flush)es and temporary pages
+    /// are not affected.
+    ///
+    /// By default, group commit is disabled.
+    pub fn group_commit_window(mut self, window: Duration) -> Self {
+        self.group_commit_window = Some(window);
+        self
+    }
+
+    /// Sets the fraction of a frame group's frames that must be dirty before the background
+    /// flusher writes any of them back.
+    ///
+    /// Writing back a page that is dirtied again soon after is wasted I/O, so with a threshold the
+    /// flusher leaves a frame group alone until enough of its frames are dirty, and then only
+    /// writes back pages until the group is below the threshold again. Like the write coalescing
+    /// window, the threshold is ignored for frame groups that are running out of free frames. Note
+    /// that `fraction` must be between `0.0` and `1.0`, which is checked when the buffer pool
+    /// manager is initialized.
+    ///
+    /// By default, the threshold is `0.0`, so the flusher writes back every dirty page.
+    pub fn flusher_dirty_threshold(mut self, fraction: f64) -> Self {
+        self.flusher_dirty_threshold = fraction;
+        self
+    }
+
+    /// Sets whether the buffer frames are registered with `io_uring`.
+    ///
+    /// If enabled, every thread registers the buffer frames with its `io_uring` instance the first
+    /// time it reads or writes a page, and from then on reads and writes pages with the
+    /// `ReadFixed` and `WriteFixed` operations. This saves the kernel from pinning and unpinning a
+    /// frame's memory on every operation.
+    ///
+    /// Only the first 1024 frames can be registered, and frames past that limit are read and
+    /// written as usual. If the registration fails (for example, because the frames exceed the
+    /// locked memory limit), the thread silently falls back to regular reads and writes.
+    ///
+    /// Since the frames are registered with a specific `io_uring` instance, a thread must not
+    /// start a second runtime after it has read or written a page.
+    ///
+    /// By default, buffer frames are not registered.
+    pub fn registered_buffers(mut self, enabled: bool) -> Self {
+        self.registered_buffers = enabled;
+        self
+    }
+
+    /// Sets whether pages are read and written through the operating system's page cache instead
+    /// of with `O_DIRECT`.
+    ///
+    /// Some filesystems (such as `tmpfs`) do not support `O_DIRECT` at all. If `O_DIRECT` is not
+    /// enabled explicitly with this option but the database files do not support it, the buffer
+    /// pool manager falls back to buffered I/O on its own (and emits a `tracing` warning with the
+    /// `tracing` feature), which can be detected with
+    /// [`BufferPoolManager::direct_io`](crate::BufferPoolManager::direct_io) or
+    /// [`HealthReport::direct_io`](crate::HealthReport::direct_io).
+    ///
+    /// Reads and writes behave the same either way, and writes are still only durable once the
+    /// database files are synced (for example, with
+    /// [`BufferPoolManager::sync_data`](crate::BufferPoolManager::sync_data)). However, every page
+    /// that the operating system caches takes up memory twice, and a completed write only copies
+    /// the page into the page cache, which the operating system writes back whenever it sees fit.
+    ///
+    /// By default, pages are read and written with `O_DIRECT`.
+    pub fn buffered_io(mut self, enabled: bool) -> Self {
+        self.buffered_io = enabled;
+        self
+    }
+
+    /// Sets whether every page is protected by a checksum.
+    ///
+    /// If enabled, the last 4 bytes of every page are reserved for a CRC32C checksum of the rest of
+    /// the page, which is updated every time the page is written to persistent storage and
+    /// verified every time the page is read back. Reading a page that does not match its checksum
+    /// fails with a [`ChecksumMismatch`](crate::error::ChecksumMismatch) error.
+    ///
+    /// Page guards do not expose the reserved bytes, so a page only holds
+    /// [`BufferPoolManager::usable_page_size`](crate::BufferPoolManager::usable_page_size) bytes of
+    /// data. Pages that have never been written are entirely zeroed, and are always considered to
+    /// be valid.
+    ///
+    /// Note that the checksum is stored in the page itself, so enabling or disabling checksums for
+    /// existing database files will cause every page that was written before to fail verification
+    /// or to expose its old trailer.
+    ///
+    /// By default, pages are not checksummed.
+    pub fn checksums(mut self, enabled: bool) -> Self {
+        self.checksums = enabled;
+        self
+    }
+
+    /// Sets whether every page is staged in a double-write buffer before it is written out, which
+    /// protects pages against torn writes.
+    ///
+    /// A drive only writes a single sector atomically, so a crash in the middle of writing out a
+    /// page can leave the page with part of its old and part of its new contents. With the
+    /// double-write buffer, every page is first written to a buffer file next to the database
+    /// files (with the extension `dwbuf`) and synced there, and only then written to its home
+    /// location and synced again. When the buffer pool manager is initialized, every torn page is
+    /// repaired from its copy in the buffer. Torn pages are detected with their checksums, so this
+    /// requires [checksums](Self::checksums).
+    ///
+    /// This makes every write of a page durable once it completes, at the cost of writing every
+    /// page twice and syncing after both writes. Temporary pages are never staged.
+    ///
+    /// By default, pages are written out directly.
+    pub fn doublewrite_buffer(mut self, enabled: bool) -> Self {
+        self.doublewrite_buffer = enabled;
+        self
+    }
+
+    /// Sets the number of bytes at the start of every page that are reserved for metadata, such as
+    /// the embedder's page header with the page's LSN.
+    ///
+    /// Page guards do not expose the reserved bytes by default, so that the metadata cannot be
+    /// clobbered by accident: offsets into a page guard are relative to the
+    /// [user region](Self::user_region) of the page. The reserved bytes can still be accessed on
+    /// purpose with [`ReadPageGuard::raw`](crate::page::ReadPageGuard::raw) and
+    /// [`WritePageGuard::raw_mut`](crate::page::WritePageGuard::raw_mut).
+    ///
+    /// Like checksums, the reservation is part of the on-disk layout of every page, so it must
+    /// stay the same for existing database files.
+    ///
+    /// By default, no bytes are reserved.
+    pub fn reserved_header(mut self, bytes: usize) -> Self {
+        self.reserved_header = bytes;
+        self
+    }
+
+    /// Sets the number of bytes at the end of every page that are reserved for metadata.
+    ///
+    /// The reserved bytes come right before the checksum, if checksums are enabled. See
+    /// [`BufferPoolManagerConfig::reserved_header`] for how reserved bytes are accessed.
+    ///
+    /// By default, no bytes are reserved.
+    pub fn reserved_trailer(mut self, bytes: usize) -> Self {
+        self.reserved_trailer = bytes;
+        self
+    }
+
+    /// Gets the range of every page that holds user data and is exposed by page guards, as byte
+    /// offsets into the page.
+    ///
+    /// Every page is laid out as the [reserved header](Self::reserved_header), the user data, the
+    /// [reserved trailer](Self::reserved_trailer), and the [checksum](Self::checksums) if checksums
+    /// are enabled. The range is empty if the reserved bytes do not fit in a page, which is
+    /// rejected by [`BufferPoolManagerConfig::validate`].
+    pub fn user_region(&self) -> Range<usize> {
+        let checksum = if self.checksums { CHECKSUM_SIZE } else { 0 };
+
+        let end = self
+            .page_size
+            .saturating_sub(self.reserved_trailer)
+            .saturating_sub(checksum);
+        self.reserved_header.min(end)..end
+    }
+
+    /// Registers a [`WalHook`] that is awaited before every write of a dirty page.
+    ///
+    /// This allows a database engine to enforce the write-ahead logging protocol: every modified
+    /// page is tagged with the LSN of its latest log record via
+    /// [`WritePageGuard::set_lsn`](crate::page::WritePageGuard::set_lsn), and the hook makes the
+    /// log durable up to that LSN before the page is written to persistent storage.
+    ///
+    /// By default, there is no hook.
+    pub fn wal_hook(mut self, hook: Arc<dyn WalHook>) -> Self {
+        self.wal_hook = Some(hook);
+        self
+    }
+
+    /// Registers a [`PageEventListener`] that is notified whenever a page is loaded into a frame,
+    /// evicted from its frame, or written out to persistent storage.
+    ///
+    /// This can be called several times to register several listeners, which are notified in the
+    /// order that they were registered. Listeners are notified asynchronously, outside of every
+    /// lock of the buffer pool, so they may access pages themselves. See [`PageEventListener`] for
+    /// the details.
+    ///
+    /// By default, there are no listeners.
+    pub fn page_event_listener(mut self, listener: Arc<dyn PageEventListener>) -> Self {
+        self.page_event_listeners.push(listener);
+        self
+    }
+
+    /// Registers a [`StorageBackend`] that every page is read from and written to, instead of the
+    /// database files.
+    ///
+    /// Every page read and write goes through the backend, including those of temporary pages,
+    /// and syncing the database files syncs the backend instead. Registered buffers and direct
+    /// I/O do not apply to the backend. The database files are never opened (so they do not need
+    /// to exist), but the page allocation state, the quarantine list, and the double-write buffer
+    /// are still stored next to the configured [paths](Self::paths).
+    ///
+    /// [`MemoryStorage`](crate::MemoryStorage) keeps every page in memory, which is useful for
+    /// tests and for embedding the buffer pool without any files.
+    ///
+    /// By default, pages are stored in the database files.
+    pub fn storage_backend(mut self, backend: Arc<dyn StorageBackend>) -> Self {
+        self.storage_backend = Some(backend);
+        self
+    }
+
+    /// Registers a [`PageCodec`] that encodes every page before it is written out and decodes it
+    /// after it is read in, for example to encrypt pages at rest.
+    ///
+    /// Pages are encoded in place in their buffer frames, so that every I/O path (including
+    /// registered buffers, vectored I/O, and the double-write buffer) writes out the encoded
+    /// page, and the page's data is restored as soon as the write completes. The codec's
+    /// [trailer](PageCodec::trailer_len) must fit in the
+    /// [reserved trailer](Self::reserved_trailer), which is checked when the buffer pool manager
+    /// is initialized. Like checksums, the codec is part of the on-disk format of every page, so
+    /// it must stay the same for existing database files.
+    ///
+    /// With the `aes-gcm` feature, [`AesGcmCodec`](crate::AesGcmCodec) encrypts every page with
+    /// AES-256-GCM.
+    ///
+    /// By default, pages are written out exactly as they are in memory.
+    pub fn page_codec(mut self, codec: Arc<dyn PageCodec>) -> Self {
+        self.page_codec = Some(codec);
+        self
+    }
+
+    /// Enables submission queue polling (`SQPOLL`) for every thread's `io_uring` instance.
+    ///
+    /// With submission queue polling, the kernel spawns a thread per `io_uring` instance that
+    /// picks up submitted I/O on its own, so threads do not need a system call to submit I/O. Once
+    /// the polling thread has had nothing to do for `idle`, it goes to sleep and flags that it
+    /// needs a wakeup, in which case the next submission falls back to a system call to wake it.
+    ///
+    /// This trades a busy kernel thread per buffer pool thread for lower submission latency, so it
+    /// mostly pays off for I/O-heavy workloads. If the kernel refuses to set up a polling thread
+    /// (for example, because of insufficient privileges on older kernels), threads fall back to
+    /// regular `io_uring` instances.
+    ///
+    /// This only applies to threads started with
+    /// [`BufferPoolManager::start_thread`](crate::BufferPoolManager::start_thread) after the buffer
+    /// pool manager is initialized, and to the hidden runtimes of the [`blocking`](crate::blocking)
+    /// API.
+    ///
+    /// By default, submission queue polling is disabled.
+    pub fn sqpoll(mut self, idle: Duration) -> Self {
+        self.sqpoll_idle = Some(idle);
+        self
+    }
+
+    /// Sets the largest number of reads and writes that every thread keeps in flight at the same
+    /// time.
+    ///
+    /// Every thread submits I/O to its own `io_uring` instance, whose submission queue only holds
+    /// so many entries. Once it is full, the runtime's driver retries the submission until the
+    /// kernel makes room, which keeps the whole thread busy. With a limit, a read or write that
+    /// would exceed it instead yields until one of the thread's operations completes, so other
+    /// tasks on the thread keep running in the meantime. A limit of `0` is treated as `1`. The
+    /// number of operations that had to wait is reported by
+    /// [`UringStats::throttled`](crate::UringStats::throttled).
+    ///
+    /// A limit at or below the size of the submission queue (256 entries) makes sure that the
+    /// queue never fills up with reads and writes of the buffer pool.
+    ///
+    /// By default, there is no limit.
+    pub fn max_in_flight_io(mut self, max_ops: usize) -> Self {
+        self.max_in_flight_io = Some(max_ops.max(1));
+        self
+    }
+
+    /// Performs every read, write, and sync of the buffer pool on a pool of `num_threads`
+    /// dedicated I/O driver threads, which are started when the buffer pool manager is initialized
+    /// and stopped by [`BufferPoolManager::shutdown`](crate::BufferPoolManager::shutdown).
+    ///
+    /// The driver threads own the only `io_uring` instances that page I/O is submitted to. Every
+    /// other thread sends its reads and writes to one of them (round-robin) over a lock-free
+    /// channel and awaits the completion, so pages can be accessed from threads that do not run a
+    /// [`tokio_uring`] runtime, such as the worker threads of a multi-threaded Tokio runtime. This
+    /// costs a cross-thread hand-off per operation, but keeps compute threads from ever stalling on
+    /// a full submission queue. A count of `0` is treated as `1`.
+    ///
+    /// Punching holes into the database files, syncing a single page, and the tasks that the buffer
+    /// pool spawns on the calling thread (such as prefetches) still need a [`tokio_uring`] runtime
+    /// on the calling thread.
+    ///
+    /// By default, every thread submits its own I/O.
+    pub fn io_threads(mut self, num_threads: usize) -> Self {
+        self.io_threads = Some(num_threads.max(1));
+        self
+    }
+
+    /// Enables NUMA-aware allocation of the buffer frames.
+    ///
+    /// In this mode, the memory of every frame group is bound to a single NUMA node (spreading the
+    /// frame groups evenly across all online nodes), and threads only take free frames from the
+    /// frame groups that are local to the node they are running on. This avoids slow remote memory
+    /// accesses on multi-socket machines, as long as threads are pinned to CPUs.
+    ///
+    /// If the machine does not expose its NUMA topology, the frames are allocated as usual.
+    ///
+    /// By default, NUMA-aware allocation is disabled.
+    pub fn numa_aware(mut self, enabled: bool) -> Self {
+        self.numa_aware = enabled;
+        self
+    }
+
+    /// Sets how frame groups are chosen when a task needs a frame (and therefore possibly has to
+    /// evict a page).
+    ///
+    /// By default, frame groups are chosen at random, which makes the eviction behavior of the
+    /// buffer pool nondeterministic. [`GroupSelection::PowerOfTwoChoices`] evicts fewer pages while
+    /// the buffer pool still has free frames, at the cost of reading the free frame counts of two
+    /// frame groups per choice. Tests that need to reproduce an exact sequence of evictions
+    /// should use [`GroupSelection::Seeded`] or [`GroupSelection::RoundRobin`], and access pages
+    /// from a single task.
+    pub fn group_selection(mut self, selection: GroupSelection) -> Self {
+        self.group_selection = selection;
+        self
+    }
+
+    /// Sets how long a task waits for a free frame before giving up with a
+    /// [`BufferPoolFull`](crate::error::BufferPoolFull) error.
+    ///
+    /// A task only has to wait if every frame of the frame group it chose holds a page that is in
+    /// use, so that no page can be evicted. The task then parks until a frame is released, and
+    /// periodically retries evicting pages in case one of them was unpinned.
+    ///
+    /// By default, tasks wait forever.
+    pub fn free_frame_timeout(mut self, timeout: Duration) -> Self {
+        self.free_frame_timeout = Some(timeout);
+        self
+    }
+
+    /// Skips updating the eviction state of a page that is accessed again within `threshold` of an
+    /// access that did update it.
+    ///
+    /// Recording an access locks the eviction states of the page's frame group, which becomes a
+    /// point of contention when many tasks access the same page over and over (for example, the
+    /// root page of an index). With a threshold, such a page only takes the lock about once per
+    /// threshold, at the cost of the replacement policy seeing fewer of its accesses. A page is
+    /// always recorded again once it has been chosen to cool down, so it cannot be evicted because
+    /// its accesses were skipped.
+    ///
+    /// Accesses are timed with a coarse clock, so thresholds below a few milliseconds are not
+    /// precise.
+    ///
+    /// The number of skipped updates is reported in [`PoolStats::skipped_access_records`].
+    ///
+    /// By default, the eviction state is updated on every access.
+    ///
+    /// [`PoolStats::skipped_access_records`]: crate::PoolStats::skipped_access_records
+    pub fn hot_access_threshold(mut self, threshold: Duration) -> Self {
+        self.hot_access_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets how long a page that was loaded by
+    /// [`BufferPoolManager::prefetch_range`](crate::BufferPoolManager::prefetch_range) is
+    /// protected from eviction before it is accessed.
+    ///
+    /// Prefetched pages are speculative: until a page is accessed for the first time, the
+    /// replacement policy does not see it, so that prefetches that are never used do not displace
+    /// genuinely hot pages. A speculative page is never evicted before `expiry` has passed, so
+    /// that it survives until the scan that prefetched it gets to it, and it is evicted before any
+    /// other page once `expiry` has passed.
+    ///
+    /// How many prefetched pages were used and wasted is reported in
+    /// [`PoolStats::speculative_hits`] and [`PoolStats::wasted_prefetches`].
+    ///
+    /// By default, prefetched pages are protected for 100 milliseconds.
+    ///
+    /// [`PoolStats::speculative_hits`]: crate::PoolStats::speculative_hits
+    /// [`PoolStats::wasted_prefetches`]: crate::PoolStats::wasted_prefetches
+    pub fn prefetch_expiry(mut self, expiry: Duration) -> Self {
+        self.prefetch_expiry = expiry;
+        self
+    }
+
+    /// Sets how long a single eviction pass over a frame group may keep evicting pages.
+    ///
+    /// Eviction runs on the same single-threaded runtime as the tasks that access pages, as well as
+    /// the task that completes their I/O. A pass that evicts many clean pages never has to wait, so
+    /// it always yields to the runtime every few pages. With a budget, once a pass has run for
+    /// longer than `budget`, it also stops and leaves the rest of its victims to the next pass,
+    /// which bounds how long a task that needs a free frame is held up by a single pass. Every pass
+    /// evicts at least one page, so a budget of zero evicts one page per pass.
+    ///
+    /// Note that a budget makes the pages that are evicted depend on timing, so a workload with a
+    /// [seeded](GroupSelection::Seeded) group selection is no longer reproducible.
+    ///
+    /// By default, a pass evicts every victim that the replacement policy chose.
+    pub fn eviction_time_budget(mut self, budget: Duration) -> Self {
+        self.eviction_time_budget = Some(budget);
+        self
+    }
+
+    /// Sets the largest number of dirty pages that an eviction pass writes back together.
+    ///
+    /// An eviction pass that chooses several dirty victims starts writing all of them back before
+    /// waiting for any of the writes, so that they reach the `io_uring` submission queue together
+    /// and are submitted with a single system call. A batch of `1` writes back one page at a time,
+    /// and a batch of `0` is treated as `1`. The mean size of the batches is reported by
+    /// [`PoolStats::mean_eviction_write_batch`](crate::PoolStats::mean_eviction_write_batch).
+    ///
+    /// By default, an eviction pass writes back up to a whole frame group together.
+    pub fn eviction_write_batch(mut self, max_pages: usize) -> Self {
+        self.eviction_write_batch = max_pages.max(1);
+        self
+    }
+
+    /// Sets how the page table hashes page IDs.
+    ///
+    /// Every call to [`BufferPoolManager::get_page`](crate::BufferPoolManager::get_page) hashes the
+    /// page ID to find the page in the page table. The default SipHash is resistant to
+    /// hash-flooding attacks, but is relatively expensive for integer keys, which shows up on the
+    /// hit path of workloads that get page handles often. Page IDs are rarely chosen by an
+    /// adversary, so a cheaper hash such as [`PageHashing::Fx`] is usually a better fit.
+    ///
+    /// By default, page IDs are hashed with [`PageHashing::SipHash`].
+    pub fn page_hashing(mut self, hashing: PageHashing) -> Self {
+        self.page_hashing = hashing;
+        self
+    }
+
+    /// Sets whether readers check if a page is loaded before taking its read lock.
+    ///
+    /// Every page has a flag that hints whether its data is in memory. [`PageHandle::read`] and
+    /// [`PageHandle::try_read`] check the flag first, and go straight to loading the page if it is
+    /// not set, instead of taking the read lock only to find out that they need the write lock.
+    /// The hint is wrong when the page is evicted after a reader checks it, in which case the
+    /// reader has to drop its read lock and retry with the write lock. How often that happens is
+    /// reported in [`PoolStats::loaded_hint_misses`].
+    ///
+    /// Workloads whose pages are almost always in memory pay for the extra atomic load without
+    /// benefiting from it, and workloads with heavy eviction pressure pay for the retries, so
+    /// either may be faster with the hint disabled. Without the hint, readers always take the read
+    /// lock first.
+    ///
+    /// By default, the hint is enabled.
+    ///
+    /// [`PageHandle::read`]: crate::page::PageHandle::read
+    /// [`PageHandle::try_read`]: crate::page::PageHandle::try_read
+    /// [`PoolStats::loaded_hint_misses`]: crate::PoolStats::loaded_hint_misses
+    pub fn loaded_hint(mut self, enabled: bool) -> Self {
+        self.loaded_hint = enabled;
+        self
+    }
+
+    /// Limits the number of frames that `tenant` keeps resident to `max_frames`.
+    ///
+    /// Pages that are read into memory through a handle from
+    /// [`BufferPoolManager::get_page_for`](crate::BufferPoolManager::get_page_for) are charged to
+    /// the handle's tenant. Once a tenant has `max_frames` resident frames, every page it reads in
+    /// evicts one of its own pages first, so that a tenant that scans through lots of pages cannot
+    /// evict the pages of every other tenant. Pinned pages still count towards the quota, so a
+    /// tenant whose resident pages are all pinned goes over its quota rather than waiting.
+    ///
+    /// The quotas do not need to add up to the number of frames: frames that no tenant has a
+    /// quota for are shared by every page. Calling this again for the same tenant replaces its
+    /// quota.
+    ///
+    /// By default, no tenant has a quota.
+    pub fn tenant_quota(mut self, tenant: TenantId, max_frames: usize) -> Self {
+        self.tenant_quotas.insert(tenant, max_frames);
+        self
+    }
+
+    /// Sets the fraction of the buffer frames that
+    /// [`BufferPoolManager::preload`](crate::BufferPoolManager::preload) fills at most, counting
+    /// the frames that are already occupied.
+    ///
+    /// Leaving some frames free lets the first pages that are accessed after a warm-up be read in
+    /// without evicting anything. Note that `fraction` must be between `0.0` and `1.0`, which is
+    /// checked when the buffer pool manager is initialized.
+    ///
+    /// By default, the fraction is `1.0`, so preloading may fill every frame.
+    pub fn preload_fraction(mut self, fraction: f64) -> Self {
+        self.preload_fraction = fraction;
+        self
+    }
+
+    /// Sets whether the latencies of page reads, write guard acquisitions, and eviction writes are
+    /// recorded in histograms, which are retrieved with
+    /// [`BufferPoolManager::latency_histograms`](crate::BufferPoolManager::latency_histograms).
+    ///
+    /// Recording a latency reads the clock twice and increments a few shared counters, which is
+    /// noticeable on the fast path of reads of pages that are in memory.
+    ///
+    /// By default, latencies are not recorded.
+    pub fn latency_histograms(mut self, enabled: bool) -> Self {
+        self.latency_histograms = enabled;
+        self
+    }
+
+    /// Sets how long a task may wait for the lock of a page before a `tracing` warning reports
+    /// the wait, along with the tasks that hold the lock.
+    ///
+    /// The warning is repeated every `threshold` for as long as the wait goes on. Waits that form
+    /// a deadlock are reported right away, regardless of this threshold.
+    ///
+    /// This option is only available with the `deadlock-detection` feature.
+    ///
+    /// By default, waits are reported after 1 second.
+    #[cfg(feature = "deadlock-detection")]
+    pub fn lock_wait_threshold(mut self, threshold: Duration) -> Self {
+        self.lock_wait_threshold = threshold;
+        self
+    }
+
+    /// Checks that this configuration describes a buffer pool that can be constructed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`] describing the first problem with this configuration.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.num_frames == 0 {
+            return Err(ConfigError::TooFewFrames);
+        }
+
+        if let Some(max_frames) = self.max_frames.filter(|&max| max < self.num_frames) {
+            return Err(ConfigError::MaxFramesTooSmall {
+                num_frames: self.num_frames,
+                max_frames,
+            });
+        }
+
+        if let Some(group_size) = self
+            .frame_group_size
+            .filter(|&size| size == 0 || size > self.total_frames())
+        {
+            return Err(ConfigError::InvalidFrameGroupSize {
+                frame_group_size: group_size,
+                num_frames: self.total_frames(),
+            });
+        }
+
+        if self.capacity <= self.total_frames() {
+            return Err(ConfigError::CapacityTooSmall {
+                num_frames: self.total_frames(),
+                capacity: self.capacity,
+            });
+        }
+
+        if self.page_size == 0 || self.page_size % DIRECT_IO_ALIGNMENT != 0 {
+            return Err(ConfigError::InvalidPageSize {
+                page_size: self.page_size,
+            });
+        }
+
+        if self.user_region().is_empty() {
+            return Err(ConfigError::NoUserData {
+                page_size: self.page_size,
+                reserved: self.page_size - self.user_region().len(),
+            });
+        }
+
+        if self.directory.is_none() && self.paths.is_empty() {
+            return Err(ConfigError::NoDatabaseFiles);
+        }
+
+        if matches!(self.replacement_policy, ReplacementPolicy::LruK(0)) {
+            return Err(ConfigError::InvalidLruK);
+        }
+
+        if !(0.0..=1.0).contains(&self.flusher_dirty_threshold) {
+            return Err(ConfigError::InvalidDirtyThreshold);
+        }
+
+        if !(0.0..=1.0).contains(&self.preload_fraction) {
+            return Err(ConfigError::InvalidPreloadFraction);
+        }
+
+        if self.doublewrite_buffer && !self.checksums {
+            return Err(ConfigError::DoublewriteWithoutChecksums);
+        }
+
+        if let Some(codec) = self
+            .page_codec
+            .as_ref()
+            .filter(|codec| codec.trailer_len() > self.reserved_trailer)
+        {
+            return Err(ConfigError::CodecTrailerTooLarge {
+                trailer_len: codec.trailer_len(),
+                reserved: self.reserved_trailer,
+            });
+        }
+
+        if let Some(tenant) = self
+            .tenant_quotas
+            .iter()
+            .filter(|(_, &max_frames)| max_frames == 0)
+            .map(|(&tenant, _)| tenant)
+            .min()
+        {
+            return Err(ConfigError::EmptyTenantQuota { tenant });
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new instance of the configured replacement policy for a single frame group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configured `K` of the LRU-K replacement policy is zero.
+    pub(crate) fn new_replacer(&self) -> Box<dyn Replacer> {
+        match self.replacement_policy {
+            ReplacementPolicy::Clock => Box::new(ClockReplacer),
+            ReplacementPolicy::Fifo => Box::new(FifoReplacer::new(self.group_size())),
+            ReplacementPolicy::LruK(k) => Box::new(LrukReplacer::new(k, self.group_size())),
+            ReplacementPolicy::Custom(new_replacer) => new_replacer(),
+        }
+    }
+}
+
+/// The policy that decides which pages get evicted from every frame group.
+///
+/// Set with [`BufferPoolManagerConfig::replacement_policy`].
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ReplacementPolicy {
+    /// The clock (second chance) policy, which evicts pages that were not accessed since the
+    /// previous cooling pass of their frame group.
+    #[default]
+    Clock,
+
+    /// The first-in, first-out policy, which evicts the pages that were loaded the longest time
+    /// ago, no matter how often they were accessed.
+    Fifo,
+
+    /// The LRU-K policy, which evicts the pages whose `K`-th most recent access is the oldest, and
+    /// evicts pages that have been accessed fewer than `K` times before any other page.
+    ///
+    /// This prevents large scans from pushing frequently accessed pages out of memory, at the cost
+    /// of tracking the last `K` accesses of every page in memory. A `K` of 2 is usually enough.
+    LruK(usize),
+
+    /// A user-provided policy. The function is called once for every frame group, and must return
+    /// a new [`Replacer`] every time.
+    Custom(fn() -> Box<dyn Replacer>),
+}
+
+/// The strategy for choosing a frame group when a task needs a frame.
+///
+/// Every strategy only chooses among the frame groups that are local to the calling thread's NUMA
+/// node if [NUMA-aware allocation](BufferPoolManagerConfig::numa_aware) is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupSelection {
+    /// Choose a frame group uniformly at random, with a thread-local random number generator.
+    #[default]
+    Random,
+
+    /// Choose a frame group uniformly at random, with a single random number generator that is
+    /// seeded with the given seed.
+    ///
+    /// The random number generator is shared by every thread, so the sequence of choices is only
+    /// reproducible if the order of the choices is, for example if there is a single task.
+    Seeded(u64),
+
+    /// Choose every frame group in turn.
+    RoundRobin,
+
+    /// Choose two distinct frame groups uniformly at random, and take the one with more free
+    /// frames (the "power of two choices").
+    ///
+    /// Random choices regularly pick a frame group that has no free frames left (and therefore has
+    /// to evict a page) while other frame groups still have free frames. Comparing two random
+    /// frame groups avoids most of those evictions and keeps the free frames balanced across the
+    /// frame groups, while still only looking at two frame groups per choice.
+    PowerOfTwoChoices,
+}
+
+/// The hash function that the page table uses for page IDs.
+///
+/// Set with [`BufferPoolManagerConfig::page_hashing`].
+#[derive(Debug, Clone, Copy, Default)]
+pub enum PageHashing {
+    /// SipHash-1-3 with random keys, the default hash function of the standard library.
+    #[default]
+    SipHash,
+
+    /// FxHash, the multiply-rotate hash function of `rustc`, which is much cheaper than SipHash for
+    /// integer keys but is not resistant to hash-flooding attacks.
+    Fx,
+
+    /// A user-provided hash function.
+    ///
+    /// The function should spread page IDs evenly over all 64 bits, since the page table uses both
+    /// the high and low bits of the hash.
+    Custom(fn(PageId) -> u64),
+}
+
+/// The policy for handling poisoned internal latches.
+///
+/// The buffer pool uses a small number of blocking mutexes internally (for example, to protect the
+/// eviction state of a group of frames). If a task panics while holding one of these mutexes, the
+/// mutex becomes poisoned, and this policy determines what every subsequent task that observes the
+/// poisoned mutex will do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoisonPolicy {
+    /// Panic immediately, bringing down the current thread.
+    #[default]
+    Panic,
+
+    /// Propagate a [`Poisoned`](crate::error::Poisoned) error to the caller.
+    ///
+    /// Note that the latch stays poisoned, so every subsequent operation that needs the latch will
+    /// also fail.
+    Error,
+
+    /// Clear the poison and attempt to rebuild the protected state before continuing.
+    ///
+    /// Since the eviction state only serves as a hint to the eviction algorithm, it is always
+    /// possible to rebuild it from the frames themselves.
+    Recover,
+}
+
+/// The policy that decides when pages that were modified in memory are written out to persistent
+/// storage.
+///
+/// Set with [`BufferPoolManagerConfig::cache_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    /// Modified pages stay dirty in memory until they are evicted, written back by the background
+    /// flusher, or flushed explicitly (for example, with
+    /// [`WritePageGuard::flush`](crate::page::WritePageGuard::flush) or
+    /// [`BufferPoolManager::flush_all`](crate::BufferPoolManager::flush_all)).
+    #[default]
+    WriteBack,
+
+    /// Dropping a [`WritePageGuard`](crate::page::WritePageGuard) schedules a write of the page in
+    /// the background, unless the page was already flushed through the guard.
+    ///
+    /// The write waits until the page is unlocked, and writes out every update that was made to
+    /// the page by then, so a burst of guards on the same page may be written out together. If a
+    /// write fails, the page stays dirty and is written out once it is evicted or flushed.
+    WriteThrough,
+}
@@ -0,0 +1,174 @@
+//! This module contains the machinery for running long-lived internal background tasks, which we
+//! refer to as daemons.
+//!
+//! Every daemon (for example, the eviction task) is supervised: it stops once the buffer pool
+//! manager signals a shutdown, and if it ever fails with an error, the error is reported on a
+//! channel that users can subscribe to via [`BufferPoolManager::daemon_errors`] and the daemon is
+//! restarted after an exponential backoff. This ensures that a transient error does not silently
+//! kill a daemon that the rest of the system depends on.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::DaemonError;
+use crate::executor;
+use async_channel::{Receiver, Sender};
+use std::future::Future;
+use std::io::Result;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Instant;
+use tokio::sync::Notify;
+use tokio::task;
+use tokio::time::Duration;
+
+/// The maximum number of daemon errors that can be buffered before new errors are dropped.
+const ERROR_CHANNEL_CAPACITY: usize = 64;
+
+/// The amount of time a daemon waits before restarting after its first failure.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// The maximum amount of time a daemon waits before restarting after a failure.
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// The shared state that all daemons of a buffer pool manager use to coordinate with it.
+#[derive(Debug)]
+pub(crate) struct DaemonRegistry {
+    /// A flag representing if a shutdown has been requested.
+    shutdown: AtomicBool,
+
+    /// Wakes up all daemons waiting on a shutdown once a shutdown has been requested.
+    notify: Notify,
+
+    /// The number of daemons that are currently running.
+    alive: AtomicUsize,
+
+    /// A bounded channel of errors that daemons have encountered.
+    errors: (Sender<DaemonError>, Receiver<DaemonError>),
+}
+
+impl DaemonRegistry {
+    /// Creates a new `DaemonRegistry`.
+    pub(crate) fn new() -> Self {
+        Self {
+            shutdown: AtomicBool::new(false),
+            notify: Notify::new(),
+            alive: AtomicUsize::new(0),
+            errors: async_channel::bounded(ERROR_CHANNEL_CAPACITY),
+        }
+    }
+
+    /// Signals to every daemon that it should stop.
+    pub(crate) fn signal_shutdown(&self) {
+        self.shutdown.store(true, Ordering::Release);
+        self.notify.notify_waiters();
+    }
+
+    /// Allows daemons to be spawned again after a shutdown was requested.
+    ///
+    /// Daemons that were stopped by the shutdown are not restarted.
+    #[cfg(feature = "test-util")]
+    pub(crate) fn clear_shutdown(&self) {
+        self.shutdown.store(false, Ordering::Release);
+    }
+
+    /// Checks if a shutdown has been requested.
+    pub(crate) fn is_shutting_down(&self) -> bool {
+        self.shutdown.load(Ordering::Acquire)
+    }
+
+    /// Waits until a shutdown has been requested.
+    pub(crate) async fn wait_for_shutdown(&self) {
+        // Register for the notification _before_ checking the flag so we can't miss the signal.
+        let notified = self.notify.notified();
+
+        if self.is_shutting_down() {
+            return;
+        }
+
+        notified.await;
+    }
+
+    /// Gets the number of daemons that are currently running, across all threads.
+    pub(crate) fn num_alive(&self) -> usize {
+        self.alive.load(Ordering::Acquire)
+    }
+
+    /// Gets a receiver for the errors that daemons have encountered.
+    pub(crate) fn errors(&self) -> Receiver<DaemonError> {
+        self.errors.1.clone()
+    }
+
+    /// Reports an error that a daemon has encountered.
+    ///
+    /// If the error channel is full, then the error is dropped.
+    pub(crate) fn report(&self, error: DaemonError) {
+        let _ = self.errors.0.try_send(error);
+    }
+}
+
+/// Spawns a supervised daemon on the current thread.
+///
+/// The `body` closure creates the future that the daemon runs. If that future completes
+/// successfully, the daemon exits. If it completes with an error, the error is reported and a new
+/// future is created from `body` after a backoff. The daemon will also exit as soon as the buffer
+/// pool manager signals a shutdown.
+pub(crate) fn spawn_daemon<F, Fut>(name: &'static str, body: F) -> task::JoinHandle<()>
+where
+    F: Fn() -> Fut + 'static,
+    Fut: Future<Output = Result<()>> + 'static,
+{
+    executor::spawn_local(async move {
+        let registry = &BufferPoolManager::get().daemons;
+        let _alive = AliveGuard::new(registry);
+
+        let mut backoff = INITIAL_BACKOFF;
+
+        while !registry.is_shutting_down() {
+            let start = Instant::now();
+
+            let res = tokio::select! {
+                res = body() => res,
+                _ = registry.wait_for_shutdown() => return,
+            };
+
+            let Err(error) = res else {
+                return;
+            };
+
+            registry.report(DaemonError::new(name, error));
+
+            // If the daemon ran for a while before failing, then this is a new failure.
+            if start.elapsed() > MAX_BACKOFF {
+                backoff = INITIAL_BACKOFF;
+            }
+
+            tokio::select! {
+                _ = executor::sleep(backoff) => {},
+                _ = registry.wait_for_shutdown() => return,
+            }
+
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    })
+}
+
+/// A guard that counts a daemon as alive for as long as the guard exists.
+///
+/// Since the guard is dropped even when the daemon's task is aborted or panics, the count of live
+/// daemons stays accurate.
+struct AliveGuard<'a> {
+    /// The registry that the daemon belongs to.
+    registry: &'a DaemonRegistry,
+}
+
+impl<'a> AliveGuard<'a> {
+    /// Marks a daemon as alive.
+    fn new(registry: &'a DaemonRegistry) -> Self {
+        registry.alive.fetch_add(1, Ordering::AcqRel);
+        Self { registry }
+    }
+}
+
+impl Drop for AliveGuard<'_> {
+    fn drop(&mut self) {
+        self.registry.alive.fetch_sub(1, Ordering::AcqRel);
+    }
+}
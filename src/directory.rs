@@ -0,0 +1,193 @@
+//! This module manages the layout of a database directory.
+//!
+//! Instead of pointing the buffer pool manager at individual database files, embedders can point it
+//! at a directory with [`BufferPoolManagerConfig::directory`], and the buffer pool manager takes
+//! care of the files inside of it:
+//!
+//! - `superblock`: a small text file that records the format version, page size, capacity, and
+//!   number of data files of the database, so that a database is never opened with a mismatched
+//!   configuration.
+//! - `data-<n>.db`: the data files that pages are striped across.
+//!
+//! The directory and its files are created if they do not exist yet, and the data files are grown
+//! whenever the database is opened with a larger capacity than before.
+//!
+//! [`BufferPoolManagerConfig::directory`]: crate::BufferPoolManagerConfig::directory
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+
+/// The name of the superblock file.
+const SUPERBLOCK_NAME: &str = "superblock";
+
+/// The version of the directory layout.
+const FORMAT_VERSION: u64 = 1;
+
+/// The number of data files in a database directory.
+const DATA_FILES: usize = 1;
+
+/// The contents of a database directory's superblock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Superblock {
+    /// The version of the directory layout.
+    version: u64,
+
+    /// The size of every page in bytes.
+    page_size: usize,
+
+    /// The number of pages that the data files can hold.
+    capacity: usize,
+
+    /// The number of data files that pages are striped across.
+    data_files: usize,
+}
+
+impl Superblock {
+    /// Reads the superblock at the given path, or returns `None` if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the superblock cannot be read or is malformed.
+    fn read(path: &Path) -> Result<Option<Self>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let field = |name: &str| -> Result<u64> {
+            contents
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(key, _)| key.trim() == name)
+                .and_then(|(_, value)| value.trim().parse().ok())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("The superblock at {} has no valid `{name}`", path.display()),
+                    )
+                })
+        };
+
+        Ok(Some(Self {
+            version: field("version")?,
+            page_size: field("page_size")? as usize,
+            capacity: field("capacity")? as usize,
+            data_files: field("data_files")? as usize,
+        }))
+    }
+
+    /// Atomically replaces the superblock at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the superblock cannot be written.
+    fn write(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+
+        let mut file = File::create(&tmp)?;
+        writeln!(file, "version={}", self.version)?;
+        writeln!(file, "page_size={}", self.page_size)?;
+        writeln!(file, "capacity={}", self.capacity)?;
+        writeln!(file, "data_files={}", self.data_files)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp, path)
+    }
+}
+
+/// Prepares a database directory for a buffer pool manager with the given page size and capacity,
+/// returning the paths of the data files.
+///
+/// This creates the directory, its superblock, and its data files if they do not exist yet, checks
+/// that an existing superblock matches the configuration, and grows the data files (and the
+/// recorded capacity) if `capacity` is larger than before. Data files are never shrunk.
+///
+/// # Errors
+///
+/// Returns an error if an I/O error occurs, or if the directory holds a database with a different
+/// layout version, page size, or number of data files.
+pub(crate) fn prepare(dir: &Path, page_size: usize, capacity: usize) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+
+    let superblock_path = dir.join(SUPERBLOCK_NAME);
+    let configured = Superblock {
+        version: FORMAT_VERSION,
+        page_size,
+        capacity,
+        data_files: DATA_FILES,
+    };
+
+    let existing = Superblock::read(&superblock_path)?;
+    let superblock = match existing {
+        Some(existing) => {
+            let mismatch = |what: &str, found: u64, expected: u64| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "The database in {} has a {what} of {found}, but {expected} was configured",
+                        dir.display()
+                    ),
+                )
+            };
+
+            if existing.version != configured.version {
+                return Err(mismatch(
+                    "layout version",
+                    existing.version,
+                    configured.version,
+                ));
+            }
+            if existing.page_size != configured.page_size {
+                return Err(mismatch(
+                    "page size",
+                    existing.page_size as u64,
+                    configured.page_size as u64,
+                ));
+            }
+            if existing.data_files != configured.data_files {
+                return Err(mismatch(
+                    "number of data files",
+                    existing.data_files as u64,
+                    configured.data_files as u64,
+                ));
+            }
+
+            Superblock {
+                capacity: existing.capacity.max(capacity),
+                ..existing
+            }
+        }
+        None => configured,
+    };
+
+    // Every data file holds an equal share of the pages, rounded up.
+    let pages_per_file = superblock.capacity.div_ceil(superblock.data_files);
+    let file_len = (pages_per_file * superblock.page_size) as u64;
+
+    let paths: Vec<PathBuf> = (0..superblock.data_files)
+        .map(|n| dir.join(format!("data-{n}.db")))
+        .collect();
+
+    for path in &paths {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        if file.metadata()?.len() < file_len {
+            file.set_len(file_len)?;
+        }
+        file.sync_all()?;
+    }
+
+    // Only record the new capacity once the data files are large enough to hold it.
+    if existing != Some(superblock) {
+        superblock.write(&superblock_path)?;
+    }
+
+    Ok(paths)
+}
@@ -0,0 +1,177 @@
+//! This module contains the stats emitter, which periodically writes the [`PoolStats`] of the
+//! [`BufferPoolManager`] to a user-provided writer as structured lines.
+//!
+//! Every line describes a single window of time (see [`StatsWindow`](crate::StatsWindow)), so
+//! benchmark results can be post-processed or fed into a dashboard without scraping logs.
+
+use crate::bpm::BufferPoolManager;
+use crate::daemon;
+use crate::stats::PoolStats;
+use std::cell::{Cell, RefCell};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tokio::task;
+
+/// The columns of every line, in order.
+const COLUMNS: [&str; 20] = [
+    "elapsed_secs",
+    "read_accesses",
+    "write_accesses",
+    "hits",
+    "misses",
+    "page_reads",
+    "page_writes",
+    "evictions",
+    "dirty_write_backs",
+    "clean_reclaims",
+    "free_frame_waits",
+    "loaded_hint_misses",
+    "skipped_access_records",
+    "rebalanced_frames",
+    "speculative_hits",
+    "wasted_prefetches",
+    "occupancy",
+    "mean_read_latency_us",
+    "mean_write_latency_us",
+    "mean_eviction_write_batch",
+];
+
+/// The format of the lines written by [`BufferPoolManager::spawn_stats_emitter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsFormat {
+    /// Comma-separated values, preceded by a single header line with the column names.
+    ///
+    /// Latencies are left empty for windows without any I/O.
+    Csv,
+
+    /// One JSON object per line (also known as JSON Lines), with a field for every column.
+    ///
+    /// Latencies are `null` for windows without any I/O.
+    Json,
+}
+
+impl StatsFormat {
+    /// Gets the header line of the format, if it has one.
+    fn header(self) -> Option<String> {
+        match self {
+            Self::Csv => Some(format!("{}\n", COLUMNS.join(","))),
+            Self::Json => None,
+        }
+    }
+
+    /// Formats the statistics of a single window that ended `elapsed` after the emitter started.
+    fn line(self, elapsed: Duration, stats: &PoolStats) -> String {
+        let micros = |latency: Option<Duration>| latency.map(|l| l.as_secs_f64() * 1e6);
+
+        let values = [
+            Some(elapsed.as_secs_f64()),
+            Some(stats.read_accesses as f64),
+            Some(stats.write_accesses as f64),
+            Some(stats.hits() as f64),
+            Some(stats.misses as f64),
+            Some(stats.page_reads as f64),
+            Some(stats.page_writes as f64),
+            Some(stats.evictions as f64),
+            Some(stats.dirty_write_backs as f64),
+            Some(stats.clean_reclaims as f64),
+            Some(stats.free_frame_waits as f64),
+            Some(stats.loaded_hint_misses as f64),
+            Some(stats.skipped_access_records as f64),
+            Some(stats.rebalanced_frames as f64),
+            Some(stats.speculative_hits as f64),
+            Some(stats.wasted_prefetches as f64),
+            Some(stats.occupancy()),
+            micros(stats.mean_read_latency()),
+            micros(stats.mean_write_latency()),
+            stats.mean_eviction_write_batch(),
+        ];
+
+        let mut line = String::new();
+
+        match self {
+            Self::Csv => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    if let Some(value) = value {
+                        let _ = write!(line, "{value}");
+                    }
+                }
+            }
+            Self::Json => {
+                line.push('{');
+                for (i, (column, value)) in COLUMNS.iter().zip(values).enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    let _ = match value {
+                        Some(value) => write!(line, "\"{column}\":{value}"),
+                        None => write!(line, "\"{column}\":null"),
+                    };
+                }
+                line.push('}');
+            }
+        }
+
+        line.push('\n');
+        line
+    }
+}
+
+impl BufferPoolManager {
+    /// Spawns a daemon on the current thread that writes the [`PoolStats`] of every window of
+    /// length `period` to `writer`, one line per window, in the given [`StatsFormat`].
+    ///
+    /// Every line holds the time since the emitter started, the number of read and write
+    /// accesses, hits and misses, page reads and writes, evictions, dirty write-backs, and free
+    /// frame waits during the window, the fraction of occupied frames at the end of the window, as
+    /// well as the mean read and write latencies in microseconds. The writer is flushed after every
+    /// line.
+    ///
+    /// If writing to `writer` fails, the emitter reports the error on the channel returned by
+    /// [`BufferPoolManager::daemon_errors`] and restarts after a backoff.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn spawn_stats_emitter<W>(
+        writer: W,
+        period: Duration,
+        format: StatsFormat,
+    ) -> task::JoinHandle<()>
+    where
+        W: Write + 'static,
+    {
+        assert!(!period.is_zero(), "The stats window must not be empty");
+
+        let writer = Rc::new(RefCell::new(writer));
+        let wrote_header = Rc::new(Cell::new(false));
+        let start = Instant::now();
+
+        daemon::spawn_daemon("stats-emitter", move || {
+            let writer = writer.clone();
+            let wrote_header = wrote_header.clone();
+
+            async move {
+                let mut window = Self::get().stats_window(period);
+
+                if let (false, Some(header)) = (wrote_header.get(), format.header()) {
+                    writer.borrow_mut().write_all(header.as_bytes())?;
+                    wrote_header.set(true);
+                }
+
+                loop {
+                    let stats = window.next().await;
+                    let line = format.line(start.elapsed(), &stats);
+
+                    let mut writer = writer.borrow_mut();
+                    writer.write_all(line.as_bytes())?;
+                    writer.flush()?;
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,845 @@
+//! Error types that the buffer pool manager can raise.
+//!
+//! The page access and I/O API of this crate (getting page handles, reading and writing pages, and
+//! flushing them), as well as initializing and resizing the buffer pool, allocating pages, and
+//! creating and dropping data files, returns a [`Result`] with a [`BpmError`], whose variants
+//! distinguish I/O failures from pool exhaustion, corruption, and misuse.
+//!
+//! The rest of the public API returns [`std::io::Result`]s. Errors that do not originate from the
+//! operating system are wrapped in a [`std::io::Error`] with a custom payload, which callers can
+//! recover with [`std::io::Error::get_ref`] and [`std::error::Error::downcast_ref`].
+
+use crate::page::PageId;
+use crate::tenant::TenantId;
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// An error raised when the buffer pool observes a poisoned internal latch.
+///
+/// This error is only ever raised when the buffer pool is configured with
+/// [`PoisonPolicy::Error`](crate::PoisonPolicy::Error).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poisoned {
+    /// A description of the latch that was poisoned.
+    latch: &'static str,
+}
+
+impl Poisoned {
+    /// Creates a new `Poisoned` error for the given latch.
+    pub(crate) fn new(latch: &'static str) -> Self {
+        Self { latch }
+    }
+
+    /// Returns a description of the latch that was poisoned.
+    pub fn latch(&self) -> &'static str {
+        self.latch
+    }
+}
+
+impl Display for Poisoned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the `{}` latch was poisoned", self.latch)
+    }
+}
+
+impl std::error::Error for Poisoned {}
+
+impl From<Poisoned> for io::Error {
+    fn from(value: Poisoned) -> Self {
+        io::Error::other(value)
+    }
+}
+
+/// An error that a background daemon of the buffer pool manager has encountered.
+///
+/// Daemons are restarted after they fail, so these errors are reported on a channel rather than
+/// returned to any caller. See [`BufferPoolManager::daemon_errors`].
+///
+/// [`BufferPoolManager::daemon_errors`]: crate::BufferPoolManager::daemon_errors
+#[derive(Debug)]
+pub struct DaemonError {
+    /// The name of the daemon that failed.
+    daemon: &'static str,
+
+    /// The error that the daemon failed with.
+    error: io::Error,
+}
+
+impl DaemonError {
+    /// Creates a new `DaemonError`.
+    pub(crate) fn new(daemon: &'static str, error: io::Error) -> Self {
+        Self { daemon, error }
+    }
+
+    /// Returns the name of the daemon that failed.
+    pub fn daemon(&self) -> &'static str {
+        self.daemon
+    }
+
+    /// Returns the error that the daemon failed with.
+    pub fn error(&self) -> &io::Error {
+        &self.error
+    }
+
+    /// Consumes this `DaemonError` and returns the error that the daemon failed with.
+    pub fn into_error(self) -> io::Error {
+        self.error
+    }
+}
+
+impl Display for DaemonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the `{}` daemon failed: {}", self.daemon, self.error)
+    }
+}
+
+impl std::error::Error for DaemonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An error raised when [`BufferPoolManager::flush_all`] was unable to write out some of the dirty
+/// pages.
+///
+/// Every other dirty page was still written out.
+///
+/// [`BufferPoolManager::flush_all`]: crate::BufferPoolManager::flush_all
+#[derive(Debug)]
+pub struct FlushAllError {
+    /// The pages that could not be written out, along with the error for each of them.
+    failures: Vec<(PageId, io::Error)>,
+}
+
+impl FlushAllError {
+    /// Creates a new `FlushAllError`.
+    pub(crate) fn new(failures: Vec<(PageId, io::Error)>) -> Self {
+        Self { failures }
+    }
+
+    /// Returns the pages that could not be written out, along with the error for each of them.
+    pub fn failures(&self) -> &[(PageId, io::Error)] {
+        &self.failures
+    }
+}
+
+impl Display for FlushAllError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unable to flush {} pages", self.failures.len())?;
+
+        if let Some((pid, error)) = self.failures.first() {
+            write!(f, " (first failure: {pid}: {error})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for FlushAllError {}
+
+impl From<FlushAllError> for io::Error {
+    fn from(value: FlushAllError) -> Self {
+        io::Error::other(value)
+    }
+}
+
+/// An error raised when a page that was read from persistent storage does not match its checksum,
+/// which means that the page was corrupted on persistent storage.
+///
+/// This error is only ever raised when the buffer pool is configured with
+/// [`BufferPoolManagerConfig::checksums`](crate::BufferPoolManagerConfig::checksums).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    /// The page that was corrupted.
+    pid: PageId,
+
+    /// The checksum stored in the page's trailer.
+    stored: u32,
+
+    /// The checksum computed from the page's data.
+    computed: u32,
+}
+
+impl ChecksumMismatch {
+    /// Creates a new `ChecksumMismatch` error.
+    pub(crate) fn new(pid: PageId, stored: u32, computed: u32) -> Self {
+        Self {
+            pid,
+            stored,
+            computed,
+        }
+    }
+
+    /// Returns the ID of the page that was corrupted.
+    pub fn pid(&self) -> PageId {
+        self.pid
+    }
+
+    /// Returns the checksum stored in the page's trailer.
+    pub fn stored(&self) -> u32 {
+        self.stored
+    }
+
+    /// Returns the checksum computed from the page's data.
+    pub fn computed(&self) -> u32 {
+        self.computed
+    }
+}
+
+impl Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "checksum mismatch on {}: stored {:#010x}, computed {:#010x}",
+            self.pid, self.stored, self.computed
+        )
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+impl From<ChecksumMismatch> for io::Error {
+    fn from(value: ChecksumMismatch) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, value)
+    }
+}
+
+/// An error raised when reading a page that is quarantined, because it failed checksum
+/// verification before.
+///
+/// Reads of a quarantined page fail immediately with this error, without touching persistent
+/// storage, until the page is repaired with
+/// [`BufferPoolManager::overwrite_corrupt`](crate::BufferPoolManager::overwrite_corrupt).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorruptPage {
+    /// The page that is quarantined.
+    pid: PageId,
+}
+
+impl CorruptPage {
+    /// Creates a new `CorruptPage` error.
+    pub(crate) fn new(pid: PageId) -> Self {
+        Self { pid }
+    }
+
+    /// Returns the ID of the page that is quarantined.
+    pub fn pid(&self) -> PageId {
+        self.pid
+    }
+}
+
+impl Display for CorruptPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is quarantined as corrupt", self.pid)
+    }
+}
+
+impl std::error::Error for CorruptPage {}
+
+impl From<CorruptPage> for io::Error {
+    fn from(value: CorruptPage) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, value)
+    }
+}
+
+/// An error raised when a task could not get a free frame before the configured timeout, because
+/// every frame of the chosen frame group held a page that was in use.
+///
+/// This error is only ever raised when the buffer pool is configured with
+/// [`BufferPoolManagerConfig::free_frame_timeout`](crate::BufferPoolManagerConfig::free_frame_timeout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferPoolFull {
+    /// The ID of the frame group that the task was waiting on.
+    group_id: usize,
+
+    /// How long the task waited for a free frame.
+    waited: Duration,
+}
+
+impl BufferPoolFull {
+    /// Creates a new `BufferPoolFull` error.
+    pub(crate) fn new(group_id: usize, waited: Duration) -> Self {
+        Self { group_id, waited }
+    }
+
+    /// Returns the ID of the frame group that the task was waiting on.
+    pub fn group_id(&self) -> usize {
+        self.group_id
+    }
+
+    /// Returns how long the task waited for a free frame.
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+}
+
+impl Display for BufferPoolFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "no free frame in frame group {} after waiting for {:?}",
+            self.group_id, self.waited
+        )
+    }
+}
+
+impl std::error::Error for BufferPoolFull {}
+
+impl From<BufferPoolFull> for io::Error {
+    fn from(value: BufferPoolFull) -> Self {
+        io::Error::new(io::ErrorKind::TimedOut, value)
+    }
+}
+
+/// An error raised when a [`BufferPoolManagerConfig`](crate::BufferPoolManagerConfig) describes a
+/// buffer pool that cannot be constructed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The buffer pool was configured with no buffer frames.
+    TooFewFrames,
+
+    /// The storage capacity is not larger than the number of buffer frames.
+    CapacityTooSmall {
+        /// The configured number of buffer frames.
+        num_frames: usize,
+
+        /// The configured storage capacity, in pages.
+        capacity: usize,
+    },
+
+    /// The maximum number of buffer frames is smaller than the initial number of buffer frames.
+    MaxFramesTooSmall {
+        /// The configured number of buffer frames.
+        num_frames: usize,
+
+        /// The configured maximum number of buffer frames.
+        max_frames: usize,
+    },
+
+    /// The frame group size is zero or larger than the number of buffer frames.
+    InvalidFrameGroupSize {
+        /// The configured number of buffer frames in every frame group.
+        frame_group_size: usize,
+
+        /// The number of buffer frames that the pool can grow to.
+        num_frames: usize,
+    },
+
+    /// The page size is not a non-zero multiple of the direct I/O alignment.
+    InvalidPageSize {
+        /// The configured page size, in bytes.
+        page_size: usize,
+    },
+
+    /// The bytes reserved for metadata leave no room for user data in a page.
+    NoUserData {
+        /// The configured page size, in bytes.
+        page_size: usize,
+
+        /// The number of bytes reserved for metadata, which may be capped at the page size.
+        reserved: usize,
+    },
+
+    /// No database files or database directory were configured.
+    NoDatabaseFiles,
+
+    /// The LRU-K replacement policy was configured with a `K` of zero.
+    InvalidLruK,
+
+    /// The dirty threshold of the background flusher is not between `0.0` and `1.0`.
+    InvalidDirtyThreshold,
+
+    /// The fraction of the frames that preloading fills is not between `0.0` and `1.0`.
+    InvalidPreloadFraction,
+
+    /// The double-write buffer was enabled without checksums, which it needs to detect torn pages.
+    DoublewriteWithoutChecksums,
+
+    /// The trailer of the page codec does not fit in the reserved trailer of every page.
+    CodecTrailerTooLarge {
+        /// The number of bytes that the codec needs at the end of every page.
+        trailer_len: usize,
+
+        /// The configured number of bytes reserved at the end of every page.
+        reserved: usize,
+    },
+
+    /// A tenant was given a quota of zero frames.
+    EmptyTenantQuota {
+        /// The tenant whose quota is zero.
+        tenant: TenantId,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewFrames => write!(f, "the buffer pool needs at least one buffer frame"),
+            Self::CapacityTooSmall {
+                num_frames,
+                capacity,
+            } => write!(
+                f,
+                "the capacity of {capacity} pages must be larger than the {num_frames} buffer frames"
+            ),
+            Self::MaxFramesTooSmall {
+                num_frames,
+                max_frames,
+            } => write!(
+                f,
+                "the pool cannot grow to {max_frames} buffer frames from {num_frames} buffer frames"
+            ),
+            Self::InvalidFrameGroupSize {
+                frame_group_size,
+                num_frames,
+            } => write!(
+                f,
+                "the frame group size of {frame_group_size} frames must be between 1 and the \
+                 number of buffer frames, {num_frames}"
+            ),
+            Self::InvalidPageSize { page_size } => write!(
+                f,
+                "the page size of {page_size} bytes is not a non-zero multiple of {} bytes",
+                crate::page::DIRECT_IO_ALIGNMENT
+            ),
+            Self::NoUserData {
+                page_size,
+                reserved,
+            } => write!(
+                f,
+                "reserving {reserved} bytes leaves no room for user data in {page_size} byte pages"
+            ),
+            Self::NoDatabaseFiles => write!(f, "no database files were configured"),
+            Self::InvalidLruK => write!(f, "LRU-K needs K to be at least 1"),
+            Self::InvalidDirtyThreshold => {
+                write!(f, "the flusher's dirty threshold must be between 0.0 and 1.0")
+            }
+            Self::InvalidPreloadFraction => {
+                write!(f, "the preload fraction must be between 0.0 and 1.0")
+            }
+            Self::DoublewriteWithoutChecksums => {
+                write!(f, "the double-write buffer needs checksums to detect torn pages")
+            }
+            Self::CodecTrailerTooLarge {
+                trailer_len,
+                reserved,
+            } => write!(
+                f,
+                "the page codec needs a trailer of {trailer_len} bytes, but only {reserved} bytes \
+                 are reserved"
+            ),
+            Self::EmptyTenantQuota { tenant } => {
+                write!(f, "the quota of {tenant} must be at least one frame")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<ConfigError> for io::Error {
+    fn from(value: ConfigError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, value)
+    }
+}
+
+/// An inconsistency between the persisted state of the page allocator and the database files, see
+/// [`AllocationMismatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AllocationProblem {
+    /// The allocator has handed out pages beyond the configured capacity, for example because the
+    /// database was opened with a smaller capacity than before.
+    BeyondCapacity {
+        /// The ID of the next page that has never been allocated.
+        next_page: u64,
+
+        /// The number of pages that the database files can hold.
+        capacity: u64,
+    },
+
+    /// A database file is too short to hold every allocated page that is striped onto it, for
+    /// example because it was truncated or replaced.
+    FileTooShort {
+        /// The path to the database file.
+        path: PathBuf,
+
+        /// The length of the database file, in bytes.
+        len: u64,
+
+        /// The length that the database file needs to hold every allocated page, in bytes.
+        required: u64,
+    },
+}
+
+impl Display for AllocationProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BeyondCapacity {
+                next_page,
+                capacity,
+            } => write!(
+                f,
+                "{next_page} pages were allocated, but the capacity is {capacity} pages"
+            ),
+            Self::FileTooShort {
+                path,
+                len,
+                required,
+            } => write!(
+                f,
+                "{} is {len} bytes long, but its allocated pages need {required} bytes",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// An error raised when the buffer pool manager is initialized with database files that do not
+/// match the persisted state of the page allocator.
+///
+/// See [`BufferPoolManager::allocate_page`](crate::BufferPoolManager::allocate_page).
+#[derive(Debug)]
+pub struct AllocationMismatch {
+    /// The path to the allocation file.
+    path: PathBuf,
+
+    /// Every inconsistency that was found.
+    problems: Vec<AllocationProblem>,
+}
+
+impl AllocationMismatch {
+    /// Creates a new `AllocationMismatch`.
+    pub(crate) fn new(path: PathBuf, problems: Vec<AllocationProblem>) -> Self {
+        Self { path, problems }
+    }
+
+    /// Returns the path to the allocation file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns every inconsistency that was found.
+    pub fn problems(&self) -> &[AllocationProblem] {
+        &self.problems
+    }
+}
+
+impl Display for AllocationMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the allocation state in {} does not match the database files",
+            self.path.display()
+        )?;
+
+        for problem in &self.problems {
+            write!(f, "; {problem}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for AllocationMismatch {}
+
+impl From<AllocationMismatch> for io::Error {
+    fn from(value: AllocationMismatch) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, value)
+    }
+}
+
+/// An error raised when a typed view of a page's data does not fit the page.
+///
+/// See [`ReadPageGuard::as_slice_of`](crate::page::ReadPageGuard::as_slice_of) and
+/// [`ReadPageGuard::header_at`](crate::page::ReadPageGuard::header_at).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ViewError {
+    /// The view would extend past the end of the page.
+    OutOfBounds {
+        /// The offset of the view into the page, in bytes.
+        offset: usize,
+
+        /// The size of the view, in bytes.
+        size: usize,
+
+        /// The size of the page, in bytes.
+        page_size: usize,
+    },
+
+    /// The view does not start at an address that is aligned for its type.
+    Misaligned {
+        /// The offset of the view into the page, in bytes.
+        offset: usize,
+
+        /// The alignment of the view's type, in bytes.
+        align: usize,
+    },
+
+    /// The page is not a whole number of elements of the view's type.
+    SizeMismatch {
+        /// The size of the page, in bytes.
+        page_size: usize,
+
+        /// The size of the view's element type, in bytes.
+        elem_size: usize,
+    },
+}
+
+impl Display for ViewError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds {
+                offset,
+                size,
+                page_size,
+            } => write!(
+                f,
+                "a view of {size} bytes at offset {offset} does not fit in a page of {page_size} \
+                 bytes"
+            ),
+            Self::Misaligned { offset, align } => write!(
+                f,
+                "a view at offset {offset} is not aligned to {align} bytes"
+            ),
+            Self::SizeMismatch {
+                page_size,
+                elem_size,
+            } => write!(
+                f,
+                "a page of {page_size} bytes is not a whole number of {elem_size}-byte elements"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ViewError {}
+
+impl From<ViewError> for io::Error {
+    fn from(value: ViewError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, value)
+    }
+}
+
+/// The result type of the page access and I/O API of the buffer pool manager.
+pub type Result<T> = std::result::Result<T, BpmError>;
+
+/// An error raised by the page access and I/O API of the buffer pool manager, such as
+/// [`BufferPoolManager::get_page`](crate::BufferPoolManager::get_page),
+/// [`PageHandle::read`](crate::page::PageHandle::read), and
+/// [`WritePageGuard::flush`](crate::page::WritePageGuard::flush).
+///
+/// Failures that callers are likely to handle differently get their own variant, so that callers
+/// can tell an I/O failure apart from pool exhaustion or misuse without inspecting the payload of
+/// a [`std::io::Error`]. Every other failure is an [`BpmError::Io`].
+///
+/// A `BpmError` converts to and from a [`std::io::Error`] without losing any information, so it can
+/// be propagated with `?` from functions that return [`std::io::Result`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BpmError {
+    /// An I/O error, or any other error that does not have its own variant.
+    Io {
+        /// The page that was being read or written, if the error is about a single page.
+        pid: Option<PageId>,
+
+        /// The underlying error.
+        source: io::Error,
+    },
+
+    /// No free frame could be found before the configured timeout.
+    BufferPoolFull(BufferPoolFull),
+
+    /// The page does not exist, which can only happen for temporary pages that were dropped and
+    /// pages that were deallocated.
+    PageNotFound(PageId),
+
+    /// An internal latch was poisoned.
+    Poisoned(Poisoned),
+
+    /// A page that was read from persistent storage does not match its checksum.
+    ChecksumMismatch(ChecksumMismatch),
+
+    /// A page is quarantined because it failed checksum verification before.
+    CorruptPage(CorruptPage),
+
+    /// Page I/O was attempted while the runtime of the calling thread is shutting down.
+    ShuttingDown,
+
+    /// The buffer pool manager was initialized with an invalid configuration.
+    Config(ConfigError),
+
+    /// The persisted state of the page allocator does not match the database files.
+    AllocationMismatch(AllocationMismatch),
+}
+
+impl BpmError {
+    /// Creates a `BpmError` from an error that occurred while reading or writing the page `pid`.
+    pub(crate) fn at(pid: PageId, error: io::Error) -> Self {
+        match Self::from(error) {
+            Self::Io { source, .. } => Self::Io {
+                pid: Some(pid),
+                source,
+            },
+            error => error,
+        }
+    }
+
+    /// Returns the ID of the page that the error is about, if there is one.
+    pub fn pid(&self) -> Option<PageId> {
+        match self {
+            Self::Io { pid, .. } => *pid,
+            Self::PageNotFound(pid) => Some(*pid),
+            Self::ChecksumMismatch(e) => Some(e.pid()),
+            Self::CorruptPage(e) => Some(e.pid()),
+            Self::BufferPoolFull(_)
+            | Self::Poisoned(_)
+            | Self::ShuttingDown
+            | Self::Config(_)
+            | Self::AllocationMismatch(_) => None,
+        }
+    }
+
+    /// Returns the [`io::ErrorKind`] that the error has when it is converted into a
+    /// [`std::io::Error`].
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Self::Io { source, .. } => source.kind(),
+            Self::BufferPoolFull(_) => io::ErrorKind::TimedOut,
+            Self::PageNotFound(_) => io::ErrorKind::NotFound,
+            Self::ChecksumMismatch(_) | Self::CorruptPage(_) | Self::AllocationMismatch(_) => {
+                io::ErrorKind::InvalidData
+            }
+            Self::Config(_) => io::ErrorKind::InvalidInput,
+            Self::Poisoned(_) | Self::ShuttingDown => io::ErrorKind::Other,
+        }
+    }
+}
+
+impl Display for BpmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io {
+                pid: Some(pid),
+                source,
+            } => write!(f, "I/O error on {pid}: {source}"),
+            Self::Io { pid: None, source } => write!(f, "{source}"),
+            Self::BufferPoolFull(e) => write!(f, "{e}"),
+            Self::PageNotFound(pid) => write!(f, "{pid} does not exist"),
+            Self::Poisoned(e) => write!(f, "{e}"),
+            Self::ChecksumMismatch(e) => write!(f, "{e}"),
+            Self::CorruptPage(e) => write!(f, "{e}"),
+            Self::ShuttingDown => write!(f, "the runtime is shutting down"),
+            Self::Config(e) => write!(f, "{e}"),
+            Self::AllocationMismatch(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for BpmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BpmError {
+    /// Recovers the variant of errors whose payload is one of the error types of this module.
+    fn from(error: io::Error) -> Self {
+        let Some(payload) = error.get_ref() else {
+            return Self::Io {
+                pid: None,
+                source: error,
+            };
+        };
+
+        if let Some(&e) = payload.downcast_ref::<BufferPoolFull>() {
+            Self::BufferPoolFull(e)
+        } else if let Some(&e) = payload.downcast_ref::<Poisoned>() {
+            Self::Poisoned(e)
+        } else if let Some(&e) = payload.downcast_ref::<ChecksumMismatch>() {
+            Self::ChecksumMismatch(e)
+        } else if let Some(&e) = payload.downcast_ref::<CorruptPage>() {
+            Self::CorruptPage(e)
+        } else if let Some(&e) = payload.downcast_ref::<ConfigError>() {
+            Self::Config(e)
+        } else if payload.is::<AllocationMismatch>() {
+            let payload = error
+                .into_inner()
+                .expect("We just checked that the error has a payload");
+            Self::AllocationMismatch(
+                *payload
+                    .downcast::<AllocationMismatch>()
+                    .expect("We just checked the type of the payload"),
+            )
+        } else if payload.is::<BpmError>() {
+            let payload = error
+                .into_inner()
+                .expect("We just checked that the error has a payload");
+            *payload
+                .downcast::<BpmError>()
+                .expect("We just checked the type of the payload")
+        } else {
+            Self::Io {
+                pid: None,
+                source: error,
+            }
+        }
+    }
+}
+
+impl From<BpmError> for io::Error {
+    fn from(value: BpmError) -> Self {
+        match value {
+            BpmError::Io { source, .. } => source,
+            BpmError::BufferPoolFull(e) => e.into(),
+            BpmError::Poisoned(e) => e.into(),
+            BpmError::ChecksumMismatch(e) => e.into(),
+            BpmError::CorruptPage(e) => e.into(),
+            BpmError::Config(e) => e.into(),
+            BpmError::AllocationMismatch(e) => e.into(),
+            value @ (BpmError::PageNotFound(_) | BpmError::ShuttingDown) => {
+                io::Error::new(value.kind(), value)
+            }
+        }
+    }
+}
+
+impl From<FlushAllError> for BpmError {
+    fn from(value: FlushAllError) -> Self {
+        Self::Io {
+            pid: None,
+            source: value.into(),
+        }
+    }
+}
+
+impl From<BufferPoolFull> for BpmError {
+    fn from(value: BufferPoolFull) -> Self {
+        Self::BufferPoolFull(value)
+    }
+}
+
+impl From<ConfigError> for BpmError {
+    fn from(value: ConfigError) -> Self {
+        Self::Config(value)
+    }
+}
+
+impl From<AllocationMismatch> for BpmError {
+    fn from(value: AllocationMismatch) -> Self {
+        Self::AllocationMismatch(value)
+    }
+}
+
+impl From<Poisoned> for BpmError {
+    fn from(value: Poisoned) -> Self {
+        Self::Poisoned(value)
+    }
+}
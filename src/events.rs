@@ -0,0 +1,153 @@
+//! This module contains the [`PageEventListener`] trait, which lets an embedder keep auxiliary
+//! structures (like an index of the LSNs of every resident page) in sync with the contents of the
+//! buffer pool.
+//!
+//! The buffer pool manager records an event whenever a page is loaded into a frame, evicted from
+//! its frame, or written out to persistent storage. Events are recorded while the page is locked,
+//! but they are delivered to the registered listeners later, by a task that is spawned on the
+//! thread that recorded them. Since that task does not hold any lock of the buffer pool, a
+//! listener is free to access pages (including the page of the event) without deadlocking.
+//!
+//! Every thread delivers its events in the order that they were recorded, one at a time, and to
+//! every listener in the order that the listeners were registered. Events recorded on different
+//! threads are not ordered with respect to each other.
+
+use crate::bpm::BufferPoolManager;
+use crate::page::PageId;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// The future returned by every method of [`PageEventListener`].
+///
+/// The future does not need to be [`Send`], since it is always awaited on the thread that recorded
+/// the event.
+pub type PageEventFuture<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+/// A listener that is notified when a page is loaded, evicted, or flushed.
+///
+/// Register a listener with
+/// [`BufferPoolManagerConfig::page_event_listener`](crate::BufferPoolManagerConfig::page_event_listener).
+/// Every method does nothing by default, so a listener only needs to implement the events it cares
+/// about.
+///
+/// Listeners are notified after the fact, outside of every lock, so by the time a callback runs,
+/// the page may already be in a different state (for example, a page may have been evicted again
+/// by the time [`PageEventListener::on_load`] is called).
+pub trait PageEventListener: Debug + Send + Sync {
+    /// Called after the page `pid` was read into a frame from persistent storage.
+    fn on_load(&self, pid: PageId) -> PageEventFuture<'_> {
+        let _ = pid;
+        Box::pin(future::ready(()))
+    }
+
+    /// Called after the page `pid` was evicted from its frame, after it was written out first if
+    /// it was dirty.
+    fn on_evict(&self, pid: PageId) -> PageEventFuture<'_> {
+        let _ = pid;
+        Box::pin(future::ready(()))
+    }
+
+    /// Called after a dirty version of the page `pid` was written out to persistent storage,
+    /// whether by an eviction, an explicit flush, or the background flusher. `lsn` is the latest
+    /// LSN that was set on the page, as seen by the [`WalHook`](crate::WalHook).
+    ///
+    /// Temporary pages are never reported, since they are not persistent.
+    fn on_flush(&self, pid: PageId, lsn: u64) -> PageEventFuture<'_> {
+        let _ = (pid, lsn);
+        Box::pin(future::ready(()))
+    }
+}
+
+/// An event in the lifecycle of a page, see [`PageEventListener`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PageEvent {
+    /// See [`PageEventListener::on_load`].
+    Load(PageId),
+
+    /// See [`PageEventListener::on_evict`].
+    Evict(PageId),
+
+    /// See [`PageEventListener::on_flush`].
+    Flush {
+        /// The page that was written out.
+        pid: PageId,
+
+        /// The latest LSN that was set on the page.
+        lsn: u64,
+    },
+}
+
+impl PageEvent {
+    /// Delivers this event to `listener`.
+    async fn deliver(self, listener: &dyn PageEventListener) {
+        match self {
+            Self::Load(pid) => listener.on_load(pid).await,
+            Self::Evict(pid) => listener.on_evict(pid).await,
+            Self::Flush { pid, lsn } => listener.on_flush(pid, lsn).await,
+        }
+    }
+}
+
+std::thread_local! {
+    /// The events that were recorded on this thread and have not been delivered yet.
+    static PENDING_EVENTS: RefCell<VecDeque<PageEvent>> = const { RefCell::new(VecDeque::new()) };
+
+    /// Whether this thread has a task that is delivering its pending events.
+    static DELIVERING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks the current thread as delivering its events for as long as it is alive.
+///
+/// If the delivering task is dropped before it is done (for example, because its runtime shuts
+/// down), the events that it did not deliver are discarded, so that the next runtime on the thread
+/// starts out fresh.
+struct Delivering;
+
+impl Delivering {
+    /// Marks the current thread as delivering its events.
+    fn start() -> Self {
+        DELIVERING.with(|delivering| delivering.set(true));
+        Self
+    }
+}
+
+impl Drop for Delivering {
+    fn drop(&mut self) {
+        let _ = PENDING_EVENTS.try_with(|pending| pending.borrow_mut().clear());
+        let _ = DELIVERING.try_with(|delivering| delivering.set(false));
+    }
+}
+
+/// Delivers the pending events of the current thread to every listener, until there are none left.
+async fn deliver_pending(listeners: Vec<Arc<dyn PageEventListener>>, delivering: Delivering) {
+    while let Some(event) = PENDING_EVENTS.with(|pending| pending.borrow_mut().pop_front()) {
+        for listener in &listeners {
+            event.deliver(listener.as_ref()).await;
+        }
+    }
+
+    drop(delivering);
+}
+
+impl BufferPoolManager {
+    /// Records an event in the lifecycle of a page, which is delivered to every registered
+    /// [`PageEventListener`] by a task on the current thread once the caller yields.
+    ///
+    /// This does nothing if there are no listeners, and can be called while holding any lock.
+    pub(crate) fn record_page_event(&self, event: PageEvent) {
+        let listeners = self.page_event_listeners();
+        if listeners.is_empty() {
+            return;
+        }
+
+        PENDING_EVENTS.with(|pending| pending.borrow_mut().push_back(event));
+
+        if !DELIVERING.with(Cell::get) {
+            Self::spawn_local(deliver_pending(listeners.to_vec(), Delivering::start()));
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! This module contains the thin layer between the buffer pool manager and the timers and task
+//! spawning of the asynchronous runtime that drives it.
+//!
+//! Every timer, yield point, and background task in the buffer pool goes through the functions in
+//! this module instead of naming a runtime directly. By default, timers and yields are implemented
+//! with `tokio`. With the `async-io` feature, they are instead driven by `async-io` and
+//! `futures-lite`, the building blocks of `smol`, so that the buffer pool does not need a `tokio`
+//! timer driver to make progress. This only replaces the timer driver: the buffer pool is not
+//! runtime-agnostic.
+//!
+//! Page I/O is always submitted to a thread-local `io_uring` instance owned by `tokio_uring`, so
+//! tasks are always spawned onto the `tokio_uring` runtime of the current thread, and every
+//! function in this module must be called from a thread that was started with
+//! [`BufferPoolManager::start_thread`](crate::BufferPoolManager::start_thread), regardless of which
+//! timer driver is selected.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Spawns a thread-local task onto the `tokio_uring` runtime of the current thread.
+///
+/// Like [`tokio_uring::spawn`], this panics if the current thread is not running a `tokio_uring`
+/// runtime.
+pub(crate) fn spawn_local<T: Future + 'static>(task: T) -> JoinHandle<T::Output> {
+    tokio_uring::spawn(task)
+}
+
+/// Runs the blocking function `f` on the blocking thread pool of the runtime of the current
+/// thread, so that it does not stall the other tasks on the current thread.
+#[cfg(feature = "lz4")]
+pub(crate) fn spawn_blocking<T, F>(f: F) -> JoinHandle<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+}
+
+/// Returns `true` if the current thread is running a runtime that tasks can be spawned onto.
+///
+/// This is usually `false` if the current thread was not started with [`start_thread`], or if its
+/// runtime is shutting down.
+///
+/// [`start_thread`]: crate::BufferPoolManager::start_thread
+pub(crate) fn in_runtime() -> bool {
+    tokio::runtime::Handle::try_current().is_ok()
+}
+
+#[cfg(not(feature = "async-io"))]
+/// The implementation of this module with `tokio`.
+mod imp {
+    use super::*;
+
+    /// See [`super::yield_now`].
+    pub(crate) async fn yield_now() {
+        tokio::task::yield_now().await
+    }
+
+    /// See [`super::sleep`].
+    pub(crate) async fn sleep(duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+
+    /// See [`super::sleep_until`].
+    pub(crate) async fn sleep_until(deadline: Instant) {
+        tokio::time::sleep_until(deadline.into()).await
+    }
+
+    /// See [`super::timeout`].
+    pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+        tokio::time::timeout(duration, future).await.ok()
+    }
+}
+
+#[cfg(feature = "async-io")]
+/// The implementation of this module with `async-io` and `futures-lite`.
+mod imp {
+    use super::*;
+    use async_io::Timer;
+
+    /// See [`super::yield_now`].
+    pub(crate) async fn yield_now() {
+        futures_lite::future::yield_now().await
+    }
+
+    /// See [`super::sleep`].
+    pub(crate) async fn sleep(duration: Duration) {
+        Timer::after(duration).await;
+    }
+
+    /// See [`super::sleep_until`].
+    pub(crate) async fn sleep_until(deadline: Instant) {
+        Timer::at(deadline).await;
+    }
+
+    /// See [`super::timeout`].
+    pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+        futures_lite::future::or(async { Some(future.await) }, async {
+            Timer::after(duration).await;
+            None
+        })
+        .await
+    }
+}
+
+/// Yields execution back to the runtime, so that other tasks on the current thread can run.
+pub(crate) async fn yield_now() {
+    imp::yield_now().await
+}
+
+/// Waits until `duration` has elapsed.
+pub(crate) async fn sleep(duration: Duration) {
+    imp::sleep(duration).await
+}
+
+/// Waits until `deadline` is reached.
+pub(crate) async fn sleep_until(deadline: Instant) {
+    imp::sleep_until(deadline).await
+}
+
+/// Runs `future` to completion, or returns `None` if it does not complete within `duration`.
+///
+/// If the time limit is reached, `future` is dropped without being polled again.
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    imp::timeout(duration, future).await
+}
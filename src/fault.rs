@@ -0,0 +1,192 @@
+//! This module contains [`FaultInjectingStorage`], a [`StorageBackend`] for testing how the buffer
+//! pool handles failing I/O, which is only available with the `test-util` feature.
+//!
+//! Real drives almost never fail on demand, so the error paths of evictions, flushes, and reads
+//! are hard to exercise against the database files. [`FaultInjectingStorage`] wraps another
+//! backend (a [`MemoryStorage`] by default) and can be told to fail a specific upcoming read or
+//! write, to return short reads, or to delay every operation, while the buffer pool is running.
+
+use crate::executor;
+use crate::page::PageId;
+use crate::storage::{BackendFuture, MemoryStorage, StorageBackend};
+use std::io::{Error, Result};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A [`StorageBackend`] that injects faults into the reads and writes of another backend on
+/// demand.
+///
+/// Register it with
+/// [`BufferPoolManagerConfig::storage_backend`](crate::BufferPoolManagerConfig::storage_backend),
+/// keeping a clone of the [`Arc`](std::sync::Arc) to configure faults later on.
+///
+/// Injected failures return an error of kind [`Other`](std::io::ErrorKind::Other).
+#[derive(Debug)]
+pub struct FaultInjectingStorage {
+    /// The backend that the pages are actually stored in.
+    inner: Arc<dyn StorageBackend>,
+
+    /// The number of reads that have been submitted.
+    reads: AtomicU64,
+
+    /// The number of writes that have been submitted.
+    writes: AtomicU64,
+
+    /// The value of `reads` that the read to fail brings it to, or `0` if no read fails.
+    fail_read: AtomicU64,
+
+    /// The value of `writes` that the write to fail brings it to, or `0` if no write fails.
+    fail_write: AtomicU64,
+
+    /// Whether every read transfers at most half of the bytes it was asked for.
+    short_reads: AtomicBool,
+
+    /// How long every read and write waits before it completes, in microseconds.
+    delay: AtomicU64,
+}
+
+impl FaultInjectingStorage {
+    /// Creates a new `FaultInjectingStorage` that stores pages in a new [`MemoryStorage`], where
+    /// every page is zeroed and no faults are injected.
+    pub fn new() -> Self {
+        Self::wrap(Arc::new(MemoryStorage::new()))
+    }
+
+    /// Creates a new `FaultInjectingStorage` that stores pages in `inner`, where no faults are
+    /// injected.
+    pub fn wrap(inner: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            inner,
+            reads: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+            fail_read: AtomicU64::new(0),
+            fail_write: AtomicU64::new(0),
+            short_reads: AtomicBool::new(false),
+            delay: AtomicU64::new(0),
+        }
+    }
+
+    /// Fails the `n`-th read that is submitted from now on, counting from 1. Only the latest call
+    /// to this function takes effect, and `0` cancels a pending failure.
+    ///
+    /// Every call to [`StorageBackend::read_at`] counts as a read, so a page that is read in
+    /// several parts because of [short reads](FaultInjectingStorage::short_reads) counts several
+    /// times.
+    pub fn fail_nth_read(&self, n: u64) {
+        let target = if n == 0 {
+            0
+        } else {
+            self.reads.load(Ordering::SeqCst) + n
+        };
+        self.fail_read.store(target, Ordering::SeqCst);
+    }
+
+    /// Fails the `n`-th write that is submitted from now on, counting from 1, like
+    /// [`FaultInjectingStorage::fail_nth_read`].
+    pub fn fail_nth_write(&self, n: u64) {
+        let target = if n == 0 {
+            0
+        } else {
+            self.writes.load(Ordering::SeqCst) + n
+        };
+        self.fail_write.store(target, Ordering::SeqCst);
+    }
+
+    /// Sets whether every read only transfers half of the bytes that it was asked for (but at
+    /// least one byte), so that every page read takes several reads to complete.
+    pub fn short_reads(&self, enabled: bool) {
+        self.short_reads.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Sets how long every read and write waits before it completes.
+    pub fn delay(&self, delay: Duration) {
+        let micros = delay.as_micros().min(u64::MAX as u128) as u64;
+        self.delay.store(micros, Ordering::SeqCst);
+    }
+
+    /// Gets the number of reads that have been submitted so far.
+    pub fn reads(&self) -> u64 {
+        self.reads.load(Ordering::SeqCst)
+    }
+
+    /// Gets the number of writes that have been submitted so far.
+    pub fn writes(&self) -> u64 {
+        self.writes.load(Ordering::SeqCst)
+    }
+
+    /// Counts an operation, and checks if it is the one that should fail.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation should fail.
+    fn submit(count: &AtomicU64, fail: &AtomicU64, kind: &str) -> Result<()> {
+        let n = count.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if fail
+            .compare_exchange(n, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            return Err(Error::other(format!("Injected failure of {kind} {n}")));
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the injected delay, if any.
+    async fn wait(&self) {
+        let micros = self.delay.load(Ordering::SeqCst);
+        if micros != 0 {
+            executor::sleep(Duration::from_micros(micros)).await;
+        }
+    }
+}
+
+impl Default for FaultInjectingStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StorageBackend for FaultInjectingStorage {
+    fn read_at<'a>(
+        &'a self,
+        pid: PageId,
+        offset: usize,
+        buf: &'a mut [u8],
+    ) -> BackendFuture<'a, usize> {
+        Box::pin(async move {
+            Self::submit(&self.reads, &self.fail_read, "read")?;
+            self.wait().await;
+
+            let len = if self.short_reads.load(Ordering::SeqCst) {
+                buf.len().div_ceil(2)
+            } else {
+                buf.len()
+            };
+
+            self.inner.read_at(pid, offset, &mut buf[..len]).await
+        })
+    }
+
+    fn write_at<'a>(
+        &'a self,
+        pid: PageId,
+        offset: usize,
+        buf: &'a [u8],
+    ) -> BackendFuture<'a, usize> {
+        Box::pin(async move {
+            Self::submit(&self.writes, &self.fail_write, "write")?;
+            self.wait().await;
+
+            self.inner.write_at(pid, offset, buf).await
+        })
+    }
+
+    fn sync(&self) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.wait().await;
+            self.inner.sync().await
+        })
+    }
+}
@@ -0,0 +1,240 @@
+//! A C API for the buffer pool manager, enabled with the `ffi` feature.
+//!
+//! This module exposes an `extern "C"` facade so that storage engines written in languages other
+//! than Rust can use the buffer pool. The asynchronous API is hidden behind the
+//! [`blocking`](crate::blocking) facade, so every function in this module blocks the calling
+//! thread until the underlying operation completes.
+//!
+//! All Rust objects are passed across the boundary as opaque pointers:
+//! - [`BpmPageHandle`]s are created with [`bpm_get_page`] and destroyed with
+//!   [`bpm_page_handle_free`]
+//! - [`BpmReadGuard`]s are created with [`bpm_read`] and destroyed with [`bpm_read_release`]
+//! - Write guards are created with [`bpm_write`] and destroyed with [`bpm_write_release`]
+//!
+//! Functions that can fail return `0` on success and a negated `errno` value on failure.
+//!
+//! Note that page handles and guards are thread-local, and must only ever be used on the thread
+//! that created them.
+
+use crate::blocking;
+use crate::error::BpmError;
+use crate::page::{PageHandle, PageId, ReadPageGuard, WritePageGuard};
+use crate::{BufferPoolManager, BufferPoolManagerConfig};
+use std::ffi::c_void;
+use std::ops::Deref;
+
+/// Converts an error into a negated `errno` value.
+fn errno(error: &BpmError) -> i32 {
+    let errno = match error {
+        BpmError::Io { source, .. } => source.raw_os_error().unwrap_or(libc::EIO),
+        BpmError::BufferPoolFull(_) => libc::ETIMEDOUT,
+        BpmError::PageNotFound(_) => libc::ENOENT,
+        BpmError::ShuttingDown => libc::ESHUTDOWN,
+        BpmError::Config(_) => libc::EINVAL,
+        _ => libc::EIO,
+    };
+
+    -errno
+}
+
+/// An opaque handle to a logical page of data. See [`PageHandle`].
+pub struct BpmPageHandle(PageHandle);
+
+/// An opaque read guard on a page. See [`ReadPageGuard`].
+///
+/// The guard borrows from the [`BpmPageHandle`] it was created from, so it must be released before
+/// that handle is freed.
+pub struct BpmReadGuard {
+    /// The read guard, which is only held to keep the page read-locked.
+    _guard: ReadPageGuard<'static>,
+}
+
+/// Initializes the global buffer pool manager with the given number of buffer frames and storage
+/// capacity (in pages).
+///
+/// Returns `-EINVAL` if the arguments are invalid (see
+/// [`BufferPoolManagerConfig::validate`]), `-EEXIST` if the buffer pool manager has already been
+/// initialized, or another negated `errno` value if the database files cannot be opened.
+#[no_mangle]
+pub extern "C" fn bpm_initialize(num_frames: usize, capacity: usize) -> i32 {
+    if BufferPoolManager::is_initialized() {
+        return -libc::EEXIST;
+    }
+
+    let config = BufferPoolManagerConfig::new(num_frames, capacity);
+    match BufferPoolManager::try_initialize_with_config(config) {
+        Ok(()) => 0,
+        Err(e) => errno(&e),
+    }
+}
+
+/// Gets a handle to the page with the given page ID, or a null pointer on failure.
+///
+/// The returned handle must be freed with [`bpm_page_handle_free`].
+#[no_mangle]
+pub extern "C" fn bpm_get_page(pid: u64) -> *mut BpmPageHandle {
+    if !BufferPoolManager::is_initialized() {
+        return std::ptr::null_mut();
+    }
+
+    match blocking::get_page_blocking(&PageId::new(pid)) {
+        Ok(ph) => Box::into_raw(Box::new(BpmPageHandle(ph))),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a page handle created by [`bpm_get_page`].
+///
+/// # Safety
+///
+/// `handle` must have been returned by [`bpm_get_page`] on this thread and not yet freed, and every
+/// guard created from it must have already been released.
+#[no_mangle]
+pub unsafe extern "C" fn bpm_page_handle_free(handle: *mut BpmPageHandle) {
+    if !handle.is_null() {
+        // SAFETY: The caller guarantees that this pointer came from `Box::into_raw`.
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Acquires a read guard on a page, blocking until the page's data is in memory.
+///
+/// On success, writes the page's data pointer and length into `data` and `len` and stores an
+/// opaque guard in `guard`, which must be released with [`bpm_read_release`].
+///
+/// # Safety
+///
+/// `handle` must be a valid page handle created on this thread, and `data`, `len`, and `guard` must
+/// be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bpm_read(
+    handle: *const BpmPageHandle,
+    data: *mut *const u8,
+    len: *mut usize,
+    guard: *mut *mut BpmReadGuard,
+) -> i32 {
+    // SAFETY: The caller guarantees that the handle is valid, and guards must be released before
+    // the handle is freed, so extending the lifetime of the borrow is sound.
+    let ph: &'static PageHandle = unsafe { &(*handle).0 };
+
+    match blocking::read_blocking(ph) {
+        Ok(read_guard) => {
+            // SAFETY: The caller guarantees that the out-pointers are valid for writes.
+            unsafe {
+                *data = read_guard.deref().as_ptr();
+                *len = read_guard.len();
+                *guard = Box::into_raw(Box::new(BpmReadGuard { _guard: read_guard }));
+            }
+            0
+        }
+        Err(e) => errno(&e),
+    }
+}
+
+/// Releases a read guard created by [`bpm_read`].
+///
+/// # Safety
+///
+/// `guard` must have been returned by [`bpm_read`] on this thread and not yet released.
+#[no_mangle]
+pub unsafe extern "C" fn bpm_read_release(guard: *mut BpmReadGuard) {
+    if !guard.is_null() {
+        // SAFETY: The caller guarantees that this pointer came from `Box::into_raw`.
+        drop(unsafe { Box::from_raw(guard) });
+    }
+}
+
+/// Acquires a write guard on a page, blocking until the page's data is in memory.
+///
+/// On success, writes the page's data pointer and length into `data` and `len` and stores an
+/// opaque guard in `guard`, which must be released with [`bpm_write_release`].
+///
+/// # Safety
+///
+/// `handle` must be a valid page handle created on this thread, and `data`, `len`, and `guard` must
+/// be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bpm_write(
+    handle: *const BpmPageHandle,
+    data: *mut *mut u8,
+    len: *mut usize,
+    guard: *mut *mut c_void,
+) -> i32 {
+    // SAFETY: The caller guarantees that the handle is valid.
+    let ph: &PageHandle = unsafe { &(*handle).0 };
+
+    match blocking::write_blocking(ph) {
+        Ok(write_guard) => {
+            let (ptr, length, raw) = write_guard.into_raw_parts();
+
+            // SAFETY: The caller guarantees that the out-pointers are valid for writes.
+            unsafe {
+                *data = ptr;
+                *len = length;
+                *guard = raw;
+            }
+            0
+        }
+        Err(e) => errno(&e),
+    }
+}
+
+/// Flushes the page protected by a write guard out to persistent storage.
+///
+/// # Safety
+///
+/// `data`, `len`, and `guard` must have been returned together by [`bpm_write`] on this thread, and
+/// `guard` must not have been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn bpm_write_flush(data: *mut u8, len: usize, guard: *mut c_void) -> i32 {
+    debug_assert!(!data.is_null() && len != 0);
+
+    // SAFETY: The caller guarantees that `guard` is the opaque pointer of a live write guard, which
+    // `WritePageGuard::into_raw_parts` creates by boxing the guard itself.
+    let write_guard = unsafe { &mut *(guard as *mut WritePageGuard<'static>) };
+
+    match blocking::flush_blocking(write_guard) {
+        Ok(()) => 0,
+        Err(e) => errno(&e),
+    }
+}
+
+/// Releases a write guard created by [`bpm_write`].
+///
+/// # Safety
+///
+/// `data`, `len`, and `guard` must have been returned together by [`bpm_write`] on this thread, and
+/// `guard` must not have been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn bpm_write_release(data: *mut u8, len: usize, guard: *mut c_void) {
+    if !guard.is_null() {
+        // SAFETY: The caller guarantees that these are the raw parts of a live write guard.
+        drop(unsafe { WritePageGuard::from_raw_parts(data, len, guard) });
+    }
+}
+
+/// Shuts down the buffer pool manager, writing every dirty page out to persistent storage and
+/// tearing down the buffer pool, after which [`bpm_initialize`] can be called again. See
+/// [`BufferPoolManager::shutdown`].
+///
+/// Every page handle must be freed and every guard must be released before calling this function.
+///
+/// Returns `-EBUSY` if a write guard was never released, or another negated `errno` value if a
+/// page is still in use or a dirty page fails to be written out. In either case, nothing is torn
+/// down. Does nothing if the buffer pool manager is not initialized.
+#[no_mangle]
+pub extern "C" fn bpm_shutdown() -> i32 {
+    if !BufferPoolManager::is_initialized() {
+        return 0;
+    }
+
+    let bpm = BufferPoolManager::get();
+    if !bpm.outstanding_raw_guards().is_empty() {
+        return -libc::EBUSY;
+    }
+
+    match blocking::block_on(bpm.shutdown()) {
+        Ok(()) => 0,
+        Err(e) => errno(&e),
+    }
+}
@@ -0,0 +1,434 @@
+//! This module contains the registry of data files, with [`BufferPoolManager::create_file`] and
+//! [`BufferPoolManager::drop_file`].
+//!
+//! Embedders that store several relations (for example, the tables and indexes of a database) in
+//! a single buffer pool would otherwise have to carve up the flat space of page IDs themselves.
+//! Instead, every relation can be given its own data file, which is identified by a [`FileId`]
+//! and numbers its pages from `0` (see [`PageId::in_file`]). Every data file has its own page
+//! allocator (see [`BufferPoolManager::allocate_page_in`]), and can be dropped as a whole.
+//!
+//! The database files that the buffer pool manager is configured with make up the data file
+//! [`FileId::DEFAULT`]. Every other data file lives next to the first database file, with the
+//! extension `<id>.db` (so the data file `1` of `bpm.db` is `bpm.1.db`), and keeps its allocation
+//! state next to it, with the extension `<id>.alloc`. Unlike the default data file, these files
+//! are not striped across drives. The capacity of a data file is fixed when it is created, and is
+//! recovered from the file's length after a restart.
+
+use crate::allocator::PageAllocator;
+use crate::bpm::BufferPoolManager;
+use crate::error::Result;
+use crate::page::{FileId, PageId};
+use crate::storage::StorageManager;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// A data file other than [`FileId::DEFAULT`].
+#[derive(Debug)]
+struct DataFile {
+    /// The path to the data file.
+    path: PathBuf,
+
+    /// The allocator of the pages of the data file.
+    allocator: Arc<PageAllocator>,
+}
+
+/// The registry of every data file other than [`FileId::DEFAULT`].
+#[derive(Debug)]
+pub(crate) struct DataFiles {
+    /// The path to the first database file, which every data file lives next to.
+    first_path: PathBuf,
+
+    /// The size of every page on persistent storage.
+    page_size: usize,
+
+    /// Every data file that exists.
+    files: RwLock<BTreeMap<FileId, DataFile>>,
+
+    /// The ID of the next data file to create.
+    ///
+    /// IDs only ever count up, so that a file handle to a dropped data file that is still cached
+    /// by some thread can never be mistaken for a handle to a newer data file.
+    next_id: AtomicU64,
+
+    /// The number of times that a data file was created or dropped, which lets threads tell when
+    /// their cached file handles are out of date.
+    version: AtomicU64,
+}
+
+impl DataFiles {
+    /// Loads the registry of every data file that lives next to the first of the database files at
+    /// the given paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory of the database files cannot be read, or if the
+    /// allocation state of a data file cannot be loaded (see [`PageAllocator::load`]).
+    pub(crate) fn load(paths: &[PathBuf], page_size: usize) -> io::Result<Self> {
+        let first_path = paths[0].clone();
+        let dir = match first_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        let mut files = BTreeMap::new();
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries.collect::<io::Result<Vec<_>>>()?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let Some(file) = Self::parse_file_id(&first_path, &entry.file_name()) else {
+                continue;
+            };
+
+            // A database file may happen to be named like a data file.
+            let path = data_file_path(&first_path, file);
+            if paths.contains(&path) {
+                continue;
+            }
+
+            let capacity = (fs::metadata(&path)?.len() / page_size as u64) as usize;
+            let allocator = PageAllocator::load(file, slice::from_ref(&path), capacity, page_size)?;
+
+            files.insert(
+                file,
+                DataFile {
+                    path,
+                    allocator: Arc::new(allocator),
+                },
+            );
+        }
+
+        let next_id = files
+            .keys()
+            .last()
+            .map_or(1, |file| file.as_u16() as u64 + 1);
+
+        Ok(Self {
+            first_path,
+            page_size,
+            files: RwLock::new(files),
+            next_id: AtomicU64::new(next_id),
+            version: AtomicU64::new(0),
+        })
+    }
+
+    /// Gets the ID of the data file with the given file name, if it is one.
+    fn parse_file_id(first_path: &Path, name: &std::ffi::OsStr) -> Option<FileId> {
+        let stem = first_path.file_stem()?.to_str()?;
+        let id = name
+            .to_str()?
+            .strip_prefix(stem)?
+            .strip_prefix('.')?
+            .strip_suffix(".db")?;
+
+        // Only accept the canonical spelling of every ID, so that every data file has one name.
+        let file = FileId::new(id.parse().ok()?).filter(|&file| file != FileId::DEFAULT)?;
+        let canonical = data_file_path(first_path, file);
+        (canonical.file_name()? == name).then_some(file)
+    }
+
+    /// Creates an empty data file with room for `capacity` pages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if every file ID has been used, or if the data file or its allocation
+    /// state cannot be created.
+    fn create(&self, capacity: usize) -> io::Result<FileId> {
+        let mut files = self.files.write().unwrap_or_else(|e| e.into_inner());
+
+        let id = self.next_id.load(Ordering::Relaxed);
+        let file = u16::try_from(id)
+            .ok()
+            .and_then(FileId::new)
+            .ok_or_else(|| Error::other("Every data file ID has been used"))?;
+
+        // An allocation file may have been left behind when the file was dropped before.
+        let path = data_file_path(&self.first_path, file);
+        match fs::remove_file(path.with_extension("alloc")) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+
+        let data_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        data_file.set_len((capacity * self.page_size) as u64)?;
+        data_file.sync_all()?;
+
+        let allocator =
+            PageAllocator::load(file, slice::from_ref(&path), capacity, self.page_size)?;
+
+        files.insert(
+            file,
+            DataFile {
+                path,
+                allocator: Arc::new(allocator),
+            },
+        );
+        self.next_id.store(id + 1, Ordering::Relaxed);
+        self.version.fetch_add(1, Ordering::Release);
+
+        Ok(file)
+    }
+
+    /// Removes a data file from the registry, and deletes it and its allocation state.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NotFound`](ErrorKind::NotFound) error if the data file does not exist, or an
+    /// error if it cannot be deleted, in which case it stays registered.
+    fn remove(&self, file: FileId) -> io::Result<()> {
+        let mut files = self.files.write().unwrap_or_else(|e| e.into_inner());
+        let Some(data_file) = files.get(&file) else {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{file} does not exist"),
+            ));
+        };
+
+        // Without the data file, a leftover allocation file is ignored (and later replaced).
+        fs::remove_file(&data_file.path)?;
+        let _ = fs::remove_file(data_file.path.with_extension("alloc"));
+
+        files.remove(&file);
+        self.version.fetch_add(1, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Checks if the data file with the given ID exists. [`FileId::DEFAULT`] always exists.
+    pub(crate) fn contains(&self, file: FileId) -> bool {
+        file == FileId::DEFAULT
+            || self
+                .files
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .contains_key(&file)
+    }
+
+    /// Gets the path to the data file with the given ID, if it exists and is not
+    /// [`FileId::DEFAULT`].
+    pub(crate) fn path(&self, file: FileId) -> Option<PathBuf> {
+        self.files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&file)
+            .map(|data_file| data_file.path.clone())
+    }
+
+    /// Gets the IDs of every data file other than [`FileId::DEFAULT`], in ascending order.
+    pub(crate) fn ids(&self) -> Vec<FileId> {
+        self.files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    /// Gets the page allocator of the data file with the given ID, if it exists and is not
+    /// [`FileId::DEFAULT`].
+    pub(crate) fn allocator(&self, file: FileId) -> Option<Arc<PageAllocator>> {
+        self.files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&file)
+            .map(|data_file| data_file.allocator.clone())
+    }
+
+    /// Gets the number of times that a data file was created or dropped so far.
+    pub(crate) fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+}
+
+/// Gets the path to the data file `file`, which lives next to the first database file at
+/// `first_path`.
+fn data_file_path(first_path: &Path, file: FileId) -> PathBuf {
+    first_path.with_extension(format!("{}.db", file.as_u16()))
+}
+
+/// The files that hold the home locations of pages, opened without the storage manager, which
+/// does not exist yet while torn and staged pages are being recovered.
+#[derive(Debug)]
+pub(crate) struct HomeFiles<'a> {
+    /// The paths to the database files of [`FileId::DEFAULT`].
+    paths: &'a [PathBuf],
+
+    /// The size of every page on persistent storage.
+    page_size: usize,
+
+    /// The database files of [`FileId::DEFAULT`].
+    drives: Vec<File>,
+
+    /// Every other data file that was opened so far, or `None` if it does not exist (anymore).
+    data_files: HashMap<FileId, Option<File>>,
+}
+
+impl<'a> HomeFiles<'a> {
+    /// Opens the database files at the given paths for reading and writing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the database files cannot be opened.
+    pub(crate) fn open(paths: &'a [PathBuf], page_size: usize) -> io::Result<Self> {
+        let drives = paths
+            .iter()
+            .map(|path| OpenOptions::new().read(true).write(true).open(path))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            paths,
+            page_size,
+            drives,
+            data_files: HashMap::new(),
+        })
+    }
+
+    /// Gets the file that holds the home location of the given page, along with the offset of
+    /// the page in that file.
+    ///
+    /// This mirrors [`PageId::drive`] and [`PageId::offset`]. Returns `None` if the page belongs
+    /// to a data file that was dropped, since the page is not needed anymore.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page's data file exists but cannot be opened.
+    pub(crate) fn locate(&mut self, pid: PageId) -> io::Result<Option<(&File, u64)>> {
+        let page_size = self.page_size as u64;
+
+        let file = match pid.file_id() {
+            FileId::DEFAULT => {
+                let num_drives = self.drives.len() as u64;
+                let drive = (pid.page_number() % num_drives) as usize;
+                let offset = (pid.page_number() / num_drives) * page_size;
+                return Ok(Some((&self.drives[drive], offset)));
+            }
+            file => file,
+        };
+
+        if !self.data_files.contains_key(&file) {
+            let path = data_file_path(&self.paths[0], file);
+            let data_file = match OpenOptions::new().read(true).write(true).open(path) {
+                Ok(data_file) => Some(data_file),
+                Err(e) if e.kind() == ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+            self.data_files.insert(file, data_file);
+        }
+
+        Ok(self.data_files[&file]
+            .as_ref()
+            .map(|data_file| (data_file, pid.page_number() * page_size)))
+    }
+
+    /// Flushes every file (and its metadata) to persistent storage with `fsync`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files cannot be synced.
+    pub(crate) fn sync_all(&self) -> io::Result<()> {
+        for file in self.drives.iter().chain(self.data_files.values().flatten()) {
+            file.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl BufferPoolManager {
+    /// Creates a new, empty data file with room for `capacity` pages, and returns its ID.
+    ///
+    /// The pages of the data file are identified by [`PageId::in_file`] with the returned ID, and
+    /// can be allocated with [`BufferPoolManager::allocate_page_in`]. The data file is durable
+    /// before this returns, and is registered again when the buffer pool manager is initialized
+    /// after a restart, until it is dropped with [`BufferPoolManager::drop_file`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if every file ID has been used, or if the data file cannot be created.
+    pub fn create_file(&self, capacity: usize) -> Result<FileId> {
+        Ok(StorageManager::get().data_files().create(capacity)?)
+    }
+
+    /// Drops a data file created with [`BufferPoolManager::create_file`], discarding every one of
+    /// its pages and deleting it from persistent storage.
+    ///
+    /// Every page of the data file is dropped from memory without being written out, and every
+    /// handle to one of them is invalidated: accessing a page of the data file afterwards returns a
+    /// [`BpmError::PageNotFound`](crate::error::BpmError::PageNotFound) error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if `file` is
+    /// [`FileId::DEFAULT`], a [`NotFound`](ErrorKind::NotFound) error if the data file does not
+    /// exist, an error if any of its pages is still locked by a page guard or still shared by
+    /// copy-on-write clones, or an error if the data file cannot be deleted. Nothing is dropped if
+    /// this returns an error.
+    pub async fn drop_file(&self, file: FileId) -> Result<()> {
+        if file == FileId::DEFAULT {
+            return Err(
+                Error::new(ErrorKind::InvalidInput, "Cannot drop the default data file").into(),
+            );
+        }
+
+        let mut pages = Vec::new();
+        self.pages.scan(|pid, page| {
+            if pid.file_id() == file {
+                pages.push(page.clone());
+            }
+        });
+
+        // Lock every page up front, so that nothing is dropped while any page is still in use.
+        let mut guards = Vec::with_capacity(pages.len());
+        for page in &pages {
+            let Ok(guard) = page.frame.try_write() else {
+                return Err(Error::other(format!("{} is still in use", page.pid)).into());
+            };
+            page.check_no_clones()?;
+            #[cfg(feature = "experimental")]
+            if !page.try_invalidate_replicas() {
+                return Err(Error::other(format!("{} is still in use", page.pid)).into());
+            }
+            guards.push(guard);
+        }
+
+        let sm = StorageManager::get();
+        sm.data_files().remove(file)?;
+
+        for (page, mut guard) in pages.iter().zip(guards) {
+            if let Some(mut frame) = guard.take() {
+                page.set_evicted();
+                frame.clear_dirty();
+                frame.evict_page_owner();
+                frame.group().release_frame(frame).await;
+            }
+            page.set_removed();
+
+            drop(guard);
+            self.pages.remove(&page.pid);
+        }
+
+        // The page IDs of the data file are never used again, but a restart may reuse its ID.
+        for pid in sm.quarantine().pids() {
+            if pid.file_id() == file {
+                sm.quarantine().remove(pid)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the IDs of every data file other than [`FileId::DEFAULT`], in ascending order.
+    pub fn data_files(&self) -> Vec<FileId> {
+        StorageManager::get().data_files().ids()
+    }
+}
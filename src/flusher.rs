@@ -0,0 +1,273 @@
+//! This module contains the background flusher, which writes dirty pages back to persistent storage
+//! ahead of eviction.
+//!
+//! If a dirty page is chosen for eviction, the eviction task has to wait for the page to be written
+//! out before it can reuse the page's frame. The flusher moves that work off of the eviction path
+//! by periodically writing back dirty pages while they are still in memory.
+//!
+//! Writing back a page that is about to be dirtied again is wasted I/O, so the flusher prefers
+//! pages that have not been accessed recently (cold and dirty pages) over recently accessed (hot)
+//! pages. To validate this heuristic, the flusher records how often pages are re-dirtied after
+//! being written back, separately for cold and hot pages.
+
+use crate::bpm::BufferPoolManager;
+use crate::daemon;
+use crate::executor;
+use crate::page::Page;
+use crate::storage::{EvictionState, FrameGroup, StorageManager, StorageManagerHandle};
+use std::io::Result;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::task;
+use tokio::time::Duration;
+
+/// Statistics about the pages that the background flusher has written back.
+///
+/// A page is re-dirtied if it is modified again while it is still in memory after the flusher
+/// wrote it back, which means that the write-back was wasted. The flusher prefers cold pages, so
+/// the re-dirty rate of cold pages is expected to be much lower than that of hot pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WriteBackStats {
+    /// The number of cold pages that the flusher wrote back.
+    pub cold_write_backs: u64,
+
+    /// The number of cold pages that were re-dirtied after the flusher wrote them back.
+    pub cold_redirties: u64,
+
+    /// The number of hot pages that the flusher wrote back.
+    pub hot_write_backs: u64,
+
+    /// The number of hot pages that were re-dirtied after the flusher wrote them back.
+    pub hot_redirties: u64,
+
+    /// The number of updates to pages that were already dirty.
+    ///
+    /// Every such update is written out together with the previous updates to the page, so this is
+    /// the number of physical writes that were saved by coalescing updates.
+    pub coalesced_writes: u64,
+
+    /// The number of times the flusher skipped a dirty page because it was dirtied within the
+    /// [write coalescing window](crate::BufferPoolManagerConfig::write_coalescing_window).
+    pub deferred_write_backs: u64,
+}
+
+impl WriteBackStats {
+    /// Gets the fraction of cold write-backs that were re-dirtied, or `0.0` if there were none.
+    pub fn cold_redirty_rate(&self) -> f64 {
+        rate(self.cold_redirties, self.cold_write_backs)
+    }
+
+    /// Gets the fraction of hot write-backs that were re-dirtied, or `0.0` if there were none.
+    pub fn hot_redirty_rate(&self) -> f64 {
+        rate(self.hot_redirties, self.hot_write_backs)
+    }
+}
+
+/// Computes `part / total`, treating an empty total as a rate of `0.0`.
+fn rate(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    part as f64 / total as f64
+}
+
+/// The shared counters behind [`WriteBackStats`].
+#[derive(Debug, Default)]
+pub(crate) struct WriteBackCounters {
+    /// See [`WriteBackStats::cold_write_backs`].
+    cold_write_backs: AtomicU64,
+
+    /// See [`WriteBackStats::cold_redirties`].
+    cold_redirties: AtomicU64,
+
+    /// See [`WriteBackStats::hot_write_backs`].
+    hot_write_backs: AtomicU64,
+
+    /// See [`WriteBackStats::hot_redirties`].
+    hot_redirties: AtomicU64,
+
+    /// See [`WriteBackStats::coalesced_writes`].
+    coalesced_writes: AtomicU64,
+
+    /// See [`WriteBackStats::deferred_write_backs`].
+    deferred_write_backs: AtomicU64,
+}
+
+impl WriteBackCounters {
+    /// Records that the flusher wrote back a page that was either hot or cold.
+    pub(crate) fn record_write_back(&self, hot: bool) {
+        let counter = if hot {
+            &self.hot_write_backs
+        } else {
+            &self.cold_write_backs
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a page that was hot or cold when the flusher wrote it back was re-dirtied.
+    pub(crate) fn record_redirty(&self, hot: bool) {
+        let counter = if hot {
+            &self.hot_redirties
+        } else {
+            &self.cold_redirties
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a page that was already dirty was updated again.
+    pub(crate) fn record_coalesced_write(&self) {
+        self.coalesced_writes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the flusher skipped a page that was dirtied within the coalescing window.
+    fn record_deferred_write_back(&self) {
+        self.deferred_write_backs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Takes a snapshot of the counters.
+    fn snapshot(&self) -> WriteBackStats {
+        WriteBackStats {
+            cold_write_backs: self.cold_write_backs.load(Ordering::Relaxed),
+            cold_redirties: self.cold_redirties.load(Ordering::Relaxed),
+            hot_write_backs: self.hot_write_backs.load(Ordering::Relaxed),
+            hot_redirties: self.hot_redirties.load(Ordering::Relaxed),
+            coalesced_writes: self.coalesced_writes.load(Ordering::Relaxed),
+            deferred_write_backs: self.deferred_write_backs.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl BufferPoolManager {
+    /// Spawns a daemon on the current thread that writes dirty pages back to persistent storage
+    /// every `interval`.
+    ///
+    /// On every pass, the flusher writes back a bounded number of dirty pages from every frame
+    /// group whose fraction of dirty frames is at least the
+    /// [dirty threshold](crate::BufferPoolManagerConfig::flusher_dirty_threshold), preferring pages
+    /// that have not been accessed recently. Pages that are currently locked, or that were dirtied
+    /// within the [write coalescing window](crate::BufferPoolManagerConfig::write_coalescing_window),
+    /// are skipped until a later pass. The flusher yields to the other tasks of its thread after
+    /// every write, so that it mostly runs while they are idle.
+    ///
+    /// If the flusher encounters an I/O error, it reports the error on the channel returned by
+    /// [`BufferPoolManager::daemon_errors`] and restarts after a backoff.
+    pub fn spawn_flusher(interval: Duration) -> task::JoinHandle<()> {
+        daemon::spawn_daemon("flusher", move || async move {
+            let bpm = Self::get();
+            let sm = StorageManager::get().create_handle()?;
+
+            loop {
+                for group in bpm.frame_groups() {
+                    write_back_group(bpm, group, &sm).await?;
+                }
+
+                executor::sleep(interval).await;
+            }
+        })
+    }
+
+    /// Gets the statistics of the pages that the background flusher has written back.
+    pub fn write_back_stats(&self) -> WriteBackStats {
+        self.write_backs.snapshot()
+    }
+}
+
+/// Writes back dirty pages of a [`FrameGroup`], coldest first, until the group is below the
+/// flusher's dirty threshold.
+///
+/// A single pass writes back at most an eighth of the group's frames (but at least one page).
+///
+/// # Errors
+///
+/// Returns an error if an I/O error occurs, or if the eviction state lock was poisoned and the
+/// buffer pool manager is configured to propagate poisoning errors.
+async fn write_back_group(
+    bpm: &BufferPoolManager,
+    group: &FrameGroup,
+    sm: &StorageManagerHandle,
+) -> Result<()> {
+    // If the group is running out of free frames, then the eviction task will need these pages to
+    // be clean soon, so we cannot afford to wait for more updates.
+    let pressured = group.is_under_pressure();
+
+    let threshold = if pressured {
+        0
+    } else {
+        (bpm.flusher_dirty_threshold() * group.num_frames as f64).ceil() as usize
+    };
+    let above_threshold = || {
+        let dirty = group.num_dirty_frames();
+        dirty != 0 && dirty >= threshold
+    };
+
+    if !above_threshold() {
+        return Ok(());
+    }
+
+    let mut candidates: Vec<(Arc<Page>, bool)> = group
+        .lock_eviction_states()?
+        .iter()
+        .filter_map(|state| match state {
+            EvictionState::Hot(page) => Some((page.clone(), true)),
+            EvictionState::Cool(page)
+            | EvictionState::Claimed(page)
+            | EvictionState::Speculative(page, _) => Some((page.clone(), false)),
+            EvictionState::Cold => None,
+        })
+        .collect();
+
+    // Prefer cold dirty pages, since hot pages are likely to be dirtied again soon.
+    candidates.sort_by_key(|&(_, hot)| hot);
+
+    let window = if pressured {
+        Duration::ZERO
+    } else {
+        bpm.write_coalescing_window()
+    };
+
+    let budget = (group.num_frames / 8).max(1);
+    let mut written = 0;
+
+    for (page, hot) in candidates {
+        if written == budget || !above_threshold() {
+            break;
+        }
+
+        // If we cannot get the write guard immediately, then someone is using the page and we
+        // will try again on the next pass.
+        let Ok(mut guard) = page.frame.try_write() else {
+            continue;
+        };
+
+        // Skip pages that were evicted, moved to another group, or are already clean.
+        let Some(frame) =
+            guard.take_if(|frame| frame.is_dirty() && frame.group_id() == group.group_id)
+        else {
+            continue;
+        };
+
+        // Give recently dirtied pages a chance to absorb more updates before writing them back.
+        if frame.is_recently_dirtied(window) {
+            bpm.write_backs.record_deferred_write_back();
+            guard.replace(frame);
+            continue;
+        }
+
+        let (res, mut frame) = sm.write_from(page.pid, frame).await;
+        if res.is_ok() {
+            frame.clear_dirty();
+            frame.mark_written_back(hot);
+            bpm.write_backs.record_write_back(hot);
+            written += 1;
+        }
+
+        guard.replace(frame);
+        res?;
+
+        drop(guard);
+        executor::yield_now().await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! This module contains the hashers that the page table of the [`BufferPoolManager`] can be
+//! configured with via [`BufferPoolManagerConfig::page_hashing`].
+//!
+//! [`BufferPoolManager`]: crate::BufferPoolManager
+//! [`BufferPoolManagerConfig::page_hashing`]: crate::BufferPoolManagerConfig::page_hashing
+
+use crate::config::PageHashing;
+use crate::page::PageId;
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::hash::{BuildHasher, Hasher};
+
+/// The multiplier of FxHash, as used by `rustc`.
+const FX_SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// The [`BuildHasher`] of the page table, which builds the hasher selected by a [`PageHashing`].
+#[derive(Debug, Clone)]
+pub(crate) enum PageTableHasher {
+    /// See [`PageHashing::SipHash`].
+    Sip(RandomState),
+
+    /// See [`PageHashing::Fx`].
+    Fx,
+
+    /// See [`PageHashing::Custom`].
+    Custom(fn(PageId) -> u64),
+}
+
+impl PageTableHasher {
+    /// Creates the [`BuildHasher`] for a [`PageHashing`].
+    pub(crate) fn new(hashing: PageHashing) -> Self {
+        match hashing {
+            PageHashing::SipHash => Self::Sip(RandomState::new()),
+            PageHashing::Fx => Self::Fx,
+            PageHashing::Custom(hash) => Self::Custom(hash),
+        }
+    }
+}
+
+impl BuildHasher for PageTableHasher {
+    type Hasher = PageHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        match self {
+            Self::Sip(state) => PageHasher::Sip(state.build_hasher()),
+            Self::Fx => PageHasher::Fx(0),
+            Self::Custom(hash) => PageHasher::Custom(*hash, 0),
+        }
+    }
+}
+
+/// A [`Hasher`] built by a [`PageTableHasher`].
+#[derive(Debug, Clone)]
+pub(crate) enum PageHasher {
+    /// A SipHash hasher with random keys.
+    Sip(DefaultHasher),
+
+    /// An FxHash hasher, with the hash so far.
+    Fx(u64),
+
+    /// A user-provided hash function, with the ID of the page that is being hashed.
+    Custom(fn(PageId) -> u64, u64),
+}
+
+impl PageHasher {
+    /// Mixes a word into an FxHash hash.
+    fn fx(hash: u64, word: u64) -> u64 {
+        (hash.rotate_left(5) ^ word).wrapping_mul(FX_SEED)
+    }
+}
+
+impl Hasher for PageHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sip(hasher) => hasher.write(bytes),
+            // A `PageId` only ever writes a single `u64`, so this is never hit in practice.
+            Self::Fx(hash) | Self::Custom(_, hash) => {
+                for chunk in bytes.chunks(8) {
+                    let mut word = [0; 8];
+                    word[..chunk.len()].copy_from_slice(chunk);
+                    *hash = Self::fx(*hash, u64::from_le_bytes(word));
+                }
+            }
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        match self {
+            Self::Sip(hasher) => hasher.write_u64(i),
+            Self::Fx(hash) => *hash = Self::fx(*hash, i),
+            Self::Custom(_, id) => *id = i,
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        match self {
+            Self::Sip(hasher) => hasher.finish(),
+            Self::Fx(hash) => *hash,
+            Self::Custom(hash, id) => hash(PageId::new(*id)),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! This module contains the [`HealthReport`] type, which describes the health of a running
+//! [`BufferPoolManager`].
+//!
+//! A [`HealthReport`] is intended to be cheap enough to generate that it can be wired directly
+//! into a service's readiness probe.
+
+use crate::bpm::BufferPoolManager;
+use crate::probe::{self, RingProbeReport};
+use crate::storage::StorageManager;
+use tokio::time::Duration;
+
+/// A snapshot of the health of the [`BufferPoolManager`].
+///
+/// Generated by [`BufferPoolManager::health`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    /// The number of background daemons (for example, eviction tasks) that are currently running,
+    /// across all threads.
+    pub daemons_alive: usize,
+
+    /// The round-trip latency of a `Nop` operation on the current thread's `io_uring` instance,
+    /// or `None` if the operation failed or did not complete in time.
+    pub ring_latency: Option<Duration>,
+
+    /// The latest measurements of every thread that runs an `io_uring` latency probe.
+    ///
+    /// See [`BufferPoolManager::spawn_ring_probe`].
+    pub ring_probes: Vec<RingProbeReport>,
+
+    /// Whether the database file on persistent storage can be opened for writing.
+    pub storage_writable: bool,
+
+    /// Whether pages are read and written with `O_DIRECT`, which is `false` if the buffer pool
+    /// manager fell back to buffered I/O.
+    ///
+    /// See [`BufferPoolManager::direct_io`].
+    pub direct_io: bool,
+
+    /// The number of frames that are currently free, across all frame groups.
+    pub free_frames: usize,
+
+    /// The total number of frames that the buffer pool manages.
+    pub total_frames: usize,
+
+    /// The number of frames holding data that has not yet been written to persistent storage.
+    pub dirty_frames: usize,
+
+    /// The number of page reads and writes that have been submitted but have not completed yet,
+    /// across all threads.
+    pub in_flight_io: usize,
+
+    /// Whether a shutdown of the background daemons has been requested.
+    pub shutting_down: bool,
+}
+
+impl HealthReport {
+    /// Returns `true` if the buffer pool manager is able to serve requests.
+    ///
+    /// This requires the current thread's `io_uring` instance to be responsive, no probed ring to
+    /// be stalled, persistent storage to be writable, and for no shutdown to have been requested.
+    pub fn is_healthy(&self) -> bool {
+        self.ring_latency.is_some()
+            && self.num_stalled_rings() == 0
+            && self.storage_writable
+            && !self.shutting_down
+    }
+
+    /// Returns the number of probed `io_uring` instances that currently appear to be stalled.
+    pub fn num_stalled_rings(&self) -> usize {
+        self.ring_probes
+            .iter()
+            .filter(|report| report.is_stalled())
+            .count()
+    }
+}
+
+impl BufferPoolManager {
+    /// Generates a [`HealthReport`] for this buffer pool manager.
+    ///
+    /// Note that the `io_uring` responsiveness check only applies to the thread that this function
+    /// is called on, so it must be called from a thread started with
+    /// [`BufferPoolManager::start_thread`].
+    pub async fn health(&self) -> HealthReport {
+        let ring_latency = probe::probe_ring().await;
+        let groups = self.active_frame_groups();
+
+        HealthReport {
+            daemons_alive: self.daemons.num_alive(),
+            ring_latency,
+            ring_probes: self.ring_probes(),
+            storage_writable: StorageManager::get().is_writable(),
+            direct_io: self.direct_io(),
+            free_frames: groups.iter().map(|group| group.num_free_frames()).sum(),
+            total_frames: self.num_frames(),
+            dirty_frames: groups.iter().map(|group| group.num_dirty_frames()).sum(),
+            in_flight_io: StorageManager::get().num_in_flight_io(),
+            shutting_down: self.daemons.is_shutting_down(),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! This module contains the step-by-step initialization of the [`BufferPoolManager`], and the
+//! asynchronous [`BufferPoolManager::initialize_async`] that is built on top of it.
+//!
+//! Initializing a buffer pool with hundreds of gigabytes of frames and database files can take a
+//! long time. Instead of blocking in a single call, [`BufferPoolManager::initialize_async`]
+//! allocates the frames one frame group at a time and preallocates the database files in chunks,
+//! reporting its progress to a callback and yielding to the runtime in between, so that a service
+//! can report its startup progress (or give up on starting up) instead of appearing hung.
+
+use crate::allocator::PageAllocator;
+use crate::bpm::BufferPoolManager;
+use crate::commit;
+use crate::config::BufferPoolManagerConfig;
+use crate::directory;
+use crate::executor;
+use crate::files::DataFiles;
+use crate::numa::{self, NumaLayout};
+use crate::page::FileId;
+use crate::quarantine::Quarantine;
+use crate::storage::{DoublewriteBuffer, FrameArena, StorageManager};
+use std::io::{Error, Result};
+use std::mem;
+use std::path::PathBuf;
+
+/// The number of bytes of a database file that are preallocated at once.
+const PREALLOCATION_CHUNK: u64 = 64 * 1024 * 1024;
+
+/// The progress of [`BufferPoolManager::initialize_async`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InitProgress {
+    /// The number of buffer frames that have been allocated so far.
+    pub frames_allocated: usize,
+
+    /// The total number of buffer frames to allocate.
+    pub total_frames: usize,
+
+    /// The number of bytes of the database files that have been preallocated so far.
+    pub bytes_preallocated: u64,
+
+    /// The total number of bytes of the database files to preallocate.
+    pub total_bytes: u64,
+}
+
+impl InitProgress {
+    /// Checks if initialization has nothing left to allocate.
+    pub fn is_complete(&self) -> bool {
+        self.frames_allocated == self.total_frames && self.bytes_preallocated == self.total_bytes
+    }
+}
+
+/// A buffer pool manager whose frame memory is being allocated.
+///
+/// If the builder is dropped before [`BufferPoolManager::install`] takes its memory, every arena
+/// that was allocated so far is freed, which makes initialization cancellable.
+#[derive(Debug)]
+pub(crate) struct PoolBuilder {
+    /// The configuration of the buffer pool manager.
+    pub(crate) config: BufferPoolManagerConfig,
+
+    /// The paths to the database files.
+    pub(crate) paths: Vec<PathBuf>,
+
+    /// The quarantine list of the database files, which is handed to the storage manager once the
+    /// pool is installed.
+    pub(crate) quarantine: Option<Quarantine>,
+
+    /// The double-write buffer of the database files, if it is enabled, which is handed to the
+    /// storage manager once the pool is installed.
+    pub(crate) doublewrite: Option<DoublewriteBuffer>,
+
+    /// The page allocator of the database files, which is handed to the buffer pool manager once
+    /// the pool is installed.
+    pub(crate) allocator: Option<PageAllocator>,
+
+    /// The registry of the data files next to the database files, which is handed to the storage
+    /// manager once the pool is installed.
+    pub(crate) data_files: Option<DataFiles>,
+
+    /// Whether pages are read and written with `O_DIRECT`, which is disabled if it was configured
+    /// so or if the database files do not support it.
+    pub(crate) direct_io: bool,
+
+    /// The number of buffer frames.
+    pub(crate) num_frames: usize,
+
+    /// The NUMA nodes that the frame groups are bound to, if NUMA-aware allocation is enabled and
+    /// available.
+    pub(crate) numa: Option<NumaLayout>,
+
+    /// The memory of every arena that has been allocated so far.
+    pub(crate) memory: Vec<&'static mut [u8]>,
+
+    /// Every arena that has been allocated so far, in the same order as `memory`.
+    pub(crate) arenas: Vec<FrameArena>,
+}
+
+impl PoolBuilder {
+    /// Validates the configuration and prepares the database directory, if one is configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`](crate::error::ConfigError) if the configuration is invalid, or an
+    /// error if the configured database directory cannot be prepared, torn pages or an interrupted
+    /// [multi-page commit](BufferPoolManager::commit_pages) cannot be recovered, the double-write
+    /// buffer cannot be created, or the quarantine list, page allocation state, or data files
+    /// cannot be loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer pool manager is already initialized.
+    pub(crate) fn new(config: BufferPoolManagerConfig) -> Result<Self> {
+        assert!(
+            !BufferPoolManager::is_initialized(),
+            "Tried to initialize a BufferPoolManager more than once"
+        );
+
+        config.validate()?;
+
+        let capacity = config.capacity;
+        let page_size = config.page_size;
+        let num_frames = config.total_frames();
+
+        let paths = match &config.directory {
+            Some(dir) => directory::prepare(dir, page_size, capacity).map_err(|e| {
+                Error::new(
+                    e.kind(),
+                    format!("Unable to prepare {}: {e}", dir.display()),
+                )
+            })?,
+            None => config.paths.clone(),
+        };
+
+        // Repair any page that was torn by a crash, and then finish any multi-page commit that was
+        // interrupted before its pages were published.
+        DoublewriteBuffer::recover(&paths, page_size)?;
+        commit::recover(&paths, page_size)?;
+
+        let doublewrite = config
+            .doublewrite_buffer
+            .then(|| DoublewriteBuffer::create(&paths, page_size))
+            .transpose()?;
+
+        let quarantine = Quarantine::load(&paths)?;
+        let allocator = PageAllocator::load(FileId::DEFAULT, &paths, capacity, page_size)?;
+        let data_files = DataFiles::load(&paths, page_size)?;
+
+        let direct_io = !config.buffered_io && StorageManager::supports_direct_io(&paths);
+        #[cfg(feature = "tracing")]
+        if !config.buffered_io && !direct_io {
+            tracing::warn!(
+                "O_DIRECT is not supported for the database files, falling back to buffered I/O"
+            );
+        }
+
+        let num_groups = num_frames.div_ceil(config.group_size());
+
+        let numa = config
+            .numa_aware
+            .then(|| NumaLayout::detect(num_groups))
+            .flatten();
+
+        Ok(Self {
+            config,
+            paths,
+            quarantine: Some(quarantine),
+            doublewrite,
+            allocator: Some(allocator),
+            data_files: Some(data_files),
+            direct_io,
+            num_frames,
+            numa,
+            memory: Vec::with_capacity(num_groups),
+            arenas: Vec::with_capacity(num_groups),
+        })
+    }
+
+    /// Gets the number of buffer frames that have been allocated so far.
+    pub(crate) fn frames_allocated(&self) -> usize {
+        self.arenas.iter().map(|arena| arena.num_frames).sum()
+    }
+
+    /// Checks if the arenas of every frame group have been allocated.
+    pub(crate) fn is_allocated(&self) -> bool {
+        self.frames_allocated() == self.num_frames
+    }
+
+    /// Allocates the arena of the next frame group, initialized to 0s.
+    ///
+    /// Every frame group holds the configured
+    /// [number of frames](BufferPoolManagerConfig::frame_group_size), except for the last one,
+    /// which holds the remaining frames if the number of frames is not a multiple of the group
+    /// size.
+    ///
+    /// In NUMA-aware mode, the arena is bound to the NUMA node of the frame group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if every arena has already been allocated, or if NUMA-aware frame memory cannot be
+    /// mapped.
+    pub(crate) fn allocate_group(&mut self) {
+        assert!(
+            !self.is_allocated(),
+            "Every frame group is already allocated"
+        );
+
+        let id = self.arenas.len();
+        let page_size = self.config.page_size;
+        let group_size = self.config.group_size();
+        let num_frames = group_size.min(self.num_frames - self.frames_allocated());
+        let arena_len = num_frames * page_size;
+
+        let (bytes, numa_node) = match &self.numa {
+            Some(numa) => {
+                let node = numa.node_of_group(id);
+                let bytes = numa::alloc_on_node(arena_len, node)
+                    .unwrap_or_else(|e| panic!("Unable to allocate frames on node {node}: {e}"));
+                (bytes, Some(node))
+            }
+            None => (FrameArena::alloc_heap(arena_len), None),
+        };
+
+        self.arenas.push(FrameArena {
+            base: bytes.as_ptr() as usize,
+            first_frame_id: id * group_size,
+            num_frames,
+            frame_size: page_size,
+            first_buf_index: None,
+            numa_node,
+        });
+        self.memory.push(bytes);
+    }
+
+    /// Gets the number of bytes that every database file needs to hold its share of the pages.
+    fn file_len(&self) -> u64 {
+        let pages_per_file = self.config.capacity.div_ceil(self.paths.len().max(1));
+        (pages_per_file * self.config.page_size) as u64
+    }
+}
+
+impl Drop for PoolBuilder {
+    fn drop(&mut self) {
+        // Nothing points into the arenas until they are installed, at which point they are taken.
+        for arena in mem::take(&mut self.arenas) {
+            // Safety: Every arena was allocated by `allocate_group`, and no `Frame` exists yet.
+            unsafe { arena.free() };
+        }
+    }
+}
+
+impl BufferPoolManager {
+    /// Constructs a new buffer pool manager with the given [`BufferPoolManagerConfig`]
+    /// asynchronously, reporting its progress to `progress`.
+    ///
+    /// Unlike [`BufferPoolManager::initialize_with_config`], this also preallocates the database
+    /// files (creating them if they do not exist) with `fallocate`, so that the space for every
+    /// page is reserved up front. The frames are allocated one frame group at a time and the files
+    /// are preallocated in chunks, and `progress` is called after every step, with the task
+    /// yielding to the runtime in between.
+    ///
+    /// Initialization can be cancelled by dropping the returned future, in which case all of the
+    /// frame memory that was allocated so far is freed and the buffer pool manager is not
+    /// initialized. Space that was already preallocated in the database files is kept.
+    ///
+    /// This must be called from a thread started with [`BufferPoolManager::start_thread`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`](crate::error::ConfigError) if the configuration is invalid, or an
+    /// error if the configured database directory cannot be prepared or a database file cannot be
+    /// opened or preallocated. The buffer pool manager is not initialized in that case.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if NUMA-aware frame memory cannot be mapped, or if the buffer pool
+    /// manager is already initialized.
+    pub async fn initialize_async<F>(config: BufferPoolManagerConfig, mut progress: F) -> Result<()>
+    where
+        F: FnMut(InitProgress),
+    {
+        let mut builder = PoolBuilder::new(config)?;
+
+        let file_len = builder.file_len();
+        let mut report = InitProgress {
+            frames_allocated: 0,
+            total_frames: builder.num_frames,
+            bytes_preallocated: 0,
+            total_bytes: file_len * builder.paths.len() as u64,
+        };
+        progress(report);
+
+        while !builder.is_allocated() {
+            builder.allocate_group();
+
+            report.frames_allocated = builder.frames_allocated();
+            progress(report);
+
+            executor::yield_now().await;
+        }
+
+        for path in &builder.paths {
+            let file = tokio_uring::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .open(path)
+                .await?;
+
+            let mut offset = 0;
+            while offset < file_len {
+                let len = PREALLOCATION_CHUNK.min(file_len - offset);
+                file.fallocate(offset, len, 0).await?;
+                offset += len;
+
+                report.bytes_preallocated += len;
+                progress(report);
+            }
+
+            file.sync_all().await?;
+            file.close().await?;
+        }
+
+        Self::install(builder);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+//! A pool-wide invariant checker, only available in debug builds.
+//!
+//! The buffer pool manager spreads its state over several structures that are updated
+//! independently: the free lists and free-frame counters of every [`FrameGroup`], the dirty bits
+//! and dirty-frame counters, the page table and the owners of every [`Frame`], and the `is_loaded`
+//! hint of every [`Page`]. The checker periodically cross-checks these structures and reports any
+//! divergence, so that bugs are caught close to where they were introduced.
+//!
+//! Since every structure is allowed to change while the checker runs, the checker only inspects
+//! state that it can observe consistently (for example, it skips pages that are currently locked).
+
+use crate::bpm::BufferPoolManager;
+use crate::daemon;
+use crate::error::DaemonError;
+use crate::executor;
+use crate::page::Page;
+use crate::storage::{Frame, FrameGroup};
+use std::collections::HashMap;
+use std::io::Error;
+use std::ops::Deref;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::task;
+use tokio::time::Duration;
+
+impl BufferPoolManager {
+    /// Spawns a daemon on the current thread that runs [`BufferPoolManager::check_invariants`]
+    /// every `interval` and reports every violation on the channel returned by
+    /// [`BufferPoolManager::daemon_errors`].
+    ///
+    /// Violations do not stop or restart the daemon.
+    ///
+    /// This function is only available in debug builds.
+    pub fn spawn_invariant_checker(interval: Duration) -> task::JoinHandle<()> {
+        daemon::spawn_daemon("invariant-checker", move || async move {
+            let bpm = Self::get();
+
+            loop {
+                for violation in bpm.check_invariants().await {
+                    let error = Error::other(format!("invariant violation: {violation}"));
+                    bpm.daemons
+                        .report(DaemonError::new("invariant-checker", error));
+                }
+
+                executor::sleep(interval).await;
+            }
+        })
+    }
+
+    /// Checks the invariants between the internal structures of the buffer pool manager, returning
+    /// a description of every violation that was found.
+    ///
+    /// This function is only available in debug builds.
+    pub async fn check_invariants(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        for group in self.frame_groups() {
+            check_free_frames(group, &mut violations).await;
+        }
+
+        let mut pages: Vec<Arc<Page>> = Vec::new();
+        self.pages.scan(|_, page| pages.push(page.clone()));
+
+        // The number of dirty frames we observed in each group, and whether we were able to observe
+        // every frame of the group that is owned by a page.
+        let mut dirty: HashMap<usize, usize> = HashMap::new();
+        let mut complete = true;
+
+        // The page that owns each frame, to catch frames with more than one owner.
+        let mut owners: HashMap<usize, Arc<Page>> = HashMap::new();
+
+        for page in pages {
+            let Ok(guard) = page.frame.try_read() else {
+                complete = false;
+                continue;
+            };
+
+            // The `is_loaded` flag is cleared while holding the write lock whenever a page is
+            // evicted, so it can only be set under the read lock if the page has a frame.
+            let Some(frame) = guard.deref() else {
+                if page.is_loaded.load(Ordering::Acquire) {
+                    violations.push(format!(
+                        "{} is marked as loaded but does not hold a frame",
+                        page.pid
+                    ));
+                }
+                continue;
+            };
+
+            check_frame_owner(&page, frame, &mut violations);
+
+            if let Some(other) = owners.insert(frame.frame_id(), page.clone()) {
+                violations.push(format!(
+                    "Frame {} is owned by both {} and {}",
+                    frame.frame_id(),
+                    other.pid,
+                    page.pid
+                ));
+            }
+
+            if frame.is_dirty() {
+                *dirty.entry(frame.group_id()).or_default() += 1;
+            }
+        }
+
+        // Dirty counters can only be compared if we were able to observe every page.
+        if complete {
+            for group in self.frame_groups() {
+                let observed = dirty.get(&group.group_id).copied().unwrap_or_default();
+                let counted = group.num_dirty_frames();
+
+                if observed != counted {
+                    violations.push(format!(
+                        "FrameGroup {} counts {counted} dirty frames but {observed} frames are dirty",
+                        group.group_id
+                    ));
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+/// Checks that the free-frame counter of a [`FrameGroup`] matches the length of its free list.
+///
+/// The counter is updated right after the free list, so a single mismatch may be transient. We only
+/// report a violation if the mismatch persists after yielding to other tasks.
+async fn check_free_frames(group: &FrameGroup, violations: &mut Vec<String>) {
+    let sample = |group: &FrameGroup| (group.num_free_frames(), group.free_list.1.len());
+
+    let (counted, actual) = sample(group);
+    if counted == actual {
+        return;
+    }
+
+    executor::yield_now().await;
+
+    if sample(group) == (counted, actual) {
+        violations.push(format!(
+            "FrameGroup {} counts {counted} free frames but its free list has {actual} frames",
+            group.group_id
+        ));
+    }
+}
+
+/// Checks that a [`Frame`] owned by a [`Page`] has that same page recorded as its owner.
+fn check_frame_owner(page: &Arc<Page>, frame: &Frame, violations: &mut Vec<String>) {
+    match frame.page_owner() {
+        Some(owner) if Arc::ptr_eq(owner, page) => {}
+        Some(owner) => violations.push(format!(
+            "{} holds frame {} but the frame's owner is {}",
+            page.pid,
+            frame.frame_id(),
+            owner.pid
+        )),
+        None => violations.push(format!(
+            "{} holds frame {} but the frame has no owner",
+            page.pid,
+            frame.frame_id()
+        )),
+    }
+}
@@ -0,0 +1,145 @@
+//! This module contains artificial latency injection for page reads and writes.
+//!
+//! Staging environments often run on fast local NVMe drives, while production runs on network
+//! attached block storage with latencies that are orders of magnitude higher. To check that the
+//! asynchronous design actually hides slower storage, a staging environment can make every page
+//! read and write on a drive wait for an [`InjectedLatency`] before it is submitted, with
+//! [`BufferPoolManager::set_injected_latency`]. The latency can be changed at any time while the
+//! buffer pool is running.
+
+use crate::bpm::BufferPoolManager;
+use crate::executor;
+use crate::storage::{IoKind, StorageManager};
+use rand::Rng;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The artificial latency that is added to every page read and write on a drive.
+///
+/// Every operation waits for its base latency plus a uniformly random jitter between zero and
+/// `jitter`. The default adds no latency at all.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InjectedLatency {
+    /// The latency added to every page read.
+    pub read: Duration,
+
+    /// The latency added to every page write.
+    pub write: Duration,
+
+    /// The maximum random latency added to every page read and write on top of `read` or `write`.
+    pub jitter: Duration,
+}
+
+/// The [`InjectedLatency`] of a single drive, in microseconds, which can be updated at any time.
+#[derive(Debug, Default)]
+struct DriveLatency {
+    /// See [`InjectedLatency::read`].
+    read: AtomicU64,
+
+    /// See [`InjectedLatency::write`].
+    write: AtomicU64,
+
+    /// See [`InjectedLatency::jitter`].
+    jitter: AtomicU64,
+}
+
+/// The injected latency of every drive.
+#[derive(Debug)]
+pub(crate) struct LatencyInjector {
+    /// The injected latency of every drive, indexed by drive.
+    drives: Box<[DriveLatency]>,
+}
+
+impl LatencyInjector {
+    /// Creates a new `LatencyInjector` for the given number of drives, which injects no latency.
+    pub(crate) fn new(num_drives: usize) -> Self {
+        Self {
+            drives: (0..num_drives).map(|_| DriveLatency::default()).collect(),
+        }
+    }
+
+    /// Gets the injected latency of the given drive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `drive` is out of bounds.
+    fn get(&self, drive: usize) -> InjectedLatency {
+        let knobs = &self.drives[drive];
+        let load = |knob: &AtomicU64| Duration::from_micros(knob.load(Ordering::Relaxed));
+
+        InjectedLatency {
+            read: load(&knobs.read),
+            write: load(&knobs.write),
+            jitter: load(&knobs.jitter),
+        }
+    }
+
+    /// Sets the injected latency of the given drive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `drive` is out of bounds.
+    fn set(&self, drive: usize, latency: InjectedLatency) {
+        let knobs = &self.drives[drive];
+        let store = |knob: &AtomicU64, d: Duration| {
+            knob.store(
+                d.as_micros().min(u64::MAX as u128) as u64,
+                Ordering::Relaxed,
+            )
+        };
+
+        store(&knobs.read, latency.read);
+        store(&knobs.write, latency.write);
+        store(&knobs.jitter, latency.jitter);
+    }
+
+    /// Waits for the injected latency of an operation on the given drive, if any.
+    ///
+    /// Drives that latency cannot be injected into (such as the spill file of temporary pages)
+    /// never wait.
+    pub(crate) async fn delay(&self, drive: usize, kind: IoKind) {
+        let Some(knobs) = self.drives.get(drive) else {
+            return;
+        };
+
+        let base = match kind {
+            IoKind::Read => knobs.read.load(Ordering::Relaxed),
+            IoKind::Write => knobs.write.load(Ordering::Relaxed),
+        };
+        let jitter = knobs.jitter.load(Ordering::Relaxed);
+        if base == 0 && jitter == 0 {
+            return;
+        }
+
+        let jitter = rand::thread_rng().gen_range(0..=jitter);
+        executor::sleep(Duration::from_micros(base.saturating_add(jitter))).await;
+    }
+}
+
+impl BufferPoolManager {
+    /// Sets the artificial latency that is added to every page read and write on the given drive,
+    /// where drives are numbered in the order of
+    /// [`BufferPoolManagerConfig::paths`](crate::BufferPoolManagerConfig::paths).
+    ///
+    /// This is intended for staging environments that emulate slower production storage, and
+    /// takes effect for every operation that starts afterwards. Latencies are rounded down to
+    /// whole microseconds. Temporary pages are never delayed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `drive` is not the index of a database file.
+    pub fn set_injected_latency(&self, drive: usize, latency: InjectedLatency) {
+        StorageManager::get().latency().set(drive, latency);
+    }
+
+    /// Gets the artificial latency that is added to every page read and write on the given drive.
+    ///
+    /// See [`BufferPoolManager::set_injected_latency`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `drive` is not the index of a database file.
+    pub fn injected_latency(&self, drive: usize) -> InjectedLatency {
+        StorageManager::get().latency().get(drive)
+    }
+}
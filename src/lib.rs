@@ -6,10 +6,77 @@
 #![warn(clippy::missing_panics_doc)]
 #![warn(clippy::missing_safety_doc)]
 
+mod access;
+mod allocator;
+pub mod blocking;
 mod bpm;
+mod bypass;
+mod commit;
+mod config;
+mod daemon;
+mod directory;
+mod emitter;
+pub mod error;
+mod events;
+mod executor;
+#[cfg(feature = "test-util")]
+mod fault;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod files;
+mod flusher;
+mod hashing;
+mod health;
+mod init;
+#[cfg(debug_assertions)]
+mod invariants;
+mod latency;
+#[cfg(feature = "deadlock-detection")]
+mod lock_tracker;
+pub mod metrics;
+mod numa;
 pub mod page;
+mod prefetch;
+mod preload;
+mod probe;
+mod quarantine;
+#[cfg(feature = "experimental")]
+mod rebalancer;
+mod resize;
+mod scan;
+mod stats;
 pub(crate) mod storage;
+mod tenant;
+#[cfg(feature = "test-util")]
+mod test_util;
+mod wal;
 
+pub use access::FlushPolicy;
 pub use bpm::BufferPoolManager;
+pub use bypass::PageBuf;
+pub use config::{
+    BufferPoolManagerConfig, CachePolicy, GroupSelection, PageHashing, PoisonPolicy,
+    ReplacementPolicy,
+};
+pub use emitter::StatsFormat;
+pub use events::{PageEventFuture, PageEventListener};
+#[cfg(feature = "test-util")]
+pub use fault::FaultInjectingStorage;
+pub use flusher::WriteBackStats;
+pub use health::HealthReport;
+pub use init::InitProgress;
+pub use latency::InjectedLatency;
+pub use probe::RingProbeReport;
+pub use scan::{PageScan, DEFAULT_SCAN_READ_AHEAD};
+pub use stats::{FrameGroupOccupancy, PoolStats, StatsWindow, UringStats};
+pub use tenant::TenantId;
+pub use wal::{WalFuture, WalHook};
 
-pub use storage::IO_OPERATIONS;
+#[cfg(feature = "aes-gcm")]
+pub use storage::AesGcmCodec;
+#[cfg(feature = "lz4")]
+pub use storage::CompressedStorage;
+pub use storage::{
+    BackendFuture, MemoryStorage, PageCodec, ReplacementCandidate, Replacer, StorageBackend,
+    IO_OPERATIONS,
+};
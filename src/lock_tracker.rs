@@ -0,0 +1,261 @@
+//! A tracker of page lock acquisitions that detects deadlocks and stalls, only available with the
+//! `deadlock-detection` feature.
+//!
+//! Taking the locks of two pages in opposite orders in two tasks (for example, when each holds a
+//! [`WritePageGuard`] and then asks for the other's page) makes both tasks wait forever, without
+//! any indication of what went wrong. With this feature, every page guard records which task holds
+//! it, and every contended acquisition through a [`PageHandle`] records which page the task waits
+//! for. This forms a wait-for graph, which is checked for a cycle every time a task starts waiting:
+//!
+//! - If a cycle is found, the deadlock is reported with every task and page that is part of it.
+//!   Debug builds panic in the task that closed the cycle, which releases its guards and lets the
+//!   other tasks of the cycle continue. Release builds emit a `tracing` warning instead.
+//! - If a wait takes longer than [`BufferPoolManagerConfig::lock_wait_threshold`], a `tracing`
+//!   warning names the page and the tasks that hold it, and is repeated for as long as the wait
+//!   goes on.
+//!
+//! Outside of a task (for example, with the [`blocking`](crate::blocking) API), the current thread
+//! stands in for the task.
+//!
+//! [`WritePageGuard`]: crate::page::WritePageGuard
+//! [`PageHandle`]: crate::page::PageHandle
+//! [`BufferPoolManagerConfig::lock_wait_threshold`]:
+//!     crate::BufferPoolManagerConfig::lock_wait_threshold
+
+use crate::bpm::BufferPoolManager;
+use crate::executor;
+use crate::page::PageId;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Write};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::task::Poll;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+use tokio::task;
+
+/// The default time after which a waiting task is reported as stalled.
+pub(crate) const DEFAULT_LOCK_WAIT_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// The wait-for graph of every page lock.
+static GRAPH: LazyLock<Mutex<WaitForGraph>> = LazyLock::new(Mutex::default);
+
+/// Something that can hold and wait for page locks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Holder {
+    /// A task of a Tokio runtime.
+    Task(task::Id),
+
+    /// A thread that is not running a task.
+    Thread(ThreadId),
+}
+
+impl Holder {
+    /// Gets the task that is currently running, or the current thread if there is none.
+    fn current() -> Self {
+        match task::try_id() {
+            Some(id) => Self::Task(id),
+            None => Self::Thread(thread::current().id()),
+        }
+    }
+}
+
+impl Display for Holder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Task(id) => write!(f, "task {id}"),
+            Self::Thread(id) => write!(f, "thread {id:?}"),
+        }
+    }
+}
+
+/// Which holders hold and wait for which page locks.
+#[derive(Debug, Default)]
+struct WaitForGraph {
+    /// The holders of every locked page, once per guard.
+    holds: HashMap<PageId, Vec<Holder>>,
+
+    /// The page that every waiting holder waits for.
+    waits: HashMap<Holder, PageId>,
+}
+
+impl WaitForGraph {
+    /// Locks the global wait-for graph.
+    fn get() -> MutexGuard<'static, Self> {
+        GRAPH.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Finds a cycle that `waiter` closes by waiting for `pid`.
+    ///
+    /// Returns every page along the cycle along with one of its holders, starting at `pid` and
+    /// ending with a page that `waiter` holds itself.
+    fn find_cycle(&self, waiter: Holder, pid: PageId) -> Option<Vec<(PageId, Holder)>> {
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+
+        self.search(waiter, pid, &mut path, &mut visited)
+            .then_some(path)
+    }
+
+    /// Searches the holders of `pid` (and transitively, the pages that they wait for) for
+    /// `waiter`, extending `path` with the way there.
+    fn search(
+        &self,
+        waiter: Holder,
+        pid: PageId,
+        path: &mut Vec<(PageId, Holder)>,
+        visited: &mut HashSet<Holder>,
+    ) -> bool {
+        for &holder in self.holds.get(&pid).into_iter().flatten() {
+            path.push((pid, holder));
+            if holder == waiter {
+                return true;
+            }
+
+            if visited.insert(holder) {
+                if let Some(&next) = self.waits.get(&holder) {
+                    if self.search(waiter, next, path, visited) {
+                        return true;
+                    }
+                }
+            }
+            path.pop();
+        }
+
+        false
+    }
+
+    /// Describes the holders of `pid`.
+    fn describe_holders(&self, pid: PageId) -> String {
+        let mut holders: Vec<String> = Vec::new();
+        for holder in self.holds.get(&pid).into_iter().flatten() {
+            let holder = holder.to_string();
+            if !holders.contains(&holder) {
+                holders.push(holder);
+            }
+        }
+
+        if holders.is_empty() {
+            return "nobody".to_string();
+        }
+        holders.join(", ")
+    }
+}
+
+/// Records that the current task acquired a guard on the page `pid`.
+pub(crate) fn acquired(pid: PageId) {
+    let holder = Holder::current();
+    WaitForGraph::get()
+        .holds
+        .entry(pid)
+        .or_default()
+        .push(holder);
+}
+
+/// Records that a guard on the page `pid` was released.
+///
+/// The guard is assumed to belong to the current task, unless the current task holds no guard on
+/// the page, in which case the guard was moved to the current task after it was acquired.
+pub(crate) fn released(pid: PageId) {
+    let holder = Holder::current();
+    let mut graph = WaitForGraph::get();
+
+    let Some(holders) = graph.holds.get_mut(&pid) else {
+        return;
+    };
+    let i = holders.iter().position(|&h| h == holder).unwrap_or(0);
+    holders.swap_remove(i);
+    if holders.is_empty() {
+        graph.holds.remove(&pid);
+    }
+}
+
+/// Records that the current task waits for the lock of page `pid` from when it is created until
+/// it is dropped.
+struct Waiting {
+    /// The waiting task.
+    holder: Holder,
+}
+
+impl Waiting {
+    /// Records the wait, and reports a deadlock if the wait closes a cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if the wait closes a cycle.
+    fn start(holder: Holder, pid: PageId) -> Self {
+        let mut graph = WaitForGraph::get();
+        graph.waits.insert(holder, pid);
+        let cycle = graph.find_cycle(holder, pid);
+        drop(graph);
+
+        let waiting = Self { holder };
+
+        if let Some(cycle) = cycle {
+            let mut message = format!("Deadlock detected: {holder} waits for {pid}");
+            for (i, (pid, holder)) in cycle.iter().enumerate() {
+                if i > 0 {
+                    let _ = write!(message, ", which waits for {pid}");
+                }
+                let _ = write!(message, ", held by {holder}");
+            }
+
+            if cfg!(debug_assertions) {
+                panic!("{message}");
+            }
+            tracing::warn!("{message}");
+        }
+
+        waiting
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        WaitForGraph::get().waits.remove(&self.holder);
+    }
+}
+
+/// Awaits `lock`, which acquires the lock of page `pid`, while recording the wait in the wait-for
+/// graph if the lock is contended.
+///
+/// # Panics
+///
+/// Panics in debug builds if the wait closes a cycle of tasks that wait for each other.
+pub(crate) async fn wait_for<F: Future>(pid: PageId, lock: F) -> F::Output {
+    let mut lock = pin!(lock);
+
+    // Most acquisitions are not contended, and are not worth recording.
+    let acquired = std::future::poll_fn(|cx| match lock.as_mut().poll(cx) {
+        Poll::Ready(guard) => Poll::Ready(Some(guard)),
+        Poll::Pending => Poll::Ready(None),
+    })
+    .await;
+    if let Some(guard) = acquired {
+        return guard;
+    }
+
+    let holder = Holder::current();
+    let _waiting = Waiting::start(holder, pid);
+
+    // Without a runtime, there is no timer to report stalls with.
+    if !executor::in_runtime() {
+        return lock.await;
+    }
+
+    let threshold = BufferPoolManager::get().lock_wait_threshold();
+    let start = Instant::now();
+    loop {
+        match executor::timeout(threshold, lock.as_mut()).await {
+            Some(guard) => return guard,
+            None => {
+                let holders = WaitForGraph::get().describe_holders(pid);
+                tracing::warn!(
+                    "{holder} has waited {:?} for {pid}, which is held by {holders}",
+                    start.elapsed()
+                );
+            }
+        }
+    }
+}
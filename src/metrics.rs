@@ -0,0 +1,412 @@
+//! This module contains [`LatencyHistograms`], which records how long the operations of the
+//! [`BufferPoolManager`] take, and exports them in the Prometheus text format.
+//!
+//! Counters such as [`PoolStats`](crate::PoolStats) tell how often something happened and how long
+//! it took in total, but not how the time was distributed. A mean read latency of 10µs may come
+//! from every read taking 10µs, or from one read in a hundred waiting a millisecond for I/O. With
+//! [`BufferPoolManagerConfig::latency_histograms`] enabled, every one of the following operations
+//! is recorded in a histogram of its own:
+//!
+//! - [`read_hit`](LatencyHistograms::read_hit): a [`PageHandle::read`] that found its page in
+//!   memory.
+//! - [`read_miss`](LatencyHistograms::read_miss): a [`PageHandle::read`] that had to read its page
+//!   from persistent storage, including waiting for a free frame and the I/O itself.
+//! - [`write_guard`](LatencyHistograms::write_guard): a [`PageHandle::write`], from the call until
+//!   the guard is handed out, including waiting for the page's lock and loading the page.
+//! - [`eviction_write`](LatencyHistograms::eviction_write): a write of a run of dirty pages that an
+//!   eviction pass wrote back together.
+//!
+//! The histograms use HDR-style buckets: every power of two nanoseconds is split into 8 buckets of
+//! equal width, so every recorded latency is known to within 12.5%, from nanoseconds up to about
+//! 18 minutes, with a fixed amount of memory and a single relaxed atomic increment per recording.
+//!
+//! [`PageHandle::read`]: crate::page::PageHandle::read
+//! [`PageHandle::write`]: crate::page::PageHandle::write
+//! [`BufferPoolManagerConfig::latency_histograms`]:
+//!     crate::BufferPoolManagerConfig::latency_histograms
+
+use crate::bpm::BufferPoolManager;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// The number of bits of a latency (below its highest set bit) that select its bucket within its
+/// power of two.
+const SUB_BUCKET_BITS: u32 = 3;
+
+/// The number of buckets that every power of two is split into.
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+/// The largest latency in nanoseconds that is recorded precisely. Longer latencies are recorded
+/// as this latency.
+const MAX_NANOS: u64 = (1 << 40) - 1;
+
+/// The number of buckets of every histogram.
+const NUM_BUCKETS: usize = bucket_index(MAX_NANOS) + 1;
+
+/// The name of the histogram metric in the Prometheus text format.
+const PROMETHEUS_METRIC: &str = "async_bpm_page_latency_seconds";
+
+/// The powers of two nanoseconds that are exported as the upper bounds of the Prometheus buckets,
+/// from about 1µs to about 17s.
+const PROMETHEUS_BOUNDS: std::ops::RangeInclusive<u32> = 10..=34;
+
+/// Gets the index of the bucket that a latency of `nanos` nanoseconds is counted in.
+const fn bucket_index(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+
+    let exponent = 63 - nanos.leading_zeros();
+    let sub_bucket = (nanos >> (exponent - SUB_BUCKET_BITS)) as usize & (SUB_BUCKETS - 1);
+
+    (exponent - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS + sub_bucket
+}
+
+/// Gets the smallest latency in nanoseconds that is counted in the bucket at `index`.
+const fn bucket_lower_bound(index: usize) -> u64 {
+    let group = index / SUB_BUCKETS;
+    let sub_bucket = (index % SUB_BUCKETS) as u64;
+
+    if group == 0 {
+        sub_bucket
+    } else {
+        (SUB_BUCKETS as u64 + sub_bucket) << (group - 1)
+    }
+}
+
+/// Gets the largest latency in nanoseconds that is counted in the bucket at `index`.
+const fn bucket_upper_bound(index: usize) -> u64 {
+    bucket_lower_bound(index + 1) - 1
+}
+
+/// A snapshot of the distribution of the latencies of a single kind of operation.
+///
+/// Part of [`LatencyHistograms`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// The number of latencies that were counted in every bucket.
+    counts: Vec<u64>,
+
+    /// The number of recorded latencies.
+    count: u64,
+
+    /// The sum of every recorded latency, in nanoseconds.
+    sum: u64,
+
+    /// The largest recorded latency, in nanoseconds.
+    max: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; NUM_BUCKETS],
+            count: 0,
+            sum: 0,
+            max: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Gets the number of recorded latencies.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Gets the sum of every recorded latency.
+    pub fn sum(&self) -> Duration {
+        Duration::from_nanos(self.sum)
+    }
+
+    /// Gets the mean of the recorded latencies, or `None` if no latency was recorded.
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count {
+            0 => None,
+            count => Some(Duration::from_nanos(self.sum / count)),
+        }
+    }
+
+    /// Gets the largest recorded latency, or `None` if no latency was recorded.
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_nanos(self.max))
+    }
+
+    /// Gets the latency that a fraction `q` of the recorded latencies did not exceed (for example,
+    /// `0.99` for the 99th percentile), or `None` if no latency was recorded.
+    ///
+    /// The latency is the upper bound of the bucket that the quantile falls into, so it
+    /// overestimates the true quantile by at most 12.5%. It never exceeds
+    /// [`LatencyHistogram::max`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not between `0.0` and `1.0`.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        assert!(
+            (0.0..=1.0).contains(&q),
+            "The quantile {q} is not in [0, 1]"
+        );
+
+        if self.count == 0 {
+            return None;
+        }
+
+        // The rank of the latency that the quantile falls on, starting at 1.
+        let rank = ((q * self.count as f64).ceil() as u64).clamp(1, self.count);
+
+        let mut seen = 0;
+        let index = self
+            .counts
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(NUM_BUCKETS - 1);
+
+        Some(Duration::from_nanos(
+            bucket_upper_bound(index).min(self.max),
+        ))
+    }
+
+    /// Iterates over every bucket that counted at least one latency, in increasing order of
+    /// latency, as the largest latency that the bucket counts along with its number of latencies.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(index, &count)| (Duration::from_nanos(bucket_upper_bound(index)), count))
+    }
+
+    /// Gets the number of recorded latencies that were shorter than `2^exponent` nanoseconds.
+    fn count_below_power_of_two(&self, exponent: u32) -> u64 {
+        self.counts[..bucket_index(1 << exponent)].iter().sum()
+    }
+}
+
+/// A snapshot of the latency histograms of the [`BufferPoolManager`], see the
+/// [module documentation](self).
+///
+/// Generated by [`BufferPoolManager::latency_histograms`]. Every histogram is cumulative since the
+/// buffer pool manager was initialized, and is empty unless
+/// [`BufferPoolManagerConfig::latency_histograms`] is enabled.
+///
+/// [`BufferPoolManagerConfig::latency_histograms`]:
+///     crate::BufferPoolManagerConfig::latency_histograms
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LatencyHistograms {
+    /// The latencies of reads that found their page in memory.
+    pub read_hit: LatencyHistogram,
+
+    /// The latencies of reads that had to read their page from persistent storage.
+    pub read_miss: LatencyHistogram,
+
+    /// The latencies of acquiring write guards.
+    pub write_guard: LatencyHistogram,
+
+    /// The latencies of writing back runs of dirty pages during eviction.
+    pub eviction_write: LatencyHistogram,
+}
+
+impl LatencyHistograms {
+    /// Gets every histogram along with the name of its operation.
+    fn operations(&self) -> [(&'static str, &LatencyHistogram); 4] {
+        [
+            ("read_hit", &self.read_hit),
+            ("read_miss", &self.read_miss),
+            ("write_guard", &self.write_guard),
+            ("eviction_write", &self.eviction_write),
+        ]
+    }
+
+    /// Formats every histogram in the Prometheus text exposition format.
+    ///
+    /// The histograms are exported as a single `async_bpm_page_latency_seconds` metric, with an
+    /// `operation` label of `read_hit`, `read_miss`, `write_guard`, or `eviction_write`. Its
+    /// buckets are bounded by every power of two nanoseconds from 1.024µs to about 17s, so that
+    /// every snapshot has the same buckets. Since every bound is also a bound of the underlying
+    /// buckets, the counts are exact, except that a bucket does not count latencies that are
+    /// exactly as long as its bound.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP {PROMETHEUS_METRIC} The latency of page operations of the buffer pool manager."
+        );
+        let _ = writeln!(out, "# TYPE {PROMETHEUS_METRIC} histogram");
+
+        for (operation, histogram) in self.operations() {
+            for exponent in PROMETHEUS_BOUNDS {
+                let bound = Duration::from_nanos(1 << exponent).as_secs_f64();
+                let _ = writeln!(
+                    out,
+                    "{PROMETHEUS_METRIC}_bucket{{operation=\"{operation}\",le=\"{bound}\"}} {}",
+                    histogram.count_below_power_of_two(exponent)
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{PROMETHEUS_METRIC}_bucket{{operation=\"{operation}\",le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "{PROMETHEUS_METRIC}_sum{{operation=\"{operation}\"}} {}",
+                histogram.sum().as_secs_f64()
+            );
+            let _ = writeln!(
+                out,
+                "{PROMETHEUS_METRIC}_count{{operation=\"{operation}\"}} {}",
+                histogram.count
+            );
+        }
+
+        out
+    }
+}
+
+/// The kinds of operations that are recorded in the [`LatencyHistograms`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LatencyKind {
+    /// See [`LatencyHistograms::read_hit`].
+    ReadHit,
+
+    /// See [`LatencyHistograms::read_miss`].
+    ReadMiss,
+
+    /// See [`LatencyHistograms::write_guard`].
+    WriteGuard,
+
+    /// See [`LatencyHistograms::eviction_write`].
+    EvictionWrite,
+}
+
+/// The shared buckets behind a [`LatencyHistogram`].
+#[derive(Debug)]
+struct AtomicHistogram {
+    /// See [`LatencyHistogram::counts`].
+    counts: Box<[AtomicU64]>,
+
+    /// See [`LatencyHistogram::sum`].
+    sum: AtomicU64,
+
+    /// See [`LatencyHistogram::max`].
+    max: AtomicU64,
+}
+
+impl Default for AtomicHistogram {
+    fn default() -> Self {
+        Self {
+            counts: (0..NUM_BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+}
+
+impl AtomicHistogram {
+    /// Records a latency.
+    fn record(&self, latency: Duration) {
+        let nanos = u64::try_from(latency.as_nanos()).map_or(MAX_NANOS, |n| n.min(MAX_NANOS));
+
+        self.counts[bucket_index(nanos)].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(nanos, Ordering::Relaxed);
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    /// Takes a snapshot of the histogram.
+    fn snapshot(&self) -> LatencyHistogram {
+        let counts: Vec<u64> = self
+            .counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect();
+
+        LatencyHistogram {
+            count: counts.iter().sum(),
+            counts,
+            sum: self.sum.load(Ordering::Relaxed),
+            max: self.max.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The shared histograms behind [`LatencyHistograms`].
+#[derive(Debug)]
+pub(crate) struct LatencyRecorder {
+    /// Whether latencies are recorded at all.
+    enabled: bool,
+
+    /// See [`LatencyHistograms::read_hit`].
+    read_hit: AtomicHistogram,
+
+    /// See [`LatencyHistograms::read_miss`].
+    read_miss: AtomicHistogram,
+
+    /// See [`LatencyHistograms::write_guard`].
+    write_guard: AtomicHistogram,
+
+    /// See [`LatencyHistograms::eviction_write`].
+    eviction_write: AtomicHistogram,
+}
+
+impl LatencyRecorder {
+    /// Creates empty histograms, which only record latencies if `enabled` is set.
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            read_hit: AtomicHistogram::default(),
+            read_miss: AtomicHistogram::default(),
+            write_guard: AtomicHistogram::default(),
+            eviction_write: AtomicHistogram::default(),
+        }
+    }
+
+    /// Gets the time that an operation starts at, or `None` if latencies are not recorded, which
+    /// avoids reading the clock.
+    pub(crate) fn start(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    /// Records the latency of an operation of the given kind that started at `start`, if it was
+    /// timed.
+    pub(crate) fn record(&self, kind: LatencyKind, start: Option<Instant>) {
+        let Some(start) = start else {
+            return;
+        };
+
+        let histogram = match kind {
+            LatencyKind::ReadHit => &self.read_hit,
+            LatencyKind::ReadMiss => &self.read_miss,
+            LatencyKind::WriteGuard => &self.write_guard,
+            LatencyKind::EvictionWrite => &self.eviction_write,
+        };
+        histogram.record(start.elapsed());
+    }
+
+    /// Takes a snapshot of every histogram.
+    fn snapshot(&self) -> LatencyHistograms {
+        LatencyHistograms {
+            read_hit: self.read_hit.snapshot(),
+            read_miss: self.read_miss.snapshot(),
+            write_guard: self.write_guard.snapshot(),
+            eviction_write: self.eviction_write.snapshot(),
+        }
+    }
+}
+
+impl BufferPoolManager {
+    /// Gets a snapshot of the latency histograms of the buffer pool manager.
+    ///
+    /// Every histogram is empty unless [`BufferPoolManagerConfig::latency_histograms`] is enabled.
+    /// Use [`LatencyHistograms::to_prometheus`] to export them to a Prometheus scrape endpoint.
+    ///
+    /// [`BufferPoolManagerConfig::latency_histograms`]:
+    ///     crate::BufferPoolManagerConfig::latency_histograms
+    pub fn latency_histograms(&self) -> LatencyHistograms {
+        self.latencies.snapshot()
+    }
+}
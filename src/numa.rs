@@ -0,0 +1,179 @@
+//! This module contains the NUMA-aware allocation mode of the buffer pool manager.
+//!
+//! On machines with several NUMA nodes (for example, multi-socket servers), memory that is attached
+//! to a different node than the CPU accessing it is noticeably slower. When NUMA-aware allocation
+//! is enabled with [`BufferPoolManagerConfig::numa_aware`], every frame group's arena is bound to
+//! a single NUMA node (round-robin across all online nodes), and threads prefer to take free frames
+//! from the frame groups that are local to the node they are currently running on.
+//!
+//! The topology is read from `/sys/devices/system/node`, and memory is bound with the `mbind`
+//! system call, so no external NUMA library is required.
+//!
+//! [`BufferPoolManagerConfig::numa_aware`]: crate::BufferPoolManagerConfig::numa_aware
+
+use std::fs;
+use std::io::{Error, Result};
+use std::ptr;
+
+/// The directory that describes the NUMA nodes of the machine.
+const NODE_DIR: &str = "/sys/devices/system/node";
+
+/// The `mbind` mode that restricts memory allocation to the given nodes.
+const MPOL_BIND: libc::c_int = 2;
+
+/// The `mbind` flag that moves pages that were already allocated elsewhere.
+const MPOL_MF_MOVE: libc::c_uint = 1 << 1;
+
+/// The NUMA nodes of the machine, along with the frame groups that are bound to each node.
+#[derive(Debug)]
+pub(crate) struct NumaLayout {
+    /// The IDs of every online NUMA node, in ascending order.
+    nodes: Vec<usize>,
+
+    /// The NUMA node of every CPU, indexed by CPU number.
+    cpu_nodes: Vec<Option<usize>>,
+
+    /// The IDs of the frame groups that are bound to every node, in the same order as `nodes`.
+    groups: Vec<Vec<usize>>,
+}
+
+impl NumaLayout {
+    /// Reads the NUMA topology of the machine and assigns `num_groups` frame groups to its nodes
+    /// round-robin.
+    ///
+    /// Returns `None` if the machine does not expose its NUMA topology, in which case the buffer
+    /// pool falls back to regular allocation.
+    pub(crate) fn detect(num_groups: usize) -> Option<Self> {
+        let online = fs::read_to_string(format!("{NODE_DIR}/online")).ok()?;
+        let nodes = parse_list(&online)?;
+        if nodes.is_empty() {
+            return None;
+        }
+
+        let mut cpu_nodes = Vec::new();
+        for &node in &nodes {
+            let cpulist = fs::read_to_string(format!("{NODE_DIR}/node{node}/cpulist")).ok()?;
+            for cpu in parse_list(&cpulist)? {
+                if cpu >= cpu_nodes.len() {
+                    cpu_nodes.resize(cpu + 1, None);
+                }
+                cpu_nodes[cpu] = Some(node);
+            }
+        }
+
+        let mut groups = vec![Vec::new(); nodes.len()];
+        for group_id in 0..num_groups {
+            groups[group_id % nodes.len()].push(group_id);
+        }
+
+        Some(Self {
+            nodes,
+            cpu_nodes,
+            groups,
+        })
+    }
+
+    /// Gets the NUMA node that the given frame group is bound to.
+    pub(crate) fn node_of_group(&self, group_id: usize) -> usize {
+        self.nodes[group_id % self.nodes.len()]
+    }
+
+    /// Gets the IDs of the frame groups that are local to the NUMA node that the calling thread is
+    /// currently running on, or `None` if the node cannot be determined or has no frame groups.
+    pub(crate) fn local_groups(&self) -> Option<&[usize]> {
+        // Safety: `sched_getcpu` has no preconditions.
+        let cpu = usize::try_from(unsafe { libc::sched_getcpu() }).ok()?;
+        let node = (*self.cpu_nodes.get(cpu)?)?;
+        let index = self.nodes.iter().position(|&n| n == node)?;
+
+        let groups = &self.groups[index];
+        (!groups.is_empty()).then_some(groups.as_slice())
+    }
+}
+
+/// Parses a kernel list of ranges such as `0-3,8,10-11`.
+fn parse_list(list: &str) -> Option<Vec<usize>> {
+    let list = list.trim();
+    if list.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut values = Vec::new();
+    for range in list.split(',') {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start.parse().ok()?;
+                let end: usize = end.parse().ok()?;
+                values.extend(start..=end);
+            }
+            None => values.push(range.parse().ok()?),
+        }
+    }
+
+    Some(values)
+}
+
+/// Allocates `len` bytes of zeroed memory that is bound to the given NUMA node.
+///
+/// The memory is mapped directly from the kernel, so it is page-aligned and is only backed by
+/// physical memory (on the given node) once it is first touched. Binding the memory is best
+/// effort: if the kernel refuses to bind it (for example, because the process is not allowed to
+/// use the node), the memory is still returned, and is allocated wherever the kernel sees fit.
+///
+/// The memory must be freed with [`free_on_node`].
+///
+/// # Errors
+///
+/// Returns an error if the memory cannot be mapped.
+pub(crate) fn alloc_on_node(len: usize, node: usize) -> Result<&'static mut [u8]> {
+    // Safety: We are creating a fresh anonymous mapping, which does not alias any memory.
+    let addr = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    if addr == libc::MAP_FAILED {
+        return Err(Error::last_os_error());
+    }
+
+    let bits = libc::c_ulong::BITS as usize;
+    let mut mask: Vec<libc::c_ulong> = vec![0; node / bits + 1];
+    mask[node / bits] |= 1 << (node % bits);
+
+    // The kernel only looks at the first `maxnode - 1` bits of the mask.
+    let maxnode = mask.len() * bits + 1;
+
+    // Safety: The range is exactly the mapping we just created, and the mask holds `maxnode - 1`
+    // bits. A failure leaves the mapping untouched, which is why the result is ignored.
+    let _ = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            addr,
+            len,
+            MPOL_BIND,
+            mask.as_ptr(),
+            maxnode,
+            MPOL_MF_MOVE,
+        )
+    };
+
+    // Safety: The mapping is `len` bytes long, zeroed, and never unmapped until `free_on_node`.
+    Ok(unsafe { std::slice::from_raw_parts_mut(addr.cast::<u8>(), len) })
+}
+
+/// Frees memory that was allocated with [`alloc_on_node`].
+///
+/// # Safety
+///
+/// `base` and `len` must describe an allocation returned by [`alloc_on_node`], and no reference
+/// into the memory may be used afterwards.
+pub(crate) unsafe fn free_on_node(base: usize, len: usize) {
+    // Safety: Guaranteed by the caller.
+    let res = unsafe { libc::munmap(base as *mut libc::c_void, len) };
+    debug_assert_eq!(res, 0, "Unable to unmap a NUMA frame arena");
+}
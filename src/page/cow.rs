@@ -0,0 +1,436 @@
+//! Copy-on-write clones of pages.
+//!
+//! [`PageHandle::clone_cow`] logically duplicates a page under a new [`PageId`] without copying
+//! its data: the clone reads the frame of the page it was cloned from (its _source_) until either
+//! of them is written to. Only then does the clone get a frame of its own, with a copy of the data
+//! from before the write. This makes snapshots of pages as cheap as a few pointer updates, which is
+//! what engines that implement multi-version concurrency control on top of the buffer pool need.
+//!
+//! Every lock of a clone is taken after the lock of its source, if both are needed:
+//!
+//! - A reader of a clone only takes the read lock of its source.
+//! - A writer of a clone takes the write lock of its source (which waits for every reader of the
+//!   clone), copies the source's data into a new frame for the clone, and then takes the write
+//!   lock of the clone.
+//! - A writer of a source copies its data into a new frame for every clone before it modifies it,
+//!   while holding the write lock of the source.
+//!
+//! Cloning a clone that still shares its source's frame clones the source instead, so a source is
+//! never a clone that shares its own source's frame.
+//!
+//! Once a clone stops sharing its source's frame, it can be cloned into again (for example to take
+//! a fresh snapshot under the same [`PageId`]). Tasks may still hold a reference to the previous
+//! source while they wait for its lock, so the links of a page to its sources are only ever
+//! appended to, and every task checks that the clone still shares the frame of the source it
+//! locked.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::{BpmError, Result};
+use crate::page::{Page, PageHandle, PageId, ReadPageGuard};
+use crate::storage::Frame;
+use std::io::{Error, ErrorKind};
+use std::mem;
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tokio::sync::RwLockWriteGuard;
+
+/// A link of a copy-on-write clone to a page that it was cloned from.
+#[derive(Debug)]
+struct SourceLink {
+    /// The page that the clone was cloned from.
+    source: Arc<Page>,
+
+    /// The link to the page that the clone was cloned from after this one, if any.
+    next: OnceLock<Box<SourceLink>>,
+}
+
+/// The copy-on-write links of a [`Page`] to the page it was cloned from and to its own clones.
+#[derive(Debug, Default)]
+pub(crate) struct CowLinks {
+    /// The links to every page that this page was cloned from, oldest first, where only the last
+    /// one is its current source.
+    ///
+    /// Links are never removed, so that references to a previous source stay valid.
+    sources: OnceLock<Box<SourceLink>>,
+
+    /// Whether this page still shares the frame of its current source.
+    ///
+    /// This is only ever cleared while holding the write locks of both this page and its source
+    /// (unless this page is being removed), and is only set again by [`PageHandle::clone_cow`]
+    /// once a new source is linked.
+    shared: AtomicBool,
+
+    /// The clones that still share this page's frame.
+    clones: Mutex<Vec<Arc<Page>>>,
+}
+
+impl CowLinks {
+    /// Gets the page whose frame this page shares, if it is a clone that was not written to yet.
+    pub(crate) fn shared_source(&self) -> Option<&Arc<Page>> {
+        // A new source is linked before the page is marked as shared again.
+        if !self.is_shared() {
+            return None;
+        }
+
+        let mut link = self.sources.get()?;
+        while let Some(next) = link.next.get() {
+            link = next;
+        }
+
+        Some(&link.source)
+    }
+
+    /// Returns whether this page is a clone that still shares the frame of its source.
+    pub(crate) fn is_shared(&self) -> bool {
+        self.shared.load(Ordering::Acquire)
+    }
+
+    /// Returns whether this page is a clone that still shares the frame of `source`, which may
+    /// not be the case anymore once a task that locked `source` gets its lock.
+    pub(crate) fn shares_frame_of(&self, source: &Page) -> bool {
+        self.shared_source()
+            .is_some_and(|shared| ptr::eq(shared.as_ref(), source))
+    }
+
+    /// Links this page to a new source, whose frame it shares from now on.
+    ///
+    /// Must be called while holding the write lock of this page, which must not share the frame of
+    /// a source already.
+    fn link_source(&self, source: Arc<Page>) {
+        debug_assert!(!self.is_shared());
+
+        let mut slot = &self.sources;
+        while let Some(link) = slot.get() {
+            slot = &link.next;
+        }
+
+        let link = Box::new(SourceLink {
+            source,
+            next: OnceLock::new(),
+        });
+        if slot.set(link).is_err() {
+            unreachable!("Sources are only linked while holding the write lock of the clone");
+        }
+        self.shared.store(true, Ordering::Release);
+    }
+
+    /// Returns whether any clone still shares this page's frame.
+    pub(crate) fn has_clones(&self) -> bool {
+        !self.clones().is_empty()
+    }
+
+    /// Locks the clones that still share this page's frame.
+    fn clones(&self) -> MutexGuard<'_, Vec<Arc<Page>>> {
+        self.clones.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Page {
+    /// Gives every clone that still shares this page's frame a frame of its own, with a copy of
+    /// `frame`'s data.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`], which holds `frame`, before
+    /// the page's data is modified.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a free frame could not be found for a clone, in which case the clones
+    /// that did not get a frame still share this page's frame.
+    pub(crate) async fn detach_clones(&self, frame: &Frame) -> Result<()> {
+        let mut clones = mem::take(&mut *self.cow.clones());
+
+        while let Some(clone) = clones.pop() {
+            let mut guard = clone.lock_write().await;
+
+            // The clone may have been removed (or cloned into again) in the meantime.
+            if !clone.cow.shares_frame_of(self) {
+                continue;
+            }
+
+            if let Err(e) = copy_into(&clone, &mut guard, frame).await {
+                drop(guard);
+                clones.push(clone);
+                self.cow.clones().append(&mut clones);
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops this page from sharing the frame of its source, if it still does, because the page is
+    /// being removed.
+    pub(crate) fn unlink_source(&self) {
+        let Some(source) = self.cow.shared_source() else {
+            return;
+        };
+
+        source
+            .cow
+            .clones()
+            .retain(|clone| !ptr::eq(clone.as_ref(), self));
+        self.cow.shared.store(false, Ordering::Release);
+        let _ = BufferPoolManager::get().cow_clones.remove(&self.pid);
+    }
+
+    /// Checks that no clone still shares this page's frame, so that the page can be removed.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a clone still shares this page's frame.
+    pub(crate) fn check_no_clones(&self) -> std::io::Result<()> {
+        if self.cow.has_clones() {
+            return Err(Error::other(format!(
+                "{} is still shared by copy-on-write clones",
+                self.pid
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that this page can be read from persistent storage, which is not the case for a
+    /// clone that still shares its source's frame.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page was cloned into while it was about to be loaded, which only
+    /// happens if the target of [`PageHandle::clone_cow`] was in use.
+    pub(crate) fn check_loadable(&self) -> Result<()> {
+        if self.cow.is_shared() {
+            return Err(Error::other(format!(
+                "{} was cloned into while it was being loaded",
+                self.pid
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Gives `clone` a frame of its own with a copy of the data of its source's `frame`, which makes
+/// the clone dirty.
+///
+/// Must be called while holding the write locks of both the clone (`guard`) and its source.
+///
+/// # Errors
+///
+/// Returns an error if a free frame could not be found.
+async fn copy_into(
+    clone: &Arc<Page>,
+    guard: &mut RwLockWriteGuard<'_, Option<Frame>>,
+    frame: &Frame,
+) -> Result<()> {
+    debug_assert!(guard.is_none(), "{} shares a frame but has one", clone.pid);
+
+    let bpm = BufferPoolManager::get();
+    let mut copy = bpm.get_random_frame_group().get_free_frame().await?;
+    let none = copy.replace_page_owner(clone.clone());
+    debug_assert!(none.is_none());
+
+    copy.copy_from_slice(frame);
+    copy.set_lsn(frame.lsn());
+    copy.set_dirty();
+
+    clone.set_loaded(&copy);
+    let copy = guard.insert(copy);
+    clone.cow.shared.store(false, Ordering::Release);
+    let _ = bpm.cow_clones.remove(&clone.pid);
+
+    Ok(copy.record_access(clone)?)
+}
+
+impl PageHandle {
+    /// Creates a copy-on-write clone of this page as the page `new_pid`, and returns a handle to
+    /// the clone.
+    ///
+    /// The clone does not get a frame of its own yet: reads of the clone read this page's frame
+    /// (loading this page if it is not in memory). Once either page is written to, the clone gets
+    /// a frame of its own with a copy of the data from before the write, and the two pages go
+    /// their separate ways. Read guards of a clone that still shares this page's frame lock this
+    /// page instead of the clone. Cloning a clone that still shares its source's frame clones the
+    /// source instead.
+    ///
+    /// Whatever `new_pid` held before is discarded without being written out. Since a clone has
+    /// no data in persistent storage until it has a frame of its own,
+    /// [`BufferPoolManager::flush_all`] (and therefore [`BufferPoolManager::shutdown`]) gives
+    /// every clone that still shares a frame a frame of its own, and writes it out.
+    ///
+    /// This takes the read lock of this page, so the calling task must not hold a
+    /// [`WritePageGuard`](super::WritePageGuard) of it. Nobody else may use `new_pid` while it is
+    /// cloned into, and `new_pid` can only be cloned into again once it no longer shares the frame
+    /// of the page it was cloned from before. This page can be cloned any number of times, but it
+    /// cannot be deallocated (see [`BufferPoolManager::deallocate_page`]) or dropped along with its
+    /// data file while it has clones that still share its frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if `new_pid` is this page, an
+    /// [`AlreadyExists`](ErrorKind::AlreadyExists) error if `new_pid` still shares the frame of a
+    /// page it was cloned from or has clones of its own, a [`BpmError::PageNotFound`] error if
+    /// `new_pid` does not exist, or an error if `new_pid` is locked by a page guard.
+    pub async fn clone_cow(&self, new_pid: PageId) -> Result<PageHandle> {
+        let bpm = BufferPoolManager::get();
+        if new_pid == self.page.pid {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot clone {new_pid} into itself"),
+            )
+            .into());
+        }
+
+        let target = bpm.get_page(&new_pid)?;
+
+        // Hold the read lock of the source, so that it cannot be modified (which would give its
+        // clones frames of their own) while the clone is linked to it.
+        let (source, _source_guard) = match self.page.cow.shared_source() {
+            Some(source) => {
+                let guard = source.lock_read().await;
+                if self.page.cow.shares_frame_of(source) {
+                    (source, guard)
+                } else {
+                    // This page was written to while we waited for its source's lock.
+                    drop(guard);
+                    (&self.page, self.page.lock_read().await)
+                }
+            }
+            None => (&self.page, self.page.lock_read().await),
+        };
+        if source.pid == new_pid {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot clone {new_pid} into its own source"),
+            )
+            .into());
+        }
+
+        let Ok(mut guard) = target.page.frame.try_write() else {
+            return Err(Error::other(format!("{new_pid} is still in use")).into());
+        };
+        if target.page.is_removed() {
+            return Err(BpmError::PageNotFound(new_pid));
+        }
+        if target.page.cow.is_shared() || target.page.cow.has_clones() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{new_pid} is already linked to a copy-on-write clone"),
+            )
+            .into());
+        }
+
+        // Discard whatever the target held before.
+        if let Some(mut frame) = guard.take() {
+            target.page.set_evicted();
+            frame.clear_dirty();
+            frame.evict_page_owner();
+            frame.group().release_frame(frame).await;
+        }
+
+        target.page.cow.link_source(source.clone());
+        source.cow.clones().push(target.page.clone());
+        let _ = bpm.cow_clones.insert(new_pid);
+
+        drop(guard);
+        Ok(target)
+    }
+
+    /// Gets a read guard on the frame of this page's source, if this page is a clone that still
+    /// shares it.
+    ///
+    /// # Errors
+    ///
+    /// Raises an error if an I/O error occurs while trying to load the source into memory.
+    pub(crate) async fn read_shared(&self) -> Result<Option<ReadPageGuard<'_>>> {
+        let Some(source) = self.page.cow.shared_source() else {
+            return Ok(None);
+        };
+
+        loop {
+            let guard = source.lock_read().await;
+
+            // The clone may have been written to (or cloned into again) while we waited for its
+            // source's lock.
+            if !self.page.cow.shares_frame_of(source) {
+                return Ok(None);
+            }
+
+            if let Some(frame) = guard.deref() {
+                frame.record_access(source)?;
+                return Ok(Some(ReadPageGuard::shared(source, guard, self.page.pid)));
+            }
+            drop(guard);
+
+            // Load the source, which may be evicted again before we get its read lock. A source is
+            // never a clone that shares a frame, so this does not come back here.
+            let mut guard = source.lock_write().await;
+            self.source_handle(source).load(&mut guard).await?;
+        }
+    }
+
+    /// Gives this page a frame of its own with a copy of its source's data, if it is a clone that
+    /// still shares its source's frame.
+    ///
+    /// # Errors
+    ///
+    /// Raises an error if an I/O error occurs while trying to load the source into memory, or if
+    /// a free frame could not be found.
+    pub(crate) async fn materialize(&self) -> Result<()> {
+        let Some(source) = self.page.cow.shared_source() else {
+            return Ok(());
+        };
+
+        // The source's write lock waits for every reader of the clone.
+        let mut source_guard = source.lock_write().await;
+        if source_guard.is_none() && self.page.cow.shares_frame_of(source) {
+            self.source_handle(source).load(&mut source_guard).await?;
+        }
+
+        let mut guard = self.page.lock_write().await;
+        match source_guard.deref() {
+            Some(frame) if self.page.cow.shares_frame_of(source) => {
+                copy_into(&self.page, &mut guard, frame).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates a handle to `source` that reads it into memory on behalf of the same tenant as this
+    /// handle.
+    fn source_handle(&self, source: &Arc<Page>) -> PageHandle {
+        PageHandle {
+            page: source.clone(),
+            sm: self.sm.clone(),
+            tenant: self.tenant,
+        }
+    }
+}
+
+impl BufferPoolManager {
+    /// Gives every copy-on-write clone that still shares its source's frame a frame of its own.
+    ///
+    /// Returns the pages that could not be given a frame of their own, along with their errors.
+    pub(crate) async fn materialize_clones(&self) -> Vec<(PageId, std::io::Error)> {
+        let mut pids = Vec::new();
+        self.cow_clones.scan(|pid| pids.push(*pid));
+
+        let mut failures = Vec::new();
+        for pid in pids {
+            let res = match self.get_page(&pid) {
+                Ok(ph) => ph.materialize().await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = res {
+                failures.push((pid, e.into()));
+            }
+        }
+
+        failures
+    }
+}
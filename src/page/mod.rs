@@ -10,13 +10,30 @@
 //! [`WritePageGuard`] to access the inner buffer frame and data in either read-locked or
 //! write-locked mode.
 //!
+//! A [`PageHandle`] can also create a copy-on-write clone of its page with
+//! [`PageHandle::clone_cow`], which shares the page's frame until either page is written to.
+//!
+//! Guards can also hand out zero-copy typed views of the page's data, for any type that implements
+//! the re-exported [`zerocopy`] traits [`FromBytes`] (and [`IntoBytes`] for mutable views),
+//! [`Immutable`], and [`KnownLayout`]. A [`ReadPageGuard`] can be turned into a [`PageSnapshot`],
+//! which copies the page's data so that the page is no longer pinned.
+//!
 //! Finally, this module provides other wrapper types like [`PageId`] to facilitate easy use of the
 //! [`Page`] API.
 
+mod cow;
 mod page_guard;
 mod page_handle;
 mod pagedef;
+#[cfg(feature = "experimental")]
+mod replica;
+mod snapshot;
+mod view;
 
 pub use page_guard::*;
 pub use page_handle::*;
 pub use pagedef::*;
+#[cfg(feature = "experimental")]
+pub use replica::ReplicaReadGuard;
+pub use snapshot::PageSnapshot;
+pub use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
@@ -1,25 +1,44 @@
 //! Wrappers around `tokio`'s `RwLockReadGuard` and `RwLockWriteGuard`, dedicated for pages of data.
 
-use crate::page::PageId;
+use crate::bpm::BufferPoolManager;
+use crate::config::CachePolicy;
+use crate::error::{BpmError, Result};
+use crate::executor;
+use crate::page::{view, Page, PageId};
 use crate::storage::{Frame, StorageManager};
-use std::io::Result;
-use std::ops::{Deref, DerefMut};
+use std::ffi::c_void;
+use std::io;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut, Range};
+use std::ptr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
-/// A read guard for a [`Page`](super::Page)'s `Frame`, which pins the page's data in memory.
+/// A read guard for a [`Page`]'s `Frame`, which pins the page's data in memory.
 ///
 /// When this guard is dereferenced, it is guaranteed to point to valid and correct page data.
 ///
 /// This guard can only be dereferenced in read mode, but other tasks (potentially on different
 /// worker threads) are allowed to read from this same page.
+///
+/// The page's [pin count](Page::pin_count) includes this guard until it is dropped.
 pub struct ReadPageGuard<'a> {
+    /// The page this guard read protects.
+    page: &'a Page,
+
+    /// The ID of the page that this guard reads, which is only different from the ID of `page` if
+    /// this guard reads the frame of a copy-on-write clone's source.
+    pid: PageId,
+
     /// The `RwLock` read guard of the optional frame, that _must_ be the [`Some`] variant.
     ///
     /// The only reason that this guard protects an `Option<Frame>` instead of just a [`Frame`] is
-    /// because the [`Page`](super::Page) type may have the `None` variant.
+    /// because the [`Page`] type may have the `None` variant.
     ///
     /// However, we guarantee through invariants that a `ReadPageGuard` can only be constructed
-    /// while the [`Page`](super::Page) has ownership over a [`Frame`], and thus we can make the
+    /// while the [`Page`] has ownership over a [`Frame`], and thus we can make the
     /// assumption that this is _always_ the `Some` variant that holds an owned frame.
     guard: RwLockReadGuard<'a, Option<Frame>>,
 }
@@ -31,14 +50,140 @@ impl<'a> ReadPageGuard<'a> {
     ///
     /// This function will panic if the `RwLockReadGuard` holds a `None` instead of a `Some(frame)`,
     /// since we cannot have a page guard that points to nothing.
-    pub(crate) fn new(pid: PageId, guard: RwLockReadGuard<'a, Option<Frame>>) -> Self {
+    pub(crate) fn new(page: &'a Page, guard: RwLockReadGuard<'a, Option<Frame>>) -> Self {
         assert!(
             guard.deref().is_some(),
             "Cannot create a ReadPageGuard for {} that does not own a Frame",
-            pid
+            page.pid
         );
 
-        Self { guard }
+        BufferPoolManager::get().stats.record_read_access();
+        page.pin();
+
+        #[cfg(feature = "deadlock-detection")]
+        crate::lock_tracker::acquired(page.pid);
+
+        Self {
+            page,
+            pid: page.pid,
+            guard,
+        }
+    }
+
+    /// Creates a new `ReadPageGuard` of the page `pid`, which is a copy-on-write clone that
+    /// shares the frame of `source`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the `RwLockReadGuard` holds a `None` instead of a `Some(frame)`.
+    pub(crate) fn shared(
+        source: &'a Page,
+        guard: RwLockReadGuard<'a, Option<Frame>>,
+        pid: PageId,
+    ) -> Self {
+        let mut this = Self::new(source, guard);
+        this.pid = pid;
+        this
+    }
+
+    /// Gets the ID of the page this guard read protects.
+    pub(crate) fn pid(&self) -> PageId {
+        self.pid
+    }
+
+    /// Views the page's data as a slice of `T`, without copying.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ViewError`](crate::error::ViewError) if the page is not a whole number of `T`s,
+    /// or if the frame buffer is not aligned for `T`. Frame buffers are aligned to
+    /// [`BufferPoolManager::frame_alignment`].
+    pub fn as_slice_of<T: FromBytes + Immutable>(&self) -> io::Result<&[T]> {
+        view::slice_of(self)
+    }
+
+    /// Views the `T` at `offset` bytes into the page's data, without copying. This is intended for
+    /// reading fixed-layout headers.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ViewError`](crate::error::ViewError) if the `T` does not fit in the page at
+    /// `offset`, or if `offset` is not aligned for `T`.
+    pub fn header_at<T: FromBytes + KnownLayout + Immutable>(
+        &self,
+        offset: usize,
+    ) -> io::Result<&T> {
+        view::header_at(self, offset)
+    }
+
+    /// Gets the entire page, including the bytes reserved for metadata and the checksum.
+    ///
+    /// Dereferencing the guard only gives access to the page's
+    /// [user region](BufferPoolManager::user_region), which starts at that offset into the raw
+    /// page.
+    pub fn raw(&self) -> &[u8] {
+        match self.guard.as_ref() {
+            Some(frame) => frame,
+            None => unreachable!("ReadPageGuard somehow had no Frame"),
+        }
+    }
+
+    /// Attempts to turn this guard into a [`WritePageGuard`] without blocking.
+    ///
+    /// A read lock cannot be upgraded in place, so this releases the read lock and then tries to
+    /// take the write lock. The page stays pinned in between, so it cannot be evicted, but another
+    /// task may still take the lock in the meantime. The upgrade only succeeds if the write lock is
+    /// free and nobody modified the page in between, so the returned guard sees exactly the data
+    /// that this guard saw. Like every write guard, it marks the page dirty.
+    ///
+    /// Returns `None` if the upgrade failed, in which case the page is no longer locked, and the
+    /// caller has to get a new guard from the page's [`PageHandle`](super::PageHandle) (and read
+    /// the page again, since it may have changed). The upgrade always fails for a copy-on-write
+    /// clone that still shares its source's frame, and for a page that has such clones, since
+    /// they need a frame of their own first.
+    pub fn try_upgrade(self) -> Option<WritePageGuard<'a>> {
+        if self.pid != self.page.pid {
+            return None;
+        }
+
+        let this = ManuallyDrop::new(self);
+        let page = this.page;
+
+        // The version only changes under the write lock, so it is stable while we hold the read
+        // lock.
+        let version = page.version.load(Ordering::Acquire);
+
+        // Safety: `this` is never used or dropped again, so the read guard is moved out of it
+        // exactly once. Its pin is released below instead.
+        drop(unsafe { ptr::read(&this.guard) });
+
+        #[cfg(feature = "deadlock-detection")]
+        crate::lock_tracker::released(page.pid);
+
+        let upgraded = page
+            .frame
+            .try_write()
+            .ok()
+            .filter(|guard| guard.is_some())
+            .filter(|_| page.version.load(Ordering::Acquire) == version)
+            .filter(|_| !page.cow.has_clones());
+        #[cfg(feature = "experimental")]
+        let upgraded = upgraded.filter(|_| page.try_invalidate_replicas());
+        let upgraded = upgraded.map(|guard| WritePageGuard::new(page, guard));
+
+        // The write guard holds its own pin.
+        page.unpin();
+
+        upgraded
+    }
+}
+
+impl Drop for ReadPageGuard<'_> {
+    fn drop(&mut self) {
+        #[cfg(feature = "deadlock-detection")]
+        crate::lock_tracker::released(self.page.pid);
+
+        self.page.unpin();
     }
 }
 
@@ -50,28 +195,40 @@ impl Deref for ReadPageGuard<'_> {
             .deref()
             .as_ref()
             .expect("Somehow have a ReadPageGuard without an owned frame")
+            .data()
     }
 }
 
-/// A write guard for a [`Page`](super::Page)'s `Frame`, which pins the page's data in memory.
+/// A write guard for a [`Page`]'s `Frame`, which pins the page's data in memory.
 ///
 /// When this guard is dereferenced, it is guaranteed to point to valid and correct page data.
 ///
 /// This guard can be dereferenced in both read and write mode, and no other tasks or threads can
 /// access the page's data while a task has this guard.
+///
+/// The page's [pin count](Page::pin_count) includes this guard until it is dropped.
 pub struct WritePageGuard<'a> {
-    /// The unique page ID of the page this guard read protects.
-    pid: PageId,
+    /// The page this guard write protects.
+    page: &'a Page,
 
     /// The `RwLock` write guard of the optional frame, that _must_ be the [`Some`] variant.
     ///
     /// The only reason that this guard protects an `Option<Frame>` instead of just a [`Frame`] is
-    /// because the [`Page`](super::Page) type may have the `None` variant.
+    /// because the [`Page`] type may have the `None` variant.
     ///
     /// However, we guarantee through invariants that a `WritePageGuard` can only be constructed
-    /// while the [`Page`](super::Page) has ownership over a [`Frame`], and thus we can make the
+    /// while the [`Page`] has ownership over a [`Frame`], and thus we can make the
     /// assumption that this is _always_ the `Some` variant that holds an owned frame.
     guard: RwLockWriteGuard<'a, Option<Frame>>,
+
+    /// The dirty range of the frame from before this guard was created.
+    ///
+    /// Until [`WritePageGuard::mark_dirty_range`] is called, the guard is assumed to modify the
+    /// whole page, after which the frame's dirty range is rebuilt from this and the marked ranges.
+    prev_dirty_range: Option<Range<usize>>,
+
+    /// Whether [`WritePageGuard::mark_dirty_range`] has been called on this guard.
+    marked: bool,
 }
 
 impl<'a> WritePageGuard<'a> {
@@ -81,13 +238,146 @@ impl<'a> WritePageGuard<'a> {
     ///
     /// This function will panic if the `RwLockWriteGuard` holds a `None` instead of a
     /// `Some(frame)`, since we cannot have a page guard that points to nothing.
-    pub(crate) fn new(pid: PageId, mut guard: RwLockWriteGuard<'a, Option<Frame>>) -> Self {
-        match guard.as_mut() {
-            Some(frame) => frame.set_dirty(),
+    pub(crate) fn new(page: &'a Page, mut guard: RwLockWriteGuard<'a, Option<Frame>>) -> Self {
+        let prev_dirty_range = match guard.as_mut() {
+            Some(frame) => {
+                frame.set_dirty();
+
+                // Until told otherwise, assume that the whole page is modified.
+                let prev = frame.dirty_range();
+                frame.set_dirty_range(Some(0..frame.data().len()));
+                prev
+            }
             None => unreachable!("Cannot create a WritePageGuard that does not own a Frame"),
+        };
+
+        BufferPoolManager::get().stats.record_write_access();
+        page.pin();
+
+        // Optimistic readers must not observe the page's data while it may be modified.
+        page.begin_write();
+
+        #[cfg(feature = "deadlock-detection")]
+        crate::lock_tracker::acquired(page.pid);
+
+        Self {
+            page,
+            guard,
+            prev_dirty_range,
+            marked: false,
+        }
+    }
+
+    /// Gets the ID of the page this guard write protects.
+    pub(crate) fn pid(&self) -> PageId {
+        self.page.pid
+    }
+
+    /// Gets the frame that holds the page's data.
+    pub(crate) fn frame_mut(&mut self) -> &mut Frame {
+        match self.guard.as_mut() {
+            Some(frame) => frame,
+            None => unreachable!("WritePageGuard somehow had no Frame"),
+        }
+    }
+
+    /// Temporarily takes ownership of the frame from the guard, which must be given back with
+    /// [`WritePageGuard::restore_frame`] before the guard is used again.
+    pub(crate) fn take_frame(&mut self) -> Frame {
+        match self.guard.take() {
+            Some(frame) => frame,
+            None => unreachable!("WritePageGuard somehow had no Frame"),
+        }
+    }
+
+    /// Gives ownership of the frame back to the guard, marking it clean if its data was written
+    /// out.
+    pub(crate) fn restore_frame(&mut self, mut frame: Frame, written: bool) {
+        if written {
+            frame.clear_dirty();
+            self.prev_dirty_range = None;
         }
 
-        Self { pid, guard }
+        let none = self.guard.replace(frame);
+        debug_assert!(none.is_none());
+    }
+
+    /// Views the page's data as a slice of `T`, without copying.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ViewError`](crate::error::ViewError) if the page is not a whole number of `T`s,
+    /// or if the frame buffer is not aligned for `T`. Frame buffers are aligned to
+    /// [`BufferPoolManager::frame_alignment`].
+    pub fn as_slice_of<T: FromBytes + Immutable>(&self) -> io::Result<&[T]> {
+        view::slice_of(self)
+    }
+
+    /// Views the page's data as a mutable slice of `T`, without copying.
+    ///
+    /// Like writes through [`DerefMut`], the whole page is assumed to be modified unless
+    /// [`WritePageGuard::mark_dirty_range`] is called.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ViewError`](crate::error::ViewError) if the page is not a whole number of `T`s,
+    /// or if the frame buffer is not aligned for `T`. Frame buffers are aligned to
+    /// [`BufferPoolManager::frame_alignment`].
+    pub fn as_mut_slice_of<T: FromBytes + IntoBytes>(&mut self) -> io::Result<&mut [T]> {
+        view::slice_of_mut(self)
+    }
+
+    /// Views the `T` at `offset` bytes into the page's data, without copying. This is intended for
+    /// reading fixed-layout headers.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ViewError`](crate::error::ViewError) if the `T` does not fit in the page at
+    /// `offset`, or if `offset` is not aligned for `T`.
+    pub fn header_at<T: FromBytes + KnownLayout + Immutable>(
+        &self,
+        offset: usize,
+    ) -> io::Result<&T> {
+        view::header_at(self, offset)
+    }
+
+    /// Views the `T` at `offset` bytes into the page's data mutably, without copying. This is
+    /// intended for updating fixed-layout headers in place.
+    ///
+    /// Since a header only covers part of the page, consider calling
+    /// [`WritePageGuard::mark_dirty_range`] with the header's range.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ViewError`](crate::error::ViewError) if the `T` does not fit in the page at
+    /// `offset`, or if `offset` is not aligned for `T`.
+    pub fn header_at_mut<T: FromBytes + IntoBytes + KnownLayout>(
+        &mut self,
+        offset: usize,
+    ) -> io::Result<&mut T> {
+        view::header_at_mut(self, offset)
+    }
+
+    /// Gets the entire page, including the bytes reserved for metadata and the checksum.
+    ///
+    /// See [`ReadPageGuard::raw`].
+    pub fn raw(&self) -> &[u8] {
+        match self.guard.as_ref() {
+            Some(frame) => frame,
+            None => unreachable!("WritePageGuard somehow had no Frame"),
+        }
+    }
+
+    /// Gets the entire page mutably, including the bytes reserved for metadata and the checksum.
+    ///
+    /// This is an escape hatch for maintaining the metadata in the reserved bytes of the page (see
+    /// [`BufferPoolManagerConfig::reserved_header`]). Note that the checksum is overwritten
+    /// whenever the page is written out, and that modifications outside of the
+    /// [user region](BufferPoolManager::user_region) are never part of the page's dirty range.
+    ///
+    /// [`BufferPoolManagerConfig::reserved_header`]: crate::BufferPoolManagerConfig::reserved_header
+    pub fn raw_mut(&mut self) -> &mut [u8] {
+        self.frame_mut()
     }
 
     /// Flushes a page's data out to persistent storage.
@@ -97,25 +387,238 @@ impl<'a> WritePageGuard<'a> {
     /// This function will return an error if it is unable to complete the write operation to a
     /// file.
     pub async fn flush(&mut self) -> Result<()> {
+        let sm = StorageManager::get().create_handle()?;
+
         // Temporarily take ownership of the frame from the guard.
-        let frame = match self.guard.take() {
+        let frame = self.take_frame();
+
+        // Write the data out to persistent storage.
+        let (res, frame) = sm.write_from(self.page.pid, frame).await;
+
+        // Give ownership back to the guard, even if the write failed.
+        self.restore_frame(frame, res.is_ok());
+
+        res.map_err(|e| BpmError::at(self.page.pid, e))
+    }
+
+    /// Flushes a page's data out to persistent storage, and then waits until the data is durable.
+    ///
+    /// [`WritePageGuard::flush`] only hands the page to the operating system, so the write can
+    /// still be lost on a crash. This additionally syncs the page's file with `fdatasync`, so once
+    /// this returns `Ok`, the page's data survives a crash. This can be used to build durable
+    /// commit points.
+    ///
+    /// Note that syncing a file makes every completed write to that file durable, not only this
+    /// page's.
+    ///
+    /// If [group commit](crate::BufferPoolManagerConfig::group_commit_window) is enabled, the page
+    /// is written out and synced together with every other page that is flushed durably during
+    /// the same batch window.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it is unable to complete the write operation to a
+    /// file, or if it is unable to sync the file.
+    pub async fn flush_sync(&mut self) -> Result<()> {
+        if let Some(group_commit) = StorageManager::get().group_commit() {
+            if !self.page.pid.is_temp() {
+                let frame = self.take_frame();
+                let (res, frame) = group_commit.commit(self.page.pid, frame).await;
+                self.restore_frame(frame, res.is_ok());
+
+                return res.map_err(|e| BpmError::at(self.page.pid, e));
+            }
+        }
+
+        self.flush().await?;
+
+        StorageManager::get()
+            .create_handle()?
+            .sync_page(self.page.pid)
+            .await
+            .map_err(|e| BpmError::at(self.page.pid, e))
+    }
+
+    /// Gets the log sequence number of the latest log record that modified this page, or `0` if
+    /// none was set since the page was loaded into memory.
+    pub fn lsn(&self) -> u64 {
+        match self.guard.as_ref() {
+            Some(frame) => frame.lsn(),
+            None => unreachable!("WritePageGuard somehow had no Frame"),
+        }
+    }
+
+    /// Sets the log sequence number (LSN) of the latest log record that modified this page.
+    ///
+    /// Before the page is written to persistent storage, the registered
+    /// [`WalHook`](crate::WalHook) is asked to make the log durable up to this LSN. LSNs are
+    /// expected to increase monotonically, so this keeps the larger of the current and the given
+    /// LSN.
+    pub fn set_lsn(&mut self, lsn: u64) {
+        match self.guard.as_mut() {
+            Some(frame) => frame.set_lsn(frame.lsn().max(lsn)),
+            None => unreachable!("WritePageGuard somehow had no Frame"),
+        }
+    }
+
+    /// Marks `range` of the page's data as modified by this guard.
+    ///
+    /// By default, a write guard is assumed to modify the whole page. Once this is called, the
+    /// guard is instead assumed to only modify the ranges that were marked. The modified range of
+    /// a page is accumulated across guards until the page is written out, and is passed to
+    /// [`WalHook::before_evict_range`](crate::WalHook::before_evict_range), so that a
+    /// write-ahead log can record byte-range diffs instead of full page images.
+    ///
+    /// This is only a hint for the log: pages are always written to persistent storage in full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds of the page's data, or if its start is after its end.
+    pub fn mark_dirty_range(&mut self, range: Range<usize>) {
+        let len = self.len();
+        assert!(
+            range.start <= range.end && range.end <= len,
+            "Dirty range {range:?} is out of bounds for a page of {len} bytes"
+        );
+
+        let frame = match self.guard.as_mut() {
             Some(frame) => frame,
             None => unreachable!("WritePageGuard somehow had no Frame"),
         };
 
-        // Write the data out to persistent storage.
-        let (res, mut frame) = StorageManager::get()
-            .create_handle()?
-            .write_from(self.pid, frame)
-            .await;
-        res?;
+        // The page may have been flushed since this guard was created.
+        if !frame.is_dirty() {
+            frame.set_dirty();
+        }
 
-        frame.clear_dirty();
+        if !self.marked {
+            frame.set_dirty_range(self.prev_dirty_range.take());
+            self.marked = true;
+        }
+        frame.extend_dirty_range(range);
+    }
+
+    /// Decomposes a `WritePageGuard` into its raw parts, keeping the page write-locked and pinned
+    /// in memory.
+    ///
+    /// Returns a pointer to the page's data, the length of the page's data, and an opaque pointer
+    /// to the guard itself. This is intended for embedding the buffer pool in C or C++ engines
+    /// that need to hold a page across an FFI boundary.
+    ///
+    /// The data pointer is valid for reads and writes of the returned length until the opaque
+    /// pointer is passed back to [`WritePageGuard::from_raw_parts`] and the resulting guard is
+    /// dropped.
+    ///
+    /// Every raw guard _must_ eventually be returned via [`WritePageGuard::from_raw_parts`],
+    /// otherwise the page will stay write-locked forever. Raw guards that have not yet been
+    /// returned can be listed with [`BufferPoolManager::outstanding_raw_guards`].
+    pub fn into_raw_parts(mut self) -> (*mut u8, usize, *mut c_void) {
+        let data = self.deref_mut().as_mut_ptr();
+        let len = self.len();
+
+        let _ = BufferPoolManager::get().raw_guards.insert(self.page.pid);
+
+        let raw = Box::into_raw(Box::new(self)) as *mut c_void;
+
+        (data, len, raw)
+    }
+
+    /// Reconstructs a `WritePageGuard` from the raw parts returned by
+    /// [`WritePageGuard::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that:
+    /// - `raw` was returned by a call to [`WritePageGuard::into_raw_parts`], along with `data` and
+    ///   `len`
+    /// - `raw` has not already been passed to this function
+    /// - No pointers derived from `data` are used after the returned guard is dropped
+    /// - The returned guard does not outlive the [`Page`] it was created from, which
+    ///   is guaranteed as long as the caller holds a [`PageHandle`](super::PageHandle) to the page
+    pub unsafe fn from_raw_parts(data: *mut u8, len: usize, raw: *mut c_void) -> Self {
+        // SAFETY: The caller guarantees that `raw` came from `into_raw_parts`, which created it
+        // via `Box::into_raw` on a `WritePageGuard`.
+        let mut guard = unsafe { Box::from_raw(raw as *mut WritePageGuard<'a>) };
+
+        debug_assert_eq!(guard.deref_mut().as_mut_ptr(), data);
+        debug_assert_eq!(guard.len(), len);
+
+        let _ = BufferPoolManager::get().raw_guards.remove(&guard.page.pid);
+
+        *guard
+    }
+
+    /// Turns this guard into a [`ReadPageGuard`] on the same page, without releasing the lock in
+    /// between.
+    ///
+    /// No other writer can modify the page in between, so the read guard sees exactly the data
+    /// that was written through this guard. The page stays dirty (it is not written out), and
+    /// other readers can access the page as soon as this returns, unless the buffer pool is
+    /// configured with [`CachePolicy::WriteThrough`], in which case the page is written out like
+    /// when the guard is dropped.
+    pub fn downgrade(mut self) -> ReadPageGuard<'a> {
+        self.write_through();
+
+        let this = ManuallyDrop::new(self);
+        let page = this.page;
+
+        // Safety: `this` is never used or dropped again, so the write guard is moved out of it
+        // exactly once. Its pin is handed over to the read guard.
+        let guard = unsafe { ptr::read(&this.guard) };
+
+        page.end_write();
+
+        ReadPageGuard {
+            page,
+            pid: page.pid,
+            guard: guard.downgrade(),
+        }
+    }
+
+    /// Writes the page out if the buffer pool is configured with [`CachePolicy::WriteThrough`] and
+    /// the page is still dirty.
+    ///
+    /// The page cannot be written out by a separate task while this guard holds its lock, so this
+    /// spawns a task that writes the page out once the lock is released, unless such a task is
+    /// already waiting for the lock (which then writes out this guard's updates as well). Outside
+    /// of a runtime (for example, for guards of the [`blocking`](crate::blocking) API), there is
+    /// nothing to spawn the task on, so the page is flushed right away instead.
+    fn write_through(&mut self) {
+        if BufferPoolManager::get().cache_policy() != CachePolicy::WriteThrough {
+            return;
+        }
+
+        let Some(frame) = self.guard.as_ref().filter(|frame| frame.is_dirty()) else {
+            return;
+        };
+
+        if !executor::in_runtime() {
+            // If the write fails, the page stays dirty and is written out later.
+            let _ = crate::blocking::block_on(self.flush());
+            return;
+        }
+
+        if let Some(page) = frame.page_owner().cloned() {
+            if !page.write_through_pending.swap(true, Ordering::AcqRel) {
+                executor::spawn_local(write_through(page));
+            }
+        }
+    }
+}
 
-        // Give ownership back to the guard.
-        self.guard.replace(frame);
+impl Drop for WritePageGuard<'_> {
+    fn drop(&mut self) {
+        // The future of a flush was dropped before it gave the frame back.
+        if self.guard.is_none() {
+            *self.guard = Frame::take_interrupted(self.page);
+        }
+
+        self.write_through();
+        self.page.end_write();
+        self.page.unpin();
 
-        Ok(())
+        #[cfg(feature = "deadlock-detection")]
+        crate::lock_tracker::released(self.page.pid);
     }
 }
 
@@ -127,6 +630,7 @@ impl Deref for WritePageGuard<'_> {
             .deref()
             .as_ref()
             .expect("Somehow have a WritePageGuard without an owned frame")
+            .data()
     }
 }
 
@@ -136,5 +640,34 @@ impl DerefMut for WritePageGuard<'_> {
             .deref_mut()
             .as_mut()
             .expect("Somehow have a WritePageGuard without an owned frame")
+            .data_mut()
+    }
+}
+
+/// Writes a page out to persistent storage on behalf of a [`WritePageGuard`] that was dropped
+/// while the buffer pool is configured with [`CachePolicy::WriteThrough`].
+///
+/// If the write fails, the page stays dirty, so that it is written out once it is evicted or
+/// flushed.
+async fn write_through(page: Arc<Page>) {
+    let mut guard = page.lock_write().await;
+
+    // Every guard that is dropped from now on was acquired after us, so it schedules another write.
+    page.write_through_pending.store(false, Ordering::Release);
+
+    let Ok(sm) = StorageManager::get().create_handle() else {
+        return;
+    };
+
+    // Someone may have evicted or flushed the page before we got the lock.
+    let Some(frame) = guard.take_if(|frame| frame.is_dirty()) else {
+        return;
+    };
+
+    let (res, mut frame) = sm.write_from(page.pid, frame).await;
+    if res.is_ok() {
+        frame.clear_dirty();
     }
+
+    guard.replace(frame);
 }
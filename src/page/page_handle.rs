@@ -6,11 +6,14 @@
 //! one of the methods on [`PageHandle`].
 
 use crate::bpm::BufferPoolManager;
+use crate::error::{BpmError, Result};
+use crate::events::PageEvent;
+use crate::metrics::LatencyKind;
 use crate::page::page_guard::{ReadPageGuard, WritePageGuard};
-use crate::page::Page;
+use crate::page::{Page, PageId};
 use crate::storage::{Frame, StorageManagerHandle};
+use crate::tenant::TenantId;
 use derivative::Derivative;
-use std::io::Result;
 use std::ops::Deref;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -28,12 +31,89 @@ pub struct PageHandle {
     /// By including this field, `PageHandle` is `!Send` and `!Sync`.
     #[derivative(PartialEq = "ignore", Hash = "ignore")]
     pub(crate) sm: StorageManagerHandle,
+
+    /// The tenant that this handle reads the page into memory on behalf of, if any.
+    pub(crate) tenant: Option<TenantId>,
 }
 
 impl PageHandle {
-    /// Creates a new page handle.
+    /// Creates a new page handle that does not belong to any tenant.
     pub(crate) fn new(page: Arc<Page>, sm: StorageManagerHandle) -> Self {
-        Self { page, sm }
+        Self {
+            page,
+            sm,
+            tenant: None,
+        }
+    }
+
+    /// Gets the ID of this handle's page.
+    pub fn pid(&self) -> PageId {
+        self.page.pid
+    }
+
+    /// Gets the tenant that this handle was created for with
+    /// [`BufferPoolManager::get_page_for`], if any.
+    ///
+    /// If this handle reads the page into memory, the page's frame is charged to this tenant.
+    pub fn tenant(&self) -> Option<TenantId> {
+        self.tenant
+    }
+
+    /// Gets the number of page guards of this handle's page that currently exist, plus the number
+    /// of explicit pins.
+    ///
+    /// See [`Page::pin_count`] for more information.
+    pub fn pin_count(&self) -> usize {
+        self.page.pin_count()
+    }
+
+    /// Pins this handle's page in memory without holding a page guard, reading it into memory
+    /// first if needed.
+    ///
+    /// A pinned page is never chosen for eviction, until every call to this function has been
+    /// matched by a call to [`PageHandle::unpin`], so independent callers can pin the same page.
+    /// This is intended for pages that are accessed all the time, such as the root page of an
+    /// index, which would otherwise need to hold a guard forever to stay in memory.
+    ///
+    /// Pinned pages still take up a frame, so a buffer pool with too many pinned pages may be
+    /// unable to find a free frame for other pages.
+    ///
+    /// # Errors
+    ///
+    /// Raises an error if an I/O error occurs while trying to load the data from disk into memory.
+    pub async fn pin(&self) -> Result<()> {
+        // Pin the page while holding the read lock, so that it cannot be evicted in between.
+        let guard = self.read().await?;
+        self.page.explicit_pins.fetch_add(1, Ordering::AcqRel);
+        self.page.pin();
+        drop(guard);
+
+        Ok(())
+    }
+
+    /// Releases a pin of this handle's page that was taken with [`PageHandle::pin`].
+    ///
+    /// Once every pin has been released, the page can be evicted again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the page has not been pinned with [`PageHandle::pin`] more often than it was
+    /// unpinned.
+    pub fn unpin(&self) {
+        let pinned = self
+            .page
+            .explicit_pins
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pins| {
+                pins.checked_sub(1)
+            })
+            .is_ok();
+        assert!(
+            pinned,
+            "Tried to unpin {}, which is not pinned",
+            self.page.pid
+        );
+
+        self.page.unpin();
     }
 
     /// Gets a read guard on a logical page, which guarantees the data is in memory.
@@ -41,28 +121,93 @@ impl PageHandle {
     /// # Errors
     ///
     /// Raises an error if an I/O error occurs while trying to load the data from disk into memory.
-    pub async fn read(&self) -> Result<ReadPageGuard> {
-        // Optimization: attempt to read only if we observe that the `is_loaded` flag is set.
-        if self.page.is_loaded.load(Ordering::Acquire) {
-            let read_guard = self.page.frame.read().await;
+    pub async fn read(&self) -> Result<ReadPageGuard<'_>> {
+        let bpm = BufferPoolManager::get();
+        let start = bpm.latencies.start();
 
-            // If it is already loaded, then we're done.
+        // Optimization: attempt to read only if we observe that the `is_loaded` flag is set, unless
+        // the hint is disabled.
+        let hint = bpm.loaded_hint();
+        if !hint || self.page.is_loaded.load(Ordering::Acquire) {
+            // Fast path: if nobody holds the write lock, take the read lock without awaiting.
+            let read_guard = match self.page.frame.try_read() {
+                Ok(read_guard) => read_guard,
+                Err(_) => self.page.lock_read().await,
+            };
+
+            // If it is already loaded, then we're done. We just observed the `is_loaded` flag
+            // set, so there is no need to set it again (and dirty its cache line).
             if let Some(frame) = read_guard.deref() {
-                self.page.is_loaded.store(true, Ordering::Release);
-                frame.record_access(self.page.clone());
-                return Ok(ReadPageGuard::new(self.page.pid, read_guard));
+                frame.record_access(&self.page)?;
+                bpm.latencies.record(LatencyKind::ReadHit, start);
+                return Ok(ReadPageGuard::new(&self.page, read_guard));
             }
 
             // Otherwise someone evicted the page underneath us and we need to load the page into
             // memory with a write guard.
             drop(read_guard);
+            self.record_loaded_hint_miss(hint);
         }
 
-        let mut write_guard = self.page.frame.write().await;
+        // A copy-on-write clone reads its source's frame until either of them is written to.
+        if let Some(guard) = self.read_shared().await? {
+            bpm.latencies.record(LatencyKind::ReadHit, start);
+            return Ok(guard);
+        }
 
-        self.load(&mut write_guard).await?;
+        let mut write_guard = self.page.lock_write().await;
+
+        // Someone else may have loaded the page while we waited for its lock.
+        let kind = if self.load(&mut write_guard).await? {
+            LatencyKind::ReadMiss
+        } else {
+            LatencyKind::ReadHit
+        };
+        bpm.latencies.record(kind, start);
 
-        Ok(ReadPageGuard::new(self.page.pid, write_guard.downgrade()))
+        Ok(ReadPageGuard::new(&self.page, write_guard.downgrade()))
+    }
+
+    /// Copies `buf.len()` bytes of the page's data starting at `offset` into `buf`, without taking
+    /// the page's read lock if possible.
+    ///
+    /// If the page is in memory, its data is copied without acquiring any lock, and the copy is
+    /// validated against the page's version afterwards. If the page was modified or evicted while
+    /// it was being copied (or is not in memory, or a [`WritePageGuard`] currently exists), this
+    /// falls back to [`PageHandle::read`]. This avoids contending on the page's lock for
+    /// read-heavy workloads, at the cost of a copy.
+    ///
+    /// Note that reads that do not fall back do not update the page's eviction state, so a page
+    /// that is only ever read optimistically may be evicted even though it is read often.
+    ///
+    /// # Errors
+    ///
+    /// Raises an error if an I/O error occurs while trying to load the data from disk into memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range of `buf.len()` bytes starting at `offset` is out of bounds of the page's
+    /// data, which is [`BufferPoolManager::usable_page_size`] bytes long.
+    pub async fn optimistic_read(&self, offset: usize, buf: &mut [u8]) -> Result<()> {
+        let len = BufferPoolManager::get().usable_page_size();
+        let end = offset
+            .checked_add(buf.len())
+            .filter(|&end| end <= len)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Range of {} bytes at offset {offset} is out of bounds for a page of {len} bytes",
+                    buf.len()
+                )
+            });
+
+        if self.page.try_optimistic_read(offset, buf) {
+            return Ok(());
+        }
+
+        let guard = self.read().await?;
+        buf.copy_from_slice(&guard[offset..end]);
+
+        Ok(())
     }
 
     /// Attempts to optimistically get a read guard _without_ blocking.
@@ -73,31 +218,38 @@ impl PageHandle {
     /// # Errors
     ///
     /// Raises an error if an I/O error occurs while trying to load the data from disk into memory.
-    pub async fn try_read(&self) -> Result<Option<ReadPageGuard>> {
-        // Optimization: attempt to read only if we observe that the `is_loaded` flag is set.
-        if self.page.is_loaded.load(Ordering::Acquire) {
+    pub async fn try_read(&self) -> Result<Option<ReadPageGuard<'_>>> {
+        // Optimization: attempt to read only if we observe that the `is_loaded` flag is set, unless
+        // the hint is disabled.
+        let hint = BufferPoolManager::get().loaded_hint();
+        if !hint || self.page.is_loaded.load(Ordering::Acquire) {
             let Ok(read_guard) = self.page.frame.try_read() else {
                 return Ok(None);
             };
 
             // If it is already loaded, then we're done.
             if let Some(frame) = read_guard.deref() {
-                self.page.is_loaded.store(true, Ordering::Release);
-                frame.record_access(self.page.clone());
-                return Ok(Some(ReadPageGuard::new(self.page.pid, read_guard)));
+                frame.record_access(&self.page)?;
+                return Ok(Some(ReadPageGuard::new(&self.page, read_guard)));
             }
 
             // Otherwise someone evicted the page underneath us and we need to load the page into
             // memory with a write guard.
             drop(read_guard);
+            self.record_loaded_hint_miss(hint);
         }
 
-        let mut write_guard = self.page.frame.write().await;
+        // A copy-on-write clone reads its source's frame until either of them is written to.
+        if let Some(guard) = self.read_shared().await? {
+            return Ok(Some(guard));
+        }
+
+        let mut write_guard = self.page.lock_write().await;
 
         self.load(&mut write_guard).await?;
 
         Ok(Some(ReadPageGuard::new(
-            self.page.pid,
+            &self.page,
             write_guard.downgrade(),
         )))
     }
@@ -107,20 +259,32 @@ impl PageHandle {
     /// # Errors
     ///
     /// Raises an error if an I/O error occurs while trying to load the data from disk into memory.
-    pub async fn write(&self) -> Result<WritePageGuard> {
-        let mut write_guard = self.page.frame.write().await;
+    pub async fn write(&self) -> Result<WritePageGuard<'_>> {
+        let latencies = &BufferPoolManager::get().latencies;
+        let start = latencies.start();
 
-        // If it is already loaded, then we're done.
+        // A copy-on-write clone that still shares its source's frame gets a frame of its own.
+        self.materialize().await?;
+
+        let mut write_guard = self.page.lock_write().await;
+        #[cfg(feature = "experimental")]
+        self.page.invalidate_replicas().await;
+
+        // If it is not loaded yet, we need to load the page into memory.
         if let Some(frame) = write_guard.deref() {
             self.page.is_loaded.store(true, Ordering::Release);
-            frame.record_access(self.page.clone());
-            return Ok(WritePageGuard::new(self.page.pid, write_guard));
+            frame.record_access(&self.page)?;
+        } else {
+            self.load(&mut write_guard).await?;
         }
 
-        // Otherwise we need to load the page into memory.
-        self.load(&mut write_guard).await?;
+        // Copy-on-write clones of this page keep the data from before this write.
+        if let Some(frame) = write_guard.as_ref() {
+            self.page.detach_clones(frame).await?;
+        }
+        latencies.record(LatencyKind::WriteGuard, start);
 
-        Ok(WritePageGuard::new(self.page.pid, write_guard))
+        Ok(WritePageGuard::new(&self.page, write_guard))
     }
 
     /// Attempts to optimistically get a write guard _without_ blocking.
@@ -131,40 +295,77 @@ impl PageHandle {
     /// # Errors
     ///
     /// Raises an error if an I/O error occurs while trying to load the data from disk into memory.
-    pub async fn try_write(&self) -> Result<Option<WritePageGuard>> {
+    pub async fn try_write(&self) -> Result<Option<WritePageGuard<'_>>> {
         let Ok(mut write_guard) = self.page.frame.try_write() else {
             return Ok(None);
         };
 
+        // Copy-on-write clones need a frame of their own before the page can be written to, which
+        // may block.
+        if self.page.cow.is_shared() || self.page.cow.has_clones() {
+            return Ok(None);
+        }
+        #[cfg(feature = "experimental")]
+        if !self.page.try_invalidate_replicas() {
+            return Ok(None);
+        }
+
         // If it is already loaded, then we're done.
         if let Some(frame) = write_guard.deref() {
             self.page.is_loaded.store(true, Ordering::Release);
-            frame.record_access(self.page.clone());
-            return Ok(Some(WritePageGuard::new(self.page.pid, write_guard)));
+            frame.record_access(&self.page)?;
+            return Ok(Some(WritePageGuard::new(&self.page, write_guard)));
         }
 
         // Otherwise we need to load the page into memory.
         self.load(&mut write_guard).await?;
 
-        Ok(Some(WritePageGuard::new(self.page.pid, write_guard)))
+        Ok(Some(WritePageGuard::new(&self.page, write_guard)))
+    }
+
+    /// Records that the `is_loaded` flag was set but the page had no frame once we held its read
+    /// lock, if the flag was checked at all.
+    fn record_loaded_hint_miss(&self, hint: bool) {
+        if hint {
+            BufferPoolManager::get().stats.record_loaded_hint_miss();
+        }
     }
 
     /// Loads page data from persistent storage into a frame in memory.
     ///
+    /// Returns whether the data was read from persistent storage, which is not the case if someone
+    /// else loaded the page while we waited for its lock.
+    ///
     /// # Errors
     ///
     /// Raises an error if an I/O error occurs while trying to load the data from disk into memory.
-    async fn load(&self, guard: &mut RwLockWriteGuard<'_, Option<Frame>>) -> Result<()> {
+    pub(super) async fn load(
+        &self,
+        guard: &mut RwLockWriteGuard<'_, Option<Frame>>,
+    ) -> Result<bool> {
+        // A page that was deallocated must not come back to life through an outstanding handle.
+        if self.page.is_removed() {
+            return Err(BpmError::PageNotFound(self.page.pid));
+        }
+        self.page.check_loadable()?;
+
         // If someone else got in front of us and loaded the page for us.
         if let Some(frame) = guard.deref().deref() {
             self.page.is_loaded.store(true, Ordering::Release);
-            frame.record_access(self.page.clone());
-            return Ok(());
+            frame.record_access(&self.page)?;
+            return Ok(false);
         }
 
-        // Randomly choose a `FrameGroup` to place load this page into.
+        // A tenant that is at its quota makes room by evicting one of its own pages, and loads this
+        // page into the frame that was freed. Otherwise, randomly choose a `FrameGroup` to place
+        // load this page into.
         let bpm = BufferPoolManager::get();
-        let frame_group = bpm.get_random_frame_group();
+        bpm.stats.record_miss();
+        let frame_group = match self.tenant {
+            Some(tenant) => bpm.enforce_tenant_quota(tenant).await?,
+            None => None,
+        };
+        let frame_group = frame_group.unwrap_or_else(|| bpm.get_random_frame_group());
 
         // Wait for a free frame.
         let mut frame = frame_group.get_free_frame().await?;
@@ -172,16 +373,28 @@ impl PageHandle {
         debug_assert!(none.is_none());
 
         // Read the data in from persistent storage via the storage manager handle.
-        let (res, frame) = self.sm.read_into(self.page.pid, frame).await;
-        res?;
+        let (res, mut frame) = self.sm.read_into(self.page.pid, frame).await;
 
-        self.page.is_loaded.store(true, Ordering::Release);
-        frame.record_access(self.page.clone());
+        // If the read failed (or the page failed verification), give the frame back so that it is
+        // not leaked.
+        if let Err(e) = res {
+            frame.evict_page_owner();
+            frame.group().release_frame(frame).await;
+            return Err(BpmError::at(self.page.pid, e));
+        }
 
         // Give ownership of the frame to the actual page.
         let old: Option<Frame> = guard.replace(frame);
         debug_assert!(old.is_none());
 
-        Ok(())
+        let frame = guard.as_ref().expect("We just gave the page a frame");
+        self.page.set_loaded(frame);
+        if let Some(tenant) = self.tenant {
+            self.page.charge(tenant);
+        }
+        bpm.record_page_event(PageEvent::Load(self.page.pid));
+        frame.record_access(&self.page)?;
+
+        Ok(true)
     }
 }
@@ -1,21 +1,44 @@
 //! Definitions and types related to logical pages of data.
 
+use crate::bpm::BufferPoolManager;
+use crate::page::cow::CowLinks;
+#[cfg(feature = "experimental")]
+use crate::page::replica::ReplicaSlot;
 use crate::storage::{Frame, StorageManager};
+use crate::tenant::TenantId;
 use derivative::Derivative;
-use std::{fmt::Display, sync::atomic::AtomicBool};
-use tokio::sync::RwLock;
+use std::fmt::Display;
+use std::ptr;
+use std::sync::atomic::{self, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+#[cfg(feature = "experimental")]
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-/// The size of a buffer `Frame` / logical [`Page`] of data.
+/// The default size of a buffer `Frame` / logical [`Page`] of data.
+///
+/// The page size can be changed with
+/// [`BufferPoolManagerConfig::page_size`](crate::BufferPoolManagerConfig::page_size), and the
+/// actual page size of the buffer pool can be retrieved with
+/// [`BufferPoolManager::page_size`](crate::BufferPoolManager::page_size).
 pub const PAGE_SIZE: usize = 1 << 12;
 
+/// The alignment that every page size and every buffer frame must respect so that pages can be
+/// read and written with `O_DIRECT`.
+pub(crate) const DIRECT_IO_ALIGNMENT: usize = 512;
+
+/// The value of [`Page::tenant`] for a page whose frame is not charged to any tenant.
+const NO_TENANT: u64 = u64::MAX;
+
 /// A shared logical [`Page`] object. All access should be done through a
 /// [`PageHandle`](super::PageHandle).
+///
+/// The fields that every access of a resident page touches are laid out first, so that a read of
+/// a resident page touches as few cache lines as possible.
 #[derive(Derivative)]
 #[derivative(Debug, PartialEq, Eq, Hash)]
+#[repr(C)]
 pub struct Page {
-    /// The unique ID of this logical page of data.
-    pub(crate) pid: PageId,
-
     /// A flag representing if the page of data has been loaded into a [`Frame`] in memory.
     ///
     /// This flag is not necessarily synced to the exact status of the data, and it only exists to
@@ -31,6 +54,44 @@ pub struct Page {
     #[derivative(PartialEq = "ignore", Hash = "ignore")]
     pub(crate) is_loaded: AtomicBool,
 
+    /// The time of the latest access that updated the eviction state of this page's frame, in
+    /// microseconds as measured by [`access_time`], or `0` if the frame has not been
+    /// [`Hot`](crate::storage::EvictionState::Hot) since then.
+    ///
+    /// This is only maintained if the buffer pool manager is configured with
+    /// [`BufferPoolManagerConfig::hot_access_threshold`](crate::BufferPoolManagerConfig::hot_access_threshold).
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) recorded_at: AtomicU64,
+
+    /// The number of [`ReadPageGuard`](super::ReadPageGuard)s and
+    /// [`WritePageGuard`](super::WritePageGuard)s of this page that currently exist, plus the
+    /// number of explicit pins in `explicit_pins`.
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) pins: AtomicUsize,
+
+    /// The number of times this page was pinned with [`PageHandle::pin`](super::PageHandle::pin)
+    /// and not unpinned yet.
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) explicit_pins: AtomicUsize,
+
+    /// The version of this page's data, which is odd while the data may be modified (or the page
+    /// is being evicted) and is incremented again once the modification is done.
+    ///
+    /// Together with [`Page::data`], this is a sequence lock that lets readers copy the page's data
+    /// without taking the read lock on [`Page::frame`] (see
+    /// [`PageHandle::optimistic_read`](super::PageHandle::optimistic_read)).
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) version: AtomicU64,
+
+    /// A pointer to the data of the [`Frame`] that this page is loaded into, or null if the page is
+    /// not loaded.
+    ///
+    /// Frame memory is never freed while the buffer pool is running, so this pointer is always
+    /// safe to read from, even if the page was evicted after the pointer was loaded. Whether the
+    /// data that was read is still this page's data must be validated with [`Page::version`].
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) data: AtomicPtr<u8>,
+
     /// An optional pointer to a buffer [`Frame`], protected by a [`RwLock`].
     ///
     /// Either a page's data is in a [`Frame`] in memory, or it is only stored on persistent
@@ -40,9 +101,321 @@ pub struct Page {
     /// tasks can access the optional frame with proper synchronization.
     #[derivative(PartialEq = "ignore", Hash = "ignore")]
     pub(crate) frame: RwLock<Option<Frame>>,
+
+    /// The replicas of this page's data, if it was [replicated](super::PageHandle::replicate).
+    #[cfg(feature = "experimental")]
+    #[derivative(Debug = "ignore", PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) replicas: OnceLock<Box<[ReplicaSlot]>>,
+
+    /// The links of this page to the page it was cloned from and to its own clones, see
+    /// [`PageHandle::clone_cow`](super::PageHandle::clone_cow).
+    #[derivative(Debug = "ignore", PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) cow: CowLinks,
+
+    /// The raw ID of the tenant that this page's frame is charged to, or [`NO_TENANT`] if the page
+    /// is not loaded or its frame is not charged to any tenant.
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) tenant: AtomicU64,
+
+    /// Whether this page was deallocated (or dropped, if it is a temporary page), in which case
+    /// every handle to it fails with a
+    /// [`BpmError::PageNotFound`](crate::error::BpmError::PageNotFound) error instead of loading
+    /// it again.
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) removed: AtomicBool,
+
+    /// Whether a write of this page is already scheduled on behalf of a
+    /// [`WritePageGuard`](super::WritePageGuard) that was dropped while the buffer pool is
+    /// configured with [`CachePolicy::WriteThrough`](crate::CachePolicy::WriteThrough).
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub(crate) write_through_pending: AtomicBool,
+
+    /// The unique ID of this logical page of data.
+    pub(crate) pid: PageId,
+}
+
+impl Page {
+    /// Creates a new `Page` that is not loaded into memory.
+    pub(crate) fn new(pid: PageId) -> Self {
+        Self {
+            is_loaded: AtomicBool::new(false),
+            recorded_at: AtomicU64::new(0),
+            pins: AtomicUsize::new(0),
+            explicit_pins: AtomicUsize::new(0),
+            version: AtomicU64::new(0),
+            data: AtomicPtr::new(ptr::null_mut()),
+            frame: RwLock::new(None),
+            #[cfg(feature = "experimental")]
+            replicas: OnceLock::new(),
+            cow: CowLinks::default(),
+            tenant: AtomicU64::new(NO_TENANT),
+            removed: AtomicBool::new(false),
+            write_through_pending: AtomicBool::new(false),
+            pid,
+        }
+    }
+
+    /// Gets the number of page guards of this page that currently exist, plus the number of
+    /// explicit pins (see [`PageHandle::pin`](super::PageHandle::pin)).
+    ///
+    /// A page with a non-zero pin count is never chosen for eviction. Since other tasks may create
+    /// or drop guards at any time, the returned value is only a snapshot, intended for
+    /// diagnostics.
+    pub fn pin_count(&self) -> usize {
+        self.pins.load(Ordering::Acquire)
+    }
+
+    /// Increments the pin count of this page when a page guard is created.
+    pub(crate) fn pin(&self) {
+        self.pins.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Decrements the pin count of this page when a page guard is dropped.
+    pub(crate) fn unpin(&self) {
+        let prev = self.pins.fetch_sub(1, Ordering::AcqRel);
+        debug_assert_ne!(
+            prev, 0,
+            "Unpinned {} more often than it was pinned",
+            self.pid
+        );
+    }
+
+    /// Marks this page as loaded into `frame`, which makes the frame's data visible to optimistic
+    /// readers.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`], after the page's data has
+    /// been read into `frame`.
+    pub(crate) fn set_loaded(&self, frame: &Frame) {
+        self.data
+            .store(frame.data().as_ptr().cast_mut(), Ordering::Release);
+        self.is_loaded.store(true, Ordering::Release);
+    }
+
+    /// Marks this page as evicted from its frame, which invalidates every optimistic read that is
+    /// in progress.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`].
+    pub(crate) fn set_evicted(&self) {
+        self.begin_write();
+        self.data.store(ptr::null_mut(), Ordering::Release);
+        self.is_loaded.store(false, Ordering::Release);
+        self.end_write();
+    }
+
+    /// Takes the read lock on this page's frame.
+    ///
+    /// With the `deadlock-detection` feature, a contended wait is recorded by the lock tracker.
+    pub(crate) async fn lock_read(&self) -> RwLockReadGuard<'_, Option<Frame>> {
+        let lock = self.frame.read();
+        #[cfg(feature = "deadlock-detection")]
+        let lock = crate::lock_tracker::wait_for(self.pid, lock);
+        lock.await
+    }
+
+    /// Takes the write lock on this page's frame.
+    ///
+    /// With the `deadlock-detection` feature, a contended wait is recorded by the lock tracker.
+    pub(crate) async fn lock_write(&self) -> RwLockWriteGuard<'_, Option<Frame>> {
+        let lock = self.frame.write();
+        #[cfg(feature = "deadlock-detection")]
+        let lock = crate::lock_tracker::wait_for(self.pid, lock);
+        lock.await
+    }
+
+    /// Marks this page as removed from the buffer pool, so that outstanding handles to it can no
+    /// longer load it. A copy-on-write clone that still shares its source's frame stops sharing
+    /// it.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`], after the page was evicted.
+    pub(crate) fn set_removed(&self) {
+        self.removed.store(true, Ordering::Release);
+        self.unlink_source();
+    }
+
+    /// Checks if this page was removed from the buffer pool with [`Page::set_removed`].
+    pub(crate) fn is_removed(&self) -> bool {
+        self.removed.load(Ordering::Acquire)
+    }
+
+    /// Gets the tenant that this page's frame is charged to, if any.
+    pub(crate) fn tenant(&self) -> Option<TenantId> {
+        match self.tenant.load(Ordering::Acquire) {
+            NO_TENANT => None,
+            raw => Some(TenantId::new(raw as u32)),
+        }
+    }
+
+    /// Charges the frame that this page was just loaded into to `tenant`.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`], after the page has been
+    /// given its frame.
+    pub(crate) fn charge(&self, tenant: TenantId) {
+        let prev = self.tenant.swap(tenant.as_u32() as u64, Ordering::AcqRel);
+        debug_assert_eq!(prev, NO_TENANT, "{} was charged twice", self.pid);
+
+        BufferPoolManager::get().tenants.charge(tenant);
+    }
+
+    /// Releases the charge of this page's frame, if it was charged to a tenant, because the page
+    /// is being evicted from it.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`].
+    pub(crate) fn release_charge(&self) {
+        let raw = self.tenant.swap(NO_TENANT, Ordering::AcqRel);
+        if raw != NO_TENANT {
+            BufferPoolManager::get()
+                .tenants
+                .release(TenantId::new(raw as u32));
+        }
+    }
+
+    /// Starts a modification of this page's data, which optimistic readers must not observe.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`], and must be followed by a
+    /// call to [`Page::end_write`].
+    pub(crate) fn begin_write(&self) {
+        let prev = self.version.fetch_add(1, Ordering::Acquire);
+        debug_assert_eq!(prev % 2, 0, "{} is already being modified", self.pid);
+
+        // Order every following write of the page's data after the version increment.
+        atomic::fence(Ordering::Release);
+    }
+
+    /// Ends a modification of this page's data that was started with [`Page::begin_write`].
+    pub(crate) fn end_write(&self) {
+        let prev = self.version.fetch_add(1, Ordering::Release);
+        debug_assert_eq!(prev % 2, 1, "{} is not being modified", self.pid);
+    }
+
+    /// Attempts to copy `buf.len()` bytes of this page's data starting at `offset` into `buf`
+    /// without taking any lock, returning `false` if the page is not loaded or if the copy
+    /// conflicted with a modification of the page.
+    ///
+    /// The caller must make sure that the copy is in bounds of the page's data.
+    pub(crate) fn try_optimistic_read(&self, offset: usize, buf: &mut [u8]) -> bool {
+        let version = self.version.load(Ordering::Acquire);
+        if version % 2 != 0 {
+            return false;
+        }
+
+        let data = self.data.load(Ordering::Acquire);
+        if data.is_null() {
+            return false;
+        }
+
+        // SAFETY: Frame memory is never freed while the buffer pool is running, and the caller
+        // checked that the copy is in bounds of a frame's data. The copy may race with a write of
+        // the frame (either by a writer of this page or, if this page was evicted in the meantime,
+        // by a user of the frame's new page), in which case the copied bytes may be torn. That is
+        // detected below, and the torn bytes are never handed out.
+        unsafe {
+            ptr::copy_nonoverlapping(data.add(offset), buf.as_mut_ptr(), buf.len());
+        }
+
+        // Order the copy before the validation.
+        atomic::fence(Ordering::Acquire);
+        self.version.load(Ordering::Relaxed) == version
+    }
+
+    /// Checks if an access of this page updated its frame's eviction state less than `threshold`
+    /// ago, while the frame has stayed [`Hot`](crate::storage::EvictionState::Hot).
+    pub(crate) fn was_recorded_within(&self, threshold: Duration) -> bool {
+        let recorded_at = self.recorded_at.load(Ordering::Relaxed);
+        recorded_at != 0 && access_time().saturating_sub(recorded_at) < threshold.as_micros() as u64
+    }
+
+    /// Remembers that an access of this page just updated its frame's eviction state.
+    ///
+    /// Must be called while holding the lock on the eviction states of the frame's group.
+    pub(crate) fn set_recorded(&self) {
+        self.recorded_at.store(access_time(), Ordering::Relaxed);
+    }
+
+    /// Forgets the latest recorded access of this page, because its frame is no longer
+    /// [`Hot`](crate::storage::EvictionState::Hot).
+    ///
+    /// Must be called while holding the lock on the eviction states of the frame's group.
+    pub(crate) fn clear_recorded(&self) {
+        self.recorded_at.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Gets the current time in microseconds since an unspecified point, plus one so that it is never
+/// `0`.
+///
+/// This is read on every access of a resident page, so it uses the coarse monotonic clock, which is
+/// several times cheaper to read than [`Instant::now`](std::time::Instant::now) at the cost of a
+/// resolution of a few milliseconds.
+fn access_time() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+
+    // SAFETY: `ts` is a valid pointer to a `timespec`, and `CLOCK_MONOTONIC_COARSE` is always
+    // supported on Linux, which `io_uring` requires anyway.
+    let res = unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC_COARSE, &mut ts) };
+    debug_assert_eq!(res, 0);
+
+    ts.tv_sec as u64 * 1_000_000 + ts.tv_nsec as u64 / 1_000 + 1
+}
+
+/// The bit of a [`PageId`] that marks a temporary page.
+const TEMP_PAGE_BIT: u64 = 1 << 63;
+
+/// The number of low bits of a [`PageId`] that hold the page's number within its file. The file
+/// ID is stored in the bits above, up to the temporary page bit.
+const FILE_ID_SHIFT: u32 = 48;
+
+/// The mask of the bits of a [`PageId`] that hold the page's number within its file.
+const PAGE_NUMBER_MASK: u64 = (1 << FILE_ID_SHIFT) - 1;
+
+/// A unique identifier for a data file of the buffer pool, which holds the pages of a single
+/// relation.
+///
+/// [`FileId::DEFAULT`] identifies the database files that the buffer pool manager is configured
+/// with, which hold every page created with [`PageId::new`]. Every other data file is created
+/// with [`BufferPoolManager::create_file`](crate::BufferPoolManager::create_file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId {
+    /// Inner representation subject to change...
+    inner: u16,
+}
+
+impl FileId {
+    /// The ID of the database files that the buffer pool manager is configured with.
+    pub const DEFAULT: Self = Self { inner: 0 };
+
+    /// The largest file ID that fits into a [`PageId`].
+    pub(crate) const MAX: Self = Self {
+        inner: (TEMP_PAGE_BIT >> FILE_ID_SHIFT) as u16 - 1,
+    };
+
+    /// Creates a `FileId` from a `u16`, or returns `None` if it does not fit into a [`PageId`].
+    pub fn new(id: u16) -> Option<Self> {
+        (id <= Self::MAX.inner).then_some(Self { inner: id })
+    }
+
+    /// Returns the `FileId` as a `u16`.
+    pub fn as_u16(self) -> u16 {
+        self.inner
+    }
+}
+
+impl Display for FileId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "File {}", self.inner)
+    }
 }
 
 /// A unique identifier for a shared [`Page`].
+///
+/// Page IDs with the highest bit set are reserved for temporary pages, which are created with
+/// [`BufferPoolManager::new_temp_page`](crate::BufferPoolManager::new_temp_page).
+///
+/// Every other page ID is made up of the [`FileId`] of the data file that the page belongs to and
+/// the page's number within that file, see [`PageId::in_file`]. Page IDs created with
+/// [`PageId::new`] from a number below 2<sup>48</sup> belong to [`FileId::DEFAULT`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PageId {
     /// Inner representation subject to change...
@@ -51,7 +424,10 @@ pub struct PageId {
 
 impl Display for PageId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Page {}", self.inner)
+        match self.file_id() {
+            FileId::DEFAULT => write!(f, "Page {}", self.inner),
+            file => write!(f, "Page {} of file {}", self.page_number(), file.inner),
+        }
     }
 }
 
@@ -69,9 +445,88 @@ impl PageId {
         self.inner
     }
 
+    /// Creates the `PageId` of the page with the given number within the data file `file`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_number` is not below 2<sup>48</sup>.
+    pub fn in_file(file: FileId, page_number: u64) -> Self {
+        assert!(
+            page_number <= PAGE_NUMBER_MASK,
+            "Page number {page_number} is too large for a page of {file}"
+        );
+
+        Self {
+            inner: (file.inner as u64) << FILE_ID_SHIFT | page_number,
+        }
+    }
+
+    /// Returns the ID of the data file that this page belongs to.
+    ///
+    /// Temporary pages belong to [`FileId::DEFAULT`], even though they are never stored in it.
+    pub fn file_id(&self) -> FileId {
+        if self.is_temp() {
+            return FileId::DEFAULT;
+        }
+
+        FileId {
+            inner: (self.inner >> FILE_ID_SHIFT) as u16,
+        }
+    }
+
+    /// Returns the number of this page within its data file.
+    pub fn page_number(&self) -> u64 {
+        if self.is_temp() {
+            return self.inner & !TEMP_PAGE_BIT;
+        }
+
+        self.inner & PAGE_NUMBER_MASK
+    }
+
+    /// Creates the `PageId` of the temporary page with the given index.
+    pub(crate) fn temp(index: u64) -> Self {
+        debug_assert_eq!(index & TEMP_PAGE_BIT, 0);
+        Self {
+            inner: index | TEMP_PAGE_BIT,
+        }
+    }
+
+    /// Checks if this is the ID of a temporary page, whose data is never persisted.
+    pub fn is_temp(&self) -> bool {
+        self.inner & TEMP_PAGE_BIT != 0
+    }
+
+    /// Returns the index of the drive that this page's data is stored on.
+    ///
+    /// Temporary pages are stored in the spill file, whose index is the number of drives. The
+    /// pages of every data file other than [`FileId::DEFAULT`] are stored next to the first
+    /// database file, on drive `0`.
+    pub(crate) fn drive(&self) -> usize {
+        let num_drives = StorageManager::get_num_drives();
+        if self.is_temp() {
+            return num_drives;
+        }
+
+        (self.page_number() % self.stripe_width() as u64) as usize
+    }
+
+    /// Returns the number of files that the pages of this page's data file are striped across,
+    /// which is the number of drives for [`FileId::DEFAULT`], and `1` for every other data file.
+    pub(crate) fn stripe_width(&self) -> usize {
+        match self.file_id() {
+            FileId::DEFAULT => StorageManager::get_num_drives(),
+            _ => 1,
+        }
+    }
+
     /// Returns the offset of this page's data on persistent storage into the file it belongs to.
     pub(crate) fn offset(&self) -> u64 {
-        (self.as_u64() / StorageManager::get_num_drives() as u64) * PAGE_SIZE as u64
+        let page_size = StorageManager::get().page_size() as u64;
+        if self.is_temp() {
+            return self.page_number() * page_size;
+        }
+
+        (self.page_number() / self.stripe_width() as u64) * page_size
     }
 }
 
@@ -0,0 +1,189 @@
+//! Read-mostly replication of pages that are read by every thread.
+//!
+//! Every [`ReadPageGuard`] takes the read lock of its page, and so every reader of a page writes to
+//! the same cache line. For a page that every thread reads all the time (such as the root of a
+//! tree), that cache line bounces between cores, which limits how well reads scale at high core
+//! counts.
+//!
+//! A replicated page keeps one copy of its data per frame group, each behind its own read lock on
+//! its own cache line, and every thread reads from a single replica. Writers follow a single-writer
+//! invalidation protocol: once a writer holds the page's write lock, it takes the write lock of
+//! every replica and discards it, which waits for the readers of every replica to finish. Readers
+//! refill their replica from the page the next time they read it.
+//!
+//! Replicas live outside of the buffer pool's frames, so they never take frames away from other
+//! pages, and they are never evicted.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::{BpmError, Result};
+use crate::page::{Page, PageHandle, ReadPageGuard};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+/// The index of the next thread that reads from a replica.
+static NEXT_READER: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The index of the replica slot that this thread reads from, modulo the number of replicas.
+    static READER_INDEX: usize = NEXT_READER.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A replica of a page's data.
+///
+/// Slots are aligned to two cache lines, so that readers of different replicas never share a
+/// cache line (even with adjacent-line prefetching).
+#[derive(Debug, Default)]
+#[repr(align(128))]
+pub(crate) struct ReplicaSlot {
+    /// The replicated data, or `None` if it was invalidated and has not been read since.
+    data: RwLock<Option<Box<[u8]>>>,
+}
+
+impl Page {
+    /// Invalidates every replica of this page, waiting for every reader of the replicas.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`], before the page's data is
+    /// modified.
+    pub(crate) async fn invalidate_replicas(&self) {
+        if let Some(slots) = self.replicas.get() {
+            for slot in slots.iter() {
+                slot.data.write().await.take();
+            }
+        }
+    }
+
+    /// Attempts to invalidate every replica of this page without waiting, returning `false` if a
+    /// replica is still being read.
+    ///
+    /// Must be called while holding the write lock on [`Page::frame`], before the page's data is
+    /// modified.
+    pub(crate) fn try_invalidate_replicas(&self) -> bool {
+        let Some(slots) = self.replicas.get() else {
+            return true;
+        };
+
+        slots.iter().all(|slot| match slot.data.try_write() {
+            Ok(mut guard) => {
+                guard.take();
+                true
+            }
+            Err(_) => false,
+        })
+    }
+}
+
+impl PageHandle {
+    /// Replicates this page, with one replica per frame group, so that
+    /// [`PageHandle::read_replica`] scales with the number of reading threads.
+    ///
+    /// This is only worth it for small numbers of pages that are read by every thread and rarely
+    /// written, since every write has to wait for the readers of every replica. Replicating a page
+    /// that is already replicated does nothing.
+    pub fn replicate(&self) {
+        self.page.replicas.get_or_init(|| {
+            let replicas = BufferPoolManager::get().frame_groups().len().max(1);
+            (0..replicas).map(|_| ReplicaSlot::default()).collect()
+        });
+    }
+
+    /// Returns whether this page is replicated.
+    pub fn is_replicated(&self) -> bool {
+        self.page.replicas.get().is_some()
+    }
+
+    /// Gets a read guard on this thread's replica of the page, which is the same as
+    /// [`PageHandle::read`] if the page is not [replicated](PageHandle::replicate).
+    ///
+    /// Reads of a replica do not pin the page, do not update its eviction state, and are not
+    /// counted in the buffer pool's [stats](BufferPoolManager::stats). Like a [`ReadPageGuard`],
+    /// the guard must be dropped before this task writes to the page.
+    ///
+    /// # Errors
+    ///
+    /// Raises an error if an I/O error occurs while trying to load the data from disk into memory.
+    pub async fn read_replica(&self) -> Result<ReplicaReadGuard<'_>> {
+        let Some(slots) = self.page.replicas.get() else {
+            return Ok(ReplicaReadGuard::primary(self.read().await?));
+        };
+        let slot = &slots[READER_INDEX.with(|index| *index) % slots.len()];
+
+        // A page that was deallocated must not be read through a replica that outlived it.
+        if self.page.is_removed() {
+            return Err(BpmError::PageNotFound(self.page.pid));
+        }
+
+        // Fast path: if the replica is valid and no writer is invalidating it, read it without
+        // awaiting.
+        if let Ok(guard) = slot.data.try_read() {
+            if guard.is_some() {
+                return Ok(ReplicaReadGuard::replica(guard));
+            }
+        }
+
+        let guard = slot.data.read().await;
+        if guard.is_some() {
+            return Ok(ReplicaReadGuard::replica(guard));
+        }
+        drop(guard);
+
+        // Refill the replica. Writers take the page's lock before the replica's lock, so we must
+        // do the same, and the replica cannot be invalidated while we hold the page's read lock.
+        let primary = self.read().await?;
+        let mut guard = slot.data.write().await;
+        if guard.is_none() {
+            *guard = Some(Box::from(primary.deref()));
+        }
+
+        Ok(ReplicaReadGuard::replica(guard.downgrade()))
+    }
+}
+
+/// A read guard for a page's data that was returned by [`PageHandle::read_replica`].
+///
+/// When this guard is dereferenced, it is guaranteed to point to the page's current data, since
+/// every writer of the page waits for this guard to be dropped.
+pub struct ReplicaReadGuard<'a> {
+    /// The guard of the data that this guard reads.
+    inner: ReplicaGuardInner<'a>,
+}
+
+/// The guard of the data that a [`ReplicaReadGuard`] reads.
+enum ReplicaGuardInner<'a> {
+    /// A guard of this thread's replica of a replicated page, that _must_ hold the replica.
+    Replica(RwLockReadGuard<'a, Option<Box<[u8]>>>),
+
+    /// A guard of a page that is not replicated.
+    Primary(ReadPageGuard<'a>),
+}
+
+impl<'a> ReplicaReadGuard<'a> {
+    /// Creates a guard of a replica, which must hold the replicated data.
+    fn replica(guard: RwLockReadGuard<'a, Option<Box<[u8]>>>) -> Self {
+        debug_assert!(guard.is_some());
+        Self {
+            inner: ReplicaGuardInner::Replica(guard),
+        }
+    }
+
+    /// Creates a guard of a page that is not replicated.
+    fn primary(guard: ReadPageGuard<'a>) -> Self {
+        Self {
+            inner: ReplicaGuardInner::Primary(guard),
+        }
+    }
+}
+
+impl Deref for ReplicaReadGuard<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.inner {
+            ReplicaGuardInner::Replica(guard) => match guard.as_deref() {
+                Some(data) => data,
+                None => unreachable!("ReplicaReadGuard somehow had no replicated data"),
+            },
+            ReplicaGuardInner::Primary(guard) => guard,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Detached, read-only copies of pages.
+//!
+//! A [`ReadPageGuard`] pins its page in memory, so a reader that keeps a guard for a long time
+//! (such as an analytical scan) keeps the page's frame from being evicted for that whole time.
+//! [`ReadPageGuard::into_snapshot`] copies the page's data out of its frame and releases the
+//! guard, so that the reader keeps a consistent view of the page while the page can be written to
+//! and evicted as usual.
+
+use crate::page::{view, PageId, ReadPageGuard};
+use std::io::Result;
+use std::ops::Deref;
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+/// An immutable copy of a page's data, taken by [`ReadPageGuard::into_snapshot`].
+///
+/// A snapshot does not pin the page and holds no lock on it, so it never blocks writers or
+/// eviction. It is never updated, so it may be stale as soon as it is taken.
+///
+/// The copy is aligned to 16 bytes, which is enough for typed views of every primitive type, but
+/// may be less than the [frame alignment](crate::BufferPoolManager::frame_alignment).
+#[derive(Debug, Clone)]
+pub struct PageSnapshot {
+    /// The ID of the page that this is a snapshot of.
+    pid: PageId,
+
+    /// The length of the page's data in bytes.
+    len: usize,
+
+    /// The page's data, stored as `u128`s so that it is aligned to 16 bytes, and padded to a whole
+    /// number of them.
+    data: Box<[u128]>,
+}
+
+impl PageSnapshot {
+    /// Copies the data of the page held by `guard`.
+    fn new(guard: &ReadPageGuard<'_>) -> Self {
+        let len = guard.len();
+        let mut data = vec![0u128; len.div_ceil(size_of::<u128>())].into_boxed_slice();
+        data.as_mut_bytes()[..len].copy_from_slice(guard);
+
+        Self {
+            pid: guard.pid(),
+            len,
+            data,
+        }
+    }
+
+    /// Gets the ID of the page that this is a snapshot of.
+    pub fn pid(&self) -> PageId {
+        self.pid
+    }
+
+    /// Views the snapshot's data as a slice of `T`, without copying.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ViewError`](crate::error::ViewError) if the page is not a whole number of `T`s,
+    /// or if `T` needs an alignment of more than 16 bytes.
+    pub fn as_slice_of<T: FromBytes + Immutable>(&self) -> Result<&[T]> {
+        view::slice_of(self)
+    }
+
+    /// Views the `T` at `offset` bytes into the snapshot's data, without copying.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ViewError`](crate::error::ViewError) if the `T` does not fit in the page at
+    /// `offset`, or if `offset` is not aligned for `T`.
+    pub fn header_at<T: FromBytes + KnownLayout + Immutable>(&self, offset: usize) -> Result<&T> {
+        view::header_at(self, offset)
+    }
+}
+
+impl Deref for PageSnapshot {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.data.as_bytes()[..self.len]
+    }
+}
+
+impl ReadPageGuard<'_> {
+    /// Copies the page's data into a [`PageSnapshot`] and releases this guard.
+    ///
+    /// Once the guard is released, the page is no longer pinned, so it can be written to by other
+    /// tasks and evicted, while the snapshot keeps the data as it was when the snapshot was taken.
+    /// This is meant for readers that hold on to a page for a long time; short reads should keep
+    /// using the guard, which does not copy anything.
+    pub fn into_snapshot(self) -> PageSnapshot {
+        PageSnapshot::new(&self)
+    }
+}
@@ -0,0 +1,101 @@
+//! Zero-copy typed views of a page's data, shared by [`ReadPageGuard`](super::ReadPageGuard) and
+//! [`WritePageGuard`](super::WritePageGuard).
+//!
+//! The views are checked casts built on [`zerocopy`]: a type can only be viewed if it is
+//! [`FromBytes`] (every bit pattern is a valid value), and every view is checked against the
+//! bounds of the page and the alignment of the frame buffer before it is handed out.
+
+use crate::error::ViewError;
+use std::io::Result;
+use std::mem::{align_of, size_of};
+use zerocopy::{ConvertError, FromBytes, Immutable, IntoBytes, KnownLayout};
+
+/// Views the whole page as a slice of `T`.
+///
+/// # Errors
+///
+/// Returns a [`ViewError`] if the page is not a whole number of `T`s, or if the frame buffer is not
+/// aligned for `T`.
+pub(super) fn slice_of<T: FromBytes + Immutable>(data: &[u8]) -> Result<&[T]> {
+    let page_size = data.len();
+    <[T]>::ref_from_bytes(data).map_err(|e| match e {
+        ConvertError::Alignment(_) => misaligned::<T>(0).into(),
+        ConvertError::Size(_) => size_mismatch::<T>(page_size).into(),
+    })
+}
+
+/// Views the whole page as a mutable slice of `T`.
+///
+/// # Errors
+///
+/// Returns a [`ViewError`] if the page is not a whole number of `T`s, or if the frame buffer is not
+/// aligned for `T`.
+pub(super) fn slice_of_mut<T: FromBytes + IntoBytes>(data: &mut [u8]) -> Result<&mut [T]> {
+    let page_size = data.len();
+    <[T]>::mut_from_bytes(data).map_err(|e| match e {
+        ConvertError::Alignment(_) => misaligned::<T>(0).into(),
+        ConvertError::Size(_) => size_mismatch::<T>(page_size).into(),
+    })
+}
+
+/// Views the `T` at `offset` bytes into the page.
+///
+/// # Errors
+///
+/// Returns a [`ViewError`] if the `T` does not fit in the page at `offset`, or if it would not be
+/// aligned.
+pub(super) fn header_at<T: FromBytes + KnownLayout + Immutable>(
+    data: &[u8],
+    offset: usize,
+) -> Result<&T> {
+    let bytes = data
+        .get(offset..)
+        .and_then(|rest| rest.get(..size_of::<T>()))
+        .ok_or_else(|| out_of_bounds::<T>(offset, data.len()))?;
+
+    T::ref_from_bytes(bytes).map_err(|_| misaligned::<T>(offset).into())
+}
+
+/// Views the `T` at `offset` bytes into the page mutably.
+///
+/// # Errors
+///
+/// Returns a [`ViewError`] if the `T` does not fit in the page at `offset`, or if it would not be
+/// aligned.
+pub(super) fn header_at_mut<T: FromBytes + IntoBytes + KnownLayout>(
+    data: &mut [u8],
+    offset: usize,
+) -> Result<&mut T> {
+    let page_size = data.len();
+    let bytes = data
+        .get_mut(offset..)
+        .and_then(|rest| rest.get_mut(..size_of::<T>()))
+        .ok_or_else(|| out_of_bounds::<T>(offset, page_size))?;
+
+    T::mut_from_bytes(bytes).map_err(|_| misaligned::<T>(offset).into())
+}
+
+/// Creates the error for a view of a `T` at `offset` that does not fit in the page.
+fn out_of_bounds<T>(offset: usize, page_size: usize) -> ViewError {
+    ViewError::OutOfBounds {
+        offset,
+        size: size_of::<T>(),
+        page_size,
+    }
+}
+
+/// Creates the error for a view of a `T` at `offset` that is not aligned.
+fn misaligned<T>(offset: usize) -> ViewError {
+    ViewError::Misaligned {
+        offset,
+        align: align_of::<T>(),
+    }
+}
+
+/// Creates the error for a page that is not a whole number of `T`s.
+fn size_mismatch<T>(page_size: usize) -> ViewError {
+    ViewError::SizeMismatch {
+        page_size,
+        elem_size: size_of::<T>(),
+    }
+}
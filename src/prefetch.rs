@@ -0,0 +1,120 @@
+//! This module contains the read-ahead API of the [`BufferPoolManager`].
+//!
+//! Sequential scans know which pages they are going to need long before they need them. Instead of
+//! waiting for every page to be read in one at a time, a scan can ask the buffer pool manager to
+//! prefetch a range of pages, which submits reads for every page that is not yet in memory without
+//! waiting for them to complete. By the time the scan gets to a page, its read has most likely
+//! completed and the scan hits memory.
+//!
+//! Prefetched pages are speculative until they are accessed for the first time: they are protected
+//! from eviction for a short while (see [`BufferPoolManagerConfig::prefetch_expiry`]), and are
+//! evicted before any other page once that has passed, so that prefetches that are never used do
+//! not displace genuinely hot pages.
+//!
+//! [`BufferPoolManagerConfig::prefetch_expiry`]: crate::BufferPoolManagerConfig::prefetch_expiry
+
+use crate::bpm::BufferPoolManager;
+use crate::events::PageEvent;
+use crate::executor;
+use crate::page::{PageHandle, PageId};
+use crate::storage::FrameGroup;
+use std::io::Result;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The default of [`BufferPoolManagerConfig::prefetch_expiry`].
+///
+/// [`BufferPoolManagerConfig::prefetch_expiry`]: crate::BufferPoolManagerConfig::prefetch_expiry
+pub(crate) const DEFAULT_PREFETCH_EXPIRY: Duration = Duration::from_millis(100);
+
+impl BufferPoolManager {
+    /// Prefetches the `count` pages starting at `start` into memory in the background.
+    ///
+    /// For every page in the range that is not already in memory, this schedules a read into a free
+    /// frame on the current thread's `io_uring` instance and returns immediately, without waiting
+    /// for any of the reads to complete. Subsequent calls to [`PageHandle::read`] or
+    /// [`PageHandle::write`] on a prefetched page will then hit memory (or wait for the read that
+    /// is already in flight).
+    ///
+    /// Prefetching is best effort: it never evicts pages to make room, so it stops early once the
+    /// randomly chosen frame groups run out of free frames. Pages that are currently locked are
+    /// skipped, and read errors are ignored (they will resurface when the page is accessed).
+    ///
+    /// Returns the number of reads that were scheduled.
+    ///
+    /// This must be called from a thread started with [`BufferPoolManager::start_thread`].
+    ///
+    /// # Errors
+    ///
+    /// If this function is unable to create a [`File`](tokio_uring::fs::File), this function will
+    /// raise the I/O error in the form of [`Result`].
+    pub fn prefetch_range(&self, start: PageId, count: usize) -> Result<usize> {
+        let mut scheduled = 0;
+
+        for offset in 0..count as u64 {
+            let ph = self.get_page(&PageId::new(start.as_u64() + offset))?;
+            if ph.page.is_loaded.load(Ordering::Acquire) {
+                continue;
+            }
+
+            let group = self.get_random_frame_group();
+            if !Self::spawn_prefetch(ph, group) {
+                break;
+            }
+
+            scheduled += 1;
+        }
+
+        Ok(scheduled)
+    }
+
+    /// Spawns a task that reads a page into a free frame of a [`FrameGroup`].
+    ///
+    /// Returns `false` if the group has no free frames.
+    pub(crate) fn spawn_prefetch(ph: PageHandle, group: Arc<FrameGroup>) -> bool {
+        // Reserve the frame up front, so that the caller knows when to stop prefetching.
+        let Some(mut frame) = group.try_get_free_frame() else {
+            return false;
+        };
+
+        executor::spawn_local(async move {
+            let page = ph.page.clone();
+
+            // If someone else is using the page or already loaded it, or the page was deallocated
+            // or is a copy-on-write clone that shares its source's frame, there is nothing to do.
+            let Ok(mut guard) = page.frame.try_write() else {
+                frame.group().release_frame(frame).await;
+                return;
+            };
+            if guard.is_some() || page.is_removed() || page.cow.is_shared() {
+                drop(guard);
+                frame.group().release_frame(frame).await;
+                return;
+            }
+
+            let none = frame.replace_page_owner(page.clone());
+            debug_assert!(none.is_none());
+
+            let (res, mut frame) = ph.sm.read_into(page.pid, frame).await;
+            if res.is_err() {
+                frame.evict_page_owner();
+                frame.group().release_frame(frame).await;
+                return;
+            }
+
+            page.set_loaded(&frame);
+            guard.replace(frame);
+            BufferPoolManager::get().record_page_event(PageEvent::Load(page.pid));
+
+            // Make the frame visible to the eviction task, so that the page is evicted once it
+            // expires if it is never accessed.
+            if let Some(frame) = guard.as_ref() {
+                let expires = Instant::now() + BufferPoolManager::get().prefetch_expiry();
+                let _ = frame.record_prefetch(&page, expires);
+            }
+        });
+
+        true
+    }
+}
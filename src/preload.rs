@@ -0,0 +1,111 @@
+//! This module contains the warm-up API of the [`BufferPoolManager`], which saves the working set
+//! of the buffer pool and loads it back in after a restart.
+//!
+//! A freshly started buffer pool has no pages in memory, so every access misses until the working
+//! set has been read back in one page at a time. Instead, the working set can be saved with
+//! [`BufferPoolManager::resident_pages`] before shutting down, and read back in with
+//! [`BufferPoolManager::preload`] right after starting up, which reads the pages in batches before
+//! the workload starts.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::Result;
+use crate::page::{PageHandle, PageId};
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+
+/// The largest number of pages that [`BufferPoolManager::preload`] reads together.
+const PRELOAD_BATCH: usize = 64;
+
+impl BufferPoolManager {
+    /// Reads the given pages into memory, until the configured
+    /// [fraction](crate::BufferPoolManagerConfig::preload_fraction) of the buffer frames is
+    /// occupied.
+    ///
+    /// Pages are read in batches with [`BufferPoolManager::read_pages`], so that the reads of every
+    /// batch are submitted together, and runs of consecutive pages are read with vectored reads.
+    /// Pages that are already in memory, temporary pages, and duplicates are skipped. Pages are
+    /// loaded in the order they are given in, so the hottest pages should come first.
+    ///
+    /// Returns the number of pages that were read in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading a page, in which case the pages that
+    /// were read in before stay in memory.
+    pub async fn preload<I>(&self, pids: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = PageId>,
+    {
+        let limit = (self.num_frames() as f64 * self.preload_fraction()) as usize;
+
+        let mut seen = HashSet::new();
+        let mut pids = pids
+            .into_iter()
+            .filter(|pid| !pid.is_temp() && seen.insert(*pid));
+
+        let mut loaded = 0;
+        loop {
+            let room = limit.saturating_sub(self.num_occupied_frames());
+            if room == 0 {
+                break;
+            }
+
+            let mut batch: Vec<PageHandle> = Vec::with_capacity(PRELOAD_BATCH.min(room));
+            for pid in pids.by_ref() {
+                let ph = self.get_page(&pid)?;
+                if !ph.page.is_loaded.load(Ordering::Acquire) {
+                    batch.push(ph);
+                }
+
+                if batch.len() == batch.capacity() {
+                    break;
+                }
+            }
+
+            if batch.is_empty() {
+                break;
+            }
+
+            loaded += batch.len();
+            drop(self.read_pages(&batch).await?);
+        }
+
+        Ok(loaded)
+    }
+
+    /// Gets the IDs of every page that is currently in memory, in ascending order, except for
+    /// temporary pages.
+    ///
+    /// The result is a snapshot of the working set, which can be saved before shutting down and
+    /// given to [`BufferPoolManager::preload`] after a restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the eviction state lock of a frame group was poisoned and the buffer
+    /// pool manager is configured to propagate poisoning errors.
+    pub fn resident_pages(&self) -> Result<Vec<PageId>> {
+        let mut pids = Vec::new();
+        for group in self.active_frame_groups() {
+            let pages = group.resident_pages()?;
+            pids.extend(
+                pages
+                    .iter()
+                    .map(|page| page.pid)
+                    .filter(|pid| !pid.is_temp()),
+            );
+        }
+
+        pids.sort_unstable_by_key(|pid| pid.as_u64());
+        pids.dedup();
+
+        Ok(pids)
+    }
+
+    /// Gets the number of buffer frames that are not free.
+    fn num_occupied_frames(&self) -> usize {
+        self.active_frame_groups()
+            .iter()
+            .map(|group| group.num_frames - group.num_free_frames())
+            .sum()
+    }
+}
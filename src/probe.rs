@@ -0,0 +1,135 @@
+//! This module contains the `io_uring` latency probe.
+//!
+//! Every thread of the buffer pool manager owns a thread-local `io_uring` instance. If the thread's
+//! submission or completion processing ever stalls, every I/O operation on that thread will hang.
+//! The probe periodically submits `Nop` operations on the thread-local ring and records how long
+//! they take to complete, which lets us detect a stalled ring before user I/O hangs.
+
+use crate::bpm::BufferPoolManager;
+use crate::daemon;
+use crate::executor;
+use std::thread::{self, ThreadId};
+use tokio::task;
+use tokio::time::{Duration, Instant};
+
+/// The round-trip latency above which a single `Nop` operation is considered a stall.
+const STALL_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// The maximum amount of time we wait for a `Nop` operation to complete.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The latest measurements of the `io_uring` latency probe on a single thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingProbeReport {
+    /// The thread that the probe runs on.
+    pub thread: ThreadId,
+
+    /// The interval at which the probe submits `Nop` operations.
+    pub interval: Duration,
+
+    /// The round-trip latency of the most recent probe, or `None` if it did not complete in time.
+    pub last_latency: Option<Duration>,
+
+    /// The largest round-trip latency the probe has observed.
+    pub max_latency: Duration,
+
+    /// The total number of probes that have completed.
+    pub probes: u64,
+
+    /// The number of probes that either exceeded the stall threshold or did not complete at all.
+    pub stalls: u64,
+
+    /// The instant that the most recent probe finished.
+    pub last_probe: Instant,
+}
+
+impl RingProbeReport {
+    /// Creates an empty report for the current thread.
+    fn new(interval: Duration) -> Self {
+        Self {
+            thread: thread::current().id(),
+            interval,
+            last_latency: None,
+            max_latency: Duration::ZERO,
+            probes: 0,
+            stalls: 0,
+            last_probe: Instant::now(),
+        }
+    }
+
+    /// Returns `true` if the ring on this thread currently appears to be stalled.
+    ///
+    /// A ring is stalled if the latest probe was too slow or never completed, or if the probe
+    /// itself has not been able to run for much longer than its interval (which means that the
+    /// thread's event loop is stuck).
+    pub fn is_stalled(&self) -> bool {
+        let overdue = self.last_probe.elapsed() > 2 * self.interval + PROBE_TIMEOUT;
+        let slow = !matches!(self.last_latency, Some(latency) if latency <= STALL_THRESHOLD);
+
+        overdue || slow
+    }
+
+    /// Records the result of a single probe.
+    fn record(&mut self, latency: Option<Duration>) {
+        self.probes += 1;
+        self.last_latency = latency;
+        self.last_probe = Instant::now();
+
+        match latency {
+            Some(latency) => {
+                self.max_latency = self.max_latency.max(latency);
+                if latency > STALL_THRESHOLD {
+                    self.stalls += 1;
+                }
+            }
+            None => self.stalls += 1,
+        }
+    }
+}
+
+/// Submits a single `Nop` operation on the current thread's `io_uring` instance and returns the
+/// round-trip latency, or `None` if the operation failed or did not complete in time.
+pub(crate) async fn probe_ring() -> Option<Duration> {
+    let start = Instant::now();
+
+    match executor::timeout(PROBE_TIMEOUT, tokio_uring::no_op()).await {
+        Some(Ok(())) => Some(start.elapsed()),
+        _ => None,
+    }
+}
+
+impl BufferPoolManager {
+    /// Spawns a daemon on the current thread that submits a `Nop` operation to the thread-local
+    /// `io_uring` instance every `interval` and records the round-trip latency.
+    ///
+    /// The measurements of every thread can be retrieved with
+    /// [`BufferPoolManager::ring_probes`], and are also included in the
+    /// [`HealthReport`](crate::HealthReport).
+    pub fn spawn_ring_probe(interval: Duration) -> task::JoinHandle<()> {
+        daemon::spawn_daemon("ring-probe", move || async move {
+            let bpm = Self::get();
+            let thread = thread::current().id();
+
+            loop {
+                let latency = probe_ring().await;
+
+                bpm.ring_probes
+                    .entry(thread)
+                    .or_insert_with(|| RingProbeReport::new(interval))
+                    .get_mut()
+                    .record(latency);
+
+                executor::sleep(interval).await;
+            }
+        })
+    }
+
+    /// Gets the latest measurements of every thread's `io_uring` latency probe.
+    ///
+    /// Only threads that have called [`BufferPoolManager::spawn_ring_probe`] have a report.
+    pub fn ring_probes(&self) -> Vec<RingProbeReport> {
+        let mut reports = Vec::new();
+        self.ring_probes.scan(|_, report| reports.push(*report));
+        reports
+    }
+}
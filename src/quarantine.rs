@@ -0,0 +1,213 @@
+//! This module contains the quarantine list of pages that failed checksum verification, and
+//! [`BufferPoolManager::overwrite_corrupt`], which repairs them.
+//!
+//! Reading a corrupted page fails no matter how often it is retried, and every retry costs a full
+//! read from persistent storage. So once a page fails checksum verification, it is quarantined:
+//! every later read of it fails immediately with a [`CorruptPage`](crate::error::CorruptPage)
+//! error, until an operator repairs it with [`BufferPoolManager::overwrite_corrupt`] (for example,
+//! with an image from a backup).
+//!
+//! The quarantine list survives restarts. It lives next to the first database file, with the
+//! extension `quarantine`, and holds the ID of one quarantined page per line.
+
+use crate::bpm::BufferPoolManager;
+use crate::page::PageId;
+use crate::storage::StorageManager;
+use std::fs::{self, OpenOptions};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The persistent set of pages that failed checksum verification.
+#[derive(Debug)]
+pub(crate) struct Quarantine {
+    /// The path to the quarantine file.
+    path: PathBuf,
+
+    /// The quarantined pages, which is checked on every read.
+    pids: scc::HashSet<PageId>,
+
+    /// Serializes updates of the quarantine file.
+    file_lock: Mutex<()>,
+}
+
+impl Quarantine {
+    /// Loads the quarantine list of the database files at the given paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the quarantine file exists but cannot be read or is malformed.
+    pub(crate) fn load(paths: &[PathBuf]) -> Result<Self> {
+        let path = paths[0].with_extension("quarantine");
+        let pids = scc::HashSet::new();
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let pid = line.trim().parse::<u64>().map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "The quarantine file {} has an invalid page ID `{line}`",
+                        path.display()
+                    ),
+                )
+            })?;
+            let _ = pids.insert(PageId::new(pid));
+        }
+
+        Ok(Self {
+            path,
+            pids,
+            file_lock: Mutex::new(()),
+        })
+    }
+
+    /// Checks if the given page is quarantined.
+    pub(crate) fn contains(&self, pid: PageId) -> bool {
+        !self.pids.is_empty() && self.pids.contains(&pid)
+    }
+
+    /// Gets every quarantined page, in ascending order.
+    pub(crate) fn pids(&self) -> Vec<PageId> {
+        let mut pids = Vec::with_capacity(self.pids.len());
+        self.pids.scan(|&pid| pids.push(pid));
+        pids.sort_by_key(|pid| pid.as_u64());
+        pids
+    }
+
+    /// Quarantines the given page and durably appends it to the quarantine file.
+    ///
+    /// The page is quarantined for the lifetime of the buffer pool even if the file cannot be
+    /// updated, in which case it is no longer quarantined after a restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the quarantine file cannot be written or synced.
+    pub(crate) fn insert(&self, pid: PageId) -> Result<()> {
+        let _lock = self.file_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if self.pids.insert(pid).is_err() {
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", pid.as_u64())?;
+        file.sync_data()
+    }
+
+    /// Lifts the quarantine of the given page, if it is quarantined.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the quarantine file cannot be rewritten, in which case the page stays
+    /// quarantined.
+    pub(crate) fn remove(&self, pid: PageId) -> Result<()> {
+        let _lock = self.file_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.pids.contains(&pid) {
+            return Ok(());
+        }
+
+        let remaining: String = self
+            .pids()
+            .into_iter()
+            .filter(|&other| other != pid)
+            .map(|other| format!("{}\n", other.as_u64()))
+            .collect();
+        write_atomically(&self.path, remaining.as_bytes())?;
+
+        let _ = self.pids.remove(&pid);
+        Ok(())
+    }
+}
+
+/// Durably replaces the contents of the file at `path` with `contents`, such that a crash leaves
+/// either the old or the new contents.
+///
+/// # Errors
+///
+/// Returns an error if the temporary file cannot be written, synced, or renamed.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("quarantine.tmp");
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    fs::rename(&tmp, path)
+}
+
+impl BufferPoolManager {
+    /// Gets every page that is quarantined because it failed checksum verification, in ascending
+    /// order of page ID.
+    ///
+    /// See [`CorruptPage`](crate::error::CorruptPage) for more information.
+    pub fn quarantined_pages(&self) -> Vec<PageId> {
+        StorageManager::get().quarantine().pids()
+    }
+
+    /// Repairs a quarantined page by overwriting it with `data` on persistent storage, and lifts
+    /// its quarantine.
+    ///
+    /// `data` must hold the page's entire [usable](BufferPoolManager::usable_page_size) data, and
+    /// the page's checksum is recomputed from it. The page is then loaded into memory, so that it
+    /// can be read right away. Note that the registered [`WalHook`](crate::WalHook) is not
+    /// consulted, since the page's previous contents were lost anyway.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if the page is not quarantined
+    /// or if `data` is not exactly one page long, or an error if the page cannot be written out or
+    /// the quarantine list cannot be updated.
+    pub async fn overwrite_corrupt(&self, pid: &PageId, data: &[u8]) -> Result<()> {
+        let sm = StorageManager::get();
+        if !sm.quarantine().contains(*pid) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{pid} is not quarantined"),
+            ));
+        }
+
+        let len = self.usable_page_size();
+        if data.len() != len {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Expected {len} bytes of page data, got {}", data.len()),
+            ));
+        }
+
+        let ph = self.get_page(pid)?;
+        let mut guard = ph.page.frame.write().await;
+        #[cfg(feature = "experimental")]
+        ph.page.invalidate_replicas().await;
+
+        // A quarantined page can never be loaded, since every read of it fails.
+        debug_assert!(guard.is_none(), "{pid} is quarantined but loaded");
+
+        let mut frame = self.get_random_frame_group().get_free_frame().await?;
+        let none = frame.replace_page_owner(ph.page.clone());
+        debug_assert!(none.is_none());
+        frame.data_mut().copy_from_slice(data);
+
+        let (res, mut frame) = ph.sm.write_from(*pid, frame).await;
+        if let Err(e) = res.and_then(|()| sm.quarantine().remove(*pid)) {
+            frame.evict_page_owner();
+            frame.group().release_frame(frame).await;
+            return Err(e);
+        }
+
+        ph.page.set_loaded(&frame);
+        let frame = guard.insert(frame);
+        frame.record_access(&ph.page)
+    }
+}
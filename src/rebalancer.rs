@@ -0,0 +1,68 @@
+//! This module contains the background rebalancer, which moves free frames from frame groups that
+//! have plenty of them to frame groups that are running out.
+//!
+//! A task that needs a free frame chooses a frame group and only ever takes a frame from that
+//! group. With a skewed workload, the frames of one group may all hold pages that are pinned while
+//! other groups sit idle, in which case every task that chooses the starved group has to wait for
+//! a page to be unpinned. The rebalancer lends free frames of idle groups to starved groups, so
+//! that getting a free frame takes about as long no matter which group a task chooses.
+
+use crate::bpm::BufferPoolManager;
+use crate::daemon;
+use crate::executor;
+use std::sync::Arc;
+use tokio::task;
+use tokio::time::Duration;
+
+impl BufferPoolManager {
+    /// Spawns a daemon on the current thread that rebalances free frames across frame groups every
+    /// `interval`.
+    ///
+    /// On every pass, every frame group that is running out of free frames is topped up with free
+    /// frames from the groups that have the most of them, as long as those groups keep at least
+    /// twice as many free frames as they need to not run out themselves. A lent frame still belongs
+    /// to the group that lent it, and returns to that group once the page loaded into it is
+    /// evicted.
+    ///
+    /// The number of frames that were moved is reported in
+    /// [`PoolStats::rebalanced_frames`](crate::PoolStats::rebalanced_frames).
+    pub fn spawn_rebalancer(interval: Duration) -> task::JoinHandle<()> {
+        daemon::spawn_daemon("rebalancer", move || async move {
+            let bpm = Self::get();
+
+            loop {
+                bpm.rebalance_free_frames();
+                executor::sleep(interval).await;
+            }
+        })
+    }
+
+    /// Lends free frames of the frame groups with the most free frames to every frame group that
+    /// is running out of free frames.
+    fn rebalance_free_frames(&self) {
+        let groups = self.active_frame_groups();
+
+        for starved in groups.iter().filter(|group| group.is_under_pressure()) {
+            let mut donors: Vec<_> = groups
+                .iter()
+                .filter(|group| !Arc::ptr_eq(group, starved))
+                .collect();
+            donors.sort_by_key(|group| std::cmp::Reverse(group.num_free_frames()));
+
+            for donor in donors {
+                let needed = starved
+                    .low_water_mark()
+                    .saturating_sub(starved.num_free_frames());
+                if needed == 0 {
+                    break;
+                }
+
+                let surplus = donor
+                    .num_free_frames()
+                    .saturating_sub(2 * donor.low_water_mark());
+                let lent = donor.lend_free_frames(starved, needed.min(surplus));
+                self.stats.record_rebalanced_frames(lent);
+            }
+        }
+    }
+}
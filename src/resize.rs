@@ -0,0 +1,188 @@
+//! This module contains [`BufferPoolManager::resize`], which grows and shrinks the buffer pool
+//! while it is running.
+//!
+//! Long-running services may need to react to changes in the memory available to them, such as a
+//! lowered cgroup memory limit. Since frames are handed to `io_uring` (and possibly registered with
+//! it), the memory of every frame that the pool can ever use is reserved when the pool is
+//! initialized (see [`BufferPoolManagerConfig::max_frames`]), and the pool is resized by retiring
+//! and reactivating whole frame groups:
+//!
+//! - Shrinking the pool retires the frame groups at the end: no frame is taken from them anymore,
+//!   every page they hold is written out (if dirty) and evicted, and their memory is released to
+//!   the operating system.
+//! - Growing the pool reactivates retired frame groups, whose memory is faulted back in lazily.
+//!
+//! [`BufferPoolManagerConfig::max_frames`]: crate::BufferPoolManagerConfig::max_frames
+
+use crate::bpm::BufferPoolManager;
+use crate::error::Result;
+use crate::events::PageEvent;
+use crate::executor;
+use crate::page::Page;
+use crate::storage::{EvictionState, Frame, FrameGroup, StorageManager};
+use std::io::{self, Error, ErrorKind};
+use std::iter;
+use std::ops::Deref;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long to wait before checking again whether a retired frame group has been drained.
+const DRAIN_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+impl BufferPoolManager {
+    /// Resizes the buffer pool to hold `num_frames` buffer frames, and returns the new number of
+    /// frames.
+    ///
+    /// The pool always holds a whole number of frame groups, so `num_frames` is rounded up to the
+    /// next multiple of the frame group size (or to [`BufferPoolManager::max_frames`]). Growing the
+    /// pool takes effect immediately. Shrinking the pool writes out every dirty page of the
+    /// retired frame groups and evicts it, waiting for any page guards of those pages to be
+    /// dropped, and then releases their memory to the operating system.
+    ///
+    /// Concurrent calls are serialized.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if `num_frames` is zero or larger
+    /// than [`BufferPoolManager::max_frames`], or an error if a dirty page cannot be written out.
+    /// If shrinking fails, the frame groups stay retired, and the pages that could not be written
+    /// out stay in memory until the pool is resized again.
+    pub async fn resize(&self, num_frames: usize) -> Result<usize> {
+        if num_frames == 0 || num_frames > self.max_frames() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Cannot resize the buffer pool to {num_frames} frames, it must hold between 1 \
+                     and {} frames",
+                    self.max_frames()
+                ),
+            )
+            .into());
+        }
+
+        let _lock = self.resize_lock.lock().await;
+
+        let groups = num_frames.div_ceil(self.frame_group_size());
+        let prev = self.active_groups.swap(groups, Ordering::AcqRel);
+
+        // No frame is taken from the retired frame groups anymore, so once they are drained they
+        // stay drained.
+        for group in self.frame_groups().iter().take(prev).skip(groups) {
+            self.retire_group(group).await?;
+        }
+
+        Ok(self.num_frames())
+    }
+
+    /// Drains the given [`FrameGroup`] and releases its memory to the operating system.
+    ///
+    /// # Errors
+    ///
+    /// See [`BufferPoolManager::drain_group`].
+    async fn retire_group(&self, group: &FrameGroup) -> io::Result<()> {
+        loop {
+            self.drain_group(group).await?;
+
+            // A task that chose this frame group before it was retired may still take a frame
+            // from it, so we hold on to every frame while the memory is released. Frames that
+            // another group lent to this one in the meantime go back to their own group.
+            let (frames, lent): (Vec<Frame>, Vec<Frame>) =
+                iter::from_fn(|| group.try_get_free_frame())
+                    .partition(|frame| frame.group_id() == group.group_id);
+            for frame in lent {
+                frame.group().release_frame(frame).await;
+            }
+
+            let drained = frames.len() == group.num_frames;
+            if drained {
+                self.arenas[group.group_id].release_memory();
+            }
+
+            for frame in frames {
+                group.release_frame(frame).await;
+            }
+
+            if drained {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Writes out and evicts every page that is held by a frame of the given [`FrameGroup`], and
+    /// waits until every one of its frames is free.
+    ///
+    /// The frame group must be retired, so that no new pages are loaded into it. With the
+    /// `experimental` feature, the rebalancer may have lent some of its free frames to other
+    /// groups, so those are taken back first (otherwise pages could still be loaded into them).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a dirty page cannot be written out, or if the eviction state lock was
+    /// poisoned and the buffer pool manager is configured to propagate poisoning errors.
+    async fn drain_group(&self, group: &FrameGroup) -> io::Result<()> {
+        let sm = StorageManager::get().create_handle()?;
+
+        loop {
+            #[cfg(feature = "experimental")]
+            group.reclaim_lent_frames(self.frame_groups()).await;
+            if group.num_free_frames() >= group.num_frames {
+                return Ok(());
+            }
+
+            // Pages that are locked right now might be in the frame group, so we check them once
+            // we hold their lock.
+            let mut pages: Vec<Arc<Page>> = Vec::new();
+            self.pages.scan(|_, page| {
+                let in_group = page.frame.try_read().map_or(true, |guard| {
+                    guard
+                        .deref()
+                        .as_ref()
+                        .is_some_and(|frame| frame.group_id() == group.group_id)
+                });
+
+                if in_group {
+                    pages.push(page.clone());
+                }
+            });
+
+            for page in pages {
+                let mut guard = page.frame.write().await;
+                let Some(mut frame) = guard.take_if(|frame| frame.group_id() == group.group_id)
+                else {
+                    continue;
+                };
+
+                let dirty = frame.is_dirty();
+                if dirty {
+                    let (res, mut written) = sm.write_from(page.pid, frame).await;
+                    if let Err(e) = res {
+                        guard.replace(written);
+                        return Err(e);
+                    }
+
+                    written.clear_dirty();
+                    frame = written;
+                }
+
+                page.set_evicted();
+                frame.evict_page_owner();
+                self.record_page_event(PageEvent::Evict(page.pid));
+
+                {
+                    let index = frame.group_index();
+                    let mut eviction_guard = group.lock_eviction_states()?;
+                    eviction_guard[index] = EvictionState::Cold;
+                    eviction_guard.replacer.record_eviction(index);
+                }
+
+                self.stats.record_eviction(dirty);
+                group.release_frame(frame).await;
+            }
+
+            if group.num_free_frames() < group.num_frames {
+                executor::sleep(DRAIN_RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
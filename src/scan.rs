@@ -0,0 +1,139 @@
+//! This module contains the [`PageScan`] type, which reads a range of pages in order.
+//!
+//! A scan could get a handle to every page in a range and read them one after the other, but then
+//! it waits for every read that misses memory before it submits the next one. A [`PageScan`]
+//! instead keeps a bounded window of the pages after the current one
+//! [prefetched](BufferPoolManager::prefetch_range), so that the reads of upcoming pages overlap
+//! with the processing of the current one.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::Result;
+use crate::page::{PageHandle, PageId, ReadPageGuard};
+use std::sync::atomic::Ordering;
+
+/// The number of pages that a [`PageScan`] keeps prefetched ahead of the page it is reading, unless
+/// it is set with [`PageScan::read_ahead`].
+pub const DEFAULT_SCAN_READ_AHEAD: usize = 32;
+
+impl BufferPoolManager {
+    /// Creates a [`PageScan`] over the pages from `start` up to, but not including, `end`.
+    ///
+    /// The scan does not do anything until [`PageScan::next`] is first called. If `end` is not
+    /// after `start`, the scan is empty.
+    pub fn scan(&self, start: PageId, end: PageId) -> PageScan {
+        let start = start.as_u64();
+
+        PageScan {
+            next: start,
+            prefetched: start,
+            end: end.as_u64().max(start),
+            read_ahead: DEFAULT_SCAN_READ_AHEAD,
+            handle: None,
+        }
+    }
+}
+
+/// Reads a range of pages in order, prefetching the pages ahead of the current one.
+///
+/// Created by [`BufferPoolManager::scan`]. Every call to [`PageScan::next`] returns a read guard on
+/// the next page of the range, after making sure that the following
+/// [`read_ahead`](PageScan::read_ahead) pages are being prefetched. Prefetching is best effort
+/// (see [`BufferPoolManager::prefetch_range`]), so pages that could not be prefetched are simply
+/// read when the scan gets to them.
+///
+/// The guard returned by [`PageScan::next`] borrows the scan, so it has to be dropped before the
+/// scan can move on to the next page.
+///
+/// A scan must be used on a thread started with [`BufferPoolManager::start_thread`].
+#[derive(Debug)]
+pub struct PageScan {
+    /// The ID of the next page to read.
+    next: u64,
+
+    /// The ID of the first page after `next` that has not been considered for prefetching yet.
+    prefetched: u64,
+
+    /// The ID of the first page after the range.
+    end: u64,
+
+    /// The number of pages to keep prefetched ahead of the page that is being read.
+    read_ahead: usize,
+
+    /// The handle of the page that was read last, which the guard returned by [`PageScan::next`]
+    /// borrows.
+    handle: Option<PageHandle>,
+}
+
+impl PageScan {
+    /// Sets the number of pages to keep prefetched ahead of the page that is being read.
+    ///
+    /// A read-ahead of `0` disables prefetching, which makes the scan read one page at a time.
+    ///
+    /// By default, a scan reads ahead [`DEFAULT_SCAN_READ_AHEAD`] pages.
+    pub fn read_ahead(mut self, pages: usize) -> Self {
+        self.read_ahead = pages;
+        self
+    }
+
+    /// Gets the number of pages that the scan has not returned yet.
+    pub fn remaining(&self) -> u64 {
+        self.end - self.next
+    }
+
+    /// Reads the next page of the range, returning `None` once every page has been read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while trying to load the page into memory, or while
+    /// trying to prefetch the pages after it. The scan moves on to the next page either way, so
+    /// the caller may keep going after an error.
+    pub async fn next(&mut self) -> Option<Result<ReadPageGuard<'_>>> {
+        if self.next >= self.end {
+            return None;
+        }
+
+        let bpm = BufferPoolManager::get();
+        let pid = PageId::new(self.next);
+        self.next += 1;
+
+        // Start the reads of the upcoming pages before waiting for this one, so that they overlap.
+        if let Err(e) = self.prefetch(bpm) {
+            return Some(Err(e));
+        }
+
+        let handle = match bpm.get_page(&pid) {
+            Ok(handle) => self.handle.insert(handle),
+            Err(e) => return Some(Err(e)),
+        };
+
+        Some(handle.read().await)
+    }
+
+    /// Prefetches the pages after the page that is being read, up to the read-ahead window.
+    ///
+    /// If a page cannot be prefetched because there is no free frame, we stop and try again from
+    /// that page on the next call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this thread is unable to create a handle to a page.
+    fn prefetch(&mut self, bpm: &BufferPoolManager) -> Result<()> {
+        let window = self
+            .end
+            .min(self.next.saturating_add(self.read_ahead as u64));
+        self.prefetched = self.prefetched.max(self.next);
+
+        while self.prefetched < window {
+            let ph = bpm.get_page(&PageId::new(self.prefetched))?;
+            if !ph.page.is_loaded.load(Ordering::Acquire)
+                && !BufferPoolManager::spawn_prefetch(ph, bpm.get_random_frame_group())
+            {
+                break;
+            }
+
+            self.prefetched += 1;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,564 @@
+//! This module contains the [`PoolStats`] type, which counts the work that the
+//! [`BufferPoolManager`] has done, and the [`StatsWindow`] type, which reports that work as deltas
+//! over fixed windows of time.
+//!
+//! Every counter is cumulative since the buffer pool manager was initialized. Most monitoring loops
+//! want rates instead (for example, page accesses per second), which [`StatsWindow`] computes by
+//! subtracting consecutive snapshots.
+
+use crate::bpm::BufferPoolManager;
+use crate::executor;
+use crate::storage::StorageManager;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A snapshot of the counters of the [`BufferPoolManager`].
+///
+/// Generated by [`BufferPoolManager::stats`], or as a delta by [`StatsWindow`].
+///
+/// Every counter is maintained with relaxed atomics, so a snapshot taken while other threads are
+/// accessing pages is not necessarily consistent across counters.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PoolStats {
+    /// The number of read guards that were handed out.
+    pub read_accesses: u64,
+
+    /// The number of write guards that were handed out.
+    pub write_accesses: u64,
+
+    /// The number of page accesses that had to read the page from persistent storage.
+    pub misses: u64,
+
+    /// The number of pages that were read from persistent storage.
+    pub page_reads: u64,
+
+    /// The number of pages that were written to persistent storage.
+    pub page_writes: u64,
+
+    /// The number of pages that were evicted from memory.
+    pub evictions: u64,
+
+    /// The number of evicted pages that were dirty, and therefore had to be written back to
+    /// persistent storage before their frame could be reused.
+    pub dirty_write_backs: u64,
+
+    /// The number of batches that the dirty pages chosen by eviction passes were written back in.
+    ///
+    /// See [`BufferPoolManagerConfig::eviction_write_batch`](crate::BufferPoolManagerConfig::eviction_write_batch).
+    pub eviction_write_batches: u64,
+
+    /// The number of evictions that reclaimed the frame of a clean page that was already cooled
+    /// down, without running a cooling pass.
+    ///
+    /// A task that needs a free frame tries this before it runs a cooling pass, since a cooling
+    /// pass may have to write dirty pages back first. These evictions are also counted in
+    /// [`PoolStats::evictions`].
+    pub clean_reclaims: u64,
+
+    /// The number of times a task needed a free frame but had to wait for pages to be evicted
+    /// because there was none.
+    pub free_frame_waits: u64,
+
+    /// The number of reads that observed that a page was loaded, but found that it had been
+    /// evicted once they took its read lock, and had to retry with the write lock.
+    ///
+    /// See [`BufferPoolManagerConfig::loaded_hint`](crate::BufferPoolManagerConfig::loaded_hint).
+    pub loaded_hint_misses: u64,
+
+    /// The number of page accesses that skipped updating the page's eviction state, because the
+    /// page was accessed recently.
+    ///
+    /// See [`BufferPoolManagerConfig::hot_access_threshold`](crate::BufferPoolManagerConfig::hot_access_threshold).
+    pub skipped_access_records: u64,
+
+    /// The number of free frames that were moved from one frame group to another, because the
+    /// other group was running out of free frames.
+    ///
+    /// This is always 0 unless the `experimental` feature is enabled and a rebalancer is running.
+    /// See `BufferPoolManager::spawn_rebalancer`.
+    pub rebalanced_frames: u64,
+
+    /// The number of pages loaded by
+    /// [`BufferPoolManager::prefetch_range`](crate::BufferPoolManager::prefetch_range) that were
+    /// accessed before they expired.
+    ///
+    /// See [`BufferPoolManagerConfig::prefetch_expiry`](crate::BufferPoolManagerConfig::prefetch_expiry).
+    pub speculative_hits: u64,
+
+    /// The number of pages loaded by
+    /// [`BufferPoolManager::prefetch_range`](crate::BufferPoolManager::prefetch_range) that were
+    /// evicted after they expired without ever being accessed.
+    pub wasted_prefetches: u64,
+
+    /// The total time spent waiting for page reads to complete.
+    pub read_time: Duration,
+
+    /// The total time spent waiting for page writes to complete.
+    pub write_time: Duration,
+
+    /// The occupancy of every frame group, indexed by frame group ID.
+    ///
+    /// Unlike the counters, this describes the state of the buffer pool at the time of the
+    /// snapshot.
+    pub frame_groups: Vec<FrameGroupOccupancy>,
+}
+
+impl PoolStats {
+    /// Gets the total number of page accesses, both reads and writes.
+    pub fn accesses(&self) -> u64 {
+        self.read_accesses + self.write_accesses
+    }
+
+    /// Gets the number of page accesses that found the page in memory.
+    pub fn hits(&self) -> u64 {
+        self.accesses().saturating_sub(self.misses)
+    }
+
+    /// Gets the fraction of page accesses that found the page in memory, or `0.0` if there were
+    /// no accesses.
+    pub fn hit_rate(&self) -> f64 {
+        match self.accesses() {
+            0 => 0.0,
+            accesses => self.hits() as f64 / accesses as f64,
+        }
+    }
+
+    /// Gets the total number of I/O operations on persistent storage.
+    pub fn io_operations(&self) -> u64 {
+        self.page_reads + self.page_writes
+    }
+
+    /// Gets the fraction of prefetched pages that were accessed, out of those that were either
+    /// accessed or wasted, or `None` if no prefetched page was either.
+    pub fn prefetch_accuracy(&self) -> Option<f64> {
+        match self.speculative_hits + self.wasted_prefetches {
+            0 => None,
+            total => Some(self.speculative_hits as f64 / total as f64),
+        }
+    }
+
+    /// Gets the number of evicted pages that were clean, and therefore did not need any I/O.
+    pub fn clean_evictions(&self) -> u64 {
+        self.evictions.saturating_sub(self.dirty_write_backs)
+    }
+
+    /// Gets the mean number of dirty pages that were written back together by an eviction pass, or
+    /// `None` if no eviction pass wrote back any page.
+    pub fn mean_eviction_write_batch(&self) -> Option<f64> {
+        match self.eviction_write_batches {
+            0 => None,
+            batches => Some(self.dirty_write_backs as f64 / batches as f64),
+        }
+    }
+
+    /// Gets the mean latency of a page read, or `None` if there were no reads.
+    pub fn mean_read_latency(&self) -> Option<Duration> {
+        mean(self.read_time, self.page_reads)
+    }
+
+    /// Gets the mean latency of a page write, or `None` if there were no writes.
+    pub fn mean_write_latency(&self) -> Option<Duration> {
+        mean(self.write_time, self.page_writes)
+    }
+
+    /// Gets the fraction of buffer frames that hold a page, or `0.0` if there are no frames.
+    pub fn occupancy(&self) -> f64 {
+        let total: usize = self.frame_groups.iter().map(|g| g.total_frames).sum();
+        let occupied: usize = self.frame_groups.iter().map(|g| g.occupied_frames()).sum();
+
+        match total {
+            0 => 0.0,
+            total => occupied as f64 / total as f64,
+        }
+    }
+
+    /// Computes the counters accumulated since an `earlier` snapshot.
+    ///
+    /// The frame group occupancy is taken from `self`, since it is not a counter.
+    pub fn since(&self, earlier: &PoolStats) -> PoolStats {
+        PoolStats {
+            read_accesses: self.read_accesses.saturating_sub(earlier.read_accesses),
+            write_accesses: self.write_accesses.saturating_sub(earlier.write_accesses),
+            misses: self.misses.saturating_sub(earlier.misses),
+            page_reads: self.page_reads.saturating_sub(earlier.page_reads),
+            page_writes: self.page_writes.saturating_sub(earlier.page_writes),
+            evictions: self.evictions.saturating_sub(earlier.evictions),
+            dirty_write_backs: self
+                .dirty_write_backs
+                .saturating_sub(earlier.dirty_write_backs),
+            eviction_write_batches: self
+                .eviction_write_batches
+                .saturating_sub(earlier.eviction_write_batches),
+            clean_reclaims: self.clean_reclaims.saturating_sub(earlier.clean_reclaims),
+            free_frame_waits: self
+                .free_frame_waits
+                .saturating_sub(earlier.free_frame_waits),
+            loaded_hint_misses: self
+                .loaded_hint_misses
+                .saturating_sub(earlier.loaded_hint_misses),
+            skipped_access_records: self
+                .skipped_access_records
+                .saturating_sub(earlier.skipped_access_records),
+            rebalanced_frames: self
+                .rebalanced_frames
+                .saturating_sub(earlier.rebalanced_frames),
+            speculative_hits: self
+                .speculative_hits
+                .saturating_sub(earlier.speculative_hits),
+            wasted_prefetches: self
+                .wasted_prefetches
+                .saturating_sub(earlier.wasted_prefetches),
+            read_time: self.read_time.saturating_sub(earlier.read_time),
+            write_time: self.write_time.saturating_sub(earlier.write_time),
+            frame_groups: self.frame_groups.clone(),
+        }
+    }
+}
+
+/// The occupancy of a single frame group at the time of a [`PoolStats`] snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameGroupOccupancy {
+    /// The number of frames in the group that are free.
+    pub free_frames: usize,
+
+    /// The number of frames in the group that hold a page that has to be written back before the
+    /// frame can be reused.
+    pub dirty_frames: usize,
+
+    /// The total number of frames in the group.
+    pub total_frames: usize,
+}
+
+impl FrameGroupOccupancy {
+    /// Gets the number of frames in the group that hold a page.
+    pub fn occupied_frames(&self) -> usize {
+        self.total_frames.saturating_sub(self.free_frames)
+    }
+
+    /// Gets the number of frames in the group that hold a page that can be evicted without any
+    /// I/O.
+    pub fn clean_frames(&self) -> usize {
+        self.occupied_frames().saturating_sub(self.dirty_frames)
+    }
+}
+
+/// A snapshot of the counters of the reads and writes that the calling thread submitted to its
+/// `io_uring` instance.
+///
+/// Generated by [`BufferPoolManager::uring_stats`]. Every runtime started with
+/// [`BufferPoolManager::start_thread`] has its own `io_uring` instance, so the counters start
+/// from zero in every runtime.
+///
+/// Retries of submissions that found the submission queue full are handled inside the runtime's
+/// `io_uring` driver, so they are not counted here. A submission queue depth that stays close to
+/// the size of the queue (256 entries by default) is a sign of them, which
+/// [`BufferPoolManagerConfig::max_in_flight_io`](crate::BufferPoolManagerConfig::max_in_flight_io)
+/// avoids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UringStats {
+    /// The number of reads and writes that were submitted but have not completed yet.
+    pub in_flight: u64,
+
+    /// The largest number of reads and writes that were in flight at the same time.
+    pub max_in_flight: u64,
+
+    /// The number of reads and writes that were submitted.
+    pub submissions: u64,
+
+    /// The number of reads and writes that completed (or were cancelled because their future was
+    /// dropped).
+    pub completions: u64,
+
+    /// The total time from submission to completion of every completed read and write, including
+    /// any [injected latency](crate::InjectedLatency).
+    pub completion_time: Duration,
+
+    /// The longest time from submission to completion of a single read or write.
+    pub max_completion_time: Duration,
+
+    /// The number of reads and writes that had to wait before they were submitted, because the
+    /// thread already had the
+    /// [maximum number](crate::BufferPoolManagerConfig::max_in_flight_io) of them in flight.
+    pub throttled: u64,
+}
+
+impl UringStats {
+    /// Gets the mean time from submission to completion of a read or write, or `None` if no read
+    /// or write completed.
+    pub fn mean_completion_latency(&self) -> Option<Duration> {
+        mean(self.completion_time, self.completions)
+    }
+}
+
+/// Computes the mean of `count` durations that add up to `total`.
+fn mean(total: Duration, count: u64) -> Option<Duration> {
+    if count == 0 {
+        return None;
+    }
+
+    Some(Duration::from_nanos(
+        (total.as_nanos() / count as u128) as u64,
+    ))
+}
+
+/// The shared counters behind [`PoolStats`].
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    /// See [`PoolStats::read_accesses`].
+    read_accesses: AtomicU64,
+
+    /// See [`PoolStats::write_accesses`].
+    write_accesses: AtomicU64,
+
+    /// See [`PoolStats::misses`].
+    misses: AtomicU64,
+
+    /// See [`PoolStats::page_reads`].
+    page_reads: AtomicU64,
+
+    /// See [`PoolStats::page_writes`].
+    page_writes: AtomicU64,
+
+    /// See [`PoolStats::evictions`].
+    evictions: AtomicU64,
+
+    /// See [`PoolStats::dirty_write_backs`].
+    dirty_write_backs: AtomicU64,
+
+    /// See [`PoolStats::eviction_write_batches`].
+    eviction_write_batches: AtomicU64,
+
+    /// See [`PoolStats::clean_reclaims`].
+    clean_reclaims: AtomicU64,
+
+    /// See [`PoolStats::free_frame_waits`].
+    free_frame_waits: AtomicU64,
+
+    /// See [`PoolStats::loaded_hint_misses`].
+    loaded_hint_misses: AtomicU64,
+
+    /// See [`PoolStats::skipped_access_records`].
+    skipped_access_records: AtomicU64,
+
+    /// See [`PoolStats::rebalanced_frames`].
+    rebalanced_frames: AtomicU64,
+
+    /// See [`PoolStats::speculative_hits`].
+    speculative_hits: AtomicU64,
+
+    /// See [`PoolStats::wasted_prefetches`].
+    wasted_prefetches: AtomicU64,
+
+    /// See [`PoolStats::read_time`], in nanoseconds.
+    read_nanos: AtomicU64,
+
+    /// See [`PoolStats::write_time`], in nanoseconds.
+    write_nanos: AtomicU64,
+}
+
+impl StatsCounters {
+    /// Records that a read guard was handed out.
+    pub(crate) fn record_read_access(&self) {
+        self.read_accesses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a write guard was handed out.
+    pub(crate) fn record_write_access(&self) {
+        self.write_accesses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a page access had to read the page from persistent storage.
+    pub(crate) fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a page was read from persistent storage, which took `latency`.
+    pub(crate) fn record_page_read(&self, latency: Duration) {
+        self.page_reads.fetch_add(1, Ordering::Relaxed);
+        self.read_nanos
+            .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Records that a page was written to persistent storage, which took `latency`.
+    pub(crate) fn record_page_write(&self, latency: Duration) {
+        self.page_writes.fetch_add(1, Ordering::Relaxed);
+        self.write_nanos
+            .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Records that a page was evicted from memory, which required writing it back first if it
+    /// was `dirty`.
+    pub(crate) fn record_eviction(&self, dirty: bool) {
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+        if dirty {
+            self.dirty_write_backs.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Records that an eviction pass wrote back a batch of dirty pages.
+    pub(crate) fn record_eviction_write_batch(&self) {
+        self.eviction_write_batches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the frame of a clean, cool page was reclaimed without a cooling pass.
+    pub(crate) fn record_clean_reclaim(&self) {
+        self.clean_reclaims.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a task had to wait for pages to be evicted to get a free frame.
+    pub(crate) fn record_free_frame_wait(&self) {
+        self.free_frame_waits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a read found a page evicted even though the page's loaded hint was set.
+    pub(crate) fn record_loaded_hint_miss(&self) {
+        self.loaded_hint_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a page access skipped updating the page's eviction state.
+    pub(crate) fn record_skipped_access_record(&self) {
+        self.skipped_access_records.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that `count` free frames were moved from one frame group to another.
+    #[cfg(feature = "experimental")]
+    pub(crate) fn record_rebalanced_frames(&self, count: usize) {
+        self.rebalanced_frames
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// Records that a prefetched page was accessed before it expired.
+    pub(crate) fn record_speculative_hit(&self) {
+        self.speculative_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a prefetched page was evicted without ever being accessed.
+    pub(crate) fn record_wasted_prefetch(&self) {
+        self.wasted_prefetches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Takes a snapshot of the counters, without any frame group occupancy.
+    fn snapshot(&self) -> PoolStats {
+        PoolStats {
+            read_accesses: self.read_accesses.load(Ordering::Relaxed),
+            write_accesses: self.write_accesses.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            page_reads: self.page_reads.load(Ordering::Relaxed),
+            page_writes: self.page_writes.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            dirty_write_backs: self.dirty_write_backs.load(Ordering::Relaxed),
+            eviction_write_batches: self.eviction_write_batches.load(Ordering::Relaxed),
+            clean_reclaims: self.clean_reclaims.load(Ordering::Relaxed),
+            free_frame_waits: self.free_frame_waits.load(Ordering::Relaxed),
+            loaded_hint_misses: self.loaded_hint_misses.load(Ordering::Relaxed),
+            skipped_access_records: self.skipped_access_records.load(Ordering::Relaxed),
+            rebalanced_frames: self.rebalanced_frames.load(Ordering::Relaxed),
+            speculative_hits: self.speculative_hits.load(Ordering::Relaxed),
+            wasted_prefetches: self.wasted_prefetches.load(Ordering::Relaxed),
+            read_time: Duration::from_nanos(self.read_nanos.load(Ordering::Relaxed)),
+            write_time: Duration::from_nanos(self.write_nanos.load(Ordering::Relaxed)),
+            frame_groups: Vec::new(),
+        }
+    }
+}
+
+/// Reports the counters of the [`BufferPoolManager`] as deltas over consecutive windows of time.
+///
+/// Created by [`BufferPoolManager::stats_window`]. Every call to [`StatsWindow::next`] (or
+/// [`StatsWindow::next_blocking`]) waits until the end of the current window, and then returns the
+/// counters accumulated during that window. Windows are back to back, so no work is missed or
+/// counted twice even if the caller is late.
+#[derive(Debug, Clone)]
+pub struct StatsWindow {
+    /// The length of every window.
+    period: Duration,
+
+    /// The instant that the current window ends.
+    deadline: Instant,
+
+    /// The snapshot taken at the start of the current window.
+    last: PoolStats,
+}
+
+impl StatsWindow {
+    /// Gets the length of every window.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Waits asynchronously until the end of the current window, and returns the counters
+    /// accumulated during the window.
+    ///
+    /// This must be called from within an asynchronous runtime with a timer, such as a thread
+    /// started with [`BufferPoolManager::start_thread`].
+    pub async fn next(&mut self) -> PoolStats {
+        executor::sleep_until(self.deadline).await;
+        self.advance()
+    }
+
+    /// Blocks the current thread until the end of the current window, and returns the counters
+    /// accumulated during the window.
+    pub fn next_blocking(&mut self) -> PoolStats {
+        std::thread::sleep(self.deadline.saturating_duration_since(Instant::now()));
+        self.advance()
+    }
+
+    /// Ends the current window and starts the next one.
+    fn advance(&mut self) -> PoolStats {
+        let now = BufferPoolManager::get().stats();
+        let delta = now.since(&self.last);
+
+        self.last = now;
+        self.deadline += self.period;
+
+        delta
+    }
+}
+
+impl BufferPoolManager {
+    /// Gets a snapshot of the cumulative counters of the buffer pool manager, along with the
+    /// current occupancy of every frame group.
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            frame_groups: self
+                .active_frame_groups()
+                .iter()
+                .map(|group| FrameGroupOccupancy {
+                    free_frames: group.num_free_frames(),
+                    dirty_frames: group.num_dirty_frames(),
+                    total_frames: group.num_frames,
+                })
+                .collect(),
+            ..self.stats.snapshot()
+        }
+    }
+
+    /// Gets a snapshot of the counters of the reads and writes that the calling thread submitted
+    /// to the `io_uring` instance of its current runtime.
+    ///
+    /// With the `tracing` feature enabled, every read and write is also covered by a `page_io`
+    /// span at the `DEBUG` level, with the page ID, the kind of operation, the number of
+    /// operations in flight on the thread when it was submitted (`depth`), and its latency in
+    /// microseconds (`latency_us`), which is recorded once it completes.
+    pub fn uring_stats(&self) -> UringStats {
+        StorageManager::uring_stats()
+    }
+
+    /// Creates a [`StatsWindow`] that reports the counters of the buffer pool manager as deltas
+    /// over consecutive windows of length `period`, starting now.
+    ///
+    /// For example, a `period` of one second reports per-second rates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn stats_window(&self, period: Duration) -> StatsWindow {
+        assert!(!period.is_zero(), "The stats window must not be empty");
+
+        StatsWindow {
+            period,
+            deadline: Instant::now() + period,
+            last: self.stats(),
+        }
+    }
+}
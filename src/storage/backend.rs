@@ -0,0 +1,154 @@
+//! This module contains the [`StorageBackend`] trait, which lets the pages of the buffer pool be
+//! stored somewhere other than the database files.
+//!
+//! By default, the storage manager reads and writes pages directly from and to the database files
+//! through `io_uring`, handing the buffer frames themselves to the kernel so that no data is
+//! copied. That path does not go through the trait, since a [`StorageBackend`] only ever borrows
+//! the frames. If a backend is registered with
+//! [`BufferPoolManagerConfig::storage_backend`](crate::BufferPoolManagerConfig::storage_backend),
+//! every page read and write (and every sync) goes through the backend instead.
+//!
+//! [`MemoryStorage`] is a backend that keeps every page in memory.
+
+use crate::page::PageId;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::io::Result;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+/// The future returned by the methods of a [`StorageBackend`].
+///
+/// The future does not need to be [`Send`], since it is always awaited on the thread that
+/// submitted the operation.
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + 'a>>;
+
+/// A place to store the pages of the buffer pool.
+///
+/// Register a backend with
+/// [`BufferPoolManagerConfig::storage_backend`](crate::BufferPoolManagerConfig::storage_backend).
+///
+/// Reads and writes follow the semantics of `pread` and `pwrite` within a single page: they may
+/// transfer fewer bytes than requested, in which case the storage manager submits another
+/// operation for the rest of the buffer. Every page has the
+/// [page size](crate::BufferPoolManager::page_size) of the buffer pool, and pages that have never
+/// been written must read as zeroes.
+pub trait StorageBackend: Debug + Send + Sync {
+    /// Reads the data of page `pid` starting at `offset` bytes into the page into `buf`, and
+    /// returns the number of bytes that were read.
+    ///
+    /// Returning `0` for a non-empty `buf` means that the page ends before `offset`, which fails
+    /// the read with an [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error.
+    ///
+    /// # Errors
+    ///
+    /// If this returns an error, the read fails with that error.
+    fn read_at<'a>(
+        &'a self,
+        pid: PageId,
+        offset: usize,
+        buf: &'a mut [u8],
+    ) -> BackendFuture<'a, usize>;
+
+    /// Writes `buf` to page `pid` starting at `offset` bytes into the page, and returns the
+    /// number of bytes that were written.
+    ///
+    /// Returning `0` for a non-empty `buf` fails the write with a
+    /// [`WriteZero`](std::io::ErrorKind::WriteZero) error.
+    ///
+    /// # Errors
+    ///
+    /// If this returns an error, the write fails with that error, and the page stays dirty.
+    fn write_at<'a>(
+        &'a self,
+        pid: PageId,
+        offset: usize,
+        buf: &'a [u8],
+    ) -> BackendFuture<'a, usize>;
+
+    /// Makes every completed write durable.
+    ///
+    /// This is called wherever the database files would be synced, for example by
+    /// [`WritePageGuard::flush_sync`](crate::page::WritePageGuard::flush_sync) and
+    /// [`BufferPoolManager::sync_data`](crate::BufferPoolManager::sync_data).
+    ///
+    /// # Errors
+    ///
+    /// If this returns an error, the sync fails with that error.
+    fn sync(&self) -> BackendFuture<'_, ()>;
+}
+
+/// A [`StorageBackend`] that keeps every page in memory.
+///
+/// Nothing is ever persisted, so every page starts out zeroed when the buffer pool manager is
+/// initialized with a new instance, and [`StorageBackend::sync`] does nothing. This makes the
+/// buffer pool usable without any files, for example in unit tests or as a bounded cache in front
+/// of data that can be recomputed. Note that pages that are evicted still take up memory here.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    /// The data of every page that has been written, which may be shorter than a page if only
+    /// its start was written.
+    pages: Mutex<HashMap<PageId, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    /// Creates a new, empty `MemoryStorage`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the number of pages that have been written so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a read or write of this storage panicked while holding its lock.
+    pub fn num_pages(&self) -> usize {
+        self.pages
+            .lock()
+            .expect("Memory storage lock poisoned")
+            .len()
+    }
+}
+
+impl StorageBackend for MemoryStorage {
+    fn read_at<'a>(
+        &'a self,
+        pid: PageId,
+        offset: usize,
+        buf: &'a mut [u8],
+    ) -> BackendFuture<'a, usize> {
+        // Pages (or parts of pages) that were never written read as zeroes.
+        buf.fill(0);
+
+        let pages = self.pages.lock().expect("Memory storage lock poisoned");
+        if let Some(data) = pages.get(&pid).and_then(|data| data.get(offset..)) {
+            let n = data.len().min(buf.len());
+            buf[..n].copy_from_slice(&data[..n]);
+        }
+
+        let len = buf.len();
+        Box::pin(async move { Ok(len) })
+    }
+
+    fn write_at<'a>(
+        &'a self,
+        pid: PageId,
+        offset: usize,
+        buf: &'a [u8],
+    ) -> BackendFuture<'a, usize> {
+        let mut pages = self.pages.lock().expect("Memory storage lock poisoned");
+        let data = pages.entry(pid).or_default();
+        if data.len() < offset + buf.len() {
+            data.resize(offset + buf.len(), 0);
+        }
+        data[offset..offset + buf.len()].copy_from_slice(buf);
+
+        let len = buf.len();
+        Box::pin(async move { Ok(len) })
+    }
+
+    fn sync(&self) -> BackendFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -0,0 +1,76 @@
+//! This module contains the per-page checksums that protect pages against silent corruption on
+//! persistent storage.
+//!
+//! If checksums are enabled, the last [`CHECKSUM_SIZE`] bytes of every page are reserved for a
+//! trailer that holds the CRC32C (Castagnoli) checksum of the rest of the page. The trailer is
+//! written right before a page is written out, and verified right after a page is read in.
+
+/// The number of bytes at the end of every page that hold the page's checksum.
+pub(crate) const CHECKSUM_SIZE: usize = 4;
+
+/// The reversed CRC32C (Castagnoli) polynomial.
+const POLYNOMIAL: u32 = 0x82F6_3B78;
+
+/// A lookup table with the checksum of every byte value.
+const TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = byte as u32;
+
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[byte] = crc;
+        byte += 1;
+    }
+
+    table
+};
+
+/// Computes the CRC32C checksum of some data.
+pub(crate) fn crc32c(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(!0u32, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    });
+
+    !crc
+}
+
+/// Splits a page into its data and its checksum trailer.
+fn split_trailer(page: &[u8]) -> (&[u8], u32) {
+    let (data, trailer) = page.split_at(page.len() - CHECKSUM_SIZE);
+    let stored = u32::from_le_bytes(trailer.try_into().expect("The trailer has a fixed size"));
+
+    (data, stored)
+}
+
+/// Writes the checksum of a page's data into the page's trailer.
+pub(crate) fn seal(page: &mut [u8]) {
+    let (data, trailer) = page.split_at_mut(page.len() - CHECKSUM_SIZE);
+    let checksum = crc32c(data);
+
+    trailer.copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// Verifies the checksum of a page, returning the stored and computed checksums on a mismatch.
+///
+/// A page that is entirely zeroed has never been written, so it is considered to be valid.
+pub(crate) fn verify(page: &[u8]) -> Result<(), (u32, u32)> {
+    let (data, stored) = split_trailer(page);
+    let computed = crc32c(data);
+
+    if stored == computed || page.iter().all(|&b| b == 0) {
+        return Ok(());
+    }
+
+    Err((stored, computed))
+}
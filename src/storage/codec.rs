@@ -0,0 +1,248 @@
+//! This module contains the [`PageCodec`] trait, which transforms pages on their way to and from
+//! persistent storage, for example to encrypt them at rest.
+//!
+//! The storage manager hands the buffer frames themselves to the kernel, so a codec cannot work on
+//! a separate copy of the page that is written out. Instead, a page is encoded in place right
+//! before it is written, while its plain data is kept in a scratch buffer, and the plain data is
+//! copied back as soon as the write completes. Likewise, a page is decoded in place right after it
+//! is read, from a copy in a scratch buffer. Scratch buffers are pooled per thread, so that the
+//! I/O path does not allocate once it is warmed up.
+//!
+//! With the `aes-gcm` feature, [`AesGcmCodec`] encrypts and authenticates every page with
+//! AES-256-GCM.
+
+use crate::page::PageId;
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::io::Result;
+use std::ops::{Deref, DerefMut};
+
+/// The largest number of scratch buffers that a thread keeps around for reuse.
+const MAX_POOLED_SCRATCH: usize = 64;
+
+std::thread_local! {
+    /// The scratch buffers of this thread that are not in use.
+    static SCRATCH: RefCell<Vec<Box<[u8]>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A transformation of the contents of every page between memory and persistent storage, such as
+/// encryption or compression.
+///
+/// Register a codec with
+/// [`BufferPoolManagerConfig::page_codec`](crate::BufferPoolManagerConfig::page_codec).
+///
+/// The codec works on the whole page except for its checksum trailer (if checksums are enabled),
+/// including the [reserved bytes](crate::BufferPoolManagerConfig::reserved_header), and encoding
+/// must not change the size of the page. A codec that needs room for its own metadata (like a
+/// nonce or an authentication tag) stores it in the last [`PageCodec::trailer_len`] bytes, which
+/// the embedder has to reserve with
+/// [`BufferPoolManagerConfig::reserved_trailer`](crate::BufferPoolManagerConfig::reserved_trailer).
+/// Checksums are computed over the encoded page, so corruption on persistent storage is detected
+/// before the page is decoded.
+///
+/// Pages that have never been written read as zeroes, and are not decoded.
+pub trait PageCodec: Debug + Send + Sync {
+    /// Encodes the data of page `pid` from `plain` into `encoded`, right before the page is
+    /// written out. Both slices have the same length.
+    ///
+    /// # Errors
+    ///
+    /// If this returns an error, the write fails with that error, and the page stays dirty.
+    fn encode(&self, pid: PageId, plain: &[u8], encoded: &mut [u8]) -> Result<()>;
+
+    /// Decodes the data of page `pid` from `encoded` into `plain`, right after the page is read
+    /// in. Both slices have the same length.
+    ///
+    /// # Errors
+    ///
+    /// If this returns an error, the read fails with that error.
+    fn decode(&self, pid: PageId, encoded: &[u8], plain: &mut [u8]) -> Result<()>;
+
+    /// Gets the number of bytes at the end of every encoded page that the codec uses for its own
+    /// metadata, which must be reserved with
+    /// [`BufferPoolManagerConfig::reserved_trailer`](crate::BufferPoolManagerConfig::reserved_trailer).
+    ///
+    /// By default, a codec uses no bytes of its own.
+    fn trailer_len(&self) -> usize {
+        0
+    }
+}
+
+/// Decodes the encoded `page` of `pid` in place with `codec`.
+///
+/// A page that is all zeroes was never written, so it is left as is instead of being decoded.
+///
+/// # Errors
+///
+/// Returns the error of the codec.
+pub(crate) fn decode_page(pid: PageId, codec: &dyn PageCodec, page: &mut [u8]) -> Result<()> {
+    if page.iter().all(|&b| b == 0) {
+        return Ok(());
+    }
+
+    let encoded = ScratchBuf::copy_of(page);
+    codec.decode(pid, &encoded, page)
+}
+
+/// A buffer that holds a copy of a page while it is encoded or decoded, which is returned to the
+/// thread's pool when it is dropped.
+#[derive(Debug)]
+pub(crate) struct ScratchBuf {
+    /// The buffer, which is exactly as long as the data it holds.
+    buf: Box<[u8]>,
+}
+
+impl ScratchBuf {
+    /// Takes a scratch buffer with a copy of `data` from this thread's pool, allocating a new one
+    /// if none of the right length is available.
+    pub(crate) fn copy_of(data: &[u8]) -> Self {
+        let pooled = SCRATCH
+            .try_with(|pool| {
+                let mut pool = pool.borrow_mut();
+                let index = pool.iter().position(|buf| buf.len() == data.len())?;
+                Some(pool.swap_remove(index))
+            })
+            .ok()
+            .flatten();
+
+        let mut buf = pooled.unwrap_or_else(|| vec![0; data.len()].into_boxed_slice());
+        buf.copy_from_slice(data);
+
+        Self { buf }
+    }
+}
+
+impl Drop for ScratchBuf {
+    fn drop(&mut self) {
+        let buf = std::mem::take(&mut self.buf);
+
+        // The pool may already be gone if the thread is exiting, in which case the buffer is freed.
+        let _ = SCRATCH.try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < MAX_POOLED_SCRATCH {
+                pool.push(buf);
+            }
+        });
+    }
+}
+
+impl Deref for ScratchBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.buf
+    }
+}
+
+impl DerefMut for ScratchBuf {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buf
+    }
+}
+
+#[cfg(feature = "aes-gcm")]
+pub use self::aes::AesGcmCodec;
+
+#[cfg(feature = "aes-gcm")]
+/// The implementation of [`AesGcmCodec`] with the `aes-gcm` crate.
+mod aes {
+    use super::PageCodec;
+    use crate::page::PageId;
+    use aes_gcm::aead::{AeadInPlace, KeyInit};
+    use aes_gcm::{Aes256Gcm, Nonce, Tag};
+    use std::fmt::{self, Debug};
+    use std::io::{Error, ErrorKind, Result};
+
+    /// The number of bytes of the random nonce that every page is encrypted with.
+    const NONCE_LEN: usize = 12;
+
+    /// The number of bytes of the tag that authenticates every page.
+    const TAG_LEN: usize = 16;
+
+    /// A [`PageCodec`] that encrypts and authenticates every page with AES-256-GCM, which is only
+    /// available with the `aes-gcm` feature.
+    ///
+    /// Every write encrypts the page with a fresh random nonce, and the page's ID is authenticated
+    /// along with its data, so a page that is modified on persistent storage or copied to another
+    /// location fails to decode with an [`InvalidData`](ErrorKind::InvalidData) error. The nonce
+    /// and the tag are stored in the last [`AesGcmCodec::TRAILER_LEN`] bytes of every page, which
+    /// must be reserved with
+    /// [`BufferPoolManagerConfig::reserved_trailer`](crate::BufferPoolManagerConfig::reserved_trailer).
+    ///
+    /// Since nonces are random, a single key should not be used for more than about 2^32 page
+    /// writes.
+    pub struct AesGcmCodec {
+        /// The cipher, initialized with the key.
+        cipher: Aes256Gcm,
+    }
+
+    impl AesGcmCodec {
+        /// The number of bytes at the end of every page that hold the nonce and the tag.
+        pub const TRAILER_LEN: usize = NONCE_LEN + TAG_LEN;
+
+        /// Creates a new `AesGcmCodec` with the given 256-bit key.
+        pub fn new(key: &[u8; 32]) -> Self {
+            Self {
+                cipher: Aes256Gcm::new(key.into()),
+            }
+        }
+    }
+
+    impl Debug for AesGcmCodec {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            // Never print the key.
+            f.debug_struct("AesGcmCodec").finish_non_exhaustive()
+        }
+    }
+
+    impl PageCodec for AesGcmCodec {
+        fn encode(&self, pid: PageId, plain: &[u8], encoded: &mut [u8]) -> Result<()> {
+            let (data, trailer) = encoded.split_at_mut(plain.len() - Self::TRAILER_LEN);
+            data.copy_from_slice(&plain[..data.len()]);
+
+            let nonce: [u8; NONCE_LEN] = rand::random();
+            let tag = self
+                .cipher
+                .encrypt_in_place_detached(
+                    Nonce::from_slice(&nonce),
+                    &pid.as_u64().to_le_bytes(),
+                    data,
+                )
+                .map_err(|_| Error::other(format!("Unable to encrypt page {pid}")))?;
+
+            trailer[..NONCE_LEN].copy_from_slice(&nonce);
+            trailer[NONCE_LEN..].copy_from_slice(&tag);
+
+            Ok(())
+        }
+
+        fn decode(&self, pid: PageId, encoded: &[u8], plain: &mut [u8]) -> Result<()> {
+            let (data, trailer) = encoded.split_at(encoded.len() - Self::TRAILER_LEN);
+            let (nonce, tag) = trailer.split_at(NONCE_LEN);
+
+            plain[..data.len()].copy_from_slice(data);
+            self.cipher
+                .decrypt_in_place_detached(
+                    Nonce::from_slice(nonce),
+                    &pid.as_u64().to_le_bytes(),
+                    &mut plain[..data.len()],
+                    Tag::from_slice(tag),
+                )
+                .map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Page {pid} failed to authenticate"),
+                    )
+                })?;
+
+            // The trailer is not part of the page's data, but it is reserved, so we keep it.
+            plain[data.len()..].copy_from_slice(trailer);
+
+            Ok(())
+        }
+
+        fn trailer_len(&self) -> usize {
+            Self::TRAILER_LEN
+        }
+    }
+}
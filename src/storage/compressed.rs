@@ -0,0 +1,560 @@
+//! This module contains [`CompressedStorage`], a [`StorageBackend`] that stores every page
+//! compressed with LZ4, which is only available with the `lz4` feature.
+//!
+//! Compressed pages have different lengths, so they cannot live at a fixed offset in a file like
+//! the pages of the database files do. Instead, the data file is divided into sectors of
+//! [`SECTOR_SIZE`] bytes, and every page is stored in an extent of consecutive sectors that is
+//! just large enough for its compressed data. An indirection table maps every page to its extent,
+//! and an extent allocator hands out free runs of sectors, reusing the extents of pages that were
+//! rewritten.
+//!
+//! A page is never overwritten in place: every write stores the page in a new extent, and the old
+//! extent is only freed once a [sync](StorageBackend::sync) has persisted an indirection table
+//! that no longer refers to it. Every sync writes the whole table to a metadata file next to the
+//! data file (with a checksum) and atomically renames it into place, so after a crash the storage
+//! reopens with every page as of the last completed sync, and the free extents are rebuilt from
+//! the gaps between the extents in the table.
+
+use super::backend::{BackendFuture, StorageBackend};
+use super::checksum::crc32c;
+use crate::executor;
+use crate::page::PageId;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// The number of bytes in a sector, which is the granularity of extents in the data file.
+const SECTOR_SIZE: u64 = 512;
+
+/// The magic number at the start of every metadata file.
+const MAGIC: [u8; 8] = *b"BPMLZ4M1";
+
+/// The number of bytes of the metadata file header: the magic number, the page size, and the
+/// number of entries.
+const HEADER_LEN: usize = 8 + 4 + 8;
+
+/// The number of bytes of every entry of the indirection table in the metadata file.
+const ENTRY_LEN: usize = 8 + 8 + 4 + 4;
+
+/// The location of a page's data in the data file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Extent {
+    /// The first sector of the extent.
+    start: u64,
+
+    /// The number of sectors in the extent.
+    sectors: u64,
+
+    /// The number of bytes of data stored in the extent. If this is the page size, the page did
+    /// not compress and is stored as is.
+    len: u32,
+}
+
+impl Extent {
+    /// Gets the offset of the extent in the data file, in bytes.
+    fn offset(&self) -> u64 {
+        self.start * SECTOR_SIZE
+    }
+}
+
+/// A first-fit allocator of runs of sectors in the data file.
+#[derive(Debug, Default)]
+struct ExtentAllocator {
+    /// The free runs of sectors before `end`, by their first sector, which never touch each other.
+    free: BTreeMap<u64, u64>,
+
+    /// The sector after the last allocated sector.
+    end: u64,
+}
+
+impl ExtentAllocator {
+    /// Creates an allocator where every sector that is not in `used` is free.
+    fn from_used<'a>(used: impl IntoIterator<Item = &'a Extent>) -> Self {
+        let mut used: Vec<(u64, u64)> = used.into_iter().map(|e| (e.start, e.sectors)).collect();
+        used.sort_unstable();
+
+        let mut allocator = Self::default();
+        for (start, sectors) in used {
+            if start > allocator.end {
+                allocator.free.insert(allocator.end, start - allocator.end);
+            }
+            allocator.end = allocator.end.max(start + sectors);
+        }
+
+        allocator
+    }
+
+    /// Allocates a run of `sectors` sectors, and returns its first sector.
+    fn allocate(&mut self, sectors: u64) -> u64 {
+        let fit = self
+            .free
+            .iter()
+            .find(|(_, &len)| len >= sectors)
+            .map(|(&start, &len)| (start, len));
+
+        match fit {
+            Some((start, len)) => {
+                self.free.remove(&start);
+                if len > sectors {
+                    self.free.insert(start + sectors, len - sectors);
+                }
+                start
+            }
+            None => {
+                let start = self.end;
+                self.end += sectors;
+                start
+            }
+        }
+    }
+
+    /// Frees a run of sectors, merging it with the free runs around it.
+    fn free(&mut self, mut start: u64, mut sectors: u64) {
+        if let Some(next) = self.free.remove(&(start + sectors)) {
+            sectors += next;
+        }
+
+        if let Some((&prev, &len)) = self.free.range(..start).next_back() {
+            if prev + len == start {
+                self.free.remove(&prev);
+                start = prev;
+                sectors += len;
+            }
+        }
+
+        if start + sectors == self.end {
+            self.end = start;
+        } else {
+            self.free.insert(start, sectors);
+        }
+    }
+}
+
+/// The mutable state of a [`CompressedStorage`].
+#[derive(Debug, Default)]
+struct State {
+    /// The extent of every page that has been written.
+    table: HashMap<PageId, Extent>,
+
+    /// The allocator of the sectors of the data file.
+    allocator: ExtentAllocator,
+
+    /// The extents that are no longer in `table`, but that the persisted table may still refer
+    /// to, so they cannot be reused until the next sync.
+    pending: Vec<Extent>,
+}
+
+/// A [`StorageBackend`] that compresses every page with LZ4 into a single data file, which is
+/// only available with the `lz4` feature.
+///
+/// Register it with
+/// [`BufferPoolManagerConfig::storage_backend`](crate::BufferPoolManagerConfig::storage_backend).
+///
+/// Every page is stored in a variable-length extent of the data file that is rounded up to 512
+/// bytes, so a mostly empty or repetitive page takes up a fraction of its size on disk, and a page
+/// that does not compress is stored as is. The indirection table that locates every page is kept
+/// in memory and persisted to a metadata file (the data file's path with `.map` appended) on
+/// every [sync](StorageBackend::sync). Writes since the last sync are lost if the process crashes.
+///
+/// Compression costs CPU time on every read and write, and the data file is accessed with
+/// positional I/O on the blocking thread pool of the `tokio` runtime rather than through
+/// `io_uring`, so this is meant for cold data where space matters more than latency. Thus, its
+/// futures must be polled on a `tokio` runtime, like the runtimes of the buffer pool's threads.
+#[derive(Debug)]
+pub struct CompressedStorage {
+    /// The data file, which holds the extents of the pages.
+    file: Arc<File>,
+
+    /// The path of the metadata file, which holds the indirection table.
+    map_path: PathBuf,
+
+    /// The size of every page.
+    page_size: usize,
+
+    /// The indirection table and the extent allocator.
+    state: Mutex<State>,
+
+    /// Serializes syncs, since they all write the same metadata file.
+    sync_lock: tokio::sync::Mutex<()>,
+}
+
+/// Runs blocking file I/O on the blocking thread pool, so that it does not stall every other task
+/// on the current thread.
+///
+/// # Errors
+///
+/// Returns the error of `f`, or an error if `f` panicked.
+async fn blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    executor::spawn_blocking(f).await.map_err(Error::other)?
+}
+
+impl CompressedStorage {
+    /// Opens the compressed storage at `path` for pages of `page_size` bytes, creating it if it
+    /// does not exist.
+    ///
+    /// `page_size` must be the [page size](crate::BufferPoolManager::page_size) of the buffer
+    /// pool that the storage is registered with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files cannot be opened, if the metadata file is corrupted, or if the
+    /// storage was created with a different page size.
+    pub fn open(path: impl AsRef<Path>, page_size: usize) -> Result<Self> {
+        let path = path.as_ref();
+        if page_size == 0 || u32::try_from(page_size).is_err() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid page size {page_size} for compressed storage"),
+            ));
+        }
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut map_path = OsString::from(path);
+        map_path.push(".map");
+        let map_path = PathBuf::from(map_path);
+
+        let table = match std::fs::read(&map_path) {
+            Ok(bytes) => Self::decode_table(&bytes, page_size)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        let state = State {
+            allocator: ExtentAllocator::from_used(table.values()),
+            table,
+            pending: Vec::new(),
+        };
+
+        Ok(Self {
+            file: Arc::new(file),
+            map_path,
+            page_size,
+            state: Mutex::new(state),
+            sync_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Gets the number of pages that have been written so far.
+    pub fn num_pages(&self) -> usize {
+        self.state().table.len()
+    }
+
+    /// Gets the number of bytes of the data file that the pages take up, including the padding of
+    /// their extents.
+    pub fn stored_bytes(&self) -> u64 {
+        self.state()
+            .table
+            .values()
+            .map(|extent| extent.sectors * SECTOR_SIZE)
+            .sum()
+    }
+
+    /// Locks the state of the storage.
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("Compressed storage lock poisoned")
+    }
+
+    /// Reads the whole page `pid` into `page`, which must be exactly one page long.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the extent cannot be read, or if it does not decompress into a page.
+    async fn read_page(&self, pid: PageId, page: &mut [u8]) -> Result<()> {
+        let Some(extent) = self.state().table.get(&pid).copied() else {
+            page.fill(0);
+            return Ok(());
+        };
+
+        let len = extent.len as usize;
+        let file = self.file.clone();
+        let data = blocking(move || {
+            let mut data = vec![0; len];
+            file.read_exact_at(&mut data, extent.offset())?;
+            Ok(data)
+        })
+        .await?;
+
+        if len == self.page_size {
+            page.copy_from_slice(&data);
+            return Ok(());
+        }
+
+        let decompressed = lz4_flex::block::decompress_into(&data, page).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Unable to decompress page {pid}: {e}"),
+            )
+        })?;
+        if decompressed != self.page_size {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Page {pid} decompressed to {decompressed} bytes"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Writes the whole page `pid` from `page`, which must be exactly one page long, into a new
+    /// extent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the extent cannot be written, in which case the page keeps its old
+    /// data.
+    async fn write_page(&self, pid: PageId, page: &[u8]) -> Result<()> {
+        let mut compressed = vec![0; lz4_flex::block::get_maximum_output_size(page.len())];
+        let len = lz4_flex::block::compress_into(page, &mut compressed)
+            .map_err(|e| Error::other(format!("Unable to compress page {pid}: {e}")))?;
+
+        // A page that does not compress is stored as is.
+        let data = if len < page.len() {
+            compressed.truncate(len);
+            compressed
+        } else {
+            page.to_vec()
+        };
+
+        let sectors = (data.len() as u64).div_ceil(SECTOR_SIZE);
+        let start = self.state().allocator.allocate(sectors);
+        let extent = Extent {
+            start,
+            sectors,
+            len: data.len() as u32,
+        };
+
+        let file = self.file.clone();
+        let res = blocking(move || file.write_all_at(&data, extent.offset())).await;
+        if let Err(e) = res {
+            self.state().allocator.free(start, sectors);
+            return Err(e);
+        }
+
+        let mut state = self.state();
+        if let Some(old) = state.table.insert(pid, extent) {
+            state.pending.push(old);
+        }
+
+        Ok(())
+    }
+
+    /// Serializes the indirection table into the contents of a metadata file.
+    fn encode_table(&self, table: &[(PageId, Extent)]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + table.len() * ENTRY_LEN + 4);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&(self.page_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&(table.len() as u64).to_le_bytes());
+
+        for (pid, extent) in table {
+            bytes.extend_from_slice(&pid.as_u64().to_le_bytes());
+            bytes.extend_from_slice(&extent.start.to_le_bytes());
+            bytes.extend_from_slice(&(extent.sectors as u32).to_le_bytes());
+            bytes.extend_from_slice(&extent.len.to_le_bytes());
+        }
+
+        let checksum = crc32c(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        bytes
+    }
+
+    /// Deserializes the indirection table from the contents of a metadata file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metadata file is corrupted, or if it was written for a different
+    /// page size.
+    fn decode_table(bytes: &[u8], page_size: usize) -> Result<HashMap<PageId, Extent>> {
+        let corrupted = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                "Compressed storage map is corrupted",
+            )
+        };
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+
+        if bytes.len() < HEADER_LEN + 4 || bytes[..MAGIC.len()] != MAGIC {
+            return Err(corrupted());
+        }
+
+        let (data, checksum) = bytes.split_at(bytes.len() - 4);
+        if crc32c(data) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(corrupted());
+        }
+
+        let stored_page_size = u32_at(8) as usize;
+        if stored_page_size != page_size {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Compressed storage has a page size of {stored_page_size}, not {page_size}"
+                ),
+            ));
+        }
+
+        let entries = u64_at(12) as usize;
+        if data.len() != HEADER_LEN + entries.saturating_mul(ENTRY_LEN) {
+            return Err(corrupted());
+        }
+
+        let table = (0..entries)
+            .map(|i| {
+                let at = HEADER_LEN + i * ENTRY_LEN;
+                let extent = Extent {
+                    start: u64_at(at + 8),
+                    sectors: u32_at(at + 16) as u64,
+                    len: u32_at(at + 20),
+                };
+                (PageId::new(u64_at(at)), extent)
+            })
+            .collect();
+
+        Ok(table)
+    }
+
+    /// Makes every completed write durable, and then persists the indirection table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data file cannot be synced or the metadata file cannot be written,
+    /// in which case the previously persisted table stays in place.
+    async fn sync_all(&self) -> Result<()> {
+        let _sync = self.sync_lock.lock().await;
+
+        // Every extent that is freed from now on may still be referenced by the snapshot.
+        let (table, pending) = {
+            let mut state = self.state();
+            let table: Vec<_> = state.table.iter().map(|(&pid, &e)| (pid, e)).collect();
+            (table, std::mem::take(&mut state.pending))
+        };
+
+        let bytes = self.encode_table(&table);
+        let file = self.file.clone();
+        let map_path = self.map_path.clone();
+        let res = blocking(move || Self::persist(&file, &map_path, &bytes)).await;
+
+        let mut state = self.state();
+        match res {
+            Ok(()) => {
+                for extent in pending {
+                    state.allocator.free(extent.start, extent.sectors);
+                }
+            }
+            Err(_) => state.pending.extend(pending),
+        }
+
+        res
+    }
+
+    /// Syncs the data file `file` and atomically replaces the metadata file at `map_path` with
+    /// the encoded table `bytes`, blocking the current thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files cannot be written or synced.
+    fn persist(file: &File, map_path: &Path, bytes: &[u8]) -> Result<()> {
+        // The extents must be durable before the table that points to them is.
+        file.sync_data()?;
+
+        let mut tmp_path = map_path.as_os_str().to_os_string();
+        tmp_path.push(".tmp");
+
+        let tmp = File::create(&tmp_path)?;
+        tmp.write_all_at(bytes, 0)?;
+        tmp.sync_all()?;
+        drop(tmp);
+
+        std::fs::rename(&tmp_path, map_path)?;
+
+        // The rename is only durable once the directory is synced.
+        let dir = match map_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()
+    }
+
+    /// Checks that a transfer of `len` bytes at `offset` stays within a page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transfer goes past the end of the page.
+    fn check_bounds(&self, offset: usize, len: usize) -> Result<()> {
+        if offset.saturating_add(len) > self.page_size {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Transfer of {len} bytes at offset {offset} exceeds the page size of {}",
+                    self.page_size
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl StorageBackend for CompressedStorage {
+    fn read_at<'a>(
+        &'a self,
+        pid: PageId,
+        offset: usize,
+        buf: &'a mut [u8],
+    ) -> BackendFuture<'a, usize> {
+        Box::pin(async move {
+            self.check_bounds(offset, buf.len())?;
+
+            if offset == 0 && buf.len() == self.page_size {
+                self.read_page(pid, buf).await?;
+            } else {
+                let mut page = vec![0; self.page_size];
+                self.read_page(pid, &mut page).await?;
+                buf.copy_from_slice(&page[offset..offset + buf.len()]);
+            }
+
+            Ok(buf.len())
+        })
+    }
+
+    fn write_at<'a>(
+        &'a self,
+        pid: PageId,
+        offset: usize,
+        buf: &'a [u8],
+    ) -> BackendFuture<'a, usize> {
+        Box::pin(async move {
+            self.check_bounds(offset, buf.len())?;
+
+            // Extents are immutable, so a partial write rewrites the whole page.
+            if offset == 0 && buf.len() == self.page_size {
+                self.write_page(pid, buf).await?;
+            } else {
+                let mut page = vec![0; self.page_size];
+                self.read_page(pid, &mut page).await?;
+                page[offset..offset + buf.len()].copy_from_slice(buf);
+                self.write_page(pid, &page).await?;
+            }
+
+            Ok(buf.len())
+        })
+    }
+
+    fn sync(&self) -> BackendFuture<'_, ()> {
+        Box::pin(self.sync_all())
+    }
+}
@@ -0,0 +1,294 @@
+//! This module contains the [`DoublewriteBuffer`], which protects pages against torn writes.
+//!
+//! Pages are written with `O_DIRECT`, but a drive only guarantees that a write is atomic up to its
+//! sector size, which can be smaller than a page. If the machine loses power in the middle of
+//! writing a page out, the page can be left with part of its old contents and part of its new
+//! contents. Checksums detect such a torn page, but cannot repair it.
+//!
+//! If the double-write buffer is enabled (see [`BufferPoolManagerConfig::doublewrite_buffer`]),
+//! every page is written out in three steps:
+//!
+//! 1. The page's image is written to a free slot of the double-write buffer file, along with the
+//!    page's ID, a sequence number, and a checksum of the slot, and the file is synced.
+//! 2. The page is written to its home location in the database files, and that file is synced.
+//! 3. The slot is freed.
+//!
+//! A crash can therefore tear at most one of the two copies of a page. When the buffer pool
+//! manager is initialized, the home location of every page that has a valid copy in the buffer is
+//! verified against its checksum, and a torn page is repaired from its latest copy. Pages whose
+//! home location is intact are left alone, since their copy may be older than the page.
+//!
+//! The double-write buffer file lives next to the first database file, with the extension
+//! `dwbuf`.
+//!
+//! [`BufferPoolManagerConfig::doublewrite_buffer`]: crate::BufferPoolManagerConfig::doublewrite_buffer
+
+use crate::files::HomeFiles;
+use crate::page::PageId;
+use crate::storage::checksum;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Result};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// The number of slots in the double-write buffer, which is the number of pages that can be
+/// written out at the same time.
+pub(crate) const DOUBLEWRITE_SLOTS: usize = 128;
+
+/// The magic number at the start of a valid slot.
+const MAGIC: [u8; 8] = *b"BPMDWS01";
+
+/// The length of the header of a slot: the magic number, the page's ID, the sequence number, the
+/// page size, and the CRC32C checksum of everything else in the slot.
+const SLOT_HEADER_LEN: usize = 32;
+
+/// Gets the path to the double-write buffer file of the database files at the given paths.
+fn doublewrite_buffer_path(paths: &[PathBuf]) -> PathBuf {
+    paths[0].with_extension("dwbuf")
+}
+
+/// The double-write buffer of a storage manager.
+#[derive(Debug)]
+pub(crate) struct DoublewriteBuffer {
+    /// The path to the double-write buffer file.
+    path: PathBuf,
+
+    /// The size of every page.
+    page_size: usize,
+
+    /// A permit for every free slot, so that a write that needs several slots gets all of them at
+    /// once, or waits without holding any of them.
+    permits: Semaphore,
+
+    /// The indices of the free slots.
+    free_slots: Mutex<Vec<usize>>,
+
+    /// The sequence number of the next staged page, which orders the copies of the same page.
+    sequence: AtomicU64,
+}
+
+impl DoublewriteBuffer {
+    /// Creates the double-write buffer file of the database files at the given paths, with every
+    /// slot free.
+    ///
+    /// This must be called after [`DoublewriteBuffer::recover`], since it discards the contents
+    /// of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub(crate) fn create(paths: &[PathBuf], page_size: usize) -> Result<Self> {
+        let path = doublewrite_buffer_path(paths);
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        file.set_len((DOUBLEWRITE_SLOTS * (SLOT_HEADER_LEN + page_size)) as u64)?;
+        file.sync_all()?;
+
+        Ok(Self {
+            path,
+            page_size,
+            permits: Semaphore::new(DOUBLEWRITE_SLOTS),
+            free_slots: Mutex::new((0..DOUBLEWRITE_SLOTS).rev().collect()),
+            sequence: AtomicU64::new(0),
+        })
+    }
+
+    /// Gets the path to the double-write buffer file.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the images of the given pages to free slots of the double-write buffer through
+    /// `file`, and syncs the file.
+    ///
+    /// The slots stay in use until the returned [`StagedSlots`] is dropped, which must only happen
+    /// once the pages were written to their home locations and those are durable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the slots cannot be written, or if the file cannot be synced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than [`DOUBLEWRITE_SLOTS`] pages are staged at once.
+    pub(crate) async fn stage(
+        &self,
+        file: &tokio_uring::fs::File,
+        pages: &[(PageId, &[u8])],
+    ) -> Result<StagedSlots<'_>> {
+        assert!(
+            pages.len() <= DOUBLEWRITE_SLOTS,
+            "Tried to stage more pages than the double-write buffer has slots"
+        );
+
+        let permit = self
+            .permits
+            .acquire_many(pages.len() as u32)
+            .await
+            .expect("The double-write buffer's semaphore is never closed");
+
+        let slots = {
+            let mut free_slots = self.free_slots.lock().unwrap_or_else(|e| e.into_inner());
+            let at = free_slots.len() - pages.len();
+            free_slots.split_off(at)
+        };
+        let staged = StagedSlots {
+            buffer: self,
+            slots,
+            _permit: permit,
+        };
+
+        for (&slot, &(pid, image)) in staged.slots.iter().zip(pages) {
+            let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+            let buf = encode_slot(pid, sequence, image);
+
+            let offset = (slot * (SLOT_HEADER_LEN + self.page_size)) as u64;
+            let (res, _) = file.write_all_at(buf, offset).await;
+            res?;
+        }
+        file.sync_data().await?;
+
+        Ok(staged)
+    }
+
+    /// Repairs every torn page of the database files at the given paths (and of the data files next
+    /// to them) from its latest copy in their double-write buffer file, returning the number of
+    /// pages that were repaired.
+    ///
+    /// This must be called before the database files are opened by the storage manager.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the double-write buffer file or the database files cannot be read or
+    /// written.
+    pub(crate) fn recover(paths: &[PathBuf], page_size: usize) -> Result<usize> {
+        if paths.is_empty() {
+            return Ok(0);
+        }
+
+        let path = doublewrite_buffer_path(paths);
+        let mut buf = Vec::new();
+        match File::open(&path) {
+            Ok(mut file) => file.read_to_end(&mut buf)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        // Only the latest copy of every page is relevant.
+        let mut latest: HashMap<u64, (u64, &[u8])> = HashMap::new();
+        for slot in buf.chunks_exact(SLOT_HEADER_LEN + page_size) {
+            let Some((pid, sequence, image)) = decode_slot(slot, page_size) else {
+                continue;
+            };
+
+            let entry = latest.entry(pid.as_u64()).or_insert((sequence, image));
+            if entry.0 < sequence {
+                *entry = (sequence, image);
+            }
+        }
+
+        let mut files = HomeFiles::open(paths, page_size)?;
+        let mut repaired = 0;
+        let mut home = vec![0; page_size];
+        for (&pid, &(_, image)) in &latest {
+            let Some((file, offset)) = files.locate(PageId::new(pid))? else {
+                continue;
+            };
+
+            let torn = match file.read_exact_at(&mut home, offset) {
+                Ok(()) => checksum::verify(&home).is_err(),
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => true,
+                Err(e) => return Err(e),
+            };
+            if torn && checksum::verify(image).is_ok() {
+                file.write_all_at(image, offset)?;
+                repaired += 1;
+            }
+        }
+
+        if repaired > 0 {
+            files.sync_all()?;
+        }
+
+        // Every copy is either redundant or was just written back, so the slots can be discarded.
+        let file = OpenOptions::new().write(true).open(&path)?;
+        file.set_len(0)?;
+        file.sync_all()?;
+
+        Ok(repaired)
+    }
+}
+
+/// Encodes a slot that holds a copy of a page's image.
+fn encode_slot(pid: PageId, sequence: u64, image: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(SLOT_HEADER_LEN + image.len());
+
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&pid.as_u64().to_le_bytes());
+    buf.extend_from_slice(&sequence.to_le_bytes());
+    buf.extend_from_slice(&(image.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(image);
+
+    let crc = checksum::crc32c(&buf);
+    buf[SLOT_HEADER_LEN - 4..SLOT_HEADER_LEN].copy_from_slice(&crc.to_le_bytes());
+
+    buf
+}
+
+/// Decodes a slot into the page's ID, the sequence number, and the page's image.
+///
+/// Returns `None` if the slot does not hold a complete copy of a page, which is the case if it was
+/// never used, or if the buffer pool crashed while writing it.
+fn decode_slot(slot: &[u8], page_size: usize) -> Option<(PageId, u64, &[u8])> {
+    let (header, image) = slot.split_at(SLOT_HEADER_LEN);
+    if header[..MAGIC.len()] != MAGIC {
+        return None;
+    }
+
+    let word = |i: usize| u64::from_le_bytes(header[8 * i..8 * (i + 1)].try_into().unwrap());
+    let half = |i: usize| u32::from_le_bytes(header[4 * i..4 * (i + 1)].try_into().unwrap());
+    if half(6) as usize != page_size {
+        return None;
+    }
+
+    let mut unsealed = slot.to_vec();
+    unsealed[SLOT_HEADER_LEN - 4..SLOT_HEADER_LEN].fill(0);
+    if checksum::crc32c(&unsealed) != half(7) {
+        return None;
+    }
+
+    Some((PageId::new(word(1)), word(2), image))
+}
+
+/// The slots of the double-write buffer that hold the copies of pages that are being written out,
+/// which are freed when this is dropped.
+#[derive(Debug)]
+pub(crate) struct StagedSlots<'a> {
+    /// The double-write buffer that the slots belong to.
+    buffer: &'a DoublewriteBuffer,
+
+    /// The indices of the slots.
+    slots: Vec<usize>,
+
+    /// The permits of the slots, which are returned after the slots are freed.
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Drop for StagedSlots<'_> {
+    fn drop(&mut self) {
+        self.buffer
+            .free_slots
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .append(&mut self.slots);
+    }
+}
@@ -1,29 +1,47 @@
 //! This module contains the type definitions and implementation for the [`Frame`] struct.
 //!
-//! A [`Frame`] is intended to hold [`PAGE_SIZE`] bytes of data, and is also intended to be shared
+//! A [`Frame`] is intended to hold a single page of data, and is also intended to be shared
 //! with the the kernel to avoid unnecessary `memcpy`s from the kernel's internal buffers into
 //! user-space buffers.
 
-use crate::storage::frame_group::{EvictionState, FrameGroup, FRAME_GROUP_SIZE};
+use crate::storage::frame_group::{EvictionState, FrameGroup};
+use crate::storage::{decode_page, IoKind, PageCodec, ScratchBuf};
 use crate::{
     bpm::BufferPoolManager,
-    page::{Page, PAGE_SIZE},
+    page::{Page, PageId},
 };
 use std::{
-    ops::{Deref, DerefMut},
-    sync::Arc,
+    cell::RefCell,
+    io::Result,
+    mem,
+    ops::{Deref, DerefMut, Range},
+    ptr,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
 };
+use tokio::sync::oneshot;
 use tokio_uring::buf::{IoBuf, IoBufMut};
 
+std::thread_local! {
+    /// The `Frame`s of interrupted I/O operations that could not be recovered when they were
+    /// dropped, either because the kernel could still be using their registered buffers or because
+    /// their pages were still locked.
+    static INTERRUPTED: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
 /// An owned buffer frame, intended to be shared between user and kernel space.
 #[derive(Debug)]
 pub(crate) struct Frame {
     /// The unique ID of this `Frame`.
     ///
-    /// Each `Frame` is assigned a monotonically increasing ID, where every chunk of
-    /// [`FRAME_GROUP_SIZE`] `Frame`s represent a single [`FrameGroup`].
+    /// Each `Frame` is assigned a monotonically increasing ID, where every chunk of `group_size`
+    /// `Frame`s represent a single [`FrameGroup`].
     frame_id: usize,
 
+    /// The number of `Frame`s in every [`FrameGroup`] (except possibly the last one), which the
+    /// frame ID is divided by to find the group of this `Frame`.
+    group_size: usize,
+
     /// The owner of this `Frame`, if one exists.
     ///
     /// If a [`Page`] "owns" this `Frame` (the `Frame` holds the [`Page`]s data), then it is the
@@ -38,6 +56,64 @@ pub(crate) struct Frame {
     /// absolutely necessary.
     dirty: bool,
 
+    /// If the background flusher wrote this `Frame`'s data back and the data has not been modified
+    /// since, whether the [`Page`] was hot when it was written back.
+    ///
+    /// This is used to measure how often write-backs are wasted on pages that are re-dirtied.
+    written_back: Option<bool>,
+
+    /// The instant that this `Frame` most recently went from clean to dirty, if it is dirty.
+    dirtied_at: Option<Instant>,
+
+    /// The range of the page's data that was modified since the `Frame` was last clean, or `None`
+    /// if it is clean.
+    dirty_range: Option<Range<usize>>,
+
+    /// The index of this `Frame`'s buffer in the table of buffers registered with `io_uring`, or
+    /// `None` if the buffer is not registered.
+    ///
+    /// This is assigned once when the frame's arena is laid out, so that the `ReadFixed` and
+    /// `WriteFixed` operations do not have to look up the index on every operation.
+    buf_index: Option<usize>,
+
+    /// The log sequence number of the latest log record that modified the page held by this
+    /// `Frame`, or `0` if none was set since the page was loaded.
+    lsn: u64,
+
+    /// The range of the buffer that holds the page's user data.
+    ///
+    /// The rest of the buffer is reserved for metadata, such as the page's checksum if checksums
+    /// are enabled (see [`BufferPoolManagerConfig::user_region`]).
+    ///
+    /// [`BufferPoolManagerConfig::user_region`]: crate::BufferPoolManagerConfig::user_region
+    data: Range<usize>,
+
+    /// The kind of I/O operation that this `Frame` is currently being used for, if any.
+    ///
+    /// If the future of that operation is dropped before it completes (for example, because the
+    /// runtime shuts down while the operation is in flight), the `Frame` is dropped as well once
+    /// the kernel is done with it, and this tells its [`Drop`] implementation how to recover it.
+    in_flight: Option<IoKind>,
+
+    /// Whether the kernel may keep using this `Frame`'s registered buffer if the future of its I/O
+    /// operation is dropped, see [`Frame::begin_fixed_io`].
+    fixed_io: bool,
+
+    /// Where to send this `Frame` if it is dropped while it is being used for I/O, instead of
+    /// recovering it in place.
+    ///
+    /// This is set while an [`IoDriver`](super::IoDriver) thread performs I/O with this `Frame` on
+    /// behalf of another thread, so that the frame goes back to that thread even if the driver
+    /// thread drops the operation.
+    recover_to: Option<oneshot::Sender<Frame>>,
+
+    /// The data of the page from before it was encoded by the registered [`PageCodec`] for a
+    /// write, if the write is in flight.
+    ///
+    /// The data is copied back into the buffer once the write completes, or if the `Frame` is
+    /// dropped before then, so that the page never appears encoded in memory.
+    plain: Option<ScratchBuf>,
+
     /// The buffer that this `Frame` holds ownership over.
     ///
     /// Since `Frame` is not [`Clone`]able, this `Frame` is guaranteed to have exclusive access to
@@ -46,21 +122,201 @@ pub(crate) struct Frame {
 }
 
 impl Frame {
-    /// Creates a new `Frame` given a static mutable buffer and a frame ID.
+    /// Creates a new `Frame` given a frame ID, the size of the frame groups, a static mutable
+    /// buffer, the index of the buffer in the table of registered buffers, and the range of the
+    /// buffer that holds user data.
     ///
     /// All `Frame`s are initialized without any page owner.
-    pub(crate) fn new(frame_id: usize, buf: &'static mut [u8]) -> Self {
+    pub(crate) fn new(
+        frame_id: usize,
+        group_size: usize,
+        buf: &'static mut [u8],
+        buf_index: Option<usize>,
+        data: Range<usize>,
+    ) -> Self {
+        assert!(data.start <= data.end && data.end <= buf.len());
+
         Self {
             frame_id,
+            group_size,
+            data,
             buf,
             dirty: false,
+            dirty_range: None,
+            written_back: None,
+            dirtied_at: None,
+            buf_index,
+            lsn: 0,
+            in_flight: None,
+            fixed_io: false,
+            recover_to: None,
+            plain: None,
             page_owner: None,
         }
     }
 
+    /// Marks this `Frame` as being used for an I/O operation of the given kind, until
+    /// [`Frame::end_io`] is called.
+    ///
+    /// If the `Frame` is dropped in the meantime, it is recovered instead of being leaked: the
+    /// frame of an interrupted read is returned to its frame group, and the frame of an interrupted
+    /// write is given back to its page (which stays dirty). The kernel is done with an owned
+    /// buffer by the time the `Frame` could be dropped, but operations on registered buffers must
+    /// also be marked with [`Frame::begin_fixed_io`].
+    pub(crate) fn begin_io(&mut self, kind: IoKind) {
+        self.in_flight = Some(kind);
+    }
+
+    /// Marks the I/O operation started with [`Frame::begin_io`] as completed.
+    pub(crate) fn end_io(&mut self) {
+        self.in_flight = None;
+    }
+
+    /// Marks this `Frame`'s registered buffer as being used by the kernel for the I/O operation
+    /// started with [`Frame::begin_io`], until [`Frame::end_fixed_io`] is called.
+    ///
+    /// The kernel may keep using a registered buffer after the future of the operation is dropped,
+    /// so if the `Frame` is dropped in the meantime, it is only recovered once the thread's runtime
+    /// has shut down, which waits for the kernel to finish every operation that is still in flight
+    /// (see [`Frame::recover_interrupted`]).
+    pub(crate) fn begin_fixed_io(&mut self) {
+        self.fixed_io = true;
+    }
+
+    /// Marks the operation on the registered buffer started with [`Frame::begin_fixed_io`] as
+    /// completed.
+    pub(crate) fn end_fixed_io(&mut self) {
+        self.fixed_io = false;
+    }
+
+    /// Recovers every `Frame` of an interrupted I/O operation that could not be recovered when it
+    /// was dropped on this thread.
+    ///
+    /// This must only be called once the runtime that ran the interrupted operations was dropped,
+    /// which waits for the kernel to finish with every registered buffer and releases every page
+    /// lock that was held by the runtime's tasks.
+    pub(crate) fn recover_interrupted() {
+        for mut frame in INTERRUPTED.with_borrow_mut(mem::take) {
+            // Dropping the frame now recovers it like any other interrupted frame.
+            frame.end_fixed_io();
+        }
+    }
+
+    /// Takes the `Frame` of an interrupted write of `page` that could not be given back to the
+    /// page when it was dropped on this thread, because the page was still locked.
+    ///
+    /// This lets a [`WritePageGuard`](crate::page::WritePageGuard) take back the frame of a flush
+    /// whose future was dropped before the guard itself.
+    pub(crate) fn take_interrupted(page: &Page) -> Option<Frame> {
+        let mut frame = INTERRUPTED
+            .try_with(|frames| {
+                let mut frames = frames.borrow_mut();
+                let index = frames.iter().position(|frame| {
+                    !frame.fixed_io
+                        && frame
+                            .page_owner
+                            .as_ref()
+                            .is_some_and(|owner| ptr::eq(Arc::as_ptr(owner), page))
+                })?;
+                Some(frames.swap_remove(index))
+            })
+            .ok()??;
+
+        frame.end_io();
+        Some(frame)
+    }
+
+    /// Keeps this `Frame` of an interrupted I/O operation until it can be recovered, see
+    /// [`Frame::recover_interrupted`]. If the thread is exiting, the frame is leaked instead.
+    fn defer_recovery(self) {
+        let mut frame = Some(self);
+        let _ = INTERRUPTED.try_with(|frames| frames.borrow_mut().extend(frame.take()));
+        mem::forget(frame);
+    }
+
+    /// Makes this `Frame` be sent to the returned receiver if it is dropped, until
+    /// [`Frame::clear_recovery`] is called, instead of being recovered like described in
+    /// [`Frame::begin_io`].
+    pub(crate) fn recover_to_channel(&mut self) -> oneshot::Receiver<Frame> {
+        let (sender, receiver) = oneshot::channel();
+        self.recover_to = Some(sender);
+        receiver
+    }
+
+    /// Stops sending this `Frame` to the receiver returned by [`Frame::recover_to_channel`].
+    pub(crate) fn clear_recovery(&mut self) {
+        self.recover_to = None;
+    }
+
+    /// Encodes the first `len` bytes of the buffer in place with `codec` for a write of page `pid`,
+    /// keeping a copy of the data until [`Frame::restore_plain`] is called.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the codec fails, in which case the buffer is left unchanged.
+    pub(crate) fn encode(&mut self, pid: PageId, codec: &dyn PageCodec, len: usize) -> Result<()> {
+        debug_assert!(self.plain.is_none(), "Tried to encode {pid} twice");
+
+        let plain = ScratchBuf::copy_of(&self.buf[..len]);
+        if let Err(e) = codec.encode(pid, &plain, &mut self.buf[..len]) {
+            self.buf[..len].copy_from_slice(&plain);
+            return Err(e);
+        }
+
+        self.plain = Some(plain);
+        Ok(())
+    }
+
+    /// Copies the data from before [`Frame::encode`] back into the buffer, if the buffer is
+    /// encoded.
+    pub(crate) fn restore_plain(&mut self) {
+        if let Some(plain) = self.plain.take() {
+            self.buf[..plain.len()].copy_from_slice(&plain);
+        }
+    }
+
+    /// Decodes the first `len` bytes of the buffer in place with `codec` after a read of page
+    /// `pid`.
+    ///
+    /// A page that is entirely zeroed has never been written, so it is left as it is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the codec fails, in which case the buffer's contents are unspecified.
+    pub(crate) fn decode(&mut self, pid: PageId, codec: &dyn PageCodec, len: usize) -> Result<()> {
+        decode_page(pid, codec, &mut self.buf[..len])
+    }
+
+    /// Gets the unique ID of this frame.
+    pub(crate) fn frame_id(&self) -> usize {
+        self.frame_id
+    }
+
+    /// Gets the index of this frame's buffer in the table of registered buffers, if it is
+    /// registered.
+    pub(crate) fn buf_index(&self) -> Option<usize> {
+        self.buf_index
+    }
+
+    /// Gets the user data of the page held by this frame, excluding any reserved bytes.
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.buf[self.data.clone()]
+    }
+
+    /// Gets the user data of the page held by this frame mutably, excluding any reserved bytes.
+    pub(crate) fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[self.data.clone()]
+    }
+
     /// Gets the frame group ID of the group that this frame belongs to.
     pub(crate) fn group_id(&self) -> usize {
-        self.frame_id / FRAME_GROUP_SIZE
+        self.frame_id / self.group_size
+    }
+
+    /// Gets the index of this frame within its frame group, which indexes the group's eviction
+    /// states.
+    pub(crate) fn group_index(&self) -> usize {
+        self.frame_id % self.group_size
     }
 
     /// Gets an [`Arc`] to the [`FrameGroup`] that this frame belongs to.
@@ -70,30 +326,105 @@ impl Frame {
         bpm.get_frame_group(self.group_id())
     }
 
+    /// Gets the owning [`Page`] of this `Frame`, if one exists.
+    pub(crate) fn page_owner(&self) -> Option<&Arc<Page>> {
+        self.page_owner.as_ref()
+    }
+
     /// Replaces the owning [`Page`] of this `Frame` with another [`Page`].
     pub(crate) fn replace_page_owner(&mut self, page: Arc<Page>) -> Option<Arc<Page>> {
         self.page_owner.replace(page)
     }
 
-    /// Replaces the owning [`Page`] of this `Frame` with `None`.
+    /// Replaces the owning [`Page`] of this `Frame` with `None`, releasing the frame's charge to
+    /// the page's tenant (if any).
     pub(crate) fn evict_page_owner(&mut self) -> Option<Arc<Page>> {
-        self.page_owner.take()
+        self.written_back = None;
+        self.lsn = 0;
+        self.dirty_range = None;
+
+        let page = self.page_owner.take();
+        if let Some(page) = &page {
+            page.release_charge();
+        }
+        page
     }
 
     /// Updates the eviction state after this frame has been accessed.
     ///
-    /// This function will simply update the [`EvictionState`] of the `Frame` to
-    /// [`Hot`](EvictionState::Hot).
-    pub(crate) fn record_access(&self, page: Arc<Page>) {
+    /// This function will update the [`EvictionState`] of the `Frame` to
+    /// [`Hot`](EvictionState::Hot), and let the replacer of the `Frame`'s group know about the
+    /// access.
+    ///
+    /// If the buffer pool manager is configured with a
+    /// [`hot_access_threshold`](crate::BufferPoolManagerConfig::hot_access_threshold) and the
+    /// page's access was already recorded within the threshold, this does nothing, so that the
+    /// lock on the eviction states is not taken.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the eviction state lock was poisoned and the buffer pool manager is
+    /// configured to propagate poisoning errors.
+    pub(crate) fn record_access(&self, page: &Arc<Page>) -> Result<()> {
+        let bpm = BufferPoolManager::get();
+        let threshold = bpm.hot_access_threshold();
+        if threshold.is_some_and(|threshold| page.was_recorded_within(threshold)) {
+            bpm.stats.record_skipped_access_record();
+            return Ok(());
+        }
+
         let group = self.group();
-        let index = self.frame_id % FRAME_GROUP_SIZE;
+        let index = self.group_index();
 
-        let mut eviction_guard = group
-            .eviction_states
-            .lock()
-            .expect("Fatal: `EvictionState` lock was poisoned somehow");
+        let mut eviction_guard = group.lock_eviction_states()?;
+
+        let speculative = match &eviction_guard[index] {
+            EvictionState::Speculative(prefetched, _) => Arc::ptr_eq(prefetched, page),
+            _ => false,
+        };
+        if speculative {
+            bpm.stats.record_speculative_hit();
+        }
 
         eviction_guard[index] = EvictionState::Hot(page.clone());
+        eviction_guard.replacer.record_access(index, page.pid);
+
+        if threshold.is_some() {
+            page.set_recorded();
+        }
+
+        Ok(())
+    }
+
+    /// Records that `page` was loaded into this frame by a prefetch, which makes the frame
+    /// [`Speculative`](EvictionState::Speculative) until `expires` or until the page is accessed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the eviction state lock was poisoned and the buffer pool manager is
+    /// configured to propagate poisoning errors.
+    pub(crate) fn record_prefetch(&self, page: &Arc<Page>, expires: Instant) -> Result<()> {
+        let group = self.group();
+        let index = self.group_index();
+
+        // The first access must be recorded to confirm the prefetch, even if the page was
+        // recorded recently before it was evicted.
+        page.clear_recorded();
+
+        let mut eviction_guard = group.lock_eviction_states()?;
+        eviction_guard[index] = EvictionState::Speculative(page.clone(), expires);
+
+        Ok(())
+    }
+
+    /// Gets the log sequence number of the latest log record that modified the page.
+    pub(crate) fn lsn(&self) -> u64 {
+        self.lsn
+    }
+
+    /// Sets the log sequence number of the latest log record that modified the page.
+    pub(crate) fn set_lsn(&mut self, lsn: u64) {
+        self.lsn = lsn;
     }
 
     /// Checks if the dirty bit is set.
@@ -102,13 +433,149 @@ impl Frame {
     }
 
     /// Sets the dirty bit.
+    ///
+    /// If the `Frame` is already dirty, the update will be written out together with the previous
+    /// updates, which is recorded as a coalesced write.
     pub(crate) fn set_dirty(&mut self) {
+        let write_backs = &BufferPoolManager::get().write_backs;
+
+        if self.dirty {
+            write_backs.record_coalesced_write();
+        } else {
+            self.group()
+                .num_dirty_frames
+                .fetch_add(1, Ordering::Release);
+
+            if let Some(hot) = self.written_back.take() {
+                write_backs.record_redirty(hot);
+            }
+
+            self.dirtied_at = Some(Instant::now());
+        }
         self.dirty = true;
     }
 
+    /// Gets the range of the page's data that was modified since the `Frame` was last clean, or
+    /// `None` if it is clean.
+    pub(crate) fn dirty_range(&self) -> Option<Range<usize>> {
+        self.dirty_range.clone()
+    }
+
+    /// Replaces the range of the page's data that was modified since the `Frame` was last clean.
+    pub(crate) fn set_dirty_range(&mut self, range: Option<Range<usize>>) {
+        self.dirty_range = range;
+    }
+
+    /// Extends the range of the page's data that was modified since the `Frame` was last clean to
+    /// also cover `range`.
+    pub(crate) fn extend_dirty_range(&mut self, range: Range<usize>) {
+        self.dirty_range = Some(match self.dirty_range.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+
+    /// Checks if the `Frame` went from clean to dirty less than `window` ago.
+    pub(crate) fn is_recently_dirtied(&self, window: Duration) -> bool {
+        self.dirtied_at
+            .is_some_and(|dirtied_at| dirtied_at.elapsed() < window)
+    }
+
     /// Clears the dirty bit.
     pub(crate) fn clear_dirty(&mut self) {
+        if self.dirty {
+            self.group()
+                .num_dirty_frames
+                .fetch_sub(1, Ordering::Release);
+        }
         self.dirty = false;
+        self.dirty_range = None;
+        self.written_back = None;
+        self.dirtied_at = None;
+    }
+
+    /// Records that the background flusher just wrote this `Frame`'s data back, and whether the
+    /// [`Page`] was hot at the time.
+    pub(crate) fn mark_written_back(&mut self, hot: bool) {
+        self.written_back = Some(hot);
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        let in_flight = self.in_flight.take();
+        let recover_to = self.recover_to.take();
+        if in_flight.is_none() && recover_to.is_none() {
+            return;
+        }
+
+        // The buffer is only ever owned by one `Frame`, so we move it into a new one.
+        let mut frame = Frame::new(
+            self.frame_id,
+            self.group_size,
+            mem::take(&mut self.buf),
+            self.buf_index,
+            self.data.clone(),
+        );
+        frame.dirty = self.dirty;
+        frame.dirty_range = self.dirty_range.take();
+        frame.dirtied_at = self.dirtied_at;
+        frame.lsn = self.lsn;
+        frame.page_owner = self.page_owner.take();
+        frame.plain = self.plain.take();
+
+        // The kernel may still be using the registered buffer, so the frame can only be recovered
+        // once the runtime has shut down.
+        if self.fixed_io {
+            frame.in_flight = in_flight;
+            frame.recover_to = recover_to;
+            frame.fixed_io = true;
+            return frame.defer_recovery();
+        }
+
+        // The data must be restored even if the write was interrupted.
+        frame.restore_plain();
+
+        // The thread that a driver thread performed the I/O for decides what to do with the frame.
+        if let Some(recover_to) = recover_to {
+            match recover_to.send(frame) {
+                Ok(()) => return,
+                Err(unsent) => frame = unsent,
+            }
+        }
+
+        let Some(kind) = in_flight else {
+            return;
+        };
+        if !BufferPoolManager::is_initialized() {
+            return;
+        }
+
+        let Some(page) = frame.page_owner.clone() else {
+            return frame.group().try_release_frame(frame);
+        };
+
+        // The write was interrupted, so if no one else reloaded the page in the meantime, its
+        // data is still only in this frame.
+        if kind == IoKind::Write {
+            let Ok(mut guard) = page.frame.try_write() else {
+                // The page is most likely locked by the write guard that the frame was taken from,
+                // which takes the frame back once it is dropped (see `Frame::take_interrupted`).
+                frame.in_flight = Some(kind);
+                return frame.defer_recovery();
+            };
+            if guard.is_none() {
+                page.set_loaded(&frame);
+                guard.replace(frame);
+                return;
+            }
+        }
+
+        if frame.is_dirty() {
+            frame.clear_dirty();
+        }
+        frame.evict_page_owner();
+        frame.group().try_release_frame(frame);
     }
 }
 
@@ -140,11 +607,11 @@ unsafe impl IoBuf for Frame {
     }
 
     fn bytes_init(&self) -> usize {
-        PAGE_SIZE
+        self.buf.len()
     }
 
     fn bytes_total(&self) -> usize {
-        PAGE_SIZE
+        self.buf.len()
     }
 }
 
@@ -4,18 +4,171 @@
 //! pre-determined groups of frames without having to manage which logical pages are in memory or
 //! not in memory.
 
-use crate::page::Page;
+use crate::bpm::BufferPoolManager;
+use crate::config::PoisonPolicy;
+use crate::error::{BufferPoolFull, Poisoned};
+use crate::events::PageEvent;
+use crate::executor;
+use crate::metrics::LatencyKind;
+use crate::page::{Page, PageId};
 use crate::storage::frame::Frame;
-use crate::storage::storage_manager::StorageManager;
+use crate::storage::replacer::{ReplacementCandidate, Replacer};
+use crate::storage::storage_manager::{StorageManager, StorageManagerHandle};
+use crate::tenant::TenantId;
 use async_channel::{Receiver, Sender};
-use std::io::Result;
+use std::future::Future;
+use std::io::{Error, Result};
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
-    Arc, Mutex,
+    Arc, Mutex, MutexGuard,
 };
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLockWriteGuard;
 
-/// The number of frames in a [`FrameGroup`].
-pub(crate) const FRAME_GROUP_SIZE: usize = 64;
+/// The number of frames in a [`FrameGroup`], unless configured otherwise with
+/// [`BufferPoolManagerConfig::frame_group_size`](crate::BufferPoolManagerConfig::frame_group_size).
+pub(crate) const DEFAULT_FRAME_GROUP_SIZE: usize = 64;
+
+/// How often a task that is waiting for a free frame retries evicting a page.
+const FREE_FRAME_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The number of pages that [`FrameGroup::cool_frames`] evicts between yields to the runtime.
+const EVICTION_YIELD_INTERVAL: usize = 8;
+
+/// A dirty page that [`FrameGroup::cool_frames`] took the frame of, and is waiting to be written
+/// back along with the rest of its batch.
+struct PendingWriteBack<'a> {
+    /// The index of the page's frame in the frame group.
+    index: usize,
+
+    /// The page that is being evicted.
+    page: &'a Arc<Page>,
+
+    /// Whether the page was a prefetch that was never accessed.
+    speculative: bool,
+
+    /// The write guard on the page's frame, which keeps the page from being accessed until it has
+    /// been written back.
+    guard: RwLockWriteGuard<'a, Option<Frame>>,
+}
+
+/// A [`Frame`] that was taken from its page to be evicted.
+enum EvictableFrame {
+    /// A frame whose data is already on persistent storage.
+    Clean(CleanFrame),
+
+    /// A frame whose data has to be written back before the frame can be reused.
+    Dirty(DirtyFrame),
+}
+
+impl From<Frame> for EvictableFrame {
+    fn from(frame: Frame) -> Self {
+        if frame.is_dirty() {
+            Self::Dirty(DirtyFrame(frame))
+        } else {
+            Self::Clean(CleanFrame(frame))
+        }
+    }
+}
+
+/// A [`Frame`] that was taken from its page to be evicted, and can be reused without any I/O.
+struct CleanFrame(Frame);
+
+impl CleanFrame {
+    /// Detaches the frame from its page, so that it can be reused for another page.
+    fn evict(self) -> Frame {
+        let mut frame = self.0;
+        frame
+            .evict_page_owner()
+            .expect("Tried to evict a frame that had no page owner");
+
+        frame
+    }
+}
+
+/// A [`Frame`] that was taken from its page to be evicted, and has to be written back before it can
+/// be reused.
+struct DirtyFrame(Frame);
+
+impl DirtyFrame {
+    /// Writes the data of `frames` back to the consecutive pages starting at `start`, which makes
+    /// them clean.
+    ///
+    /// A single frame is written on its own, while several frames are written together with
+    /// [`StorageManagerHandle::write_range_from`], which submits a single vectored write per drive.
+    /// The latency of a successful write of the run is recorded once, no matter how many frames it
+    /// has.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error along with every frame, which are all still dirty, if any write fails.
+    async fn write_back_run(
+        frames: Vec<Self>,
+        sm: &StorageManagerHandle,
+        start: PageId,
+    ) -> std::result::Result<Vec<CleanFrame>, (Error, Vec<Frame>)> {
+        let mut frames: Vec<Frame> = frames.into_iter().map(|frame| frame.0).collect();
+
+        let latencies = &BufferPoolManager::get().latencies;
+        let timer = latencies.start();
+
+        let (res, frames) = if frames.len() == 1 {
+            let frame = frames.pop().expect("The run has a frame");
+            let (res, frame) = sm.write_from(start, frame).await;
+            (res, vec![frame])
+        } else {
+            sm.write_range_from(start, frames).await
+        };
+
+        match res {
+            Ok(()) => {
+                latencies.record(LatencyKind::EvictionWrite, timer);
+                Ok(frames
+                    .into_iter()
+                    .map(|mut frame| {
+                        frame.clear_dirty();
+                        CleanFrame(frame)
+                    })
+                    .collect())
+            }
+            Err(e) => Err((e, frames)),
+        }
+    }
+}
+
+/// Awaits every future in `futures` concurrently on the current task, returning their outputs in
+/// order.
+pub(crate) async fn join_all<F: Future>(futures: Vec<F>) -> Vec<F::Output> {
+    let mut futures: Vec<Pin<Box<F>>> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+
+    std::future::poll_fn(|cx| {
+        let mut done = true;
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(value) => *output = Some(value),
+                    Poll::Pending => done = false,
+                }
+            }
+        }
+
+        if done {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+
+    outputs
+        .into_iter()
+        .map(|output| output.expect("Every future has completed"))
+        .collect()
+}
 
 /// A fixed group of frames.
 ///
@@ -26,60 +179,91 @@ pub(crate) const FRAME_GROUP_SIZE: usize = 64;
 ///
 /// By grouping frames together as such, we can say that a [`Frame`] can be in one of three states:
 /// - A [`Frame`] can be owned by a [`Page`]
-///     - The [`Frame`]'s [`EvictionState`] can be either [`Hot`] or [`Cool`]
+///     - The [`Frame`]'s [`EvictionState`] can be either [`Hot`], [`Cool`], or [`Claimed`]
 /// - A [`Frame`] can have an active task trying to evict the data the [`Frame`] holds
-///     - The [`Frame`]'s [`EvictionState`] can be either [`Cool`] or [`Cold`]
+///     - The [`Frame`]'s [`EvictionState`] can be either [`Claimed`] or [`Cold`]
 /// - A [`Frame`] can be in the free list of frames in a `FrameGroup`
 ///     - The [`Frame`]'s [`EvictionState`] _must_ be [`Cold`]
 ///
 /// [`Hot`]: EvictionState::Hot
 /// [`Cool`]: EvictionState::Cool
+/// [`Claimed`]: EvictionState::Claimed
 /// [`Cold`]: EvictionState::Cold
 #[derive(Debug)]
 pub(crate) struct FrameGroup {
     /// The unique ID of this `FrameGroup`.
-    #[allow(dead_code)]
     pub(crate) group_id: usize,
 
+    /// The number of [`Frame`]s that belong to this `FrameGroup`, which is the configured
+    /// [frame group size](BufferPoolManager::frame_group_size) for every group except possibly the
+    /// last one.
+    pub(crate) num_frames: usize,
+
     /// The states of the [`Frame`]s that belong to this `FrameGroup`.
     ///
     /// Note that we use a blocking mutex here because we do not need to hold the lock across any
     /// `.await` points.
-    pub(crate) eviction_states: Mutex<[EvictionState; FRAME_GROUP_SIZE]>,
+    pub(crate) eviction_states: Mutex<EvictionStates>,
 
     /// The number of free frames in the free list.
     pub(crate) num_free_frames: AtomicUsize,
 
+    /// The number of [`Frame`]s in this `FrameGroup` that are currently dirty.
+    pub(crate) num_dirty_frames: AtomicUsize,
+
     /// An asynchronous channel of free [`Frame`]s. Behaves as the free list of frames.
+    ///
+    /// The free list may also hold frames that another `FrameGroup` lent to this one (see
+    /// `FrameGroup::lend_free_frames`). A lent frame still belongs to its own group: once the page
+    /// that was loaded into it is evicted, the frame goes back to its own group's free list.
     pub(crate) free_list: (Sender<Frame>, Receiver<Frame>),
 }
 
 impl FrameGroup {
-    /// Creates a new [`FrameGroup`] given an iterator of [`FRAME_GROUP_SIZE`] frames.
+    /// Creates a new [`FrameGroup`] given an iterator of at most `group_size` frames.
     ///
     /// # Panics
     ///
-    /// This function will panic if the iterator does not contain exactly [`FRAME_GROUP_SIZE`]
+    /// This function will panic if the iterator is empty or contains more than `group_size`
     /// frames.
-    pub(crate) fn new<I>(group_id: usize, frames: I) -> Self
+    pub(crate) fn new<I>(
+        group_id: usize,
+        group_size: usize,
+        frames: I,
+        replacer: Box<dyn Replacer>,
+    ) -> Self
     where
         I: IntoIterator<Item = Frame>,
     {
-        let (rx, tx) = async_channel::bounded(FRAME_GROUP_SIZE);
+        // The free list is unbounded, since it may hold frames lent by other groups on top of this
+        // group's own frames.
+        let (rx, tx) = async_channel::unbounded();
 
-        let mut counter = 0;
+        let mut num_frames = 0;
         for frame in frames {
-            rx.send_blocking(frame).expect("Channel cannot be closed");
-            counter += 1;
+            rx.try_send(frame)
+                .expect("The free list channel cannot be closed");
+            num_frames += 1;
         }
-        assert_eq!(counter, FRAME_GROUP_SIZE);
+        assert_ne!(num_frames, 0, "A frame group needs at least one frame");
+        assert!(
+            num_frames <= group_size,
+            "A frame group holds at most {group_size} frames"
+        );
 
-        let eviction_states = core::array::from_fn(|_| EvictionState::default());
+        // Every frame of the group is indexed by its position within the group, so the last group
+        // only needs as many states as it has frames.
+        let eviction_states = EvictionStates {
+            states: (0..num_frames).map(|_| EvictionState::default()).collect(),
+            replacer,
+        };
 
         Self {
             group_id,
+            num_frames,
             eviction_states: Mutex::new(eviction_states),
-            num_free_frames: AtomicUsize::new(FRAME_GROUP_SIZE),
+            num_free_frames: AtomicUsize::new(num_frames),
+            num_dirty_frames: AtomicUsize::new(0),
             free_list: (rx, tx),
         }
     }
@@ -87,41 +271,150 @@ impl FrameGroup {
     /// Gets a free frame in this `FrameGroup`.
     ///
     /// This function will evict other frames in this `FrameGroup` if there are no free frames
-    /// available.
+    /// available, preferring a clean page that is already cool (which needs no I/O) over running a
+    /// cooling pass. If no frame can be evicted because every page is in use, this parks the task
+    /// until a frame is released, retrying the eviction every [`FREE_FRAME_RETRY_INTERVAL`] in case
+    /// a page was unpinned in the meantime.
     ///
     /// # Errors
     ///
-    /// Returns an error if an I/O error occurs.
+    /// Returns an error if an I/O error occurs, or a [`BufferPoolFull`] error if the configured
+    /// [free frame timeout](crate::BufferPoolManagerConfig::free_frame_timeout) expires.
     pub(crate) async fn get_free_frame(&self) -> Result<Frame> {
+        if let Some(frame) = self.try_get_free_frame() {
+            return Ok(frame);
+        }
+
+        let bpm = BufferPoolManager::get();
+        bpm.stats.record_free_frame_wait();
+
+        let start = Instant::now();
+        let deadline = bpm.free_frame_timeout().map(|timeout| start + timeout);
+
         loop {
-            if let Ok(frame) = self.free_list.1.try_recv() {
-                self.num_free_frames.fetch_sub(1, Ordering::Release);
+            // Reclaiming a clean page that is already cool does not need any I/O, so it is much
+            // cheaper than a cooling pass that may have to write dirty pages back.
+            if let Some(frame) = self.try_reclaim_clean_frame()? {
+                return Ok(frame);
+            }
+
+            self.cool_frames(None).await?;
+
+            if let Some(frame) = self.try_get_free_frame() {
                 return Ok(frame);
             }
 
-            self.cool_frames().await?;
+            let mut retry_at = Instant::now() + FREE_FRAME_RETRY_INTERVAL;
+            if let Some(deadline) = deadline {
+                if Instant::now() >= deadline {
+                    return Err(BufferPoolFull::new(self.group_id, start.elapsed()).into());
+                }
+                retry_at = retry_at.min(deadline);
+            }
+
+            // Receiving from the free list is cancel safe, so no frame is lost if we time out.
+            tokio::select! {
+                frame = self.free_list.1.recv() => {
+                    self.num_free_frames.fetch_sub(1, Ordering::Release);
+                    return Ok(frame.expect("The free list channel cannot be closed"));
+                }
+                _ = executor::sleep_until(retry_at) => {}
+            }
         }
     }
 
-    /// Runs the second chance / clock algorithm on all of the [`Frame`]s in this `FrameGroup`, and
-    /// then evicts all of the frames that have been cooled twice.
+    /// Gets a free frame in this `FrameGroup` if one is available, without evicting anything.
+    pub(crate) fn try_get_free_frame(&self) -> Option<Frame> {
+        let frame = self.free_list.1.try_recv().ok()?;
+        self.num_free_frames.fetch_sub(1, Ordering::Release);
+
+        Some(frame)
+    }
+
+    /// Runs the [`Replacer`] of this `FrameGroup` to choose eviction victims, evicts them, and
+    /// cools down every other frame.
+    ///
+    /// Eviction happens in two phases. First, every victim is [`Claimed`](EvictionState::Claimed)
+    /// while holding the eviction state lock. Then, once we
+    /// have write-locked a claimed page, we revalidate the claim: if the page was accessed since
+    /// we claimed it, the access will have marked the frame as [`Hot`](EvictionState::Hot) again
+    /// and we leave the page in memory.
+    ///
+    /// The second phase yields to the runtime every [`EVICTION_YIELD_INTERVAL`] pages, and once it
+    /// has run for longer than [`BufferPoolManager::eviction_time_budget`] (if set), it gives up
+    /// the remaining claims instead of evicting them. Dirty victims are written back in batches of
+    /// up to [`BufferPoolManager::eviction_write_batch`] pages, merging the writes of consecutive
+    /// pages (see [`FrameGroup::write_back`]).
+    ///
+    /// If `tenant` is set, only pages that are charged to that tenant are considered, and the
+    /// frames of every other page are left as they are (see
+    /// [`BufferPoolManager::enforce_tenant_quota`]).
     ///
     /// # Errors
     ///
     /// Returns an error if an I/O error occurs.
-    pub(crate) async fn cool_frames(&self) -> Result<()> {
-        let mut eviction_pages: Vec<Arc<Page>> = Vec::with_capacity(FRAME_GROUP_SIZE);
+    pub(crate) async fn cool_frames(&self, tenant: Option<TenantId>) -> Result<()> {
+        // Every claimed page, along with whether it was a prefetch that was never accessed.
+        let mut eviction_pages: Vec<(usize, Arc<Page>, bool)> = Vec::with_capacity(self.num_frames);
 
-        // Find page eviction candidates.
+        // Find and claim page eviction candidates.
         {
-            let mut evicton_guard = self
-                .eviction_states
-                .lock()
-                .expect("Fatal: `EvictionState` lock was poisoned somehow");
-
-            for frame_temperature in evicton_guard.iter_mut() {
-                if let Some(page) = frame_temperature.cool() {
-                    eviction_pages.push(page);
+            let bpm = BufferPoolManager::get();
+            let mut evicton_guard = self.lock_eviction_states()?;
+            let states = &mut *evicton_guard;
+            let now = Instant::now();
+            let mut expired = Vec::new();
+
+            // Pages that are exempt from eviction or pinned by a page guard are never cooled, and
+            // neither are speculative pages, which are evicted once they expire instead.
+            let candidates: Vec<ReplacementCandidate> = (0..self.num_frames)
+                .filter_map(|index| {
+                    let page = states[index].page()?;
+                    if bpm.is_eviction_exempt(page.pid) || page.pin_count() != 0 {
+                        return None;
+                    }
+
+                    if tenant.is_some_and(|tenant| page.tenant() != Some(tenant)) {
+                        return None;
+                    }
+
+                    if let EvictionState::Speculative(_, expires) = states[index] {
+                        if expires <= now {
+                            expired.push(index);
+                        }
+                        return None;
+                    }
+
+                    Some(ReplacementCandidate {
+                        index,
+                        pid: page.pid,
+                        accessed: matches!(states[index], EvictionState::Hot(_)),
+                    })
+                })
+                .collect();
+
+            // Expired speculative pages make room before any page that was actually accessed.
+            let victims = if expired.is_empty() {
+                states.replacer.victims(&candidates)
+            } else {
+                Vec::new()
+            };
+
+            for index in expired {
+                let page = states[index]
+                    .claim()
+                    .expect("Speculative frames always hold a page");
+                eviction_pages.push((index, page, true));
+            }
+
+            for index in candidates.into_iter().map(|candidate| candidate.index) {
+                if victims.contains(&index) {
+                    let page = states[index]
+                        .claim()
+                        .expect("Candidates always hold a page");
+                    eviction_pages.push((index, page, false));
+                } else {
+                    states[index].cool();
                 }
             }
         }
@@ -132,47 +425,543 @@ impl FrameGroup {
         }
 
         let sm = StorageManager::get().create_handle()?;
+        let deadline = BufferPoolManager::get()
+            .eviction_time_budget()
+            .map(|budget| Instant::now() + budget);
+
+        // Attempt to evict all of the claimed frames. Clean pages are evicted right away, while
+        // dirty pages are set aside so that they can be written back together.
+        let max_batch = BufferPoolManager::get().eviction_write_batch();
+        let mut batch: Vec<(PendingWriteBack<'_>, DirtyFrame)> =
+            Vec::with_capacity(max_batch.min(self.num_frames));
+        for (attempts, &(index, ref page, speculative)) in eviction_pages.iter().enumerate() {
+            // Evicting clean pages never waits, so periodically give the other tasks on this
+            // thread (including the one that completes I/O) a chance to run, and leave the rest of
+            // the claimed frames to the next pass once we run out of time.
+            if attempts > 0 {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    let written = self.write_back(&sm, &mut batch).await;
+                    self.release_claims(eviction_pages[attempts..].iter().cloned())?;
+                    return written;
+                }
+                if attempts % EVICTION_YIELD_INTERVAL == 0 {
+                    executor::yield_now().await;
+                }
+            }
 
-        // Attempt to evict all of the already cool frames.
-        for page in eviction_pages {
             // If we cannot get the write guard immediately, then someone else has it and we don't
             // need to evict this frame now.
-            if let Ok(mut guard) = page.frame.try_write() {
-                // Check if someone got in front of us and already evicted this page.
-                if guard.is_none() {
+            let Ok(mut guard) = page.frame.try_write() else {
+                continue;
+            };
+
+            // Check if someone got in front of us and already evicted this page (it may have even
+            // been loaded into a different frame since).
+            let owns_frame = guard.as_ref().is_some_and(|frame| {
+                frame.group_id() == self.group_id && frame.group_index() == index
+            });
+
+            // Since we hold the write lock, no one can access the page until we are done, so if the
+            // claim is still valid then it will stay valid.
+            if !self.revalidate_claim(index, page, owns_frame)? {
+                continue;
+            }
+
+            // Take ownership over the frame and remove from the page.
+            let frame = guard
+                .take()
+                .expect("We just checked that the page owns a frame");
+
+            let frame = match EvictableFrame::from(frame) {
+                EvictableFrame::Clean(frame) => {
+                    self.finish_eviction(page, frame, false, speculative).await;
                     continue;
                 }
+                EvictableFrame::Dirty(frame) => frame,
+            };
 
-                page.is_loaded.store(false, Ordering::Release);
+            let write = PendingWriteBack {
+                index,
+                page,
+                speculative,
+                guard,
+            };
+            batch.push((write, frame));
+            if batch.len() >= max_batch {
+                if let Err(e) = self.write_back(&sm, &mut batch).await {
+                    self.release_claims(eviction_pages[attempts + 1..].iter().cloned())?;
+                    return Err(e);
+                }
+            }
+        }
 
-                // Take ownership over the frame and remove from the page.
-                let mut frame = guard.take().unwrap();
-                frame
-                    .evict_page_owner()
-                    .expect("Tried to evict a frame that had no page owner");
+        self.write_back(&sm, &mut batch).await
+    }
 
-                if frame.is_dirty() {
-                    // Write the data out to persistent storage.
-                    let (res, mut empty_frame) = sm.write_from(page.pid, frame).await;
-                    res?;
+    /// Writes back a batch of dirty pages that [`FrameGroup::cool_frames`] is evicting, and then
+    /// finishes evicting them, leaving `batch` empty.
+    ///
+    /// The pages are sorted by their position on persistent storage, and every run of consecutive
+    /// pages is merged into a single vectored write per drive (see
+    /// [`DirtyFrame::write_back_run`]). Every write is started before any of them is awaited, so
+    /// that all of them are submitted to the `io_uring` instance together. The writes are awaited
+    /// in the same task, so none of the frames can be lost if the eviction pass is cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of any of the writes. Every page whose run failed keeps its frame
+    /// and becomes an eviction candidate again, while the other pages are still evicted.
+    async fn write_back(
+        &self,
+        sm: &StorageManagerHandle,
+        batch: &mut Vec<(PendingWriteBack<'_>, DirtyFrame)>,
+    ) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let mut batch = std::mem::take(batch);
+        batch.sort_unstable_by_key(|(write, _)| write.page.pid.as_u64());
+
+        // Temporary pages cannot be written as a range, so they are always written on their own.
+        let mut runs: Vec<Vec<(PendingWriteBack<'_>, DirtyFrame)>> = Vec::new();
+        for (write, frame) in batch {
+            let pid = write.page.pid;
+            let extends_run = runs
+                .last()
+                .and_then(|run| run.last())
+                .is_some_and(|(last, _)| {
+                    !pid.is_temp()
+                        && !last.page.pid.is_temp()
+                        && last.page.pid.as_u64() + 1 == pid.as_u64()
+                });
+
+            match runs.last_mut() {
+                Some(run) if extends_run => run.push((write, frame)),
+                _ => runs.push(vec![(write, frame)]),
+            }
+        }
 
-                    empty_frame.clear_dirty();
+        let (pending, writes): (Vec<Vec<_>>, Vec<_>) = runs
+            .into_iter()
+            .map(|run| {
+                let (pending, frames): (Vec<_>, Vec<_>) = run.into_iter().unzip();
+                let start = pending[0].page.pid;
+                (pending, DirtyFrame::write_back_run(frames, sm, start))
+            })
+            .unzip();
+        let results = join_all(writes).await;
+        BufferPoolManager::get().stats.record_eviction_write_batch();
+
+        let mut first_error = None;
+        for (run, result) in pending.into_iter().zip(results) {
+            match result {
+                Ok(frames) => {
+                    for (write, frame) in run.into_iter().zip(frames) {
+                        self.finish_eviction(write.page, frame, true, write.speculative)
+                            .await;
+                    }
+                }
+                Err((e, frames)) => {
+                    // Give the frames back to their pages so that their data is not lost, and make
+                    // them eviction candidates again so that we retry on the next pass.
+                    for (mut write, frame) in run.into_iter().zip(frames) {
+                        write.guard.replace(frame);
+                        self.lock_eviction_states()?[write.index] =
+                            EvictionState::Cool(write.page.clone());
+                    }
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Finishes evicting `page` from `frame`, which has been taken from the page, and returns the
+    /// frame to the free list.
+    async fn finish_eviction(
+        &self,
+        page: &Page,
+        frame: CleanFrame,
+        dirty: bool,
+        speculative: bool,
+    ) {
+        let frame = Self::evict(page, frame, dirty, speculative);
+        self.release_frame(frame).await;
+    }
+
+    /// Evicts `page` from `frame`, which has been taken from the page, and returns the frame so
+    /// that it can be reused.
+    ///
+    /// `dirty` is whether the frame had to be written back first, and `speculative` is whether the
+    /// page was a prefetch that was never accessed.
+    fn evict(page: &Page, frame: CleanFrame, dirty: bool, speculative: bool) -> Frame {
+        page.set_evicted();
+        let frame = frame.evict();
+
+        let bpm = BufferPoolManager::get();
+        bpm.record_page_event(PageEvent::Evict(page.pid));
+
+        let stats = &bpm.stats;
+        stats.record_eviction(dirty);
+        if speculative {
+            stats.record_wasted_prefetch();
+        }
+
+        frame
+    }
+
+    /// Evicts a clean page that is [`Cool`](EvictionState::Cool) and returns its frame, without
+    /// running a cooling pass or writing anything back.
+    ///
+    /// The [`Replacer`] ranks every page that could be evicted, as it does for a cooling pass, and
+    /// this reclaims the first of its victims that is clean and cool, skipping dirty victims and
+    /// victims whose frames are locked. Returns `None` if there is no such victim, or if a victim
+    /// that was accessed since the last cooling pass comes first, since only a cooling pass can
+    /// evict it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the eviction state lock was poisoned and the buffer pool manager is
+    /// configured to propagate poisoning errors.
+    fn try_reclaim_clean_frame(&self) -> Result<Option<Frame>> {
+        let bpm = BufferPoolManager::get();
+        let mut eviction_guard = self.lock_eviction_states()?;
+        let states = &mut *eviction_guard;
+
+        // Dirty frames can only be checked while holding the frame's lock, but there is no point
+        // in looking if every page is dirty.
+        if self.num_dirty_frames() >= self.num_frames {
+            return Ok(None);
+        }
 
-                    frame = empty_frame;
+        let candidates: Vec<ReplacementCandidate> = (0..self.num_frames)
+            .filter_map(|index| {
+                let (EvictionState::Hot(page) | EvictionState::Cool(page)) = &states[index] else {
+                    return None;
+                };
+                if bpm.is_eviction_exempt(page.pid) || page.pin_count() != 0 {
+                    return None;
                 }
 
-                self.free_list.0.send(frame).await.unwrap();
-                self.num_free_frames.fetch_add(1, Ordering::Release);
+                Some(ReplacementCandidate {
+                    index,
+                    pid: page.pid,
+                    accessed: matches!(states[index], EvictionState::Hot(_)),
+                })
+            })
+            .collect();
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        for index in states.replacer.victims(&candidates) {
+            if !candidates.iter().any(|candidate| candidate.index == index) {
+                continue;
+            }
+            let page = match &states[index] {
+                EvictionState::Cool(page) => page.clone(),
+                _ => return Ok(None),
+            };
+
+            // Checking the frame never waits, since we must not hold the eviction state lock
+            // across an await.
+            let Ok(mut guard) = page.frame.try_write() else {
+                continue;
+            };
+            let owns_frame = guard.as_ref().is_some_and(|frame| {
+                frame.group_id() == self.group_id && frame.group_index() == index
+            });
+            if !owns_frame {
+                continue;
+            }
+
+            let frame = guard
+                .take()
+                .expect("We just checked that the page owns a frame");
+            let frame = match EvictableFrame::from(frame) {
+                EvictableFrame::Clean(frame) => frame,
+                EvictableFrame::Dirty(frame) => {
+                    guard.replace(frame.0);
+                    continue;
+                }
+            };
+
+            states[index] = EvictionState::Cold;
+            states.replacer.record_eviction(index);
+            bpm.stats.record_clean_reclaim();
+
+            return Ok(Some(Self::evict(&page, frame, false, false)));
+        }
+
+        Ok(None)
+    }
+
+    /// Gives up the claims on frames that [`FrameGroup::cool_frames`] did not get to, making their
+    /// pages eviction candidates again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the eviction state lock was poisoned and the buffer pool manager is
+    /// configured to propagate poisoning errors.
+    fn release_claims(&self, claims: impl Iterator<Item = (usize, Arc<Page>, bool)>) -> Result<()> {
+        let mut eviction_guard = self.lock_eviction_states()?;
+        for (index, page, _) in claims {
+            let state = &mut eviction_guard[index];
+
+            // The page may have been accessed since we claimed it, in which case it is hot again.
+            if matches!(state, EvictionState::Claimed(claimed) if Arc::ptr_eq(claimed, &page)) {
+                *state = EvictionState::Cool(page);
             }
         }
 
         Ok(())
     }
 
+    /// Revalidates the claim on a frame before the frame is evicted.
+    ///
+    /// Returns `true` if the frame at `index` is still [`Claimed`](EvictionState::Claimed) by
+    /// `page` and the page still owns the frame, in which case the state is set to
+    /// [`Cold`](EvictionState::Cold) and the caller is responsible for evicting the page. A stale
+    /// claim on a frame that the page no longer owns is also reset to
+    /// [`Cold`](EvictionState::Cold).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the eviction state lock was poisoned and the buffer pool manager is
+    /// configured to propagate poisoning errors.
+    fn revalidate_claim(&self, index: usize, page: &Arc<Page>, owns_frame: bool) -> Result<bool> {
+        let mut eviction_guard = self.lock_eviction_states()?;
+        let state = &mut eviction_guard[index];
+
+        // If the page was accessed since we claimed it, then it is hot again.
+        if !matches!(state, EvictionState::Claimed(claimed) if Arc::ptr_eq(claimed, page)) {
+            return Ok(false);
+        }
+
+        *state = EvictionState::Cold;
+        eviction_guard.replacer.record_eviction(index);
+
+        Ok(owns_frame)
+    }
+
+    /// Returns a [`Frame`] that belongs to this `FrameGroup` back to its free list.
+    ///
+    /// The caller must make sure that the frame no longer has a page owner.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the free list channel has been closed, which should never happen.
+    pub(crate) async fn release_frame(&self, frame: Frame) {
+        debug_assert_eq!(frame.group_id(), self.group_id);
+
+        self.free_list.0.send(frame).await.unwrap();
+        self.num_free_frames.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns a [`Frame`] that belongs to this `FrameGroup` back to its free list without waiting,
+    /// which is possible outside of an asynchronous context.
+    ///
+    /// The caller must make sure that the frame no longer has a page owner.
+    pub(crate) fn try_release_frame(&self, frame: Frame) {
+        debug_assert_eq!(frame.group_id(), self.group_id);
+
+        // The free list is unbounded and never closed while the buffer pool is running.
+        if self.free_list.0.try_send(frame).is_ok() {
+            self.num_free_frames.fetch_add(1, Ordering::Release);
+        }
+    }
+
     /// Gets the number of free frames in this `FrameGroup`.
     pub(crate) fn num_free_frames(&self) -> usize {
         self.num_free_frames.load(Ordering::Acquire)
     }
+
+    /// Checks if this `FrameGroup` is running out of free frames, in which case the eviction task
+    /// should start evicting its frames.
+    pub(crate) fn is_under_pressure(&self) -> bool {
+        self.num_free_frames() < self.low_water_mark()
+    }
+
+    /// Gets the number of free frames below which this `FrameGroup` is under pressure.
+    pub(crate) fn low_water_mark(&self) -> usize {
+        (self.num_frames / 10).max(1)
+    }
+
+    /// Moves up to `count` free frames from this `FrameGroup`'s free list to the free list of
+    /// another `FrameGroup`, returning the number of frames that were moved.
+    ///
+    /// The frames still belong to this group, and come back to it once they are evicted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the other group's free list channel has been closed, which should never happen.
+    #[cfg(feature = "experimental")]
+    pub(crate) fn lend_free_frames(&self, to: &FrameGroup, count: usize) -> usize {
+        let mut lent = 0;
+        while lent < count {
+            let Some(frame) = self.try_get_free_frame() else {
+                break;
+            };
+
+            to.push_free_frame(frame);
+            lent += 1;
+        }
+
+        lent
+    }
+
+    /// Takes back every free frame of this `FrameGroup` that was lent to one of the other `groups`,
+    /// and gives every free frame that another group lent to this one back to its own group.
+    ///
+    /// Afterwards, this group's free list only holds its own frames, unless frames are lent again.
+    #[cfg(feature = "experimental")]
+    pub(crate) async fn reclaim_lent_frames(&self, groups: &[Arc<FrameGroup>]) {
+        for group in groups {
+            // Only the frames that are in the free list right now are taken out, so frames that
+            // are put back are not taken out again.
+            let frames: Vec<Frame> = (0..group.num_free_frames())
+                .map_while(|_| group.try_get_free_frame())
+                .collect();
+
+            for frame in frames {
+                if frame.group_id() == self.group_id {
+                    self.release_frame(frame).await;
+                } else if group.group_id == self.group_id {
+                    frame.group().release_frame(frame).await;
+                } else {
+                    group.push_free_frame(frame);
+                }
+            }
+        }
+    }
+
+    /// Pushes a free [`Frame`] onto this `FrameGroup`'s free list, which may be a frame of another
+    /// group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the free list channel has been closed, which should never happen.
+    #[cfg(feature = "experimental")]
+    fn push_free_frame(&self, frame: Frame) {
+        self.free_list
+            .0
+            .try_send(frame)
+            .expect("The free list channel cannot be closed");
+        self.num_free_frames.fetch_add(1, Ordering::Release);
+    }
+
+    /// Gets all of the [`Page`]s that the eviction states of this `FrameGroup` believe to be
+    /// resident in one of its frames.
+    ///
+    /// Since the eviction states are only hints, a returned page may have already been evicted or
+    /// may now live in a frame of a different `FrameGroup`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the eviction state lock was poisoned and the buffer pool manager is
+    /// configured to propagate poisoning errors.
+    pub(crate) fn resident_pages(&self) -> Result<Vec<Arc<Page>>> {
+        let eviction_guard = self.lock_eviction_states()?;
+
+        Ok(eviction_guard
+            .iter()
+            .filter_map(|state| state.page().cloned())
+            .collect())
+    }
+
+    /// Gets the number of dirty frames in this `FrameGroup`.
+    pub(crate) fn num_dirty_frames(&self) -> usize {
+        self.num_dirty_frames.load(Ordering::Acquire)
+    }
+
+    /// Acquires the lock on the [`EvictionState`]s of this `FrameGroup`.
+    ///
+    /// If the lock has been poisoned, this function follows the [`PoisonPolicy`] that the buffer
+    /// pool manager was configured with.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Poisoned`] error if the lock was poisoned and the policy is
+    /// [`PoisonPolicy::Error`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned and the policy is [`PoisonPolicy::Panic`].
+    pub(crate) fn lock_eviction_states(&self) -> Result<MutexGuard<'_, EvictionStates>> {
+        let poisoned = match self.eviction_states.lock() {
+            Ok(guard) => return Ok(guard),
+            Err(poisoned) => poisoned,
+        };
+
+        match BufferPoolManager::get().poison_policy() {
+            PoisonPolicy::Panic => panic!("Fatal: `EvictionState` lock was poisoned"),
+            PoisonPolicy::Error => Err(Poisoned::new("EvictionState").into()),
+            PoisonPolicy::Recover => {
+                let mut guard = poisoned.into_inner();
+                self.eviction_states.clear_poison();
+
+                self.rebuild_eviction_states(&mut guard);
+
+                Ok(guard)
+            }
+        }
+    }
+
+    /// Rebuilds the [`EvictionState`]s of this `FrameGroup` from the frames themselves.
+    ///
+    /// Any state that refers to a [`Page`] that no longer owns the corresponding [`Frame`] is reset
+    /// to [`Cold`](EvictionState::Cold). If we cannot observe the page's frame without blocking,
+    /// then we leave the state as it is, since it is only a hint.
+    fn rebuild_eviction_states(&self, states: &mut EvictionStates) {
+        for (index, state) in states.states.iter_mut().enumerate() {
+            let Some(page) = state.page().cloned() else {
+                continue;
+            };
+
+            let Ok(guard) = page.frame.try_read() else {
+                continue;
+            };
+
+            let owns_frame = guard.deref().as_ref().is_some_and(|frame| {
+                frame.group_id() == self.group_id && frame.group_index() == index
+            });
+
+            if !owns_frame {
+                *state = EvictionState::Cold;
+                states.replacer.record_eviction(index);
+            }
+        }
+    }
+}
+
+/// The [`EvictionState`]s of every [`Frame`] in a [`FrameGroup`], along with the [`Replacer`] that
+/// decides which of the frames to evict.
+///
+/// This type dereferences to the slice of eviction states, indexed by each frame's index within the
+/// group.
+#[derive(Debug)]
+pub(crate) struct EvictionStates {
+    /// The eviction state of every [`Frame`] in the group.
+    states: Box<[EvictionState]>,
+
+    /// The replacement policy of the group.
+    pub(crate) replacer: Box<dyn Replacer>,
+}
+
+impl Deref for EvictionStates {
+    type Target = [EvictionState];
+
+    fn deref(&self) -> &Self::Target {
+        &self.states
+    }
+}
+
+impl DerefMut for EvictionStates {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.states
+    }
 }
 
 /// The enum representing the possible states that a [`Frame`] can be in with respect to the
@@ -180,7 +969,7 @@ impl FrameGroup {
 ///
 /// Note that these states may not necessarily be synced to the actual state of the [`Frame`]s, and
 /// these only serve as hints to the eviction algorithm.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub(crate) enum EvictionState {
     /// Represents a frequently / recently accessed [`Frame`] that currently holds a [`Page`]'s
     /// data.
@@ -188,36 +977,56 @@ pub(crate) enum EvictionState {
     /// Represents an infrequently or old [`Frame`] that might be evicted soon, and also still
     /// currently holds a [`Page`] data.
     Cool(Arc<Page>),
+    /// Represents a [`Frame`] that a prefetch loaded a [`Page`] into, which has not been accessed
+    /// yet.
+    ///
+    /// The frame is never evicted before the [`Instant`], and is evicted before any other frame
+    /// once it has passed. The [`Replacer`] does not know about the page until it is accessed.
+    Speculative(Arc<Page>, Instant),
+    /// Represents a [`Frame`] that was [`Cool`](EvictionState::Cool) and that an eviction task is
+    /// about to evict, unless the [`Page`] is accessed before the eviction task can lock it.
+    Claimed(Arc<Page>),
     /// Represents either a [`Frame`] that does not hold any [`Page`] data, or a [`Frame`] that has
     /// an active thread trying to evict it from memory.
+    #[default]
     Cold,
 }
 
 impl EvictionState {
-    /// Runs the cooling algorithm, returning an optional [`Page`] if we want to evict the
-    /// page.
-    ///
-    /// If the state is [`Hot`](EvictionState::Hot), then this function cools it down to be
-    /// [`Cool`](EvictionState::Cool), and if it was already [`Cool`](EvictionState::Cool), then
-    /// this function does nothing. It is on the caller to deal with eviction of the
-    /// [`Cool`](EvictionState::Cool) page via the [`Page`] that is returned.
-    ///
-    /// If the state transitions to [`Cold`](EvictionState::Cold), this function will return the
-    /// [`Page`] that it used to hold.
-    pub(crate) fn cool(&mut self) -> Option<Arc<Page>> {
+    /// Gets the [`Page`] that this state refers to, if the state is not
+    /// [`Cold`](EvictionState::Cold).
+    pub(crate) fn page(&self) -> Option<&Arc<Page>> {
         match self {
-            Self::Hot(page) => {
-                *self = Self::Cool(page.clone());
-                None
-            }
-            Self::Cool(page) => Some(page.clone()),
+            Self::Hot(page)
+            | Self::Cool(page)
+            | Self::Claimed(page)
+            | Self::Speculative(page, _) => Some(page),
             Self::Cold => None,
         }
     }
-}
 
-impl Default for EvictionState {
-    fn default() -> Self {
-        Self::Cold
+    /// Cools down a frame that was not chosen for eviction.
+    ///
+    /// A [`Hot`](EvictionState::Hot) frame becomes [`Cool`](EvictionState::Cool), and a claim on a
+    /// [`Claimed`](EvictionState::Claimed) frame is given up. A
+    /// [`Speculative`](EvictionState::Speculative) frame stays speculative.
+    pub(crate) fn cool(&mut self) {
+        if let Self::Hot(page) | Self::Claimed(page) = self {
+            page.clear_recorded();
+            *self = Self::Cool(page.clone());
+        }
+    }
+
+    /// Claims a frame for eviction by transitioning it to [`Claimed`](EvictionState::Claimed),
+    /// returning the [`Page`] that it holds.
+    ///
+    /// It is on the caller to deal with eviction of the [`Claimed`](EvictionState::Claimed) page
+    /// via the [`Page`] that is returned. If the state is [`Cold`](EvictionState::Cold), this
+    /// function does nothing and returns `None`.
+    pub(crate) fn claim(&mut self) -> Option<Arc<Page>> {
+        let page = self.page()?.clone();
+        page.clear_recorded();
+        *self = Self::Claimed(page.clone());
+        Some(page)
     }
 }
@@ -0,0 +1,226 @@
+//! This module contains [`GroupCommit`], which batches concurrent durable flushes of pages so
+//! that they share their writes and their syncs.
+//!
+//! Without group commit, every [`WritePageGuard::flush_sync`] writes its page out and then syncs
+//! the page's database file on its own, so `n` tasks that commit at the same time issue `n` writes
+//! and `n` `fdatasync`s, even though a single sync would have made every one of those writes
+//! durable. With [`BufferPoolManagerConfig::group_commit_window`], the first flush of a batch opens
+//! a window during which every other flush (from any thread) joins the batch. Once the window
+//! closes, the pages of the batch are written out with vectored writes of every run of
+//! consecutive pages, every database file that was written to is synced exactly once, and every
+//! waiting flush completes together.
+//!
+//! [`WritePageGuard::flush_sync`]: crate::page::WritePageGuard::flush_sync
+//! [`BufferPoolManagerConfig::group_commit_window`]:
+//!     crate::BufferPoolManagerConfig::group_commit_window
+
+use crate::bpm::BufferPoolManager;
+use crate::error::BpmError;
+use crate::executor;
+use crate::page::{FileId, PageId};
+use crate::storage::{join_all, Frame, IoKind, StorageManager};
+use std::collections::BTreeMap;
+use std::io::{Error, Result};
+use std::mem;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio_uring::BufResult;
+
+/// A page that waits to be written out and synced with the next batch.
+///
+/// The frame is marked as being used for I/O (see [`Frame::begin_io`]) while it is queued, so that
+/// it is given back to its page if the batch is dropped before it is written out.
+struct CommitRequest {
+    /// The page to write out.
+    pid: PageId,
+
+    /// The frame that holds the page's data.
+    frame: Frame,
+
+    /// Sends the result of the commit and the frame back to the flush that is waiting for it.
+    reply: oneshot::Sender<BufResult<(), Frame>>,
+}
+
+impl std::fmt::Debug for CommitRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Commit of {}", self.pid)
+    }
+}
+
+/// Batches the durable flushes of pages, see the [module documentation](self).
+#[derive(Debug)]
+pub(crate) struct GroupCommit {
+    /// How long the first flush of a batch waits for other flushes to join it.
+    window: Duration,
+
+    /// The pages of the batch that is currently open.
+    pending: Mutex<Vec<CommitRequest>>,
+}
+
+impl GroupCommit {
+    /// Creates a coordinator whose batches stay open for `window`.
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Writes the frame of page `pid` out and syncs it to persistent storage, together with every
+    /// other page that is committed during the same window.
+    ///
+    /// Like [`StorageManagerHandle::write_from`](crate::storage::StorageManagerHandle::write_from),
+    /// this takes ownership of the frame and gives it back on return.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page could not be written out, or if its database file could not be
+    /// synced. The errors of other pages in the same batch do not affect this page. Returns a
+    /// [`BpmError::ShuttingDown`] error if the batch was dropped before it completed, which happens
+    /// if the runtime of the thread that opened the batch shuts down first.
+    pub(crate) async fn commit(&self, pid: PageId, mut frame: Frame) -> BufResult<(), Frame> {
+        let (reply, completion) = oneshot::channel();
+
+        frame.begin_io(IoKind::Write);
+        let recovered = frame.recover_to_channel();
+        let opens_batch = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.push(CommitRequest { pid, frame, reply });
+            pending.len() == 1
+        };
+
+        // The first page of a batch is responsible for committing it once the window closes. The
+        // batch runs in its own task, so that it completes even if this future is dropped.
+        if opens_batch {
+            BufferPoolManager::spawn_local(async {
+                let group_commit = StorageManager::get()
+                    .group_commit()
+                    .expect("Group commit is enabled while a batch is open");
+                group_commit.run_batch().await;
+            });
+        }
+
+        match completion.await {
+            Ok((res, mut frame)) => {
+                frame.clear_recovery();
+                (res, frame)
+            }
+            Err(_) => {
+                // The frame is sent back once it is dropped along with the batch.
+                let frame = recovered
+                    .await
+                    .expect("A frame of a dropped batch is always sent back");
+                (Err(BpmError::ShuttingDown.into()), frame)
+            }
+        }
+    }
+
+    /// Waits for the window of the open batch to close, and then commits every page in it.
+    async fn run_batch(&self) {
+        if self.window.is_zero() {
+            // Still give the flushes that are ready to run a chance to join.
+            executor::yield_now().await;
+        } else {
+            executor::sleep(self.window).await;
+        }
+
+        let mut batch = mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        batch.sort_unstable_by_key(|request| request.pid.as_u64());
+
+        // Split the batch into runs of consecutive pages, which are each written with a single
+        // vectored write.
+        let mut runs: Vec<Vec<CommitRequest>> = Vec::new();
+        for request in batch {
+            match runs.last_mut() {
+                Some(run) if run[run.len() - 1].pid.as_u64() + 1 == request.pid.as_u64() => {
+                    run.push(request);
+                }
+                _ => runs.push(vec![request]),
+            }
+        }
+
+        let sm = match StorageManager::get().create_handle() {
+            Ok(sm) => sm,
+            Err(e) => {
+                for request in runs.into_iter().flatten() {
+                    request.send(Err(copy_error(&e)));
+                }
+                return;
+            }
+        };
+
+        let writes = runs
+            .into_iter()
+            .map(|run| {
+                let start = run[0].pid;
+                let (frames, replies): (Vec<Frame>, Vec<_>) = run
+                    .into_iter()
+                    .map(|mut request| {
+                        request.frame.end_io();
+                        (request.frame, (request.pid, request.reply))
+                    })
+                    .unzip();
+
+                let sm = &sm;
+                async move { (sm.write_range_from(start, frames).await, replies) }
+            })
+            .collect();
+        let written = join_all(writes).await;
+
+        // Sync every file that was written to once, no matter how many pages of the batch it holds.
+        let mut files: BTreeMap<(FileId, usize), Option<Error>> = BTreeMap::new();
+        for ((res, _), replies) in &written {
+            if res.is_ok() {
+                for (pid, _) in replies {
+                    files.entry(file_of(*pid)).or_insert(None);
+                }
+            }
+        }
+
+        // Page `i` of a data file is stored on drive `i`, so it stands in for every page of the
+        // data file on its drive.
+        let syncs = files
+            .keys()
+            .map(|&(file, drive)| sm.sync_page(PageId::in_file(file, drive as u64)))
+            .collect();
+        for (error, res) in files.values_mut().zip(join_all(syncs).await) {
+            *error = res.err();
+        }
+
+        for ((res, frames), replies) in written {
+            for (frame, (pid, reply)) in frames.into_iter().zip(replies) {
+                let res = match (&res, files.get(&file_of(pid))) {
+                    (Err(e), _) => Err(copy_error(e)),
+                    (Ok(()), Some(Some(e))) => Err(copy_error(e)),
+                    (Ok(()), _) => Ok(()),
+                };
+
+                CommitRequest { pid, frame, reply }.send(res);
+            }
+        }
+    }
+}
+
+impl CommitRequest {
+    /// Sends the result of the commit and the frame back to the flush that is waiting for it.
+    ///
+    /// If the flush has given up on the result in the meantime, the frame is dropped while still
+    /// marked as being used for I/O, which gives it back to its page.
+    fn send(mut self, res: Result<()>) {
+        self.frame.end_io();
+        if let Err((_, mut frame)) = self.reply.send((res, self.frame)) {
+            frame.begin_io(IoKind::Write);
+        }
+    }
+}
+
+/// Gets the data file and the drive that a page is stored on, which identify its file.
+fn file_of(pid: PageId) -> (FileId, usize) {
+    (pid.file_id(), pid.drive())
+}
+
+/// Copies an I/O error that is shared by several pages of a batch.
+fn copy_error(e: &Error) -> Error {
+    Error::new(e.kind(), e.to_string())
+}
@@ -0,0 +1,434 @@
+//! This module contains [`IoDriver`], a small pool of dedicated threads that perform every read
+//! and write of the buffer pool on behalf of the threads that request them.
+//!
+//! By default, every thread that accesses pages submits I/O to its own `io_uring` instance, which
+//! ties the buffer pool to `tokio_uring` runtimes. With [`BufferPoolManagerConfig::io_threads`],
+//! the driver threads own the only `io_uring` instances instead: a [`StorageManagerHandle`] on any
+//! other thread sends its operation over a lock-free channel to one of the driver threads, and
+//! awaits the completion on a oneshot channel. The compute threads only need an executor that can
+//! drive those channels (and the timers of the buffer pool), so they can run on any Tokio runtime.
+//!
+//! [`StorageManagerHandle`]: super::StorageManagerHandle
+//! [`BufferPoolManagerConfig::io_threads`]: crate::BufferPoolManagerConfig::io_threads
+
+use crate::bpm::BufferPoolManager;
+use crate::bypass::PageBuf;
+use crate::error::BpmError;
+use crate::page::PageId;
+use crate::storage::{Frame, IoKind, StorageManager};
+use async_channel::{Receiver, Sender};
+use std::cell::Cell;
+use std::io::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use tokio::sync::oneshot;
+use tokio_uring::BufResult;
+
+std::thread_local! {
+    /// Whether the current thread is one of the I/O driver threads, which perform their I/O
+    /// directly instead of sending it to a driver thread.
+    static IS_IO_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// An I/O operation that a driver thread performs on behalf of another thread.
+///
+/// The frames of a request are marked as being used for I/O (see [`Frame::begin_io`]) while the
+/// request is queued, so that they are given back to the buffer pool if the request is dropped
+/// before it is performed.
+enum IoRequest {
+    /// A read or write of a single page, see [`StorageManagerHandle::read_into`] and
+    /// [`StorageManagerHandle::write_from`].
+    ///
+    /// [`StorageManagerHandle::read_into`]: super::StorageManagerHandle::read_into
+    /// [`StorageManagerHandle::write_from`]: super::StorageManagerHandle::write_from
+    Page {
+        /// Whether the page is read or written.
+        kind: IoKind,
+
+        /// The page to read or write.
+        pid: PageId,
+
+        /// The frame to read the page into or to write the page from.
+        frame: Frame,
+
+        /// Sends the result and the frame back to the requesting thread.
+        reply: oneshot::Sender<BufResult<(), Frame>>,
+    },
+
+    /// A read or write of a contiguous range of pages, see
+    /// [`StorageManagerHandle::read_range_into`] and [`StorageManagerHandle::write_range_from`].
+    ///
+    /// [`StorageManagerHandle::read_range_into`]: super::StorageManagerHandle::read_range_into
+    /// [`StorageManagerHandle::write_range_from`]: super::StorageManagerHandle::write_range_from
+    Range {
+        /// Whether the pages are read or written.
+        kind: IoKind,
+
+        /// The first page of the range.
+        start: PageId,
+
+        /// The frames to read the pages into or to write the pages from, one per page.
+        frames: Vec<Frame>,
+
+        /// Sends the result and the frames back to the requesting thread.
+        reply: oneshot::Sender<BufResult<(), Vec<Frame>>>,
+    },
+
+    /// A read of a single page into a buffer outside of the buffer pool, see
+    /// [`StorageManagerHandle::read_bypass`].
+    ///
+    /// [`StorageManagerHandle::read_bypass`]: super::StorageManagerHandle::read_bypass
+    Bypass {
+        /// The page to read.
+        pid: PageId,
+
+        /// The buffer to read the page into.
+        buf: PageBuf,
+
+        /// Sends the result and the buffer back to the requesting thread.
+        reply: oneshot::Sender<BufResult<(), PageBuf>>,
+    },
+
+    /// A flush of every database file, see [`StorageManagerHandle::sync_all`] and
+    /// [`StorageManagerHandle::sync_data`].
+    ///
+    /// [`StorageManagerHandle::sync_all`]: super::StorageManagerHandle::sync_all
+    /// [`StorageManagerHandle::sync_data`]: super::StorageManagerHandle::sync_data
+    Sync {
+        /// Whether to flush with `fdatasync` instead of `fsync`.
+        data_only: bool,
+
+        /// Sends the result back to the requesting thread.
+        reply: oneshot::Sender<Result<()>>,
+    },
+}
+
+impl std::fmt::Debug for IoRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Page { kind, pid, .. } => write!(f, "{kind:?} of {pid}"),
+            Self::Range {
+                kind,
+                start,
+                frames,
+                ..
+            } => {
+                write!(f, "{kind:?} of {} pages from {start}", frames.len())
+            }
+            Self::Bypass { pid, .. } => write!(f, "Bypassing read of {pid}"),
+            Self::Sync { data_only, .. } => write!(f, "Sync (data only: {data_only})"),
+        }
+    }
+}
+
+impl IoRequest {
+    /// Performs the request on the current driver thread and sends its result back.
+    ///
+    /// If the requesting task has given up on the result in the meantime, the frames are dropped
+    /// while still marked as being used for I/O, which gives them back to the buffer pool.
+    async fn run(self) {
+        let sm = StorageManager::get().create_handle();
+
+        match self {
+            Self::Page {
+                kind,
+                pid,
+                mut frame,
+                reply,
+            } => {
+                frame.end_io();
+                let (res, frame) = match (sm, kind) {
+                    (Err(e), _) => (Err(e), frame),
+                    (Ok(sm), IoKind::Read) => sm.read_into(pid, frame).await,
+                    (Ok(sm), IoKind::Write) => sm.write_from(pid, frame).await,
+                };
+
+                if let Err((_, mut frame)) = reply.send((res, frame)) {
+                    frame.begin_io(kind);
+                }
+            }
+            Self::Range {
+                kind,
+                start,
+                mut frames,
+                reply,
+            } => {
+                frames.iter_mut().for_each(Frame::end_io);
+                let (res, frames) = match (sm, kind) {
+                    (Err(e), _) => (Err(e), frames),
+                    (Ok(sm), IoKind::Read) => sm.read_range_into(start, frames).await,
+                    (Ok(sm), IoKind::Write) => sm.write_range_from(start, frames).await,
+                };
+
+                if let Err((_, mut frames)) = reply.send((res, frames)) {
+                    frames.iter_mut().for_each(|frame| frame.begin_io(kind));
+                }
+            }
+            Self::Bypass { pid, buf, reply } => {
+                let result = match sm {
+                    Err(e) => (Err(e), buf),
+                    Ok(sm) => sm.read_bypass(pid, buf).await,
+                };
+
+                let _ = reply.send(result);
+            }
+            Self::Sync { data_only, reply } => {
+                let res = match sm {
+                    Err(e) => Err(e),
+                    Ok(sm) if data_only => sm.sync_data().await,
+                    Ok(sm) => sm.sync_all().await,
+                };
+
+                let _ = reply.send(res);
+            }
+        }
+    }
+}
+
+/// A pool of dedicated threads that own the `io_uring` instances of the buffer pool, see the
+/// [module-level documentation](self).
+#[derive(Debug)]
+pub(crate) struct IoDriver {
+    /// The request queue of every driver thread.
+    queues: Box<[(Sender<IoRequest>, Receiver<IoRequest>)]>,
+
+    /// The index of the queue that the next request is sent to.
+    next: AtomicUsize,
+
+    /// The join handles of the driver threads, which are taken on shutdown.
+    threads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl IoDriver {
+    /// Creates the queues of `num_threads` driver threads, without starting the threads yet.
+    pub(crate) fn new(num_threads: usize) -> Self {
+        Self {
+            queues: (0..num_threads)
+                .map(|_| async_channel::unbounded())
+                .collect(),
+            next: AtomicUsize::new(0),
+            threads: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Starts every driver thread. Must be called once the global [`StorageManager`] has been
+    /// initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread cannot be spawned.
+    pub(crate) fn start(&self) {
+        let mut threads = self.threads.lock().unwrap_or_else(|e| e.into_inner());
+
+        for (i, (_, queue)) in self.queues.iter().enumerate() {
+            let queue = queue.clone();
+            let thread = thread::Builder::new()
+                .name(format!("bpm-io-{i}"))
+                .spawn(move || Self::run(queue))
+                .expect("Unable to spawn an I/O driver thread");
+
+            threads.push(thread);
+        }
+    }
+
+    /// The main loop of a driver thread, which performs every request from its queue concurrently
+    /// until the queue is closed.
+    fn run(queue: Receiver<IoRequest>) {
+        IS_IO_THREAD.with(|is_io_thread| is_io_thread.set(true));
+
+        BufferPoolManager::start_thread(async move {
+            while let Ok(request) = queue.recv().await {
+                tokio_uring::spawn(request.run());
+            }
+        });
+    }
+
+    /// Gets the I/O driver that the current thread should send its I/O to, which is `None` if the
+    /// buffer pool does not have one or if this is one of the driver threads.
+    pub(crate) fn for_current_thread() -> Option<&'static Self> {
+        if IS_IO_THREAD.with(Cell::get) {
+            return None;
+        }
+
+        BufferPoolManager::get().io_driver()
+    }
+
+    /// Sends a request to the next driver thread, round-robin.
+    ///
+    /// # Errors
+    ///
+    /// Gives the request back if the driver has been shut down.
+    fn send(&self, request: IoRequest) -> std::result::Result<(), Box<IoRequest>> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.queues.len();
+
+        self.queues[index]
+            .0
+            .try_send(request)
+            .map_err(|e| Box::new(e.into_inner()))
+    }
+
+    /// Reads or writes a single page on a driver thread.
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::read_into`] and [`StorageManagerHandle::write_from`]. Returns a
+    /// [`BpmError::ShuttingDown`] error if the driver has been shut down, or if the driver thread
+    /// dropped the request (for example, because it exited while the request was in flight).
+    ///
+    /// [`StorageManagerHandle::read_into`]: super::StorageManagerHandle::read_into
+    /// [`StorageManagerHandle::write_from`]: super::StorageManagerHandle::write_from
+    pub(crate) async fn page(
+        &self,
+        kind: IoKind,
+        pid: PageId,
+        mut frame: Frame,
+    ) -> BufResult<(), Frame> {
+        let (reply, completion) = oneshot::channel();
+
+        frame.begin_io(kind);
+        let recovered = frame.recover_to_channel();
+        let request = IoRequest::Page {
+            kind,
+            pid,
+            frame,
+            reply,
+        };
+
+        if let Err(request) = self.send(request) {
+            if let IoRequest::Page { mut frame, .. } = *request {
+                frame.end_io();
+                frame.clear_recovery();
+                return (Err(BpmError::ShuttingDown.into()), frame);
+            }
+        }
+
+        match completion.await {
+            Ok((res, mut frame)) => {
+                frame.clear_recovery();
+                (res, frame)
+            }
+            Err(_) => (Err(BpmError::ShuttingDown.into()), recover(recovered).await),
+        }
+    }
+
+    /// Reads or writes a contiguous range of pages on a driver thread.
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::read_range_into`] and
+    /// [`StorageManagerHandle::write_range_from`]. Returns a [`BpmError::ShuttingDown`] error if
+    /// the driver has been shut down, or if the driver thread dropped the request.
+    ///
+    /// [`StorageManagerHandle::read_range_into`]: super::StorageManagerHandle::read_range_into
+    /// [`StorageManagerHandle::write_range_from`]: super::StorageManagerHandle::write_range_from
+    pub(crate) async fn range(
+        &self,
+        kind: IoKind,
+        start: PageId,
+        mut frames: Vec<Frame>,
+    ) -> BufResult<(), Vec<Frame>> {
+        let (reply, completion) = oneshot::channel();
+
+        frames.iter_mut().for_each(|frame| frame.begin_io(kind));
+        let recovered: Vec<_> = frames.iter_mut().map(Frame::recover_to_channel).collect();
+        let request = IoRequest::Range {
+            kind,
+            start,
+            frames,
+            reply,
+        };
+
+        if let Err(request) = self.send(request) {
+            if let IoRequest::Range { mut frames, .. } = *request {
+                frames.iter_mut().for_each(|frame| {
+                    frame.end_io();
+                    frame.clear_recovery();
+                });
+                return (Err(BpmError::ShuttingDown.into()), frames);
+            }
+        }
+
+        match completion.await {
+            Ok((res, mut frames)) => {
+                frames.iter_mut().for_each(Frame::clear_recovery);
+                (res, frames)
+            }
+            Err(_) => {
+                let mut frames = Vec::with_capacity(recovered.len());
+                for receiver in recovered {
+                    frames.push(recover(receiver).await);
+                }
+                (Err(BpmError::ShuttingDown.into()), frames)
+            }
+        }
+    }
+
+    /// Reads a single page into a buffer outside of the buffer pool on a driver thread.
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::read_bypass`]. Returns a [`BpmError::ShuttingDown`] error if
+    /// the driver has been shut down, or if the driver thread dropped the request.
+    ///
+    /// [`StorageManagerHandle::read_bypass`]: super::StorageManagerHandle::read_bypass
+    pub(crate) async fn bypass(&self, pid: PageId, buf: PageBuf) -> BufResult<(), PageBuf> {
+        let (reply, completion) = oneshot::channel();
+
+        if let Err(request) = self.send(IoRequest::Bypass { pid, buf, reply }) {
+            if let IoRequest::Bypass { buf, .. } = *request {
+                return (Err(BpmError::ShuttingDown.into()), buf);
+            }
+        }
+
+        // The buffer is freed along with the request, so the caller gets an empty one back.
+        completion
+            .await
+            .unwrap_or_else(|_| (Err(BpmError::ShuttingDown.into()), PageBuf::default()))
+    }
+
+    /// Flushes every database file on a driver thread, with `fdatasync` if `data_only` is set and
+    /// with `fsync` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::sync_all`]. Returns a [`BpmError::ShuttingDown`] error if the
+    /// driver has been shut down, or if the driver thread dropped the request.
+    ///
+    /// [`StorageManagerHandle::sync_all`]: super::StorageManagerHandle::sync_all
+    pub(crate) async fn sync(&self, data_only: bool) -> Result<()> {
+        let (reply, completion) = oneshot::channel();
+
+        if self.send(IoRequest::Sync { data_only, reply }).is_err() {
+            return Err(BpmError::ShuttingDown.into());
+        }
+
+        completion
+            .await
+            .unwrap_or_else(|_| Err(BpmError::ShuttingDown.into()))
+    }
+
+    /// Stops every driver thread once it has performed the requests in its queue, and waits for
+    /// them to exit.
+    ///
+    /// Must only be called once no more I/O is in flight, since the operations that a driver
+    /// thread has started but not completed yet are cancelled when it exits.
+    pub(crate) fn shutdown(&self) {
+        self.queues.iter().for_each(|(queue, _)| {
+            queue.close();
+        });
+
+        let threads = std::mem::take(&mut *self.threads.lock().unwrap_or_else(|e| e.into_inner()));
+        for thread in threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Waits for a frame that a driver thread dropped while performing a request with it, which is
+/// sent back once the kernel is done with it (see [`Frame::recover_to_channel`]).
+async fn recover(recovered: oneshot::Receiver<Frame>) -> Frame {
+    recovered
+        .await
+        .expect("A frame of a dropped request is always sent back")
+}
@@ -1,19 +1,41 @@
 //! This module contains the definition and implementation of [`Frame`] and [`FrameGroup`], which
 //! are types that represent the buffer frames that the buffer pool manager is in charge of.
 //!
-//! A [`Frame`] is intended to hold [`PAGE_SIZE`](crate::page::PAGE_SIZE) bytes of data, and is also
-//! intended to be shared with the the kernel to avoid unnecessary `memcpy`s from the kernel's
-//! internal buffers into user-space buffers.
+//! A [`Frame`] is intended to hold a single page of data, and is also intended to be shared with
+//! the the kernel to avoid unnecessary `memcpy`s from the kernel's internal buffers into user-space
+//! buffers.
 //!
 //! A [`FrameGroup`] instance groups [`Frame`]s together so that evictions do not have to search
 //! every single [`Frame`] in the buffer pool for an eviction candidate.
 
+mod backend;
+pub(crate) mod checksum;
+mod codec;
+#[cfg(feature = "lz4")]
+mod compressed;
+mod doublewrite;
 mod frame;
 mod frame_group;
+mod group_commit;
+mod io_driver;
+mod replacer;
 mod storage_manager;
 
+pub(crate) use checksum::CHECKSUM_SIZE;
+pub(crate) use codec::{decode_page, ScratchBuf};
+pub(crate) use doublewrite::*;
 pub(crate) use frame::*;
 pub(crate) use frame_group::*;
+pub(crate) use group_commit::GroupCommit;
+pub(crate) use io_driver::IoDriver;
+pub(crate) use replacer::*;
 pub(crate) use storage_manager::*;
 
+pub use backend::{BackendFuture, MemoryStorage, StorageBackend};
+#[cfg(feature = "aes-gcm")]
+pub use codec::AesGcmCodec;
+pub use codec::PageCodec;
+#[cfg(feature = "lz4")]
+pub use compressed::CompressedStorage;
+pub use replacer::{ReplacementCandidate, Replacer};
 pub use storage_manager::IO_OPERATIONS;
@@ -0,0 +1,35 @@
+//! Implementation of the [`ClockReplacer`] type.
+
+use crate::page::PageId;
+use crate::storage::replacer::{ReplacementCandidate, Replacer};
+
+/// The second chance / clock replacement policy.
+///
+/// Every access marks a frame as [`Hot`], and every cooling pass cools [`Hot`] frames down to
+/// [`Cool`]. A frame that is still [`Cool`] on the next cooling pass has not been accessed in
+/// between, so it is chosen for eviction.
+///
+/// Since the [`EvictionState`]s already track everything this policy needs, the replacer itself is
+/// stateless.
+///
+/// [`EvictionState`]: crate::storage::EvictionState
+/// [`Hot`]: crate::storage::EvictionState::Hot
+/// [`Cool`]: crate::storage::EvictionState::Cool
+#[derive(Debug, Default)]
+pub(crate) struct ClockReplacer;
+
+impl Replacer for ClockReplacer {
+    fn record_access(&mut self, _index: usize, _pid: PageId) {}
+
+    fn record_eviction(&mut self, _index: usize) {}
+
+    fn victims(&mut self, candidates: &[ReplacementCandidate]) -> Vec<usize> {
+        // Frames that are already claimed were chosen on a previous pass but could not be evicted,
+        // so they were not accessed either, and we try again.
+        candidates
+            .iter()
+            .filter(|candidate| !candidate.accessed)
+            .map(|candidate| candidate.index)
+            .collect()
+    }
+}
@@ -0,0 +1,57 @@
+//! Implementation of the [`FifoReplacer`] type.
+
+use crate::page::PageId;
+use crate::storage::replacer::{ReplacementCandidate, Replacer};
+
+/// The first-in, first-out replacement policy.
+///
+/// The replacer evicts the pages that were loaded into memory the longest time ago, no matter how
+/// often or how recently they were accessed. This is cheaper than any other policy, but is mostly
+/// useful as a baseline.
+#[derive(Debug)]
+pub(crate) struct FifoReplacer {
+    /// A logical clock that is incremented every time a page is loaded.
+    now: u64,
+
+    /// The page that every frame holds and the time that it was loaded, if any.
+    loaded: Vec<Option<(PageId, u64)>>,
+
+    /// The maximum number of frames that the replacer chooses on a single cooling pass, which is
+    /// an eighth of the frame group (but at least one frame).
+    max_victims: usize,
+}
+
+impl FifoReplacer {
+    /// Creates a new FIFO replacer for a frame group of `group_size` frames.
+    pub(crate) fn new(group_size: usize) -> Self {
+        Self {
+            now: 0,
+            loaded: vec![None; group_size],
+            max_victims: (group_size / 8).max(1),
+        }
+    }
+}
+
+impl Replacer for FifoReplacer {
+    fn record_access(&mut self, index: usize, pid: PageId) {
+        // Only a page that was just loaded into the frame moves to the back of the queue.
+        if self.loaded[index].is_some_and(|(resident, _)| resident == pid) {
+            return;
+        }
+
+        self.now += 1;
+        self.loaded[index] = Some((pid, self.now));
+    }
+
+    fn record_eviction(&mut self, index: usize) {
+        self.loaded[index] = None;
+    }
+
+    fn victims(&mut self, candidates: &[ReplacementCandidate]) -> Vec<usize> {
+        let mut victims: Vec<usize> = candidates.iter().map(|candidate| candidate.index).collect();
+        victims.sort_by_key(|&index| self.loaded[index].map_or(0, |(_, loaded_at)| loaded_at));
+        victims.truncate(self.max_victims);
+
+        victims
+    }
+}
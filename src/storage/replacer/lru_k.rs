@@ -0,0 +1,105 @@
+//! Implementation of the [`LrukReplacer`] type.
+
+use crate::page::PageId;
+use crate::storage::replacer::{ReplacementCandidate, Replacer};
+use std::collections::{HashMap, VecDeque};
+
+/// The LRU-K replacement policy.
+///
+/// The replacer tracks the timestamps of the last `K` accesses to every resident page, and evicts
+/// the pages with the largest backward K-distance: the time since the K-th most recent access.
+/// Pages that have been accessed fewer than `K` times have an infinite backward K-distance, and
+/// are evicted before any other page (least recently accessed first).
+///
+/// Since a page that is only touched once by a sequential scan never reaches `K` accesses, scans
+/// cannot push frequently accessed pages out of memory, unlike with the
+/// [`ClockReplacer`](super::ClockReplacer).
+///
+/// The access history of a page is only kept while the page is resident in this group.
+#[derive(Debug)]
+pub(crate) struct LrukReplacer {
+    /// The number of accesses `K` that the backward K-distance is measured over.
+    k: usize,
+
+    /// A logical clock that is incremented on every access.
+    now: u64,
+
+    /// The page that every frame holds, if any.
+    resident: Vec<Option<PageId>>,
+
+    /// The maximum number of frames that the replacer chooses on a single cooling pass, which is
+    /// an eighth of the frame group (but at least one frame).
+    max_victims: usize,
+
+    /// The timestamps of the last `K` accesses to every resident page, from oldest to newest.
+    history: HashMap<PageId, VecDeque<u64>>,
+}
+
+impl LrukReplacer {
+    /// Creates a new LRU-K replacer for a frame group of `group_size` frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    pub(crate) fn new(k: usize, group_size: usize) -> Self {
+        assert!(k != 0, "LRU-K needs K to be at least 1");
+
+        Self {
+            k,
+            now: 0,
+            resident: vec![None; group_size],
+            max_victims: (group_size / 8).max(1),
+            history: HashMap::new(),
+        }
+    }
+
+    /// Computes the eviction priority of the page in the frame at `index`, where smaller keys
+    /// should be evicted first.
+    ///
+    /// Pages with an infinite backward K-distance sort first by their most recent access, and then
+    /// every other page sorts by its K-th most recent access.
+    fn priority(&self, index: usize) -> (bool, u64) {
+        let Some(history) = self.resident[index].and_then(|pid| self.history.get(&pid)) else {
+            return (false, 0);
+        };
+
+        if history.len() < self.k {
+            (false, history.back().copied().unwrap_or_default())
+        } else {
+            (true, history.front().copied().unwrap_or_default())
+        }
+    }
+}
+
+impl Replacer for LrukReplacer {
+    fn record_access(&mut self, index: usize, pid: PageId) {
+        self.now += 1;
+
+        // If the frame used to hold a different page, we have missed its eviction.
+        if let Some(old) = self.resident[index].replace(pid) {
+            if old != pid {
+                self.history.remove(&old);
+            }
+        }
+
+        let history = self.history.entry(pid).or_default();
+        history.push_back(self.now);
+        if history.len() > self.k {
+            history.pop_front();
+        }
+    }
+
+    fn record_eviction(&mut self, index: usize) {
+        if let Some(pid) = self.resident[index].take() {
+            self.history.remove(&pid);
+        }
+    }
+
+    fn victims(&mut self, candidates: &[ReplacementCandidate]) -> Vec<usize> {
+        let mut victims: Vec<usize> = candidates.iter().map(|candidate| candidate.index).collect();
+        victims.sort_by_key(|&index| self.priority(index));
+        victims.truncate(self.max_victims);
+
+        victims
+    }
+}
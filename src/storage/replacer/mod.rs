@@ -0,0 +1,70 @@
+//! This module contains the [`Replacer`] trait, which decides which [`Frame`]s of a [`FrameGroup`]
+//! get evicted, along with every replacement policy that the buffer pool manager ships with.
+//!
+//! Every [`FrameGroup`] owns its own replacer, which is protected by the same lock as the group's
+//! [`EvictionState`]s. The eviction states track which page every frame holds and whether a frame
+//! has been claimed for eviction, and the replacer only decides which of the frames that hold a
+//! page should be claimed next.
+//!
+//! The replacement policy is chosen when the buffer pool manager is initialized, with
+//! [`BufferPoolManagerConfig::replacement_policy`](crate::BufferPoolManagerConfig::replacement_policy).
+//!
+//! [`Frame`]: crate::storage::Frame
+//! [`FrameGroup`]: crate::storage::FrameGroup
+//! [`EvictionState`]: crate::storage::EvictionState
+
+mod clock;
+mod fifo;
+mod lru_k;
+
+pub(crate) use clock::*;
+pub(crate) use fifo::*;
+pub(crate) use lru_k::*;
+
+use crate::page::PageId;
+use std::fmt::Debug;
+
+/// A frame that a [`Replacer`] may choose to evict during a cooling pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReplacementCandidate {
+    /// The index of the frame within its frame group.
+    pub index: usize,
+
+    /// The page that the frame holds.
+    pub pid: PageId,
+
+    /// Whether the page was accessed since the previous cooling pass of the frame group.
+    pub accessed: bool,
+}
+
+/// A replacement policy for the buffer frames of a single frame group, which decides which pages
+/// get evicted.
+///
+/// Every frame group owns its own replacer, which is created when the buffer pool manager is
+/// initialized with the configured
+/// [`ReplacementPolicy`](crate::ReplacementPolicy). Frames are identified by their index within the
+/// group, which is always less than the
+/// [frame group size](crate::BufferPoolManagerConfig::frame_group_size).
+///
+/// The replacer is called while holding the lock on the frame group's eviction state, so every
+/// method should return quickly and must not access any pages.
+pub trait Replacer: Debug + Send {
+    /// Records that the page with the given [`PageId`] was accessed while held by the frame at
+    /// `index`.
+    ///
+    /// This is also called when a page is loaded into the frame, so a page that differs from the
+    /// previously recorded page of the frame means that the previous page was evicted.
+    fn record_access(&mut self, index: usize, pid: PageId);
+
+    /// Records that the frame at `index` no longer holds a page.
+    fn record_eviction(&mut self, index: usize);
+
+    /// Chooses the frames that should be claimed for eviction during a cooling pass, returning
+    /// their indices.
+    ///
+    /// `candidates` holds every frame that currently holds a page that may be evicted. Returned
+    /// indices that are not one of the candidates are ignored. Every candidate that is not chosen
+    /// is marked as not accessed for the next cooling pass.
+    fn victims(&mut self, candidates: &[ReplacementCandidate]) -> Vec<usize>;
+}
@@ -9,105 +9,893 @@
 //! this buffer pool manager will operate at its best when given access to several NVMe SSDs, all
 //! attached via PCIe lanes.
 
-use crate::{page::PageId, storage::frame::Frame};
-use std::io::Result;
-use std::ops::Deref;
+use crate::bpm::BufferPoolManager;
+use crate::bypass::PageBuf;
+use crate::error::{BpmError, ChecksumMismatch, CorruptPage};
+use crate::events::PageEvent;
+use crate::executor;
+use crate::files::DataFiles;
+use crate::latency::LatencyInjector;
+use crate::numa;
+use crate::quarantine::Quarantine;
+use crate::stats::UringStats;
+use crate::{
+    page::{FileId, PageId, DIRECT_IO_ALIGNMENT},
+    storage::{
+        checksum, decode_page, doublewrite::DoublewriteBuffer, frame::Frame, GroupCommit, IoDriver,
+        StorageBackend, CHECKSUM_SIZE, DOUBLEWRITE_SLOTS,
+    },
+};
+use std::alloc::{self, Layout};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
 use std::os::unix::fs::OpenOptionsExt;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::LazyLock;
-use std::{rc::Rc, sync::OnceLock};
+use std::path::PathBuf;
+use std::ptr;
+use std::rc::Rc;
+use std::slice;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::task::{Poll, Waker};
+use std::time::Instant;
+use tokio_uring::buf::fixed::{FixedBuf, FixedBufRegistry};
+use tokio_uring::buf::{BoundedBuf, IoBuf, IoBufMut};
 use tokio_uring::fs::File;
 use tokio_uring::BufResult;
 
 /// The name of the database's file.
 pub const DATABASE_NAME: &str = "bpm.db";
 
-/// The global storage manager instance.
-pub(crate) static STORAGE_MANAGER: OnceLock<StorageManager> = OnceLock::new();
+/// The global storage manager instance, or null if it has not been initialized.
+///
+/// Every instance is leaked, since [`StorageManager::get`] hands out `'static` references.
+static STORAGE_MANAGER: AtomicPtr<StorageManager> = AtomicPtr::new(ptr::null_mut());
+
+/// The number of storage manager instances that have been initialized so far.
+///
+/// Every instance is tagged with a unique pool ID, and all thread-local state is keyed by that ID,
+/// so that the thread-local state of an instance is never used by another one and can be
+/// recognized and discarded once its instance is shut down.
+static NEXT_POOL_ID: AtomicUsize = AtomicUsize::new(0);
 
 /// The total number of I/O operations.
 pub static IO_OPERATIONS: AtomicUsize = AtomicUsize::new(0);
 
 std::thread_local! {
-    static DB_FILE: LazyLock<Rc<File>> = LazyLock::new(|| {
-        let std_file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .custom_flags(libc::O_DIRECT)
-            .open(DATABASE_NAME)
-            .expect("Thread is unable to create a file handle");
+    /// The thread-local handles to every storage manager instance, which hold the file handles to
+    /// every drive and every data file, keyed by the pool ID of the instance.
+    static DB_FILES: RefCell<Vec<(usize, StorageManagerHandle)>> =
+        const { RefCell::new(Vec::new()) };
+
+    /// The buffer frames registered with the thread-local `io_uring` instance (or `None` if the
+    /// frames could not be registered), along with the pool ID of the storage manager that they
+    /// belong to.
+    ///
+    /// An `io_uring` instance only has a single table of registered buffers, so only one storage
+    /// manager per thread can use registered buffers at a time.
+    static REGISTERED_FRAMES: RefCell<Option<(usize, Option<FixedBufRegistry<RegisteredFrame>>)>> =
+        const { RefCell::new(None) };
+
+    /// The counters of the reads and writes submitted to the thread-local `io_uring` instance.
+    static URING_STATS: Cell<UringStats> = Cell::new(UringStats::default());
+
+    /// The tasks of this thread that are waiting for one of the thread's reads and writes to
+    /// complete, because the thread has the maximum number of them in flight.
+    static IO_WAITERS: RefCell<Vec<Waker>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The alignment of the start of every [`FrameArena`], which is the page size of the operating
+/// system.
+///
+/// Pages are read and written with `O_DIRECT`, which requires buffers to be aligned to the logical
+/// block size of the drive (at most 4 KiB in practice).
+pub(crate) const ARENA_ALIGNMENT: usize = 4096;
+
+/// A contiguous arena of memory that holds buffer frames.
+///
+/// The buffer pool allocates its frames in several arenas instead of a single allocation, and every
+/// arena is registered with `io_uring` as part of the same table of registered buffers. Every arena
+/// starts at a multiple of [`ARENA_ALIGNMENT`], so that every frame can be used with `O_DIRECT`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FrameArena {
+    /// The address of the first buffer frame in the arena.
+    ///
+    /// The `i`-th frame of the arena starts at `base + i * frame_size`.
+    pub(crate) base: usize,
+
+    /// The ID of the first buffer frame in the arena. Frame IDs are contiguous within an arena.
+    pub(crate) first_frame_id: usize,
+
+    /// The number of buffer frames in the arena.
+    pub(crate) num_frames: usize,
+
+    /// The size of every buffer frame in the arena.
+    pub(crate) frame_size: usize,
+
+    /// The index of the arena's first frame in the table of registered buffers, or `None` if the
+    /// arena is not registered.
+    ///
+    /// This is assigned by [`FrameArena::assign_buffer_indices`].
+    pub(crate) first_buf_index: Option<usize>,
+
+    /// The NUMA node that the arena's memory was allocated on, or `None` if the arena was
+    /// allocated on the heap.
+    pub(crate) numa_node: Option<usize>,
+}
+
+impl FrameArena {
+    /// Allocates `len` zeroed bytes on the heap for an arena, aligned to [`ARENA_ALIGNMENT`].
+    ///
+    /// The memory is leaked, and must be freed with [`FrameArena::free`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is zero or too large for an allocation, and aborts if the allocation fails.
+    pub(crate) fn alloc_heap(len: usize) -> &'static mut [u8] {
+        let layout = Self::heap_layout(len);
+        assert_ne!(layout.size(), 0, "Cannot allocate an empty frame arena");
+
+        // Safety: The layout has a non-zero size.
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+
+        // Safety: We just allocated `len` zeroed bytes at `ptr`, which nothing else points to.
+        unsafe { slice::from_raw_parts_mut(ptr, len) }
+    }
+
+    /// Gets the layout of a heap-allocated arena of `len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is too large for an allocation.
+    fn heap_layout(len: usize) -> Layout {
+        Layout::from_size_align(len, ARENA_ALIGNMENT).expect("The frame arena is too large")
+    }
+
+    /// Lays out the frames of every arena one after another in the table of registered buffers,
+    /// assigning every arena the index of its first frame.
+    ///
+    /// `io_uring` can only register a limited number of buffers, so any frames past that limit are
+    /// left unregistered and are read and written without registered buffers.
+    pub(crate) fn assign_buffer_indices(arenas: &mut [FrameArena]) {
+        let mut next = 0;
+
+        for arena in arenas {
+            arena.first_buf_index = (next < MAX_REGISTERED_FRAMES).then_some(next);
+            next += arena.num_frames;
+        }
+    }
+
+    /// Gets the index of the `i`-th frame of the arena in the table of registered buffers, or
+    /// `None` if the frame is not registered.
+    pub(crate) fn buf_index(&self, i: usize) -> Option<usize> {
+        self.first_buf_index
+            .map(|first| first + i)
+            .filter(|&index| index < MAX_REGISTERED_FRAMES)
+    }
+
+    /// Checks that the `i`-th frame of the arena was created with the expected frame ID, memory,
+    /// and registered buffer index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame does not match its position in the arena.
+    pub(crate) fn check_frame(&self, i: usize, frame: &Frame) {
+        assert_eq!(frame.frame_id(), self.first_frame_id + i);
+        assert_eq!(frame.buf_index(), self.buf_index(i));
+        assert_eq!(frame.as_ptr() as usize, self.base + i * self.frame_size);
+        assert_eq!(frame.as_ptr() as usize % DIRECT_IO_ALIGNMENT, 0);
+        assert_eq!(frame.len(), self.frame_size);
+    }
+
+    /// Gets a raw view of the `i`-th frame of the arena.
+    fn registered_frame(&self, i: usize) -> RegisteredFrame {
+        RegisteredFrame {
+            ptr: (self.base + i * self.frame_size) as *mut u8,
+            len: self.frame_size,
+        }
+    }
+
+    /// Releases the memory of every frame in the arena to the operating system, while keeping it
+    /// mapped. The memory reads as zeroes the next time it is touched.
+    ///
+    /// Only whole pages of memory that lie entirely within the arena are released. The caller must
+    /// make sure that no frame of the arena holds any data that is still needed.
+    pub(crate) fn release_memory(&self) {
+        // Safety: `sysconf` has no preconditions.
+        let os_page_size = usize::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) })
+            .unwrap_or(ARENA_ALIGNMENT);
+
+        let start = self.base.next_multiple_of(os_page_size);
+        let end = (self.base + self.num_frames * self.frame_size) / os_page_size * os_page_size;
+        if start >= end {
+            return;
+        }
+
+        // Safety: The range lies entirely within the arena's memory, which stays mapped until the
+        // arena is freed, and `MADV_DONTNEED` only replaces its contents with zeroes. Optimistic
+        // readers may still read from it, which is fine since they validate what they read.
+        unsafe { libc::madvise(start as *mut libc::c_void, end - start, libc::MADV_DONTNEED) };
+    }
+
+    /// Frees the memory of the arena.
+    ///
+    /// # Safety
+    ///
+    /// The arena must have been allocated with [`FrameArena::alloc_heap`] with a length of
+    /// `num_frames * frame_size` bytes (or with [`numa::alloc_on_node`] if `numa_node` is set), and
+    /// no [`Frame`] may point into the arena anymore.
+    pub(crate) unsafe fn free(&self) {
+        let len = self.num_frames * self.frame_size;
+
+        if self.numa_node.is_some() {
+            // Safety: Guaranteed by the caller.
+            unsafe { numa::free_on_node(self.base, len) };
+            return;
+        }
 
-        let uring_file = tokio_uring::fs::File::from_std(std_file);
-        Rc::new(uring_file)
-    });
+        // Safety: Guaranteed by the caller.
+        unsafe { alloc::dealloc(self.base as *mut u8, Self::heap_layout(len)) };
+    }
 }
 
 /// Manages reads into and writes from `Frame`s between memory and persistent storage.
+///
+/// Pages are striped across all of the drives (files) that the storage manager was initialized
+/// with, in the style of RAID-0: page `n` is stored on drive `n % num_drives`. The pages of every
+/// other [data file](crate::BufferPoolManager::create_file) are stored in a single file of their
+/// own instead.
 #[derive(Debug)]
-pub(crate) struct StorageManager;
+pub(crate) struct StorageManager {
+    /// The unique pool ID of this instance.
+    pool_id: usize,
+
+    /// The size of every page on persistent storage.
+    page_size: usize,
+
+    /// The paths to the database file on every drive.
+    paths: Vec<PathBuf>,
+
+    /// The path to the spill file that temporary pages are written to when they are evicted.
+    spill_path: PathBuf,
+
+    /// The arenas of buffer frames to register with every thread's `io_uring` instance, or `None`
+    /// if reads and writes should not use registered buffers.
+    registered_frames: Option<Vec<FrameArena>>,
+
+    /// Whether every page holds a checksum in its trailer.
+    checksums: bool,
+
+    /// Whether the database files and the spill file are opened with `O_DIRECT`, as opposed to
+    /// reading and writing pages through the operating system's page cache.
+    direct_io: bool,
+
+    /// The double-write buffer that every page is staged in before it is written out, or `None` if
+    /// pages are written out directly.
+    doublewrite: Option<DoublewriteBuffer>,
+
+    /// The pages that failed checksum verification, which are never read again until they are
+    /// repaired.
+    quarantine: Quarantine,
+
+    /// The registry of every data file other than [`FileId::DEFAULT`].
+    data_files: DataFiles,
+
+    /// The coordinator that batches durable flushes, or `None` if every durable flush writes and
+    /// syncs on its own.
+    group_commit: Option<GroupCommit>,
+
+    /// The artificial latency that is injected into the reads and writes of every drive.
+    latency: LatencyInjector,
+
+    /// The number of reads and writes that have been submitted but have not completed yet.
+    in_flight_io: AtomicUsize,
+
+    /// The pages that are currently being written out, used to check that a page is never written
+    /// out twice at the same time.
+    #[cfg(debug_assertions)]
+    in_flight_writes: scc::HashSet<PageId>,
+}
+
+/// The state and settings that a [`StorageManager`] is initialized with, other than its page size
+/// and the paths of its database files.
+#[derive(Debug)]
+pub(crate) struct StorageOptions {
+    /// The arenas of buffer frames to register with every thread's `io_uring` instance, or `None`
+    /// if reads and writes should not use registered buffers.
+    ///
+    /// If this is set, every thread registers the frames of those arenas with its `io_uring`
+    /// instance the first time it performs I/O, and then reads and writes pages with the
+    /// `ReadFixed` and `WriteFixed` operations, which saves the kernel from having to pin the
+    /// frame's memory on every operation. The arenas must have been laid out with
+    /// [`FrameArena::assign_buffer_indices`].
+    pub(crate) registered_frames: Option<Vec<FrameArena>>,
+
+    /// Whether every page is written out with a checksum in its trailer, which is verified every
+    /// time the page is read back in. Pages that fail verification are added to `quarantine`.
+    pub(crate) checksums: bool,
+
+    /// Whether pages are read and written with `O_DIRECT`, as opposed to through the operating
+    /// system's page cache.
+    pub(crate) direct_io: bool,
+
+    /// The double-write buffer that every page (other than temporary pages) is staged in before it
+    /// is written out, see [`DoublewriteBuffer`].
+    pub(crate) doublewrite: Option<DoublewriteBuffer>,
+
+    /// The pages that failed checksum verification.
+    pub(crate) quarantine: Quarantine,
+
+    /// Every data file other than [`FileId::DEFAULT`], whose pages are stored in that data file
+    /// instead of the database files.
+    pub(crate) data_files: DataFiles,
+
+    /// The coordinator that batches durable flushes, see [`GroupCommit`].
+    pub(crate) group_commit: Option<GroupCommit>,
+}
 
 impl StorageManager {
-    /// Creates a new shared [`StorageManager`] instance.
+    /// Creates a new shared [`StorageManager`] instance that stripes pages across the files at
+    /// the given paths, where each file is expected to live on a different drive, with the given
+    /// [`StorageOptions`].
     ///
     /// # Panics
     ///
-    /// Panics on I/O errors, or if this function is called a second time after a successful return.
-    pub(crate) fn initialize(_capacity: usize) {
-        tokio_uring::start(async {
-            // let _ = tokio_uring::fs::remove_file(DATABASE_NAME).await;
+    /// Panics if `paths` is empty, if the registered buffer indices of the arenas are
+    /// inconsistent, or if this function is called a second time after a successful return without
+    /// a call to [`StorageManager::shutdown`] in between.
+    pub(crate) fn initialize_with_paths(
+        page_size: usize,
+        paths: &[PathBuf],
+        options: StorageOptions,
+    ) {
+        let StorageOptions {
+            registered_frames,
+            checksums,
+            direct_io,
+            doublewrite,
+            quarantine,
+            data_files,
+            group_commit,
+        } = options;
 
-            // let file = File::create(DATABASE_NAME).await?;
-            // file.fallocate(0, (capacity * PAGE_SIZE) as u64, libc::FALLOC_FL_ZERO_RANGE)
-            //     .await?;
+        assert!(
+            !paths.is_empty(),
+            "The storage manager needs at least one file"
+        );
 
-            // file.close().await?;
-            Ok::<(), std::io::Error>(())
-        })
-        .expect("I/O error on initialization");
+        // Every thread registers the frames in the order of their buffer indices, so the indices
+        // must count up from 0 without any gaps.
+        let buf_indices = registered_frames
+            .iter()
+            .flatten()
+            .flat_map(|arena| (0..arena.num_frames).map_while(|i| arena.buf_index(i)));
+        for (expected, index) in buf_indices.enumerate() {
+            assert_eq!(index, expected, "Inconsistent registered buffer indices");
+        }
+
+        // Temporary pages never outlive the buffer pool, so a leftover spill file is garbage.
+        let spill_path = paths[0].with_extension("spill");
+        let _ = std::fs::remove_file(&spill_path);
+
+        let sm = Box::into_raw(Box::new(Self {
+            pool_id: NEXT_POOL_ID.fetch_add(1, Ordering::Relaxed) + 1,
+            page_size,
+            paths: paths.to_vec(),
+            spill_path,
+            registered_frames,
+            checksums,
+            direct_io,
+            doublewrite,
+            quarantine,
+            data_files,
+            group_commit,
+            latency: LatencyInjector::new(paths.len()),
+            in_flight_io: AtomicUsize::new(0),
+            #[cfg(debug_assertions)]
+            in_flight_writes: scc::HashSet::new(),
+        }));
 
         STORAGE_MANAGER
-            .set(Self)
+            .compare_exchange(ptr::null_mut(), sm, Ordering::AcqRel, Ordering::Acquire)
             .expect("Tried to set the global storage manager more than once");
     }
 
+    /// Resets the global storage manager, so that a new instance can be initialized.
+    ///
+    /// This drops the calling thread's file handles and unregisters its registered buffers. Other
+    /// threads discard the thread-local state of the instance when their runtime exits (see
+    /// [`StorageManager::release_thread_state`]), or lazily, the next time they use a storage
+    /// manager.
+    ///
+    /// The caller must make sure that no I/O is in flight on any thread.
+    pub(crate) fn shutdown() {
+        let sm = STORAGE_MANAGER.swap(ptr::null_mut(), Ordering::AcqRel);
+
+        // Safety: Every instance is leaked, so a non-null pointer is valid forever.
+        if let Some(sm) = unsafe { sm.as_ref() } {
+            let _ = std::fs::remove_file(&sm.spill_path);
+        }
+
+        Self::release_thread_state();
+    }
+
+    /// Drops the calling thread's file handles and unregisters its registered buffers, for every
+    /// storage manager instance.
+    ///
+    /// This must be called from within the runtime that the buffers were registered with, and the
+    /// caller must make sure that no I/O is in flight on the calling thread. Any storage manager
+    /// that is still alive recreates the state lazily the next time the thread uses it.
+    pub(crate) fn release_thread_state() {
+        DB_FILES.with(|files| files.borrow_mut().clear());
+        URING_STATS.set(UringStats::default());
+
+        if let Some((_, Some(registry))) = REGISTERED_FRAMES.with(|cell| cell.borrow_mut().take()) {
+            let _ = registry.unregister();
+        }
+    }
+
+    /// Checks if the storage manager instance with the given pool ID has not been shut down yet.
+    fn is_live(pool_id: usize) -> bool {
+        let sm = STORAGE_MANAGER.load(Ordering::Acquire);
+
+        // Safety: Every instance is leaked, so a non-null pointer is valid forever.
+        !sm.is_null() && unsafe { (*sm).pool_id } == pool_id
+    }
+
     /// Retrieve a static reference to the global storage manager.
     ///
     /// # Panics
     ///
-    /// This function will panic if it is called before a call to [`StorageManager::initialize`].
+    /// This function will panic if it is called before a call to
+    /// [`StorageManager::initialize_with_paths`].
     pub(crate) fn get() -> &'static Self {
-        STORAGE_MANAGER
-            .get()
-            .expect("Tried to get a reference to the storage manager before it was initialized")
+        let sm = STORAGE_MANAGER.load(Ordering::Acquire);
+        assert!(
+            !sm.is_null(),
+            "Tried to get a reference to the storage manager before it was initialized"
+        );
+
+        // Safety: Every instance is leaked, so a non-null pointer is valid forever.
+        unsafe { &*sm }
     }
 
     /// Creates a thread-local [`StorageManagerHandle`] that has a reference back to this storage
     /// manager.
     ///
+    /// The first call to this function on a thread opens a file handle to every drive (and to the
+    /// spill file, creating it if needed), and all subsequent calls on the same thread share those
+    /// file handles. The file handles to other data files are opened lazily, the first time that
+    /// one of their pages is read or written on the thread.
+    ///
+    /// Unlike the buffer frames, the file handles are not registered with the thread's `io_uring`
+    /// instance: `tokio_uring` submits every operation on a [`File`] with its raw file descriptor
+    /// and does not expose a table of registered files, so there is no fixed-file variant of the
+    /// operations to switch to.
+    ///
     /// # Errors
     ///
     /// Returns an error if unable to create a [`File`] to the database files on disk.
     pub(crate) fn create_handle(&self) -> Result<StorageManagerHandle> {
-        let file = DB_FILE.with(|f| f.deref().clone());
+        let cached = DB_FILES.with(|cell| {
+            let mut cache = cell.borrow_mut();
+
+            // Close the file handles of every instance that has been shut down in the meantime.
+            cache.retain(|&(pool_id, _)| Self::is_live(pool_id));
+
+            cache
+                .iter()
+                .find(|&&(pool_id, _)| pool_id == self.pool_id)
+                .map(|(_, handle)| handle.clone())
+        });
+        if let Some(handle) = cached {
+            return Ok(handle);
+        }
+
+        // The spill file comes after the database files, so that the drive of a temporary page is
+        // `num_drives`, followed by the double-write buffer file (which is not read with
+        // `O_DIRECT`), if there is one. With a storage backend, pages never touch the database
+        // files or the spill file, so only the double-write buffer file is opened.
+        let flags = if self.direct_io { libc::O_DIRECT } else { 0 };
+        let page_files: &[PathBuf] = match BufferPoolManager::get().storage_backend() {
+            Some(_) => &[],
+            None => &self.paths[..],
+        };
+        let files: Rc<[Rc<File>]> = page_files
+            .iter()
+            .chain(page_files.first().map(|_| &self.spill_path))
+            .map(|path| {
+                let std_file = std::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(*path == self.spill_path)
+                    .custom_flags(flags)
+                    .open(path)?;
+
+                Ok(Rc::new(File::from_std(std_file)))
+            })
+            .chain(self.doublewrite.iter().map(|doublewrite| {
+                let std_file = std::fs::OpenOptions::new()
+                    .write(true)
+                    .open(doublewrite.path())?;
+
+                Ok(Rc::new(File::from_std(std_file)))
+            }))
+            .collect::<Result<_>>()?;
+
+        let handle = StorageManagerHandle {
+            files,
+            data_files: Rc::default(),
+        };
+        DB_FILES.with(|cell| cell.borrow_mut().push((self.pool_id, handle.clone())));
+
+        Ok(handle)
+    }
+
+    /// Gets the buffer frames registered with the thread-local `io_uring` instance, registering
+    /// them first if this is the first call on this thread.
+    ///
+    /// Returns `None` if registered buffers are disabled or if the registration failed (for
+    /// example, because the frames exceed the thread's locked memory limit), in which case the
+    /// caller should fall back to regular reads and writes.
+    ///
+    /// Note that the frames are only registered with the `io_uring` instance of the runtime that
+    /// is running when this is first called, so a thread must call
+    /// [`StorageManager::release_thread_state`] before starting a second runtime after performing
+    /// I/O with registered buffers. [`BufferPoolManager::start_thread`] takes care of this.
+    fn registered_frames(&self) -> Option<FixedBufRegistry<RegisteredFrame>> {
+        let arenas = self.registered_frames.as_ref()?;
+
+        REGISTERED_FRAMES.with(|cell| {
+            let mut cell = cell.borrow_mut();
+
+            match cell.take() {
+                Some((pool_id, registry)) if pool_id == self.pool_id => {
+                    *cell = Some((pool_id, registry));
+                }
+                stale => {
+                    // Buffers registered by a previous storage manager must be unregistered before
+                    // we can register our own.
+                    if let Some((_, Some(registry))) = stale {
+                        let _ = registry.unregister();
+                    }
+
+                    // Register every frame that was assigned a buffer index, in index order.
+                    let frames = arenas.iter().flat_map(|arena| {
+                        (0..arena.num_frames)
+                            .map_while(|i| arena.buf_index(i).map(|_| arena.registered_frame(i)))
+                    });
+
+                    let registry = FixedBufRegistry::new(frames);
+                    *cell = Some((self.pool_id, registry.register().ok().map(|()| registry)));
+                }
+            }
+
+            cell.as_ref().and_then(|(_, registry)| registry.clone())
+        })
+    }
+
+    /// Gets the size of every page on persistent storage.
+    pub(crate) fn page_size(&self) -> usize {
+        self.page_size
+    }
 
-        Ok(StorageManagerHandle { file })
+    /// Checks if every page holds a checksum in its trailer.
+    pub(crate) fn checksums(&self) -> bool {
+        self.checksums
+    }
+
+    /// Gets the number of bytes at the start of every page that the registered
+    /// [`PageCodec`](crate::PageCodec) encodes, which is every byte except for the checksum.
+    pub(crate) fn codec_len(&self) -> usize {
+        if self.checksums {
+            self.page_size - CHECKSUM_SIZE
+        } else {
+            self.page_size
+        }
+    }
+
+    /// Checks if pages are read and written with `O_DIRECT`.
+    pub(crate) fn direct_io(&self) -> bool {
+        self.direct_io
+    }
+
+    /// Checks if the filesystems of the database files support `O_DIRECT`.
+    ///
+    /// Files that cannot be opened for another reason (for example, because they do not exist yet)
+    /// are assumed to support it, so that the error surfaces when the files are actually opened.
+    pub(crate) fn supports_direct_io(paths: &[PathBuf]) -> bool {
+        paths.iter().all(|path| {
+            let res = std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_DIRECT)
+                .open(path);
+
+            !matches!(res, Err(e) if e.raw_os_error() == Some(libc::EINVAL))
+        })
+    }
+
+    /// Gets the counters of the reads and writes submitted to the calling thread's `io_uring`
+    /// instance.
+    pub(crate) fn uring_stats() -> UringStats {
+        URING_STATS.get()
+    }
+
+    /// Gets the number of reads and writes that have been submitted but have not completed yet.
+    pub(crate) fn num_in_flight_io(&self) -> usize {
+        self.in_flight_io.load(Ordering::Acquire)
+    }
+
+    /// Gets the quarantine list of pages that failed checksum verification.
+    pub(crate) fn quarantine(&self) -> &Quarantine {
+        &self.quarantine
+    }
+
+    /// Gets the registry of every data file other than [`FileId::DEFAULT`].
+    pub(crate) fn data_files(&self) -> &DataFiles {
+        &self.data_files
+    }
+
+    /// Gets the coordinator that batches durable flushes, if group commit is enabled.
+    pub(crate) fn group_commit(&self) -> Option<&GroupCommit> {
+        self.group_commit.as_ref()
+    }
+
+    /// Gets the artificial latency that is injected into the reads and writes of every drive.
+    pub(crate) fn latency(&self) -> &LatencyInjector {
+        &self.latency
+    }
+
+    /// Gets the paths to the database file on every drive.
+    pub(crate) fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Checks if the database files on persistent storage can currently be opened for writing.
+    pub(crate) fn is_writable(&self) -> bool {
+        // A storage backend reports its failures through its reads and writes instead.
+        if BufferPoolManager::get().storage_backend().is_some() {
+            return true;
+        }
+
+        self.paths
+            .iter()
+            .all(|path| std::fs::OpenOptions::new().write(true).open(path).is_ok())
     }
 
     /// Retrieves the number of drives that the pages are stored on in persistent storage.
     ///
     /// # Panics
     ///
-    /// This function will panic if it is called before a call to [`StorageManager::initialize`].
+    /// This function will panic if it is called before a call to
+    /// [`StorageManager::initialize_with_paths`].
     pub(crate) fn get_num_drives() -> usize {
-        1 // This buffer pool manager currently only supports 1 drive.
+        Self::get().paths.len()
     }
 }
 
+/// The kind of I/O operation that is performed on a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IoKind {
+    /// A page read.
+    Read,
+
+    /// A page write.
+    Write,
+}
+
 /// A thread-local handle to a [`StorageManager`].
 #[derive(Debug, Clone)]
 pub(crate) struct StorageManagerHandle {
-    /// A shared pointer to the thread-local file handle.
-    file: Rc<File>,
+    /// A shared pointer to the thread-local file handles of every drive.
+    files: Rc<[Rc<File>]>,
+
+    /// A shared pointer to the thread-local file handles of the data files other than
+    /// [`FileId::DEFAULT`] that have been opened so far.
+    data_files: Rc<RefCell<DataFileHandles>>,
+}
+
+/// The thread-local file handles of the data files other than [`FileId::DEFAULT`].
+#[derive(Debug, Default)]
+struct DataFileHandles {
+    /// The [version](DataFiles::version) of the registry of data files that the handles were last
+    /// checked against.
+    version: u64,
+
+    /// The file handle of every data file that has been opened so far.
+    files: BTreeMap<FileId, Rc<File>>,
+}
+
+/// Updates the counters of the thread-local `io_uring` instance, and returns the updated counters.
+///
+/// Does nothing if the thread-local state has already been destroyed because the thread is
+/// exiting.
+fn update_uring_stats(f: impl FnOnce(&mut UringStats)) -> UringStats {
+    URING_STATS
+        .try_with(|cell| {
+            let mut stats = cell.get();
+            f(&mut stats);
+            cell.set(stats);
+            stats
+        })
+        .unwrap_or_default()
+}
+
+/// Counts a read or a write as in flight for as long as it is alive, both globally and in the
+/// counters of the thread-local `io_uring` instance.
+struct InFlightIo {
+    /// The instant that the operation was submitted.
+    start: Instant,
+
+    /// The span that covers the operation, which is closed once it completes.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl InFlightIo {
+    /// Marks a read or a write of the given page as in flight, first waiting until the calling
+    /// thread has fewer than
+    /// [`BufferPoolManagerConfig::max_in_flight_io`](crate::BufferPoolManagerConfig::max_in_flight_io)
+    /// operations in flight.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    async fn submit(pid: PageId, kind: IoKind) -> Self {
+        if let Some(max) = BufferPoolManager::get().max_in_flight_io() {
+            Self::wait_for_slot(max as u64).await;
+        }
+
+        StorageManager::get()
+            .in_flight_io
+            .fetch_add(1, Ordering::AcqRel);
+
+        let stats = update_uring_stats(|stats| {
+            stats.submissions += 1;
+            stats.in_flight += 1;
+            stats.max_in_flight = stats.max_in_flight.max(stats.in_flight);
+        });
+
+        Self {
+            start: Instant::now(),
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(
+                "page_io",
+                %pid,
+                ?kind,
+                depth = stats.in_flight,
+                latency_us = tracing::field::Empty,
+            ),
+        }
+    }
+
+    /// Waits until the calling thread has fewer than `max` reads and writes in flight.
+    async fn wait_for_slot(max: u64) {
+        let mut throttled = false;
+
+        std::future::poll_fn(|cx| {
+            if URING_STATS.get().in_flight < max {
+                return Poll::Ready(());
+            }
+
+            if !throttled {
+                throttled = true;
+                update_uring_stats(|stats| stats.throttled += 1);
+            }
+
+            IO_WAITERS.with(|waiters| waiters.borrow_mut().push(cx.waker().clone()));
+            Poll::Pending
+        })
+        .await;
+    }
+}
+
+impl Drop for InFlightIo {
+    fn drop(&mut self) {
+        StorageManager::get()
+            .in_flight_io
+            .fetch_sub(1, Ordering::AcqRel);
+
+        // Operations that are dropped by a runtime shutdown may outlive the thread-local state.
+        let latency = self.start.elapsed();
+        update_uring_stats(|stats| {
+            stats.in_flight = stats.in_flight.saturating_sub(1);
+            stats.completions += 1;
+            stats.completion_time += latency;
+            stats.max_completion_time = stats.max_completion_time.max(latency);
+        });
+
+        #[cfg(feature = "tracing")]
+        self.span.record("latency_us", latency.as_micros() as u64);
+
+        // Every waiter checks for a free slot again, since some of them may have been cancelled.
+        let waiters = IO_WAITERS
+            .try_with(|waiters| std::mem::take(&mut *waiters.borrow_mut()))
+            .unwrap_or_default();
+        waiters.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Marks a page as being written out for as long as it is alive.
+///
+/// This is only used in debug builds, to check that a page is never written out twice at the same
+/// time.
+#[cfg(debug_assertions)]
+struct InFlightWrite {
+    /// The page that is being written out.
+    pid: PageId,
+}
+
+#[cfg(debug_assertions)]
+impl InFlightWrite {
+    /// Marks a page as being written out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the page is already being written out.
+    fn new(pid: PageId) -> Self {
+        assert!(
+            StorageManager::get().in_flight_writes.insert(pid).is_ok(),
+            "{pid} is being written out twice concurrently"
+        );
+
+        Self { pid }
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for InFlightWrite {
+    fn drop(&mut self) {
+        StorageManager::get().in_flight_writes.remove(&self.pid);
+    }
+}
+
+/// The maximum number of buffer frames that can be registered with `io_uring` (`UIO_MAXIOV`).
+const MAX_REGISTERED_FRAMES: usize = 1024;
+
+/// The maximum number of buffers of a single vectored read or write (`UIO_MAXIOV`).
+const MAX_IOVECS: usize = 1024;
+
+/// A raw view of a buffer frame's memory, used to register the frame with `io_uring`.
+///
+/// The registry that owns these views never reads or writes the memory itself. The memory is
+/// only accessed by the kernel while a [`FixedBuf`] for the frame is checked out, which only
+/// happens while the caller owns the corresponding [`Frame`].
+#[derive(Debug, Clone)]
+struct RegisteredFrame {
+    /// A pointer to the start of the frame's memory, which is never deallocated.
+    ptr: *mut u8,
+
+    /// The length of the frame's memory.
+    len: usize,
+}
+
+/// # Safety
+///
+/// The frame's memory is leaked when the buffer pool is initialized, so the pointer stays valid
+/// and stable forever.
+unsafe impl IoBuf for RegisteredFrame {
+    fn stable_ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    fn bytes_init(&self) -> usize {
+        self.len
+    }
+
+    fn bytes_total(&self) -> usize {
+        self.len
+    }
+}
+
+/// # Safety
+///
+/// See the [`IoBuf`] implementation. Every byte of a frame is always initialized.
+unsafe impl IoBufMut for RegisteredFrame {
+    fn stable_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr
+    }
+
+    unsafe fn set_init(&mut self, _pos: usize) {}
 }
 
 impl StorageManagerHandle {
@@ -120,13 +908,181 @@ impl StorageManagerHandle {
     /// the kernel to write the data into it), this function takes full ownership of the frame and
     /// then gives it back to the caller on return.
     ///
+    /// If checksums are enabled, the page's checksum is verified after the read, and a page that
+    /// does not match its checksum is quarantined. The page is then decoded with the registered
+    /// [`PageCodec`](crate::PageCodec), if there is one. The read waits for any
+    /// [injected latency](crate::InjectedLatency) of the page's drive first.
+    ///
+    /// If the buffer pool has [I/O driver threads](crate::BufferPoolManagerConfig::io_threads),
+    /// the read is performed by one of them (see [`IoDriver`]), unless this is called on one.
+    ///
     /// # Errors
     ///
     /// On any sort of error, we still need to return the `Frame` back to the caller, so both the
-    /// `Ok` and `Err` cases return the frame back.
-    pub(crate) async fn read_into(&self, pid: PageId, frame: Frame) -> BufResult<(), Frame> {
+    /// `Ok` and `Err` cases return the frame back. If the page does not match its checksum, this
+    /// returns a [`ChecksumMismatch`] error, and every later read of the page returns a
+    /// [`CorruptPage`] error without reading anything. Returns an error if this is not called from
+    /// within a runtime, where no I/O can be submitted.
+    ///
+    /// If this future is dropped before it completes (for example, because the runtime shuts
+    /// down), the frame is returned to its frame group (see [`Frame::begin_io`]).
+    pub(crate) async fn read_into(&self, pid: PageId, mut frame: Frame) -> BufResult<(), Frame> {
+        if let Some(driver) = IoDriver::for_current_thread() {
+            return driver.page(IoKind::Read, pid, frame).await;
+        }
+
+        if let Err(e) = Self::check_runtime() {
+            return (Err(e), frame);
+        }
+
+        frame.begin_io(IoKind::Read);
+        let (res, mut frame) = self.read_page(pid, frame).await;
+        frame.end_io();
+
+        (res, frame)
+    }
+
+    /// Reads a page's data into a `Frame`, see [`StorageManagerHandle::read_into`].
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::read_into`].
+    async fn read_page(&self, pid: PageId, frame: Frame) -> BufResult<(), Frame> {
+        let sm = StorageManager::get();
+        if sm.quarantine.contains(pid) {
+            return (Err(CorruptPage::new(pid).into()), frame);
+        }
+
+        IO_OPERATIONS.fetch_add(1, Ordering::Relaxed);
+        let _in_flight = InFlightIo::submit(pid, IoKind::Read).await;
+        let start = Instant::now();
+        sm.latency.delay(pid.drive(), IoKind::Read).await;
+
+        let (res, mut frame) = match BufferPoolManager::get().storage_backend() {
+            Some(backend) => {
+                let mut frame = frame;
+                let res = Self::backend_read(backend, pid, &mut frame).await;
+                (res, frame)
+            }
+            None => self.read_file(pid, frame).await,
+        };
+
+        let res = Self::finish_read(pid, &mut frame, res, start);
+        (res, frame)
+    }
+
+    /// Finishes a read of a page into `buf` that was started at `start` and completed with `res`,
+    /// by recording it, verifying the page's checksum, and decoding the page.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the read, or a [`ChecksumMismatch`] error if the page does not match
+    /// its checksum, or the error of the registered [`PageCodec`](crate::PageCodec).
+    fn finish_read(pid: PageId, buf: &mut [u8], mut res: Result<()>, start: Instant) -> Result<()> {
+        let sm = StorageManager::get();
+
+        // A temporary page that was never evicted has not been written to the spill file yet.
+        if pid.is_temp()
+            && res
+                .as_ref()
+                .is_err_and(|e| e.kind() == ErrorKind::UnexpectedEof)
+        {
+            buf.fill(0);
+            res = Ok(());
+        }
+
+        if res.is_ok() {
+            BufferPoolManager::get()
+                .stats
+                .record_page_read(start.elapsed());
+        }
+
+        if res.is_ok() && sm.checksums {
+            if let Err((stored, computed)) = checksum::verify(buf) {
+                // Temporary pages are never read again after a restart, so they are only reported.
+                if !pid.is_temp() {
+                    let _ = sm.quarantine.insert(pid);
+                }
+
+                return Err(ChecksumMismatch::new(pid, stored, computed).into());
+            }
+        }
+
+        if let Some(codec) = BufferPoolManager::get().page_codec() {
+            res = res.and_then(|()| decode_page(pid, codec, &mut buf[..sm.codec_len()]));
+        }
+
+        res
+    }
+
+    /// Reads a page's data into a [`PageBuf`] that does not belong to the buffer pool, see
+    /// [`BufferPoolManager::read_bypass`].
+    ///
+    /// This behaves like [`StorageManagerHandle::read_into`], except that the data is always read
+    /// without a registered buffer.
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::read_into`]. The buffer is given back in both the `Ok` and
+    /// `Err` cases.
+    pub(crate) async fn read_bypass(&self, pid: PageId, buf: PageBuf) -> BufResult<(), PageBuf> {
+        if let Some(driver) = IoDriver::for_current_thread() {
+            return driver.bypass(pid, buf).await;
+        }
+
+        if let Err(e) = Self::check_runtime() {
+            return (Err(e), buf);
+        }
+
+        let sm = StorageManager::get();
+        if sm.quarantine.contains(pid) {
+            return (Err(CorruptPage::new(pid).into()), buf);
+        }
+
         IO_OPERATIONS.fetch_add(1, Ordering::Relaxed);
-        self.file.read_exact_at(frame, pid.offset()).await
+        let _in_flight = InFlightIo::submit(pid, IoKind::Read).await;
+        let start = Instant::now();
+        sm.latency.delay(pid.drive(), IoKind::Read).await;
+
+        let (res, mut buf) = match BufferPoolManager::get().storage_backend() {
+            Some(backend) => {
+                let mut buf = buf;
+                let res = Self::backend_read(backend, pid, &mut buf).await;
+                (res, buf)
+            }
+            None => match self.file(pid) {
+                Ok(file) => file.read_exact_at(buf, pid.offset()).await,
+                Err(e) => (Err(e), buf),
+            },
+        };
+
+        let res = Self::finish_read(pid, &mut buf, res, start);
+        (res, buf)
+    }
+
+    /// Reads a page's data into a `Frame` from its database file (or from the spill file).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read fails, or if the file ends before the entire page was read.
+    async fn read_file(&self, pid: PageId, frame: Frame) -> BufResult<(), Frame> {
+        let file = match self.file(pid) {
+            Ok(file) => file,
+            Err(e) => return (Err(e), frame),
+        };
+
+        match Self::check_out(&frame) {
+            Some(fixed) => {
+                // The kernel may keep reading into a registered buffer after this future is
+                // dropped, so the frame must not be recovered until it is done.
+                let mut frame = frame;
+                frame.begin_fixed_io();
+                let res = Self::read_fixed(&file, pid, fixed).await;
+                frame.end_fixed_io();
+                (res, frame)
+            }
+            None => file.read_exact_at(frame, pid.offset()).await,
+        }
     }
 
     /// Writes a page's data on a `Frame` to persistent storage.
@@ -138,12 +1094,836 @@ impl StorageManagerHandle {
     /// the kernel to write the data into it), this function takes full ownership of the frame and
     /// then gives it back to the caller on return.
     ///
+    /// The page is encoded in place with the registered [`PageCodec`](crate::PageCodec) (if there
+    /// is one) for the duration of the write, and if checksums are enabled, the page's checksum is
+    /// updated before the write. If the frame is dirty and does not hold a temporary page, the
+    /// registered [`WalHook`](crate::WalHook) is awaited before the write. The write waits for any
+    /// [injected latency](crate::InjectedLatency) of the page's drive first.
+    ///
+    /// Every caller takes the frame out of the page's write guard for the duration of the write,
+    /// so a page can never be written out twice concurrently (for example, by an explicit flush
+    /// racing with an eviction): whoever comes second has to wait for the page's write lock, and
+    /// then writes out the page's latest data. Debug builds check this on every write.
+    ///
+    /// Like [`StorageManagerHandle::read_into`], the write is performed by an I/O driver thread if
+    /// the buffer pool has any.
+    ///
     /// # Errors
     ///
     /// On any sort of error, we still need to return the `Frame` back to the caller, so both the
-    /// `Ok` and `Err` cases return the frame back.
-    pub(crate) async fn write_from(&self, pid: PageId, frame: Frame) -> BufResult<(), Frame> {
+    /// `Ok` and `Err` cases return the frame back. Returns an error if this is not called from
+    /// within a runtime, where no I/O can be submitted.
+    ///
+    /// If this future is dropped before it completes (for example, because the runtime shuts
+    /// down), the frame is given back to its page, which stays dirty (see [`Frame::begin_io`]).
+    pub(crate) async fn write_from(&self, pid: PageId, mut frame: Frame) -> BufResult<(), Frame> {
+        if let Some(driver) = IoDriver::for_current_thread() {
+            return driver.page(IoKind::Write, pid, frame).await;
+        }
+
+        if let Err(e) = Self::check_runtime() {
+            return (Err(e), frame);
+        }
+
+        frame.begin_io(IoKind::Write);
+        let (res, mut frame) = self.write_page(pid, frame).await;
+        frame.restore_plain();
+        frame.end_io();
+
+        if res.is_ok() {
+            Self::record_flushes(pid, slice::from_ref(&frame));
+        }
+
+        (res, frame)
+    }
+
+    /// Writes a page's data on a `Frame` to persistent storage, see
+    /// [`StorageManagerHandle::write_from`].
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::write_from`].
+    async fn write_page(&self, pid: PageId, mut frame: Frame) -> BufResult<(), Frame> {
+        // Write-ahead logging: the log records of a dirty page must be durable before the page is.
+        if frame.is_dirty() && !pid.is_temp() {
+            let bpm = BufferPoolManager::get();
+            let dirty = frame.dirty_range().unwrap_or(0..frame.data().len());
+            if let Err(e) = bpm.before_write_back(pid, frame.lsn(), dirty).await {
+                return (Err(e), frame);
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        let _in_flight_write = InFlightWrite::new(pid);
+
+        // The checksum covers the encoded page, so that corruption is detected before decoding.
+        if let Some(codec) = BufferPoolManager::get().page_codec() {
+            if let Err(e) = frame.encode(pid, codec, StorageManager::get().codec_len()) {
+                return (Err(e), frame);
+            }
+        }
+
+        if StorageManager::get().checksums {
+            checksum::seal(&mut frame);
+        }
+
+        if StorageManager::get().doublewrite.is_some() && !pid.is_temp() {
+            let (res, mut frames) = self.write_staged(pid, vec![frame]).await;
+            let frame = frames.pop().expect("Staged writes give back every frame");
+            return (res, frame);
+        }
+
         IO_OPERATIONS.fetch_add(1, Ordering::Relaxed);
-        self.file.write_all_at(frame, pid.offset()).await
+        let _in_flight = InFlightIo::submit(pid, IoKind::Write).await;
+
+        let start = Instant::now();
+        StorageManager::get()
+            .latency
+            .delay(pid.drive(), IoKind::Write)
+            .await;
+
+        let (res, frame) = match BufferPoolManager::get().storage_backend() {
+            Some(backend) => {
+                let res = Self::backend_write(backend, pid, &frame).await;
+                (res, frame)
+            }
+            None => self.write_file(pid, frame).await,
+        };
+
+        if res.is_ok() {
+            BufferPoolManager::get()
+                .stats
+                .record_page_write(start.elapsed());
+        }
+
+        (res, frame)
+    }
+
+    /// Writes a page's data on a `Frame` to its database file (or to the spill file).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the write fails.
+    async fn write_file(&self, pid: PageId, mut frame: Frame) -> BufResult<(), Frame> {
+        let file = match self.file(pid) {
+            Ok(file) => file,
+            Err(e) => return (Err(e), frame),
+        };
+
+        match Self::check_out(&frame) {
+            Some(fixed) => {
+                // The kernel may keep reading from a registered buffer after this future is
+                // dropped, so the frame must not be recovered until it is done.
+                frame.begin_fixed_io();
+                let (res, _) = file.write_fixed_all_at(fixed, pid.offset()).await;
+                frame.end_fixed_io();
+                (res, frame)
+            }
+            None => file.write_all_at(frame, pid.offset()).await,
+        }
+    }
+
+    /// Reads an entire page from a [`StorageBackend`] into `buf`, retrying on short reads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend fails the read, or if the page ends before `buf` is full.
+    /// Returns a [`BpmError::PageNotFound`] error if the page's data file does not exist.
+    async fn backend_read(backend: &dyn StorageBackend, pid: PageId, buf: &mut [u8]) -> Result<()> {
+        Self::check_data_file(pid)?;
+        let mut read = 0;
+
+        while read < buf.len() {
+            match backend.read_at(pid, read, &mut buf[read..]).await? {
+                0 => return Err(Error::from(ErrorKind::UnexpectedEof)),
+                n => read += n,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes an entire page from `buf` to a [`StorageBackend`], retrying on short writes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend fails the write, or if it stops accepting data. Returns a
+    /// [`BpmError::PageNotFound`] error if the page's data file does not exist.
+    async fn backend_write(backend: &dyn StorageBackend, pid: PageId, buf: &[u8]) -> Result<()> {
+        Self::check_data_file(pid)?;
+        let mut written = 0;
+
+        while written < buf.len() {
+            match backend.write_at(pid, written, &buf[written..]).await? {
+                0 => return Err(Error::from(ErrorKind::WriteZero)),
+                n => written += n,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the data of the contiguous pages `start, start + 1, ...` into `frames`, where the
+    /// `i`-th frame receives page `start + i`.
+    ///
+    /// The pages of every drive are contiguous in its file, so instead of one operation per page,
+    /// this submits a single vectored read (`readv`) per drive (or per [`MAX_IOVECS`] pages of a
+    /// drive) that scatters the data into every frame at once. Otherwise, this behaves like
+    /// [`StorageManagerHandle::read_into`] on every page: checksums are verified, and the injected
+    /// latency of every drive is waited for before its read.
+    ///
+    /// # Errors
+    ///
+    /// Like [`StorageManagerHandle::read_into`], every frame is given back in both the `Ok` and
+    /// `Err` cases. Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if the range holds
+    /// a temporary page, a [`CorruptPage`] error if a page in the range is quarantined, a
+    /// [`ChecksumMismatch`] error if a page does not match its checksum, or an error if any of the
+    /// reads fails. The data of every frame is unspecified on error.
+    pub(crate) async fn read_range_into(
+        &self,
+        start: PageId,
+        mut frames: Vec<Frame>,
+    ) -> BufResult<(), Vec<Frame>> {
+        if let Some(driver) = IoDriver::for_current_thread() {
+            return driver.range(IoKind::Read, start, frames).await;
+        }
+
+        if let Err(e) = Self::check_runtime().and_then(|()| Self::check_range(start, frames.len()))
+        {
+            return (Err(e), frames);
+        }
+
+        frames
+            .iter_mut()
+            .for_each(|frame| frame.begin_io(IoKind::Read));
+        let (res, mut frames) = self.read_range(start, frames).await;
+        frames.iter_mut().for_each(Frame::end_io);
+
+        (res, frames)
+    }
+
+    /// Reads the data of a contiguous range of pages, see
+    /// [`StorageManagerHandle::read_range_into`].
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::read_range_into`].
+    async fn read_range(&self, start: PageId, frames: Vec<Frame>) -> BufResult<(), Vec<Frame>> {
+        let sm = StorageManager::get();
+        let pids = Self::range_pids(start, frames.len());
+        if let Some(pid) = pids.clone().find(|&pid| sm.quarantine.contains(pid)) {
+            return (Err(CorruptPage::new(pid).into()), frames);
+        }
+
+        let (mut res, mut frames) = self.vectored(start, frames, IoKind::Read).await;
+
+        if res.is_ok() && sm.checksums {
+            for (pid, frame) in pids.clone().zip(&frames) {
+                if let Err((stored, computed)) = checksum::verify(frame) {
+                    let _ = sm.quarantine.insert(pid);
+                    res = res.and(Err(ChecksumMismatch::new(pid, stored, computed).into()));
+                }
+            }
+        }
+
+        if let Some(codec) = BufferPoolManager::get().page_codec() {
+            for (pid, frame) in pids.zip(&mut frames) {
+                res = res.and_then(|()| frame.decode(pid, codec, sm.codec_len()));
+            }
+        }
+
+        (res, frames)
+    }
+
+    /// Writes the data of `frames` to the contiguous pages `start, start + 1, ...`, where the
+    /// `i`-th frame holds the data of page `start + i`.
+    ///
+    /// Like [`StorageManagerHandle::read_range_into`], this submits a single vectored write
+    /// (`writev`) per drive (or per [`MAX_IOVECS`] pages of a drive) that gathers the data of
+    /// every frame at once. Otherwise, this behaves like [`StorageManagerHandle::write_from`] on
+    /// every page: the registered [`WalHook`](crate::WalHook) is awaited for every dirty frame
+    /// before anything is written, checksums are updated, and the injected latency of every drive
+    /// is waited for before its write.
+    ///
+    /// # Errors
+    ///
+    /// Like [`StorageManagerHandle::write_from`], every frame is given back in both the `Ok` and
+    /// `Err` cases. Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if the range holds
+    /// a temporary page, or an error if the hook or any of the writes fails, in which case any
+    /// subset of the pages may have been written.
+    pub(crate) async fn write_range_from(
+        &self,
+        start: PageId,
+        mut frames: Vec<Frame>,
+    ) -> BufResult<(), Vec<Frame>> {
+        if let Some(driver) = IoDriver::for_current_thread() {
+            return driver.range(IoKind::Write, start, frames).await;
+        }
+
+        if let Err(e) = Self::check_runtime().and_then(|()| Self::check_range(start, frames.len()))
+        {
+            return (Err(e), frames);
+        }
+
+        frames
+            .iter_mut()
+            .for_each(|frame| frame.begin_io(IoKind::Write));
+        let (res, mut frames) = self.write_range(start, frames).await;
+        frames.iter_mut().for_each(|frame| {
+            frame.restore_plain();
+            frame.end_io();
+        });
+
+        if res.is_ok() {
+            Self::record_flushes(start, &frames);
+        }
+
+        (res, frames)
+    }
+
+    /// Records a [`PageEvent::Flush`] for every dirty frame that was just written to the
+    /// contiguous pages `start, start + 1, ...`, unless they are temporary pages.
+    fn record_flushes(start: PageId, frames: &[Frame]) {
+        if start.is_temp() {
+            return;
+        }
+
+        let bpm = BufferPoolManager::get();
+        for (pid, frame) in Self::range_pids(start, frames.len()).zip(frames) {
+            if frame.is_dirty() {
+                bpm.record_page_event(PageEvent::Flush {
+                    pid,
+                    lsn: frame.lsn(),
+                });
+            }
+        }
+    }
+
+    /// Writes the data of a contiguous range of pages, see
+    /// [`StorageManagerHandle::write_range_from`].
+    ///
+    /// # Errors
+    ///
+    /// See [`StorageManagerHandle::write_range_from`].
+    async fn write_range(
+        &self,
+        start: PageId,
+        mut frames: Vec<Frame>,
+    ) -> BufResult<(), Vec<Frame>> {
+        let bpm = BufferPoolManager::get();
+        for (pid, frame) in Self::range_pids(start, frames.len()).zip(&frames) {
+            if frame.is_dirty() {
+                let dirty = frame.dirty_range().unwrap_or(0..frame.data().len());
+                if let Err(e) = bpm.before_write_back(pid, frame.lsn(), dirty).await {
+                    return (Err(e), frames);
+                }
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        let _in_flight_writes: Vec<InFlightWrite> = Self::range_pids(start, frames.len())
+            .map(InFlightWrite::new)
+            .collect();
+
+        if let Some(codec) = bpm.page_codec() {
+            let len = StorageManager::get().codec_len();
+            for (pid, frame) in Self::range_pids(start, frames.len()).zip(&mut frames) {
+                if let Err(e) = frame.encode(pid, codec, len) {
+                    return (Err(e), frames);
+                }
+            }
+        }
+
+        if StorageManager::get().checksums {
+            frames.iter_mut().for_each(|frame| checksum::seal(frame));
+        }
+
+        if StorageManager::get().doublewrite.is_none() {
+            return self.vectored(start, frames, IoKind::Write).await;
+        }
+
+        // The range may have more pages than the double-write buffer has slots.
+        let len = frames.len();
+        let mut written = Vec::with_capacity(len);
+        let mut rest = frames.into_iter();
+        while written.len() < len {
+            let pid = PageId::new(start.as_u64() + written.len() as u64);
+            let chunk = rest.by_ref().take(DOUBLEWRITE_SLOTS).collect();
+
+            let (res, chunk) = self.write_staged(pid, chunk).await;
+            written.extend(chunk);
+
+            if let Err(e) = res {
+                written.extend(rest);
+                return (Err(e), written);
+            }
+        }
+
+        (Ok(()), written)
+    }
+
+    /// Writes the data of `frames` to the contiguous pages starting at `start` through the
+    /// double-write buffer: the pages are staged in the buffer first, then written to their home
+    /// locations, and their slots are only freed once the home locations are durable.
+    ///
+    /// The frames must already be sealed, and there must be at most [`DOUBLEWRITE_SLOTS`] of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pages cannot be staged, written, or synced.
+    async fn write_staged(&self, start: PageId, frames: Vec<Frame>) -> BufResult<(), Vec<Frame>> {
+        let sm = StorageManager::get();
+        let doublewrite = sm
+            .doublewrite
+            .as_ref()
+            .expect("Only called with a double-write buffer");
+
+        let pages: Vec<(PageId, &[u8])> = Self::range_pids(start, frames.len())
+            .zip(frames.iter().map(|frame| &**frame))
+            .collect();
+        let staged = match doublewrite
+            .stage(&self.files[self.files.len() - 1], &pages)
+            .await
+        {
+            Ok(staged) => staged,
+            Err(e) => return (Err(e), frames),
+        };
+
+        let len = frames.len();
+        let (mut res, frames) = self.vectored(start, frames, IoKind::Write).await;
+        if res.is_ok() {
+            res = self.sync_pages(start, len).await;
+        }
+
+        drop(staged);
+        (res, frames)
+    }
+
+    /// Flushes the data of every database file that holds a page of the contiguous range of `len`
+    /// pages starting at `start` with `fdatasync`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files cannot be synced.
+    async fn sync_pages(&self, start: PageId, len: usize) -> Result<()> {
+        if let Some(backend) = BufferPoolManager::get().storage_backend() {
+            return backend.sync().await;
+        }
+
+        for pid in Self::range_pids(start, len.min(start.stripe_width())) {
+            self.file(pid)?.sync_data().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the contiguous range of `len` pages starting at `start` holds no temporary
+    /// pages, and that every page of the range belongs to the same data file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidInput`](ErrorKind::InvalidInput) error if it does not.
+    fn check_range(start: PageId, len: usize) -> Result<()> {
+        let last = start.as_u64().checked_add(len.saturating_sub(1) as u64);
+        let last = last.map(PageId::new);
+        if start.is_temp()
+            || last.map_or(true, |last| {
+                last.is_temp() || last.file_id() != start.file_id()
+            })
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot transfer a range of {len} pages starting at {start}"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Gets the IDs of the contiguous range of `len` pages starting at `start`.
+    fn range_pids(start: PageId, len: usize) -> impl Iterator<Item = PageId> + Clone {
+        (start.as_u64()..start.as_u64() + len as u64).map(PageId::new)
+    }
+
+    /// Reads or writes the data of `frames` from or to the contiguous range of pages starting at
+    /// `start`, with one vectored operation per drive and per [`MAX_IOVECS`] pages.
+    ///
+    /// The operations are submitted one after another, and the first failure stops the transfer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the operations fails, or if a read reaches the end of a file.
+    async fn vectored(
+        &self,
+        start: PageId,
+        frames: Vec<Frame>,
+        kind: IoKind,
+    ) -> BufResult<(), Vec<Frame>> {
+        let num_drives = start.stripe_width();
+        let len = frames.len();
+        let mut slots: Vec<Option<Frame>> = frames.into_iter().map(Some).collect();
+        let mut res = Ok(());
+
+        // Page `start + i` is on drive `(start + i) % num_drives`, and the pages of a drive are
+        // contiguous in its file. The pages of every other data file are all in a single file.
+        'drives: for first in 0..num_drives.min(len) {
+            let indices: Vec<usize> = (first..len).step_by(num_drives).collect();
+
+            for chunk in indices.chunks(MAX_IOVECS) {
+                let pid = PageId::new(start.as_u64() + chunk[0] as u64);
+                let bufs: Vec<Frame> = chunk.iter().filter_map(|&i| slots[i].take()).collect();
+
+                let (chunk_res, bufs) = self.vectored_chunk(pid, bufs, kind).await;
+                for (&i, frame) in chunk.iter().zip(bufs) {
+                    slots[i] = Some(frame);
+                }
+
+                if let Err(e) = chunk_res {
+                    res = Err(e);
+                    break 'drives;
+                }
+            }
+        }
+
+        (res, slots.into_iter().flatten().collect())
+    }
+
+    /// Reads or writes the data of `frames` from or to consecutive pages of the file of `pid`'s
+    /// drive, starting at `pid`, with a single vectored operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails, or if a read reaches the end of the file.
+    async fn vectored_chunk(
+        &self,
+        pid: PageId,
+        frames: Vec<Frame>,
+        kind: IoKind,
+    ) -> BufResult<(), Vec<Frame>> {
+        IO_OPERATIONS.fetch_add(1, Ordering::Relaxed);
+        let _in_flight = InFlightIo::submit(pid, kind).await;
+        let start = Instant::now();
+        StorageManager::get().latency.delay(pid.drive(), kind).await;
+
+        let count = frames.len();
+        let len: usize = frames.iter().map(IoBuf::bytes_total).sum();
+
+        let (res, frames) = match (BufferPoolManager::get().storage_backend(), kind) {
+            (Some(backend), _) => {
+                let mut frames = frames;
+                let res = Self::backend_chunk(backend, pid, &mut frames, kind).await;
+                (res, frames)
+            }
+            (None, kind) => match (self.file(pid), kind) {
+                (Err(e), _) => (Err(e), frames),
+                (Ok(file), IoKind::Read) => match file.readv_at(frames, pid.offset()).await {
+                    (Ok(n), frames) if n < len => Self::short_read(&file, pid, frames, n).await,
+                    (res, frames) => (res.map(drop), frames),
+                },
+                (Ok(file), IoKind::Write) => {
+                    let (res, frames) = file.writev_at_all(frames, Some(pid.offset())).await;
+                    (res.map(drop), frames)
+                }
+            },
+        };
+
+        if res.is_ok() {
+            let stats = &BufferPoolManager::get().stats;
+            let latency = start.elapsed() / count as u32;
+            for _ in 0..count {
+                match kind {
+                    IoKind::Read => stats.record_page_read(latency),
+                    IoKind::Write => stats.record_page_write(latency),
+                }
+            }
+        }
+
+        (res, frames)
+    }
+
+    /// Reads or writes the data of `frames` from or to consecutive pages of `pid`'s drive, starting
+    /// at `pid`, one page at a time through a [`StorageBackend`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the reads or writes fails, which stops the transfer.
+    async fn backend_chunk(
+        backend: &dyn StorageBackend,
+        pid: PageId,
+        frames: &mut [Frame],
+        kind: IoKind,
+    ) -> Result<()> {
+        let num_drives = pid.stripe_width() as u64;
+
+        for (i, frame) in (0..).zip(frames) {
+            let pid = PageId::new(pid.as_u64() + i * num_drives);
+            match kind {
+                IoKind::Read => Self::backend_read(backend, pid, frame).await?,
+                IoKind::Write => Self::backend_write(backend, pid, frame).await?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finishes a vectored read of consecutive pages starting at `pid` that only read `n` bytes,
+    /// by reading every frame that was not filled completely on its own.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the reads fails, or if the file ends before a frame was filled.
+    async fn short_read(
+        file: &File,
+        pid: PageId,
+        frames: Vec<Frame>,
+        n: usize,
+    ) -> BufResult<(), Vec<Frame>> {
+        let mut res = Ok(());
+        let mut done = Vec::with_capacity(frames.len());
+        let mut end = 0;
+
+        for frame in frames {
+            let offset = pid.offset() + end as u64;
+            end += IoBuf::bytes_total(&frame);
+
+            if end <= n || res.is_err() {
+                done.push(frame);
+                continue;
+            }
+
+            let (frame_res, frame) = file.read_exact_at(frame, offset).await;
+            res = frame_res;
+            done.push(frame);
+        }
+
+        (res, done)
+    }
+
+    /// Checks that the caller runs within a runtime, since submitting an operation outside of one
+    /// panics.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BpmError::ShuttingDown`] error if there is no runtime, which is usually because
+    /// it is shutting down.
+    fn check_runtime() -> Result<()> {
+        if executor::in_runtime() {
+            Ok(())
+        } else {
+            Err(BpmError::ShuttingDown.into())
+        }
+    }
+
+    /// Checks out the registered buffer of a `Frame`, if the frame is registered with the
+    /// thread-local `io_uring` instance.
+    ///
+    /// The caller must own the frame for as long as the returned [`FixedBuf`] is alive.
+    fn check_out(frame: &Frame) -> Option<FixedBuf> {
+        let index = frame.buf_index()?;
+
+        StorageManager::get().registered_frames()?.check_out(index)
+    }
+
+    /// Reads an entire page from `file` into a registered buffer, retrying on short reads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read fails, or if the file ends before the entire page was read.
+    async fn read_fixed(file: &File, pid: PageId, mut fixed: FixedBuf) -> Result<()> {
+        let len = IoBuf::bytes_total(&fixed);
+        let mut read = 0;
+
+        while read < len {
+            let (res, slice) = file
+                .read_fixed_at(fixed.slice(read..), pid.offset() + read as u64)
+                .await;
+            fixed = slice.into_inner();
+
+            match res? {
+                0 => return Err(Error::from(ErrorKind::UnexpectedEof)),
+                n => read += n,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flushes every database file and every other data file (and their metadata) to persistent
+    /// storage with `fsync`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files cannot be synced.
+    pub(crate) async fn sync_all(&self) -> Result<()> {
+        if let Some(driver) = IoDriver::for_current_thread() {
+            return driver.sync(false).await;
+        }
+
+        if let Some(backend) = BufferPoolManager::get().storage_backend() {
+            return backend.sync().await;
+        }
+
+        // The spill file (which comes after the database files) never needs to be durable.
+        for file in self.durable_files()? {
+            file.sync_all().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the data of every database file and every other data file to persistent storage with
+    /// `fdatasync`.
+    ///
+    /// Unlike [`StorageManagerHandle::sync_all`], this does not flush file metadata that is not
+    /// needed to read the data back (such as modification times), which is cheaper. Since the
+    /// database files are allocated up front, page writes never change their size.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files cannot be synced.
+    pub(crate) async fn sync_data(&self) -> Result<()> {
+        if let Some(driver) = IoDriver::for_current_thread() {
+            return driver.sync(true).await;
+        }
+
+        if let Some(backend) = BufferPoolManager::get().storage_backend() {
+            return backend.sync().await;
+        }
+
+        for file in self.durable_files()? {
+            file.sync_data().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the data of the database file that the given page is stored on to persistent
+    /// storage with `fdatasync`. This does nothing for temporary pages, which never need to be
+    /// durable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be synced.
+    pub(crate) async fn sync_page(&self, pid: PageId) -> Result<()> {
+        if pid.is_temp() {
+            return Ok(());
+        }
+
+        if let Some(backend) = BufferPoolManager::get().storage_backend() {
+            return backend.sync().await;
+        }
+
+        self.file(pid)?.sync_data().await
+    }
+
+    /// Releases the space of the given page in its database file to the file system, by punching a
+    /// hole into the file. The page reads as zeroes afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the hole cannot be punched, for example because the file system does not
+    /// support it.
+    pub(crate) async fn punch_hole(&self, pid: PageId) -> Result<()> {
+        let page_size = StorageManager::get().page_size() as u64;
+
+        // A backend has no holes, so the page is zeroed instead.
+        if let Some(backend) = BufferPoolManager::get().storage_backend() {
+            return Self::backend_write(backend, pid, &vec![0; page_size as usize]).await;
+        }
+
+        let mode = libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE;
+
+        self.file(pid)?
+            .fallocate(pid.offset(), page_size, mode)
+            .await
+    }
+
+    /// Gets the file handle of the drive that the given page is stored on, which is the spill file
+    /// for temporary pages, and the page's data file for the pages of other data files.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BpmError::PageNotFound`] error if the page's data file does not exist, or an
+    /// error if the data file cannot be opened.
+    fn file(&self, pid: PageId) -> Result<Rc<File>> {
+        match pid.file_id() {
+            FileId::DEFAULT => Ok(self.files[pid.drive()].clone()),
+            file => self
+                .data_file(file)?
+                .ok_or_else(|| BpmError::PageNotFound(pid).into()),
+        }
+    }
+
+    /// Gets the file handle of the data file `file`, opening it first if this is the first time
+    /// that it is used on this thread. Returns `None` if the data file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data file cannot be opened.
+    fn data_file(&self, file: FileId) -> Result<Option<Rc<File>>> {
+        let sm = StorageManager::get();
+        let mut handles = self.data_files.borrow_mut();
+
+        // Close the file handles of the data files that were dropped in the meantime.
+        let version = sm.data_files.version();
+        if handles.version != version {
+            handles.files.retain(|&id, _| sm.data_files.contains(id));
+            handles.version = version;
+        }
+
+        if let Some(handle) = handles.files.get(&file) {
+            return Ok(Some(handle.clone()));
+        }
+
+        let Some(path) = sm.data_files.path(file) else {
+            return Ok(None);
+        };
+
+        let flags = if sm.direct_io { libc::O_DIRECT } else { 0 };
+        let std_file = match std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(flags)
+            .open(path)
+        {
+            Ok(std_file) => std_file,
+            // The data file was dropped since it was looked up.
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let handle = Rc::new(File::from_std(std_file));
+        handles.files.insert(file, handle.clone());
+
+        Ok(Some(handle))
+    }
+
+    /// Gets the file handles of every database file and every other data file, which are the
+    /// files that need to be durable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a data file cannot be opened.
+    fn durable_files(&self) -> Result<Vec<Rc<File>>> {
+        let mut files = self.files[..StorageManager::get_num_drives()].to_vec();
+        for file in StorageManager::get().data_files.ids() {
+            files.extend(self.data_file(file)?);
+        }
+
+        Ok(files)
+    }
+
+    /// Checks that the data file of the given page exists, for pages that are stored by a
+    /// [`StorageBackend`] instead of in files.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BpmError::PageNotFound`] error if it does not.
+    fn check_data_file(pid: PageId) -> Result<()> {
+        if !StorageManager::get().data_files.contains(pid.file_id()) {
+            return Err(BpmError::PageNotFound(pid).into());
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,130 @@
+//! This module contains [`TenantId`] and the per-tenant accounting of resident frames, which keeps
+//! several logical components that share one buffer pool from evicting each other's pages.
+//!
+//! A page that is read into memory through a handle from
+//! [`BufferPoolManager::get_page_for`] is charged to that handle's tenant until it is evicted
+//! again. A tenant with a
+//! [quota](crate::BufferPoolManagerConfig::tenant_quota) that has as many resident frames as its
+//! quota allows makes room for a new page by evicting one of its own pages first (see
+//! [`BufferPoolManager::enforce_tenant_quota`]), so a scan-heavy tenant recycles its own frames
+//! instead of pushing out the pages of every other tenant.
+
+use crate::bpm::BufferPoolManager;
+use crate::error::Result;
+use crate::storage::FrameGroup;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// The number of passes over every frame group that [`BufferPoolManager::enforce_tenant_quota`]
+/// makes, since a page that was accessed recently is only cooled by the first pass.
+const QUOTA_PASSES: usize = 2;
+
+/// The ID of a tenant of the buffer pool, which is a logical component (like a table, an index, or
+/// a query) whose resident pages are accounted for separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TenantId {
+    /// The raw ID of the tenant.
+    inner: u32,
+}
+
+impl TenantId {
+    /// Creates a new `TenantId` from a `u32`.
+    pub fn new(id: u32) -> Self {
+        Self { inner: id }
+    }
+
+    /// Returns the `TenantId` as a `u32`.
+    pub fn as_u32(self) -> u32 {
+        self.inner
+    }
+}
+
+impl Display for TenantId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Tenant {}", self.inner)
+    }
+}
+
+/// The number of frames that hold a page of every tenant.
+#[derive(Debug, Default)]
+pub(crate) struct TenantAccounting {
+    /// The number of resident frames of every tenant that has ever had a page charged to it.
+    resident: scc::HashMap<TenantId, AtomicUsize>,
+}
+
+impl TenantAccounting {
+    /// Charges a frame that a page was just loaded into to `tenant`.
+    pub(crate) fn charge(&self, tenant: TenantId) {
+        self.resident
+            .entry(tenant)
+            .or_default()
+            .get()
+            .fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Releases a frame that was charged to `tenant`, because its page was evicted.
+    pub(crate) fn release(&self, tenant: TenantId) {
+        let prev = self.resident.read(&tenant, |_, resident| {
+            resident.fetch_sub(1, Ordering::AcqRel)
+        });
+        debug_assert!(
+            prev.is_some_and(|prev| prev != 0),
+            "Released more frames of {tenant} than were charged"
+        );
+    }
+
+    /// Gets the number of frames that are currently charged to `tenant`.
+    pub(crate) fn resident_frames(&self, tenant: TenantId) -> usize {
+        self.resident
+            .read(&tenant, |_, resident| resident.load(Ordering::Acquire))
+            .unwrap_or(0)
+    }
+}
+
+impl BufferPoolManager {
+    /// Gets the number of frames that currently hold a page charged to `tenant`.
+    ///
+    /// A page is charged to the tenant of the [`PageHandle`](crate::page::PageHandle) that read it
+    /// into memory (see [`BufferPoolManager::get_page_for`]), until it is evicted. Pages that were
+    /// read in through a handle without a tenant (or by a prefetch) are not charged to anyone.
+    pub fn tenant_resident_frames(&self, tenant: TenantId) -> usize {
+        self.tenants.resident_frames(tenant)
+    }
+
+    /// Evicts pages of `tenant` until it has fewer resident frames than its quota, and returns the
+    /// frame group that the last page was evicted from, whose free frame the tenant should load
+    /// its next page into.
+    ///
+    /// Returns `None` if the tenant has no quota or is already below it, or if none of its pages
+    /// could be evicted because they are all pinned or exempt from eviction, in which case the
+    /// tenant goes over its quota rather than waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while writing back a dirty page.
+    pub(crate) async fn enforce_tenant_quota(
+        &self,
+        tenant: TenantId,
+    ) -> Result<Option<Arc<FrameGroup>>> {
+        let Some(quota) = self.tenant_quota(tenant) else {
+            return Ok(None);
+        };
+
+        if self.tenants.resident_frames(tenant) < quota {
+            return Ok(None);
+        }
+
+        for _ in 0..QUOTA_PASSES {
+            for group in self.active_frame_groups() {
+                group.cool_frames(Some(tenant)).await?;
+
+                if self.tenants.resident_frames(tenant) < quota {
+                    return Ok(Some(group.clone()));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
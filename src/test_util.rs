@@ -0,0 +1,148 @@
+//! This module contains hooks for benchmarks that run in several phases, which are only available
+//! with the `test-util` feature.
+//!
+//! A benchmark that wants to measure a cold cache and then a warm cache would otherwise have to
+//! start a fresh process for every phase. Instead, it can wait at a barrier between phases while
+//! the buffer pool is [quiesced](BufferPoolManager::quiesce), and then
+//! [drop every clean frame](BufferPoolManager::drop_clean_frames) to start the next phase with a
+//! cold cache.
+
+use crate::bpm::BufferPoolManager;
+use crate::events::PageEvent;
+use crate::executor;
+use crate::storage::{EvictionState, FrameGroup, StorageManager};
+use std::io::Result;
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(debug_assertions)]
+use std::sync::atomic::Ordering;
+use tokio::time::Duration;
+
+/// How long to wait between checks while waiting for the buffer pool to become quiet.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+impl BufferPoolManager {
+    /// Stops every background daemon and waits for every in-flight page read and write to
+    /// complete.
+    ///
+    /// Once this returns, the buffer pool does no work in the background until daemons are spawned
+    /// again, which is possible after calling [`BufferPoolManager::resume_daemons`]. Tasks that
+    /// access pages are not stopped, so the caller is responsible for making sure that no other
+    /// task starts new I/O (for example, by waiting at a barrier), otherwise this function may
+    /// wait forever.
+    pub async fn quiesce(&self) {
+        self.stop_daemons();
+        while self.daemons.num_alive() != 0 {
+            executor::sleep(POLL_INTERVAL).await;
+        }
+
+        let sm = StorageManager::get();
+        while sm.num_in_flight_io() != 0 {
+            executor::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Allows background daemons to be spawned again after they were stopped by
+    /// [`BufferPoolManager::quiesce`] or [`BufferPoolManager::stop_daemons`].
+    ///
+    /// Daemons that were stopped are not restarted, so they must be spawned again (for example,
+    /// with [`BufferPoolManager::spawn_evictor`]).
+    pub fn resume_daemons(&self) {
+        self.daemons.clear_shutdown();
+    }
+
+    /// Poisons the eviction state lock of every frame group, as if a thread had panicked while
+    /// holding it, so that tests can exercise the configured
+    /// [`PoisonPolicy`](crate::PoisonPolicy).
+    pub fn poison_eviction_states(&self) {
+        for group in self.frame_groups() {
+            // Unwinding without the panic hook still poisons the lock, but prints nothing.
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                let _guard = group.eviction_states.lock();
+                panic::resume_unwind(Box::new("Poisoning the eviction state lock"));
+            }));
+        }
+    }
+
+    /// Counts one more dirty frame in the first frame group than it holds, as a bug in the dirty
+    /// page bookkeeping would, so that tests can check that
+    /// [`BufferPoolManager::check_invariants`] reports it.
+    ///
+    /// This function is only available in debug builds.
+    #[cfg(debug_assertions)]
+    pub fn skew_dirty_frame_count(&self) {
+        self.frame_groups()[0]
+            .num_dirty_frames
+            .fetch_add(1, Ordering::Release);
+    }
+
+    /// Evicts every page that is clean and not in use, without writing anything out, and returns
+    /// the number of pages that were evicted.
+    ///
+    /// Dirty pages and pages that are locked by a page guard stay in memory. The evicted pages are
+    /// not counted in [`PoolStats::evictions`](crate::PoolStats::evictions), so that the statistics
+    /// of a benchmark only reflect the benchmark itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an eviction state lock was poisoned and the buffer pool manager is
+    /// configured to propagate poisoning errors.
+    pub async fn drop_clean_frames(&self) -> Result<usize> {
+        let mut dropped = 0;
+        for group in self.frame_groups() {
+            dropped += drop_clean_frames(group).await?;
+        }
+
+        Ok(dropped)
+    }
+}
+
+/// Evicts every clean page of a [`FrameGroup`] that is not in use, returning the number of pages
+/// that were evicted.
+///
+/// # Panics
+///
+/// Panics if a page loses its frame while we hold its write lock, which should never happen.
+///
+/// # Errors
+///
+/// Returns an error if the eviction state lock was poisoned and the buffer pool manager is
+/// configured to propagate poisoning errors.
+async fn drop_clean_frames(group: &FrameGroup) -> Result<usize> {
+    let mut dropped = 0;
+
+    for page in group.resident_pages()? {
+        // Skip pages that are in use.
+        let Ok(mut guard) = page.frame.try_write() else {
+            continue;
+        };
+
+        // Skip pages that were evicted, moved to another group, or are dirty.
+        let Some(index) = guard
+            .as_ref()
+            .filter(|frame| !frame.is_dirty() && frame.group_id() == group.group_id)
+            .map(|frame| frame.group_index())
+        else {
+            continue;
+        };
+
+        {
+            let mut states = group.lock_eviction_states()?;
+            states[index] = EvictionState::Cold;
+            states.replacer.record_eviction(index);
+        }
+
+        let mut frame = guard
+            .take()
+            .expect("We just checked that the page owns a frame");
+
+        page.set_evicted();
+        frame.evict_page_owner();
+        drop(guard);
+        BufferPoolManager::get().record_page_event(PageEvent::Evict(page.pid));
+
+        group.release_frame(frame).await;
+        dropped += 1;
+    }
+
+    Ok(dropped)
+}
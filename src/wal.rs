@@ -0,0 +1,85 @@
+//! This module contains the [`WalHook`] trait, which lets a database engine built on top of the
+//! buffer pool manager enforce the write-ahead logging (WAL) protocol.
+//!
+//! Under write-ahead logging, a modified page may only be written to persistent storage after every
+//! log record that describes a modification of the page has been made durable. The buffer pool
+//! manager does not know anything about the log, so engines tag every modified page with the log
+//! sequence number (LSN) of its latest log record via [`WritePageGuard::set_lsn`], and the buffer
+//! pool manager asks the registered [`WalHook`] to make the log durable up to that LSN before it
+//! writes the page out.
+//!
+//! [`WritePageGuard::set_lsn`]: crate::page::WritePageGuard::set_lsn
+
+use crate::bpm::BufferPoolManager;
+use crate::page::PageId;
+use std::fmt::Debug;
+use std::future::Future;
+use std::io::Result;
+use std::ops::Range;
+use std::pin::Pin;
+
+/// The future returned by [`WalHook::before_evict`].
+///
+/// The future does not need to be [`Send`], since it is always awaited on the thread that is
+/// writing the page out.
+pub type WalFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
+
+/// A hook that is invoked before a dirty page is written to persistent storage.
+///
+/// Register a hook with [`BufferPoolManagerConfig::wal_hook`](crate::BufferPoolManagerConfig::wal_hook).
+pub trait WalHook: Debug + Send + Sync {
+    /// Makes the log durable up to (and including) `lsn`, before the dirty page `pid` is written
+    /// to persistent storage.
+    ///
+    /// This is awaited before every write of a dirty page, whether the page is being evicted,
+    /// flushed explicitly with [`WritePageGuard::flush`](crate::page::WritePageGuard::flush), or
+    /// written back by the background flusher. `lsn` is the latest LSN that was set on the page
+    /// since it was loaded into memory, or `0` if none was set.
+    ///
+    /// The page is write-locked while this is awaited, so the hook must not try to access the
+    /// page itself.
+    ///
+    /// # Errors
+    ///
+    /// If this returns an error, the page is not written out and stays dirty, and the error is
+    /// returned to whoever tried to write the page out.
+    fn before_evict(&self, pid: PageId, lsn: u64) -> WalFuture<'_>;
+
+    /// Like [`WalHook::before_evict`], but also receives the range of the page's data that was
+    /// modified since the page was last written out.
+    ///
+    /// The range covers every range marked with
+    /// [`WritePageGuard::mark_dirty_range`](crate::page::WritePageGuard::mark_dirty_range), and
+    /// the whole page for every write guard that did not mark any range. This allows a log to
+    /// record a byte-range diff instead of a full page image.
+    ///
+    /// This is the method that the buffer pool manager calls. The default implementation ignores
+    /// the range and calls [`WalHook::before_evict`].
+    ///
+    /// # Errors
+    ///
+    /// See [`WalHook::before_evict`].
+    fn before_evict_range(&self, pid: PageId, lsn: u64, dirty: Range<usize>) -> WalFuture<'_> {
+        let _ = dirty;
+        self.before_evict(pid, lsn)
+    }
+}
+
+impl BufferPoolManager {
+    /// Awaits the registered [`WalHook`] (if any) before a dirty page is written out.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the hook.
+    pub(crate) async fn before_write_back(
+        &self,
+        pid: PageId,
+        lsn: u64,
+        dirty: Range<usize>,
+    ) -> Result<()> {
+        match self.wal_hook() {
+            Some(hook) => hook.before_evict_range(pid, lsn, dirty).await,
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use async_bpm::page::{PageId, PAGE_SIZE};
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+use std::io::ErrorKind;
+
+/// The database file for this test.
+const PATH: &str = "allocation.db";
+
+/// The allocation state of the database file.
+const ALLOCATION: &str = "allocation.alloc";
+
+/// The number of pages that the database file can hold.
+const CAPACITY: usize = 128;
+
+fn config() -> BufferPoolManagerConfig {
+    BufferPoolManagerConfig::new(64, CAPACITY).paths([PATH])
+}
+
+#[test]
+#[ignore]
+fn test_allocation() {
+    let _ = std::fs::remove_file(ALLOCATION);
+    let file = std::fs::File::create(PATH).unwrap();
+    file.set_len((CAPACITY * PAGE_SIZE) as u64).unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let pids: Vec<_> = (0..4).map(|_| bpm.allocate_page().unwrap()).collect();
+        assert_eq!(
+            pids.iter().map(|pid| pid.as_u64()).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+
+        for (i, pid) in pids.iter().enumerate() {
+            let ph = bpm.get_page(pid).unwrap();
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(b'a' + i as u8);
+            guard.flush().await.unwrap();
+        }
+
+        // A page that is in use cannot be deallocated.
+        let ph = bpm.get_page(&pids[2]).unwrap();
+        let guard = ph.read().await.unwrap();
+        assert!(bpm.deallocate_page(&pids[2]).await.is_err());
+        drop(guard);
+
+        // Deallocate a page, which can only be done once.
+        let stale = bpm.get_page(&pids[1]).unwrap();
+
+        // A replica of the page that is being read keeps it in use.
+        #[cfg(feature = "experimental")]
+        {
+            stale.replicate();
+            let replica = stale.read_replica().await.unwrap();
+            assert!(replica.iter().all(|&b| b == b'b'));
+            assert!(bpm.deallocate_page(&pids[1]).await.is_err());
+        }
+
+        bpm.deallocate_page(&pids[1]).await.unwrap();
+        let err = bpm.deallocate_page(&pids[1]).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // Handles that were created before the deallocation cannot bring the page back.
+        let Err(err) = stale.read().await else {
+            panic!("Read a page through a handle from before it was deallocated");
+        };
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(stale.write().await.is_err());
+        #[cfg(feature = "experimental")]
+        {
+            let Err(err) = stale.read_replica().await else {
+                panic!("Read a replica of a page after it was deallocated");
+            };
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+        }
+
+        // Pages that were never allocated cannot be deallocated.
+        let never = PageId::new(100);
+        let err = bpm.deallocate_page(&never).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // The allocation state survives a restart.
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // The deallocated page is allocated again first, and its data was discarded.
+        let reused = bpm.allocate_page().unwrap();
+        assert_eq!(reused.as_u64(), 1);
+        let ph = bpm.get_page(&reused).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 0));
+
+        // The pages that are still allocated are never handed out again.
+        assert_eq!(bpm.allocate_page().unwrap().as_u64(), 4);
+        let ph = bpm.get_page(&PageId::new(2)).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == b'c'));
+
+        // Allocation fails once the capacity is exhausted.
+        for _ in 5..CAPACITY {
+            bpm.allocate_page().unwrap();
+        }
+        assert!(bpm.allocate_page().is_err());
+
+        bpm.shutdown().await.unwrap();
+    });
+}
@@ -0,0 +1,103 @@
+use async_bpm::error::{AllocationProblem, BpmError};
+use async_bpm::page::PAGE_SIZE;
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// The database file for this test.
+const PATH: &str = "allocation_check.db";
+
+/// The allocation state of the database file.
+const ALLOCATION: &str = "allocation_check.alloc";
+
+/// The temporary allocation state that an interrupted update leaves behind.
+const ALLOCATION_TMP: &str = "allocation_check.alloc.tmp";
+
+/// The number of pages that the database file can hold.
+const CAPACITY: usize = 128;
+
+/// The number of pages that are allocated.
+const ALLOCATED: usize = 72;
+
+fn config(capacity: usize) -> BufferPoolManagerConfig {
+    BufferPoolManagerConfig::new(64, capacity).paths([PATH])
+}
+
+/// Initializes the buffer pool manager, expecting it to fail with a
+/// [`BpmError::AllocationMismatch`], and returns the problems that were found.
+fn mismatch(capacity: usize) -> Vec<AllocationProblem> {
+    let err = BufferPoolManager::try_initialize_with_config(config(capacity)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let BpmError::AllocationMismatch(mismatch) = err else {
+        panic!("Expected an allocation mismatch, got {err}");
+    };
+    assert_eq!(mismatch.path(), Path::new(ALLOCATION));
+    mismatch.problems().to_vec()
+}
+
+fn set_len(len: u64) {
+    let file = std::fs::OpenOptions::new().write(true).open(PATH).unwrap();
+    file.set_len(len).unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_allocation_check() {
+    let _ = std::fs::remove_file(ALLOCATION);
+    let file = std::fs::File::create(PATH).unwrap();
+    file.set_len((CAPACITY * PAGE_SIZE) as u64).unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(config(CAPACITY));
+    let bpm = BufferPoolManager::get();
+    BufferPoolManager::start_thread(async move {
+        for _ in 0..ALLOCATED {
+            bpm.allocate_page().unwrap();
+        }
+        bpm.shutdown().await.unwrap();
+    });
+
+    // An interrupted update of the allocation state and an interrupted extension of the database
+    // file are repaired.
+    std::fs::write(ALLOCATION_TMP, "next_page=1000\n").unwrap();
+    set_len((CAPACITY * PAGE_SIZE + 100) as u64);
+
+    BufferPoolManager::initialize_with_config(config(CAPACITY));
+    let bpm = BufferPoolManager::get();
+    assert!(!Path::new(ALLOCATION_TMP).exists());
+    let len = std::fs::metadata(PATH).unwrap().len();
+    assert_eq!(len, (CAPACITY * PAGE_SIZE) as u64);
+    BufferPoolManager::start_thread(async move {
+        assert_eq!(bpm.allocate_page().unwrap().as_u64(), ALLOCATED as u64);
+        bpm.shutdown().await.unwrap();
+    });
+
+    // Allocated pages must fit in the configured capacity.
+    assert_eq!(
+        mismatch(65),
+        [AllocationProblem::BeyondCapacity {
+            next_page: ALLOCATED as u64 + 1,
+            capacity: 65,
+        }]
+    );
+
+    // Allocated pages must fit in the database file.
+    set_len((32 * PAGE_SIZE) as u64);
+    assert_eq!(
+        mismatch(CAPACITY),
+        [AllocationProblem::FileTooShort {
+            path: PATH.into(),
+            len: (32 * PAGE_SIZE) as u64,
+            required: ((ALLOCATED + 1) * PAGE_SIZE) as u64,
+        }]
+    );
+
+    // Nothing was initialized, so a consistent database can still be opened.
+    set_len((CAPACITY * PAGE_SIZE) as u64);
+    BufferPoolManager::initialize_with_config(config(CAPACITY));
+    let bpm = BufferPoolManager::get();
+    BufferPoolManager::start_thread(async move {
+        bpm.shutdown().await.unwrap();
+    });
+}
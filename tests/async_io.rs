@@ -0,0 +1,46 @@
+#![cfg(feature = "async-io")]
+
+use async_bpm::error::BpmError;
+use async_bpm::page::{PageHandle, PageId};
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+use std::time::Duration;
+
+const FRAMES: usize = 64;
+
+/// Runs the timers of the buffer pool on the `async-io` timer driver.
+#[test]
+#[ignore]
+fn test_async_io_timers() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 256).free_frame_timeout(Duration::from_millis(100)),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // The latency probe sleeps between probes and times out every probe.
+        let probe = BufferPoolManager::spawn_ring_probe(Duration::from_millis(10));
+
+        // Waiting for a free frame retries on a timer until the free frame timeout expires.
+        let handles: Vec<PageHandle> = (0..FRAMES as u64)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+        let mut guards = Vec::new();
+        for ph in &handles {
+            guards.push(ph.write().await.unwrap());
+        }
+
+        let ph = bpm.get_page(&PageId::new(FRAMES as u64)).unwrap();
+        let Err(BpmError::BufferPoolFull(full)) = ph.read().await else {
+            panic!("Expected the buffer pool to be full");
+        };
+        assert!(full.waited() >= Duration::from_millis(100));
+        drop(guards);
+
+        let probes = bpm.ring_probes();
+        assert_eq!(probes.len(), 1);
+        assert!(probes[0].probes > 0);
+
+        bpm.stop_daemons();
+        probe.await.unwrap();
+    });
+}
@@ -1,10 +1,16 @@
 use async_bpm::{page::PageId, BufferPoolManager};
 use std::ops::DerefMut;
+use std::sync::Mutex;
 use std::thread;
 
+/// Serializes the tests in this file, since only one buffer pool manager can exist at a time.
+static BPM_LOCK: Mutex<()> = Mutex::new(());
+
 #[test]
 #[ignore]
 fn test_single_thread() {
+    let _lock = BPM_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
     BufferPoolManager::initialize(64, 128);
     let bpm = BufferPoolManager::get();
 
@@ -17,6 +23,8 @@ fn test_single_thread() {
             guard.deref_mut().fill(b'A');
             guard.flush().await.unwrap();
         }
+
+        bpm.shutdown().await.unwrap();
     });
 }
 
@@ -25,6 +33,8 @@ fn test_single_thread() {
 fn test_basic() {
     const THREADS: usize = 8;
 
+    let _lock = BPM_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
     BufferPoolManager::initialize(64, 256);
     let bpm = BufferPoolManager::get();
 
@@ -65,4 +75,6 @@ fn test_basic() {
             });
         }
     });
+
+    BufferPoolManager::start_thread(bpm.shutdown()).unwrap();
 }
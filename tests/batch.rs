@@ -0,0 +1,56 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+use std::io::ErrorKind;
+use std::ops::DerefMut;
+
+#[test]
+#[ignore]
+fn test_read_pages() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let handles: Vec<_> = (0..16)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+
+        // Load a few of the pages beforehand so that the batch has both hits and misses.
+        for ph in handles.iter().step_by(3) {
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(b'A');
+            guard.flush().await.unwrap();
+        }
+
+        let guards = bpm.read_pages(&handles).await.unwrap();
+        assert_eq!(guards.len(), handles.len());
+
+        for (i, guard) in guards.iter().enumerate() {
+            if i % 3 == 0 {
+                assert!(guard.iter().all(|&b| b == b'A'));
+            }
+        }
+        drop(guards);
+
+        let duplicates = [handles[0].clone(), handles[0].clone()];
+        let Err(e) = bpm.read_pages(&duplicates).await else {
+            panic!("Read the same page twice in a single batch");
+        };
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+
+        // A batch can take up the entire pool, evicting every other page.
+        let handles: Vec<_> = (32..32 + bpm.num_frames() as u64)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+        let guards = bpm.read_pages(&handles).await.unwrap();
+        assert_eq!(guards.len(), bpm.num_frames());
+        drop(guards);
+
+        // A batch that does not fit in the pool could never be loaded.
+        let handles: Vec<_> = (0..bpm.num_frames() as u64 + 1)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+        let Err(e) = bpm.read_pages(&handles).await else {
+            panic!("Read more pages in a single batch than the pool has frames");
+        };
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    });
+}
@@ -0,0 +1,74 @@
+use async_bpm::blocking::{
+    block_on, flush_blocking, get_page_blocking, read_blocking, write_blocking,
+};
+use async_bpm::{page::PageId, BufferPoolManager, IO_OPERATIONS};
+use std::ops::DerefMut;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+/// The number of pages that are written, which fit in the buffer pool.
+const PAGES: u64 = 8;
+
+/// The number of other pages that are read to evict the written pages, which is more than the
+/// number of frames.
+const OTHER_PAGES: u64 = 128;
+
+#[test]
+#[ignore]
+fn test_blocking() {
+    BufferPoolManager::initialize(64, 256);
+
+    // Plain threads that never set up a runtime use the buffer pool through the blocking API.
+    thread::spawn(|| {
+        for i in 0..PAGES {
+            let ph = get_page_blocking(&PageId::new(i)).unwrap();
+            let mut guard = write_blocking(&ph).unwrap();
+            guard.deref_mut().fill(i as u8 + 1);
+            flush_blocking(&mut guard).unwrap();
+        }
+
+        // Evict the written pages, so that they must be read back from persistent storage.
+        for i in PAGES..PAGES + OTHER_PAGES {
+            let ph = get_page_blocking(&PageId::new(i)).unwrap();
+            drop(read_blocking(&ph).unwrap());
+        }
+
+        let io_before = IO_OPERATIONS.load(Ordering::Relaxed);
+        for i in 0..PAGES {
+            let ph = get_page_blocking(&PageId::new(i)).unwrap();
+            let guard = read_blocking(&ph).unwrap();
+            assert!(
+                guard.iter().all(|&b| b == i as u8 + 1),
+                "Page {i} has the wrong data"
+            );
+        }
+        assert!(IO_OPERATIONS.load(Ordering::Relaxed) > io_before);
+
+        // Dirty a page without flushing it, which shutting down writes out.
+        let ph = get_page_blocking(&PageId::new(0)).unwrap();
+        write_blocking(&ph).unwrap().deref_mut().fill(42);
+        drop(ph);
+
+        block_on(BufferPoolManager::get().shutdown()).unwrap();
+    })
+    .join()
+    .unwrap();
+
+    BufferPoolManager::initialize(64, 256);
+
+    thread::spawn(|| {
+        for i in 0..PAGES {
+            let expected = if i == 0 { 42 } else { i as u8 + 1 };
+            let ph = get_page_blocking(&PageId::new(i)).unwrap();
+            let guard = read_blocking(&ph).unwrap();
+            assert!(
+                guard.iter().all(|&b| b == expected),
+                "Page {i} was not persisted"
+            );
+        }
+
+        block_on(BufferPoolManager::get().shutdown()).unwrap();
+    })
+    .join()
+    .unwrap();
+}
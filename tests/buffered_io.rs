@@ -0,0 +1,36 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::ops::DerefMut;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted and read back in.
+const PAGES: u64 = 192;
+
+#[test]
+#[ignore]
+fn test_buffered_io() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).buffered_io(true),
+    );
+    let bpm = BufferPoolManager::get();
+    assert!(!bpm.direct_io());
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(i as u8);
+            guard.flush().await.unwrap();
+        }
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let guard = ph.read().await.unwrap();
+            assert!(
+                guard.iter().all(|&b| b == i as u8),
+                "Page {i} has the wrong data"
+            );
+        }
+    });
+}
@@ -0,0 +1,92 @@
+use async_bpm::error::BpmError;
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::DerefMut;
+
+/// The database file for this test.
+const PATH: &str = "checksum.db";
+
+/// The number of pages to write.
+const PAGES: u64 = 4;
+
+/// The page that gets corrupted on disk.
+const CORRUPTED: u64 = 2;
+
+fn config() -> BufferPoolManagerConfig {
+    BufferPoolManagerConfig::new(64, 128)
+        .checksums(true)
+        .paths([PATH])
+}
+
+#[test]
+#[ignore]
+fn test_checksum_mismatch() {
+    let file = std::fs::File::create(PATH).unwrap();
+    file.set_len(128 * 4096).unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+    let page_size = bpm.page_size();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            // Pages that were never written are valid, and the trailer is not exposed.
+            let mut guard = ph.write().await.unwrap();
+            assert_eq!(guard.len(), bpm.usable_page_size());
+            assert!(guard.len() < bpm.page_size());
+
+            guard.deref_mut().fill(i as u8 + 1);
+            guard.flush().await.unwrap();
+        }
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // Flip a single bit in the middle of one of the pages.
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(PATH)
+        .unwrap();
+    let offset = CORRUPTED * page_size as u64 + 100;
+    let mut byte = [0u8];
+    file.seek(SeekFrom::Start(offset)).unwrap();
+    file.read_exact(&mut byte).unwrap();
+    byte[0] ^= 0x10;
+    file.seek(SeekFrom::Start(offset)).unwrap();
+    file.write_all(&byte).unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            if i != CORRUPTED {
+                let guard = ph.read().await.unwrap();
+                assert!(guard.iter().all(|&b| b == i as u8 + 1));
+                continue;
+            }
+
+            let Err(error) = ph.read().await else {
+                panic!("Read a corrupted page without an error");
+            };
+            let BpmError::ChecksumMismatch(mismatch) = error else {
+                panic!("Expected a checksum mismatch, got {error}");
+            };
+            assert_eq!(mismatch.pid(), PageId::new(CORRUPTED));
+            assert_ne!(mismatch.stored(), mismatch.computed());
+        }
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    std::fs::remove_file(PATH).unwrap();
+    std::fs::remove_file("checksum.quarantine").unwrap();
+}
@@ -0,0 +1,45 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+
+/// The number of frames, which is a single frame group.
+const FRAMES: u64 = 64;
+
+#[test]
+#[ignore]
+fn test_clean_reclaim() {
+    BufferPoolManager::initialize_with_config(BufferPoolManagerConfig::new(FRAMES as usize, 256));
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..FRAMES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        let group = bpm.stats().frame_groups[0];
+        assert_eq!(group.dirty_frames, FRAMES as usize);
+        assert_eq!(group.clean_frames(), 0);
+
+        bpm.flush_all().await.unwrap();
+        let group = bpm.stats().frame_groups[0];
+        assert_eq!(group.dirty_frames, 0);
+        assert_eq!(group.clean_frames(), FRAMES as usize);
+
+        // Once a cooling pass has cooled the clean pages down, they are reclaimed without running
+        // another pass.
+        let before = bpm.stats();
+        for i in FRAMES..3 * FRAMES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.read().await.unwrap();
+        }
+
+        let stats = bpm.stats().since(&before);
+        assert!(stats.clean_reclaims > 0);
+        assert_eq!(stats.dirty_write_backs, 0);
+        assert_eq!(stats.clean_evictions(), stats.evictions);
+
+        for i in 0..FRAMES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+    });
+}
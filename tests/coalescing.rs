@@ -0,0 +1,43 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::ops::DerefMut;
+use std::time::Duration;
+
+#[test]
+#[ignore]
+fn test_write_coalescing() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 128).write_coalescing_window(Duration::from_millis(200)),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let flusher = BufferPoolManager::spawn_flusher(Duration::from_millis(10));
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+
+        // A burst of updates to the same page.
+        for i in 0..10 {
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(b'a' + i);
+            drop(guard);
+
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // The page is still within the coalescing window, so it should not have been written back.
+        let stats = bpm.write_back_stats();
+        assert_eq!(stats.coalesced_writes, 9);
+        assert_eq!(stats.cold_write_backs + stats.hot_write_backs, 0);
+        assert!(stats.deferred_write_backs > 0);
+        assert_eq!(bpm.health().await.dirty_frames, 1);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        // Once the window has passed, the entire burst is written back with a single write.
+        let stats = bpm.write_back_stats();
+        assert_eq!(stats.cold_write_backs + stats.hot_write_backs, 1);
+        assert_eq!(bpm.health().await.dirty_frames, 0);
+
+        bpm.stop_daemons();
+        flusher.await.unwrap();
+    });
+}
@@ -0,0 +1,115 @@
+use async_bpm::page::{PageHandle, PageId, WritePageGuard, PAGE_SIZE};
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig, WalFuture, WalHook};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// The database file that the buffer pool manager uses by default.
+const DATABASE: &str = "bpm.db";
+
+/// The doublewrite file of the database file.
+const DOUBLEWRITE: &str = "bpm.dwb";
+
+/// The pages that are committed together.
+const PAGES: [u64; 3] = [0, 1, 2];
+
+/// A hook that fails the second time it is asked about an armed page, which is when the page is
+/// published after being staged.
+#[derive(Debug, Default)]
+struct FailingHook {
+    armed: Mutex<Option<PageId>>,
+    calls: Mutex<HashMap<PageId, usize>>,
+}
+
+impl WalHook for FailingHook {
+    fn before_evict(&self, pid: PageId, _lsn: u64) -> WalFuture<'_> {
+        Box::pin(async move {
+            let mut calls = self.calls.lock().unwrap();
+            let count = calls.entry(pid).or_default();
+            *count += 1;
+
+            let mut armed = self.armed.lock().unwrap();
+            if *armed == Some(pid) && *count == 2 {
+                *armed = None;
+                return Err(io::Error::other("the log is unavailable"));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Reads a page straight from the database file.
+fn on_disk(pid: u64) -> Vec<u8> {
+    let file = std::fs::read(DATABASE).unwrap();
+    let offset = pid as usize * PAGE_SIZE;
+    file[offset..offset + PAGE_SIZE].to_vec()
+}
+
+/// Write-locks every page and fills it with `byte`.
+async fn fill(handles: &[PageHandle], byte: u8) -> Vec<WritePageGuard<'_>> {
+    let mut guards = Vec::new();
+    for ph in handles {
+        let mut guard = ph.write().await.unwrap();
+        guard.fill(byte);
+        guards.push(guard);
+    }
+    guards
+}
+
+#[test]
+#[ignore]
+fn test_commit_pages() {
+    let hook = Arc::new(FailingHook::default());
+    let config = BufferPoolManagerConfig::new(64, 256).wal_hook(hook.clone());
+
+    BufferPoolManager::initialize_with_config(config.clone());
+    let bpm = BufferPoolManager::get();
+
+    // A successful commit publishes every page and invalidates the staged images.
+    BufferPoolManager::start_thread(async move {
+        let handles = PAGES.map(|pid| bpm.get_page(&PageId::new(pid)).unwrap());
+        let mut guards = fill(&handles, 0xAA).await;
+        bpm.commit_pages(&mut guards).await.unwrap();
+    });
+    for pid in PAGES {
+        assert!(on_disk(pid).iter().all(|&b| b == 0xAA));
+    }
+    assert!(std::fs::read(DOUBLEWRITE).unwrap()[..8]
+        .iter()
+        .all(|&b| b == 0));
+
+    // A commit that fails after staging its pages only publishes some of them.
+    *hook.armed.lock().unwrap() = Some(PageId::new(2));
+    hook.calls.lock().unwrap().clear();
+    BufferPoolManager::start_thread(async move {
+        let handles = PAGES.map(|pid| bpm.get_page(&PageId::new(pid)).unwrap());
+        let mut guards = fill(&handles, 0xBB).await;
+        assert!(bpm.commit_pages(&mut guards).await.is_err());
+    });
+    assert!(on_disk(0).iter().all(|&b| b == 0xBB));
+    assert!(on_disk(2).iter().all(|&b| b == 0xAA));
+
+    // Simulate a crash at this point: shutting down writes out the dirty pages, so keep the files
+    // as they were and put them back afterwards.
+    let database = std::fs::read(DATABASE).unwrap();
+    let doublewrite = std::fs::read(DOUBLEWRITE).unwrap();
+    BufferPoolManager::start_thread(async move { bpm.shutdown().await.unwrap() });
+    std::fs::write(DATABASE, database).unwrap();
+    std::fs::write(DOUBLEWRITE, doublewrite).unwrap();
+
+    // Initializing again replays the staged pages, so that every page has its new contents.
+    BufferPoolManager::initialize_with_config(config);
+    for pid in PAGES {
+        assert!(on_disk(pid).iter().all(|&b| b == 0xBB));
+    }
+    assert!(std::fs::read(DOUBLEWRITE).unwrap().is_empty());
+
+    let bpm = BufferPoolManager::get();
+    BufferPoolManager::start_thread(async move {
+        for pid in PAGES {
+            let ph = bpm.get_page(&PageId::new(pid)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == 0xBB));
+        }
+    });
+}
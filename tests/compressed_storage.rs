@@ -0,0 +1,133 @@
+#![cfg(feature = "lz4")]
+
+use async_bpm::{
+    page::PageId, BufferPoolManager, BufferPoolManagerConfig, CompressedStorage, StorageBackend,
+};
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+/// The data file of the storage that is reopened.
+const REOPEN: &str = "compressed_storage_reopen.db";
+
+/// The data file of the storage that backs the buffer pool.
+const POOL: &str = "compressed_storage_pool.db";
+
+/// The size of the pages that are written directly to the storage.
+const PAGE_SIZE: usize = 4096;
+
+/// The number of pages to write, which is more than the number of frames so that pages must be
+/// evicted and read back in.
+const PAGES: u64 = 128;
+
+/// Removes the files of the storage at `path`.
+fn remove(path: &str) {
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(format!("{path}.map"));
+}
+
+/// Fills a page with pseudo-random bytes that do not compress.
+fn noise() -> Vec<u8> {
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    (0..PAGE_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+#[ignore]
+fn test_compressed_storage_reopen() {
+    remove(REOPEN);
+
+    let random = noise();
+
+    let storage = CompressedStorage::open(REOPEN, PAGE_SIZE).unwrap();
+    async_bpm::blocking::block_on(async {
+        for i in 0..PAGES {
+            storage
+                .write_at(PageId::new(i), 0, &vec![i as u8; PAGE_SIZE])
+                .await
+                .unwrap();
+        }
+
+        // A page that does not compress is stored as is, and a partial write keeps the rest.
+        storage.write_at(PageId::new(1), 0, &random).await.unwrap();
+        storage
+            .write_at(PageId::new(2), 100, &[0xFF; 4])
+            .await
+            .unwrap();
+        storage.sync().await.unwrap();
+
+        // Writes after the last sync are lost when the storage is reopened.
+        storage.write_at(PageId::new(3), 0, &random).await.unwrap();
+    });
+
+    assert_eq!(storage.num_pages(), PAGES as usize);
+    assert!(storage.stored_bytes() < PAGES * PAGE_SIZE as u64 / 2);
+    drop(storage);
+
+    // The storage must be reopened with the same page size.
+    assert!(CompressedStorage::open(REOPEN, 2 * PAGE_SIZE).is_err());
+
+    let storage = CompressedStorage::open(REOPEN, PAGE_SIZE).unwrap();
+    async_bpm::blocking::block_on(async {
+        let mut page = vec![0; PAGE_SIZE];
+
+        storage.read_at(PageId::new(1), 0, &mut page).await.unwrap();
+        assert_eq!(page, random);
+
+        storage.read_at(PageId::new(2), 0, &mut page).await.unwrap();
+        assert_eq!(page[99..105], [2, 0xFF, 0xFF, 0xFF, 0xFF, 2]);
+
+        storage.read_at(PageId::new(3), 0, &mut page).await.unwrap();
+        assert!(page.iter().all(|&b| b == 3));
+
+        // Pages that were never written read as zeroes.
+        storage
+            .read_at(PageId::new(PAGES), 0, &mut page)
+            .await
+            .unwrap();
+        assert!(page.iter().all(|&b| b == 0));
+    });
+
+    drop(storage);
+    remove(REOPEN);
+}
+
+#[test]
+#[ignore]
+fn test_compressed_storage_pool() {
+    remove(POOL);
+
+    let storage = Arc::new(CompressedStorage::open(POOL, PAGE_SIZE).unwrap());
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).storage_backend(storage.clone()),
+    );
+    let bpm = BufferPoolManager::get();
+    assert_eq!(bpm.page_size(), PAGE_SIZE);
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().deref_mut().fill(i as u8);
+        }
+
+        // Evicted pages are decompressed when they are read back in.
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(
+                ph.read().await.unwrap().iter().all(|&b| b == i as u8),
+                "Page {i} has the wrong data"
+            );
+        }
+    });
+
+    assert!(storage.num_pages() > 0);
+    assert!(storage.stored_bytes() < storage.num_pages() as u64 * PAGE_SIZE as u64);
+
+    remove(POOL);
+}
@@ -0,0 +1,123 @@
+use async_bpm::error::{BpmError, ConfigError};
+use async_bpm::page::PageId;
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+use std::io::ErrorKind;
+
+/// A number of frames that is not a multiple of the frame group size, so the last frame group is
+/// smaller than the others.
+const FRAMES: usize = 100;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted from both frame groups.
+const PAGES: u64 = 300;
+
+fn config_error(config: BufferPoolManagerConfig) -> ConfigError {
+    let err = BufferPoolManager::try_initialize_with_config(config).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let BpmError::Config(err) = err else {
+        panic!("Expected a configuration error, got {err}");
+    };
+    err
+}
+
+#[test]
+#[ignore]
+fn test_config_validation() {
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(0, 256)),
+        ConfigError::TooFewFrames
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(256, 256)),
+        ConfigError::CapacityTooSmall {
+            num_frames: 256,
+            capacity: 256
+        }
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).max_frames(32)),
+        ConfigError::MaxFramesTooSmall {
+            num_frames: 64,
+            max_frames: 32
+        }
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).max_frames(256)),
+        ConfigError::CapacityTooSmall {
+            num_frames: 256,
+            capacity: 256
+        }
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).frame_group_size(0)),
+        ConfigError::InvalidFrameGroupSize {
+            frame_group_size: 0,
+            num_frames: 64
+        }
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).frame_group_size(65)),
+        ConfigError::InvalidFrameGroupSize {
+            frame_group_size: 65,
+            num_frames: 64
+        }
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).page_size(1000)),
+        ConfigError::InvalidPageSize { page_size: 1000 }
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).paths(Vec::<String>::new())),
+        ConfigError::NoDatabaseFiles
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).lru_k_replacement(0)),
+        ConfigError::InvalidLruK
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).flusher_dirty_threshold(1.5)),
+        ConfigError::InvalidDirtyThreshold
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).preload_fraction(-0.5)),
+        ConfigError::InvalidPreloadFraction
+    );
+    assert_eq!(
+        config_error(BufferPoolManagerConfig::new(64, 256).doublewrite_buffer(true)),
+        ConfigError::DoublewriteWithoutChecksums
+    );
+    assert_eq!(
+        config_error(
+            BufferPoolManagerConfig::new(64, 256)
+                .reserved_header(4000)
+                .reserved_trailer(96)
+        ),
+        ConfigError::NoUserData {
+            page_size: 4096,
+            reserved: 4096
+        }
+    );
+    assert!(!BufferPoolManager::is_initialized());
+
+    BufferPoolManager::try_initialize_with_config(BufferPoolManagerConfig::new(FRAMES, 1024))
+        .unwrap();
+    let bpm = BufferPoolManager::get();
+
+    let stats = bpm.stats();
+    assert_eq!(stats.frame_groups.len(), 2);
+    assert_eq!(stats.frame_groups[0].total_frames, 64);
+    assert_eq!(stats.frame_groups[1].total_frames, FRAMES - 64);
+    assert_eq!(bpm.num_frames(), FRAMES);
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+    });
+}
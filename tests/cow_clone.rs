@@ -0,0 +1,82 @@
+use async_bpm::page::PageId;
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+use std::io::ErrorKind;
+
+/// Gets the number of buffer frames that hold a page.
+fn occupied_frames(bpm: &BufferPoolManager) -> usize {
+    bpm.stats()
+        .frame_groups
+        .iter()
+        .map(|group| group.occupied_frames())
+        .sum()
+}
+
+#[test]
+#[ignore]
+fn test_cow_clone() {
+    let config = || BufferPoolManagerConfig::new(16, 64);
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let source = bpm.get_page(&PageId::new(0)).unwrap();
+        source.write().await.unwrap().fill(b'a');
+
+        // The clone shares the source's frame, and reads its data.
+        let clone = source.clone_cow(PageId::new(1)).await.unwrap();
+        let occupied = occupied_frames(bpm);
+        let guard = clone.read().await.unwrap();
+        assert!(guard.iter().all(|&b| b == b'a'));
+        assert_eq!(guard.into_snapshot().pid(), PageId::new(1));
+        assert_eq!(occupied_frames(bpm), occupied);
+
+        // Writing to the source gives the clone a copy of the data from before the write.
+        source.write().await.unwrap().fill(b'b');
+        assert_eq!(occupied_frames(bpm), occupied + 1);
+        assert!(clone.read().await.unwrap().iter().all(|&b| b == b'a'));
+        assert!(source.read().await.unwrap().iter().all(|&b| b == b'b'));
+
+        // Writing to a clone leaves the source alone.
+        let other = source.clone_cow(PageId::new(2)).await.unwrap();
+        other.write().await.unwrap().fill(b'c');
+        assert!(other.read().await.unwrap().iter().all(|&b| b == b'c'));
+        assert!(source.read().await.unwrap().iter().all(|&b| b == b'b'));
+
+        // A page cannot be cloned into itself, or into a clone that still shares a frame.
+        let err = source.clone_cow(PageId::new(0)).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let shared = source.clone_cow(PageId::new(3)).await.unwrap();
+        let err = other.clone_cow(PageId::new(3)).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+
+        // A clone that no longer shares a frame can be cloned into again, which takes a fresh
+        // snapshot of the source under the same page ID.
+        let resnapshot = source.clone_cow(PageId::new(1)).await.unwrap();
+        assert!(clone.read().await.unwrap().iter().all(|&b| b == b'b'));
+        source.write().await.unwrap().fill(b'd');
+        assert!(resnapshot.read().await.unwrap().iter().all(|&b| b == b'b'));
+        assert!(source.read().await.unwrap().iter().all(|&b| b == b'd'));
+
+        // A clone that still shares its source's frame is written out on shutdown.
+        let latest = source.clone_cow(PageId::new(4)).await.unwrap();
+        assert!(shared.read().await.unwrap().iter().all(|&b| b == b'b'));
+        assert!(latest.read().await.unwrap().iter().all(|&b| b == b'd'));
+        drop(shared);
+        drop(latest);
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for (pid, byte) in [(0, b'd'), (1, b'b'), (2, b'c'), (3, b'b'), (4, b'd')] {
+            let ph = bpm.get_page(&PageId::new(pid)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == byte));
+        }
+
+        bpm.shutdown().await.unwrap();
+    });
+}
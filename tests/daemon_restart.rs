@@ -0,0 +1,48 @@
+#![cfg(feature = "test-util")]
+
+use async_bpm::error::Poisoned;
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, PoisonPolicy};
+use std::time::Duration;
+
+/// The number of buffer frames, which make up a single frame group.
+const FRAMES: usize = 64;
+
+#[test]
+#[ignore]
+fn test_daemon_restart() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 4 * FRAMES).poison_policy(PoisonPolicy::Error),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // Fill every frame, so that the evictor has work to do.
+        for i in 0..FRAMES as u64 {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+
+        // Every pass of the evictor now fails, since the eviction state lock stays poisoned.
+        bpm.poison_eviction_states();
+        let errors = bpm.daemon_errors();
+        let evictor = BufferPoolManager::spawn_evictor();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // The evictor was restarted after every failure, and every failure was reported.
+        assert!(!evictor.is_finished());
+        let mut failures = 0;
+        while let Ok(error) = errors.try_recv() {
+            assert_eq!(error.daemon(), "evictor");
+            assert!(error.error().get_ref().is_some_and(|e| e.is::<Poisoned>()));
+            failures += 1;
+        }
+        assert!(failures >= 3, "The evictor only failed {failures} times");
+
+        // The backoff doubles after every failure (10, 20, 40, and 80 milliseconds), so only a few
+        // restarts fit into the time we waited.
+        assert!(failures <= 6, "The evictor failed {failures} times");
+
+        bpm.stop_daemons();
+        evictor.await.unwrap();
+    });
+}
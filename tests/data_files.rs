@@ -0,0 +1,124 @@
+use async_bpm::page::{FileId, PageId, PAGE_SIZE};
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+use std::io::ErrorKind;
+
+/// The database file for this test.
+const PATH: &str = "data_files.db";
+
+/// The allocation state of the database file.
+const ALLOCATION: &str = "data_files.alloc";
+
+/// The number of pages that the database file can hold.
+const CAPACITY: usize = 64;
+
+/// The number of pages that every data file can hold.
+const FILE_CAPACITY: usize = 16;
+
+fn config() -> BufferPoolManagerConfig {
+    BufferPoolManagerConfig::new(32, CAPACITY).paths([PATH])
+}
+
+#[test]
+#[ignore]
+fn test_data_files() {
+    let _ = std::fs::remove_file(ALLOCATION);
+    let file = std::fs::File::create(PATH).unwrap();
+    file.set_len((CAPACITY * PAGE_SIZE) as u64).unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    let (users, orders) = BufferPoolManager::start_thread(async move {
+        // Clean up after a previous run.
+        for file in bpm.data_files() {
+            bpm.drop_file(file).await.unwrap();
+        }
+
+        let users = bpm.create_file(FILE_CAPACITY).unwrap();
+        let orders = bpm.create_file(FILE_CAPACITY).unwrap();
+        assert_ne!(users, orders);
+        assert_ne!(users, FileId::DEFAULT);
+        assert_eq!(bpm.data_files(), [users, orders]);
+
+        // Every data file numbers its pages on its own.
+        for file in [users, orders] {
+            for i in 0..2 {
+                let pid = bpm.allocate_page_in(file).unwrap();
+                assert_eq!(pid, PageId::in_file(file, i));
+                assert_eq!(pid.file_id(), file);
+                assert_eq!(pid.page_number(), i);
+            }
+        }
+        assert_eq!(bpm.allocate_page().unwrap(), PageId::new(0));
+
+        // Pages with the same number in different data files are different pages.
+        for (file, byte) in [(users, b'u'), (orders, b'o'), (FileId::DEFAULT, b'd')] {
+            let ph = bpm.get_page(&PageId::in_file(file, 0)).unwrap();
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(byte);
+            guard.flush().await.unwrap();
+        }
+        for (file, byte) in [(users, b'u'), (orders, b'o'), (FileId::DEFAULT, b'd')] {
+            let ph = bpm.get_page(&PageId::in_file(file, 0)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == byte));
+        }
+
+        // A data file cannot be dropped while one of its pages is in use.
+        let stale = bpm.get_page(&PageId::in_file(orders, 0)).unwrap();
+        let guard = stale.read().await.unwrap();
+        assert!(bpm.drop_file(orders).await.is_err());
+        drop(guard);
+
+        // Dropping a data file invalidates every handle to its pages.
+        bpm.drop_file(orders).await.unwrap();
+        let Err(err) = stale.read().await else {
+            panic!("Read a page of a data file after it was dropped");
+        };
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(bpm.get_page(&PageId::in_file(orders, 1)).is_err());
+        assert_eq!(
+            bpm.allocate_page_in(orders).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            bpm.drop_file(orders).await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        // The default data file cannot be dropped.
+        assert_eq!(
+            bpm.drop_file(FileId::DEFAULT).await.unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        bpm.shutdown().await.unwrap();
+        (users, orders)
+    });
+
+    // The data files that were not dropped, and their allocation state, survive a restart.
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        assert_eq!(bpm.data_files(), [users]);
+        assert_eq!(
+            bpm.allocate_page_in(users).unwrap(),
+            PageId::in_file(users, 2)
+        );
+
+        let ph = bpm.get_page(&PageId::in_file(users, 0)).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == b'u'));
+
+        // Allocation fails once the capacity of the data file is exhausted.
+        for _ in 3..FILE_CAPACITY {
+            bpm.allocate_page_in(users).unwrap();
+        }
+        assert!(bpm.allocate_page_in(users).is_err());
+
+        assert!(bpm.get_page(&PageId::in_file(orders, 0)).is_err());
+
+        bpm.drop_file(users).await.unwrap();
+        bpm.shutdown().await.unwrap();
+    });
+}
@@ -0,0 +1,46 @@
+#![cfg(all(feature = "deadlock-detection", debug_assertions))]
+
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::time::Duration;
+
+/// Write-locks `first` and then `second`, pausing in between so that another task can lock
+/// `second` first.
+async fn lock_in_order(first: u64, second: u64) {
+    let bpm = BufferPoolManager::get();
+
+    let first = bpm.get_page(&PageId::new(first)).unwrap();
+    let second = bpm.get_page(&PageId::new(second)).unwrap();
+
+    let _first = first.write().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let _second = second.write().await.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_deadlock_detection() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 128).lock_wait_threshold(Duration::from_millis(5)),
+    );
+
+    BufferPoolManager::start_thread(async move {
+        // Locks that are taken in a consistent order never deadlock.
+        let a = BufferPoolManager::spawn_local(lock_in_order(0, 1));
+        let b = BufferPoolManager::spawn_local(lock_in_order(0, 1));
+        a.await.unwrap();
+        b.await.unwrap();
+
+        // Locks that are taken in opposite orders deadlock. The task that closes the cycle panics,
+        // which releases its lock and lets the other task finish.
+        let a = BufferPoolManager::spawn_local(lock_in_order(2, 3));
+        let b = BufferPoolManager::spawn_local(lock_in_order(3, 2));
+        let results = [a.await, b.await];
+
+        let panics = results
+            .iter()
+            .filter(|res| res.as_ref().is_err_and(|e| e.is_panic()))
+            .count();
+        assert_eq!(panics, 1);
+        assert!(results.iter().any(Result::is_ok));
+    });
+}
@@ -0,0 +1,49 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, GroupSelection};
+
+const FRAMES: usize = 256;
+const CAPACITY: usize = 1024;
+
+/// The number of pages to write, which is more than the number of frames so that pages must be
+/// evicted.
+const PAGES: u64 = 768;
+
+/// Runs a single-task workload on a fresh buffer pool manager, and returns the IDs of the pages
+/// that are still in memory afterwards.
+fn resident_pages(selection: GroupSelection) -> Vec<u64> {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, CAPACITY).group_selection(selection),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+
+            // Touch some earlier pages again, so that the replacer has something to work with.
+            if i % 3 == 0 {
+                let ph = bpm.get_page(&PageId::new(i / 2)).unwrap();
+                ph.read().await.unwrap();
+            }
+        }
+
+        let resident = (0..PAGES)
+            .filter(|&i| bpm.try_get_page(&PageId::new(i)).is_some())
+            .collect();
+
+        bpm.shutdown().await.unwrap();
+        resident
+    })
+}
+
+#[test]
+#[ignore]
+fn test_deterministic_eviction() {
+    for selection in [GroupSelection::Seeded(42), GroupSelection::RoundRobin] {
+        let first = resident_pages(selection);
+        let second = resident_pages(selection);
+
+        assert!(first.len() <= FRAMES);
+        assert_eq!(first, second, "{selection:?} is not reproducible");
+    }
+}
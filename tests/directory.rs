@@ -0,0 +1,49 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::path::Path;
+
+/// The database directory for this test.
+const DIR: &str = "directory_test_db";
+
+#[test]
+#[ignore]
+fn test_directory() {
+    let _ = std::fs::remove_dir_all(DIR);
+
+    // The first run creates the directory and its files.
+    BufferPoolManager::initialize_with_config(BufferPoolManagerConfig::new(64, 128).directory(DIR));
+    let bpm = BufferPoolManager::get();
+    assert!(Path::new(DIR).join("superblock").exists());
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(100)).unwrap();
+        ph.write().await.unwrap().fill(42);
+        bpm.shutdown().await.unwrap();
+    });
+
+    let data = Path::new(DIR).join("data-0.db");
+    assert_eq!(std::fs::metadata(&data).unwrap().len(), 128 * 4096);
+
+    // Reopening with a larger capacity grows the data file and keeps the data.
+    BufferPoolManager::initialize_with_config(BufferPoolManagerConfig::new(64, 256).directory(DIR));
+    let bpm = BufferPoolManager::get();
+    assert_eq!(std::fs::metadata(&data).unwrap().len(), 256 * 4096);
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(100)).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 42));
+        bpm.shutdown().await.unwrap();
+    });
+
+    // Reopening with a different page size fails.
+    let res = std::panic::catch_unwind(|| {
+        BufferPoolManager::initialize_with_config(
+            BufferPoolManagerConfig::new(64, 256)
+                .page_size(8192)
+                .directory(DIR),
+        );
+    });
+    assert!(res.is_err());
+    assert!(!BufferPoolManager::is_initialized());
+
+    std::fs::remove_dir_all(DIR).unwrap();
+}
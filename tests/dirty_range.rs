@@ -0,0 +1,70 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, WalFuture, WalHook};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// A hook that records every dirty range it was given, in order.
+#[derive(Debug, Default)]
+struct RangeHook {
+    ranges: Mutex<Vec<(PageId, Range<usize>)>>,
+}
+
+impl WalHook for RangeHook {
+    fn before_evict(&self, _pid: PageId, _lsn: u64) -> WalFuture<'_> {
+        unreachable!("The buffer pool manager always calls `before_evict_range`")
+    }
+
+    fn before_evict_range(&self, pid: PageId, _lsn: u64, dirty: Range<usize>) -> WalFuture<'_> {
+        Box::pin(async move {
+            self.ranges.lock().unwrap().push((pid, dirty));
+            Ok(())
+        })
+    }
+}
+
+#[test]
+#[ignore]
+fn test_dirty_range() {
+    let hook = Arc::new(RangeHook::default());
+
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).wal_hook(hook.clone()),
+    );
+    let bpm = BufferPoolManager::get();
+    let page_size = bpm.page_size();
+
+    BufferPoolManager::start_thread(async move {
+        // A guard that does not mark anything is assumed to modify the whole page.
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        let mut guard = ph.write().await.unwrap();
+        guard[10] = 1;
+        guard.flush().await.unwrap();
+        drop(guard);
+
+        // Marked ranges are accumulated across guards until the page is written out.
+        let ph = bpm.get_page(&PageId::new(1)).unwrap();
+        let mut guard = ph.write().await.unwrap();
+        guard[100..110].fill(1);
+        guard.mark_dirty_range(100..110);
+        drop(guard);
+
+        let mut guard = ph.write().await.unwrap();
+        guard[300..320].fill(1);
+        guard.mark_dirty_range(300..320);
+        guard.flush().await.unwrap();
+
+        // After the write, the range starts from scratch.
+        guard[40..50].fill(2);
+        guard.mark_dirty_range(40..50);
+        guard.flush().await.unwrap();
+    });
+
+    let ranges = hook.ranges.lock().unwrap();
+    assert_eq!(
+        *ranges,
+        [
+            (PageId::new(0), 0..page_size),
+            (PageId::new(1), 100..320),
+            (PageId::new(1), 40..50),
+        ]
+    );
+}
@@ -0,0 +1,100 @@
+use async_bpm::error::BpmError;
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::fs::OpenOptions;
+use std::os::unix::fs::FileExt;
+
+/// The database file for this test.
+const PATH: &str = "doublewrite_buffer.db";
+
+/// The double-write buffer file of the database file.
+const BUFFER: &str = "doublewrite_buffer.dwbuf";
+
+/// The number of pages to write one at a time, and then as a single range.
+const PAGES: u64 = 8;
+
+/// The page that is torn on disk while its copy is still in the double-write buffer, which is the
+/// case for every page of the range that was written last.
+const TORN: u64 = PAGES + 3;
+
+/// The page that is torn on disk after its slot in the double-write buffer was reused.
+const LOST: u64 = 3;
+
+fn config() -> BufferPoolManagerConfig {
+    BufferPoolManagerConfig::new(64, 128)
+        .checksums(true)
+        .doublewrite_buffer(true)
+        .paths([PATH])
+}
+
+#[test]
+#[ignore]
+fn test_doublewrite_buffer() {
+    let file = std::fs::File::create(PATH).unwrap();
+    file.set_len(128 * 4096).unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+    let page_size = bpm.page_size();
+    assert!(std::fs::metadata(BUFFER).unwrap().len() > 0);
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(i as u8 + 1);
+            guard.flush().await.unwrap();
+        }
+
+        // Contiguous ranges are staged as well.
+        let handles: Vec<_> = (PAGES..2 * PAGES)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+        let mut guards = Vec::new();
+        for ph in &handles {
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(ph.pid().as_u64() as u8 + 1);
+            guards.push(guard);
+        }
+        bpm.flush_pages(&mut guards).await.unwrap();
+        drop(guards);
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // Simulate writes that were torn by a crash: the second half of the page still holds older
+    // data.
+    let file = OpenOptions::new().write(true).open(PATH).unwrap();
+    let half = vec![0xEE; page_size / 2];
+    for pid in [TORN, LOST] {
+        let offset = pid * page_size as u64 + (page_size / 2) as u64;
+        file.write_all_at(&half, offset).unwrap();
+    }
+    drop(file);
+
+    // Initializing again repairs the torn page from its copy in the double-write buffer. The copy
+    // of the other page was overwritten, so it can only be detected.
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..2 * PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            if i == LOST {
+                let error = ph.read().await.err().unwrap();
+                assert!(matches!(error, BpmError::ChecksumMismatch(_)));
+                continue;
+            }
+
+            let guard = ph.read().await.unwrap();
+            assert!(guard.iter().all(|&b| b == i as u8 + 1));
+        }
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    std::fs::remove_file(PATH).unwrap();
+    std::fs::remove_file(BUFFER).unwrap();
+    let _ = std::fs::remove_file("doublewrite_buffer.quarantine");
+}
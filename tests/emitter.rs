@@ -0,0 +1,86 @@
+use async_bpm::{page::PageId, BufferPoolManager, StatsFormat};
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::time::Duration;
+
+/// A writer that appends to a buffer that the test can inspect.
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.borrow().clone())
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+#[test]
+#[ignore]
+fn test_stats_emitter() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let csv = SharedBuffer::default();
+        let json = SharedBuffer::default();
+
+        let period = Duration::from_millis(20);
+        let csv_emitter =
+            BufferPoolManager::spawn_stats_emitter(csv.clone(), period, StatsFormat::Csv);
+        let json_emitter =
+            BufferPoolManager::spawn_stats_emitter(json.clone(), period, StatsFormat::Json);
+
+        // Let the emitters take their initial snapshots.
+        tokio::task::yield_now().await;
+
+        for i in 0..16 {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(1);
+            ph.read().await.unwrap();
+        }
+
+        tokio::time::sleep(Duration::from_millis(110)).await;
+        bpm.stop_daemons();
+        csv_emitter.await.unwrap();
+        json_emitter.await.unwrap();
+
+        let csv = csv.lines();
+        assert!(csv.len() >= 3, "{csv:?}");
+        assert!(csv[0].starts_with("elapsed_secs,read_accesses,write_accesses,hits,misses"));
+
+        let columns = csv[0].split(',').count();
+        assert!(csv.iter().all(|line| line.split(',').count() == columns));
+
+        // Across all windows, every page was missed once and then hit once.
+        let sum = |column: usize| -> u64 {
+            csv[1..]
+                .iter()
+                .map(|line| line.split(',').nth(column).unwrap().parse::<u64>().unwrap())
+                .sum()
+        };
+        assert_eq!(sum(1), 16);
+        assert_eq!(sum(2), 16);
+        assert_eq!(sum(3), 16);
+        assert_eq!(sum(4), 16);
+
+        let json = json.lines();
+        assert!(json.len() >= 2, "{json:?}");
+        assert!(json
+            .iter()
+            .all(|line| line.starts_with("{\"elapsed_secs\":") && line.ends_with('}')));
+    });
+}
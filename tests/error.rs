@@ -0,0 +1,23 @@
+use async_bpm::error::BpmError;
+use async_bpm::page::PageId;
+use std::io::{self, ErrorKind};
+
+#[test]
+fn test_bpm_error_conversions() {
+    // Operating system errors keep their kind and code.
+    let error = BpmError::from(io::Error::from_raw_os_error(libc::EIO));
+    assert!(matches!(error, BpmError::Io { pid: None, .. }));
+    assert_eq!(io::Error::from(error).raw_os_error(), Some(libc::EIO));
+
+    // Variants without a payload type of their own survive a round trip through `io::Error`.
+    let pid = PageId::new(42);
+    let error = io::Error::from(BpmError::PageNotFound(pid));
+    assert_eq!(error.kind(), ErrorKind::NotFound);
+    let error = BpmError::from(error);
+    assert!(matches!(error, BpmError::PageNotFound(p) if p == pid));
+    assert_eq!(error.pid(), Some(pid));
+
+    let error = BpmError::from(io::Error::from(BpmError::ShuttingDown));
+    assert!(matches!(error, BpmError::ShuttingDown));
+    assert_eq!(error.pid(), None);
+}
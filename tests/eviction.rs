@@ -0,0 +1,70 @@
+use async_bpm::{page::PageId, BufferPoolManager, IO_OPERATIONS};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// The number of pages that are constantly being accessed.
+const HOT_PAGES: u64 = 60;
+
+/// The number of tasks accessing the hot pages.
+const TASKS: usize = 4;
+
+/// The maximum fraction of accesses that are allowed to reload a page.
+const MAX_RELOAD_RATE: f64 = 0.01;
+
+/// Stresses the eviction task with a working set that barely fits in memory, and measures how
+/// often a page that is being accessed constantly gets evicted and reloaded anyway.
+#[test]
+#[ignore]
+fn test_wasted_reloads() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // Bring the entire hot set into memory.
+        for i in 0..HOT_PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+
+        // Since almost every frame is taken, the evictor will cool frames on every pass.
+        let evictor = BufferPoolManager::spawn_evictor();
+
+        let start_io = IO_OPERATIONS.load(Ordering::Acquire);
+        let deadline = Instant::now() + Duration::from_secs(1);
+
+        let tasks: Vec<_> = (0..TASKS)
+            .map(|t| {
+                BufferPoolManager::spawn_local(async move {
+                    let mut accesses = 0usize;
+
+                    while Instant::now() < deadline {
+                        let pid = PageId::new((t as u64 + accesses as u64) % HOT_PAGES);
+                        let ph = bpm.get_page(&pid).unwrap();
+
+                        drop(ph.read().await.unwrap());
+                        accesses += 1;
+
+                        tokio::task::yield_now().await;
+                    }
+
+                    accesses
+                })
+            })
+            .collect();
+
+        let mut accesses = 0;
+        for task in tasks {
+            accesses += task.await.unwrap();
+        }
+
+        let reloads = IO_OPERATIONS.load(Ordering::Acquire) - start_io;
+        let rate = reloads as f64 / accesses as f64;
+        assert!(
+            rate < MAX_RELOAD_RATE,
+            "{reloads} reloads over {accesses} accesses is a wasted reload rate of {rate}"
+        );
+
+        bpm.stop_daemons();
+        evictor.await.unwrap();
+    });
+}
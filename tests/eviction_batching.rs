@@ -0,0 +1,43 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, PoolStats};
+
+/// The number of pages to write, which is more than fit in memory.
+const PAGES: u64 = 192;
+
+/// Dirties every page while only some of them fit in memory, and then reads them all back,
+/// returning the stats of the pass that evicted the dirty pages.
+fn dirty_scan(config: BufferPoolManagerConfig) -> PoolStats {
+    BufferPoolManager::initialize_with_config(config);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+        let before = bpm.stats();
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+
+        let stats = bpm.stats().since(&before);
+        bpm.shutdown().await.unwrap();
+        stats
+    })
+}
+
+#[test]
+#[ignore]
+fn test_eviction_batching() {
+    // By default, the dirty victims of a pass are written back together.
+    let stats = dirty_scan(BufferPoolManagerConfig::new(64, 256));
+    assert!(stats.dirty_write_backs > 0);
+    assert!(stats.mean_eviction_write_batch().unwrap() > 1.0);
+
+    // With a batch of one, every dirty page is written back on its own.
+    let stats = dirty_scan(BufferPoolManagerConfig::new(64, 256).eviction_write_batch(1));
+    assert!(stats.dirty_write_backs > 0);
+    assert_eq!(stats.eviction_write_batches, stats.dirty_write_backs);
+    assert_eq!(stats.mean_eviction_write_batch(), Some(1.0));
+}
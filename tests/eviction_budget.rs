@@ -0,0 +1,46 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// The number of pages to scan, which is more than fit in memory.
+const PAGES: u64 = 192;
+
+#[test]
+#[ignore]
+fn test_eviction_budget() {
+    // With no time budget, every eviction pass evicts exactly one page.
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).eviction_time_budget(Duration::ZERO),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        // Another task on the same thread keeps running while pages are evicted.
+        let ticks = Rc::new(Cell::new(0u64));
+        let ticker = BufferPoolManager::spawn_local({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    ticks.set(ticks.get() + 1);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            }
+        });
+
+        let before = bpm.stats().evictions;
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+        assert!(bpm.stats().evictions > before);
+        assert!(ticks.get() > 0);
+
+        ticker.abort();
+    });
+}
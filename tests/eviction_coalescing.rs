@@ -0,0 +1,33 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, IO_OPERATIONS};
+use std::sync::atomic::Ordering;
+
+/// The number of pages to write, which is more than fit in memory.
+const PAGES: u64 = 192;
+
+#[test]
+#[ignore]
+fn test_eviction_coalescing() {
+    BufferPoolManager::initialize_with_config(BufferPoolManagerConfig::new(64, 256));
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // Dirty consecutive pages, so that the victims of every eviction pass are mostly adjacent.
+        let before = bpm.stats();
+        let io_before = IO_OPERATIONS.load(Ordering::Relaxed);
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+        let stats = bpm.stats().since(&before);
+        let io_operations = IO_OPERATIONS.load(Ordering::Relaxed) - io_before;
+
+        // Adjacent dirty victims are written back with a single operation.
+        assert!(stats.dirty_write_backs > 0);
+        assert!(io_operations < (stats.page_reads + stats.page_writes) as usize);
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+    });
+}
@@ -0,0 +1,60 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, IO_OPERATIONS};
+use std::ops::DerefMut;
+use std::sync::atomic::Ordering;
+
+/// The number of pages that are exempt from eviction.
+const EXEMPT_PAGES: u64 = 8;
+
+/// The number of other pages that are read, which is several times the number of frames.
+const OTHER_PAGES: u64 = 240;
+
+#[test]
+#[ignore]
+fn test_eviction_exemption() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).eviction_exemption(|pid| pid.as_u64() < EXEMPT_PAGES),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..EXEMPT_PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().deref_mut().fill(i as u8 + 1);
+        }
+
+        // Cycle every other page through the remaining frames, both with evictions on demand and
+        // with the background eviction task.
+        let evictor = BufferPoolManager::spawn_evictor();
+        for _ in 0..2 {
+            for i in EXEMPT_PAGES..EXEMPT_PAGES + OTHER_PAGES {
+                let ph = bpm.get_page(&PageId::new(i)).unwrap();
+                drop(ph.read().await.unwrap());
+                tokio::task::yield_now().await;
+            }
+        }
+
+        // The exempt pages were never evicted, so they are read from memory with their data.
+        let io_before = IO_OPERATIONS.load(Ordering::Relaxed);
+        for i in 0..EXEMPT_PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            let guard = ph.read().await.unwrap();
+            assert!(
+                guard.iter().all(|&b| b == i as u8 + 1),
+                "Exempt page {i} lost its data"
+            );
+        }
+        assert_eq!(
+            IO_OPERATIONS.load(Ordering::Relaxed),
+            io_before,
+            "An exempt page was evicted"
+        );
+
+        // Pages that are not exempt were evicted to make room.
+        let ph = bpm.get_page(&PageId::new(EXEMPT_PAGES)).unwrap();
+        drop(ph.read().await.unwrap());
+        assert!(IO_OPERATIONS.load(Ordering::Relaxed) > io_before);
+
+        bpm.stop_daemons();
+        evictor.await.unwrap();
+    });
+}
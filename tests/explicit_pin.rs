@@ -0,0 +1,41 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+
+/// The number of pages to write, which is more than the number of frames so that pages must be
+/// evicted around the pinned page.
+const PAGES: u64 = 256;
+
+#[test]
+#[ignore]
+fn test_explicit_pin() {
+    BufferPoolManager::initialize(64, 512);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let root = bpm.get_page(&PageId::new(0)).unwrap();
+        root.write().await.unwrap().fill(0xAB);
+
+        // Pins compose, and do not hold a lock on the page.
+        root.pin().await.unwrap();
+        root.pin().await.unwrap();
+        assert_eq!(root.pin_count(), 2);
+        root.unpin();
+        assert_eq!(root.pin_count(), 1);
+        let guard = root.try_write().await.unwrap();
+        assert!(guard.is_some(), "Pins do not lock the page");
+        drop(guard);
+
+        // Churn through enough pages to evict every unpinned page several times over.
+        for i in 1..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        // The pinned page was never evicted, so reading it does not touch persistent storage.
+        let before = bpm.stats();
+        assert!(root.read().await.unwrap().iter().all(|&b| b == 0xAB));
+        assert_eq!(bpm.stats().since(&before).page_reads, 0);
+
+        root.unpin();
+        assert_eq!(root.pin_count(), 0);
+    });
+}
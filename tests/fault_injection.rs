@@ -0,0 +1,58 @@
+#![cfg(feature = "test-util")]
+
+use async_bpm::error::BpmError;
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, FaultInjectingStorage};
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted and read back in.
+const PAGES: u64 = 128;
+
+#[test]
+#[ignore]
+fn test_fault_injection() {
+    let storage = Arc::new(FaultInjectingStorage::new());
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).storage_backend(storage.clone()),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // A failed read leaves the page out of memory, and the next read succeeds.
+        let ph = bpm.get_page(&PageId::new(200)).unwrap();
+        storage.fail_nth_read(1);
+        assert!(matches!(ph.read().await, Err(BpmError::Io { .. })));
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 0));
+
+        // A failed flush keeps the page dirty, so that it can be flushed again.
+        let mut guard = ph.write().await.unwrap();
+        guard.fill(1);
+        storage.fail_nth_write(1);
+        assert!(guard.flush().await.is_err());
+        let writes = storage.writes();
+        guard.flush().await.unwrap();
+        assert_eq!(storage.writes(), writes + 1);
+        drop(guard);
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(i as u8);
+            guard.flush().await.unwrap();
+        }
+
+        // Short reads are retried until the whole page is read.
+        storage.short_reads(true);
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let guard = ph.read().await.unwrap();
+            assert!(
+                guard.iter().all(|&b| b == i as u8),
+                "Page {i} has the wrong data"
+            );
+        }
+    });
+}
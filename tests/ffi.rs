@@ -0,0 +1,83 @@
+#![cfg(feature = "ffi")]
+
+use async_bpm::ffi::*;
+use std::ffi::c_void;
+use std::ptr;
+
+/// Writes `value` into every byte of page `pid` and flushes it through the C API.
+fn write_page(pid: u64, value: u8) {
+    let handle = bpm_get_page(pid);
+    assert!(!handle.is_null());
+
+    let mut data: *mut u8 = ptr::null_mut();
+    let mut len = 0;
+    let mut guard: *mut c_void = ptr::null_mut();
+
+    unsafe {
+        assert_eq!(bpm_write(handle, &mut data, &mut len, &mut guard), 0);
+        std::slice::from_raw_parts_mut(data, len).fill(value);
+        assert_eq!(bpm_write_flush(data, len, guard), 0);
+        bpm_write_release(data, len, guard);
+        bpm_page_handle_free(handle);
+    }
+}
+
+/// Checks that every byte of page `pid` is `value` through the C API.
+fn check_page(pid: u64, value: u8) {
+    let handle = bpm_get_page(pid);
+    assert!(!handle.is_null());
+
+    let mut data: *const u8 = ptr::null();
+    let mut len = 0;
+    let mut guard: *mut BpmReadGuard = ptr::null_mut();
+
+    unsafe {
+        assert_eq!(bpm_read(handle, &mut data, &mut len, &mut guard), 0);
+        let page = std::slice::from_raw_parts(data, len);
+        assert!(
+            page.iter().all(|&b| b == value),
+            "Page {pid} has the wrong data"
+        );
+        bpm_read_release(guard);
+        bpm_page_handle_free(handle);
+    }
+}
+
+#[test]
+#[ignore]
+fn test_ffi() {
+    // Invalid configurations are reported instead of aborting the host.
+    assert_eq!(bpm_initialize(0, 128), -libc::EINVAL);
+    assert_eq!(bpm_initialize(128, 64), -libc::EINVAL);
+
+    assert_eq!(bpm_initialize(64, 128), 0);
+    assert_eq!(bpm_initialize(64, 128), -libc::EEXIST);
+
+    write_page(7, 42);
+    check_page(7, 42);
+
+    // A write guard that is never released keeps the buffer pool from shutting down.
+    let handle = bpm_get_page(8);
+    let mut data: *mut u8 = ptr::null_mut();
+    let mut len = 0;
+    let mut guard: *mut c_void = ptr::null_mut();
+    unsafe {
+        assert_eq!(bpm_write(handle, &mut data, &mut len, &mut guard), 0);
+        std::slice::from_raw_parts_mut(data, len).fill(43);
+    }
+    assert_eq!(bpm_shutdown(), -libc::EBUSY);
+
+    // Once it is released, shutting down writes out the dirty page.
+    unsafe {
+        bpm_write_release(data, len, guard);
+        bpm_page_handle_free(handle);
+    }
+    assert_eq!(bpm_shutdown(), 0);
+    assert!(bpm_get_page(7).is_null());
+
+    // The buffer pool can be initialized again, and every page was persisted.
+    assert_eq!(bpm_initialize(64, 128), 0);
+    check_page(7, 42);
+    check_page(8, 43);
+    assert_eq!(bpm_shutdown(), 0);
+}
@@ -0,0 +1,41 @@
+use async_bpm::page::{PageId, PAGE_SIZE};
+use async_bpm::BufferPoolManager;
+use std::ops::DerefMut;
+use std::os::unix::fs::FileExt;
+
+/// The number of pages that are dirtied before the first checkpoint.
+const PAGES: u64 = 16;
+
+#[test]
+#[ignore]
+fn test_flush_all() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().deref_mut().fill(i as u8 + 1);
+        }
+
+        // Every dirty page is written out exactly once.
+        assert_eq!(bpm.flush_all().await.unwrap(), PAGES as usize);
+        assert_eq!(bpm.flush_all().await.unwrap(), 0);
+
+        // Only pages that were dirtied again are written out by the next checkpoint.
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        ph.write().await.unwrap().deref_mut().fill(42);
+        assert_eq!(bpm.flush_all().await.unwrap(), 1);
+    });
+
+    let file = std::fs::File::open("bpm.db").unwrap();
+    let mut buf = vec![0; PAGE_SIZE];
+    for i in 0..PAGES {
+        let expected = if i == 0 { 42 } else { i as u8 + 1 };
+        file.read_exact_at(&mut buf, i * PAGE_SIZE as u64).unwrap();
+        assert!(
+            buf.iter().all(|&b| b == expected),
+            "Page {i} was not written out"
+        );
+    }
+}
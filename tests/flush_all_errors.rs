@@ -0,0 +1,68 @@
+#![cfg(feature = "test-util")]
+
+use async_bpm::error::{BpmError, FlushAllError};
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, FaultInjectingStorage};
+use async_bpm::{MemoryStorage, StorageBackend};
+use std::collections::HashSet;
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+/// The number of pages that are dirtied before every checkpoint.
+const PAGES: u64 = 16;
+
+#[test]
+#[ignore]
+fn test_flush_all_errors() {
+    // Every layer of fault injection counts the writes that reach it, so wrapping one in another
+    // makes it possible to fail two writes of the same checkpoint.
+    let memory = Arc::new(MemoryStorage::new());
+    let inner = Arc::new(FaultInjectingStorage::wrap(memory.clone()));
+    let outer = Arc::new(FaultInjectingStorage::wrap(inner.clone()));
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).storage_backend(outer.clone()),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().deref_mut().fill(i as u8 + 1);
+        }
+
+        // The 3rd write fails in the outer layer, and the 5th write fails in the inner layer (as
+        // its 4th write).
+        outer.fail_nth_write(3);
+        inner.fail_nth_write(4);
+
+        let Err(BpmError::Io { pid: None, source }) = bpm.flush_all().await else {
+            panic!("A checkpoint with failed writes must fail");
+        };
+        let error = source
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<FlushAllError>())
+            .unwrap();
+        assert_eq!(error.failures().len(), 2);
+
+        // Every other page was still written out, so exactly the reported pages are missing.
+        let mut missing = HashSet::new();
+        for i in 0..PAGES {
+            let pid = PageId::new(i);
+            let mut buf = vec![0; bpm.page_size()];
+            memory.read_at(pid, 0, &mut buf).await.unwrap();
+
+            if buf.iter().all(|&b| b == 0) {
+                missing.insert(pid);
+            } else {
+                assert!(buf.iter().all(|&b| b == i as u8 + 1), "Page {i} is corrupt");
+            }
+        }
+        let reported: HashSet<_> = error.failures().iter().map(|(pid, _)| *pid).collect();
+        assert_eq!(reported, missing);
+        assert_eq!(memory.num_pages(), PAGES as usize - 2);
+
+        // The failed pages stay dirty, so the next checkpoint writes out exactly those pages.
+        assert_eq!(bpm.flush_all().await.unwrap(), 2);
+        assert_eq!(memory.num_pages(), PAGES as usize);
+        assert_eq!(bpm.flush_all().await.unwrap(), 0);
+    });
+}
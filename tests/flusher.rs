@@ -0,0 +1,40 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+use std::ops::DerefMut;
+use std::time::Duration;
+
+#[test]
+#[ignore]
+fn test_flusher() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let handles: Vec<_> = (0..4)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+
+        for (i, ph) in handles.iter().enumerate() {
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(b'a' + i as u8);
+        }
+
+        let flusher = BufferPoolManager::spawn_flusher(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let report = bpm.health().await;
+        assert_eq!(report.dirty_frames, 0);
+
+        let stats = bpm.write_back_stats();
+        assert_eq!(stats.cold_write_backs + stats.hot_write_backs, 4);
+        assert_eq!(stats.cold_redirties + stats.hot_redirties, 0);
+
+        // Dirtying a page again after it was written back counts as a re-dirty.
+        drop(handles[0].write().await.unwrap());
+
+        let stats = bpm.write_back_stats();
+        assert_eq!(stats.cold_redirties + stats.hot_redirties, 1);
+
+        bpm.stop_daemons();
+        flusher.await.unwrap();
+    });
+}
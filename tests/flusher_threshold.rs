@@ -0,0 +1,44 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::time::Duration;
+
+/// The number of buffer frames, which all belong to a single frame group.
+const FRAMES: usize = 64;
+
+#[test]
+#[ignore]
+fn test_flusher_dirty_threshold() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 128).flusher_dirty_threshold(0.5),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let dirty = |range: std::ops::Range<u64>| async move {
+            for i in range {
+                let ph = bpm.get_page(&PageId::new(i)).unwrap();
+                ph.write().await.unwrap().fill(i as u8);
+            }
+        };
+
+        // A quarter of the frames are dirty, which is below the threshold.
+        dirty(0..16).await;
+
+        let flusher = BufferPoolManager::spawn_flusher(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let stats = bpm.write_back_stats();
+        assert_eq!(stats.cold_write_backs + stats.hot_write_backs, 0);
+
+        // Once enough frames are dirty, the flusher writes back just enough of them to get below
+        // the threshold again.
+        dirty(16..40).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let stats = bpm.write_back_stats();
+        assert_eq!(stats.cold_write_backs + stats.hot_write_backs, 9);
+        assert_eq!(bpm.health().await.dirty_frames, FRAMES / 2 - 1);
+
+        bpm.stop_daemons();
+        flusher.await.unwrap();
+    });
+}
@@ -0,0 +1,32 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+
+/// The number of buffer frames, which is not a multiple of the frame group size so that the last
+/// arena is smaller than the others.
+const FRAMES: usize = 100;
+
+#[test]
+#[ignore]
+fn test_frame_alignment() {
+    for (page_size, alignment) in [(4096, 4096), (3 * 512, 512), (8192, 4096)] {
+        BufferPoolManager::initialize_with_config(
+            BufferPoolManagerConfig::new(FRAMES, 2 * FRAMES).page_size(page_size),
+        );
+        let bpm = BufferPoolManager::get();
+        assert_eq!(bpm.frame_alignment(), alignment);
+
+        BufferPoolManager::start_thread(async move {
+            // Load more pages than there are frames, so that most frames are used at least once.
+            for i in 0..2 * FRAMES as u64 {
+                let ph = bpm.get_page(&PageId::new(i)).unwrap();
+                let mut guard = ph.write().await.unwrap();
+                assert_eq!(guard.as_ptr() as usize % alignment, 0);
+
+                // Reading and writing with `O_DIRECT` fails if the frame is not aligned.
+                guard.fill(i as u8);
+                guard.flush().await.unwrap();
+            }
+
+            bpm.shutdown().await.unwrap();
+        });
+    }
+}
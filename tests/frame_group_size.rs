@@ -0,0 +1,46 @@
+use async_bpm::page::PageId;
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig, ReplacementPolicy};
+
+/// The number of frames in every frame group.
+const GROUP_SIZE: usize = 8;
+
+/// A number of frames that is not a multiple of the frame group size, so the last frame group is
+/// smaller than the others.
+const FRAMES: usize = 20;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted from every frame group.
+const PAGES: u64 = 100;
+
+#[test]
+#[ignore]
+fn test_frame_group_size() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 1024)
+            .frame_group_size(GROUP_SIZE)
+            .replacement_policy(ReplacementPolicy::Fifo),
+    );
+    let bpm = BufferPoolManager::get();
+    assert_eq!(bpm.frame_group_size(), GROUP_SIZE);
+    assert_eq!(bpm.num_frames(), FRAMES);
+
+    let stats = bpm.stats();
+    assert_eq!(stats.frame_groups.len(), 3);
+    assert_eq!(stats.frame_groups[0].total_frames, GROUP_SIZE);
+    assert_eq!(stats.frame_groups[1].total_frames, GROUP_SIZE);
+    assert_eq!(stats.frame_groups[2].total_frames, FRAMES - 2 * GROUP_SIZE);
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+    });
+
+    assert!(bpm.stats().evictions > 0);
+}
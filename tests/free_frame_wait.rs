@@ -0,0 +1,43 @@
+use async_bpm::error::BpmError;
+use async_bpm::page::{PageHandle, PageId};
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+use std::io::ErrorKind;
+use std::time::Duration;
+
+const FRAMES: usize = 64;
+
+#[test]
+#[ignore]
+fn test_free_frame_wait() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 256).free_frame_timeout(Duration::from_millis(100)),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // Pin every frame of the only frame group.
+        let handles: Vec<PageHandle> = (0..FRAMES as u64)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+        let mut guards = Vec::new();
+        for ph in &handles {
+            guards.push(ph.write().await.unwrap());
+        }
+
+        // With every page in use, nothing can be evicted, so the task gives up after the timeout.
+        let ph = bpm.get_page(&PageId::new(FRAMES as u64)).unwrap();
+        let err = ph.read().await.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        let BpmError::BufferPoolFull(full) = err else {
+            panic!("Expected the buffer pool to be full, got {err}");
+        };
+        assert!(full.waited() >= Duration::from_millis(100));
+
+        // Once the pages are unpinned, a waiting task evicts one of them and gets its frame.
+        let (res, ()) = tokio::join!(ph.read(), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            guards.clear();
+        });
+        assert!(res.is_ok());
+    });
+}
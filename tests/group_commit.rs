@@ -0,0 +1,68 @@
+use async_bpm::page::{PageId, PAGE_SIZE};
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig, IO_OPERATIONS};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// The database file that the buffer pool manager uses by default.
+const DATABASE: &str = "bpm.db";
+
+/// The number of pages that are committed concurrently.
+const PAGES: u64 = 8;
+
+/// Reads a page straight from the database file.
+fn on_disk(pid: u64) -> Vec<u8> {
+    let file = std::fs::read(DATABASE).unwrap();
+    let offset = pid as usize * PAGE_SIZE;
+    file[offset..offset + PAGE_SIZE].to_vec()
+}
+
+#[test]
+#[ignore]
+fn test_group_commit() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 128).group_commit_window(Duration::from_millis(10)),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // Load every page up front, so that the only I/O left is the commits.
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+
+        let io_before = IO_OPERATIONS.load(Ordering::Relaxed);
+
+        let tasks: Vec<_> = (0..PAGES)
+            .map(|i| {
+                BufferPoolManager::spawn_local(async move {
+                    let ph = bpm.get_page(&PageId::new(i)).unwrap();
+                    let mut guard = ph.write().await.unwrap();
+                    guard.fill(b'G' + i as u8);
+                    guard.flush_sync().await.unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // Every page was durable once its commit returned, and the consecutive pages were written
+        // together instead of one at a time.
+        for i in 0..PAGES {
+            assert!(on_disk(i).iter().all(|&b| b == b'G' + i as u8));
+        }
+        let io_operations = IO_OPERATIONS.load(Ordering::Relaxed) - io_before;
+        assert!(io_operations < PAGES as usize);
+
+        // Temporary pages are flushed on their own, and never synced.
+        let temp = bpm.new_temp_page().unwrap();
+        let mut guard = temp.write().await.unwrap();
+        guard.fill(b'T');
+        guard.flush_sync().await.unwrap();
+        drop(guard);
+        bpm.drop_temp_page(temp).await.unwrap();
+
+        bpm.shutdown().await.unwrap();
+    });
+}
@@ -0,0 +1,42 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+
+#[test]
+#[ignore]
+fn test_guard_downgrade() {
+    BufferPoolManager::initialize(64, 256);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+
+        // A downgraded guard sees the data that was written, and lets other readers in.
+        let mut guard = ph.write().await.unwrap();
+        guard.fill(b'a');
+        let guard = guard.downgrade();
+        assert!(guard.iter().all(|&b| b == b'a'));
+        assert_eq!(ph.pin_count(), 1);
+
+        let other = ph
+            .try_read()
+            .await
+            .unwrap()
+            .expect("The page is only read locked");
+        assert!(other.iter().all(|&b| b == b'a'));
+
+        // An upgrade fails while another reader holds the page.
+        assert!(guard.try_upgrade().is_none());
+        drop(other);
+        assert_eq!(ph.pin_count(), 0);
+
+        // Once the page is only held by one reader, the upgrade succeeds.
+        let guard = ph.read().await.unwrap();
+        let mut guard = guard.try_upgrade().expect("Nobody else holds the page");
+        assert!(guard.iter().all(|&b| b == b'a'));
+        guard.fill(b'b');
+        guard.flush().await.unwrap();
+        drop(guard);
+
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == b'b'));
+        assert_eq!(ph.pin_count(), 0);
+    });
+}
@@ -0,0 +1,32 @@
+use async_bpm::BufferPoolManager;
+use std::time::Duration;
+
+#[test]
+#[ignore]
+fn test_health() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let probe = BufferPoolManager::spawn_ring_probe(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let report = bpm.health().await;
+        assert!(report.is_healthy(), "{report:?}");
+        assert_eq!(report.daemons_alive, 1);
+        assert_eq!(report.free_frames, report.total_frames);
+        assert_eq!(report.dirty_frames, 0);
+        assert_eq!(report.direct_io, bpm.direct_io());
+
+        let probes = bpm.ring_probes();
+        assert_eq!(probes.len(), 1);
+        assert!(probes[0].probes > 0);
+
+        bpm.stop_daemons();
+        probe.await.unwrap();
+
+        let report = bpm.health().await;
+        assert_eq!(report.daemons_alive, 0);
+        assert!(!report.is_healthy());
+    });
+}
@@ -0,0 +1,54 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::time::Duration;
+
+/// The number of threads that read the hot page at the same time.
+const THREADS: usize = 4;
+
+/// The number of times every thread reads the hot page.
+const ACCESSES: u64 = 100_000;
+
+/// Reads a single page over and over from several threads, returning how many of the accesses
+/// skipped updating the eviction state.
+fn hammer(config: BufferPoolManagerConfig) -> u64 {
+    BufferPoolManager::initialize_with_config(config);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        ph.write().await.unwrap().fill(42);
+    });
+
+    let before = bpm.stats();
+
+    std::thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                BufferPoolManager::start_thread(async move {
+                    let ph = bpm.get_page(&PageId::new(0)).unwrap();
+                    for _ in 0..ACCESSES {
+                        assert_eq!(ph.read().await.unwrap()[0], 42);
+                    }
+                })
+            });
+        }
+    });
+
+    let stats = bpm.stats().since(&before);
+    assert_eq!(stats.read_accesses, THREADS as u64 * ACCESSES);
+
+    BufferPoolManager::start_thread(async move { bpm.shutdown().await.unwrap() });
+
+    stats.skipped_access_records
+}
+
+#[test]
+#[ignore]
+fn test_hot_access_threshold() {
+    assert_eq!(hammer(BufferPoolManagerConfig::new(64, 256)), 0);
+
+    let skipped =
+        hammer(BufferPoolManagerConfig::new(64, 256).hot_access_threshold(Duration::from_secs(60)));
+
+    // Only the first access after the page was loaded is recorded.
+    assert_eq!(skipped, THREADS as u64 * ACCESSES);
+}
@@ -0,0 +1,70 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, InitProgress};
+use std::cell::Cell;
+use std::ops::DerefMut;
+
+/// The database file of this test, which is created by the initialization.
+const DATABASE: &str = "init_async_test.db";
+
+const FRAMES: usize = 256;
+const CAPACITY: usize = 1024;
+
+#[test]
+#[ignore]
+fn test_initialize_async() {
+    let _ = std::fs::remove_file(DATABASE);
+
+    let config = || BufferPoolManagerConfig::new(FRAMES, CAPACITY).paths([DATABASE]);
+
+    BufferPoolManager::start_thread(async move {
+        // Dropping the future partway through cancels the initialization.
+        let halfway = Cell::new(false);
+        let init = BufferPoolManager::initialize_async(config(), |p| {
+            assert!(
+                p.frames_allocated <= FRAMES / 2,
+                "Initialization was not cancelled"
+            );
+            halfway.set(p.frames_allocated == FRAMES / 2);
+        });
+        tokio::select! {
+            biased;
+            _ = init => unreachable!("Initialization was not cancelled"),
+            _ = async {
+                while !halfway.get() {
+                    tokio::task::yield_now().await;
+                }
+            } => {}
+        }
+        assert!(!BufferPoolManager::is_initialized());
+
+        let mut reports: Vec<InitProgress> = Vec::new();
+        BufferPoolManager::initialize_async(config(), |p| reports.push(p))
+            .await
+            .unwrap();
+        assert!(BufferPoolManager::is_initialized());
+
+        // The progress only ever moves forward, and ends with everything allocated.
+        for pair in reports.windows(2) {
+            assert!(pair[0].frames_allocated <= pair[1].frames_allocated);
+            assert!(pair[0].bytes_preallocated <= pair[1].bytes_preallocated);
+        }
+        let last = reports.last().unwrap();
+        assert!(last.is_complete());
+        assert_eq!(last.total_frames, FRAMES);
+
+        let bpm = BufferPoolManager::get();
+        assert_eq!(last.total_bytes, (CAPACITY * bpm.page_size()) as u64);
+
+        let len = std::fs::metadata(DATABASE).unwrap().len();
+        assert_eq!(len, last.total_bytes);
+
+        for i in 0..(FRAMES as u64 * 2) {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(i as u8);
+        }
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 0));
+    });
+
+    let _ = std::fs::remove_file(DATABASE);
+}
@@ -0,0 +1,49 @@
+#![cfg(all(feature = "test-util", debug_assertions))]
+
+use async_bpm::{page::PageId, BufferPoolManager};
+use std::ops::DerefMut;
+use std::time::Duration;
+
+#[test]
+#[ignore]
+fn test_invariant_violation() {
+    BufferPoolManager::initialize(64, 256);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        ph.write().await.unwrap().deref_mut().fill(1);
+        assert!(bpm.check_invariants().await.is_empty());
+
+        // A dirty frame that is counted twice is caught by cross-checking the dirty counters with
+        // the dirty bits of every frame.
+        bpm.skew_dirty_frame_count();
+        let violations = bpm.check_invariants().await;
+        assert_eq!(violations.len(), 1, "{violations:?}");
+        assert!(
+            violations[0].contains("counts 2 dirty frames but 1 frames are dirty"),
+            "{}",
+            violations[0]
+        );
+
+        // The checker daemon reports the violation on every pass, without stopping.
+        let errors = bpm.daemon_errors();
+        let checker = BufferPoolManager::spawn_invariant_checker(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!checker.is_finished());
+
+        let mut reports = 0;
+        while let Ok(error) = errors.try_recv() {
+            assert_eq!(error.daemon(), "invariant-checker");
+            assert!(error.to_string().contains("dirty frames"), "{error}");
+            reports += 1;
+        }
+        assert!(
+            reports >= 2,
+            "The violation was only reported {reports} times"
+        );
+
+        bpm.stop_daemons();
+        checker.await.unwrap();
+    });
+}
@@ -0,0 +1,53 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, InjectedLatency};
+use std::time::{Duration, Instant};
+
+/// The latency injected into every read, so that reads stay in flight for a while.
+const LATENCY: Duration = Duration::from_millis(20);
+
+/// The largest number of reads and writes that the thread keeps in flight.
+const MAX_IN_FLIGHT: usize = 2;
+
+/// The number of pages that are read concurrently.
+const PAGES: u64 = 8;
+
+#[test]
+#[ignore]
+fn test_io_backpressure() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 128).max_in_flight_io(MAX_IN_FLIGHT),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        bpm.set_injected_latency(
+            0,
+            InjectedLatency {
+                read: LATENCY,
+                ..Default::default()
+            },
+        );
+
+        // Reads beyond the limit wait for earlier reads to complete.
+        let start = Instant::now();
+        let handles: Vec<_> = (0..PAGES)
+            .map(|i| {
+                BufferPoolManager::spawn_local(async move {
+                    let ph = bpm.get_page(&PageId::new(i)).unwrap();
+                    drop(ph.read().await.unwrap());
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert!(start.elapsed() >= LATENCY * (PAGES as usize / MAX_IN_FLIGHT) as u32);
+
+        let stats = bpm.uring_stats();
+        assert_eq!(stats.completions, PAGES);
+        assert_eq!(stats.in_flight, 0);
+        assert_eq!(stats.max_in_flight, MAX_IN_FLIGHT as u64);
+        assert_eq!(stats.throttled, PAGES - MAX_IN_FLIGHT as u64);
+
+        bpm.shutdown().await.unwrap();
+    });
+}
@@ -0,0 +1,61 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use tokio::task::LocalSet;
+
+/// The number of dedicated I/O driver threads.
+const IO_THREADS: usize = 2;
+
+/// The number of compute threads, none of which have an `io_uring` instance.
+const COMPUTE_THREADS: u64 = 4;
+
+/// The number of pages that every compute thread writes, which add up to more than the number of
+/// frames.
+const PAGES_PER_THREAD: u64 = 64;
+
+#[test]
+#[ignore]
+fn test_io_threads() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 512).io_threads(IO_THREADS),
+    );
+    let bpm = BufferPoolManager::get();
+
+    // Every compute thread runs a plain Tokio runtime, so every read and write (including the
+    // write-backs of evictions) goes through the driver threads.
+    std::thread::scope(|s| {
+        for t in 0..COMPUTE_THREADS {
+            s.spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+
+                let pids = t * PAGES_PER_THREAD..(t + 1) * PAGES_PER_THREAD;
+                LocalSet::new().block_on(&runtime, async move {
+                    let writers: Vec<_> = pids
+                        .clone()
+                        .map(|i| {
+                            tokio::task::spawn_local(async move {
+                                let ph = bpm.get_page(&PageId::new(i)).unwrap();
+                                ph.write().await.unwrap().fill(i as u8);
+                            })
+                        })
+                        .collect();
+                    for writer in writers {
+                        writer.await.unwrap();
+                    }
+
+                    for i in pids {
+                        let ph = bpm.get_page(&PageId::new(i)).unwrap();
+                        let guard = ph.read().await.unwrap();
+                        assert!(guard.iter().all(|&byte| byte == i as u8));
+                    }
+                });
+            });
+        }
+    });
+
+    // The driver threads perform the final write-backs, and then exit.
+    BufferPoolManager::start_thread(async move {
+        bpm.shutdown().await.unwrap();
+    });
+}
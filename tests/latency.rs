@@ -0,0 +1,63 @@
+use async_bpm::{page::PageId, BufferPoolManager, InjectedLatency};
+use std::time::{Duration, Instant};
+
+/// The latency injected into every read and write.
+const LATENCY: Duration = Duration::from_millis(50);
+
+/// The number of pages that are read concurrently.
+const PAGES: u64 = 8;
+
+#[test]
+#[ignore]
+fn test_injected_latency() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        assert_eq!(bpm.injected_latency(0), InjectedLatency::default());
+
+        let latency = InjectedLatency {
+            read: LATENCY,
+            write: LATENCY,
+            jitter: Duration::from_millis(1),
+        };
+        bpm.set_injected_latency(0, latency);
+        assert_eq!(bpm.injected_latency(0), latency);
+
+        // A single read and write each wait for the injected latency.
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        let start = Instant::now();
+        let mut guard = ph.write().await.unwrap();
+        assert!(start.elapsed() >= LATENCY);
+
+        let start = Instant::now();
+        guard.flush().await.unwrap();
+        assert!(start.elapsed() >= LATENCY);
+        drop(guard);
+
+        // Concurrent reads wait for their latency at the same time.
+        let start = Instant::now();
+        let handles: Vec<_> = (1..=PAGES)
+            .map(|i| {
+                BufferPoolManager::spawn_local(async move {
+                    let ph = bpm.get_page(&PageId::new(i)).unwrap();
+                    drop(ph.read().await.unwrap());
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert!(start.elapsed() >= LATENCY);
+        assert!(start.elapsed() < LATENCY * PAGES as u32 / 2);
+
+        // Pages that are already in memory are never delayed, and the latency can be removed.
+        bpm.set_injected_latency(0, InjectedLatency::default());
+        let start = Instant::now();
+        let ph = bpm.get_page(&PageId::new(PAGES + 1)).unwrap();
+        drop(ph.read().await.unwrap());
+        assert!(start.elapsed() < LATENCY);
+
+        bpm.shutdown().await.unwrap();
+    });
+}
@@ -0,0 +1,50 @@
+use async_bpm::page::PageId;
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+
+/// The number of pages to write, which is more than the number of frames so that pages must be
+/// evicted.
+const PAGES: u64 = 128;
+
+#[test]
+#[ignore]
+fn test_latency_histograms() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).latency_histograms(true),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        // The last page is still in memory, while the first page was evicted long ago.
+        let ph = bpm.get_page(&PageId::new(PAGES - 1)).unwrap();
+        drop(ph.read().await.unwrap());
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 0));
+
+        let histograms = bpm.latency_histograms();
+        assert_eq!(histograms.write_guard.count(), PAGES);
+        assert_eq!(histograms.read_hit.count(), 1);
+        assert_eq!(histograms.read_miss.count(), 1);
+        assert!(histograms.eviction_write.count() >= 1);
+
+        // The quantiles are ordered, and bounded by the largest latency.
+        let writes = &histograms.write_guard;
+        let median = writes.quantile(0.5).unwrap();
+        let tail = writes.quantile(0.99).unwrap();
+        assert!(median <= tail);
+        assert!(tail <= writes.max().unwrap());
+        assert!(writes.mean().unwrap() <= writes.max().unwrap());
+        assert_eq!(writes.buckets().map(|(_, count)| count).sum::<u64>(), PAGES);
+
+        let text = histograms.to_prometheus();
+        assert!(text.contains("# TYPE async_bpm_page_latency_seconds histogram"));
+        assert!(text.contains(&format!(
+            "async_bpm_page_latency_seconds_bucket{{operation=\"write_guard\",le=\"+Inf\"}} {PAGES}"
+        )));
+        assert!(text.contains("async_bpm_page_latency_seconds_count{operation=\"read_miss\"} 1"));
+    });
+}
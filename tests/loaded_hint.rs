@@ -0,0 +1,32 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+
+/// The number of pages to read, which is more than fit in memory.
+const PAGES: u64 = 192;
+
+#[test]
+#[ignore]
+fn test_loaded_hint_disabled() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).loaded_hint(false),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        // Without the hint, readers take the read lock first even if the page was evicted, and
+        // then load it with the write lock.
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+            assert!(ph.try_read().await.unwrap().is_some());
+        }
+
+        let stats = bpm.stats();
+        assert!(stats.evictions > 0);
+        assert_eq!(stats.loaded_hint_misses, 0);
+    });
+}
@@ -0,0 +1,47 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, IO_OPERATIONS};
+use std::sync::atomic::Ordering;
+
+/// The number of pages that are accessed repeatedly.
+const HOT_PAGES: u64 = 16;
+
+/// The number of pages that are touched once by a sequential scan.
+const SCAN_PAGES: u64 = 192;
+
+/// Checks that a sequential scan does not push frequently accessed pages out of memory when using
+/// the LRU-K replacement policy.
+#[test]
+#[ignore]
+fn test_lru_k_scan_resistance() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).lru_k_replacement(2),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let hot: Vec<_> = (0..HOT_PAGES)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+
+        // Access every hot page twice so that they all have a finite backward 2-distance.
+        for _ in 0..2 {
+            for ph in &hot {
+                drop(ph.read().await.unwrap());
+            }
+        }
+
+        // Scan through far more pages than the buffer pool can hold, forcing evictions.
+        for i in HOT_PAGES..HOT_PAGES + SCAN_PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+
+        // None of the hot pages should have to be read from persistent storage again.
+        let before = IO_OPERATIONS.load(Ordering::Acquire);
+        for ph in &hot {
+            drop(ph.read().await.unwrap());
+        }
+        let reloads = IO_OPERATIONS.load(Ordering::Acquire) - before;
+
+        assert_eq!(reloads, 0);
+    });
+}
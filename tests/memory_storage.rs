@@ -0,0 +1,42 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, MemoryStorage};
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted and read back in.
+const PAGES: u64 = 192;
+
+#[test]
+#[ignore]
+fn test_memory_storage() {
+    let storage = Arc::new(MemoryStorage::new());
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).storage_backend(storage.clone()),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(i as u8);
+            guard.flush_sync().await.unwrap();
+        }
+        assert_eq!(storage.num_pages(), PAGES as usize);
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let guard = ph.read().await.unwrap();
+            assert!(
+                guard.iter().all(|&b| b == i as u8),
+                "Page {i} has the wrong data"
+            );
+        }
+
+        // Pages that were never written read as zeroes.
+        let ph = bpm.get_page(&PageId::new(PAGES)).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 0));
+    });
+}
@@ -0,0 +1,39 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::ops::DerefMut;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted and read back in.
+const PAGES: u64 = 192;
+
+#[test]
+#[ignore]
+fn test_numa_aware() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(128, 256).numa_aware(true),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(i as u8);
+            guard.flush().await.unwrap();
+        }
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let guard = ph.read().await.unwrap();
+            assert!(
+                guard.iter().all(|&b| b == i as u8),
+                "Page {i} has the wrong data"
+            );
+            drop(guard);
+        }
+
+        // Unmapping the arenas must not fault, even though they were not allocated on the heap.
+        bpm.shutdown().await.unwrap();
+    });
+}
@@ -0,0 +1,61 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+
+/// The number of times the page is rewritten and read.
+const ITERATIONS: usize = 10_000;
+
+/// The page that is read and written concurrently.
+const PID: u64 = 0;
+
+#[test]
+#[ignore]
+fn test_optimistic_read() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+    let page_size = bpm.usable_page_size();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(PID)).unwrap();
+
+        // The page is not loaded yet, so this falls back to a regular read.
+        let mut buf = vec![0xFF; page_size];
+        ph.optimistic_read(0, &mut buf).await.unwrap();
+
+        {
+            let mut guard = ph.write().await.unwrap();
+            for (i, byte) in guard.iter_mut().enumerate() {
+                *byte = i as u8;
+            }
+        }
+
+        // Now it is loaded, so this reads without taking the lock.
+        let mut header = [0; 8];
+        ph.optimistic_read(16, &mut header).await.unwrap();
+        assert_eq!(header, [16, 17, 18, 19, 20, 21, 22, 23]);
+    });
+
+    // Readers on one thread must never observe a partially written page, while a writer on another
+    // thread keeps rewriting it.
+    std::thread::scope(|s| {
+        s.spawn(move || {
+            BufferPoolManager::start_thread(async move {
+                let ph = bpm.get_page(&PageId::new(PID)).unwrap();
+                for i in 0..ITERATIONS {
+                    ph.write().await.unwrap().fill(i as u8);
+                    tokio::task::yield_now().await;
+                }
+            });
+        });
+
+        s.spawn(move || {
+            BufferPoolManager::start_thread(async move {
+                let ph = bpm.get_page(&PageId::new(PID)).unwrap();
+                let mut buf = vec![0; page_size];
+                for _ in 0..ITERATIONS {
+                    ph.optimistic_read(0, &mut buf).await.unwrap();
+                    assert!(buf.iter().all(|&b| b == buf[0]), "Observed a torn page");
+                    tokio::task::yield_now().await;
+                }
+            });
+        });
+    });
+}
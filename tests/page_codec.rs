@@ -0,0 +1,105 @@
+use async_bpm::error::{BpmError, ConfigError};
+use async_bpm::{
+    page::PageId, BufferPoolManager, BufferPoolManagerConfig, MemoryStorage, PageCodec,
+    StorageBackend,
+};
+use std::io::{Error, ErrorKind, Result};
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted and read back in.
+const PAGES: u64 = 128;
+
+/// A codec that flips every bit of a page, and stores the page's ID in its trailer to check that
+/// pages are decoded with the right ID.
+#[derive(Debug)]
+struct FlipCodec;
+
+impl PageCodec for FlipCodec {
+    fn encode(&self, pid: PageId, plain: &[u8], encoded: &mut [u8]) -> Result<()> {
+        let (data, trailer) = encoded.split_at_mut(plain.len() - 8);
+        for (byte, &plain) in data.iter_mut().zip(plain) {
+            *byte = !plain;
+        }
+        trailer.copy_from_slice(&pid.as_u64().to_le_bytes());
+
+        Ok(())
+    }
+
+    fn decode(&self, pid: PageId, encoded: &[u8], plain: &mut [u8]) -> Result<()> {
+        let (data, trailer) = encoded.split_at(encoded.len() - 8);
+        if trailer != pid.as_u64().to_le_bytes() {
+            return Err(Error::new(ErrorKind::InvalidData, "Wrong page"));
+        }
+
+        for (byte, &encoded) in plain.iter_mut().zip(data) {
+            *byte = !encoded;
+        }
+
+        Ok(())
+    }
+
+    fn trailer_len(&self) -> usize {
+        8
+    }
+}
+
+#[test]
+#[ignore]
+fn test_page_codec() {
+    let storage = Arc::new(MemoryStorage::new());
+    let config = BufferPoolManagerConfig::new(64, 256)
+        .storage_backend(storage.clone())
+        .page_codec(Arc::new(FlipCodec));
+
+    // The codec's trailer must be reserved.
+    let err = BufferPoolManager::try_initialize_with_config(config.clone()).unwrap_err();
+    assert!(matches!(
+        err,
+        BpmError::Config(ConfigError::CodecTrailerTooLarge {
+            trailer_len: 8,
+            reserved: 0
+        })
+    ));
+
+    BufferPoolManager::initialize_with_config(config.reserved_trailer(8));
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().deref_mut().fill(i as u8);
+        }
+
+        // The page stays decoded in memory after it is written out.
+        let ph = bpm.get_page(&PageId::new(PAGES - 1)).unwrap();
+        let mut guard = ph.write().await.unwrap();
+        guard.flush().await.unwrap();
+        assert!(guard.iter().all(|&b| b == (PAGES - 1) as u8));
+        drop(guard);
+
+        // The page is encoded on persistent storage.
+        let mut raw = vec![0; bpm.page_size()];
+        storage
+            .read_at(PageId::new(PAGES - 1), 0, &mut raw)
+            .await
+            .unwrap();
+        assert!(raw[..bpm.user_region().len()]
+            .iter()
+            .all(|&b| b == !((PAGES - 1) as u8)));
+
+        // Evicted pages are decoded when they are read back in.
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(
+                ph.read().await.unwrap().iter().all(|&b| b == i as u8),
+                "Page {i} has the wrong data"
+            );
+        }
+
+        // Pages that were never written are not decoded.
+        let ph = bpm.get_page(&PageId::new(PAGES)).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 0));
+    });
+}
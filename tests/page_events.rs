@@ -0,0 +1,110 @@
+use async_bpm::{
+    page::PageId, BufferPoolManager, BufferPoolManagerConfig, PageEventFuture, PageEventListener,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The number of frames in the buffer pool.
+const FRAMES: usize = 64;
+
+/// The number of pages to read, which is more than the number of frames so that pages must be
+/// evicted.
+const PAGES: u64 = 128;
+
+/// An event that the listener was notified of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Event {
+    Load(PageId),
+    Evict(PageId),
+    Flush(PageId, u64),
+}
+
+/// A listener that records every event, in order.
+#[derive(Debug, Default)]
+struct RecordingListener {
+    events: Mutex<Vec<Event>>,
+}
+
+impl RecordingListener {
+    /// Takes every event that was recorded so far.
+    fn take(&self) -> Vec<Event> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+impl PageEventListener for RecordingListener {
+    fn on_load(&self, pid: PageId) -> PageEventFuture<'_> {
+        self.events.lock().unwrap().push(Event::Load(pid));
+        Box::pin(std::future::ready(()))
+    }
+
+    fn on_evict(&self, pid: PageId) -> PageEventFuture<'_> {
+        self.events.lock().unwrap().push(Event::Evict(pid));
+        Box::pin(std::future::ready(()))
+    }
+
+    fn on_flush(&self, pid: PageId, lsn: u64) -> PageEventFuture<'_> {
+        Box::pin(async move {
+            // The page was write-locked when it was flushed, but the listener runs outside of the
+            // lock, so it can read the page.
+            let ph = BufferPoolManager::get().get_page(&pid).unwrap();
+            assert_eq!(ph.read().await.unwrap()[0], lsn as u8);
+
+            self.events.lock().unwrap().push(Event::Flush(pid, lsn));
+        })
+    }
+}
+
+#[test]
+#[ignore]
+fn test_page_events() {
+    let listener = Arc::new(RecordingListener::default());
+
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 256).page_event_listener(listener.clone()),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let pid = PageId::new(0);
+        let ph = bpm.get_page(&pid).unwrap();
+        {
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(7);
+            guard.set_lsn(7);
+            guard.flush().await.unwrap();
+        }
+
+        // Events are delivered once the thread yields.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(listener.take(), [Event::Load(pid), Event::Flush(pid, 7)]);
+
+        for i in 1..=PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Every page was loaded, and every evicted page was loaded before it was evicted. Clean
+        // pages are never flushed.
+        let events = listener.take();
+        let loads = events
+            .iter()
+            .filter(|event| matches!(event, Event::Load(_)))
+            .count();
+        assert_eq!(loads, PAGES as usize);
+
+        let mut evictions = 0;
+        for (i, event) in events.iter().enumerate() {
+            match *event {
+                Event::Evict(pid) => {
+                    assert!(pid == PageId::new(0) || events[..i].contains(&Event::Load(pid)));
+                    evictions += 1;
+                }
+                Event::Flush(..) => panic!("A clean page was flushed"),
+                Event::Load(_) => {}
+            }
+        }
+        assert!(evictions >= PAGES as usize + 1 - FRAMES);
+    });
+}
@@ -0,0 +1,36 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, PageHashing};
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted and looked up again.
+const PAGES: u64 = 192;
+
+/// Writes and reads back every page with the given page table hash function.
+fn round_trip(hashing: PageHashing) {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).page_hashing(hashing),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+
+        bpm.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+#[ignore]
+fn test_page_hashing() {
+    round_trip(PageHashing::Fx);
+
+    // Even a terrible hash function must still find every page.
+    round_trip(PageHashing::Custom(|pid| pid.as_u64() % 3));
+}
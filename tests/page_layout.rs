@@ -0,0 +1,61 @@
+use async_bpm::page::{PageId, PAGE_SIZE};
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+
+/// The database file for this test.
+const PATH: &str = "page_layout.db";
+
+/// The number of bytes reserved at the start of every page.
+const HEADER: usize = 16;
+
+/// The number of bytes reserved at the end of every page, before the checksum.
+const TRAILER: usize = 8;
+
+#[test]
+#[ignore]
+fn test_page_layout() {
+    let _ = std::fs::remove_file("page_layout.quarantine");
+    let file = std::fs::File::create(PATH).unwrap();
+    file.set_len(128 * PAGE_SIZE as u64).unwrap();
+    drop(file);
+
+    let config = BufferPoolManagerConfig::new(64, 128)
+        .checksums(true)
+        .reserved_header(HEADER)
+        .reserved_trailer(TRAILER)
+        .paths([PATH]);
+    assert_eq!(config.user_region(), HEADER..PAGE_SIZE - TRAILER - 4);
+
+    BufferPoolManager::initialize_with_config(config);
+    let bpm = BufferPoolManager::get();
+    assert_eq!(bpm.user_region(), HEADER..PAGE_SIZE - TRAILER - 4);
+    assert_eq!(bpm.usable_page_size(), PAGE_SIZE - HEADER - TRAILER - 4);
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        let mut guard = ph.write().await.unwrap();
+
+        // The guard only exposes the user region, so filling it leaves the reserved bytes alone.
+        assert_eq!(guard.len(), bpm.usable_page_size());
+        guard.fill(b'U');
+        guard.raw_mut()[..HEADER].copy_from_slice(b"page header 0001");
+        guard.flush().await.unwrap();
+        drop(guard);
+
+        let guard = ph.read().await.unwrap();
+        assert_eq!(&guard.raw()[..HEADER], b"page header 0001");
+        assert_eq!(&guard.raw()[bpm.user_region()], &*guard);
+        assert!(guard.raw()[bpm.user_region().end..][..TRAILER]
+            .iter()
+            .all(|&b| b == 0));
+        drop(guard);
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // The reserved bytes are part of the page on disk.
+    let file = std::fs::read(PATH).unwrap();
+    assert_eq!(&file[..HEADER], b"page header 0001");
+    assert!(file[HEADER..PAGE_SIZE - TRAILER - 4]
+        .iter()
+        .all(|&b| b == b'U'));
+}
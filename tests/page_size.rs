@@ -0,0 +1,76 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::ops::DerefMut;
+
+/// The database file for this test.
+const PATH: &str = "page_size.db";
+
+/// The page size for this test, which is twice the default.
+const PAGE_SIZE: usize = 8192;
+
+/// The number of pages that the database file can hold.
+const CAPACITY: usize = 256;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted and read back in.
+const PAGES: u64 = 128;
+
+/// Fills a page so that its second half differs from its first half, which catches pages that are
+/// only partially written or read.
+fn fill(page: &mut [u8], i: u64) {
+    let (first, second) = page.split_at_mut(PAGE_SIZE / 2);
+    first.fill(i as u8);
+    second.fill(!(i as u8));
+}
+
+/// Checks that a page was filled by [`fill`].
+fn is_filled(page: &[u8], i: u64) -> bool {
+    let (first, second) = page.split_at(PAGE_SIZE / 2);
+    first.iter().all(|&b| b == i as u8) && second.iter().all(|&b| b == !(i as u8))
+}
+
+#[test]
+#[ignore]
+fn test_page_size() {
+    let file = std::fs::File::create(PATH).unwrap();
+    file.set_len((CAPACITY * PAGE_SIZE) as u64).unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, CAPACITY)
+            .page_size(PAGE_SIZE)
+            .paths([PATH]),
+    );
+    let bpm = BufferPoolManager::get();
+    assert_eq!(bpm.page_size(), PAGE_SIZE);
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let mut guard = ph.write().await.unwrap();
+            assert_eq!(guard.len(), PAGE_SIZE);
+            fill(guard.deref_mut(), i);
+            guard.flush().await.unwrap();
+        }
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let guard = ph.read().await.unwrap();
+            assert_eq!(guard.len(), PAGE_SIZE);
+            assert!(is_filled(&guard, i), "Page {i} has the wrong data");
+        }
+    });
+
+    // Every page takes up a whole 8 KiB slot in the database file.
+    let data = std::fs::read(PATH).unwrap();
+    for i in 0..PAGES {
+        let offset = i as usize * PAGE_SIZE;
+        assert!(
+            is_filled(&data[offset..offset + PAGE_SIZE], i),
+            "Page {i} is not at offset {offset}"
+        );
+    }
+
+    std::fs::remove_file(PATH).unwrap();
+}
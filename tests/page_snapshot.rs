@@ -0,0 +1,39 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+
+/// The number of pages to scan, which is more than fit in memory.
+const PAGES: u64 = 192;
+
+#[test]
+#[ignore]
+fn test_page_snapshot() {
+    BufferPoolManager::initialize_with_config(BufferPoolManagerConfig::new(64, 256));
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        ph.write().await.unwrap().fill(1);
+
+        // Taking a snapshot releases the guard, so the page can be written to again.
+        let snapshot = ph.read().await.unwrap().into_snapshot();
+        assert_eq!(snapshot.pid(), ph.pid());
+        assert_eq!(ph.pin_count(), 0);
+        ph.write().await.unwrap().fill(2);
+
+        // The page can also be evicted while the snapshot is alive.
+        let before = bpm.stats().evictions;
+        for i in 1..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(0);
+        }
+        assert!(bpm.stats().evictions > before);
+
+        assert_eq!(snapshot.len(), bpm.user_region().len());
+        assert!(snapshot.iter().all(|&b| b == 1));
+        assert!(snapshot
+            .as_slice_of::<u64>()
+            .unwrap()
+            .iter()
+            .all(|&x| x == 0x0101_0101_0101_0101));
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 2));
+    });
+}
@@ -0,0 +1,44 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+
+/// The number of pages to write, which is more than the number of frames so that pages must be
+/// evicted around the pinned page.
+const PAGES: u64 = 256;
+
+#[test]
+#[ignore]
+fn test_pin_count() {
+    BufferPoolManager::initialize(64, 512);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let pinned = bpm.get_page(&PageId::new(0)).unwrap();
+        assert_eq!(pinned.pin_count(), 0);
+
+        pinned.write().await.unwrap().fill(0xAB);
+        assert_eq!(pinned.pin_count(), 0);
+
+        let first = pinned.read().await.unwrap();
+        let second = pinned.read().await.unwrap();
+        assert_eq!(pinned.pin_count(), 2);
+        drop(second);
+        assert_eq!(pinned.pin_count(), 1);
+
+        // Churn through enough pages to evict every unpinned page several times over.
+        for i in 1..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+            assert_eq!(ph.pin_count(), 0);
+        }
+
+        // The pinned page was never evicted, so its data is still in memory.
+        assert!(first.iter().all(|&b| b == 0xAB));
+        assert_eq!(bpm.stats().page_reads, PAGES);
+        drop(first);
+        assert_eq!(pinned.pin_count(), 0);
+
+        for i in 1..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+    });
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "test-util")]
+
+use async_bpm::error::BpmError;
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, PoisonPolicy};
+use std::ops::DerefMut;
+use std::thread;
+
+/// The number of pages that are written before the eviction state lock is poisoned.
+const PAGES: u64 = 8;
+
+/// The number of other pages that are read after the lock is poisoned, which is more than the
+/// number of frames so that pages must be evicted.
+const OTHER_PAGES: u64 = 128;
+
+/// Initializes the buffer pool manager with the given policy, writes [`PAGES`] pages, and poisons
+/// the eviction state lock of every frame group.
+fn initialize_poisoned(policy: PoisonPolicy) {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).poison_policy(policy),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().deref_mut().fill(i as u8 + 1);
+        }
+    });
+
+    bpm.poison_eviction_states();
+}
+
+#[test]
+#[ignore]
+fn test_poison() {
+    // Recovering rebuilds the eviction states, and the buffer pool keeps working as usual.
+    initialize_poisoned(PoisonPolicy::Recover);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8 + 1));
+        }
+
+        for i in PAGES..PAGES + OTHER_PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+        assert!(bpm.stats().evictions > 0);
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // Propagating the poison fails every access that needs the latch, since it stays poisoned.
+    initialize_poisoned(PoisonPolicy::Error);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        for _ in 0..2 {
+            let Err(BpmError::Poisoned(e)) = ph.read().await else {
+                panic!("Read a page through a poisoned latch");
+            };
+            assert_eq!(e.latch(), "EvictionState");
+        }
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // Panicking brings down the thread that observed the poison.
+    initialize_poisoned(PoisonPolicy::Panic);
+    let bpm = BufferPoolManager::get();
+
+    let reader = thread::spawn(move || {
+        BufferPoolManager::start_thread(async move {
+            let ph = bpm.get_page(&PageId::new(0)).unwrap();
+            drop(ph.read().await);
+        });
+    });
+    assert!(reader.join().is_err());
+}
@@ -0,0 +1,35 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, GroupSelection};
+
+/// The number of frames, which make up four frame groups.
+const FRAMES: usize = 256;
+
+/// The number of pages to load, which fit in memory with room to spare.
+const PAGES: u64 = 192;
+
+#[test]
+#[ignore]
+fn test_power_of_two_choices() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 1024)
+            .group_selection(GroupSelection::PowerOfTwoChoices),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        // Frame groups with more free frames are preferred, so no frame group runs out of free
+        // frames while the others still have plenty.
+        let stats = bpm.stats();
+        assert_eq!(stats.evictions, 0);
+        assert_eq!(stats.free_frame_waits, 0);
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+    });
+}
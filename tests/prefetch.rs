@@ -0,0 +1,37 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+use std::time::Duration;
+
+/// The first page to prefetch.
+const START: u64 = 100;
+
+/// The number of pages to prefetch.
+const COUNT: usize = 32;
+
+#[test]
+#[ignore]
+fn test_prefetch_range() {
+    BufferPoolManager::initialize(128, 512);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let scheduled = bpm.prefetch_range(PageId::new(START), COUNT).unwrap();
+        assert_eq!(scheduled, COUNT);
+
+        // Give the reads time to complete.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Every page is already in memory, so prefetching again does nothing.
+        assert_eq!(bpm.prefetch_range(PageId::new(START), COUNT).unwrap(), 0);
+
+        // Reading the pages hits memory.
+        let before = bpm.stats();
+        for i in 0..COUNT as u64 {
+            let ph = bpm.try_get_page(&PageId::new(START + i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+
+        let delta = bpm.stats().since(&before);
+        assert_eq!(delta.read_accesses, COUNT as u64);
+        assert_eq!(delta.page_reads, 0);
+    });
+}
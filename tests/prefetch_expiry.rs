@@ -0,0 +1,59 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::time::Duration;
+
+/// How long prefetched pages are protected from eviction.
+const EXPIRY: Duration = Duration::from_millis(500);
+
+/// The first page to prefetch.
+const START: u64 = 100;
+
+/// The number of pages to prefetch.
+const COUNT: u64 = 16;
+
+/// Reads `count` pages starting at `start`, which evicts pages once the pool is full.
+async fn scan(bpm: &BufferPoolManager, start: u64, count: u64) {
+    for pid in start..start + count {
+        let ph = bpm.get_page(&PageId::new(pid)).unwrap();
+        drop(ph.read().await.unwrap());
+    }
+}
+
+#[test]
+#[ignore]
+fn test_prefetch_expiry() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 1024).prefetch_expiry(EXPIRY),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        assert_eq!(
+            bpm.prefetch_range(PageId::new(START), COUNT as usize)
+                .unwrap(),
+            COUNT as usize
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // Prefetched pages survive a scan that evicts every other page until they expire.
+        scan(bpm, 200, 128).await;
+
+        let before = bpm.stats();
+        scan(bpm, START, COUNT / 2).await;
+        let delta = bpm.stats().since(&before);
+        assert_eq!(delta.page_reads, 0);
+        assert_eq!(delta.speculative_hits, COUNT / 2);
+
+        // Once they expire, the prefetched pages that were never accessed are evicted first.
+        tokio::time::sleep(EXPIRY).await;
+        scan(bpm, 400, 64).await;
+
+        let stats = bpm.stats();
+        assert_eq!(stats.speculative_hits, COUNT / 2);
+        assert_eq!(stats.wasted_prefetches, COUNT / 2);
+        assert_eq!(stats.prefetch_accuracy(), Some(0.5));
+
+        let before = bpm.stats();
+        scan(bpm, START + COUNT / 2, COUNT / 2).await;
+        assert_eq!(bpm.stats().since(&before).page_reads, COUNT / 2);
+    });
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "test-util")]
+
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+
+/// The number of frames in the buffer pool.
+const FRAMES: usize = 64;
+
+/// The number of pages that are written, which is more than the number of frames.
+const PAGES: u64 = 128;
+
+#[test]
+#[ignore]
+fn test_preload() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 256).preload_fraction(0.5),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        // Only the most recently written pages are still resident.
+        let working_set = bpm.resident_pages().unwrap();
+        assert!(!working_set.is_empty() && working_set.len() <= FRAMES);
+        assert!(working_set
+            .windows(2)
+            .all(|w| w[0].as_u64() < w[1].as_u64()));
+        assert!(working_set.contains(&PageId::new(PAGES - 1)));
+
+        // Empty the pool, as if the buffer pool had been restarted.
+        bpm.flush_all().await.unwrap();
+        bpm.drop_clean_frames().await.unwrap();
+        assert!(bpm.resident_pages().unwrap().is_empty());
+
+        // Preloading stops once half of the frames are occupied, and skips duplicates.
+        let pids = working_set.iter().chain(&working_set).copied();
+        let loaded = bpm.preload(pids).await.unwrap();
+        assert_eq!(loaded, working_set.len().min(FRAMES / 2));
+
+        let resident = bpm.resident_pages().unwrap();
+        assert_eq!(resident.len(), loaded);
+        assert!(resident.iter().all(|pid| working_set.contains(pid)));
+
+        // Everything that was preloaded hits memory.
+        let before = bpm.stats();
+        for pid in &resident {
+            let ph = bpm.get_page(pid).unwrap();
+            let guard = ph.read().await.unwrap();
+            assert!(guard.iter().all(|&byte| byte == pid.as_u64() as u8));
+        }
+        assert_eq!(bpm.stats().misses, before.misses);
+
+        // Once the limit is reached, nothing else is preloaded.
+        assert_eq!(bpm.preload((0..PAGES).map(PageId::new)).await.unwrap(), 0);
+    });
+}
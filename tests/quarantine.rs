@@ -0,0 +1,122 @@
+use async_bpm::error::BpmError;
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::io::ErrorKind;
+use std::os::unix::fs::FileExt;
+
+/// The database file for this test.
+const PATH: &str = "quarantine.db";
+
+/// The quarantine list of the database file.
+const QUARANTINE: &str = "quarantine.quarantine";
+
+/// The page that gets corrupted on disk.
+const CORRUPTED: u64 = 3;
+
+fn config() -> BufferPoolManagerConfig {
+    BufferPoolManagerConfig::new(64, 128)
+        .checksums(true)
+        .paths([PATH])
+}
+
+/// Reads the corrupted page and returns the error that the read failed with.
+async fn read_corrupted(bpm: &BufferPoolManager) -> BpmError {
+    let ph = bpm.get_page(&PageId::new(CORRUPTED)).unwrap();
+    let res = ph.read().await.map(drop);
+    res.expect_err("Read a corrupted page without an error")
+}
+
+#[test]
+#[ignore]
+fn test_quarantine() {
+    let _ = std::fs::remove_file(QUARANTINE);
+    let file = std::fs::File::create(PATH).unwrap();
+    file.set_len(128 * 4096).unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+    let page_size = bpm.page_size();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(CORRUPTED)).unwrap();
+        let mut guard = ph.write().await.unwrap();
+        guard.fill(b'Q');
+        guard.flush().await.unwrap();
+        drop(guard);
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // Corrupt the page on disk.
+    let file = std::fs::OpenOptions::new().write(true).open(PATH).unwrap();
+    file.write_all_at(b"X", CORRUPTED * page_size as u64 + 7)
+        .unwrap();
+    drop(file);
+
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        assert!(bpm.quarantined_pages().is_empty());
+
+        // The first read detects the corruption, and every later read fails right away.
+        let error = read_corrupted(bpm).await;
+        assert!(matches!(error, BpmError::ChecksumMismatch(_)));
+        assert_eq!(bpm.quarantined_pages(), [PageId::new(CORRUPTED)]);
+
+        let reads = async_bpm::IO_OPERATIONS.load(std::sync::atomic::Ordering::Relaxed);
+        let error = read_corrupted(bpm).await;
+        let BpmError::CorruptPage(corrupt) = error else {
+            panic!("Expected a quarantined page, got {error}");
+        };
+        assert_eq!(corrupt.pid(), PageId::new(CORRUPTED));
+        assert_eq!(
+            async_bpm::IO_OPERATIONS.load(std::sync::atomic::Ordering::Relaxed),
+            reads
+        );
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // The quarantine survives a restart, until the page is repaired.
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        assert_eq!(bpm.quarantined_pages(), [PageId::new(CORRUPTED)]);
+        let error = read_corrupted(bpm).await;
+        assert!(matches!(error, BpmError::CorruptPage(_)));
+
+        // Only whole pages can be written over quarantined pages.
+        let pid = PageId::new(CORRUPTED);
+        let error = bpm.overwrite_corrupt(&pid, b"short").await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        let image = vec![b'R'; bpm.usable_page_size()];
+        bpm.overwrite_corrupt(&pid, &image).await.unwrap();
+        assert!(bpm.quarantined_pages().is_empty());
+
+        let ph = bpm.get_page(&pid).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == b'R'));
+
+        // Pages that are not quarantined cannot be overwritten.
+        let error = bpm.overwrite_corrupt(&pid, &image).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // The repaired page reads back after a restart.
+    BufferPoolManager::initialize_with_config(config());
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(CORRUPTED)).unwrap();
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == b'R'));
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    std::fs::remove_file(PATH).unwrap();
+    std::fs::remove_file(QUARANTINE).unwrap();
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "test-util")]
+
+use async_bpm::{page::PageId, BufferPoolManager};
+
+/// The number of pages to load before dropping the clean ones.
+const PAGES: u64 = 32;
+
+#[test]
+#[ignore]
+fn test_quiesce() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let handles: Vec<_> = (0..PAGES)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+
+        for (i, ph) in handles.iter().enumerate() {
+            ph.write().await.unwrap().fill(i as u8);
+        }
+        bpm.flush_all().await.unwrap();
+
+        let evictor = BufferPoolManager::spawn_evictor();
+        tokio::task::yield_now().await;
+        assert_eq!(bpm.health().await.daemons_alive, 1);
+
+        // Quiescing stops the evictor and leaves no I/O in flight.
+        bpm.quiesce().await;
+        evictor.await.unwrap();
+        let report = bpm.health().await;
+        assert_eq!(report.daemons_alive, 0);
+        assert_eq!(report.in_flight_io, 0);
+
+        // Only clean pages that are not in use are dropped.
+        handles[0].write().await.unwrap().fill(0xFF);
+        let pinned = handles[1].read().await.unwrap();
+        assert_eq!(bpm.drop_clean_frames().await.unwrap(), PAGES as usize - 2);
+        drop(pinned);
+
+        // Every dropped page is read back from persistent storage with its data intact.
+        let before = bpm.stats();
+        for (i, ph) in handles.iter().enumerate().skip(1) {
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+        assert_eq!(bpm.stats().since(&before).misses, PAGES - 2);
+        assert!(handles[0].read().await.unwrap().iter().all(|&b| b == 0xFF));
+
+        // Daemons can be spawned again for the next phase.
+        bpm.resume_daemons();
+        let evictor = BufferPoolManager::spawn_evictor();
+        tokio::task::yield_now().await;
+        assert_eq!(bpm.health().await.daemons_alive, 1);
+
+        bpm.stop_daemons();
+        evictor.await.unwrap();
+    });
+}
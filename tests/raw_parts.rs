@@ -0,0 +1,49 @@
+use async_bpm::page::{PageId, WritePageGuard, PAGE_SIZE};
+use async_bpm::{BufferPoolManager, IO_OPERATIONS};
+use std::sync::atomic::Ordering;
+
+/// The number of other pages that are read to evict the page, which is more than the number of
+/// frames.
+const OTHER_PAGES: u64 = 128;
+
+#[test]
+#[ignore]
+fn test_raw_parts() {
+    BufferPoolManager::initialize(64, 256);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let pid = PageId::new(0);
+        let ph = bpm.get_page(&pid).unwrap();
+
+        // Write to the page through the raw data pointer, as an embedder would.
+        let guard = ph.write().await.unwrap();
+        let (data, len, raw) = guard.into_raw_parts();
+        assert_eq!(len, PAGE_SIZE);
+        assert_eq!(bpm.outstanding_raw_guards(), [pid]);
+
+        // Safety: The raw guard has not been returned yet, so the data pointer is still valid.
+        unsafe { std::slice::from_raw_parts_mut(data, len) }.fill(42);
+
+        // Safety: The raw parts were just returned by `into_raw_parts`.
+        let mut guard = unsafe { WritePageGuard::from_raw_parts(data, len, raw) };
+        assert!(bpm.outstanding_raw_guards().is_empty());
+        guard.flush().await.unwrap();
+        drop(guard);
+
+        // Evict the page, so that it must be read back from persistent storage.
+        for i in 1..=OTHER_PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+        let io_before = IO_OPERATIONS.load(Ordering::Relaxed);
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 42));
+        assert!(IO_OPERATIONS.load(Ordering::Relaxed) > io_before);
+
+        // A raw guard that is never returned is reported as a leak.
+        let leaked = PageId::new(1);
+        let ph = bpm.get_page(&leaked).unwrap();
+        let _ = ph.write().await.unwrap().into_raw_parts();
+        assert_eq!(bpm.outstanding_raw_guards(), [leaked]);
+    });
+}
@@ -0,0 +1,49 @@
+use async_bpm::{page::PageId, BufferPoolManager, PageBuf};
+
+/// The number of pages to write, which is more than the number of frames so that most of them are
+/// evicted.
+const PAGES: u64 = 128;
+
+/// The number of pages that every range read covers.
+const RANGE: usize = 16;
+
+#[test]
+#[ignore]
+fn test_read_bypass() {
+    BufferPoolManager::initialize(64, 256);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        let before = bpm.stats();
+
+        // Every page reads back the same, whether it is still resident (and dirty) or not.
+        let mut buf = PageBuf::new();
+        assert!(buf.is_empty());
+        for i in 0..PAGES {
+            bpm.read_bypass(&PageId::new(i), &mut buf).await.unwrap();
+            assert!(buf.iter().all(|&byte| byte == i as u8));
+        }
+
+        let mut bufs: Vec<PageBuf> = (0..RANGE).map(|_| PageBuf::new()).collect();
+        for start in (0..PAGES).step_by(RANGE) {
+            bpm.read_range_bypass(&PageId::new(start), &mut bufs)
+                .await
+                .unwrap();
+            for (i, buf) in (start..).zip(&bufs) {
+                assert!(buf.iter().all(|&byte| byte == i as u8));
+            }
+        }
+
+        // None of the reads went through the buffer pool.
+        let after = bpm.stats();
+        assert_eq!(after.read_accesses, before.read_accesses);
+        assert_eq!(after.misses, before.misses);
+        assert_eq!(after.evictions, before.evictions);
+        assert!(after.page_reads > before.page_reads);
+    });
+}
@@ -0,0 +1,49 @@
+#![cfg(all(feature = "test-util", feature = "experimental"))]
+
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, GroupSelection};
+use std::time::Duration;
+
+/// The number of buffer frames, which make up two frame groups.
+const FRAMES: usize = 128;
+
+#[test]
+#[ignore]
+fn test_rebalance() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 4 * FRAMES)
+            .group_selection(GroupSelection::RoundRobin)
+            .free_frame_timeout(Duration::from_millis(100)),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let handles: Vec<_> = (0..FRAMES as u64)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+
+        // Frame groups are chosen in turn, so the even pages fill the first group and the odd pages
+        // fill the second group.
+        let mut guards = Vec::with_capacity(FRAMES);
+        for ph in &handles {
+            guards.push(ph.read().await.unwrap());
+        }
+
+        // Keep every page of the first group pinned, and free every frame of the second group.
+        let pinned: Vec<_> = guards.into_iter().step_by(2).collect();
+        assert_eq!(bpm.drop_clean_frames().await.unwrap(), FRAMES / 2);
+
+        let rebalancer = BufferPoolManager::spawn_rebalancer(Duration::from_millis(1));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(bpm.stats().rebalanced_frames > 0);
+
+        // The next load chooses the first group, which can only serve it with a lent frame.
+        let before = bpm.stats();
+        let ph = bpm.get_page(&PageId::new(FRAMES as u64)).unwrap();
+        drop(ph.read().await.unwrap());
+        assert_eq!(bpm.stats().since(&before).free_frame_waits, 0);
+
+        bpm.stop_daemons();
+        rebalancer.await.unwrap();
+        drop(pinned);
+    });
+}
@@ -0,0 +1,82 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, IO_OPERATIONS};
+use async_bpm::{ReplacementCandidate, ReplacementPolicy, Replacer};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of buffer frames.
+const FRAMES: usize = 64;
+
+/// The pages that the custom replacer never evicts.
+const PINNED: u64 = 8;
+
+/// The number of cooling passes that the custom replacer has run.
+static PASSES: AtomicUsize = AtomicUsize::new(0);
+
+/// A replacer that evicts every page except for the first few pages.
+#[derive(Debug)]
+struct KeepFirstPages;
+
+impl Replacer for KeepFirstPages {
+    fn record_access(&mut self, _index: usize, _pid: PageId) {}
+
+    fn record_eviction(&mut self, _index: usize) {}
+
+    fn victims(&mut self, candidates: &[ReplacementCandidate]) -> Vec<usize> {
+        PASSES.fetch_add(1, Ordering::Relaxed);
+        candidates
+            .iter()
+            .filter(|candidate| candidate.pid.as_u64() >= PINNED)
+            .map(|candidate| candidate.index)
+            .collect()
+    }
+}
+
+/// Reads the given pages, returning the number of pages that had to be read from persistent
+/// storage.
+async fn read_pages(bpm: &BufferPoolManager, pids: impl Iterator<Item = u64>) -> usize {
+    let before = IO_OPERATIONS.load(Ordering::Acquire);
+    for i in pids {
+        let ph = bpm.get_page(&PageId::new(i)).unwrap();
+        drop(ph.read().await.unwrap());
+    }
+    IO_OPERATIONS.load(Ordering::Acquire) - before
+}
+
+#[test]
+#[ignore]
+fn test_replacement_policies() {
+    // A custom policy decides which pages are evicted.
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 4 * FRAMES)
+            .replacement_policy(ReplacementPolicy::Custom(|| Box::new(KeepFirstPages))),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        read_pages(bpm, 0..PINNED).await;
+        read_pages(bpm, PINNED..3 * FRAMES as u64).await;
+
+        assert!(PASSES.load(Ordering::Relaxed) > 0);
+        assert_eq!(read_pages(bpm, 0..PINNED).await, 0);
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    // FIFO evicts the pages that were loaded first, even if they were accessed since.
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 4 * FRAMES)
+            .replacement_policy(ReplacementPolicy::Fifo),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let frames = FRAMES as u64;
+        read_pages(bpm, 0..frames).await;
+        read_pages(bpm, 0..PINNED).await;
+        read_pages(bpm, frames..frames + PINNED / 2).await;
+
+        assert_eq!(read_pages(bpm, PINNED..frames).await, 0);
+        assert_eq!(read_pages(bpm, 0..1).await, 1);
+
+        bpm.shutdown().await.unwrap();
+    });
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "experimental")]
+
+use async_bpm::{page::PageId, BufferPoolManager};
+
+/// The number of times the page is rewritten.
+const WRITES: usize = 250;
+
+/// The number of threads that read the page.
+const READERS: usize = 4;
+
+/// The page that is replicated.
+const PID: u64 = 0;
+
+#[test]
+#[ignore]
+fn test_replication() {
+    BufferPoolManager::initialize(256, 512);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(PID)).unwrap();
+        ph.write().await.unwrap().fill(0);
+
+        // A page that is not replicated is read like any other page.
+        assert!(!ph.is_replicated());
+        assert!(ph.read_replica().await.unwrap().iter().all(|&b| b == 0));
+
+        ph.replicate();
+        ph.replicate();
+        assert!(ph.is_replicated());
+
+        // A writer invalidates the replicas, so readers always observe the latest data.
+        assert!(ph.read_replica().await.unwrap().iter().all(|&b| b == 0));
+        ph.write().await.unwrap().fill(1);
+        assert!(ph.read_replica().await.unwrap().iter().all(|&b| b == 1));
+
+        // A writer cannot invalidate a replica that is being read.
+        let guard = ph.read_replica().await.unwrap();
+        assert!(ph.try_write().await.unwrap().is_none());
+        drop(guard);
+        ph.try_write().await.unwrap().unwrap().fill(2);
+        assert!(ph.read_replica().await.unwrap().iter().all(|&b| b == 2));
+    });
+
+    // Readers on many threads must never observe a partially written page, while a writer on
+    // another thread keeps rewriting it.
+    std::thread::scope(|s| {
+        s.spawn(move || {
+            BufferPoolManager::start_thread(async move {
+                let ph = bpm.get_page(&PageId::new(PID)).unwrap();
+                for i in 0..WRITES {
+                    ph.write().await.unwrap().fill(i as u8);
+                    tokio::task::yield_now().await;
+                }
+                ph.write().await.unwrap().fill(u8::MAX);
+            });
+        });
+
+        for _ in 0..READERS {
+            s.spawn(move || {
+                BufferPoolManager::start_thread(async move {
+                    let ph = bpm.get_page(&PageId::new(PID)).unwrap();
+                    loop {
+                        let guard = ph.read_replica().await.unwrap();
+                        assert!(guard.iter().all(|&b| b == guard[0]), "Observed a torn page");
+                        if guard[0] == u8::MAX {
+                            break;
+                        }
+                        drop(guard);
+                        tokio::task::yield_now().await;
+                    }
+                });
+            });
+        }
+    });
+}
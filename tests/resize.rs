@@ -0,0 +1,55 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+use std::io::ErrorKind;
+
+/// The number of frames the pool starts out with.
+const FRAMES: usize = 64;
+
+/// The number of frames the pool can grow to.
+const MAX_FRAMES: usize = 256;
+
+/// The number of pages that are written while the pool is large.
+const PAGES: u64 = 160;
+
+#[test]
+#[ignore]
+fn test_resize() {
+    let config = BufferPoolManagerConfig::new(FRAMES, 1024).max_frames(MAX_FRAMES);
+    BufferPoolManager::initialize_with_config(config);
+    let bpm = BufferPoolManager::get();
+
+    assert_eq!(bpm.num_frames(), FRAMES);
+    assert_eq!(bpm.max_frames(), MAX_FRAMES);
+    assert_eq!(bpm.stats().frame_groups.len(), 1);
+
+    BufferPoolManager::start_thread(async move {
+        // The pool cannot be empty or grow beyond its maximum size.
+        for invalid in [0, MAX_FRAMES + 1] {
+            let err = bpm.resize(invalid).await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+
+        // Grow the pool, and dirty more pages than the original pool could hold.
+        assert_eq!(bpm.resize(MAX_FRAMES).await.unwrap(), MAX_FRAMES);
+        assert_eq!(bpm.stats().frame_groups.len(), 4);
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        // Shrinking the pool writes the pages of the retired frame groups out, and the pool only
+        // holds whole frame groups.
+        assert_eq!(bpm.resize(FRAMES + 1).await.unwrap(), 2 * FRAMES);
+        assert_eq!(bpm.resize(FRAMES).await.unwrap(), FRAMES);
+        assert_eq!(bpm.stats().frame_groups.len(), 1);
+        assert_eq!(bpm.health().await.total_frames, FRAMES);
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            assert!(ph.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+
+        // Every frame of the retired frame groups is accounted for.
+        bpm.shutdown().await.unwrap();
+    });
+}
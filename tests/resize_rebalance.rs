@@ -0,0 +1,59 @@
+#![cfg(all(feature = "test-util", feature = "experimental"))]
+
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, GroupSelection};
+use std::time::Duration;
+
+/// The number of buffer frames, which make up two frame groups.
+const FRAMES: usize = 128;
+
+#[test]
+#[ignore]
+fn test_resize_rebalance() {
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(FRAMES, 4 * FRAMES)
+            .group_selection(GroupSelection::RoundRobin)
+            .free_frame_timeout(Duration::from_millis(100)),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let handles: Vec<_> = (0..FRAMES as u64)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+
+        let mut guards = Vec::with_capacity(FRAMES);
+        for ph in &handles {
+            guards.push(ph.read().await.unwrap());
+        }
+
+        // Keep every page of the first group pinned, so that the second group lends it some of its
+        // free frames.
+        let pinned: Vec<_> = guards.into_iter().step_by(2).collect();
+        assert_eq!(bpm.drop_clean_frames().await.unwrap(), FRAMES / 2);
+
+        let rebalancer = BufferPoolManager::spawn_rebalancer(Duration::from_millis(1));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        bpm.stop_daemons();
+        rebalancer.await.unwrap();
+        assert!(bpm.stats().rebalanced_frames > 0);
+
+        // Retiring the second group takes back the frames it lent.
+        let resized = tokio::time::timeout(Duration::from_secs(5), bpm.resize(FRAMES / 2)).await;
+        assert_eq!(
+            resized.expect("Shrinking the pool never finished").unwrap(),
+            FRAMES / 2
+        );
+        assert_eq!(bpm.health().await.total_frames, FRAMES / 2);
+
+        // The first group has no free frames of its own, so no page can be loaded into a retired
+        // frame.
+        let ph = bpm.get_page(&PageId::new(FRAMES as u64)).unwrap();
+        assert!(ph.read().await.is_err());
+
+        drop(pinned);
+        assert!(ph.read().await.is_ok());
+
+        // Every frame of the retired frame group is accounted for.
+        bpm.shutdown().await.unwrap();
+    });
+}
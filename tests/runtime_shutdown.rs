@@ -0,0 +1,82 @@
+use async_bpm::page::{PageId, PAGE_SIZE};
+use async_bpm::{BufferPoolManager, InjectedLatency};
+use std::time::Duration;
+
+/// The number of pages that are flushed and read while the runtime shuts down.
+const PAGES: u64 = 8;
+
+/// Spawns tasks that dirty and flush the pages `0..PAGES` and that read the pages
+/// `PAGES..2 * PAGES`, and returns while they are still in flight so that the runtime drops them.
+async fn interrupt_io(bpm: &'static BufferPoolManager, wait: Duration) {
+    for i in 0..PAGES {
+        BufferPoolManager::spawn_local(async move {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(b'a' + i as u8);
+            guard.flush().await.unwrap();
+        });
+
+        BufferPoolManager::spawn_local(async move {
+            let ph = bpm.get_page(&PageId::new(PAGES + i)).unwrap();
+            let _guard = ph.read().await.unwrap();
+        });
+    }
+
+    tokio::time::sleep(wait).await;
+}
+
+/// Checks that the interrupted flushes gave their dirty frames back to the pages `0..PAGES`.
+async fn check_flushed_pages(bpm: &'static BufferPoolManager) {
+    for i in 0..PAGES {
+        let ph = bpm.get_page(&PageId::new(i)).unwrap();
+        let guard = ph.read().await.unwrap();
+        assert!(
+            guard.iter().all(|&b| b == b'a' + i as u8),
+            "Page {i} lost its data"
+        );
+    }
+}
+
+#[test]
+#[ignore]
+fn test_runtime_shutdown() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    // Load the pages that are flushed later, so that the flushes are the only I/O in flight.
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+        }
+    });
+
+    // Drop the runtime while every operation waits for its injected latency.
+    let slow = InjectedLatency {
+        read: Duration::from_secs(60),
+        write: Duration::from_secs(60),
+        jitter: Duration::ZERO,
+    };
+    bpm.set_injected_latency(0, slow);
+    BufferPoolManager::start_thread(interrupt_io(bpm, Duration::from_millis(50)));
+
+    bpm.set_injected_latency(0, InjectedLatency::default());
+    BufferPoolManager::start_thread(check_flushed_pages(bpm));
+
+    // Drop the runtime while the operations are submitted to the kernel.
+    BufferPoolManager::start_thread(interrupt_io(bpm, Duration::ZERO));
+
+    BufferPoolManager::start_thread(async move {
+        check_flushed_pages(bpm).await;
+
+        // Every frame of an interrupted operation was recovered.
+        bpm.shutdown().await.unwrap();
+    });
+
+    // Shutting down wrote them out.
+    let file = std::fs::read("bpm.db").unwrap();
+    for i in 0..PAGES as usize {
+        let page = &file[i * PAGE_SIZE..(i + 1) * PAGE_SIZE];
+        assert!(page.iter().all(|&b| b == b'a' + i as u8));
+    }
+}
@@ -0,0 +1,42 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+
+/// The number of pages to scan, which is more than fit in memory.
+const PAGES: u64 = 256;
+
+#[test]
+#[ignore]
+fn test_scan() {
+    BufferPoolManager::initialize(128, 512);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+        bpm.flush_all().await.unwrap();
+
+        // Every page is returned once and in order.
+        let mut scan = bpm.scan(PageId::new(0), PageId::new(PAGES));
+        let mut expected = 0;
+        while let Some(guard) = scan.next().await {
+            assert!(guard.unwrap().iter().all(|&b| b == expected as u8));
+            expected += 1;
+        }
+        assert_eq!(expected, PAGES);
+        assert_eq!(scan.remaining(), 0);
+
+        // An empty range returns nothing.
+        let mut scan = bpm.scan(PageId::new(10), PageId::new(10));
+        assert!(scan.next().await.is_none());
+
+        // Without read-ahead, every page that is not in memory is read on its own.
+        let mut scan = bpm.scan(PageId::new(0), PageId::new(PAGES)).read_ahead(0);
+        let before = bpm.stats();
+        while let Some(guard) = scan.next().await {
+            guard.unwrap();
+        }
+        let stats = bpm.stats().since(&before);
+        assert_eq!(stats.page_reads, stats.misses);
+    });
+}
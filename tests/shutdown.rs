@@ -0,0 +1,35 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+use std::ops::DerefMut;
+
+/// The number of times to initialize and shut down the buffer pool manager.
+const CYCLES: u8 = 3;
+
+#[test]
+#[ignore]
+fn test_shutdown() {
+    for cycle in 0..CYCLES {
+        BufferPoolManager::initialize(64, 128);
+        let bpm = BufferPoolManager::get();
+
+        BufferPoolManager::start_thread(async move {
+            let ph = bpm.get_page(&PageId::new(0)).unwrap();
+
+            // The page should hold whatever the previous instance wrote to it.
+            let mut guard = ph.write().await.unwrap();
+            if cycle > 0 {
+                assert!(guard.iter().all(|&b| b == cycle - 1));
+            }
+
+            // Dirty the page without flushing it, so that shutting down has to write it out.
+            guard.deref_mut().fill(cycle);
+
+            // A page cannot be torn down while it is still in use.
+            assert!(bpm.shutdown().await.is_err());
+            drop(guard);
+
+            bpm.shutdown().await.unwrap();
+        });
+
+        assert!(!BufferPoolManager::is_initialized());
+    }
+}
@@ -0,0 +1,60 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+use std::time::Duration;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted.
+const PAGES: u64 = 128;
+
+#[test]
+#[ignore]
+fn test_stats_window() {
+    BufferPoolManager::initialize(64, 256);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let before = bpm.stats();
+        let mut window = bpm.stats_window(Duration::from_millis(50));
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(i as u8);
+        }
+
+        // The first window covers all of the writes.
+        let first = window.next().await;
+        assert_eq!(first.write_accesses, PAGES);
+        assert_eq!(first.read_accesses, 0);
+        assert_eq!(first.page_reads, PAGES);
+        assert!(first.evictions >= PAGES - 64);
+        assert_eq!(first.page_writes, first.evictions);
+
+        // Every evicted page was dirty, and every eviction happened because there were no free
+        // frames left.
+        assert_eq!(first.dirty_write_backs, first.evictions);
+        assert!(first.free_frame_waits >= 1);
+        assert_eq!(first.frame_groups.len(), 1);
+        assert_eq!(first.frame_groups[0].total_frames, 64);
+        assert!(first.occupancy() > 0.0);
+
+        let ph = bpm.get_page(&PageId::new(PAGES - 1)).unwrap();
+        assert!(ph
+            .read()
+            .await
+            .unwrap()
+            .iter()
+            .all(|&b| b == (PAGES - 1) as u8));
+
+        // The second window only covers the work done after the first window ended.
+        let second = window.next().await;
+        assert_eq!(second.accesses(), 1);
+        assert_eq!(second.read_accesses, 1);
+
+        // The windows add up to the cumulative counters.
+        let total = bpm.stats().since(&before);
+        assert_eq!(total.accesses(), first.accesses() + second.accesses());
+        assert_eq!(
+            total.io_operations(),
+            first.io_operations() + second.io_operations()
+        );
+    });
+}
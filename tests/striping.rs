@@ -0,0 +1,78 @@
+use async_bpm::page::{PageId, PAGE_SIZE};
+use async_bpm::{BufferPoolManager, BufferPoolManagerConfig};
+use std::ops::DerefMut;
+
+/// The database files that pages are striped across.
+const PATHS: [&str; 3] = ["striping_0.db", "striping_1.db", "striping_2.db"];
+
+/// The number of pages that the database files can hold together.
+const CAPACITY: usize = 192;
+
+/// The number of pages to write and read back, which is more than the number of frames so that
+/// pages must be evicted and read back in.
+const PAGES: u64 = 128;
+
+#[test]
+#[ignore]
+fn test_striping() {
+    for path in PATHS {
+        let file = std::fs::File::create(path).unwrap();
+        file.set_len((CAPACITY / PATHS.len() * PAGE_SIZE) as u64)
+            .unwrap();
+    }
+
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, CAPACITY).paths(PATHS),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let mut guard = ph.write().await.unwrap();
+            guard.deref_mut().fill(i as u8);
+            guard.flush().await.unwrap();
+        }
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let guard = ph.read().await.unwrap();
+            assert!(
+                guard.iter().all(|&b| b == i as u8),
+                "Page {i} has the wrong data"
+            );
+        }
+    });
+
+    // Page `i` is stored in file `i % 3`, at index `i / 3` within that file.
+    let files: Vec<Vec<u8>> = PATHS
+        .iter()
+        .map(|path| std::fs::read(path).unwrap())
+        .collect();
+    for i in 0..PAGES as usize {
+        let file = &files[i % PATHS.len()];
+        let offset = i / PATHS.len() * PAGE_SIZE;
+        assert!(
+            file[offset..offset + PAGE_SIZE]
+                .iter()
+                .all(|&b| b == i as u8),
+            "Page {i} is not in file {}",
+            i % PATHS.len()
+        );
+    }
+
+    // Every file only holds its share of the pages, so the rest of every file is untouched.
+    let share = (PAGES as usize).div_ceil(PATHS.len()) * PAGE_SIZE;
+    for (path, file) in PATHS.iter().zip(&files) {
+        assert!(
+            file[share..].iter().all(|&b| b == 0),
+            "{path} holds more than its share of the pages"
+        );
+    }
+
+    for path in PATHS {
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,52 @@
+use async_bpm::page::{PageId, PAGE_SIZE};
+use async_bpm::{blocking, BufferPoolManager};
+
+/// The database file that the buffer pool manager uses by default.
+const DATABASE: &str = "bpm.db";
+
+/// Reads a page straight from the database file.
+fn on_disk(pid: u64) -> Vec<u8> {
+    let file = std::fs::read(DATABASE).unwrap();
+    let offset = pid as usize * PAGE_SIZE;
+    file[offset..offset + PAGE_SIZE].to_vec()
+}
+
+#[test]
+#[ignore]
+fn test_sync() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    // A page that was flushed and synced is on disk.
+    let ph = blocking::get_page_blocking(&PageId::new(3)).unwrap();
+    let mut guard = blocking::write_blocking(&ph).unwrap();
+    guard.fill(b'S');
+    blocking::flush_sync_blocking(&mut guard).unwrap();
+    drop(guard);
+    assert!(on_disk(3).iter().all(|&b| b == b'S'));
+
+    BufferPoolManager::start_thread(async move {
+        // A checkpoint made durable with `flush_all` and then a sync.
+        for i in 0..8 {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().fill(b'C' + i as u8);
+        }
+        assert_eq!(bpm.flush_all().await.unwrap(), 8);
+        bpm.sync_data().await.unwrap();
+        bpm.sync_all().await.unwrap();
+
+        // Syncing a temporary page does nothing, but still succeeds.
+        let temp = bpm.new_temp_page().unwrap();
+        let mut guard = temp.write().await.unwrap();
+        guard.fill(b'T');
+        guard.flush_sync().await.unwrap();
+        drop(guard);
+        bpm.drop_temp_page(temp).await.unwrap();
+
+        bpm.shutdown().await.unwrap();
+    });
+
+    for i in 0..8 {
+        assert!(on_disk(i).iter().all(|&b| b == b'C' + i as u8));
+    }
+}
@@ -0,0 +1,63 @@
+use async_bpm::error::BpmError;
+use async_bpm::{page::PageId, BufferPoolManager};
+use std::path::Path;
+
+/// The number of temporary pages to create, which is more than the number of frames so that
+/// temporary pages must be spilled.
+const PAGES: usize = 256;
+
+#[test]
+#[ignore]
+fn test_temp_pages() {
+    BufferPoolManager::initialize(64, 512);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        // Persist a known pattern to the first database page.
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        ph.write().await.unwrap().fill(0xFF);
+        ph.write().await.unwrap().flush().await.unwrap();
+
+        let mut temps = Vec::with_capacity(PAGES);
+        for i in 0..PAGES {
+            let temp = bpm.new_temp_page().unwrap();
+            assert!(temp.pid().is_temp());
+
+            let mut guard = temp.write().await.unwrap();
+            assert!(guard.iter().all(|&b| b == 0));
+            guard.fill(i as u8);
+            drop(guard);
+
+            temps.push(temp);
+        }
+
+        // Existing temporary pages can be gotten by their ID.
+        let again = bpm.get_page(&temps[0].pid()).unwrap();
+        assert!(again.read().await.unwrap().iter().all(|&b| b == 0));
+        drop(again);
+
+        // Every temporary page survives being spilled.
+        for (i, temp) in temps.iter().enumerate() {
+            assert!(temp.read().await.unwrap().iter().all(|&b| b == i as u8));
+        }
+        assert!(Path::new("bpm.spill").exists());
+
+        // Dropping a temporary page removes it from the buffer pool.
+        let dropped = temps.pop().unwrap();
+        let pid = dropped.pid();
+        bpm.drop_temp_page(dropped).await.unwrap();
+        assert!(matches!(bpm.get_page(&pid), Err(BpmError::PageNotFound(p)) if p == pid));
+
+        // Persistent pages cannot be dropped.
+        assert!(bpm.drop_temp_page(ph.clone()).await.is_err());
+
+        // Spilling temporary pages never touched the database files.
+        assert!(ph.read().await.unwrap().iter().all(|&b| b == 0xFF));
+
+        drop(temps);
+        drop(ph);
+        bpm.shutdown().await.unwrap();
+    });
+
+    assert!(!Path::new("bpm.spill").exists());
+}
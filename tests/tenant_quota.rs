@@ -0,0 +1,55 @@
+use async_bpm::error::{BpmError, ConfigError};
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, TenantId};
+
+/// The number of frames that the scanning tenant may keep resident.
+const QUOTA: usize = 8;
+
+/// The number of pages that the tenant without a quota reads in.
+const WORKING_SET: u64 = 32;
+
+/// The number of pages that the scanning tenant reads, which is more than the number of frames.
+const SCAN: u64 = 256;
+
+#[test]
+#[ignore]
+fn test_tenant_quota() {
+    let scanner = TenantId::new(1);
+    let index = TenantId::new(2);
+
+    // A quota must allow at least one frame.
+    let config = BufferPoolManagerConfig::new(64, 512);
+    let err = BufferPoolManager::try_initialize_with_config(config.clone().tenant_quota(index, 0))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        BpmError::Config(ConfigError::EmptyTenantQuota { tenant }) if tenant == index
+    ));
+
+    BufferPoolManager::initialize_with_config(config.tenant_quota(scanner, QUOTA));
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..WORKING_SET {
+            let ph = bpm.get_page_for(index, &PageId::new(i)).unwrap();
+            assert_eq!(ph.tenant(), Some(index));
+            ph.write().await.unwrap().fill(i as u8);
+        }
+        assert_eq!(bpm.tenant_resident_frames(index), WORKING_SET as usize);
+
+        // The scan recycles its own frames instead of evicting the other tenant's pages.
+        for i in WORKING_SET..WORKING_SET + SCAN {
+            let ph = bpm.get_page_for(scanner, &PageId::new(i)).unwrap();
+            drop(ph.read().await.unwrap());
+            assert!(bpm.tenant_resident_frames(scanner) <= QUOTA);
+        }
+        assert_eq!(bpm.tenant_resident_frames(scanner), QUOTA);
+        assert_eq!(bpm.tenant_resident_frames(index), WORKING_SET as usize);
+
+        // Pages read in without a tenant are not charged to anyone.
+        let ph = bpm.get_page(&PageId::new(WORKING_SET + SCAN)).unwrap();
+        assert_eq!(ph.tenant(), None);
+        drop(ph.read().await.unwrap());
+        assert_eq!(bpm.tenant_resident_frames(scanner), QUOTA);
+        assert_eq!(bpm.tenant_resident_frames(index), WORKING_SET as usize);
+    });
+}
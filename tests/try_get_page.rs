@@ -0,0 +1,27 @@
+use async_bpm::{page::PageId, BufferPoolManager};
+
+#[test]
+#[ignore]
+fn test_try_get_page() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let pid = PageId::new(7);
+
+        // The page has never been loaded, so there is no fast path to it.
+        assert!(bpm.try_get_page(&pid).is_none());
+
+        let ph = bpm.get_page(&pid).unwrap();
+        assert!(bpm.try_get_page(&pid).is_none());
+
+        let mut guard = ph.write().await.unwrap();
+        guard.fill(7);
+        drop(guard);
+
+        // Now that the page is in memory, the fast path hands out a handle to the same page.
+        let fast = bpm.try_get_page(&pid).unwrap();
+        let guard = fast.read().await.unwrap();
+        assert!(guard.iter().all(|&b| b == 7));
+    });
+}
@@ -0,0 +1,85 @@
+use async_bpm::{error::ViewError, page::PageId, BufferPoolManager};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+/// A fixed-layout page header, as an index might store at the start of every page.
+#[derive(Debug, PartialEq, Eq, FromBytes, IntoBytes, Immutable, KnownLayout)]
+#[repr(C)]
+struct Header {
+    lsn: u64,
+    num_keys: u32,
+    level: u16,
+    flags: u16,
+}
+
+#[test]
+#[ignore]
+fn test_typed_views() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+
+        let mut guard = ph.write().await.unwrap();
+        let page_size = guard.len();
+
+        // Fill the page through a typed slice and check that the bytes match.
+        for (i, word) in guard
+            .as_mut_slice_of::<u64>()
+            .unwrap()
+            .iter_mut()
+            .enumerate()
+        {
+            *word = i as u64;
+        }
+        assert_eq!(guard[8..16], 1u64.to_ne_bytes());
+
+        // Update a header in place.
+        let header = guard.header_at_mut::<Header>(0).unwrap();
+        header.lsn = 7;
+        header.num_keys = 3;
+        header.level = 1;
+
+        // Views that do not fit the page are rejected.
+        let error = |res: std::io::Result<&Header>| {
+            *res.unwrap_err()
+                .get_ref()
+                .unwrap()
+                .downcast_ref::<ViewError>()
+                .unwrap()
+        };
+        assert!(matches!(
+            error(guard.header_at::<Header>(page_size - 8)),
+            ViewError::OutOfBounds { .. }
+        ));
+        assert_eq!(
+            error(guard.header_at::<Header>(4)),
+            ViewError::Misaligned {
+                offset: 4,
+                align: 8
+            }
+        );
+        assert!(guard.as_slice_of::<[u8; 3]>().is_err());
+
+        guard.flush().await.unwrap();
+        drop(guard);
+
+        // The typed views see the same data through a read guard.
+        let guard = ph.read().await.unwrap();
+        assert_eq!(
+            *guard.header_at::<Header>(0).unwrap(),
+            Header {
+                lsn: 7,
+                num_keys: 3,
+                level: 1,
+                flags: 0,
+            }
+        );
+        let words = guard.as_slice_of::<u64>().unwrap();
+        assert_eq!(words.len(), page_size / 8);
+        assert!(words[2..]
+            .iter()
+            .enumerate()
+            .all(|(i, &w)| w == i as u64 + 2));
+    });
+}
@@ -0,0 +1,48 @@
+use async_bpm::{page::PageId, BufferPoolManager, UringStats};
+
+/// The number of pages to write and read back.
+const PAGES: u64 = 32;
+
+#[test]
+#[ignore]
+fn test_uring_stats() {
+    BufferPoolManager::initialize(64, 128);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        assert_eq!(bpm.uring_stats(), UringStats::default());
+
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(i as u8);
+            guard.flush().await.unwrap();
+        }
+
+        // Every page was read in and then written out.
+        let stats = bpm.uring_stats();
+        assert_eq!(stats.submissions, 2 * PAGES);
+        assert_eq!(stats.completions, 2 * PAGES);
+        assert_eq!(stats.in_flight, 0);
+        assert!(stats.max_in_flight >= 1);
+        assert!(stats.max_completion_time <= stats.completion_time);
+        assert!(stats.mean_completion_latency().is_some());
+
+        // Reads on other threads are not counted by this thread.
+        std::thread::spawn(move || {
+            BufferPoolManager::start_thread(async move {
+                let ph = bpm.get_page(&PageId::new(PAGES)).unwrap();
+                drop(ph.read().await.unwrap());
+                assert_eq!(bpm.uring_stats().submissions, 1);
+            });
+        })
+        .join()
+        .unwrap();
+        assert_eq!(bpm.uring_stats().submissions, 2 * PAGES);
+    });
+
+    // Every runtime has its own `io_uring` instance with its own counters.
+    BufferPoolManager::start_thread(async move {
+        assert_eq!(bpm.uring_stats(), UringStats::default());
+    });
+}
@@ -0,0 +1,81 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig};
+
+/// The database files for this test, which pages are striped across.
+const PATHS: [&str; 2] = ["vectored_io_0.db", "vectored_io_1.db"];
+
+/// The number of pages that are written and read in a single batch.
+const BATCH: u64 = 16;
+
+/// The number of pages to write, which is more than fit in memory.
+const PAGES: u64 = 128;
+
+#[test]
+#[ignore]
+fn test_vectored_io() {
+    for path in PATHS {
+        let file = std::fs::File::create(path).unwrap();
+        file.set_len(128 * 4096).unwrap();
+    }
+
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256)
+            .checksums(true)
+            .paths(PATHS),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for start in (0..PAGES).step_by(BATCH as usize) {
+            let handles: Vec<_> = (start..start + BATCH)
+                .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+                .collect();
+
+            let mut guards = Vec::new();
+            for ph in handles.iter().rev() {
+                let mut guard = ph.write().await.unwrap();
+                guard.fill(ph.pid().as_u64() as u8);
+                guards.push(guard);
+            }
+
+            // A contiguous range is written with a single vectored write per drive.
+            let before = bpm.uring_stats().submissions;
+            bpm.flush_pages(&mut guards).await.unwrap();
+            assert_eq!(bpm.uring_stats().submissions - before, PATHS.len() as u64);
+
+            // The guards are sorted, and every page is clean.
+            assert!(guards.windows(2).all(|w| w[0][0] < w[1][0]));
+            drop(guards);
+            assert_eq!(bpm.flush_all().await.unwrap(), 0);
+        }
+
+        // Most of the pages were evicted, so reading them back uses vectored reads.
+        let handles: Vec<_> = (0..PAGES)
+            .map(|i| bpm.get_page(&PageId::new(i)).unwrap())
+            .collect();
+        for batch in handles.chunks(BATCH as usize) {
+            let before = bpm.uring_stats().submissions;
+            let guards = bpm.read_pages(batch).await.unwrap();
+            assert!(bpm.uring_stats().submissions - before <= BATCH);
+
+            for (guard, ph) in guards.iter().zip(batch) {
+                let pid = ph.pid().as_u64();
+                assert!(guard.iter().all(|&b| b == pid as u8));
+            }
+        }
+
+        // A batch with gaps is split into contiguous runs.
+        let mut guards = Vec::new();
+        for i in [7, 3, 4, 5, 9] {
+            let mut guard = handles[i].write().await.unwrap();
+            guard.fill(0xAA);
+            guards.push(guard);
+        }
+        bpm.flush_pages(&mut guards).await.unwrap();
+        drop(guards);
+        assert_eq!(bpm.flush_all().await.unwrap(), 0);
+    });
+
+    for path in PATHS {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,78 @@
+use async_bpm::{page::PageId, BufferPoolManager, BufferPoolManagerConfig, WalFuture, WalHook};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// The number of pages to write, which is more than the number of frames so that dirty pages must
+/// be evicted.
+const PAGES: u64 = 192;
+
+/// A page whose log can never be made durable.
+const UNLOGGABLE: u64 = PAGES;
+
+/// A hook that records the LSN it was asked to make durable for every page.
+#[derive(Debug, Default)]
+struct RecordingHook {
+    durable: Mutex<HashMap<PageId, u64>>,
+}
+
+impl WalHook for RecordingHook {
+    fn before_evict(&self, pid: PageId, lsn: u64) -> WalFuture<'_> {
+        Box::pin(async move {
+            if pid == PageId::new(UNLOGGABLE) {
+                return Err(io::Error::other("the log is unavailable"));
+            }
+
+            self.durable.lock().unwrap().insert(pid, lsn);
+            Ok(())
+        })
+    }
+}
+
+#[test]
+#[ignore]
+fn test_wal_hook() {
+    let hook = Arc::new(RecordingHook::default());
+
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256).wal_hook(hook.clone()),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+
+            let mut guard = ph.write().await.unwrap();
+            guard.fill(i as u8);
+            guard.set_lsn(i + 1);
+
+            // Explicitly flush every other page, and leave the rest to eviction.
+            if i % 2 == 0 {
+                guard.flush().await.unwrap();
+            }
+        }
+
+        // If the log cannot be made durable, the page must not be written out.
+        let ph = bpm.get_page(&PageId::new(UNLOGGABLE)).unwrap();
+        let mut guard = ph.write().await.unwrap();
+        guard.set_lsn(PAGES + 1);
+        assert!(guard.flush().await.is_err());
+        drop(guard);
+
+        // The page stays in memory with its data, and can still be accessed.
+        let guard = ph.read().await.unwrap();
+        assert_eq!(guard.len(), bpm.page_size());
+    });
+
+    // Every explicitly flushed page, and every page that was evicted, made its log durable first.
+    let durable = hook.durable.lock().unwrap();
+    assert!(durable.len() > PAGES as usize / 2);
+    for (pid, &lsn) in durable.iter() {
+        assert_eq!(lsn, pid.as_u64() + 1, "Wrong LSN for {pid}");
+    }
+    for i in (0..PAGES).step_by(2) {
+        assert!(durable.contains_key(&PageId::new(i)));
+    }
+    assert!(!durable.contains_key(&PageId::new(UNLOGGABLE)));
+}
@@ -0,0 +1,46 @@
+use async_bpm::{page::PageId, BufferPoolManager, FlushPolicy};
+
+#[test]
+#[ignore]
+fn test_with_page() {
+    BufferPoolManager::initialize(64, 256);
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        let pid = PageId::new(7);
+
+        // A deferred write leaves the page dirty in memory.
+        let before = bpm.stats();
+        let len = bpm
+            .with_page_write(&pid, FlushPolicy::Deferred, |bytes| {
+                bytes.fill(b'a');
+                bytes.len()
+            })
+            .await
+            .unwrap();
+        assert_eq!(len, bpm.usable_page_size());
+        assert_eq!(bpm.stats().since(&before).page_writes, 0);
+
+        let all_a = bpm
+            .with_page_read(&pid, |bytes| bytes.iter().all(|&b| b == b'a'))
+            .await
+            .unwrap();
+        assert!(all_a);
+
+        // A flushed write is written out before returning.
+        let before = bpm.stats();
+        bpm.with_page_write(&pid, FlushPolicy::Flush, |bytes| bytes.fill(b'b'))
+            .await
+            .unwrap();
+        assert_eq!(bpm.stats().since(&before).page_writes, 1);
+
+        // The lock is released once the closure returns.
+        let ph = bpm.get_page(&pid).unwrap();
+        let guard = ph
+            .try_write()
+            .await
+            .unwrap()
+            .expect("The page is not locked");
+        assert!(guard.iter().all(|&b| b == b'b'));
+    });
+}
@@ -0,0 +1,69 @@
+use async_bpm::{
+    page::PageId, BufferPoolManager, BufferPoolManagerConfig, CachePolicy, MemoryStorage,
+    StorageBackend,
+};
+use std::ops::DerefMut;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The number of pages to write, which is fewer than the number of frames so that no page is
+/// evicted.
+const PAGES: u64 = 16;
+
+/// The number of guards on the same page that are dropped in a burst.
+const BURST: u8 = 10;
+
+#[test]
+#[ignore]
+fn test_write_through() {
+    let storage = Arc::new(MemoryStorage::new());
+    BufferPoolManager::initialize_with_config(
+        BufferPoolManagerConfig::new(64, 256)
+            .storage_backend(storage.clone())
+            .cache_policy(CachePolicy::WriteThrough),
+    );
+    let bpm = BufferPoolManager::get();
+
+    BufferPoolManager::start_thread(async move {
+        for i in 0..PAGES {
+            let ph = bpm.get_page(&PageId::new(i)).unwrap();
+            ph.write().await.unwrap().deref_mut().fill(i as u8);
+        }
+
+        // Every page is written out in the background once its guard is dropped, without being
+        // evicted or flushed.
+        for _ in 0..100 {
+            if storage.num_pages() == PAGES as usize {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(storage.num_pages(), PAGES as usize);
+        assert_eq!(bpm.stats().evictions, 0);
+
+        // A page that was flushed through its guard is not written out again.
+        let writes = bpm.stats().page_writes;
+        let ph = bpm.get_page(&PageId::new(0)).unwrap();
+        let mut guard = ph.write().await.unwrap();
+        guard.deref_mut().fill(42);
+        guard.flush().await.unwrap();
+        drop(guard);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(bpm.stats().page_writes, writes + 1);
+
+        // A burst of guards on the same page is written out with a single write, which includes
+        // every one of their updates.
+        let writes = bpm.stats().page_writes;
+        let ph = bpm.get_page(&PageId::new(1)).unwrap();
+        for i in 0..BURST {
+            ph.write().await.unwrap().deref_mut().fill(i);
+        }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(bpm.stats().page_writes, writes + 1);
+        let mut buf = vec![0; bpm.page_size()];
+        storage.read_at(PageId::new(1), 0, &mut buf).await.unwrap();
+        assert!(buf.iter().all(|&b| b == BURST - 1));
+    });
+}